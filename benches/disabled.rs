@@ -0,0 +1,65 @@
+//! Measures the cost of recording telemetry while the session is disabled, which should be a few nanoseconds
+//! per call since no formatting, allocation, event validation, or battery dispatch takes place.
+
+use std::sync::{atomic::AtomicBool, atomic::Ordering, Arc};
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use tracing_batteries::{
+    Battery, BatteryBuilder, EventRegistryMode, EventSchema, Metadata, PropertyType, Session,
+};
+
+struct NoopBattery;
+
+impl BatteryBuilder for NoopBattery {
+    fn setup(self, _metadata: &Metadata, _enabled: Arc<AtomicBool>) -> Box<dyn Battery> {
+        Box::new(NoopBattery)
+    }
+}
+
+impl Battery for NoopBattery {}
+
+fn disabled(c: &mut Criterion) {
+    let session = Session::new("benchmark", "0.0.1")
+        .with_battery(NoopBattery)
+        .with_event_registry(EventRegistryMode::Strict);
+    session.register_event(EventSchema::new("export_pdf").require("pages", PropertyType::Number));
+    session.enable().store(false, Ordering::Relaxed);
+
+    let error = std::io::Error::new(std::io::ErrorKind::NotFound, "missing file");
+    let counter = session.counter("items_processed");
+    let histogram = session.histogram("latency_ms");
+
+    let mut group = c.benchmark_group("disabled");
+    group.bench_function("record_event", |b| {
+        b.iter(|| session.record_event(black_box("export_pdf"), [("pages", black_box(3))]))
+    });
+    group.bench_function("record_event_unregistered", |b| {
+        b.iter(|| {
+            session.record_event(
+                black_box("export_pfd"),
+                [("pages", black_box("3")), ("format", black_box("a4"))],
+            )
+        })
+    });
+    group.bench_function("record_new_page", |b| {
+        b.iter(|| session.record_new_page(black_box("/settings")))
+    });
+    group.bench_function("record_error_with", |b| {
+        b.iter(|| session.record_error_with(black_box(&error), [("attempt", black_box(3))]))
+    });
+    group.bench_function("counter", |b| {
+        b.iter(|| counter.inc_with(black_box(1), &[("queue", "priority")]))
+    });
+    group.bench_function("histogram", |b| {
+        b.iter(|| histogram.record(black_box(12.5)))
+    });
+    group.bench_function("feature_used", |b| {
+        b.iter(|| session.feature_used(black_box("tab_completion")))
+    });
+    group.finish();
+
+    session.shutdown();
+}
+
+criterion_group!(benches, disabled);
+criterion_main!(benches);
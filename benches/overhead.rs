@@ -0,0 +1,66 @@
+//! Measures the overhead of creating spans, emitting events, and recording errors for a selection of battery
+//! combinations, allowing the cost of enabling each battery to be compared before it is shipped.
+//!
+//! Since the `OpenTelemetry` battery installs the global tracing subscriber, the combinations which include it
+//! are measured last and the subscriber remains installed for the rest of the run.
+
+use std::sync::{atomic::AtomicBool, Arc};
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use tracing_batteries::{Battery, BatteryBuilder, Metadata, OpenTelemetry, Sentry, Session};
+
+struct NoopBattery;
+
+impl BatteryBuilder for NoopBattery {
+    fn setup(self, _metadata: &Metadata, _enabled: Arc<AtomicBool>) -> Box<dyn Battery> {
+        Box::new(NoopBattery)
+    }
+}
+
+impl Battery for NoopBattery {}
+
+/// A Sentry DSN which points at a closed local port, so that no telemetry leaves the machine.
+const SENTRY_DSN: &str = "http://public@127.0.0.1:9/1";
+
+/// An OTLP endpoint which points at a closed local port, so that no telemetry leaves the machine.
+const OTLP_ENDPOINT: &str = "http://127.0.0.1:9";
+
+fn measure(c: &mut Criterion, name: &str, session: &Session) {
+    let error = std::io::Error::new(std::io::ErrorKind::NotFound, "missing file");
+
+    let mut group = c.benchmark_group(name);
+    group.bench_function("span", |b| {
+        b.iter(|| tracing::info_span!("benchmark", iteration = black_box(1)).in_scope(|| {}))
+    });
+    group.bench_function("event", |b| {
+        b.iter(|| tracing::info!(iteration = black_box(1), "benchmark event"))
+    });
+    group.bench_function("record_event", |b| {
+        b.iter(|| session.record_event(black_box("export_pdf"), [("pages", black_box(3))]))
+    });
+    group.bench_function("record_error", |b| {
+        b.iter(|| session.record_error(black_box(&error)))
+    });
+    group.finish();
+}
+
+fn overhead(c: &mut Criterion) {
+    let session = Session::new("benchmark", "0.0.1").with_battery(NoopBattery);
+    measure(c, "noop", &session);
+    session.shutdown();
+
+    let session = Session::new("benchmark", "0.0.1").with_battery(Sentry::new(SENTRY_DSN));
+    measure(c, "sentry", &session);
+    session.shutdown();
+
+    let session =
+        Session::new("benchmark", "0.0.1").with_battery(OpenTelemetry::new(OTLP_ENDPOINT));
+    measure(c, "opentelemetry", &session);
+
+    let session = session.with_battery(Sentry::new(SENTRY_DSN));
+    measure(c, "opentelemetry+sentry", &session);
+    session.shutdown();
+}
+
+criterion_group!(benches, overhead);
+criterion_main!(benches);
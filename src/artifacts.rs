@@ -0,0 +1,197 @@
+use std::{
+    borrow::Cow,
+    io::Write,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use chrono::Utc;
+
+use crate::Session;
+
+/// A file (such as a screenshot, render, or state dump) which has been attached to the session using
+/// [`Session::attach_artifact`], and which is delivered to each battery through [`Battery::record_artifact`].
+///
+/// [`Battery::record_artifact`]: crate::Battery::record_artifact
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Artifact {
+    /// The file name of the artifact, such as `screenshot.png`.
+    pub name: Cow<'static, str>,
+    /// The MIME type of the artifact's contents, such as `image/png`.
+    pub content_type: Cow<'static, str>,
+    /// The contents of the artifact.
+    pub data: Arc<[u8]>,
+}
+
+impl Session {
+    /// Attaches a file (such as a screenshot, render, or dump of the application's state) to the session, so that
+    /// it accompanies the crash and error reports which follow.
+    ///
+    /// The artifact is delivered to each battery which supports artifacts (for example, as an attachment on the next
+    /// error reported to Sentry, or as an object uploaded by the [`S3Archive`](crate::S3Archive) battery). When none of the
+    /// session's batteries accept it, the artifact is written to a `{service}/artifacts` directory within the
+    /// user's cache directory (`$XDG_CACHE_HOME`, `%LOCALAPPDATA%`, or `~/.cache`) instead, and its path is logged,
+    /// so that it can still be retrieved by hand. The directory is only readable by the current user, and artifacts
+    /// are never written to a directory which belongs to another user.
+    ///
+    /// ## Example
+    /// ```no_run
+    /// use tracing_batteries::{Session, Sentry};
+    ///
+    /// let session = Session::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+    ///   .with_battery(Sentry::new("https://yourdsn@sentry.example.com"));
+    ///
+    /// let state = serde_json::json!({ "open_documents": 3 });
+    /// session.attach_artifact("state.json", state.to_string(), "application/json");
+    /// ```
+    pub fn attach_artifact<N, D, C>(&self, name: N, data: D, content_type: C)
+    where
+        N: Into<Cow<'static, str>>,
+        D: Into<Vec<u8>>,
+        C: Into<Cow<'static, str>>,
+    {
+        if !self.is_enabled() {
+            return;
+        }
+
+        let data: Vec<u8> = data.into();
+        let artifact = Artifact {
+            name: name.into(),
+            content_type: content_type.into(),
+            data: data.into(),
+        };
+
+        let mut accepted = false;
+        for battery in self.batteries().iter() {
+            accepted |= battery.record_artifact(&artifact);
+        }
+
+        if !accepted {
+            let Some(directory) = default_artifact_directory(&self.metadata.service) else {
+                tracing::warn!(
+                    "Failed to save the '{}' artifact to disk: the user's cache directory could not be determined.",
+                    artifact.name
+                );
+                return;
+            };
+
+            match save_artifact(&directory, &artifact) {
+                Ok(path) => tracing::info!(
+                    path = %path.display(),
+                    "Saved the '{}' artifact to disk, since no battery accepted it.",
+                    artifact.name
+                ),
+                Err(err) => tracing::warn!(
+                    "Failed to save the '{}' artifact to disk: {}",
+                    artifact.name,
+                    err
+                ),
+            }
+        }
+    }
+}
+
+/// Returns the directory within the user's cache directory which unclaimed artifacts are saved to.
+fn default_artifact_directory(service: &str) -> Option<PathBuf> {
+    let cache_dir = std::env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("LOCALAPPDATA").map(PathBuf::from))
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))?;
+
+    Some(cache_dir.join(service).join("artifacts"))
+}
+
+/// Creates the directory which artifacts are saved to, ensuring that it is only accessible by the current user.
+fn create_private_dir(directory: &Path) -> std::io::Result<()> {
+    let mut builder = std::fs::DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+    builder.create(directory)?;
+
+    let metadata = std::fs::symlink_metadata(directory)?;
+    if !metadata.is_dir() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::PermissionDenied,
+            format!("'{}' is not a directory", directory.display()),
+        ));
+    }
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{MetadataExt, PermissionsExt};
+
+        // The owner of a file we have just created is the current user, which avoids needing to call into libc.
+        let probe = directory.with_file_name(format!(".artifacts-owner-{}", std::process::id()));
+        let owner = std::fs::File::create(&probe)
+            .and_then(|file| file.metadata())
+            .map(|metadata| metadata.uid());
+        std::fs::remove_file(&probe).ok();
+
+        if owner? != metadata.uid() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::PermissionDenied,
+                format!("'{}' is owned by another user", directory.display()),
+            ));
+        }
+
+        if metadata.mode() & 0o077 != 0 {
+            std::fs::set_permissions(directory, std::fs::Permissions::from_mode(0o700))?;
+        }
+    }
+
+    Ok(())
+}
+
+fn save_artifact(directory: &Path, artifact: &Artifact) -> std::io::Result<PathBuf> {
+    create_private_dir(directory)?;
+
+    let name = artifact
+        .name
+        .chars()
+        .map(|c| if std::path::is_separator(c) { '_' } else { c })
+        .collect::<String>();
+    let path = directory.join(format!(
+        "{}-{}-{name}",
+        Utc::now().format("%Y%m%dT%H%M%S%.3fZ"),
+        std::process::id()
+    ));
+
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    options.open(&path)?.write_all(&artifact.data)?;
+
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn saves_artifacts_to_disk() {
+        let directory = std::env::temp_dir().join(format!("artifacts-test-{}", std::process::id()));
+        let artifact = Artifact {
+            name: "../state.json".into(),
+            content_type: "application/json".into(),
+            data: Arc::from(&b"{}"[..]),
+        };
+
+        let path = save_artifact(&directory, &artifact).unwrap();
+        assert_eq!(path.parent(), Some(directory.as_path()));
+        assert!(path.to_string_lossy().ends_with(".._state.json"));
+        assert_eq!(std::fs::read(&path).unwrap(), b"{}");
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+
+            let mode = std::fs::metadata(&directory).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o700);
+        }
+
+        std::fs::remove_dir_all(&directory).ok();
+    }
+}
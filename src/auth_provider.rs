@@ -0,0 +1,276 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{Arc, PoisonError, RwLock},
+    time::Duration,
+};
+
+use opentelemetry_http::{Bytes, HttpClient, HttpError, Request, Response};
+use opentelemetry_sdk::runtime::Runtime;
+use reqwest::header::{HeaderValue, AUTHORIZATION};
+
+type Provider = dyn Fn() -> Pin<Box<dyn Future<Output = HeaderValue> + Send>> + Send + Sync;
+
+/// Holds the most recent `Authorization` header value returned by the auth provider configured using
+/// [`OpenTelemetry::with_auth_provider`](crate::OpenTelemetry::with_auth_provider).
+pub(crate) struct AuthToken {
+    provider: Box<Provider>,
+    value: RwLock<Option<HeaderValue>>,
+}
+
+impl AuthToken {
+    pub fn new<F, Fut>(provider: F) -> Self
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = HeaderValue> + Send + 'static,
+    {
+        Self {
+            provider: Box::new(move || Box::pin(provider())),
+            value: RwLock::new(None),
+        }
+    }
+
+    /// Returns the current header value, if the provider has been consulted yet.
+    pub fn current(&self) -> Option<HeaderValue> {
+        self.value
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Consults the provider for a new header value, replacing the current value.
+    pub async fn refresh(&self) -> HeaderValue {
+        let value = (self.provider)().await;
+        *self.value.write().unwrap_or_else(PoisonError::into_inner) = Some(value.clone());
+        value
+    }
+
+    /// Refreshes the header value in the background at the provided interval, until the token is dropped.
+    pub fn start(self: &Arc<Self>, interval: Duration) {
+        let token = Arc::downgrade(self);
+        opentelemetry_sdk::runtime::Tokio.spawn(Box::pin(async move {
+            while let Some(current) = token.upgrade() {
+                current.refresh().await;
+                drop(current);
+
+                opentelemetry_sdk::runtime::Tokio.delay(interval).await;
+            }
+        }));
+    }
+
+    /// Builds a gRPC interceptor which attaches the current header value to each export request.
+    pub fn interceptor(
+        self: &Arc<Self>,
+    ) -> impl FnMut(tonic::Request<()>) -> Result<tonic::Request<()>, tonic::Status> + Clone {
+        let token = self.clone();
+        move |mut request| {
+            if let Some(value) = token
+                .current()
+                .and_then(|value| tonic::metadata::MetadataValue::try_from(value.as_bytes()).ok())
+            {
+                request.metadata_mut().insert("authorization", value);
+            }
+
+            Ok(request)
+        }
+    }
+}
+
+impl std::fmt::Debug for AuthToken {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AuthToken")
+            .field("value", &self.current())
+            .finish_non_exhaustive()
+    }
+}
+
+/// An [`HttpClient`] which attaches the current `Authorization` header value to each OTLP/HTTP export, refreshing
+/// it (and retrying the export) if the collector rejects it as unauthorized.
+#[derive(Debug)]
+pub(crate) struct AuthenticatedHttpClient {
+    client: reqwest::Client,
+    token: Arc<AuthToken>,
+}
+
+impl AuthenticatedHttpClient {
+    pub fn new(token: Arc<AuthToken>) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            token,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl HttpClient for AuthenticatedHttpClient {
+    async fn send(&self, request: Request<Vec<u8>>) -> Result<Response<Bytes>, HttpError> {
+        let value = match self.token.current() {
+            Some(value) => value,
+            None => self.token.refresh().await,
+        };
+
+        let response = HttpClient::send(&self.client, authorized(&request, value)).await?;
+        if response.status() != reqwest::StatusCode::UNAUTHORIZED {
+            return Ok(response);
+        }
+
+        let value = self.token.refresh().await;
+        HttpClient::send(&self.client, authorized(&request, value)).await
+    }
+}
+
+/// An [`HttpClient`] which signs each OTLP/HTTP export using AWS Signature Version 4, as configured using
+/// [`OpenTelemetry::with_aws_sigv4`](crate::OpenTelemetry::with_aws_sigv4).
+#[cfg(feature = "sigv4")]
+pub(crate) struct SigV4HttpClient {
+    client: reqwest::Client,
+    signer: Arc<crate::aws::AwsSigner>,
+}
+
+#[cfg(feature = "sigv4")]
+impl SigV4HttpClient {
+    pub fn new(signer: Arc<crate::aws::AwsSigner>) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            signer,
+        }
+    }
+
+    /// Adds the headers which sign the request (as of the provided time), covering its `content-type` and body.
+    fn sign(
+        &self,
+        request: &mut Request<Vec<u8>>,
+        now: chrono::DateTime<chrono::Utc>,
+    ) -> Result<(), HttpError> {
+        let url = reqwest::Url::parse(&request.uri().to_string())?;
+        let content_type = request
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.to_string());
+        let headers: Vec<(&str, &str)> = content_type
+            .iter()
+            .map(|value| ("content-type", value.as_str()))
+            .collect();
+
+        for (name, value) in self.signer.signing_headers(
+            request.method().as_str(),
+            &url,
+            &headers,
+            request.body(),
+            now,
+        ) {
+            request.headers_mut().insert(
+                reqwest::header::HeaderName::from_bytes(name.as_bytes())?,
+                HeaderValue::from_str(&value)?,
+            );
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "sigv4")]
+impl std::fmt::Debug for SigV4HttpClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SigV4HttpClient").finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "sigv4")]
+#[async_trait::async_trait]
+impl HttpClient for SigV4HttpClient {
+    async fn send(&self, mut request: Request<Vec<u8>>) -> Result<Response<Bytes>, HttpError> {
+        self.sign(&mut request, chrono::Utc::now())?;
+        HttpClient::send(&self.client, request).await
+    }
+}
+
+/// Creates a copy of the request with the provided `Authorization` header value.
+fn authorized(request: &Request<Vec<u8>>, value: HeaderValue) -> Request<Vec<u8>> {
+    let mut authorized = Request::new(request.body().clone());
+    *authorized.method_mut() = request.method().clone();
+    *authorized.uri_mut() = request.uri().clone();
+    *authorized.headers_mut() = request.headers().clone();
+    authorized.headers_mut().insert(AUTHORIZATION, value);
+    authorized
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    #[test]
+    fn refreshes_token() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let token = AuthToken::new({
+            let calls = calls.clone();
+            move || {
+                let call = calls.fetch_add(1, Ordering::Relaxed) + 1;
+                std::future::ready(HeaderValue::from_str(&format!("Bearer token-{call}")).unwrap())
+            }
+        });
+        assert!(token.current().is_none());
+
+        let mut context = std::task::Context::from_waker(std::task::Waker::noop());
+        assert!(std::pin::pin!(token.refresh())
+            .poll(&mut context)
+            .is_ready());
+        assert_eq!(token.current().unwrap(), "Bearer token-1");
+
+        let mut request = Request::new(b"spans".to_vec());
+        request.headers_mut().insert(
+            "content-type",
+            HeaderValue::from_static("application/x-protobuf"),
+        );
+
+        let request = authorized(&request, token.current().unwrap());
+        assert_eq!(request.headers()[AUTHORIZATION], "Bearer token-1");
+        assert_eq!(request.headers()["content-type"], "application/x-protobuf");
+        assert_eq!(request.body(), b"spans");
+    }
+
+    #[cfg(feature = "sigv4")]
+    #[test]
+    fn signs_requests() {
+        use chrono::TimeZone;
+
+        // The `post-x-www-form-urlencoded` example from the AWS Signature Version 4 test suite, which signs the
+        // request's content type and body hash just as an OTLP export does.
+        let signer = crate::aws::AwsSigner::new("service", "us-east-1".into()).with_credentials(
+            crate::aws::AwsCredentials {
+                access_key_id: "AKIDEXAMPLE".into(),
+                secret_access_key: "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY".into(),
+                session_token: None,
+                expires_at: None,
+            },
+        );
+        let client = SigV4HttpClient::new(Arc::new(signer));
+
+        let mut request = Request::new(b"Param1=value1".to_vec());
+        *request.method_mut() = reqwest::Method::POST;
+        *request.uri_mut() = "https://example.amazonaws.com/".parse().unwrap();
+        request.headers_mut().insert(
+            "content-type",
+            HeaderValue::from_static("application/x-www-form-urlencoded"),
+        );
+
+        client
+            .sign(
+                &mut request,
+                chrono::Utc
+                    .with_ymd_and_hms(2015, 8, 30, 12, 36, 0)
+                    .unwrap(),
+            )
+            .unwrap();
+
+        assert_eq!(request.headers()["x-amz-date"], "20150830T123600Z");
+        assert_eq!(
+            request.headers()[AUTHORIZATION],
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/service/aws4_request, SignedHeaders=content-type;host;x-amz-date, Signature=ff11897932ad3f4e8b18135d722051e5ac45fc38421b1da7b9d196a0fe09473a"
+        );
+        assert_eq!(request.body(), b"Param1=value1");
+    }
+}
@@ -0,0 +1,501 @@
+#![cfg_attr(
+    not(any(feature = "cloudwatch", feature = "opensearch", feature = "s3")),
+    allow(dead_code)
+)]
+
+use std::{
+    sync::{Mutex, PoisonError},
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use reqwest::blocking::{Client, RequestBuilder};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+/// Credentials used to sign requests to AWS APIs.
+#[derive(Debug, Clone)]
+pub(crate) struct AwsCredentials {
+    pub access_key_id: String,
+    pub secret_access_key: String,
+    pub session_token: Option<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+/// Signs requests to an AWS service using [Signature Version 4](https://docs.aws.amazon.com/IAM/latest/UserGuide/reference_sigv.html),
+/// resolving credentials from the standard AWS credential chain.
+///
+/// Credentials are loaded from (in order) the `AWS_ACCESS_KEY_ID`, `AWS_SECRET_ACCESS_KEY` and `AWS_SESSION_TOKEN`
+/// environment variables, the shared credentials file (honouring `AWS_SHARED_CREDENTIALS_FILE` and `AWS_PROFILE`),
+/// the ECS/Lambda container credentials endpoint, and finally the EC2 instance metadata service. Temporary
+/// credentials are cached and refreshed shortly before they expire.
+pub(crate) struct AwsSigner {
+    service: &'static str,
+    region: String,
+    cached: Mutex<Option<AwsCredentials>>,
+}
+
+impl AwsSigner {
+    pub fn new(service: &'static str, region: String) -> Self {
+        Self {
+            service,
+            region,
+            cached: Mutex::new(None),
+        }
+    }
+
+    /// Builds a signed request with the provided headers and body.
+    ///
+    /// If no credentials can be found the request is sent unsigned, and will be rejected by AWS.
+    pub fn request(
+        &self,
+        client: &Client,
+        method: reqwest::Method,
+        url: &str,
+        headers: &[(&str, &str)],
+        body: Vec<u8>,
+    ) -> RequestBuilder {
+        let mut request = client.request(method.clone(), url);
+        for (name, value) in headers {
+            request = request.header(*name, *value);
+        }
+
+        if let (Ok(parsed), Some(credentials)) =
+            (reqwest::Url::parse(url), self.credentials(client))
+        {
+            for (name, value) in sign(
+                &credentials,
+                &self.region,
+                self.service,
+                method.as_str(),
+                &parsed,
+                headers,
+                &body,
+                Utc::now(),
+            ) {
+                request = request.header(name, value);
+            }
+        }
+
+        request.body(body)
+    }
+
+    /// Replaces the credentials used to sign requests, bypassing the credential chain.
+    #[cfg(all(test, feature = "sigv4"))]
+    pub fn with_credentials(self, credentials: AwsCredentials) -> Self {
+        Self {
+            cached: Mutex::new(Some(credentials)),
+            ..self
+        }
+    }
+
+    /// Computes the headers which sign a request (made at the provided time) with the provided headers and body,
+    /// returning no headers if no credentials can be found.
+    ///
+    /// Unlike [`AwsSigner::request`], this may be called from an async context: when credentials need to be
+    /// (re)loaded, this is done using a blocking client on a separate thread.
+    #[cfg(feature = "sigv4")]
+    pub fn signing_headers(
+        &self,
+        method: &str,
+        url: &reqwest::Url,
+        headers: &[(&str, &str)],
+        body: &[u8],
+        now: DateTime<Utc>,
+    ) -> Vec<(String, String)> {
+        let credentials = self.credentials_with(|| {
+            std::thread::spawn(|| load_credentials(&Client::new()))
+                .join()
+                .ok()
+                .flatten()
+        });
+
+        match credentials {
+            Some(credentials) => sign(
+                &credentials,
+                &self.region,
+                self.service,
+                method,
+                url,
+                headers,
+                body,
+                now,
+            ),
+            None => Vec::new(),
+        }
+    }
+
+    fn credentials(&self, client: &Client) -> Option<AwsCredentials> {
+        self.credentials_with(|| load_credentials(client))
+    }
+
+    fn credentials_with<F: FnOnce() -> Option<AwsCredentials>>(
+        &self,
+        load: F,
+    ) -> Option<AwsCredentials> {
+        let mut cached = self.cached.lock().unwrap_or_else(PoisonError::into_inner);
+
+        let expired = cached.as_ref().map_or(true, |credentials| {
+            credentials
+                .expires_at
+                .is_some_and(|at| at - chrono::Duration::minutes(5) <= Utc::now())
+        });
+
+        if expired {
+            if let Some(credentials) = load() {
+                *cached = Some(credentials);
+            }
+        }
+
+        cached.clone()
+    }
+}
+
+/// Determines the AWS region from the `AWS_REGION` or `AWS_DEFAULT_REGION` environment variables.
+pub(crate) fn default_region() -> String {
+    std::env::var("AWS_REGION")
+        .or_else(|_| std::env::var("AWS_DEFAULT_REGION"))
+        .unwrap_or_else(|_| "us-east-1".to_string())
+}
+
+/// Returns the regional endpoint for an AWS service.
+pub(crate) fn endpoint(service: &str, region: &str) -> String {
+    if region.starts_with("cn-") {
+        format!("https://{service}.{region}.amazonaws.com.cn")
+    } else {
+        format!("https://{service}.{region}.amazonaws.com")
+    }
+}
+
+/// Computes the hex encoded SHA-256 hash of a request body, as required by the `x-amz-content-sha256` header.
+pub(crate) fn payload_hash(body: &[u8]) -> String {
+    hex(&Sha256::digest(body))
+}
+
+/// Encodes a value using the URI encoding rules required by Signature Version 4.
+pub(crate) fn uri_encode(value: &str, encode_slash: bool) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                encoded.push(byte as char)
+            }
+            b'/' if !encode_slash => encoded.push('/'),
+            _ => encoded.push_str(&format!("%{byte:02X}")),
+        }
+    }
+
+    encoded
+}
+
+/// Computes the `x-amz-date`, `x-amz-security-token` (for temporary credentials) and `authorization` headers
+/// for a request, signing the provided headers along with the request's host.
+#[allow(clippy::too_many_arguments)]
+fn sign(
+    credentials: &AwsCredentials,
+    region: &str,
+    service: &str,
+    method: &str,
+    url: &reqwest::Url,
+    headers: &[(&str, &str)],
+    body: &[u8],
+    now: DateTime<Utc>,
+) -> Vec<(String, String)> {
+    let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+    let date = now.format("%Y%m%d").to_string();
+
+    let host = match url.port() {
+        Some(port) => format!("{}:{port}", url.host_str().unwrap_or_default()),
+        None => url.host_str().unwrap_or_default().to_string(),
+    };
+
+    let mut signed_headers: Vec<(String, String)> = headers
+        .iter()
+        .map(|(name, value)| (name.to_lowercase(), value.trim().to_string()))
+        .collect();
+    signed_headers.push(("host".to_string(), host));
+    signed_headers.push(("x-amz-date".to_string(), amz_date.clone()));
+    if let Some(token) = &credentials.session_token {
+        signed_headers.push(("x-amz-security-token".to_string(), token.clone()));
+    }
+    signed_headers.sort();
+
+    let mut query: Vec<(String, String)> = url
+        .query_pairs()
+        .map(|(key, value)| (uri_encode(&key, true), uri_encode(&value, true)))
+        .collect();
+    query.sort();
+
+    let canonical_request = format!(
+        "{method}\n{}\n{}\n{}\n{}\n{}",
+        url.path(),
+        query
+            .iter()
+            .map(|(key, value)| format!("{key}={value}"))
+            .collect::<Vec<_>>()
+            .join("&"),
+        signed_headers
+            .iter()
+            .map(|(name, value)| format!("{name}:{value}\n"))
+            .collect::<String>(),
+        signed_headers
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect::<Vec<_>>()
+            .join(";"),
+        payload_hash(body)
+    );
+
+    let scope = format!("{date}/{region}/{service}/aws4_request");
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{amz_date}\n{scope}\n{}",
+        hex(&Sha256::digest(canonical_request.as_bytes()))
+    );
+
+    let key = [region, service, "aws4_request"].iter().fold(
+        hmac(
+            format!("AWS4{}", credentials.secret_access_key).as_bytes(),
+            &date,
+        ),
+        |key, part| hmac(&key, part),
+    );
+    let signature = hex(&hmac(&key, &string_to_sign));
+
+    let mut result = vec![("x-amz-date".to_string(), amz_date)];
+    if let Some(token) = &credentials.session_token {
+        result.push(("x-amz-security-token".to_string(), token.clone()));
+    }
+    result.push((
+        "authorization".to_string(),
+        format!(
+            "AWS4-HMAC-SHA256 Credential={}/{scope}, SignedHeaders={}, Signature={signature}",
+            credentials.access_key_id,
+            signed_headers
+                .iter()
+                .map(|(name, _)| name.as_str())
+                .collect::<Vec<_>>()
+                .join(";")
+        ),
+    ));
+
+    result
+}
+
+fn hmac(key: &[u8], message: &str) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(message.as_bytes());
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+fn load_credentials(client: &Client) -> Option<AwsCredentials> {
+    from_environment()
+        .or_else(from_shared_credentials)
+        .or_else(|| from_container(client))
+        .or_else(|| from_instance_metadata(client))
+}
+
+fn from_environment() -> Option<AwsCredentials> {
+    Some(AwsCredentials {
+        access_key_id: std::env::var("AWS_ACCESS_KEY_ID").ok()?,
+        secret_access_key: std::env::var("AWS_SECRET_ACCESS_KEY").ok()?,
+        session_token: std::env::var("AWS_SESSION_TOKEN").ok(),
+        expires_at: None,
+    })
+}
+
+fn from_shared_credentials() -> Option<AwsCredentials> {
+    let path = std::env::var("AWS_SHARED_CREDENTIALS_FILE")
+        .ok()
+        .or_else(|| {
+            std::env::var("HOME")
+                .or_else(|_| std::env::var("USERPROFILE"))
+                .ok()
+                .map(|home| format!("{home}/.aws/credentials"))
+        })?;
+    let profile = std::env::var("AWS_PROFILE").unwrap_or_else(|_| "default".to_string());
+
+    parse_profile(&std::fs::read_to_string(path).ok()?, &profile)
+}
+
+/// Reads the credentials for a profile from the contents of a shared credentials file.
+fn parse_profile(contents: &str, profile: &str) -> Option<AwsCredentials> {
+    let mut in_profile = false;
+    let mut access_key_id = None;
+    let mut secret_access_key = None;
+    let mut session_token = None;
+
+    for line in contents.lines().map(str::trim) {
+        if let Some(section) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            in_profile = section.trim() == profile;
+        } else if let (true, Some((key, value))) = (in_profile, line.split_once('=')) {
+            let value = Some(value.trim().to_string());
+            match key.trim() {
+                "aws_access_key_id" => access_key_id = value,
+                "aws_secret_access_key" => secret_access_key = value,
+                "aws_session_token" => session_token = value,
+                _ => {}
+            }
+        }
+    }
+
+    Some(AwsCredentials {
+        access_key_id: access_key_id?,
+        secret_access_key: secret_access_key?,
+        session_token,
+        expires_at: None,
+    })
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct RemoteCredentials {
+    access_key_id: String,
+    secret_access_key: String,
+    token: Option<String>,
+    expiration: Option<DateTime<Utc>>,
+}
+
+impl From<RemoteCredentials> for AwsCredentials {
+    fn from(credentials: RemoteCredentials) -> Self {
+        Self {
+            access_key_id: credentials.access_key_id,
+            secret_access_key: credentials.secret_access_key,
+            session_token: credentials.token,
+            expires_at: credentials.expiration,
+        }
+    }
+}
+
+fn from_container(client: &Client) -> Option<AwsCredentials> {
+    let url = match std::env::var("AWS_CONTAINER_CREDENTIALS_RELATIVE_URI") {
+        Ok(path) => format!("http://169.254.170.2{path}"),
+        Err(_) => std::env::var("AWS_CONTAINER_CREDENTIALS_FULL_URI").ok()?,
+    };
+
+    let token = std::env::var("AWS_CONTAINER_AUTHORIZATION_TOKEN")
+        .ok()
+        .or_else(|| {
+            std::env::var("AWS_CONTAINER_AUTHORIZATION_TOKEN_FILE")
+                .ok()
+                .and_then(|path| std::fs::read_to_string(path).ok())
+                .map(|token| token.trim().to_string())
+        });
+
+    let mut request = client.get(url).timeout(Duration::from_secs(2));
+    if let Some(token) = token {
+        request = request.header("Authorization", token);
+    }
+
+    request
+        .send()
+        .and_then(|response| response.error_for_status())
+        .and_then(|response| response.text())
+        .ok()
+        .and_then(|body| serde_json::from_str::<RemoteCredentials>(&body).ok())
+        .map(AwsCredentials::from)
+}
+
+fn from_instance_metadata(client: &Client) -> Option<AwsCredentials> {
+    if std::env::var("AWS_EC2_METADATA_DISABLED").is_ok_and(|disabled| disabled == "true") {
+        return None;
+    }
+
+    const METADATA: &str = "http://169.254.169.254/latest";
+    let timeout = Duration::from_secs(1);
+
+    let token = client
+        .put(format!("{METADATA}/api/token"))
+        .header("X-aws-ec2-metadata-token-ttl-seconds", "21600")
+        .timeout(timeout)
+        .send()
+        .and_then(|response| response.error_for_status())
+        .and_then(|response| response.text())
+        .ok()?;
+
+    let role = client
+        .get(format!("{METADATA}/meta-data/iam/security-credentials/"))
+        .header("X-aws-ec2-metadata-token", &token)
+        .timeout(timeout)
+        .send()
+        .and_then(|response| response.error_for_status())
+        .and_then(|response| response.text())
+        .ok()?;
+
+    client
+        .get(format!(
+            "{METADATA}/meta-data/iam/security-credentials/{}",
+            role.lines().next()?.trim()
+        ))
+        .header("X-aws-ec2-metadata-token", &token)
+        .timeout(timeout)
+        .send()
+        .and_then(|response| response.error_for_status())
+        .and_then(|response| response.text())
+        .ok()
+        .and_then(|body| serde_json::from_str::<RemoteCredentials>(&body).ok())
+        .map(AwsCredentials::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    #[test]
+    fn signature_v4() {
+        // The example request from the AWS Signature Version 4 documentation.
+        let credentials = AwsCredentials {
+            access_key_id: "AKIDEXAMPLE".into(),
+            secret_access_key: "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY".into(),
+            session_token: None,
+            expires_at: None,
+        };
+
+        let headers = sign(
+            &credentials,
+            "us-east-1",
+            "iam",
+            "GET",
+            &reqwest::Url::parse("https://iam.amazonaws.com/?Version=2010-05-08&Action=ListUsers")
+                .unwrap(),
+            &[(
+                "Content-Type",
+                "application/x-www-form-urlencoded; charset=utf-8",
+            )],
+            b"",
+            Utc.with_ymd_and_hms(2015, 8, 30, 12, 36, 0).unwrap(),
+        );
+
+        assert_eq!(
+            headers,
+            vec![
+                ("x-amz-date".to_string(), "20150830T123600Z".to_string()),
+                (
+                    "authorization".to_string(),
+                    "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/iam/aws4_request, SignedHeaders=content-type;host;x-amz-date, Signature=5d672d79c15b13162d9279b0855cfba6789a8edb4c82c400e06b5924a6f2b5d7".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn shared_credentials_profile() {
+        let contents = "[default]\naws_access_key_id = AKIDDEFAULT\naws_secret_access_key = secret\n\n[ci]\naws_access_key_id=AKIDCI\naws_secret_access_key=ci-secret\naws_session_token=token\n";
+
+        let credentials = parse_profile(contents, "ci").unwrap();
+        assert_eq!(credentials.access_key_id, "AKIDCI");
+        assert_eq!(credentials.session_token.as_deref(), Some("token"));
+
+        assert!(parse_profile(contents, "missing").is_none());
+        assert_eq!(
+            uri_encode("logs/2024 01.json", false),
+            "logs/2024%2001.json"
+        );
+    }
+}
@@ -0,0 +1,131 @@
+use std::{
+    sync::{Arc, Mutex, PoisonError},
+    thread::JoinHandle,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    queue::{Pop, PriorityQueue, SHUTDOWN_TIMEOUT},
+    DropPolicy, Importance,
+};
+
+/// A background worker which groups items into batches and delivers each batch as a single HTTP request.
+///
+/// A batch is sent whenever it reaches the configured maximum size or the flush interval elapses,
+/// whichever happens first. Batches which fail due to a connection error, throttling, or a server error
+/// are retried (with exponential backoff) up to the configured number of times. Batches are filled with the
+/// most important items first, and calling [`BatchDispatcher::shutdown`] delivers the outstanding items (for up
+/// to 5 seconds) before returning.
+pub(crate) struct BatchDispatcher<T: Send + 'static> {
+    queue: Arc<PriorityQueue<T>>,
+    thread: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl<T: Send + 'static> BatchDispatcher<T> {
+    pub fn new<F>(
+        name: &str,
+        max_batch_size: usize,
+        interval: Duration,
+        retries: usize,
+        request: F,
+    ) -> Self
+    where
+        F: Fn(&reqwest::blocking::Client, &[T]) -> reqwest::blocking::RequestBuilder
+            + Send
+            + 'static,
+    {
+        let queue = Arc::new(PriorityQueue::<T>::new(name));
+
+        let items = queue.clone();
+        let exporter = name.to_string();
+        let thread = std::thread::Builder::new()
+            .name(format!("tracing-batteries-{name}"))
+            .spawn(move || {
+                let client = reqwest::blocking::Client::new();
+                let send = |batch: &mut Vec<T>| {
+                    if batch.is_empty() {
+                        return;
+                    }
+
+                    for attempt in 0..=retries {
+                        let (retryable, error) = match request(&client, batch.as_slice()).send() {
+                            Ok(response) => {
+                                let status = response.status();
+                                (
+                                    status.is_server_error()
+                                        || status == reqwest::StatusCode::TOO_MANY_REQUESTS,
+                                    (!status.is_success())
+                                        .then(|| format!("the server responded with {status}")),
+                                )
+                            }
+                            Err(err) => (true, Some(err.without_url().to_string())),
+                        };
+
+                        if !retryable || attempt == retries {
+                            if let Some(error) = error {
+                                crate::diagnostics::record_export_error(&exporter, error);
+                            }
+                            break;
+                        }
+
+                        std::thread::sleep(Duration::from_millis(500 << attempt.min(6)));
+                    }
+
+                    batch.clear();
+                };
+
+                let mut batch = Vec::with_capacity(max_batch_size);
+                let mut deadline = Instant::now() + interval;
+                loop {
+                    match items.pop(Some(deadline)) {
+                        Pop::Item(item) => {
+                            batch.push(item);
+                            if batch.len() >= max_batch_size {
+                                send(&mut batch);
+                            }
+                        }
+                        Pop::Timeout => {
+                            send(&mut batch);
+                            deadline = Instant::now() + interval;
+                        }
+                        Pop::Closed => {
+                            send(&mut batch);
+                            break;
+                        }
+                    }
+                }
+            })
+            .ok();
+
+        Self {
+            queue,
+            thread: Mutex::new(thread),
+        }
+    }
+
+    /// Configures the maximum number of items which may be queued, and which are dropped once it is reached.
+    pub fn with_limits(self, capacity: usize, policy: DropPolicy) -> Self {
+        self.queue.set_limits(capacity, policy);
+        self
+    }
+
+    /// Queues an item to be included in an upcoming batch.
+    pub fn push(&self, importance: Importance, item: T) {
+        self.queue.push(importance, item);
+    }
+
+    /// Stops accepting new items, delivers the outstanding items, and waits for them to be sent, dropping any
+    /// which haven't been batched once the shutdown timeout elapses.
+    pub fn shutdown(&self) {
+        self.queue.close(SHUTDOWN_TIMEOUT);
+
+        if let Some(thread) = self
+            .thread
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take()
+        {
+            crate::worker::join(thread);
+        }
+    }
+}
@@ -0,0 +1,333 @@
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+    sync::{atomic::AtomicBool, Arc, Mutex, PoisonError},
+};
+
+use serde_json::Value;
+
+use crate::{
+    hash::fnv1a, Battery, BatteryBuilder, Envelope, EnvelopePayload, ErrorContext, Metadata,
+    Metric, WeakSession,
+};
+
+const CARDINALITY_TARGET: &str = "tracing_batteries::bounded";
+
+/// A combinator which wraps another battery, limiting the number of distinct values each metric attribute and
+/// event property may take, as well as the number of distinct custom event names, before they reach it.
+///
+/// Backends like Prometheus and Honeycomb store (and bill for) every distinct combination of attribute values,
+/// so a single attribute holding an unbounded value, such as a user ID or a URL, can quickly become expensive.
+/// Values which have already been seen are forwarded unchanged, while new values encountered once a limit has
+/// been reached are hashed into one of a fixed number of overflow buckets (named `other-0`, `other-1`, ...),
+/// keeping the total cardinality bounded without discarding the telemetry. A warning is emitted (with the
+/// `tracing_batteries::bounded` target) the first time each limit is exceeded.
+///
+/// By default each attribute key may take up to 100 distinct values, up to 100 distinct event names are
+/// forwarded, and overflowing values are spread across 10 buckets. Errors and page views are forwarded unchanged.
+///
+/// ## Example
+/// ```no_run
+/// use tracing_batteries::{Session, Bounded, Sentry};
+///
+/// let session = Session::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+///   .with_battery(Bounded::new(Sentry::new("https://yourdsn@sentry.example.com"))
+///     .with_max_cardinality(50)
+///     .with_attribute_limit("http.route", 200));
+///
+/// session.shutdown();
+/// ```
+pub struct Bounded<B: BatteryBuilder> {
+    battery: B,
+    max_cardinality: usize,
+    attribute_limits: HashMap<Cow<'static, str>, usize>,
+    max_event_names: usize,
+    buckets: u64,
+}
+
+impl<B: BatteryBuilder> Bounded<B> {
+    /// Wraps the provided battery, using the default cardinality limits.
+    pub fn new(battery: B) -> Self {
+        Self {
+            battery,
+            max_cardinality: 100,
+            attribute_limits: HashMap::new(),
+            max_event_names: 100,
+            buckets: 10,
+        }
+    }
+
+    /// Configures the number of distinct values each attribute key (or event property) may take.
+    pub fn with_max_cardinality(self, max_cardinality: usize) -> Self {
+        Self {
+            max_cardinality,
+            ..self
+        }
+    }
+
+    /// Configures the number of distinct values a specific attribute key (or event property) may take,
+    /// overriding [`Bounded::with_max_cardinality`] for that key.
+    pub fn with_attribute_limit<K: Into<Cow<'static, str>>>(
+        mut self,
+        key: K,
+        limit: usize,
+    ) -> Self {
+        self.attribute_limits.insert(key.into(), limit);
+        self
+    }
+
+    /// Configures the number of distinct custom event names which may be recorded.
+    pub fn with_max_event_names(self, max_event_names: usize) -> Self {
+        Self {
+            max_event_names,
+            ..self
+        }
+    }
+
+    /// Configures the number of overflow buckets which values are hashed into once a limit has been exceeded.
+    pub fn with_buckets(self, buckets: u64) -> Self {
+        Self {
+            buckets: buckets.max(1),
+            ..self
+        }
+    }
+}
+
+impl<B: BatteryBuilder> BatteryBuilder for Bounded<B> {
+    fn setup(self, metadata: &Metadata, enabled: Arc<AtomicBool>) -> Box<dyn Battery> {
+        Box::new(BoundedBattery {
+            inner: self.battery.setup(metadata, enabled),
+            max_cardinality: self.max_cardinality,
+            attribute_limits: self.attribute_limits,
+            max_event_names: self.max_event_names,
+            buckets: self.buckets,
+            seen: Mutex::new(Seen::default()),
+        })
+    }
+}
+
+#[derive(Default)]
+struct Seen {
+    attributes: HashMap<String, HashSet<String>>,
+    event_names: HashSet<String>,
+    /// The attribute keys which have exceeded their limits, so that each is only warned about once.
+    overflowed: HashSet<String>,
+    event_names_overflowed: bool,
+}
+
+struct BoundedBattery {
+    inner: Box<dyn Battery>,
+    max_cardinality: usize,
+    attribute_limits: HashMap<Cow<'static, str>, usize>,
+    max_event_names: usize,
+    buckets: u64,
+    seen: Mutex<Seen>,
+}
+
+impl BoundedBattery {
+    /// Returns the value which should be forwarded for the provided attribute, replacing it with an overflow
+    /// bucket if it would exceed the attribute's cardinality limit.
+    fn attribute<'a>(&self, seen: &mut Seen, key: &str, value: &'a str) -> Cow<'a, str> {
+        let limit = self
+            .attribute_limits
+            .get(key)
+            .copied()
+            .unwrap_or(self.max_cardinality);
+
+        let values = seen.attributes.entry(key.to_string()).or_default();
+        if values.contains(value) {
+            return Cow::Borrowed(value);
+        }
+
+        if values.len() < limit {
+            values.insert(value.to_string());
+            return Cow::Borrowed(value);
+        }
+
+        if seen.overflowed.insert(key.to_string()) {
+            tracing::warn!(
+                target: CARDINALITY_TARGET,
+                attribute = %key,
+                "The '{}' attribute exceeded its limit of {} distinct values, further values will be bucketed",
+                key,
+                limit
+            );
+        }
+
+        Cow::Owned(self.bucket(value))
+    }
+
+    /// Returns the name which should be forwarded for the provided custom event, replacing it with an overflow
+    /// bucket if it would exceed the event name limit.
+    fn event_name(&self, seen: &mut Seen, name: &str) -> String {
+        if seen.event_names.contains(name) {
+            return name.to_string();
+        }
+
+        if seen.event_names.len() < self.max_event_names {
+            seen.event_names.insert(name.to_string());
+            return name.to_string();
+        }
+
+        if !seen.event_names_overflowed {
+            seen.event_names_overflowed = true;
+            tracing::warn!(
+                target: CARDINALITY_TARGET,
+                event = %name,
+                "Custom events exceeded the limit of {} distinct names, further names will be bucketed",
+                self.max_event_names
+            );
+        }
+
+        self.bucket(name)
+    }
+
+    fn bucket(&self, value: &str) -> String {
+        format!("other-{}", fnv1a(value.bytes()) % self.buckets)
+    }
+}
+
+impl Battery for BoundedBattery {
+    fn record_error(&self, error: &dyn std::error::Error) {
+        self.inner.record_error(error);
+    }
+
+    fn record_error_with(&self, error: &dyn std::error::Error, context: &ErrorContext) {
+        self.inner.record_error_with(error, context);
+    }
+
+    fn record_envelope(&self, envelope: &Envelope) {
+        let EnvelopePayload::Event { name, properties } = &envelope.payload else {
+            self.inner.record_envelope(envelope);
+            return;
+        };
+
+        let payload = {
+            let mut seen = self.seen.lock().unwrap_or_else(PoisonError::into_inner);
+            EnvelopePayload::Event {
+                name: self.event_name(&mut seen, name),
+                properties: properties
+                    .iter()
+                    .map(|(key, value)| {
+                        let value = match value {
+                            Value::String(text) => {
+                                Value::String(self.attribute(&mut seen, key, text).into_owned())
+                            }
+                            Value::Bool(_) | Value::Null => value.clone(),
+                            value => match self.attribute(&mut seen, key, &value.to_string()) {
+                                Cow::Owned(bucket) => Value::String(bucket),
+                                Cow::Borrowed(_) => value.clone(),
+                            },
+                        };
+
+                        (key.clone(), value)
+                    })
+                    .collect(),
+            }
+        };
+
+        self.inner.record_envelope(&Envelope {
+            payload,
+            ..envelope.clone()
+        });
+    }
+
+    fn record_metric(&self, metric: &Metric) {
+        let values: Vec<(&'static str, Cow<'_, str>)> = {
+            let mut seen = self.seen.lock().unwrap_or_else(PoisonError::into_inner);
+            metric
+                .attributes
+                .iter()
+                .map(|(key, value)| (*key, self.attribute(&mut seen, key, value)))
+                .collect()
+        };
+
+        if values
+            .iter()
+            .all(|(_, value)| matches!(value, Cow::Borrowed(_)))
+        {
+            self.inner.record_metric(metric);
+            return;
+        }
+
+        let attributes: Vec<(&'static str, &str)> = values
+            .iter()
+            .map(|(key, value)| (*key, value.as_ref()))
+            .collect();
+
+        self.inner.record_metric(&Metric {
+            attributes: &attributes,
+            ..*metric
+        });
+    }
+
+    fn accepts_weighted_histograms(&self) -> bool {
+        self.inner.accepts_weighted_histograms()
+    }
+
+    fn record_artifact(&self, artifact: &crate::Artifact) -> bool {
+        self.inner.record_artifact(artifact)
+    }
+
+    fn release(&self) {
+        self.inner.release();
+    }
+
+    fn shutdown(&self) {
+        self.inner.shutdown();
+    }
+
+    fn attached(&self, session: WeakSession) {
+        self.inner.attached(session);
+    }
+
+    fn validate(&self) -> Vec<crate::ValidationCheck> {
+        self.inner.validate()
+    }
+
+    #[cfg(feature = "version-check")]
+    fn update_available(&self) -> Option<crate::AvailableUpdate> {
+        self.inner.update_available()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{test_support::EnvelopeBattery, Session};
+
+    #[test]
+    fn buckets_overflowing_values() {
+        let battery = EnvelopeBattery::default();
+        let session = Session::new("example", "0.0.1").with_battery(
+            Bounded::new(battery.clone())
+                .with_max_cardinality(2)
+                .with_max_event_names(1)
+                .with_buckets(4),
+        );
+
+        for user in ["alice", "bob", "carol", "alice"] {
+            session.record_event("login", [("user", user)]);
+        }
+        session.record_event("logout", [("user", "alice")]);
+
+        let events: Vec<(String, String)> = battery
+            .envelopes()
+            .iter()
+            .map(|envelope| match &envelope.payload {
+                EnvelopePayload::Event { name, properties } => (
+                    name.clone(),
+                    properties["user"].as_str().unwrap().to_string(),
+                ),
+                payload => panic!("unexpected payload: {payload:?}"),
+            })
+            .collect();
+
+        assert_eq!(events[0], ("login".to_string(), "alice".to_string()));
+        assert_eq!(events[1], ("login".to_string(), "bob".to_string()));
+        assert!(events[2].1.starts_with("other-"));
+        assert_eq!(events[3], ("login".to_string(), "alice".to_string()));
+        assert!(events[4].0.starts_with("other-"));
+        assert_eq!(events[4].1, "alice");
+    }
+}
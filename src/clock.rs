@@ -0,0 +1,315 @@
+use std::{
+    hash::{BuildHasher, Hasher},
+    net::{SocketAddr, ToSocketAddrs, UdpSocket},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use chrono::{DateTime, Utc};
+
+use crate::Metadata;
+
+/// The context field which holds the measured skew of the local clock, in milliseconds.
+pub(crate) const CLOCK_SKEW_KEY: &str = "clock.skew";
+
+/// Skews smaller than this are ignored, since they are indistinguishable from network latency.
+const SKEW_THRESHOLD: Duration = Duration::from_secs(1);
+
+/// Skews larger than this are rejected, since they are more likely to come from a faulty or malicious server than
+/// from the local clock.
+const MAX_SKEW: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// The number of seconds between the NTP epoch (1900-01-01) and the Unix epoch (1970-01-01).
+const NTP_EPOCH_OFFSET: i128 = 2_208_988_800;
+
+impl Metadata {
+    /// Measures how far the local clock has drifted from `pool.ntp.org`, flagging and correcting the telemetry
+    /// recorded by machines whose clocks are badly skewed.
+    ///
+    /// See [`Metadata::with_clock_check_using`] for details.
+    ///
+    /// ## Example
+    /// ```no_run
+    /// use tracing_batteries::{Session, Sentry};
+    ///
+    /// let session = Session::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+    ///   .with_clock_check()
+    ///   .with_battery(Sentry::new("https://yourdsn@sentry.example.com"));
+    /// ```
+    pub fn with_clock_check(self) -> Self {
+        self.with_clock_check_using("pool.ntp.org:123")
+    }
+
+    /// Measures how far the local clock has drifted from the provided (S)NTP server, flagging and correcting the
+    /// telemetry recorded by machines whose clocks are badly skewed.
+    ///
+    /// When the local clock is at least a second out, the skew (in milliseconds, positive when the local clock is
+    /// ahead) is added as the `clock.skew` context field, which batteries attach to the telemetry they send, and
+    /// the timestamps of the session's [`Envelope`](crate::Envelope)s are corrected to match the server's clock.
+    /// Only envelopes are corrected: the timestamps of OpenTelemetry spans, logs, and metrics are assigned by the
+    /// OpenTelemetry SDK and continue to use the local clock, so use the `clock.skew` field to adjust them.
+    ///
+    /// The check is performed once, waiting for up to a second each for the server's address to be resolved and
+    /// for it to respond. If the server cannot be reached, returns an invalid response, or reports a skew of more
+    /// than a day (which is more likely to be a faulty server than a faulty clock), the local clock is trusted.
+    pub fn with_clock_check_using(self, server: &str) -> Self {
+        match measure_skew(server, Duration::from_secs(1)) {
+            Ok(skew) if skew.unsigned_abs() >= SKEW_THRESHOLD.as_millis() as u64 => {
+                tracing::warn!(
+                    skew_ms = skew,
+                    "The local clock differs from '{}' by {}ms, telemetry timestamps will be corrected.",
+                    server,
+                    skew
+                );
+                self.with_context(CLOCK_SKEW_KEY, skew.to_string())
+            }
+            Ok(_) => self,
+            Err(err) => {
+                tracing::debug!(
+                    "Failed to check the local clock against '{}': {}",
+                    server,
+                    err
+                );
+                self
+            }
+        }
+    }
+
+    /// Returns the current time, corrected for the skew measured by [`Metadata::with_clock_check`].
+    pub(crate) fn now(&self) -> DateTime<Utc> {
+        let now = Utc::now();
+        match self
+            .context
+            .get(CLOCK_SKEW_KEY)
+            .and_then(|skew| skew.parse::<i64>().ok())
+        {
+            Some(skew) => now - chrono::Duration::milliseconds(skew),
+            None => now,
+        }
+    }
+}
+
+/// Measures the skew of the local clock using a single SNTP request, returning the number of milliseconds by
+/// which the local clock is ahead of the server.
+fn measure_skew(server: &str, timeout: Duration) -> std::io::Result<i64> {
+    let address = resolve(server, timeout)?;
+    let socket = match address {
+        SocketAddr::V4(_) => UdpSocket::bind("0.0.0.0:0")?,
+        SocketAddr::V6(_) => UdpSocket::bind("[::]:0")?,
+    };
+    socket.set_read_timeout(Some(timeout))?;
+    socket.set_write_timeout(Some(timeout))?;
+    socket.connect(address)?;
+
+    // LI = 0 (no warning), VN = 4, Mode = 3 (client), with a random transmit timestamp which the server must echo
+    // back as the origin timestamp, so that spoofed or stale responses can be rejected.
+    let mut request = [0u8; 48];
+    request[0] = 0x23;
+    let mut hasher = std::collections::hash_map::RandomState::new().build_hasher();
+    hasher.write_u32(std::process::id());
+    request[40..48].copy_from_slice(&(hasher.finish() | 1).to_be_bytes());
+
+    let sent = unix_nanos(SystemTime::now());
+    socket.send(&request)?;
+
+    let mut response = [0u8; 48];
+    let received = socket.recv(&mut response)?;
+    let returned = unix_nanos(SystemTime::now());
+
+    if received < 48 {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "the server returned a truncated response",
+        ));
+    }
+
+    validate_response(&request, &response)?;
+
+    let skew = skew_millis(sent, &response, returned);
+    if skew.unsigned_abs() > MAX_SKEW.as_millis() as u64 {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("the server reported an implausible skew of {skew}ms"),
+        ));
+    }
+
+    Ok(skew)
+}
+
+/// Resolves the server's address on a background thread, since the standard library's resolver has no timeout.
+fn resolve(server: &str, timeout: Duration) -> std::io::Result<SocketAddr> {
+    let (sender, receiver) = std::sync::mpsc::channel();
+    let server = server.to_string();
+    std::thread::Builder::new()
+        .name("tracing-batteries-clock".into())
+        .spawn(move || {
+            sender
+                .send(server.to_socket_addrs().map(|mut addrs| addrs.next()))
+                .ok();
+        })?;
+
+    match receiver.recv_timeout(timeout) {
+        Ok(Ok(Some(address))) => Ok(address),
+        Ok(Ok(None)) => Err(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            "the server's address could not be resolved",
+        )),
+        Ok(Err(err)) => Err(err),
+        Err(_) => Err(std::io::Error::new(
+            std::io::ErrorKind::TimedOut,
+            "timed out while resolving the server's address",
+        )),
+    }
+}
+
+/// Rejects SNTP responses which aren't valid server replies to the provided request, as described in
+/// [RFC 4330 §5](https://datatracker.ietf.org/doc/html/rfc4330#section-5).
+fn validate_response(request: &[u8; 48], response: &[u8; 48]) -> std::io::Result<()> {
+    let invalid = |reason: &str| {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("the server returned an invalid response: {reason}"),
+        ))
+    };
+
+    if response[0] & 0x07 != 4 {
+        return invalid("it is not a server reply");
+    }
+
+    if response[0] >> 6 == 3 {
+        return invalid("the server's clock is not synchronized");
+    }
+
+    match response[1] {
+        0 => return invalid("the server sent a kiss-of-death response"),
+        16..=u8::MAX => return invalid("the server's clock is not synchronized"),
+        _ => {}
+    }
+
+    if response[24..32] != request[40..48] {
+        return invalid("it does not match the request");
+    }
+
+    if response[40..48].iter().all(|&b| b == 0) {
+        return invalid("the transmit timestamp is missing");
+    }
+
+    Ok(())
+}
+
+/// Calculates the skew of the local clock from an SNTP response, using the standard offset calculation
+/// `((t1 - t0) + (t2 - t3)) / 2` and negating it so that a local clock which is ahead has a positive skew.
+fn skew_millis(sent: i128, response: &[u8; 48], returned: i128) -> i64 {
+    let server_received = ntp_nanos(&response[32..40]);
+    let server_sent = ntp_nanos(&response[40..48]);
+
+    let offset = ((server_received - sent) + (server_sent - returned)) / 2;
+    (-offset / 1_000_000) as i64
+}
+
+fn unix_nanos(time: SystemTime) -> i128 {
+    match time.duration_since(UNIX_EPOCH) {
+        Ok(since) => since.as_nanos() as i128,
+        Err(err) => -(err.duration().as_nanos() as i128),
+    }
+}
+
+/// Converts an NTP timestamp (32 bits of seconds since 1900 and 32 bits of fractional seconds) into nanoseconds
+/// since the Unix epoch.
+fn ntp_nanos(timestamp: &[u8]) -> i128 {
+    let seconds =
+        u32::from_be_bytes([timestamp[0], timestamp[1], timestamp[2], timestamp[3]]) as i128;
+    let fraction =
+        u32::from_be_bytes([timestamp[4], timestamp[5], timestamp[6], timestamp[7]]) as i128;
+
+    (seconds - NTP_EPOCH_OFFSET) * 1_000_000_000 + ((fraction * 1_000_000_000) >> 32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Session;
+
+    const NONCE: [u8; 8] = [1, 2, 3, 4, 5, 6, 7, 8];
+
+    fn request() -> [u8; 48] {
+        let mut request = [0u8; 48];
+        request[0] = 0x23;
+        request[40..48].copy_from_slice(&NONCE);
+        request
+    }
+
+    fn response(received: i128, sent: i128) -> [u8; 48] {
+        let encode = |nanos: i128| {
+            let seconds = (nanos / 1_000_000_000 + NTP_EPOCH_OFFSET) as u32;
+            let fraction = (((nanos % 1_000_000_000) << 32) / 1_000_000_000) as u32;
+            [seconds.to_be_bytes(), fraction.to_be_bytes()].concat()
+        };
+
+        // LI = 0 (no warning), VN = 4, Mode = 4 (server), stratum 2
+        let mut response = [0u8; 48];
+        response[0] = 0x24;
+        response[1] = 2;
+        response[24..32].copy_from_slice(&NONCE);
+        response[32..40].copy_from_slice(&encode(received));
+        response[40..48].copy_from_slice(&encode(sent));
+        response
+    }
+
+    #[test]
+    fn calculates_skew() {
+        let local = 1_700_000_000_000_000_000i128;
+        let latency = 20_000_000;
+
+        // The server's clock is 5 seconds behind the local clock.
+        let server = local - 5_000_000_000;
+        let skew = skew_millis(
+            local,
+            &response(server + latency, server + latency + 1_000_000),
+            local + 2 * latency + 1_000_000,
+        );
+        assert_eq!(skew, 5_000);
+
+        let skew = skew_millis(
+            local,
+            &response(local + latency, local + latency),
+            local + 2 * latency,
+        );
+        assert_eq!(skew, 0);
+    }
+
+    #[test]
+    fn validates_responses() {
+        let request = request();
+        let valid = response(1_700_000_000_000_000_000, 1_700_000_000_000_000_000);
+        assert!(validate_response(&request, &valid).is_ok());
+
+        let mut client = valid;
+        client[0] = 0x23;
+        assert!(validate_response(&request, &client).is_err());
+
+        let mut unsynchronized = valid;
+        unsynchronized[0] |= 0xc0;
+        assert!(validate_response(&request, &unsynchronized).is_err());
+
+        let mut kiss_of_death = valid;
+        kiss_of_death[1] = 0;
+        assert!(validate_response(&request, &kiss_of_death).is_err());
+
+        let mut unrelated = valid;
+        unrelated[24..32].copy_from_slice(&[8, 7, 6, 5, 4, 3, 2, 1]);
+        assert!(validate_response(&request, &unrelated).is_err());
+
+        let mut missing_transmit = valid;
+        missing_transmit[40..48].fill(0);
+        assert!(validate_response(&request, &missing_transmit).is_err());
+    }
+
+    #[test]
+    fn corrects_timestamps() {
+        let metadata = Session::new("example", "0.0.1").with_context(CLOCK_SKEW_KEY, "60000");
+
+        let corrected = metadata.now();
+        let expected = Utc::now() - chrono::Duration::minutes(1);
+        assert!((corrected - expected).num_seconds().abs() < 5);
+    }
+}
@@ -0,0 +1,242 @@
+use std::{
+    collections::HashMap,
+    fmt::Write,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use tracing::{
+    callsite::Identifier,
+    field::{Field, Visit},
+    Event, Level, Metadata, Subscriber,
+};
+use tracing_subscriber::{layer::Context, Layer};
+
+const COALESCE_TARGET: &str = "tracing_batteries::coalesce";
+
+/// The maximum number of distinct events which are tracked between flushes, bounding the memory used by
+/// applications which emit many unique events. Once reached, new events are passed through without being
+/// coalesced until the next flush.
+const MAX_PENDING_EVENTS: usize = 4096;
+
+type PendingEvents = Arc<Mutex<HashMap<EventKey, CoalescedEvent>>>;
+
+/// A [`Layer`] which collapses identical events emitted in quick succession into a single event.
+///
+/// The first occurrence of an event is always passed through, while any identical events (those
+/// emitted from the same callsite with the same field values) which follow it are suppressed until
+/// the next flush. When the layer is flushed, a summary event is emitted for each suppressed event
+/// with a `count` field indicating how many times it was repeated.
+pub(crate) struct CoalescingLayer {
+    events: PendingEvents,
+}
+
+/// A handle to a [`CoalescingLayer`] which allows its suppressed events to be flushed on shutdown, so that the
+/// repeats from the final interval aren't lost.
+#[derive(Clone)]
+pub(crate) struct CoalescingFlush(PendingEvents);
+
+impl CoalescingFlush {
+    pub fn flush(&self) {
+        CoalescingLayer::flush(&self.0);
+    }
+}
+
+#[derive(PartialEq, Eq, Hash)]
+struct EventKey {
+    callsite: Identifier,
+    fields: String,
+}
+
+struct CoalescedEvent {
+    metadata: &'static Metadata<'static>,
+    fields: String,
+    suppressed: usize,
+}
+
+impl CoalescingLayer {
+    pub fn new(interval: Duration) -> Self {
+        let events = Arc::new(Mutex::new(HashMap::new()));
+
+        let flushed_events = Arc::downgrade(&events);
+        std::thread::Builder::new()
+            .name("tracing-batteries-coalesce".into())
+            .spawn(move || loop {
+                std::thread::sleep(interval);
+                match flushed_events.upgrade() {
+                    Some(events) => Self::flush(&events),
+                    None => break,
+                }
+            })
+            .ok();
+
+        Self { events }
+    }
+
+    pub fn flusher(&self) -> CoalescingFlush {
+        CoalescingFlush(self.events.clone())
+    }
+
+    fn flush(events: &Mutex<HashMap<EventKey, CoalescedEvent>>) {
+        let coalesced: Vec<CoalescedEvent> = match events.lock() {
+            Ok(mut events) => events
+                .drain()
+                .map(|(_, event)| event)
+                .filter(|event| event.suppressed > 0)
+                .collect(),
+            Err(_) => return,
+        };
+
+        for event in coalesced {
+            let source = event.metadata.target();
+            let count = event.suppressed;
+            let fields = event.fields;
+
+            match *event.metadata.level() {
+                Level::ERROR => {
+                    tracing::error!(target: COALESCE_TARGET, count, source, "{}", fields)
+                }
+                Level::WARN => {
+                    tracing::warn!(target: COALESCE_TARGET, count, source, "{}", fields)
+                }
+                Level::INFO => {
+                    tracing::info!(target: COALESCE_TARGET, count, source, "{}", fields)
+                }
+                Level::DEBUG => {
+                    tracing::debug!(target: COALESCE_TARGET, count, source, "{}", fields)
+                }
+                Level::TRACE => {
+                    tracing::trace!(target: COALESCE_TARGET, count, source, "{}", fields)
+                }
+            }
+        }
+    }
+}
+
+impl<S: Subscriber> Layer<S> for CoalescingLayer {
+    fn event_enabled(&self, event: &Event<'_>, _ctx: Context<'_, S>) -> bool {
+        if event.metadata().target() == COALESCE_TARGET {
+            return true;
+        }
+
+        let mut fields = String::new();
+        event.record(&mut FieldFormatter(&mut fields));
+
+        let key = EventKey {
+            callsite: event.metadata().callsite(),
+            fields,
+        };
+
+        let Ok(mut events) = self.events.lock() else {
+            return true;
+        };
+
+        match events.get_mut(&key) {
+            Some(coalesced) => {
+                coalesced.suppressed += 1;
+                false
+            }
+            None if events.len() >= MAX_PENDING_EVENTS => true,
+            None => {
+                let fields = key.fields.clone();
+                events.insert(
+                    key,
+                    CoalescedEvent {
+                        metadata: event.metadata(),
+                        fields,
+                        suppressed: 0,
+                    },
+                );
+                true
+            }
+        }
+    }
+}
+
+struct FieldFormatter<'a>(&'a mut String);
+
+impl Visit for FieldFormatter<'_> {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if !self.0.is_empty() {
+            self.0.push(' ');
+        }
+
+        if field.name() == "message" {
+            let _ = write!(self.0, "{:?}", value);
+        } else {
+            let _ = write!(self.0, "{}={:?}", field.name(), value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use tracing_subscriber::layer::SubscriberExt;
+
+    use super::*;
+
+    #[test]
+    fn coalesces_repeated_events() {
+        let layer = CoalescingLayer::new(Duration::from_secs(3600));
+        let events = layer.events.clone();
+        let emitted = Arc::new(AtomicUsize::new(0));
+
+        let subscriber = tracing_subscriber::registry()
+            .with(layer)
+            .with(CountingLayer(emitted.clone()));
+
+        tracing::subscriber::with_default(subscriber, || {
+            for _ in 0..5 {
+                tracing::info!(attempt = 1, "retrying request");
+            }
+
+            tracing::info!(attempt = 2, "retrying request");
+            assert_eq!(emitted.load(Ordering::Relaxed), 2);
+
+            CoalescingLayer::flush(&events);
+            assert_eq!(emitted.load(Ordering::Relaxed), 3);
+
+            tracing::info!(attempt = 1, "retrying request");
+            assert_eq!(emitted.load(Ordering::Relaxed), 4);
+        });
+    }
+
+    #[test]
+    fn caps_pending_events() {
+        let layer = CoalescingLayer::new(Duration::from_secs(3600));
+        let flusher = layer.flusher();
+        let emitted = Arc::new(AtomicUsize::new(0));
+
+        let subscriber = tracing_subscriber::registry()
+            .with(layer)
+            .with(CountingLayer(emitted.clone()));
+
+        tracing::subscriber::with_default(subscriber, || {
+            for id in 0..MAX_PENDING_EVENTS {
+                tracing::info!(id, "processed item");
+            }
+            assert_eq!(flusher.0.lock().unwrap().len(), MAX_PENDING_EVENTS);
+
+            // Events beyond the cap are neither tracked nor suppressed.
+            tracing::info!(id = MAX_PENDING_EVENTS, "processed item");
+            tracing::info!(id = MAX_PENDING_EVENTS, "processed item");
+            assert_eq!(emitted.load(Ordering::Relaxed), MAX_PENDING_EVENTS + 2);
+            assert_eq!(flusher.0.lock().unwrap().len(), MAX_PENDING_EVENTS);
+
+            tracing::info!(id = 0, "processed item");
+            flusher.flush();
+            assert_eq!(emitted.load(Ordering::Relaxed), MAX_PENDING_EVENTS + 3);
+            assert!(flusher.0.lock().unwrap().is_empty());
+        });
+    }
+
+    struct CountingLayer(Arc<AtomicUsize>);
+
+    impl<S: Subscriber> Layer<S> for CountingLayer {
+        fn on_event(&self, _event: &Event<'_>, _ctx: Context<'_, S>) {
+            self.0.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
@@ -0,0 +1,121 @@
+use clap::{parser::ValueSource, ArgMatches};
+
+use crate::Session;
+
+impl Session {
+    /// Records which command the user ran as a `command_usage` event, giving you statistics on how the
+    /// different parts of your command line interface are used without adding tracking calls to each command.
+    ///
+    /// <div class="warning">
+    ///
+    /// This integration requires the `clap` feature to be enabled.
+    ///
+    /// </div>
+    ///
+    /// The event is reported to your analytics batteries through [`Session::record_event`] with two properties:
+    /// `command`, the space-separated path of the subcommand which was run (e.g. `remote add`), and `flags`, the
+    /// comma-separated, sorted names of the arguments which were provided on the command line. Only the names of
+    /// arguments are recorded, never their values, and arguments which were populated from their default value or
+    /// an environment variable are omitted.
+    ///
+    /// ## Example
+    /// ```no_run
+    /// use clap::{Arg, ArgAction, Command};
+    /// use tracing_batteries::{Session, Pirsch};
+    ///
+    /// let matches = Command::new("git")
+    ///   .subcommand(Command::new("status").arg(Arg::new("short").short('s').action(ArgAction::SetTrue)))
+    ///   .get_matches();
+    ///
+    /// let session = Session::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+    ///   .with_battery(Pirsch::new("app.example.com", "your-client-id", "your-client-secret"));
+    ///
+    /// session.record_command_usage(&matches);
+    /// session.shutdown();
+    /// ```
+    pub fn record_command_usage(&self, matches: &ArgMatches) {
+        let (command, flags) = command_usage(matches);
+        self.record_event(
+            "command_usage",
+            [("command", command), ("flags", flags.join(","))],
+        );
+    }
+}
+
+/// Resolves the subcommand path and the names of the arguments provided on the command line (at any level of the
+/// subcommand path) from the provided matches.
+fn command_usage(matches: &ArgMatches) -> (String, Vec<String>) {
+    let mut path = Vec::new();
+    let mut flags = Vec::new();
+
+    let mut current = Some(matches);
+    while let Some(matches) = current {
+        flags.extend(
+            matches
+                .ids()
+                .filter(|id| matches.value_source(id.as_str()) == Some(ValueSource::CommandLine))
+                .map(|id| id.as_str().to_string()),
+        );
+
+        current = matches.subcommand().map(|(name, matches)| {
+            path.push(name);
+            matches
+        });
+    }
+
+    flags.sort();
+    flags.dedup();
+
+    (path.join(" "), flags)
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::{Arg, ArgAction, Command};
+
+    use super::*;
+
+    #[test]
+    fn resolves_command_usage() {
+        let command = Command::new("git")
+            .arg(
+                Arg::new("verbose")
+                    .short('v')
+                    .global(true)
+                    .action(ArgAction::SetTrue),
+            )
+            .subcommand(
+                Command::new("remote").subcommand(
+                    Command::new("add")
+                        .arg(Arg::new("fetch").short('f').action(ArgAction::SetTrue))
+                        .arg(Arg::new("tags").long("tags").action(ArgAction::SetTrue))
+                        .arg(Arg::new("name"))
+                        .arg(Arg::new("url")),
+                ),
+            );
+
+        let matches = command
+            .clone()
+            .try_get_matches_from([
+                "git",
+                "remote",
+                "add",
+                "-f",
+                "origin",
+                "https://example.com/repo.git",
+            ])
+            .expect("the arguments should be valid");
+        assert_eq!(
+            command_usage(&matches),
+            (
+                "remote add".to_string(),
+                vec!["fetch".to_string(), "name".to_string(), "url".to_string()]
+            )
+        );
+
+        let matches = command
+            .try_get_matches_from(["git"])
+            .expect("the arguments should be valid");
+        assert_eq!(command_usage(&matches), (String::new(), Vec::new()));
+    }
+}
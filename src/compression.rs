@@ -0,0 +1,324 @@
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    future::Future,
+    pin::Pin,
+};
+
+use opentelemetry::{trace::SpanId, KeyValue};
+use opentelemetry_sdk::export::trace::{ExportResult, SpanData, SpanExporter};
+
+/// Attributes which differ between otherwise identical spans, and which are ignored when deciding whether two
+/// spans are repetitions of one another.
+const VOLATILE_ATTRIBUTES: &[&str] = &["busy_ns", "idle_ns", "thread.id", "thread.name"];
+
+/// The maximum number of parent spans which are remembered while waiting for them to be exported.
+const MAX_TRACKED_PARENTS: usize = 10_000;
+
+/// A [`SpanExporter`] which collapses runs of identical consecutive sibling spans (those with the same parent,
+/// name, status and attributes), such as the spans produced by tight retry and polling loops, into a single span
+/// before passing them on to the wrapped exporter.
+///
+/// Runs which are longer than the threshold are replaced by their first span, stretched to end when the last span
+/// in the run ended, and given a `compression.count` attribute holding the number of spans it represents. Spans
+/// which are the parents of other spans are never collapsed, so that their children are not orphaned, and neither
+/// are spans with events or links, which would otherwise be lost. Since children end before their parents, they
+/// are often exported in an earlier batch, so the parents seen in each batch are remembered until they are exported.
+#[derive(Debug)]
+pub(crate) struct CompressingSpanExporter<E> {
+    inner: E,
+    threshold: Option<usize>,
+    parents: RecentParents,
+}
+
+impl<E: SpanExporter> CompressingSpanExporter<E> {
+    pub fn new(inner: E, threshold: Option<usize>) -> Self {
+        Self {
+            inner,
+            threshold,
+            parents: RecentParents::default(),
+        }
+    }
+}
+
+impl<E: SpanExporter> SpanExporter for CompressingSpanExporter<E> {
+    fn export(
+        &mut self,
+        batch: Vec<SpanData>,
+    ) -> Pin<Box<dyn Future<Output = ExportResult> + Send + 'static>> {
+        match self.threshold {
+            Some(threshold) => {
+                let batch = compress(batch, threshold, &mut self.parents);
+                self.inner.export(batch)
+            }
+            None => self.inner.export(batch),
+        }
+    }
+
+    fn shutdown(&mut self) {
+        self.inner.shutdown()
+    }
+
+    fn force_flush(&mut self) -> Pin<Box<dyn Future<Output = ExportResult> + Send + 'static>> {
+        self.inner.force_flush()
+    }
+
+    fn set_resource(&mut self, resource: &opentelemetry_sdk::Resource) {
+        self.inner.set_resource(resource)
+    }
+}
+
+/// The IDs of the spans which have had children exported, remembered until the spans themselves are exported.
+#[derive(Debug, Default)]
+struct RecentParents {
+    ids: HashSet<SpanId>,
+    order: VecDeque<SpanId>,
+}
+
+impl RecentParents {
+    fn insert(&mut self, id: SpanId) {
+        if id == SpanId::INVALID || !self.ids.insert(id) {
+            return;
+        }
+
+        self.order.push_back(id);
+        if self.order.len() > MAX_TRACKED_PARENTS {
+            if let Some(oldest) = self.order.pop_front() {
+                self.ids.remove(&oldest);
+            }
+        }
+    }
+
+    fn contains(&self, id: &SpanId) -> bool {
+        self.ids.contains(id)
+    }
+
+    fn remove(&mut self, id: &SpanId) {
+        if self.ids.remove(id) {
+            self.order.retain(|parent| parent != id);
+        }
+    }
+}
+
+fn compress(
+    mut batch: Vec<SpanData>,
+    threshold: usize,
+    parents: &mut RecentParents,
+) -> Vec<SpanData> {
+    for span in &batch {
+        parents.insert(span.parent_span_id);
+    }
+
+    let mut siblings: HashMap<SpanId, Vec<usize>> = HashMap::new();
+    for (index, span) in batch.iter().enumerate() {
+        if span.parent_span_id != SpanId::INVALID {
+            siblings.entry(span.parent_span_id).or_default().push(index);
+        }
+    }
+
+    let mut removed = vec![false; batch.len()];
+    for mut indices in siblings.into_values() {
+        if indices.len() <= threshold {
+            continue;
+        }
+
+        indices.sort_by_key(|&index| batch[index].start_time);
+        let keys: Vec<_> = indices
+            .iter()
+            .map(|&index| {
+                let span = &batch[index];
+                let collapsible = !parents.contains(&span.span_context.span_id())
+                    && span.events.is_empty()
+                    && span.links.is_empty();
+
+                collapsible.then(|| {
+                    (
+                        &span.name,
+                        &span.status,
+                        span.attributes
+                            .iter()
+                            .filter(|kv| !VOLATILE_ATTRIBUTES.contains(&kv.key.as_str()))
+                            .collect::<Vec<_>>(),
+                    )
+                })
+            })
+            .collect();
+
+        for (start, length) in repeated_runs(&keys, threshold) {
+            let run = &indices[start..start + length];
+            let end_time = run
+                .iter()
+                .map(|&index| batch[index].end_time)
+                .max()
+                .unwrap_or(batch[run[0]].end_time);
+
+            let first = &mut batch[run[0]];
+            first.end_time = end_time;
+            first
+                .attributes
+                .push(KeyValue::new("compression.count", length as i64));
+
+            for &index in &run[1..] {
+                removed[index] = true;
+            }
+        }
+    }
+
+    for span in &batch {
+        parents.remove(&span.span_context.span_id());
+    }
+
+    batch
+        .into_iter()
+        .zip(removed)
+        .filter(|(_, removed)| !removed)
+        .map(|(span, _)| span)
+        .collect()
+}
+
+/// Finds the runs of identical consecutive keys which are longer than the threshold, returning the index at which
+/// each run starts and its length. Keys which are `None` are never considered part of a run.
+fn repeated_runs<K: PartialEq>(keys: &[Option<K>], threshold: usize) -> Vec<(usize, usize)> {
+    let mut runs = Vec::new();
+
+    let mut start = 0;
+    while start < keys.len() {
+        let mut end = start + 1;
+        if keys[start].is_some() {
+            while end < keys.len() && keys[end] == keys[start] {
+                end += 1;
+            }
+
+            if end - start > threshold {
+                runs.push((start, end - start));
+            }
+        }
+
+        start = end;
+    }
+
+    runs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_repeated_runs() {
+        let keys = [
+            Some("connect"),
+            Some("poll"),
+            Some("poll"),
+            Some("poll"),
+            None,
+            None,
+            Some("poll"),
+            Some("poll"),
+            Some("close"),
+        ];
+
+        assert_eq!(repeated_runs(&keys, 2), [(1, 3)]);
+        assert_eq!(repeated_runs(&keys, 1), [(1, 3), (6, 2)]);
+        assert!(repeated_runs(&keys, 3).is_empty());
+    }
+
+    fn span(id: u64, parent: u64, start: u64) -> SpanData {
+        use opentelemetry::{
+            trace::{SpanContext, SpanKind, Status, TraceFlags, TraceId, TraceState},
+            InstrumentationScope,
+        };
+        use std::time::{Duration, UNIX_EPOCH};
+
+        SpanData {
+            span_context: SpanContext::new(
+                TraceId::from_u128(1),
+                SpanId::from_u64(id),
+                TraceFlags::SAMPLED,
+                false,
+                TraceState::default(),
+            ),
+            parent_span_id: if parent == 0 {
+                SpanId::INVALID
+            } else {
+                SpanId::from_u64(parent)
+            },
+            span_kind: SpanKind::Internal,
+            name: "poll".into(),
+            start_time: UNIX_EPOCH + Duration::from_secs(start),
+            end_time: UNIX_EPOCH + Duration::from_secs(start + 1),
+            attributes: vec![KeyValue::new("busy_ns", start as i64)],
+            dropped_attributes_count: 0,
+            events: Default::default(),
+            links: Default::default(),
+            status: Status::Unset,
+            instrumentation_scope: InstrumentationScope::builder("test").build(),
+        }
+    }
+
+    #[test]
+    fn compresses_spans() {
+        let mut with_event = span(14, 1, 14);
+        with_event
+            .events
+            .events
+            .push(opentelemetry::trace::Event::with_name("retrying"));
+
+        let batch = vec![
+            span(1, 0, 0),
+            span(10, 1, 10),
+            span(11, 1, 11),
+            span(12, 1, 12),
+            span(13, 1, 13),
+            with_event,
+            span(20, 12, 12),
+        ];
+
+        let compressed = compress(batch, 1, &mut RecentParents::default());
+        let ids: Vec<u64> = compressed
+            .iter()
+            .map(|span| u64::from_be_bytes(span.span_context.span_id().to_bytes()))
+            .collect();
+        assert_eq!(ids, [1, 10, 12, 13, 14, 20]);
+
+        let first = &compressed[1];
+        assert_eq!(
+            first.end_time,
+            std::time::UNIX_EPOCH + std::time::Duration::from_secs(12)
+        );
+        assert!(first
+            .attributes
+            .contains(&KeyValue::new("compression.count", 2i64)));
+
+        assert_eq!(compressed[4].events.len(), 1);
+        assert!(compressed
+            .iter()
+            .filter(|span| span.span_context.span_id() != SpanId::from_u64(10))
+            .all(|span| !span
+                .attributes
+                .iter()
+                .any(|kv| kv.key.as_str() == "compression.count")));
+    }
+
+    #[test]
+    fn keeps_parents_of_exported_children() {
+        let mut parents = RecentParents::default();
+
+        let children = compress(vec![span(20, 12, 12)], 1, &mut parents);
+        assert_eq!(children.len(), 1);
+
+        let batch = vec![
+            span(1, 0, 0),
+            span(10, 1, 10),
+            span(11, 1, 11),
+            span(12, 1, 12),
+        ];
+
+        let compressed = compress(batch, 1, &mut parents);
+        let ids: Vec<u64> = compressed
+            .iter()
+            .map(|span| u64::from_be_bytes(span.span_context.span_id().to_bytes()))
+            .collect();
+        assert_eq!(ids, [1, 10, 12]);
+        assert!(!parents.contains(&SpanId::from_u64(12)));
+    }
+}
@@ -0,0 +1,398 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc, Mutex, PoisonError,
+    },
+    thread::JoinHandle,
+    time::Duration,
+};
+
+use crate::{
+    deferred::{Held, HeldError},
+    Battery, BatteryBuilder, Envelope, ErrorContext, Metadata, Metric, WeakSession,
+};
+
+/// The state of the machine's network connection, as observed by the [`Connectivity`] combinator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NetworkState {
+    /// The machine is connected to an unmetered network (or its connection could not be determined).
+    Online,
+    /// The machine is connected only through a metered connection, such as a cellular modem.
+    Metered,
+    /// The machine has no active network connection.
+    Offline,
+}
+
+impl NetworkState {
+    /// Determines the state of the machine's network connection.
+    ///
+    /// On Linux, this inspects the physical network interfaces listed in `/sys/class/net`: the machine is offline
+    /// when none of them are up, and metered when the only interfaces which are up are cellular (`wwan*`) or
+    /// point-to-point (`ppp*`) links. Machines without any physical interfaces (such as containers) and other
+    /// platforms are always reported as [`NetworkState::Online`], since their connectivity cannot be detected.
+    pub fn detect() -> Self {
+        #[cfg(target_os = "linux")]
+        {
+            let interfaces = std::fs::read_dir("/sys/class/net")
+                .map(|entries| {
+                    entries
+                        .filter_map(|entry| entry.ok())
+                        .filter(|entry| entry.path().join("device").exists())
+                        .map(|entry| {
+                            let up = std::fs::read_to_string(entry.path().join("operstate"))
+                                .is_ok_and(|state| state.trim() == "up");
+                            (entry.file_name().to_string_lossy().into_owned(), up)
+                        })
+                        .collect::<Vec<_>>()
+                })
+                .unwrap_or_default();
+
+            Self::from_interfaces(&interfaces)
+        }
+
+        #[cfg(not(target_os = "linux"))]
+        NetworkState::Online
+    }
+
+    #[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+    fn from_interfaces(interfaces: &[(String, bool)]) -> Self {
+        if interfaces.is_empty() {
+            return NetworkState::Online;
+        }
+
+        let mut up = interfaces
+            .iter()
+            .filter(|(_, up)| *up)
+            .map(|(name, _)| name)
+            .peekable();
+        if up.peek().is_none() {
+            NetworkState::Offline
+        } else if up.all(|name| name.starts_with("wwan") || name.starts_with("ppp")) {
+            NetworkState::Metered
+        } else {
+            NetworkState::Online
+        }
+    }
+}
+
+/// A combinator which wraps a network battery, pausing it while the machine is offline (or only connected through
+/// a metered connection) and replaying the telemetry recorded in the meantime, in order, once connectivity returns.
+///
+/// <div class="warning">
+///
+/// This combinator requires the `connectivity` feature to be enabled.
+///
+/// </div>
+///
+/// This avoids burning through retries (and the user's battery) on laptops and other devices which regularly
+/// lose their connection. The connection is checked every 10 seconds (configurable using
+/// [`Connectivity::with_interval`]) using [`NetworkState::detect`], or a custom check provided with
+/// [`Connectivity::with_check`].
+///
+/// Up to 10,000 errors, envelopes and metrics are held while paused (configurable using
+/// [`Connectivity::with_capacity`]), after which further telemetry is dropped until connectivity returns. Telemetry
+/// which is still held when the session is shut down is discarded.
+///
+/// ## Example
+/// ```no_run
+/// use tracing_batteries::{Session, Connectivity, Sentry};
+///
+/// let session = Session::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+///   .with_battery(Connectivity::new(Sentry::new("https://yourdsn@sentry.example.com")));
+///
+/// session.shutdown();
+/// ```
+pub struct Connectivity<B: BatteryBuilder> {
+    battery: B,
+    capacity: usize,
+    interval: Duration,
+    metered: bool,
+    check: Arc<dyn Fn() -> NetworkState + Send + Sync>,
+}
+
+impl<B: BatteryBuilder> Connectivity<B> {
+    /// Wraps the provided battery, pausing it while the machine is offline or on a metered connection.
+    pub fn new(battery: B) -> Self {
+        Self {
+            battery,
+            capacity: 10_000,
+            interval: Duration::from_secs(10),
+            metered: false,
+            check: Arc::new(NetworkState::detect),
+        }
+    }
+
+    /// Configures whether telemetry should continue to be sent while the machine is on a metered connection.
+    pub fn with_metered(self, metered: bool) -> Self {
+        Self { metered, ..self }
+    }
+
+    /// Configures the maximum number of errors, envelopes and metrics which are held while the battery is paused.
+    pub fn with_capacity(self, capacity: usize) -> Self {
+        Self { capacity, ..self }
+    }
+
+    /// Configures how frequently the machine's network connection is checked.
+    pub fn with_interval(self, interval: Duration) -> Self {
+        Self { interval, ..self }
+    }
+
+    /// Replaces the check used to determine the state of the machine's network connection, for example to
+    /// query the platform's network manager or to honour a user's "offline mode" setting.
+    pub fn with_check<F: Fn() -> NetworkState + Send + Sync + 'static>(self, check: F) -> Self {
+        Self {
+            check: Arc::new(check),
+            ..self
+        }
+    }
+}
+
+impl<B: BatteryBuilder> BatteryBuilder for Connectivity<B> {
+    fn setup(self, metadata: &Metadata, enabled: Arc<AtomicBool>) -> Box<dyn Battery> {
+        let gate = Arc::new(ConnectivityGate {
+            inner: self.battery.setup(metadata, enabled),
+            capacity: self.capacity,
+            held: Mutex::new(None),
+        });
+
+        let metered = self.metered;
+        let check = self.check;
+        let connected = move || match check() {
+            NetworkState::Online => true,
+            NetworkState::Metered => metered,
+            NetworkState::Offline => false,
+        };
+
+        if !connected() {
+            gate.pause();
+        }
+
+        let (stop, stopped) = mpsc::channel::<()>();
+        let interval = self.interval;
+        let monitor = gate.clone();
+        let thread = std::thread::Builder::new()
+            .name("tracing-batteries-connectivity".into())
+            .spawn(move || {
+                while let Err(mpsc::RecvTimeoutError::Timeout) = stopped.recv_timeout(interval) {
+                    if connected() {
+                        monitor.resume();
+                    } else {
+                        monitor.pause();
+                    }
+                }
+            })
+            .ok();
+
+        Box::new(ConnectivityBattery {
+            gate,
+            stop: Mutex::new(Some(stop)),
+            thread: Mutex::new(thread),
+        })
+    }
+}
+
+struct ConnectivityGate {
+    inner: Box<dyn Battery>,
+    capacity: usize,
+    /// The telemetry recorded while the battery is paused, or `None` while it is connected.
+    held: Mutex<Option<Vec<Held>>>,
+}
+
+impl ConnectivityGate {
+    /// Holds the telemetry if the battery is paused, returning `true` if it should be forwarded.
+    fn hold<F: FnOnce() -> Held>(&self, held: F) -> bool {
+        match &mut *self.held.lock().unwrap_or_else(PoisonError::into_inner) {
+            Some(buffer) => {
+                if buffer.len() < self.capacity {
+                    buffer.push(held());
+                }
+                false
+            }
+            None => true,
+        }
+    }
+
+    fn pause(&self) {
+        let mut held = self.held.lock().unwrap_or_else(PoisonError::into_inner);
+        if held.is_none() {
+            *held = Some(Vec::new());
+            tracing::info!("The network is unavailable, pausing telemetry until it returns.");
+        }
+    }
+
+    fn resume(&self) {
+        // Telemetry is replayed without holding the lock, since the wrapped battery may report telemetry through
+        // the session (and so back into this battery) while it is being replayed. Anything recorded in the
+        // meantime is held and replayed afterwards, preserving the order in which it was recorded.
+        let mut replayed = 0;
+        loop {
+            let held = {
+                let mut state = self.held.lock().unwrap_or_else(PoisonError::into_inner);
+                match &mut *state {
+                    Some(buffer) if !buffer.is_empty() => std::mem::take(buffer),
+                    Some(_) => {
+                        *state = None;
+                        break;
+                    }
+                    None => return,
+                }
+            };
+
+            replayed += held.len();
+            for held in held {
+                held.replay(self.inner.as_ref());
+            }
+        }
+
+        tracing::info!(
+            replayed,
+            "The network is available again, resuming telemetry."
+        );
+    }
+
+    fn discard(&self) {
+        self.held
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take();
+    }
+}
+
+struct ConnectivityBattery {
+    gate: Arc<ConnectivityGate>,
+    stop: Mutex<Option<mpsc::Sender<()>>>,
+    thread: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl Battery for ConnectivityBattery {
+    fn record_error(&self, error: &dyn std::error::Error) {
+        if self.gate.hold(|| Held::Error(HeldError::new(error), None)) {
+            self.gate.inner.record_error(error);
+        }
+    }
+
+    fn record_error_with(&self, error: &dyn std::error::Error, context: &ErrorContext) {
+        if self
+            .gate
+            .hold(|| Held::Error(HeldError::new(error), Some(context.clone())))
+        {
+            self.gate.inner.record_error_with(error, context);
+        }
+    }
+
+    fn record_envelope(&self, envelope: &Envelope) {
+        if self.gate.hold(|| Held::Envelope(envelope.clone())) {
+            self.gate.inner.record_envelope(envelope);
+        }
+    }
+
+    fn record_metric(&self, metric: &Metric) {
+        if self.gate.hold(|| Held::metric(metric)) {
+            self.gate.inner.record_metric(metric);
+        }
+    }
+
+    fn accepts_weighted_histograms(&self) -> bool {
+        self.gate.inner.accepts_weighted_histograms()
+    }
+
+    fn record_artifact(&self, artifact: &crate::Artifact) -> bool {
+        self.gate.hold(|| Held::Artifact(artifact.clone()))
+            && self.gate.inner.record_artifact(artifact)
+    }
+
+    fn release(&self) {
+        self.gate.inner.release();
+    }
+
+    fn shutdown(&self) {
+        self.stop
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take();
+
+        if let Some(thread) = self
+            .thread
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take()
+        {
+            crate::worker::join(thread);
+        }
+
+        self.gate.discard();
+        self.gate.inner.shutdown();
+    }
+
+    fn attached(&self, session: WeakSession) {
+        self.gate.inner.attached(session);
+    }
+
+    fn validate(&self) -> Vec<crate::ValidationCheck> {
+        self.gate.inner.validate()
+    }
+
+    #[cfg(feature = "version-check")]
+    fn update_available(&self) -> Option<crate::AvailableUpdate> {
+        self.gate.inner.update_available()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicU8;
+
+    use super::*;
+    use crate::{test_support::EnvelopeBattery, Session};
+
+    #[test]
+    fn detects_network_state() {
+        let interface = |name: &str, up: bool| (name.to_string(), up);
+
+        assert_eq!(NetworkState::from_interfaces(&[]), NetworkState::Online);
+        assert_eq!(
+            NetworkState::from_interfaces(&[interface("eth0", false), interface("wlan0", false)]),
+            NetworkState::Offline
+        );
+        assert_eq!(
+            NetworkState::from_interfaces(&[interface("eth0", false), interface("wwan0", true)]),
+            NetworkState::Metered
+        );
+        assert_eq!(
+            NetworkState::from_interfaces(&[interface("wlan0", true), interface("wwan0", true)]),
+            NetworkState::Online
+        );
+    }
+
+    #[test]
+    fn replays_once_connected() {
+        let state = Arc::new(AtomicU8::new(NetworkState::Offline as u8));
+        let battery = EnvelopeBattery::default();
+
+        let check = state.clone();
+        let session = Session::new("example", "0.0.1").with_battery(
+            Connectivity::new(battery.clone())
+                .with_interval(Duration::from_millis(10))
+                .with_check(move || match check.load(Ordering::SeqCst) {
+                    0 => NetworkState::Online,
+                    1 => NetworkState::Metered,
+                    _ => NetworkState::Offline,
+                }),
+        );
+
+        session.record_new_page("/first");
+        session.record_new_page("/second");
+        assert!(battery.pages().is_empty());
+
+        state.store(NetworkState::Metered as u8, Ordering::SeqCst);
+        std::thread::sleep(Duration::from_millis(100));
+        assert!(battery.pages().is_empty());
+
+        state.store(NetworkState::Online as u8, Ordering::SeqCst);
+        std::thread::sleep(Duration::from_millis(100));
+        session.record_new_page("/third");
+        assert_eq!(battery.pages(), ["/first", "/second", "/third"]);
+
+        session.shutdown();
+    }
+}
@@ -0,0 +1,438 @@
+use std::{
+    fmt::{Debug, Display},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, OnceLock, PoisonError,
+    },
+};
+
+use crate::{
+    Battery, BatteryBuilder, Envelope, ErrorContext, Metadata, Metric, MetricKind, Session,
+    WeakSession,
+};
+
+/// A combinator which wraps another battery, delaying its setup until [`Session::release`] is called and then
+/// replaying the telemetry recorded in the meantime, in order, into the wrapped battery.
+///
+/// This is useful when telemetry cannot (or must not) be sent as soon as your application starts, for example
+/// until a proxy or VPN connection has been established, or until the user has consented to telemetry being
+/// collected, without losing the errors and events which are recorded during startup. Since the wrapped battery
+/// isn't set up until the session is released, it has no opportunity to report anything (such as the sessions and
+/// panics which Sentry captures itself, or the spans exported by OpenTelemetry) before then.
+///
+/// Up to 10,000 errors, envelopes and metrics are held (configurable using [`Deferred::with_capacity`]), after
+/// which further telemetry is dropped until the session is released. If the session is shut down before it is
+/// released then the held telemetry is discarded without ever reaching the wrapped battery.
+///
+/// ## Example
+/// ```no_run
+/// use tracing_batteries::{Session, Deferred, Sentry};
+///
+/// let session = Session::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+///   .with_battery(Deferred::new(Sentry::new("https://yourdsn@sentry.example.com")));
+///
+/// // ...once the user has consented to telemetry being collected
+/// session.release();
+///
+/// session.shutdown();
+/// ```
+pub struct Deferred<B: BatteryBuilder> {
+    battery: B,
+    capacity: usize,
+}
+
+impl<B: BatteryBuilder> Deferred<B> {
+    /// Wraps the provided battery, holding its telemetry until the session is released.
+    pub fn new(battery: B) -> Self {
+        Self {
+            battery,
+            capacity: 10_000,
+        }
+    }
+
+    /// Configures the maximum number of errors, envelopes and metrics which are held until the session is released.
+    pub fn with_capacity(self, capacity: usize) -> Self {
+        Self { capacity, ..self }
+    }
+}
+
+impl<B: BatteryBuilder + Send + 'static> BatteryBuilder for Deferred<B> {
+    fn setup(self, metadata: &Metadata, enabled: Arc<AtomicBool>) -> Box<dyn Battery> {
+        let battery = self.battery;
+        let metadata = metadata.clone();
+
+        Box::new(DeferredBattery {
+            setup: Mutex::new(Some(Box::new(move || battery.setup(&metadata, enabled)))),
+            inner: OnceLock::new(),
+            session: OnceLock::new(),
+            capacity: self.capacity,
+            releasing: AtomicBool::new(false),
+            state: Mutex::new(DeferredState::Holding(Vec::new())),
+        })
+    }
+}
+
+impl Session {
+    /// Releases the telemetry held by any [`Deferred`] batteries, replaying it into the batteries they wrap and
+    /// forwarding any further telemetry to them directly.
+    pub fn release(&self) {
+        for battery in self.batteries().iter() {
+            battery.release();
+        }
+    }
+}
+
+enum DeferredState {
+    Holding(Vec<Held>),
+    Released,
+    Discarded,
+}
+
+/// An owned copy of the telemetry passed to a [`Battery`], which may be replayed once the session is released.
+pub(crate) enum Held {
+    Error(HeldError, Option<ErrorContext>),
+    Envelope(Envelope),
+    Artifact(crate::Artifact),
+    Metric {
+        name: String,
+        kind: MetricKind,
+        value: f64,
+        attributes: Vec<(&'static str, String)>,
+        count: u64,
+    },
+}
+
+impl Held {
+    pub(crate) fn metric(metric: &Metric) -> Self {
+        Held::Metric {
+            name: metric.name.to_string(),
+            kind: metric.kind,
+            value: metric.value,
+            attributes: metric
+                .attributes
+                .iter()
+                .map(|(key, value)| (*key, value.to_string()))
+                .collect(),
+            count: metric.count,
+        }
+    }
+
+    /// Replays the held telemetry into the provided battery.
+    pub(crate) fn replay(self, battery: &dyn Battery) {
+        match self {
+            Held::Error(error, Some(context)) => battery.record_error_with(&error, &context),
+            Held::Error(error, None) => battery.record_error(&error),
+            Held::Envelope(envelope) => battery.record_envelope(&envelope),
+            Held::Artifact(artifact) => {
+                battery.record_artifact(&artifact);
+            }
+            Held::Metric {
+                name,
+                kind,
+                value,
+                attributes,
+                count,
+            } => {
+                let attributes: Vec<(&'static str, &str)> = attributes
+                    .iter()
+                    .map(|(key, value)| (*key, value.as_str()))
+                    .collect();
+
+                battery.record_metric(&Metric {
+                    name: &name,
+                    kind,
+                    value,
+                    attributes: &attributes,
+                    count,
+                });
+            }
+        }
+    }
+}
+
+/// A copy of a recorded error, preserving its type name and message along with those of its sources.
+///
+/// Its [`Debug`] representation begins with the name of the original error's type, since that is what batteries
+/// such as Sentry use to group errors.
+pub(crate) struct HeldError {
+    type_name: String,
+    message: String,
+    source: Option<Box<HeldError>>,
+}
+
+impl HeldError {
+    pub(crate) fn new(error: &dyn std::error::Error) -> Self {
+        Self {
+            type_name: type_name(error),
+            message: error.to_string(),
+            source: error
+                .source()
+                .map(|source| Box::new(HeldError::new(source))),
+        }
+    }
+
+    /// Rewrites the message of this error, and those of its sources, using the provided function.
+    pub(crate) fn map_messages<F: Fn(&str) -> String>(self, map: &F) -> Self {
+        Self {
+            type_name: self.type_name,
+            message: map(&self.message),
+            source: self.source.map(|source| Box::new(source.map_messages(map))),
+        }
+    }
+}
+
+/// Determines the name of an error's type from the start of its [`Debug`] representation (e.g. `ParseIntError` for
+/// `ParseIntError { kind: InvalidDigit }`), as trait objects don't otherwise expose their type.
+fn type_name(error: &dyn std::error::Error) -> String {
+    let debug = format!("{error:?}");
+    let path = debug
+        .split(|c: char| !(c.is_alphanumeric() || c == '_' || c == ':'))
+        .next()
+        .unwrap_or_default();
+
+    match path.rsplit("::").next() {
+        Some(name) if !name.is_empty() => name.to_string(),
+        _ => "Error".to_string(),
+    }
+}
+
+impl Debug for HeldError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct(&self.type_name)
+            .field("message", &self.message)
+            .finish()
+    }
+}
+
+impl Display for HeldError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for HeldError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.source
+            .as_deref()
+            .map(|source| source as &(dyn std::error::Error + 'static))
+    }
+}
+
+type DeferredSetup = Box<dyn FnOnce() -> Box<dyn Battery> + Send>;
+
+struct DeferredBattery {
+    setup: Mutex<Option<DeferredSetup>>,
+    inner: OnceLock<Box<dyn Battery>>,
+    session: OnceLock<WeakSession>,
+    capacity: usize,
+    releasing: AtomicBool,
+    state: Mutex<DeferredState>,
+}
+
+impl DeferredBattery {
+    /// Holds the telemetry if the session hasn't been released yet, returning the wrapped battery if it should be
+    /// forwarded instead.
+    fn hold<F: FnOnce() -> Held>(&self, held: F) -> Option<&dyn Battery> {
+        match &mut *self.state.lock().unwrap_or_else(PoisonError::into_inner) {
+            DeferredState::Holding(buffer) => {
+                if buffer.len() < self.capacity {
+                    buffer.push(held());
+                }
+                None
+            }
+            DeferredState::Released => self.inner.get().map(|inner| inner.as_ref()),
+            DeferredState::Discarded => None,
+        }
+    }
+}
+
+impl Battery for DeferredBattery {
+    fn record_error(&self, error: &dyn std::error::Error) {
+        if let Some(inner) = self.hold(|| Held::Error(HeldError::new(error), None)) {
+            inner.record_error(error);
+        }
+    }
+
+    fn record_error_with(&self, error: &dyn std::error::Error, context: &ErrorContext) {
+        if let Some(inner) = self.hold(|| Held::Error(HeldError::new(error), Some(context.clone())))
+        {
+            inner.record_error_with(error, context);
+        }
+    }
+
+    fn record_envelope(&self, envelope: &Envelope) {
+        if let Some(inner) = self.hold(|| Held::Envelope(envelope.clone())) {
+            inner.record_envelope(envelope);
+        }
+    }
+
+    fn record_metric(&self, metric: &Metric) {
+        if let Some(inner) = self.hold(|| Held::metric(metric)) {
+            inner.record_metric(metric);
+        }
+    }
+
+    fn accepts_weighted_histograms(&self) -> bool {
+        // Until the battery has been set up, observations are held individually so that they can be replayed to it
+        // regardless of whether it supports weighted measurements.
+        self.inner
+            .get()
+            .is_some_and(|inner| inner.accepts_weighted_histograms())
+    }
+
+    fn record_artifact(&self, artifact: &crate::Artifact) -> bool {
+        self.hold(|| Held::Artifact(artifact.clone()))
+            .is_some_and(|inner| inner.record_artifact(artifact))
+    }
+
+    fn release(&self) {
+        if self.releasing.swap(true, Ordering::SeqCst) {
+            return;
+        }
+
+        if matches!(
+            *self.state.lock().unwrap_or_else(PoisonError::into_inner),
+            DeferredState::Discarded
+        ) {
+            return;
+        }
+
+        let Some(setup) = self
+            .setup
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take()
+        else {
+            return;
+        };
+
+        let inner = self.inner.get_or_init(setup);
+        if let Some(session) = self.session.get() {
+            inner.attached(session.clone());
+        }
+
+        // Telemetry is replayed without holding the lock, since the wrapped battery may report telemetry through
+        // the session (and so back into this battery) while it is being replayed. Anything recorded in the
+        // meantime is held and replayed afterwards, preserving the order in which it was recorded.
+        loop {
+            let held = {
+                let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+                match &mut *state {
+                    DeferredState::Holding(buffer) if !buffer.is_empty() => std::mem::take(buffer),
+                    DeferredState::Holding(_) => {
+                        *state = DeferredState::Released;
+                        break;
+                    }
+                    DeferredState::Released | DeferredState::Discarded => return,
+                }
+            };
+
+            for held in held {
+                held.replay(inner.as_ref());
+            }
+        }
+
+        inner.release();
+    }
+
+    fn shutdown(&self) {
+        {
+            let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+            if !matches!(*state, DeferredState::Released) {
+                *state = DeferredState::Discarded;
+            }
+        }
+
+        // A battery which was never released was never set up, so there is nothing to shut down.
+        if let Some(inner) = self.inner.get() {
+            inner.shutdown();
+        }
+    }
+
+    fn attached(&self, session: WeakSession) {
+        self.session.set(session).ok();
+    }
+
+    fn validate(&self) -> Vec<crate::ValidationCheck> {
+        self.inner
+            .get()
+            .map(|inner| inner.validate())
+            .unwrap_or_default()
+    }
+
+    #[cfg(feature = "version-check")]
+    fn update_available(&self) -> Option<crate::AvailableUpdate> {
+        self.inner.get()?.update_available()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicUsize;
+
+    use super::*;
+    use crate::test_support::EnvelopeBattery;
+
+    #[test]
+    fn replays_in_order() {
+        let battery = EnvelopeBattery::default();
+        let session = Session::new("example", "0.0.1").with_battery(Deferred::new(battery.clone()));
+
+        session.record_new_page("/first");
+        session.record_new_page("/second");
+        assert!(battery.pages().is_empty());
+
+        session.release();
+        session.record_new_page("/third");
+        assert_eq!(battery.pages(), ["/first", "/second", "/third"]);
+    }
+
+    #[test]
+    fn sets_up_on_release() {
+        let setups = Arc::new(AtomicUsize::new(0));
+        let session = Session::new("example", "0.0.1").with_battery(Deferred::new(CountingSetup {
+            setups: setups.clone(),
+        }));
+        assert_eq!(setups.load(Ordering::Relaxed), 0);
+
+        session.release();
+        session.release();
+        assert_eq!(setups.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn preserves_error_types() {
+        let error = "not a number".parse::<u32>().unwrap_err();
+        let held = HeldError::new(&error);
+
+        assert_eq!(held.to_string(), error.to_string());
+        assert!(format!("{held:?}").starts_with("ParseIntError {"));
+        assert!(format!("{:?}", held.map_messages(&|m| m.to_uppercase()))
+            .starts_with("ParseIntError {"));
+    }
+
+    struct CountingSetup {
+        setups: Arc<AtomicUsize>,
+    }
+
+    impl BatteryBuilder for CountingSetup {
+        fn setup(self, _metadata: &Metadata, _enabled: Arc<AtomicBool>) -> Box<dyn Battery> {
+            self.setups.fetch_add(1, Ordering::Relaxed);
+            Box::new(EnvelopeBattery::default())
+        }
+    }
+
+    #[test]
+    fn discards_unreleased_telemetry() {
+        let battery = EnvelopeBattery::default();
+        let session = Session::new("example", "0.0.1")
+            .with_battery(Deferred::new(battery.clone()).with_capacity(1));
+
+        session.record_new_page("/first");
+        session.record_new_page("/dropped");
+        session.clone().shutdown();
+        session.release();
+
+        assert!(battery.envelopes().is_empty());
+    }
+}
@@ -0,0 +1,69 @@
+use std::{
+    hash::{BuildHasher, Hasher},
+    path::PathBuf,
+};
+
+/// Returns the path within the user's data directory which a battery's device ID is persisted to.
+pub(crate) fn default_device_id_path(service: &str, file_name: &str) -> Option<PathBuf> {
+    let data_dir = std::env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("APPDATA").map(PathBuf::from))
+        .or_else(|| {
+            std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share"))
+        })?;
+
+    Some(data_dir.join(service).join(file_name))
+}
+
+/// Loads the persisted device ID, generating (and persisting) a new one if it does not exist yet.
+pub(crate) fn load_device_id(path: Option<PathBuf>) -> String {
+    if let Some(device_id) = path
+        .as_ref()
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .map(|device_id| device_id.trim().to_string())
+        .filter(|device_id| !device_id.is_empty())
+    {
+        return device_id;
+    }
+
+    let state = std::collections::hash_map::RandomState::new();
+    let device_id = (0..2)
+        .map(|i| {
+            let mut hasher = state.build_hasher();
+            hasher.write_u32(std::process::id());
+            hasher.write_u8(i);
+            format!("{:016x}", hasher.finish())
+        })
+        .collect::<String>();
+
+    if let Some(path) = path {
+        path.parent()
+            .map(std::fs::create_dir_all)
+            .transpose()
+            .and_then(|_| std::fs::write(&path, &device_id))
+            .ok();
+    }
+
+    device_id
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn persists_device_id() {
+        let path = std::env::temp_dir()
+            .join(format!(
+                "tracing-batteries-device-id-{}",
+                std::process::id()
+            ))
+            .join("device-id");
+
+        let device_id = load_device_id(Some(path.clone()));
+        assert_eq!(device_id.len(), 32);
+        assert_eq!(load_device_id(Some(path.clone())), device_id);
+
+        std::fs::remove_dir_all(path.parent().unwrap()).ok();
+    }
+}
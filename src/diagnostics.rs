@@ -0,0 +1,347 @@
+//! Diagnostics which help the users of your application understand (and fix) how its telemetry is configured.
+//!
+//! The [`run_doctor`] function gathers everything this library knows about a [`Session`] into a
+//! [`DoctorReport`], whose [`Display`] implementation is intended to be printed by CLI applications (for example,
+//! behind a `--telemetry-doctor` flag) when a user is trying to work out why their telemetry isn't arriving.
+//!
+//! ## Example
+//! ```no_run
+//! use tracing_batteries::{Session, Sentry, diagnostics};
+//!
+//! let session = Session::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+//!   .with_battery(Sentry::new("https://yourdsn@sentry.example.com/app-id"));
+//!
+//! if std::env::args().any(|arg| arg == "--telemetry-doctor") {
+//!   print!("{}", diagnostics::run_doctor(&session));
+//! }
+//!
+//! session.shutdown();
+//! ```
+
+use std::{
+    collections::BTreeMap,
+    fmt::Display,
+    sync::{atomic::Ordering, Mutex, PoisonError},
+};
+
+use chrono::{DateTime, Utc};
+
+use crate::{Session, ValidationReport};
+
+/// The environment variables which this library (and the SDKs it configures) read, along with whether their
+/// values are secret and should be hidden from the report.
+const ENVIRONMENT_VARIABLES: &[(&str, bool)] = &[
+    ("LOG_LEVEL", false),
+    ("OTEL_SDK_DISABLED", false),
+    ("OTEL_EXPORTER_OTLP_ENDPOINT", false),
+    ("OTEL_EXPORTER_OTLP_PROTOCOL", false),
+    ("OTEL_EXPORTER_OTLP_HEADERS", true),
+    ("TRACING_BATTERIES_OTLP_ENDPOINTS", false),
+    ("OTEL_TRACES_SAMPLER", false),
+    ("OTEL_TRACES_SAMPLER_ARG", false),
+    ("OTEL_EXPORTER_JAEGER_AGENT_HOST", false),
+    ("OTEL_EXPORTER_JAEGER_AGENT_PORT", false),
+    ("TRACEPARENT", false),
+    ("TRACESTATE", false),
+    ("SENTRY_DSN", true),
+    ("SENTRY_ENVIRONMENT", false),
+    ("CORALOGIX_PRIVATE_KEY", true),
+    ("INSTANA_AGENT_HOST", false),
+    ("INSTANA_AGENT_KEY", true),
+    ("INSTANA_ENDPOINT_URL", false),
+    ("NEW_RELIC_LICENSE_KEY", true),
+    ("SIGNOZ_INGESTION_KEY", true),
+    ("UPTRACE_DSN", true),
+    ("AWS_REGION", false),
+    ("AWS_DEFAULT_REGION", false),
+    ("AWS_PROFILE", false),
+];
+
+/// The optional features of this library, along with whether they were enabled when it was compiled.
+const FEATURES: &[(&str, bool)] = &[
+    ("actix-web", cfg!(feature = "actix-web")),
+    ("apprise", cfg!(feature = "apprise")),
+    ("betterstack", cfg!(feature = "betterstack")),
+    ("clap", cfg!(feature = "clap")),
+    ("cloudwatch", cfg!(feature = "cloudwatch")),
+    ("color-eyre", cfg!(feature = "color-eyre")),
+    ("connectivity", cfg!(feature = "connectivity")),
+    ("coralogix", cfg!(feature = "coralogix")),
+    ("countly", cfg!(feature = "countly")),
+    ("desktop-notify", cfg!(feature = "desktop-notify")),
+    ("discord", cfg!(feature = "discord")),
+    ("dynatrace", cfg!(feature = "dynatrace")),
+    ("encryption", cfg!(feature = "encryption")),
+    ("ga4", cfg!(feature = "ga4")),
+    ("github", cfg!(feature = "github")),
+    ("goatcounter", cfg!(feature = "goatcounter")),
+    ("grafana-cloud", cfg!(feature = "grafana-cloud")),
+    ("graphite", cfg!(feature = "graphite")),
+    ("graphql", cfg!(feature = "graphql")),
+    ("host-metrics", cfg!(feature = "host-metrics")),
+    ("influxdb", cfg!(feature = "influxdb")),
+    ("instana", cfg!(feature = "instana")),
+    ("jaeger", cfg!(feature = "jaeger")),
+    ("launchdarkly", cfg!(feature = "launchdarkly")),
+    ("logstash", cfg!(feature = "logstash")),
+    ("matrix", cfg!(feature = "matrix")),
+    ("newrelic", cfg!(feature = "newrelic")),
+    ("ntfy", cfg!(feature = "ntfy")),
+    ("openobserve", cfg!(feature = "openobserve")),
+    ("opensearch", cfg!(feature = "opensearch")),
+    ("opentelemetry", cfg!(feature = "opentelemetry")),
+    ("papertrail", cfg!(feature = "papertrail")),
+    ("pirsch", cfg!(feature = "pirsch")),
+    ("postgres", cfg!(feature = "postgres")),
+    ("redis", cfg!(feature = "redis")),
+    ("s3", cfg!(feature = "s3")),
+    ("sentry", cfg!(feature = "sentry")),
+    ("signoz", cfg!(feature = "signoz")),
+    ("sigv4", cfg!(feature = "sigv4")),
+    ("socket", cfg!(feature = "socket")),
+    ("sumologic", cfg!(feature = "sumologic")),
+    ("teams", cfg!(feature = "teams")),
+    ("telegram", cfg!(feature = "telegram")),
+    ("tokio", cfg!(feature = "tokio")),
+    ("unleash", cfg!(feature = "unleash")),
+    ("uptrace", cfg!(feature = "uptrace")),
+    ("version-check", cfg!(feature = "version-check")),
+    ("watchdog", cfg!(feature = "watchdog")),
+    ("xray", cfg!(feature = "xray")),
+];
+
+/// The most recent error encountered by each exporter, keyed by the exporter's name.
+static EXPORT_ERRORS: Mutex<BTreeMap<String, ExportError>> = Mutex::new(BTreeMap::new());
+
+/// The number of items which each exporter has dropped because its delivery queue was full.
+static DROPPED: Mutex<BTreeMap<String, u64>> = Mutex::new(BTreeMap::new());
+
+/// The most recent error which an exporter encountered while delivering telemetry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExportError {
+    /// The name of the exporter which encountered the error.
+    pub exporter: String,
+    /// When the error occurred.
+    pub timestamp: DateTime<Utc>,
+    /// A description of the error.
+    pub message: String,
+}
+
+/// Records that an exporter failed to deliver telemetry, replacing any previous error it reported.
+// Only the integrations which deliver telemetry in the background report export errors.
+#[allow(dead_code)]
+pub(crate) fn record_export_error<E: Display>(exporter: &str, error: E) {
+    EXPORT_ERRORS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .insert(
+            exporter.to_string(),
+            ExportError {
+                exporter: exporter.to_string(),
+                timestamp: Utc::now(),
+                message: error.to_string(),
+            },
+        );
+}
+
+/// Records that an exporter dropped an item because its delivery queue was full.
+// Only the integrations which queue telemetry for delivery drop it.
+#[allow(dead_code)]
+pub(crate) fn record_dropped(exporter: &str) {
+    *DROPPED
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .entry(exporter.to_string())
+        .or_default() += 1;
+}
+
+/// A description of how a [`Session`]'s telemetry is configured, produced by [`run_doctor`].
+#[derive(Debug, Clone)]
+pub struct DoctorReport {
+    /// The name of the service which the session reports telemetry for.
+    pub service: String,
+    /// The version of the service which the session reports telemetry for.
+    pub version: String,
+    /// Whether the session is currently reporting telemetry.
+    pub enabled: bool,
+    /// The context which is attached to the session's telemetry.
+    pub context: BTreeMap<String, String>,
+    /// The optional features of this library which were enabled when it was compiled.
+    pub features: Vec<&'static str>,
+    /// The telemetry related environment variables which are set, with secret values hidden.
+    pub environment: Vec<(&'static str, String)>,
+    /// The headers used by the installed trace context propagators.
+    pub propagators: Vec<String>,
+    /// The results of validating each of the session's batteries, using [`Session::validate`].
+    pub validation: ValidationReport,
+    /// The most recent error encountered by each exporter which has failed to deliver telemetry.
+    pub export_errors: Vec<ExportError>,
+    /// The number of items which each exporter has dropped because its delivery queue was full.
+    pub dropped: BTreeMap<String, u64>,
+}
+
+impl Display for DoctorReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Telemetry for {} v{}", self.service, self.version)?;
+        writeln!(
+            f,
+            "  status: {}",
+            if self.enabled { "enabled" } else { "disabled" }
+        )?;
+        for (key, value) in self.context.iter() {
+            writeln!(f, "  {key}: {value}")?;
+        }
+
+        writeln!(f, "\nFeatures")?;
+        writeln!(f, "  {}", list_or_none(&self.features))?;
+
+        writeln!(f, "\nEnvironment")?;
+        if self.environment.is_empty() {
+            writeln!(f, "  (none)")?;
+        }
+        for (key, value) in self.environment.iter() {
+            writeln!(f, "  {key}={value}")?;
+        }
+
+        writeln!(f, "\nPropagated headers")?;
+        writeln!(f, "  {}", list_or_none(&self.propagators))?;
+
+        writeln!(f, "\nChecks")?;
+        for line in self.validation.to_string().lines() {
+            writeln!(f, "  {line}")?;
+        }
+
+        writeln!(f, "\nExport errors")?;
+        if self.export_errors.is_empty() {
+            writeln!(f, "  (none)")?;
+        }
+        for error in self.export_errors.iter() {
+            writeln!(
+                f,
+                "  {} at {}: {}",
+                error.exporter,
+                error
+                    .timestamp
+                    .to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+                error.message
+            )?;
+        }
+
+        writeln!(f, "\nDropped telemetry")?;
+        if self.dropped.is_empty() {
+            writeln!(f, "  (none)")?;
+        }
+        for (exporter, count) in self.dropped.iter() {
+            writeln!(f, "  {exporter}: {count}")?;
+        }
+
+        Ok(())
+    }
+}
+
+fn list_or_none<T: AsRef<str>>(items: &[T]) -> String {
+    if items.is_empty() {
+        "(none)".to_string()
+    } else {
+        items
+            .iter()
+            .map(AsRef::as_ref)
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// Gathers a [`DoctorReport`] describing how the provided session's telemetry is configured, including the
+/// results of [`Session::validate`] and any errors which its exporters have encountered.
+///
+/// This may take several seconds to complete if one of the session's endpoints is unreachable.
+pub fn run_doctor(session: &Session) -> DoctorReport {
+    DoctorReport {
+        service: session.metadata.service.to_string(),
+        version: session.metadata.version.to_string(),
+        enabled: session.enabled.load(Ordering::Relaxed),
+        context: session
+            .metadata
+            .context
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect(),
+        features: FEATURES
+            .iter()
+            .filter(|(_, enabled)| *enabled)
+            .map(|(feature, _)| *feature)
+            .collect(),
+        environment: ENVIRONMENT_VARIABLES
+            .iter()
+            .filter_map(|(key, secret)| {
+                let value = std::env::var(key).ok()?;
+                Some((*key, if *secret { "(hidden)".into() } else { value }))
+            })
+            .collect(),
+        propagators: propagated_headers(),
+        validation: session.validate(),
+        export_errors: EXPORT_ERRORS
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .values()
+            .cloned()
+            .collect(),
+        dropped: DROPPED
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone(),
+    }
+}
+
+#[cfg(feature = "opentelemetry")]
+fn propagated_headers() -> Vec<String> {
+    opentelemetry::global::get_text_map_propagator(|propagator| {
+        propagator.fields().map(str::to_string).collect()
+    })
+}
+
+#[cfg(not(feature = "opentelemetry"))]
+fn propagated_headers() -> Vec<String> {
+    Vec::new()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn doctor_report() {
+        let session = Session::new("example", "0.0.1")
+            .with_context("environment", "test")
+            .with_battery(NoopBattery);
+        record_export_error("example-exporter", "connection refused");
+        record_dropped("example-exporter");
+
+        let report = run_doctor(&session);
+        assert_eq!(report.service, "example");
+        assert_eq!(report.context["environment"], "test");
+        assert!(report
+            .export_errors
+            .iter()
+            .any(|error| error.exporter == "example-exporter"));
+
+        let output = report.to_string();
+        assert!(output.starts_with("Telemetry for example v0.0.1\n"));
+        assert!(output.contains("example-exporter at "));
+        assert!(report.dropped["example-exporter"] >= 1);
+    }
+
+    struct NoopBattery;
+
+    impl crate::BatteryBuilder for NoopBattery {
+        fn setup(
+            self,
+            _metadata: &crate::Metadata,
+            _enabled: std::sync::Arc<std::sync::atomic::AtomicBool>,
+        ) -> Box<dyn crate::Battery> {
+            Box::new(NoopBattery)
+        }
+    }
+
+    impl crate::Battery for NoopBattery {}
+}
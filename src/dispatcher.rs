@@ -0,0 +1,99 @@
+use std::{
+    sync::{Arc, Mutex, PoisonError},
+    thread::JoinHandle,
+};
+
+use crate::{
+    queue::{Pop, PriorityQueue, SHUTDOWN_TIMEOUT},
+    DropPolicy, Importance,
+};
+
+type HttpJob =
+    Box<dyn FnOnce(&reqwest::blocking::Client) -> Option<reqwest::blocking::RequestBuilder> + Send>;
+
+/// A background worker which delivers HTTP requests on behalf of batteries which ship telemetry to
+/// HTTP ingestion endpoints.
+///
+/// Requests are queued and sent by a dedicated thread, most important first, ensuring that recording
+/// telemetry never blocks the application. Calling [`HttpDispatcher::shutdown`] waits (for up to 5 seconds)
+/// for any outstanding requests to be delivered before returning.
+pub(crate) struct HttpDispatcher {
+    queue: Arc<PriorityQueue<HttpJob>>,
+    thread: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl HttpDispatcher {
+    pub fn new(name: &str) -> Self {
+        let queue = Arc::new(PriorityQueue::<HttpJob>::new(name));
+
+        let jobs = queue.clone();
+        let exporter = name.to_string();
+        let thread = std::thread::Builder::new()
+            .name(format!("tracing-batteries-{name}"))
+            .spawn(move || {
+                let client = reqwest::blocking::Client::new();
+                while let Pop::Item(job) = jobs.pop(None) {
+                    let Some(request) = job(&client) else {
+                        continue;
+                    };
+
+                    if let Err(err) = request
+                        .send()
+                        .and_then(|response| response.error_for_status())
+                    {
+                        // Request URLs may hold credentials (such as webhook secrets and bot tokens), so they're
+                        // removed before the error is recorded in the diagnostics report.
+                        crate::diagnostics::record_export_error(&exporter, err.without_url());
+                    }
+                }
+            })
+            .ok();
+
+        Self {
+            queue,
+            thread: Mutex::new(thread),
+        }
+    }
+
+    /// Configures the maximum number of requests which may be queued, and which are dropped once it is reached.
+    pub fn with_limits(self, capacity: usize, policy: DropPolicy) -> Self {
+        self.queue.set_limits(capacity, policy);
+        self
+    }
+
+    /// Queues a request for delivery, built using the dispatcher's HTTP client.
+    pub fn dispatch<F>(&self, importance: Importance, request: F)
+    where
+        F: FnOnce(&reqwest::blocking::Client) -> reqwest::blocking::RequestBuilder + Send + 'static,
+    {
+        self.queue
+            .push(importance, Box::new(move |client| Some(request(client))));
+    }
+
+    /// Queues a request for delivery, which is skipped if building it fails (for example, because the credentials
+    /// needed to authenticate it could not be obtained).
+    #[cfg_attr(not(feature = "pirsch"), allow(dead_code))]
+    pub fn try_dispatch<F>(&self, importance: Importance, request: F)
+    where
+        F: FnOnce(&reqwest::blocking::Client) -> Option<reqwest::blocking::RequestBuilder>
+            + Send
+            + 'static,
+    {
+        self.queue.push(importance, Box::new(request));
+    }
+
+    /// Stops accepting new requests and waits for the outstanding requests to be delivered, dropping any
+    /// which haven't been sent once the shutdown timeout elapses.
+    pub fn shutdown(&self) {
+        self.queue.close(SHUTDOWN_TIMEOUT);
+
+        if let Some(thread) = self
+            .thread
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take()
+        {
+            crate::worker::join(thread);
+        }
+    }
+}
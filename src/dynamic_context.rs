@@ -0,0 +1,175 @@
+use std::{
+    cell::RefCell,
+    future::Future,
+    marker::PhantomData,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use crate::Str;
+
+thread_local! {
+    static CURRENT: RefCell<Vec<(&'static str, Str)>> = const { RefCell::new(Vec::new()) };
+}
+
+/// A set of key-value pairs (such as a request or user ID) which describe the work currently being performed,
+/// and which are attached to all of the telemetry recorded while they are in scope.
+///
+/// While a dynamic context has been entered, its values are attached to the errors (as fields) and envelopes (as
+/// context) recorded by the [`Session`](crate::Session), as well as to the spans and events exported by the
+/// [`OpenTelemetry`](crate::OpenTelemetry) battery. Contexts may be nested, with inner values replacing outer
+/// values which use the same key, while values which were provided explicitly when recording telemetry take
+/// precedence over both.
+///
+/// Contexts are scoped to the current thread, so use [`DynamicContext::scope`] to attach one to a future, ensuring
+/// that it is entered whenever the future is polled (regardless of which thread the executor uses to do so).
+///
+/// ## Example
+/// ```no_run
+/// use tracing_batteries::{DynamicContext, Session, Sentry};
+///
+/// let session = Session::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+///   .with_battery(Sentry::new("https://yourdsn@sentry.example.com"));
+///
+/// let _request = DynamicContext::new()
+///   .with("request.id", "c0ffee")
+///   .with("user.id", "alice")
+///   .enter();
+///
+/// session.record_event("export_pdf", [("pages", 3)]);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DynamicContext {
+    fields: Vec<(&'static str, Str)>,
+}
+
+impl DynamicContext {
+    /// Creates an empty dynamic context.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns a copy of the dynamic context which is currently in scope, allowing it to be carried over to
+    /// work which is spawned onto another thread or task.
+    pub fn current() -> Self {
+        Self { fields: current() }
+    }
+
+    /// Adds a value to the context, replacing any existing value with the same key.
+    pub fn with<V: Into<Str>>(mut self, key: &'static str, value: V) -> Self {
+        self.fields.retain(|(existing, _)| *existing != key);
+        self.fields.push((key, value.into()));
+        self
+    }
+
+    /// Retrieves the value associated with the provided key, if there is one.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.fields
+            .iter()
+            .find(|(existing, _)| *existing == key)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Enters the context on the current thread until the returned guard is dropped.
+    pub fn enter(&self) -> DynamicContextGuard {
+        CURRENT.with(|current| {
+            let mut current = current.borrow_mut();
+            let depth = current.len();
+            current.extend(self.fields.iter().cloned());
+
+            DynamicContextGuard {
+                depth,
+                _not_send: PhantomData,
+            }
+        })
+    }
+
+    /// Attaches the context to the provided future, entering it each time the future is polled.
+    pub fn scope<F: Future>(self, future: F) -> WithDynamicContext<F> {
+        WithDynamicContext {
+            context: self,
+            future: Box::pin(future),
+        }
+    }
+}
+
+/// A guard which exits a [`DynamicContext`] when it is dropped, created by [`DynamicContext::enter`].
+#[must_use = "the dynamic context is exited as soon as the guard is dropped"]
+pub struct DynamicContextGuard {
+    depth: usize,
+    _not_send: PhantomData<*const ()>,
+}
+
+impl Drop for DynamicContextGuard {
+    fn drop(&mut self) {
+        CURRENT.with(|current| current.borrow_mut().truncate(self.depth));
+    }
+}
+
+/// A future which enters a [`DynamicContext`] each time it is polled, created by [`DynamicContext::scope`].
+pub struct WithDynamicContext<F> {
+    context: DynamicContext,
+    future: Pin<Box<F>>,
+}
+
+impl<F: Future> Future for WithDynamicContext<F> {
+    type Output = F::Output;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let _guard = self.context.enter();
+        self.future.as_mut().poll(cx)
+    }
+}
+
+/// Returns the values of the dynamic context which is in scope on the current thread, with the innermost
+/// value for each key taking precedence.
+pub(crate) fn current() -> Vec<(&'static str, Str)> {
+    CURRENT.with(|current| {
+        let current = current.borrow();
+        let mut fields: Vec<(&'static str, Str)> = Vec::new();
+        for (key, value) in current.iter().rev() {
+            if !fields.iter().any(|(existing, _)| existing == key) {
+                fields.push((key, value.clone()));
+            }
+        }
+
+        fields
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{test_support::EnvelopeBattery, EnvelopePayload, Session};
+
+    #[test]
+    fn attaches_dynamic_context() {
+        let battery = EnvelopeBattery::default();
+        let session = Session::new("example", "0.0.1").with_battery(battery.clone());
+
+        {
+            let _request = DynamicContext::new()
+                .with("request.id", "c0ffee")
+                .with("user.id", "alice")
+                .enter();
+            let _retry = DynamicContext::new().with("user.id", "bob").enter();
+
+            assert_eq!(DynamicContext::current().get("user.id"), Some("bob"));
+            session.record_new_page("/settings");
+            session.record_error_with(&std::io::Error::other("disk full"), [("user.id", "carol")]);
+        }
+
+        session.record_new_page("/profile");
+
+        let envelopes = battery.envelopes();
+        assert_eq!(envelopes[0].context.get("request.id").unwrap(), "c0ffee");
+        assert_eq!(envelopes[0].context.get("user.id").unwrap(), "bob");
+        assert!(matches!(
+            &envelopes[1].payload,
+            EnvelopePayload::Error { fields, .. }
+                if fields.get("user.id").map(String::as_str) == Some("carol")
+                    && fields.get("request.id").map(String::as_str) == Some("c0ffee")
+        ));
+        assert!(envelopes[2].context.get("request.id").is_none());
+    }
+}
@@ -0,0 +1,292 @@
+use std::{
+    borrow::Cow,
+    collections::BTreeMap,
+    sync::{atomic::AtomicBool, Arc},
+};
+
+use base64::Engine;
+use crypto_box::{aead::OsRng, PublicKey, SecretKey};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    Battery, BatteryBuilder, Envelope, EnvelopePayload, ErrorContext, Metadata, Metric, Str,
+    WeakSession,
+};
+
+/// The algorithm used to seal envelope payloads, as recorded in [`EnvelopePayload::Sealed`].
+const SEALED_BOX_ALGORITHM: &str = "x25519-xsalsa20poly1305-sealedbox";
+
+/// A combinator which wraps another battery, sealing the payload of every [`Envelope`] with the provided
+/// public key before the wrapped battery receives it.
+///
+/// <div class="warning">
+///
+/// This combinator requires the `encryption` feature to be enabled.
+///
+/// </div>
+///
+/// This is intended for transports which carry your telemetry through infrastructure you don't control, such as
+/// shared message brokers, where only the collector holding the matching secret key should be able to read it.
+/// Payloads are sealed using a [libsodium sealed box](https://doc.libsodium.org/public-key_cryptography/sealed_boxes)
+/// (X25519 and XSalsa20-Poly1305), so they may be opened by any libsodium binding, or by [`Envelope::unseal`].
+///
+/// The envelope's service, version, timestamp and importance remain readable so that brokers are still able to
+/// route it, while its payload is replaced with an [`EnvelopePayload::Sealed`] payload containing the
+/// base64-encoded ciphertext of the original payload's JSON representation. The envelope's context (which may
+/// include your command line, tenant and dynamic context) is sealed alongside the payload, unless you opt into
+/// leaving specific fields readable for routing using [`Encrypted::with_routing_keys`]. Errors recorded through
+/// [`Battery::record_error_with`] and metrics are forwarded to the wrapped battery unchanged.
+///
+/// ## Example
+/// ```no_run
+/// use tracing_batteries::{Session, Encrypted, SocketEmitter};
+///
+/// let public_key = [0u8; 32]; // The collector's X25519 public key
+///
+/// let session = Session::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+///   .with_battery(Encrypted::new(SocketEmitter::tcp("127.0.0.1:7070"), public_key));
+///
+/// session.shutdown();
+/// ```
+pub struct Encrypted<B: BatteryBuilder> {
+    battery: B,
+    public_key: PublicKey,
+    routing_keys: Vec<Cow<'static, str>>,
+}
+
+impl<B: BatteryBuilder> Encrypted<B> {
+    /// Wraps the provided battery, sealing each envelope's payload and context using the provided X25519 public key.
+    pub fn new(battery: B, public_key: [u8; 32]) -> Self {
+        Self {
+            battery,
+            public_key: PublicKey::from(public_key),
+            routing_keys: Vec::new(),
+        }
+    }
+
+    /// Configures context fields which are left readable on sealed envelopes, allowing brokers to route them (for
+    /// example by tenant), while the rest of the context is sealed with the payload.
+    ///
+    /// ## Example
+    /// ```no_run
+    /// use tracing_batteries::{Encrypted, SocketEmitter};
+    ///
+    /// Encrypted::new(SocketEmitter::tcp("127.0.0.1:7070"), [0u8; 32])
+    ///   .with_routing_keys(["tenant.id"]);
+    /// ```
+    pub fn with_routing_keys<I, K>(self, keys: I) -> Self
+    where
+        I: IntoIterator<Item = K>,
+        K: Into<Cow<'static, str>>,
+    {
+        Self {
+            routing_keys: keys.into_iter().map(Into::into).collect(),
+            ..self
+        }
+    }
+}
+
+impl<B: BatteryBuilder> BatteryBuilder for Encrypted<B> {
+    fn setup(self, metadata: &Metadata, enabled: Arc<AtomicBool>) -> Box<dyn Battery> {
+        Box::new(EncryptedBattery {
+            inner: self.battery.setup(metadata, enabled),
+            public_key: self.public_key,
+            routing_keys: self.routing_keys,
+        })
+    }
+}
+
+impl Envelope {
+    /// Opens an envelope whose payload was sealed by the [`Encrypted`] combinator, using the secret key which
+    /// corresponds to the public key it was sealed with.
+    ///
+    /// Returns `None` if the envelope isn't sealed, or if its payload cannot be opened with the provided key.
+    pub fn unseal(&self, secret_key: [u8; 32]) -> Option<Envelope> {
+        let EnvelopePayload::Sealed {
+            algorithm,
+            ciphertext,
+        } = &self.payload
+        else {
+            return None;
+        };
+
+        if algorithm != SEALED_BOX_ALGORITHM {
+            return None;
+        }
+
+        let ciphertext = base64::engine::general_purpose::STANDARD
+            .decode(ciphertext)
+            .ok()?;
+        let plaintext = SecretKey::from(secret_key).unseal(&ciphertext).ok()?;
+        let contents: SealedContents = serde_json::from_slice(&plaintext).ok()?;
+
+        let mut context = self.context.clone();
+        context.extend(contents.context);
+
+        Some(Envelope {
+            context,
+            payload: contents.payload,
+            ..self.clone()
+        })
+    }
+}
+
+/// The plaintext which is sealed by the [`Encrypted`] combinator: the envelope's payload, along with any context
+/// which isn't left readable for routing.
+#[derive(Serialize, Deserialize)]
+struct SealedContents {
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    context: BTreeMap<Str, Str>,
+
+    #[serde(flatten)]
+    payload: EnvelopePayload,
+}
+
+struct EncryptedBattery {
+    inner: Box<dyn Battery>,
+    public_key: PublicKey,
+    routing_keys: Vec<Cow<'static, str>>,
+}
+
+impl EncryptedBattery {
+    fn seal(&self, envelope: &Envelope) -> Option<Envelope> {
+        let (routing, context): (BTreeMap<Str, Str>, BTreeMap<Str, Str>) = envelope
+            .context
+            .iter()
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .partition(|(key, _)| {
+                self.routing_keys
+                    .iter()
+                    .any(|routing| routing == key.as_str())
+            });
+
+        let plaintext = serde_json::to_vec(&SealedContents {
+            context,
+            payload: envelope.payload.clone(),
+        })
+        .ok()?;
+        let ciphertext = self.public_key.seal(&mut OsRng, &plaintext).ok()?;
+
+        Some(Envelope {
+            context: routing,
+            payload: EnvelopePayload::Sealed {
+                algorithm: SEALED_BOX_ALGORITHM.to_string(),
+                ciphertext: base64::engine::general_purpose::STANDARD.encode(ciphertext),
+            },
+            ..envelope.clone()
+        })
+    }
+}
+
+impl Battery for EncryptedBattery {
+    fn record_error(&self, error: &dyn std::error::Error) {
+        self.inner.record_error(error);
+    }
+
+    fn record_error_with(&self, error: &dyn std::error::Error, context: &ErrorContext) {
+        self.inner.record_error_with(error, context);
+    }
+
+    fn record_envelope(&self, envelope: &Envelope) {
+        // Envelopes which cannot be sealed are dropped, rather than risking them being sent in the clear.
+        if let Some(sealed) = self.seal(envelope) {
+            self.inner.record_envelope(&sealed);
+        }
+    }
+
+    fn record_metric(&self, metric: &Metric) {
+        self.inner.record_metric(metric);
+    }
+
+    fn accepts_weighted_histograms(&self) -> bool {
+        self.inner.accepts_weighted_histograms()
+    }
+
+    fn record_artifact(&self, artifact: &crate::Artifact) -> bool {
+        // Artifacts which cannot be sealed are left for the session to save locally, rather than being sent in
+        // the clear.
+        match self.public_key.seal(&mut OsRng, &artifact.data) {
+            Ok(ciphertext) => self.inner.record_artifact(&crate::Artifact {
+                name: format!("{}.sealed", artifact.name).into(),
+                content_type: "application/octet-stream".into(),
+                data: ciphertext.into(),
+            }),
+            Err(_) => false,
+        }
+    }
+
+    fn release(&self) {
+        self.inner.release();
+    }
+
+    fn shutdown(&self) {
+        self.inner.shutdown();
+    }
+
+    fn attached(&self, session: WeakSession) {
+        self.inner.attached(session);
+    }
+
+    fn validate(&self) -> Vec<crate::ValidationCheck> {
+        self.inner.validate()
+    }
+
+    #[cfg(feature = "version-check")]
+    fn update_available(&self) -> Option<crate::AvailableUpdate> {
+        self.inner.update_available()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{test_support::EnvelopeBattery, Session};
+
+    #[test]
+    fn seals_payloads() {
+        let secret_key = SecretKey::generate(&mut OsRng);
+        let battery = EnvelopeBattery::default();
+        let session = Session::new("example", "0.0.1")
+            .with_context("tenant.id", "acme")
+            .with_context("process.command_line", "deploy --token hunter2")
+            .with_battery(
+                Encrypted::new(battery.clone(), *secret_key.public_key().as_bytes())
+                    .with_routing_keys(["tenant.id"]),
+            );
+
+        session.record_new_page("/settings");
+
+        let sealed = battery.envelopes()[0].clone();
+        assert_eq!(sealed.service, "example");
+        assert!(!sealed.to_json().contains("/settings"));
+        assert!(!sealed.to_json().contains("hunter2"));
+        assert_eq!(
+            sealed
+                .context
+                .keys()
+                .map(|key| key.as_str())
+                .collect::<Vec<_>>(),
+            ["tenant.id"]
+        );
+        assert!(sealed.unseal([0; 32]).is_none());
+
+        let unsealed = sealed.unseal(secret_key.to_bytes()).unwrap();
+        assert_eq!(
+            unsealed
+                .context
+                .get("process.command_line")
+                .map(|v| v.as_str()),
+            Some("deploy --token hunter2")
+        );
+        assert_eq!(
+            unsealed.context.get("tenant.id").map(|v| v.as_str()),
+            Some("acme")
+        );
+        assert_eq!(
+            unsealed.payload,
+            EnvelopePayload::PageView {
+                page: "/settings".into()
+            }
+        );
+    }
+}
@@ -0,0 +1,166 @@
+use std::sync::{Mutex, PoisonError};
+
+use opentelemetry::{trace::TraceContextExt, KeyValue};
+use tracing::{span, Event, Subscriber};
+use tracing_opentelemetry::OtelData;
+use tracing_subscriber::{layer::Context, registry::LookupSpan, Layer};
+
+/// A [`Layer`] which stamps the OpenTelemetry representation of spans and events with details about
+/// the thread (and, when running on Tokio, the task) which emitted them, along with the values of the
+/// [`DynamicContext`](crate::DynamicContext) they were recorded within. Spans are also stamped with the flags
+/// reported by any registered [`FlagProvider`](crate::FlagProvider)s.
+///
+/// When a parent trace context has been propagated to this process (see [`crate::Session::inject_trace_context`]),
+/// the first root span is also attached to it, connecting this process' spans to its parent's trace. Later root
+/// spans start traces of their own, so that a long-lived process doesn't add all of its work to the trace which
+/// started it.
+///
+/// This layer must be installed after the [`tracing_opentelemetry::OpenTelemetryLayer`], since it
+/// enriches the span data which that layer has already recorded.
+pub(crate) struct EnrichmentLayer {
+    threads: bool,
+    #[cfg_attr(not(feature = "tokio"), allow(dead_code))]
+    tasks: bool,
+    parent: Mutex<Option<opentelemetry::Context>>,
+}
+
+impl EnrichmentLayer {
+    pub fn new(threads: bool, tasks: bool) -> Self {
+        Self {
+            threads,
+            tasks,
+            parent: Mutex::new(None),
+        }
+    }
+
+    pub fn with_parent(self, parent: Option<opentelemetry::Context>) -> Self {
+        Self {
+            parent: Mutex::new(parent),
+            ..self
+        }
+    }
+
+    /// Takes the propagated parent context, which is only attached to the first root span.
+    fn take_parent(&self) -> Option<opentelemetry::Context> {
+        self.parent
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take()
+    }
+
+    fn attributes(&self) -> Vec<KeyValue> {
+        let mut attributes = Vec::new();
+
+        if self.threads {
+            let thread = std::thread::current();
+            if let Some(name) = thread.name() {
+                attributes.push(KeyValue::new("thread.name", name.to_string()));
+            }
+
+            if let Some(id) = thread_id(thread.id()) {
+                attributes.push(KeyValue::new("thread.id", id));
+            }
+        }
+
+        #[cfg(feature = "tokio")]
+        if self.tasks {
+            if let Some(id) = tokio::task::try_id() {
+                attributes.push(KeyValue::new("tokio.task.id", id.to_string()));
+            }
+        }
+
+        attributes.extend(
+            crate::dynamic_context::current()
+                .into_iter()
+                .map(|(key, value)| KeyValue::new(key, value.to_string())),
+        );
+
+        attributes
+    }
+}
+
+impl<S> Layer<S> for EnrichmentLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, _attrs: &span::Attributes<'_>, id: &span::Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+
+        let mut extensions = span.extensions_mut();
+        if let Some(data) = extensions.get_mut::<OtelData>() {
+            if !data.parent_cx.has_active_span() {
+                if let Some(parent) = self.take_parent() {
+                    data.parent_cx = parent;
+                    data.builder.trace_id = None;
+                }
+            }
+
+            let attributes = data.builder.attributes.get_or_insert_with(Vec::new);
+            attributes.extend(self.attributes());
+            attributes.extend(
+                crate::feature_flags::current()
+                    .into_iter()
+                    .map(|(key, value)| {
+                        KeyValue::new(format!("feature_flag.{key}"), value.to_string())
+                    }),
+            );
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let Some(span) = ctx.event_span(event) else {
+            return;
+        };
+
+        // The OpenTelemetry layer records each event on its parent span before this layer is
+        // invoked, so the most recent event on the span is the one we are currently observing.
+        let mut extensions = span.extensions_mut();
+        if let Some(otel_event) = extensions
+            .get_mut::<OtelData>()
+            .and_then(|data| data.builder.events.as_mut())
+            .and_then(|events| events.last_mut())
+        {
+            otel_event.attributes.extend(self.attributes());
+        }
+    }
+}
+
+fn thread_id(id: std::thread::ThreadId) -> Option<i64> {
+    // ThreadId::as_u64 is unstable, so we extract the numeric ID from its `ThreadId(N)` debug representation.
+    format!("{:?}", id)
+        .trim_start_matches("ThreadId(")
+        .trim_end_matches(')')
+        .parse()
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn thread_attributes() {
+        let attributes = std::thread::Builder::new()
+            .name("enrichment-test".into())
+            .spawn(|| EnrichmentLayer::new(true, false).attributes())
+            .unwrap()
+            .join()
+            .unwrap();
+
+        assert!(attributes
+            .iter()
+            .any(|kv| kv.key.as_str() == "thread.name" && kv.value.as_str() == "enrichment-test"));
+        assert!(attributes.iter().any(|kv| kv.key.as_str() == "thread.id"));
+    }
+
+    #[test]
+    fn parent_is_attached_once() {
+        let layer =
+            EnrichmentLayer::new(false, false).with_parent(Some(opentelemetry::Context::new()));
+
+        assert!(layer.take_parent().is_some());
+        assert!(layer.take_parent().is_none());
+    }
+}
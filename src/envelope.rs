@@ -0,0 +1,324 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{Metadata, Str};
+
+/// The version of the [`Envelope`] schema which is emitted by this version of the library.
+///
+/// This version is incremented whenever a breaking change is made to the structure of the
+/// envelope, allowing downstream consumers to detect (and handle) envelopes which were emitted
+/// by a different version of this library.
+pub const ENVELOPE_SCHEMA_VERSION: u32 = 1;
+
+/// The [JSON Schema](https://json-schema.org) describing the serialized form of an [`Envelope`].
+///
+/// This schema corresponds to [`ENVELOPE_SCHEMA_VERSION`] and may be used by consumers which are
+/// not written in Rust to validate the telemetry emitted by batteries which ship [`Envelope`]s.
+pub const ENVELOPE_JSON_SCHEMA: &str = r##"{
+  "$schema": "https://json-schema.org/draft/2020-12/schema",
+  "$id": "https://github.com/SierraSoftworks/tracing-batteries-rs/schemas/envelope/v1.json",
+  "title": "Envelope",
+  "type": "object",
+  "required": ["schema_version", "timestamp", "service", "version", "kind"],
+  "properties": {
+    "schema_version": { "const": 1 },
+    "timestamp": { "type": "string", "format": "date-time" },
+    "service": { "type": "string" },
+    "version": { "type": "string" },
+    "context": {
+      "type": "object",
+      "additionalProperties": { "type": "string" }
+    },
+    "importance": { "enum": ["critical", "normal", "low"] },
+    "kind": { "enum": ["error", "event", "page_view", "sealed"] }
+  },
+  "oneOf": [
+    {
+      "properties": {
+        "kind": { "const": "error" },
+        "message": { "type": "string" },
+        "causes": { "type": "array", "items": { "type": "string" } },
+        "fields": {
+          "type": "object",
+          "additionalProperties": { "type": "string" }
+        },
+        "backtrace": { "type": "string" }
+      },
+      "required": ["message"]
+    },
+    {
+      "properties": {
+        "kind": { "const": "event" },
+        "name": { "type": "string" },
+        "properties": { "type": "object" }
+      },
+      "required": ["name"]
+    },
+    {
+      "properties": {
+        "kind": { "const": "page_view" },
+        "page": { "type": "string" }
+      },
+      "required": ["page"]
+    },
+    {
+      "properties": {
+        "kind": { "const": "sealed" },
+        "algorithm": { "type": "string" },
+        "ciphertext": { "type": "string", "contentEncoding": "base64" }
+      },
+      "required": ["algorithm", "ciphertext"]
+    }
+  ]
+}"##;
+
+/// A self-describing unit of telemetry which is emitted by the [`Session`](crate::Session).
+///
+/// Envelopes are delivered to each battery through [`Battery::record_envelope`](crate::Battery::record_envelope)
+/// and are intended to be serialized (as JSON) by batteries which ship telemetry to generic transports,
+/// such as webhooks, message queues, or files. The serialized form is described by [`ENVELOPE_JSON_SCHEMA`]
+/// and is versioned using [`ENVELOPE_SCHEMA_VERSION`], allowing downstream consumers to reliably
+/// deserialize telemetry across upgrades of this library.
+///
+/// ## Example
+/// ```rust
+/// use tracing_batteries::{Envelope, EnvelopePayload};
+///
+/// let json = r#"{
+///   "schema_version": 1,
+///   "timestamp": "2024-01-01T00:00:00Z",
+///   "service": "my-service",
+///   "version": "1.0.0",
+///   "kind": "page_view",
+///   "page": "/settings"
+/// }"#;
+///
+/// let envelope = Envelope::from_json(json).unwrap();
+/// assert_eq!(envelope.payload, EnvelopePayload::PageView { page: "/settings".into() });
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Envelope {
+    pub schema_version: u32,
+    pub timestamp: DateTime<Utc>,
+    pub service: Str,
+    pub version: Str,
+
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub context: BTreeMap<Str, Str>,
+
+    #[serde(default)]
+    pub importance: Importance,
+
+    #[serde(flatten)]
+    pub payload: EnvelopePayload,
+}
+
+/// How important it is that an [`Envelope`] is delivered, used by batteries to decide which telemetry to send
+/// first (and which to shed) when they are unable to deliver everything that has been recorded.
+///
+/// Batteries deliver critical telemetry before anything else once they fall behind, or when the session is
+/// shutting down, while low importance telemetry is the first to be dropped when their queues are full. By
+/// default, errors are [`Importance::Critical`], custom events are [`Importance::Normal`] and page views are
+/// [`Importance::Low`], however this may be changed (for example using the [`Mapped`](crate::Mapped) combinator).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Importance {
+    /// Telemetry which should be delivered whenever possible, such as errors and crash reports.
+    Critical,
+    /// Telemetry which should be delivered unless a battery is unable to keep up.
+    #[default]
+    Normal,
+    /// Telemetry which may be dropped to make room for more important telemetry, such as page views.
+    Low,
+}
+
+/// Which telemetry a battery sheds when its delivery queue is full, used alongside each envelope's
+/// [`Importance`] so that less important telemetry is always shed before more important telemetry.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DropPolicy {
+    /// Sheds the oldest queued telemetry to make room for new telemetry, favouring the most recent activity.
+    #[default]
+    DropOldest,
+    /// Drops new telemetry while the queue is full, favouring the telemetry which was recorded first.
+    DropNewest,
+}
+
+impl Importance {
+    /// The default importance of the provided payload.
+    pub fn of(payload: &EnvelopePayload) -> Self {
+        match payload {
+            EnvelopePayload::Error { .. } => Importance::Critical,
+            EnvelopePayload::PageView { .. } => Importance::Low,
+            _ => Importance::Normal,
+        }
+    }
+}
+
+/// The telemetry carried by an [`Envelope`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum EnvelopePayload {
+    /// An error which was reported using [`Session::record_error`](crate::Session::record_error).
+    Error {
+        message: String,
+
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        causes: Vec<String>,
+
+        #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+        fields: BTreeMap<String, String>,
+
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        backtrace: Option<String>,
+    },
+
+    /// A custom event which was reported using [`Session::record_event`](crate::Session::record_event).
+    Event {
+        name: String,
+
+        #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+        properties: BTreeMap<String, serde_json::Value>,
+    },
+
+    /// A page view which was reported using [`Session::record_new_page`](crate::Session::record_new_page).
+    PageView { page: String },
+
+    /// A payload which was sealed by the `Encrypted` combinator, holding the base64-encoded
+    /// ciphertext of the original payload's JSON representation.
+    Sealed {
+        algorithm: String,
+        ciphertext: String,
+    },
+
+    /// A payload emitted by a newer version of this library which this version does not understand.
+    #[serde(other)]
+    Unknown,
+}
+
+/// The service's metadata in the form which is stamped onto each [`Envelope`], built once per session so that
+/// recording an envelope only clones the shared [`Str`]s.
+pub(crate) struct EnvelopeHeader {
+    service: Str,
+    version: Str,
+    context: BTreeMap<Str, Str>,
+}
+
+impl EnvelopeHeader {
+    pub fn new(metadata: &Metadata) -> Self {
+        Self {
+            service: Str::intern(&metadata.service),
+            version: Str::intern(&metadata.version),
+            context: metadata
+                .context
+                .iter()
+                .map(|(key, value)| (Str::intern(key), Str::intern(value)))
+                .collect(),
+        }
+    }
+}
+
+impl Envelope {
+    /// Creates a new envelope for the provided payload, stamped with the current time (corrected for any clock skew
+    /// measured by [`Metadata::with_clock_check`]) and the service's metadata.
+    pub fn new(metadata: &Metadata, payload: EnvelopePayload) -> Self {
+        Self::with_header(metadata, &EnvelopeHeader::new(metadata), payload)
+    }
+
+    /// Creates a new envelope stamped with a pre-built [`EnvelopeHeader`], which avoids interning the service's
+    /// metadata for every envelope a session records.
+    pub(crate) fn with_header(
+        metadata: &Metadata,
+        header: &EnvelopeHeader,
+        payload: EnvelopePayload,
+    ) -> Self {
+        Self {
+            schema_version: ENVELOPE_SCHEMA_VERSION,
+            timestamp: metadata.now(),
+            service: header.service.clone(),
+            version: header.version.clone(),
+            context: header.context.clone(),
+            importance: Importance::of(&payload),
+            payload,
+        }
+    }
+
+    /// Serializes this envelope into its JSON representation.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+
+    /// Returns a short, human readable description of this envelope's payload, suitable for use as a log message.
+    pub fn summary(&self) -> String {
+        match &self.payload {
+            EnvelopePayload::Error { message, .. } => message.clone(),
+            EnvelopePayload::Event { name, .. } => format!("Event: {name}"),
+            EnvelopePayload::PageView { page } => format!("Page view: {page}"),
+            EnvelopePayload::Sealed { .. } => "Sealed telemetry".to_string(),
+            EnvelopePayload::Unknown => "Unknown telemetry".to_string(),
+        }
+    }
+
+    /// Deserializes an envelope from its JSON representation.
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Session;
+
+    #[test]
+    fn round_trip() {
+        let metadata = Session::new("example", "0.0.1").with_context("environment", "test");
+
+        let envelope = Envelope::new(
+            &metadata,
+            EnvelopePayload::Event {
+                name: "export_pdf".into(),
+                properties: [("pages".to_string(), serde_json::json!(3))]
+                    .into_iter()
+                    .collect(),
+            },
+        );
+
+        let json = envelope.to_json();
+        assert!(json.contains(r#""kind":"event""#));
+        assert!(json.contains(r#""importance":"normal""#));
+        assert!(json.contains(r#""schema_version":1"#));
+        assert_eq!(Envelope::from_json(&json).unwrap(), envelope);
+    }
+
+    #[test]
+    fn unknown_kind() {
+        let envelope = Envelope::from_json(
+            r#"{
+                "schema_version": 2,
+                "timestamp": "2024-01-01T00:00:00Z",
+                "service": "example",
+                "version": "0.0.1",
+                "kind": "hologram"
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(envelope.payload, EnvelopePayload::Unknown);
+    }
+
+    #[test]
+    fn summary() {
+        let metadata = Session::new("example", "0.0.1");
+
+        let envelope = Envelope::new(
+            &metadata,
+            EnvelopePayload::PageView {
+                page: "/settings".into(),
+            },
+        );
+
+        assert_eq!(envelope.summary(), "Page view: /settings");
+    }
+}
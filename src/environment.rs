@@ -0,0 +1,194 @@
+use std::collections::BTreeMap;
+
+use serde_json::Value;
+
+use crate::Session;
+
+impl Session {
+    /// Emits a single event describing the environment that the application is running in.
+    ///
+    /// The snapshot includes the operating system and kernel versions, CPU count, total memory,
+    /// locale, and any hints that the process is running inside a container or virtual machine, along
+    /// with the values of the environment variables named in `env_vars`. Only the listed environment
+    /// variables are captured, ensuring that secrets are not accidentally reported.
+    ///
+    /// The snapshot is delivered to batteries as an `environment_snapshot` custom event (see
+    /// [`Session::record_event`]) and is also emitted as a `tracing` event so that it is included in
+    /// your logs. It is usually called once, immediately after the session's batteries are attached.
+    ///
+    /// ## Example
+    /// ```no_run
+    /// use tracing_batteries::{Session, Sentry};
+    ///
+    /// let session = Session::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+    ///   .with_battery(Sentry::new("https://yourdsn@sentry.example.com/app-id"))
+    ///   .with_environment_snapshot(["RUST_LOG", "TZ"]);
+    ///
+    /// session.shutdown();
+    /// ```
+    pub fn with_environment_snapshot<I, K>(self, env_vars: I) -> Self
+    where
+        I: IntoIterator<Item = K>,
+        K: AsRef<str>,
+    {
+        let snapshot = environment_snapshot(env_vars);
+
+        tracing::info!(
+            target: "tracing_batteries::environment",
+            snapshot = %Value::Object(snapshot.clone().into_iter().collect()),
+            "Captured a snapshot of the application's environment."
+        );
+
+        self.record_event("environment_snapshot", snapshot);
+        self
+    }
+}
+
+fn environment_snapshot<I, K>(env_vars: I) -> BTreeMap<String, Value>
+where
+    I: IntoIterator<Item = K>,
+    K: AsRef<str>,
+{
+    let mut snapshot = BTreeMap::new();
+
+    snapshot.insert("os.type".into(), std::env::consts::OS.into());
+    snapshot.insert("os.arch".into(), std::env::consts::ARCH.into());
+
+    if let Some(version) = os_version() {
+        snapshot.insert("os.version".into(), version.into());
+    }
+
+    if let Some(kernel) = read("/proc/sys/kernel/osrelease") {
+        snapshot.insert("os.kernel".into(), kernel.into());
+    }
+
+    if let Ok(cpus) = std::thread::available_parallelism() {
+        snapshot.insert("host.cpu.count".into(), cpus.get().into());
+    }
+
+    if let Some(memory) = total_memory() {
+        snapshot.insert("host.memory.total".into(), memory.into());
+    }
+
+    if let Some(locale) = ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|key| std::env::var(key).ok())
+        .find(|value| !value.is_empty())
+    {
+        snapshot.insert("locale".into(), locale.into());
+    }
+
+    if let Some(container) = container_hint() {
+        snapshot.insert("container.runtime".into(), container.into());
+    }
+
+    if let Some(hypervisor) = hypervisor_hint() {
+        snapshot.insert("host.hypervisor".into(), hypervisor.into());
+    }
+
+    for key in env_vars {
+        let key = key.as_ref();
+        if let Ok(value) = std::env::var(key) {
+            snapshot.insert(format!("env.{key}"), value.into());
+        }
+    }
+
+    snapshot
+}
+
+fn read(path: &str) -> Option<String> {
+    std::fs::read_to_string(path)
+        .ok()
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+fn os_version() -> Option<String> {
+    let release = read("/etc/os-release")?;
+    release
+        .lines()
+        .find_map(|line| line.strip_prefix("PRETTY_NAME="))
+        .map(|name| name.trim_matches('"').to_string())
+}
+
+fn total_memory() -> Option<u64> {
+    let meminfo = read("/proc/meminfo")?;
+    meminfo
+        .lines()
+        .find_map(|line| line.strip_prefix("MemTotal:"))
+        .and_then(|value| {
+            value
+                .trim()
+                .trim_end_matches("kB")
+                .trim()
+                .parse::<u64>()
+                .ok()
+        })
+        .map(|kb| kb * 1024)
+}
+
+fn container_hint() -> Option<&'static str> {
+    if std::env::var_os("KUBERNETES_SERVICE_HOST").is_some() {
+        return Some("kubernetes");
+    }
+
+    if std::path::Path::new("/.dockerenv").exists() {
+        return Some("docker");
+    }
+
+    if std::path::Path::new("/run/.containerenv").exists() {
+        return Some("podman");
+    }
+
+    let cgroup = read("/proc/self/cgroup")?;
+    ["docker", "containerd", "crio", "lxc"]
+        .into_iter()
+        .find(|runtime| cgroup.contains(runtime))
+}
+
+fn hypervisor_hint() -> Option<String> {
+    let vendor = read("/sys/class/dmi/id/sys_vendor").unwrap_or_default();
+    let product = read("/sys/class/dmi/id/product_name").unwrap_or_default();
+
+    [
+        ("QEMU", "qemu"),
+        ("KVM", "kvm"),
+        ("VMware", "vmware"),
+        ("VirtualBox", "virtualbox"),
+        ("Xen", "xen"),
+        ("Amazon EC2", "aws"),
+        ("Google Compute Engine", "gcp"),
+        ("Microsoft Corporation", "hyper-v"),
+    ]
+    .into_iter()
+    .find(|(hint, _)| vendor.contains(hint) || product.contains(hint))
+    .map(|(_, name)| name.to_string())
+    .or_else(|| {
+        read("/proc/cpuinfo")
+            .filter(|cpuinfo| cpuinfo.contains(" hypervisor"))
+            .map(|_| "unknown".to_string())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_allowlisted_env_vars() {
+        std::env::set_var("TRACING_BATTERIES_SNAPSHOT_ALLOWED", "yes");
+        std::env::set_var("TRACING_BATTERIES_SNAPSHOT_SECRET", "hunter2");
+
+        let snapshot = environment_snapshot(["TRACING_BATTERIES_SNAPSHOT_ALLOWED"]);
+
+        assert_eq!(
+            snapshot.get("env.TRACING_BATTERIES_SNAPSHOT_ALLOWED"),
+            Some(&Value::from("yes"))
+        );
+        assert!(!snapshot.contains_key("env.TRACING_BATTERIES_SNAPSHOT_SECRET"));
+        assert_eq!(
+            snapshot.get("os.type"),
+            Some(&Value::from(std::env::consts::OS))
+        );
+    }
+}
@@ -0,0 +1,93 @@
+use opentelemetry::{metrics::Counter, KeyValue};
+use tracing::{Event, Level, Metadata, Subscriber};
+use tracing_subscriber::{layer::Context, Layer};
+
+/// A [`Layer`] which counts the tracing events emitted by the application, by level and target, into the
+/// `log_events_total` counter.
+///
+/// This allows error rate dashboards and alerts to be built for services which only emit logs, without
+/// requiring them to record any custom metrics.
+pub(crate) struct EventMetricsLayer {
+    events: Counter<u64>,
+}
+
+impl EventMetricsLayer {
+    pub fn new(meter: &opentelemetry::metrics::Meter) -> Self {
+        Self {
+            events: meter
+                .u64_counter("log_events_total")
+                .with_description("The number of tracing events emitted, by level and target.")
+                .build(),
+        }
+    }
+}
+
+impl<S: Subscriber> Layer<S> for EventMetricsLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        self.events.add(1, &attributes(event.metadata()));
+    }
+}
+
+/// Builds the attributes for an event, which borrow its static level and target rather than allocating.
+fn attributes(metadata: &'static Metadata<'static>) -> [KeyValue; 2] {
+    [
+        KeyValue::new("level", level_label(metadata.level())),
+        KeyValue::new("target", metadata.target()),
+    ]
+}
+
+fn level_label(level: &Level) -> &'static str {
+    match *level {
+        Level::ERROR => "error",
+        Level::WARN => "warn",
+        Level::INFO => "info",
+        Level::DEBUG => "debug",
+        Level::TRACE => "trace",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use opentelemetry::{metrics::MeterProvider, StringValue, Value};
+    use tracing_subscriber::layer::SubscriberExt;
+
+    use super::*;
+
+    #[test]
+    fn labels_events_by_level_and_target() {
+        let captured = Arc::new(Mutex::new(Vec::new()));
+        let meter = opentelemetry_sdk::metrics::SdkMeterProvider::default().meter("test");
+
+        let subscriber = tracing_subscriber::registry()
+            .with(EventMetricsLayer::new(&meter))
+            .with(CapturingLayer(captured.clone()));
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::warn!(target: "billing", "Payment was retried.");
+            tracing::debug!("Cache miss.");
+        });
+
+        let captured = captured.lock().unwrap();
+        assert_eq!(
+            captured[0],
+            [
+                KeyValue::new("level", "warn"),
+                KeyValue::new("target", "billing"),
+            ]
+        );
+        assert_eq!(captured[1][0], KeyValue::new("level", "debug"));
+        assert!(
+            matches!(&captured[1][1].value, Value::String(target) if *target == StringValue::from(module_path!())),
+            "the target should default to the module path"
+        );
+    }
+
+    struct CapturingLayer(Arc<Mutex<Vec<[KeyValue; 2]>>>);
+
+    impl<S: Subscriber> Layer<S> for CapturingLayer {
+        fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+            self.0.lock().unwrap().push(attributes(event.metadata()));
+        }
+    }
+}
@@ -0,0 +1,153 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    sync::{Mutex, PoisonError},
+};
+
+use crate::{EventSchema, Session};
+
+/// Determines how custom events which have not been registered using [`Session::register_event`], or whose
+/// properties do not match their registered [`EventSchema`], are handled.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EventRegistryMode {
+    /// Events are recorded regardless, with a warning being logged the first time each event name is misused.
+    /// No checks are performed until at least one event has been registered.
+    #[default]
+    Lenient,
+    /// Events which have not been registered, or whose properties do not match their schema, are dropped
+    /// (with a warning being logged the first time each event name is misused).
+    Strict,
+}
+
+/// Holds the custom events which have been registered with a session, along with how misused events are handled.
+pub(crate) struct EventRegistry {
+    mode: Mutex<EventRegistryMode>,
+    events: Mutex<HashMap<String, EventSchema>>,
+    warned: Mutex<HashSet<String>>,
+}
+
+impl EventRegistry {
+    pub fn new() -> Self {
+        Self {
+            mode: Mutex::new(EventRegistryMode::default()),
+            events: Mutex::new(HashMap::new()),
+            warned: Mutex::new(HashSet::new()),
+        }
+    }
+
+    /// Determines whether an event should be recorded, warning (once per event name) if it has been misused.
+    pub fn allows(&self, name: &str, properties: &BTreeMap<String, serde_json::Value>) -> bool {
+        let mode = *self.mode.lock().unwrap_or_else(PoisonError::into_inner);
+        let problem = {
+            let events = self.events.lock().unwrap_or_else(PoisonError::into_inner);
+            if events.is_empty() && mode == EventRegistryMode::Lenient {
+                return true;
+            }
+
+            match events.get(name) {
+                Some(schema) => schema.problems(properties),
+                None => Some("it has not been registered".to_string()),
+            }
+        };
+
+        let Some(problem) = problem else {
+            return true;
+        };
+
+        if self
+            .warned
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(name.to_string())
+        {
+            tracing::warn!(
+                event = name,
+                "The '{}' event does not match the event registry because {}, {}.",
+                name,
+                problem,
+                if mode == EventRegistryMode::Strict {
+                    "so it will not be recorded"
+                } else {
+                    "but it will be recorded anyway"
+                }
+            );
+        }
+
+        mode == EventRegistryMode::Lenient
+    }
+}
+
+impl Session {
+    /// Registers a custom event, along with the properties it carries, so that misspelled or one-off event names
+    /// recorded using [`Session::record_event`] can be caught before they accumulate in your analytics data.
+    ///
+    /// Once any event has been registered, recording an event which hasn't been registered (or which is missing
+    /// a required property, or has a property of the wrong type) logs a warning the first time it happens. Use
+    /// [`Session::with_event_registry`] to drop such events entirely instead. Events recorded by the library itself
+    /// (such as `session_summary`) are not checked, and property cardinality limits are only enforced by the
+    /// [`Validated`](crate::Validated) combinator.
+    ///
+    /// ## Example
+    /// ```no_run
+    /// use tracing_batteries::{Session, Sentry, EventSchema, PropertyType};
+    ///
+    /// let session = Session::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+    ///   .with_battery(Sentry::new("https://yourdsn@sentry.example.com"));
+    ///
+    /// session.register_event(EventSchema::new("export_pdf")
+    ///   .require("pages", PropertyType::Number));
+    ///
+    /// session.record_event("export_pdf", [("pages", 3)]);
+    /// ```
+    pub fn register_event(&self, schema: EventSchema) {
+        self.events
+            .events
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(schema.name().to_string(), schema);
+    }
+
+    /// Configures how custom events which don't match the event registry are handled, see [`EventRegistryMode`].
+    ///
+    /// ## Example
+    /// ```no_run
+    /// use tracing_batteries::{Session, Sentry, EventRegistryMode};
+    ///
+    /// let session = Session::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+    ///   .with_battery(Sentry::new("https://yourdsn@sentry.example.com"))
+    ///   .with_event_registry(EventRegistryMode::Strict);
+    /// ```
+    pub fn with_event_registry(self, mode: EventRegistryMode) -> Self {
+        *self
+            .events
+            .mode
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = mode;
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PropertyType;
+
+    #[test]
+    fn applies_modes() {
+        let registry = EventRegistry::new();
+        let pages = BTreeMap::from([("pages".to_string(), serde_json::Value::from(3))]);
+        assert!(registry.allows("export_pfd", &pages));
+
+        registry.events.lock().unwrap().insert(
+            "export_pdf".into(),
+            EventSchema::new("export_pdf").require("pages", PropertyType::Number),
+        );
+        assert!(registry.allows("export_pdf", &pages));
+        assert!(registry.allows("export_pfd", &pages));
+        assert!(registry.allows("export_pdf", &BTreeMap::new()));
+
+        *registry.mode.lock().unwrap() = EventRegistryMode::Strict;
+        assert!(registry.allows("export_pdf", &pages));
+        assert!(!registry.allows("export_pfd", &pages));
+        assert!(!registry.allows("export_pdf", &BTreeMap::new()));
+    }
+}
@@ -0,0 +1,183 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex, PoisonError,
+    },
+};
+
+use opentelemetry_sdk::export::trace::{ExportResult, SpanData, SpanExporter};
+
+/// A [`SpanExporter`] which exports spans to one of several collectors, failing over to the next collector
+/// (in round-robin order) whenever an export fails.
+///
+/// Batches which fail are retried against each of the other collectors before the export is reported as failed,
+/// and the collector which accepted the batch is used for subsequent exports. This allows a single collector to be
+/// restarted without losing spans in environments which don't have a load balancer in front of their collectors.
+#[derive(Debug)]
+pub(crate) struct FailoverSpanExporter<E> {
+    exporters: Arc<Vec<(String, Mutex<E>)>>,
+    current: Arc<AtomicUsize>,
+}
+
+impl<E: SpanExporter + 'static> FailoverSpanExporter<E> {
+    pub fn new(exporters: Vec<(String, E)>) -> Self {
+        Self {
+            exporters: Arc::new(
+                exporters
+                    .into_iter()
+                    .map(|(endpoint, exporter)| (endpoint, Mutex::new(exporter)))
+                    .collect(),
+            ),
+            current: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    fn each<F: FnMut(&mut E)>(&self, mut f: F) {
+        for (_, exporter) in self.exporters.iter() {
+            f(&mut exporter.lock().unwrap_or_else(PoisonError::into_inner));
+        }
+    }
+}
+
+impl<E: SpanExporter + 'static> SpanExporter for FailoverSpanExporter<E> {
+    fn export(
+        &mut self,
+        batch: Vec<SpanData>,
+    ) -> Pin<Box<dyn Future<Output = ExportResult> + Send + 'static>> {
+        if self.exporters.len() == 1 {
+            return self.exporters[0]
+                .1
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .export(batch);
+        }
+
+        let exporters = self.exporters.clone();
+        let current = self.current.clone();
+        Box::pin(async move {
+            let start = current.load(Ordering::Relaxed);
+            let mut result = Ok(());
+            for attempt in 0..exporters.len() {
+                let index = (start + attempt) % exporters.len();
+                let (endpoint, exporter) = &exporters[index];
+                let export = exporter
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .export(batch.clone());
+
+                result = export.await;
+                match &result {
+                    Ok(()) => {
+                        current.store(index, Ordering::Relaxed);
+                        break;
+                    }
+                    Err(err) => crate::diagnostics::record_export_error(
+                        &format!("opentelemetry ({endpoint})"),
+                        err,
+                    ),
+                }
+            }
+
+            result
+        })
+    }
+
+    fn shutdown(&mut self) {
+        self.each(|exporter| exporter.shutdown());
+    }
+
+    fn force_flush(&mut self) -> Pin<Box<dyn Future<Output = ExportResult> + Send + 'static>> {
+        let flushes: Vec<_> = self
+            .exporters
+            .iter()
+            .map(|(_, exporter)| {
+                exporter
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .force_flush()
+            })
+            .collect();
+
+        Box::pin(async move {
+            let mut result = Ok(());
+            for flush in flushes {
+                if let Err(err) = flush.await {
+                    result = Err(err);
+                }
+            }
+
+            result
+        })
+    }
+
+    fn set_resource(&mut self, resource: &opentelemetry_sdk::Resource) {
+        self.each(|exporter| exporter.set_resource(resource));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use opentelemetry::trace::TraceError;
+
+    use super::*;
+
+    #[derive(Debug)]
+    struct TestExporter {
+        healthy: bool,
+        exported: Arc<AtomicUsize>,
+    }
+
+    impl SpanExporter for TestExporter {
+        fn export(
+            &mut self,
+            _batch: Vec<SpanData>,
+        ) -> Pin<Box<dyn Future<Output = ExportResult> + Send + 'static>> {
+            if !self.healthy {
+                return Box::pin(std::future::ready(Err(TraceError::from(
+                    "connection refused",
+                ))));
+            }
+
+            self.exported.fetch_add(1, Ordering::Relaxed);
+            Box::pin(std::future::ready(Ok(())))
+        }
+    }
+
+    /// Resolves a future which is expected to complete immediately, as the test exporter's exports do.
+    fn resolve<F: Future>(future: F) -> F::Output {
+        let mut context = std::task::Context::from_waker(std::task::Waker::noop());
+        match std::pin::pin!(future).poll(&mut context) {
+            std::task::Poll::Ready(output) => output,
+            std::task::Poll::Pending => panic!("the export should complete immediately"),
+        }
+    }
+
+    #[test]
+    fn fails_over_to_healthy_collector() {
+        let (a, b) = (Arc::new(AtomicUsize::new(0)), Arc::new(AtomicUsize::new(0)));
+        let mut exporter = FailoverSpanExporter::new(vec![
+            (
+                "collector-a:4317".to_string(),
+                TestExporter {
+                    healthy: false,
+                    exported: a.clone(),
+                },
+            ),
+            (
+                "collector-b:4317".to_string(),
+                TestExporter {
+                    healthy: true,
+                    exported: b.clone(),
+                },
+            ),
+        ]);
+
+        assert!(resolve(exporter.export(Vec::new())).is_ok());
+        assert!(resolve(exporter.export(Vec::new())).is_ok());
+        assert_eq!(a.load(Ordering::Relaxed), 0);
+        assert_eq!(b.load(Ordering::Relaxed), 2);
+        assert_eq!(exporter.current.load(Ordering::Relaxed), 1);
+    }
+}
@@ -0,0 +1,244 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, PoisonError, RwLock},
+};
+
+use crate::{Session, Str};
+
+/// The maximum number of distinct flag keys which will be attached to errors, beyond which additional flags are
+/// only attached to spans (since each key permanently allocates an error field name).
+const MAX_FLAG_FIELDS: usize = 1024;
+
+static PROVIDERS: RwLock<Vec<Arc<dyn FlagProvider>>> = RwLock::new(Vec::new());
+
+/// A source of feature flag values which are attached to the telemetry recorded by your application, so that you
+/// can see which flags were enabled when an error occurred.
+///
+/// Providers are registered using [`Session::with_flag_provider`], after which their flags are attached to each
+/// error (as `feature_flag.{key}` fields) and to the spans exported by the [`OpenTelemetry`](crate::OpenTelemetry)
+/// battery (as `feature_flag.{key}` attributes). Since flags are retrieved whenever telemetry is recorded,
+/// implementations should return values they have already evaluated rather than performing any I/O.
+///
+/// This crate provides the [`EnvFlags`] provider, along with `LaunchDarkly` and `Unleash` providers when the
+/// `launchdarkly` and `unleash` features are enabled.
+///
+/// ## Example
+/// ```rust
+/// use tracing_batteries::{FlagProvider, Str};
+///
+/// struct StaticFlags;
+///
+/// impl FlagProvider for StaticFlags {
+///   fn flags(&self) -> Vec<(Str, Str)> {
+///     vec![("new-checkout".into(), "true".into())]
+///   }
+/// }
+/// ```
+pub trait FlagProvider: Send + Sync + 'static {
+    /// Returns the current value of each flag, keyed by the flag's name.
+    fn flags(&self) -> Vec<(Str, Str)>;
+
+    /// Called when the provider is registered with a session, allowing it to begin evaluating flags in the
+    /// background. Only called if telemetry is enabled at the time the provider is registered.
+    fn start(&self) {}
+}
+
+/// A [`FlagProvider`] which reads feature flags from environment variables sharing a common prefix.
+///
+/// The environment is read when the provider is created, with each variable's name (after removing the prefix)
+/// being converted to lowercase to form the flag's key. For example, with the `FEATURE_` prefix, the
+/// `FEATURE_NEW_CHECKOUT=true` variable is reported as the `new_checkout` flag.
+///
+/// ## Example
+/// ```no_run
+/// use tracing_batteries::{Session, Sentry, EnvFlags};
+///
+/// let session = Session::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+///   .with_battery(Sentry::new("https://yourdsn@sentry.example.com"))
+///   .with_flag_provider(EnvFlags::new("FEATURE_"));
+///
+/// session.shutdown();
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct EnvFlags {
+    flags: Vec<(Str, Str)>,
+}
+
+impl EnvFlags {
+    /// Creates a provider which reports the environment variables starting with the provided prefix as flags.
+    pub fn new<P: AsRef<str>>(prefix: P) -> Self {
+        Self::from_vars(prefix.as_ref(), std::env::vars())
+    }
+
+    fn from_vars<I: IntoIterator<Item = (String, String)>>(prefix: &str, vars: I) -> Self {
+        let mut flags: Vec<(Str, Str)> = vars
+            .into_iter()
+            .filter_map(|(name, value)| {
+                name.strip_prefix(prefix)
+                    .filter(|key| !key.is_empty())
+                    .map(|key| (key.to_lowercase().into(), value.into()))
+            })
+            .collect();
+        flags.sort();
+
+        Self { flags }
+    }
+}
+
+impl FlagProvider for EnvFlags {
+    fn flags(&self) -> Vec<(Str, Str)> {
+        self.flags.clone()
+    }
+}
+
+/// Holds the most recently evaluated flags for providers which poll a remote service in the background.
+#[cfg(any(feature = "launchdarkly", feature = "unleash"))]
+pub(crate) struct PolledFlags {
+    flags: Arc<RwLock<Vec<(Str, Str)>>>,
+    started: std::sync::Once,
+}
+
+#[cfg(any(feature = "launchdarkly", feature = "unleash"))]
+impl PolledFlags {
+    pub fn new() -> Self {
+        Self {
+            flags: Arc::new(RwLock::new(Vec::new())),
+            started: std::sync::Once::new(),
+        }
+    }
+
+    pub fn get(&self) -> Vec<(Str, Str)> {
+        self.flags
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Starts polling for flags on a background thread, which exits once this [`PolledFlags`] is dropped.
+    /// Failed polls leave the previously evaluated flags in place.
+    pub fn start<F>(&self, name: &str, interval: std::time::Duration, fetch: F)
+    where
+        F: Fn() -> Option<Vec<(Str, Str)>> + Send + 'static,
+    {
+        self.started.call_once(|| {
+            let flags = Arc::downgrade(&self.flags);
+            std::thread::Builder::new()
+                .name(format!("tracing-batteries-{name}"))
+                .spawn(move || loop {
+                    let fetched = fetch();
+                    let Some(flags) = flags.upgrade() else {
+                        break;
+                    };
+
+                    if let Some(fetched) = fetched {
+                        *flags.write().unwrap_or_else(PoisonError::into_inner) = fetched;
+                    }
+
+                    drop(flags);
+                    std::thread::sleep(interval);
+                })
+                .ok();
+        });
+    }
+}
+
+/// Returns the flags reported by every registered provider, with later providers taking precedence when
+/// several report the same flag.
+pub(crate) fn current() -> Vec<(Str, Str)> {
+    let providers = PROVIDERS.read().unwrap_or_else(PoisonError::into_inner);
+    let mut flags: Vec<(Str, Str)> = Vec::new();
+    for provider in providers.iter().rev() {
+        for (key, value) in provider.flags() {
+            if !flags.iter().any(|(existing, _)| *existing == key) {
+                flags.push((key, value));
+            }
+        }
+    }
+
+    flags
+}
+
+/// Returns the error field name used to report the provided flag, if the limit on distinct flag fields
+/// has not been reached.
+pub(crate) fn field_name(key: &str) -> Option<&'static str> {
+    static FIELDS: Mutex<Option<HashMap<Str, &'static str>>> = Mutex::new(None);
+
+    let mut fields = FIELDS.lock().unwrap_or_else(PoisonError::into_inner);
+    let fields = fields.get_or_insert_with(HashMap::new);
+    if let Some(field) = fields.get(key) {
+        return Some(field);
+    }
+
+    if fields.len() >= MAX_FLAG_FIELDS {
+        return None;
+    }
+
+    let field: &'static str = Box::leak(format!("feature_flag.{key}").into_boxed_str());
+    fields.insert(Str::intern(key), field);
+    Some(field)
+}
+
+impl Session {
+    /// Registers a [`FlagProvider`] whose flags are attached to the errors recorded by this session and the spans
+    /// exported by the [`OpenTelemetry`](crate::OpenTelemetry) battery, so that you can see which flags were
+    /// enabled when something went wrong.
+    ///
+    /// Like tracing layers, flag providers apply to the whole process rather than an individual session. When
+    /// several providers report the same flag, the most recently registered provider takes precedence, while
+    /// fields which were provided explicitly when recording an error take precedence over both.
+    ///
+    /// ## Example
+    /// ```no_run
+    /// use tracing_batteries::{Session, Sentry, EnvFlags};
+    ///
+    /// let session = Session::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+    ///   .with_battery(Sentry::new("https://yourdsn@sentry.example.com"))
+    ///   .with_flag_provider(EnvFlags::new("FEATURE_"));
+    ///
+    /// session.shutdown();
+    /// ```
+    pub fn with_flag_provider<P: FlagProvider>(self, provider: P) -> Self {
+        if self.is_enabled() {
+            provider.start();
+        }
+
+        PROVIDERS
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(Arc::new(provider));
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_env_flags() {
+        let flags = EnvFlags::from_vars(
+            "FEATURE_",
+            [
+                ("FEATURE_NEW_CHECKOUT".to_string(), "true".to_string()),
+                ("FEATURE_".to_string(), "ignored".to_string()),
+                ("PATH".to_string(), "/usr/bin".to_string()),
+                ("FEATURE_BETA_EXPORT".to_string(), "off".to_string()),
+            ],
+        );
+
+        assert_eq!(
+            flags.flags(),
+            vec![
+                ("beta_export".into(), "off".into()),
+                ("new_checkout".into(), "true".into())
+            ]
+        );
+    }
+
+    #[test]
+    fn names_flag_fields() {
+        let field = field_name("new_checkout").expect("a field name should be allocated");
+        assert_eq!(field, "feature_flag.new_checkout");
+        assert!(std::ptr::eq(field, field_name("new_checkout").unwrap()));
+    }
+}
@@ -0,0 +1,139 @@
+use std::{
+    collections::BTreeMap,
+    sync::{Mutex, Once, PoisonError},
+    time::Duration,
+};
+
+use crate::{EnvelopePayload, Session};
+
+/// Aggregates feature usage counts in memory until they are flushed as a single `feature_usage` event.
+pub(crate) struct FeatureUsage {
+    counts: Mutex<BTreeMap<String, u64>>,
+    interval: Mutex<Duration>,
+    flusher: Once,
+}
+
+impl FeatureUsage {
+    pub fn new() -> Self {
+        Self {
+            counts: Mutex::new(BTreeMap::new()),
+            interval: Mutex::new(Duration::from_secs(300)),
+            flusher: Once::new(),
+        }
+    }
+
+    fn interval(&self) -> Duration {
+        *self.interval.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn take(&self) -> BTreeMap<String, u64> {
+        std::mem::take(&mut *self.counts.lock().unwrap_or_else(PoisonError::into_inner))
+    }
+}
+
+impl Session {
+    /// Records that a feature of your application has been used.
+    ///
+    /// Rather than reporting each use individually, usage counts are aggregated in memory and flushed
+    /// periodically (every 5 minutes by default, see [`Session::with_feature_usage_interval`]) as a single
+    /// `feature_usage` custom event whose properties map each feature to the number of times it was used.
+    /// Any outstanding usage is flushed when the session is shut down.
+    ///
+    /// ## Example
+    /// ```no_run
+    /// use tracing_batteries::{Session, Sentry};
+    ///
+    /// let session = Session::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+    ///   .with_battery(Sentry::new("https://yourdsn@sentry.example.com/app-id"));
+    ///
+    /// session.feature_used("tab_completion");
+    ///
+    /// session.shutdown();
+    /// ```
+    pub fn feature_used<F: Into<String>>(&self, feature: F) {
+        if !self.is_enabled() {
+            return;
+        }
+
+        *self
+            .features
+            .counts
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .entry(feature.into())
+            .or_default() += 1;
+
+        self.features.flusher.call_once(|| {
+            let session = self.downgrade();
+            std::thread::Builder::new()
+                .name("tracing-batteries-feature-usage".into())
+                .spawn(move || loop {
+                    let Some(interval) = session.upgrade().map(|s| s.features.interval()) else {
+                        break;
+                    };
+
+                    std::thread::sleep(interval);
+                    match session.upgrade() {
+                        Some(session) => session.flush_feature_usage(),
+                        None => break,
+                    }
+                })
+                .ok();
+        });
+    }
+
+    /// Configures how frequently aggregated feature usage is reported by [`Session::feature_used`].
+    pub fn with_feature_usage_interval(self, interval: Duration) -> Self {
+        *self
+            .features
+            .interval
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = interval;
+        self
+    }
+
+    pub(crate) fn flush_feature_usage(&self) {
+        let counts = self.features.take();
+        if counts.is_empty() {
+            return;
+        }
+
+        self.record_envelope(|| EnvelopePayload::Event {
+            name: "feature_usage".into(),
+            properties: counts
+                .into_iter()
+                .map(|(feature, count)| (feature, count.into()))
+                .collect(),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{test_support::EnvelopeBattery, EnvelopePayload, Session};
+
+    #[test]
+    fn aggregates_usage() {
+        let battery = EnvelopeBattery::default();
+        let session = Session::new("example", "0.0.1").with_battery(battery.clone());
+
+        session.feature_used("tab_completion");
+        session.feature_used("tab_completion");
+        session.feature_used("export_pdf");
+        session.clone().shutdown();
+
+        let usage = battery
+            .envelopes()
+            .iter()
+            .find_map(|envelope| match &envelope.payload {
+                EnvelopePayload::Event { name, properties } if name == "feature_usage" => {
+                    Some(properties.clone())
+                }
+                _ => None,
+            })
+            .expect("a feature_usage event should be recorded");
+
+        assert_eq!(usage["tab_completion"], 2);
+        assert_eq!(usage["export_pdf"], 1);
+    }
+}
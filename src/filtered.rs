@@ -0,0 +1,288 @@
+use std::{
+    borrow::Cow,
+    sync::{atomic::AtomicBool, Arc},
+};
+
+use tracing::Level;
+
+use crate::{
+    Battery, BatteryBuilder, Envelope, EnvelopePayload, ErrorContext, Metadata, Metric, WeakSession,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TelemetryKind {
+    Error,
+    Analytics,
+    Metric,
+}
+
+impl TelemetryKind {
+    fn of(envelope: &Envelope) -> Self {
+        match envelope.payload {
+            EnvelopePayload::Error { .. } => TelemetryKind::Error,
+            _ => TelemetryKind::Analytics,
+        }
+    }
+
+    fn level(self) -> Level {
+        match self {
+            TelemetryKind::Error => Level::ERROR,
+            TelemetryKind::Analytics | TelemetryKind::Metric => Level::INFO,
+        }
+    }
+}
+
+/// A combinator which wraps another battery, only forwarding the telemetry which matches its filters.
+///
+/// This allows you to route different telemetry to different batteries, for example sending only errors to your
+/// error tracker while your OpenTelemetry collector receives everything. The following filters may
+/// be combined, with telemetry only being forwarded when it matches all of them:
+///
+/// - [`Filtered::with_min_level`] treats errors as `ERROR` and custom events, page views and metrics as `INFO`.
+/// - [`Filtered::with_targets`] only forwards telemetry recorded within a span whose target (usually the module
+///   which created it) is, or is a child of, one of the provided targets.
+/// - [`Filtered::only_errors`] and [`Filtered::only_analytics`] restrict the kinds of telemetry which are forwarded.
+///
+/// Filters apply to the errors, envelopes and metrics reported by the [`Session`](crate::Session), while the
+/// wrapped battery's setup, shutdown and any tracing layers it installs are unaffected.
+///
+/// ## Example
+/// ```no_run
+/// use tracing_batteries::{Session, Filtered, OpenTelemetry, Sentry};
+///
+/// let session = Session::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+///   .with_battery(OpenTelemetry::new("localhost:4317"))
+///   .with_battery(Filtered::new(Sentry::new("https://yourdsn@sentry.example.com"))
+///     .only_errors()
+///     .with_targets(["my_service::billing"]));
+///
+/// session.shutdown();
+/// ```
+pub struct Filtered<B: BatteryBuilder> {
+    battery: B,
+    filter: TelemetryFilter,
+}
+
+impl<B: BatteryBuilder> Filtered<B> {
+    /// Wraps the provided battery, initially forwarding all telemetry to it.
+    pub fn new(battery: B) -> Self {
+        Self {
+            battery,
+            filter: TelemetryFilter {
+                min_level: Level::TRACE,
+                targets: None,
+                kind: None,
+            },
+        }
+    }
+
+    /// Only forwards telemetry at or above the provided level.
+    pub fn with_min_level(self, level: Level) -> Self {
+        Self {
+            filter: TelemetryFilter {
+                min_level: level,
+                ..self.filter
+            },
+            ..self
+        }
+    }
+
+    /// Only forwards telemetry recorded within spans whose targets are (or are children of) the provided targets.
+    pub fn with_targets<I, T>(self, targets: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<Cow<'static, str>>,
+    {
+        Self {
+            filter: TelemetryFilter {
+                targets: Some(targets.into_iter().map(Into::into).collect()),
+                ..self.filter
+            },
+            ..self
+        }
+    }
+
+    /// Only forwards errors, dropping custom events, page views and metrics.
+    pub fn only_errors(self) -> Self {
+        Self {
+            filter: TelemetryFilter {
+                kind: Some(TelemetryKind::Error),
+                ..self.filter
+            },
+            ..self
+        }
+    }
+
+    /// Only forwards custom events and page views, dropping errors and metrics.
+    pub fn only_analytics(self) -> Self {
+        Self {
+            filter: TelemetryFilter {
+                kind: Some(TelemetryKind::Analytics),
+                ..self.filter
+            },
+            ..self
+        }
+    }
+}
+
+impl<B: BatteryBuilder> BatteryBuilder for Filtered<B> {
+    fn setup(self, metadata: &Metadata, enabled: Arc<AtomicBool>) -> Box<dyn Battery> {
+        Box::new(FilteredBattery {
+            inner: self.battery.setup(metadata, enabled),
+            filter: self.filter,
+        })
+    }
+}
+
+struct TelemetryFilter {
+    min_level: Level,
+    targets: Option<Vec<Cow<'static, str>>>,
+    kind: Option<TelemetryKind>,
+}
+
+impl TelemetryFilter {
+    fn allows(&self, kind: TelemetryKind) -> bool {
+        // Levels are ordered by verbosity, so the least verbose (ERROR) level is the smallest.
+        if kind.level() > self.min_level || self.kind.is_some_and(|allowed| allowed != kind) {
+            return false;
+        }
+
+        match &self.targets {
+            Some(targets) => tracing::Span::current()
+                .metadata()
+                .is_some_and(|metadata| matches_target(metadata.target(), targets)),
+            None => true,
+        }
+    }
+}
+
+fn matches_target(target: &str, allowed: &[Cow<'static, str>]) -> bool {
+    allowed.iter().any(|allowed| {
+        target
+            .strip_prefix(allowed.as_ref())
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with("::"))
+    })
+}
+
+struct FilteredBattery {
+    inner: Box<dyn Battery>,
+    filter: TelemetryFilter,
+}
+
+impl Battery for FilteredBattery {
+    fn record_error(&self, error: &dyn std::error::Error) {
+        if self.filter.allows(TelemetryKind::Error) {
+            self.inner.record_error(error);
+        }
+    }
+
+    fn record_error_with(&self, error: &dyn std::error::Error, context: &ErrorContext) {
+        if self.filter.allows(TelemetryKind::Error) {
+            self.inner.record_error_with(error, context);
+        }
+    }
+
+    fn record_envelope(&self, envelope: &Envelope) {
+        if self.filter.allows(TelemetryKind::of(envelope)) {
+            self.inner.record_envelope(envelope);
+        }
+    }
+
+    fn record_metric(&self, metric: &Metric) {
+        if self.filter.allows(TelemetryKind::Metric) {
+            self.inner.record_metric(metric);
+        }
+    }
+
+    fn accepts_weighted_histograms(&self) -> bool {
+        self.inner.accepts_weighted_histograms()
+    }
+
+    fn record_artifact(&self, artifact: &crate::Artifact) -> bool {
+        self.filter.allows(TelemetryKind::Error) && self.inner.record_artifact(artifact)
+    }
+
+    fn release(&self) {
+        self.inner.release();
+    }
+
+    fn shutdown(&self) {
+        self.inner.shutdown();
+    }
+
+    fn attached(&self, session: WeakSession) {
+        self.inner.attached(session);
+    }
+
+    fn validate(&self) -> Vec<crate::ValidationCheck> {
+        self.inner.validate()
+    }
+
+    #[cfg(feature = "version-check")]
+    fn update_available(&self) -> Option<crate::AvailableUpdate> {
+        self.inner.update_available()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+    use crate::Session;
+
+    struct RecordingBattery {
+        recorded: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl BatteryBuilder for RecordingBattery {
+        fn setup(self, _metadata: &Metadata, _enabled: Arc<AtomicBool>) -> Box<dyn Battery> {
+            Box::new(self)
+        }
+    }
+
+    impl Battery for RecordingBattery {
+        fn record_envelope(&self, envelope: &Envelope) {
+            self.recorded.lock().unwrap().push(envelope.summary());
+        }
+
+        fn record_metric(&self, metric: &Metric) {
+            self.recorded.lock().unwrap().push(metric.name.to_string());
+        }
+    }
+
+    #[test]
+    fn filters_telemetry() {
+        let errors = Arc::new(Mutex::new(Vec::new()));
+        let analytics = Arc::new(Mutex::new(Vec::new()));
+        let session = Session::new("example", "0.0.1")
+            .with_battery(
+                Filtered::new(RecordingBattery {
+                    recorded: errors.clone(),
+                })
+                .with_min_level(Level::WARN),
+            )
+            .with_battery(
+                Filtered::new(RecordingBattery {
+                    recorded: analytics.clone(),
+                })
+                .only_analytics(),
+            );
+
+        session.record_new_page("/settings");
+        session.record_error(&std::io::Error::other("disk full"));
+        session.counter("items_processed").inc(1);
+
+        assert_eq!(errors.lock().unwrap().len(), 1);
+        assert_eq!(analytics.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn target_allowlist() {
+        let allowed = [Cow::Borrowed("my_service::billing")];
+        assert!(matches_target("my_service::billing", &allowed));
+        assert!(matches_target("my_service::billing::invoices", &allowed));
+        assert!(!matches_target("my_service::billing_v2", &allowed));
+        assert!(!matches_target("my_service", &allowed));
+    }
+}
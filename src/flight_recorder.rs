@@ -0,0 +1,254 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt::Write as _,
+    sync::{Arc, Mutex, PoisonError},
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+use tracing::{
+    field::{Field, Visit},
+    Event, Level, Subscriber,
+};
+use tracing_subscriber::{filter::LevelFilter, layer::Context, Layer};
+
+use crate::{Breadcrumb, Session};
+
+type Trigger = dyn Fn(&dyn std::error::Error, &HashMap<&'static str, String>) -> bool + Send + Sync;
+
+/// Configures the flight recorder which is enabled using [`Session::with_flight_recorder`].
+///
+/// The flight recorder keeps every event emitted within the last `window` (at every level, including `TRACE`)
+/// in memory, and attaches them to the next error which is recorded as [`Breadcrumb`]s. This gives you the
+/// full context leading up to rare failures without paying to export trace-level telemetry continuously.
+pub struct FlightRecorder {
+    window: Duration,
+    capacity: usize,
+    trigger: Box<Trigger>,
+}
+
+impl FlightRecorder {
+    /// Creates a flight recorder which keeps the events emitted within the provided window (up to 1,000 events).
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            capacity: 1_000,
+            trigger: Box::new(|_, _| true),
+        }
+    }
+
+    /// Configures the maximum number of events which are kept in memory, discarding the oldest events first.
+    pub fn with_capacity(self, capacity: usize) -> Self {
+        Self { capacity, ..self }
+    }
+
+    /// Configures which errors the recorded events are attached to, based on the error and the context fields it
+    /// was recorded with. By default, the recorded events are attached to every error.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use std::time::Duration;
+    /// use tracing_batteries::FlightRecorder;
+    ///
+    /// FlightRecorder::new(Duration::from_secs(30))
+    ///   .with_trigger(|_error, fields| fields.get("severity").map(|s| s.as_str()) == Some("critical"));
+    /// ```
+    pub fn with_trigger<F>(self, trigger: F) -> Self
+    where
+        F: Fn(&dyn std::error::Error, &HashMap<&'static str, String>) -> bool
+            + Send
+            + Sync
+            + 'static,
+    {
+        Self {
+            trigger: Box::new(trigger),
+            ..self
+        }
+    }
+}
+
+struct FlightRecord {
+    at: DateTime<Utc>,
+    level: Level,
+    target: &'static str,
+    message: String,
+}
+
+/// The events held by the flight recorder, shared between the session and its tracing layer.
+pub(crate) struct FlightRecording {
+    config: FlightRecorder,
+    records: Mutex<VecDeque<FlightRecord>>,
+}
+
+impl FlightRecording {
+    fn new(config: FlightRecorder) -> Self {
+        Self {
+            records: Mutex::new(VecDeque::with_capacity(config.capacity.min(1_000))),
+            config,
+        }
+    }
+
+    fn push(&self, record: FlightRecord) {
+        let mut records = self.records.lock().unwrap_or_else(PoisonError::into_inner);
+        self.evict(&mut records, record.at);
+        if records.len() >= self.config.capacity {
+            records.pop_front();
+        }
+
+        records.push_back(record);
+    }
+
+    fn evict(&self, records: &mut VecDeque<FlightRecord>, now: DateTime<Utc>) {
+        let window =
+            chrono::Duration::from_std(self.config.window).unwrap_or(chrono::Duration::MAX);
+        while records
+            .front()
+            .is_some_and(|record| now.signed_duration_since(record.at) > window)
+        {
+            records.pop_front();
+        }
+    }
+
+    /// Takes the events recorded within the window as breadcrumbs, if the error should have them attached.
+    /// Once taken, events are not attached to any later errors.
+    pub fn take(
+        &self,
+        error: &dyn std::error::Error,
+        fields: &HashMap<&'static str, String>,
+    ) -> Vec<Breadcrumb> {
+        if !(self.config.trigger)(error, fields) {
+            return Vec::new();
+        }
+
+        let mut records = self.records.lock().unwrap_or_else(PoisonError::into_inner);
+        self.evict(&mut records, Utc::now());
+        records
+            .drain(..)
+            .map(|record| Breadcrumb {
+                category: format!("flight_recorder.{}", record.level.as_str().to_lowercase())
+                    .into(),
+                message: format!(
+                    "{} {}: {}",
+                    record
+                        .at
+                        .to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+                    record.target,
+                    record.message
+                ),
+            })
+            .collect()
+    }
+}
+
+/// A [`Layer`] which records every event into the flight recorder.
+///
+/// This is intended to be registered using [`attach_verbose_layer`](crate::layers::attach_verbose_layer).
+struct FlightRecorderLayer(Arc<FlightRecording>);
+
+impl<S: Subscriber> Layer<S> for FlightRecorderLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let mut message = MessageCollector::default();
+        event.record(&mut message);
+
+        self.0.push(FlightRecord {
+            at: Utc::now(),
+            level: *event.metadata().level(),
+            target: event.metadata().target(),
+            message: message.0,
+        });
+    }
+
+    fn max_level_hint(&self) -> Option<LevelFilter> {
+        Some(LevelFilter::TRACE)
+    }
+}
+
+/// Formats an event's message, followed by its other fields as `key=value` pairs.
+#[derive(Default)]
+struct MessageCollector(String);
+
+impl Visit for MessageCollector {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            self.0.insert_str(0, &format!("{value:?}"));
+        } else {
+            let _ = write!(self.0, " {}={:?}", field.name(), value);
+        }
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.0.insert_str(0, value);
+        } else {
+            let _ = write!(self.0, " {}={}", field.name(), value);
+        }
+    }
+}
+
+impl Session {
+    /// Enables the flight recorder, which keeps the events emitted within the last few seconds in memory (at every
+    /// level, regardless of the level your telemetry is exported at) and attaches them to the next error which is
+    /// recorded, as [`Breadcrumb`]s with a `flight_recorder.{level}` category.
+    ///
+    /// Events are only recorded while a tracing subscriber installed by this library (such as the one installed
+    /// by the `OpenTelemetry` battery) is active. The flight recorder may only be enabled once per session.
+    ///
+    /// ## Example
+    /// ```no_run
+    /// use std::time::Duration;
+    /// use tracing_batteries::{Session, Sentry, FlightRecorder};
+    ///
+    /// let session = Session::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+    ///   .with_battery(Sentry::new("https://yourdsn@sentry.example.com"))
+    ///   .with_flight_recorder(FlightRecorder::new(Duration::from_secs(30)));
+    ///
+    /// session.shutdown();
+    /// ```
+    pub fn with_flight_recorder(self, recorder: FlightRecorder) -> Self {
+        let recording = Arc::new(FlightRecording::new(recorder));
+        if self.recorder.set(recording.clone()).is_ok() {
+            crate::layers::attach_verbose_layer(FlightRecorderLayer(recording));
+        }
+
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tracing_subscriber::layer::SubscriberExt;
+
+    use super::*;
+
+    #[test]
+    fn records_recent_events() {
+        let recording = Arc::new(FlightRecording::new(
+            FlightRecorder::new(Duration::from_secs(60))
+                .with_capacity(2)
+                .with_trigger(|_, fields| fields.contains_key("critical")),
+        ));
+
+        let subscriber =
+            tracing_subscriber::registry().with(FlightRecorderLayer(recording.clone()));
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!("Starting export");
+            tracing::trace!(pages = 3, "Rendering pages");
+            tracing::debug!(path = "/tmp/export.pdf", "Writing file");
+        });
+
+        let error = std::io::Error::other("export failed");
+        assert!(recording.take(&error, &HashMap::new()).is_empty());
+
+        let breadcrumbs = recording.take(&error, &HashMap::from([("critical", "yes".into())]));
+        assert_eq!(breadcrumbs.len(), 2);
+        assert_eq!(breadcrumbs[0].category, "flight_recorder.trace");
+        assert!(breadcrumbs[0].message.ends_with("Rendering pages pages=3"));
+        assert!(breadcrumbs[1]
+            .message
+            .ends_with("Writing file path=/tmp/export.pdf"));
+
+        assert!(recording
+            .take(&error, &HashMap::from([("critical", "yes".into())]))
+            .is_empty());
+    }
+}
@@ -0,0 +1,342 @@
+use std::{
+    any::TypeId,
+    collections::HashMap,
+    sync::{Mutex, PoisonError},
+};
+
+use tracing::{
+    span::{self, Attributes, Id},
+    subscriber::Interest,
+    Event, Metadata, Subscriber,
+};
+use tracing_subscriber::{
+    layer::Context,
+    registry::{LookupSpan, SpanRef},
+    Layer,
+};
+
+/// A [`Layer`] which protects the wrapped OpenTelemetry layer from pathological traces (such as those produced by
+/// runaway recursion) by limiting how many spans each trace may contain, and how deeply they may be nested.
+///
+/// Spans beyond either limit (and therefore all of their descendants) are not passed to the wrapped layer. The
+/// limits are evaluated once the span's parent is known, so spans created with an explicit parent are measured
+/// against that parent rather than the span which happened to be current. Each dropped span is counted against the `opentelemetry` exporter in the
+/// [`diagnostics`](crate::diagnostics) report, and a warning describing how many spans were dropped is emitted
+/// once the trace's root span closes. Events recorded within a dropped span are not attached to the trace.
+pub(crate) struct TraceGuardrails<L> {
+    inner: L,
+    max_spans: Option<usize>,
+    max_depth: Option<usize>,
+    traces: Mutex<HashMap<Id, TraceState>>,
+}
+
+#[derive(Default)]
+struct TraceState {
+    spans: usize,
+    dropped: usize,
+}
+
+/// Marks a span which exceeded the limits and was not passed to the wrapped layer.
+struct Dropped;
+
+impl<L> TraceGuardrails<L> {
+    pub fn new(inner: L, max_spans: Option<usize>, max_depth: Option<usize>) -> Self {
+        Self {
+            inner,
+            max_spans,
+            max_depth,
+            traces: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Determines whether a span nested at the provided depth, in a trace which already contains the provided
+    /// number of spans, falls within the limits.
+    fn allows(&self, spans: usize, depth: usize) -> bool {
+        self.max_spans.map_or(true, |max| spans < max)
+            && self.max_depth.map_or(true, |max| depth <= max)
+    }
+}
+
+fn is_dropped<S>(id: &Id, ctx: &Context<'_, S>) -> bool
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    ctx.span(id)
+        .is_some_and(|span| span.extensions().get::<Dropped>().is_some())
+}
+
+/// Returns the closest ancestor of the span which was passed to the wrapped layer, if any.
+fn exported_parent<'a, S>(span: &SpanRef<'a, S>) -> Option<SpanRef<'a, S>>
+where
+    S: Subscriber + for<'b> LookupSpan<'b>,
+{
+    span.scope()
+        .skip(1)
+        .find(|ancestor| ancestor.extensions().get::<Dropped>().is_none())
+}
+
+impl<S, L> Layer<S> for TraceGuardrails<L>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    L: Layer<S>,
+{
+    fn on_layer(&mut self, subscriber: &mut S) {
+        self.inner.on_layer(subscriber);
+    }
+
+    fn register_callsite(&self, metadata: &'static Metadata<'static>) -> Interest {
+        self.inner.register_callsite(metadata)
+    }
+
+    fn enabled(&self, metadata: &Metadata<'_>, ctx: Context<'_, S>) -> bool {
+        self.inner.enabled(metadata, ctx)
+    }
+
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        // The registry has already resolved the span's parent (whether it was explicit or contextual), so the limits
+        // are measured against it. Only the parents of dropped spans can themselves have been dropped, in which case
+        // the span exceeds the limits too.
+        let Some(span) = ctx.span(id) else {
+            return self.inner.on_new_span(attrs, id, ctx);
+        };
+
+        // Root spans are always exported, while the limits of nested spans depend on the trace they belong to.
+        let Some(parent) = exported_parent(&span) else {
+            self.traces
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .entry(id.clone())
+                .or_default()
+                .spans += 1;
+            return self.inner.on_new_span(attrs, id, ctx);
+        };
+
+        let depth = parent
+            .scope()
+            .filter(|ancestor| ancestor.extensions().get::<Dropped>().is_none())
+            .count()
+            + 1;
+        let root = parent
+            .scope()
+            .from_root()
+            .next()
+            .map(|root| root.id())
+            .unwrap_or_else(|| parent.id());
+
+        let allowed = match self
+            .traces
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get_mut(&root)
+        {
+            Some(trace) if self.allows(trace.spans, depth) => {
+                trace.spans += 1;
+                true
+            }
+            Some(trace) => {
+                trace.dropped += 1;
+                false
+            }
+            None => true,
+        };
+
+        if allowed {
+            self.inner.on_new_span(attrs, id, ctx);
+        } else {
+            span.extensions_mut().insert(Dropped);
+            crate::diagnostics::record_dropped("opentelemetry");
+        }
+    }
+
+    fn on_record(&self, id: &Id, values: &span::Record<'_>, ctx: Context<'_, S>) {
+        if !is_dropped(id, &ctx) {
+            self.inner.on_record(id, values, ctx);
+        }
+    }
+
+    fn on_follows_from(&self, id: &Id, follows: &Id, ctx: Context<'_, S>) {
+        if !is_dropped(id, &ctx) && !is_dropped(follows, &ctx) {
+            self.inner.on_follows_from(id, follows, ctx);
+        }
+    }
+
+    fn event_enabled(&self, event: &Event<'_>, ctx: Context<'_, S>) -> bool {
+        self.inner.event_enabled(event, ctx)
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        self.inner.on_event(event, ctx);
+    }
+
+    fn on_enter(&self, id: &Id, ctx: Context<'_, S>) {
+        if !is_dropped(id, &ctx) {
+            self.inner.on_enter(id, ctx);
+        }
+    }
+
+    fn on_exit(&self, id: &Id, ctx: Context<'_, S>) {
+        if !is_dropped(id, &ctx) {
+            self.inner.on_exit(id, ctx);
+        }
+    }
+
+    fn on_close(&self, id: Id, ctx: Context<'_, S>) {
+        let trace = self
+            .traces
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(&id);
+
+        if let Some(trace) = trace.filter(|trace| trace.dropped > 0) {
+            let name = ctx
+                .metadata(&id)
+                .map(|metadata| metadata.name())
+                .unwrap_or_default();
+            tracing::warn!(
+                span = name,
+                spans = trace.spans,
+                dropped = trace.dropped,
+                "The '{}' trace exceeded its span limits, {} of its spans were not exported.",
+                name,
+                trace.dropped
+            );
+        }
+
+        if !is_dropped(&id, &ctx) {
+            self.inner.on_close(id, ctx);
+        }
+    }
+
+    fn on_id_change(&self, old: &Id, new: &Id, ctx: Context<'_, S>) {
+        self.inner.on_id_change(old, new, ctx);
+    }
+
+    // Forwarding downcasts allows the OpenTelemetry layer to be found through this one, which is how
+    // `OpenTelemetrySpanExt::context` reads the trace context of a span.
+    #[doc(hidden)]
+    unsafe fn downcast_raw(&self, id: TypeId) -> Option<*const ()> {
+        if id == TypeId::of::<Self>() {
+            Some(self as *const Self as *const ())
+        } else {
+            self.inner.downcast_raw(id)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use tracing_subscriber::{layer::SubscriberExt, Registry};
+
+    use super::*;
+
+    #[test]
+    fn enforces_limits() {
+        let guardrails = TraceGuardrails::new((), Some(3), Some(2));
+        assert!(guardrails.allows(1, 2));
+        assert!(!guardrails.allows(1, 3));
+        assert!(!guardrails.allows(3, 1));
+
+        let unlimited = TraceGuardrails::new((), None, None);
+        assert!(unlimited.allows(1_000_000, 1_000));
+    }
+
+    #[test]
+    fn drops_deeply_nested_spans() {
+        let recorded = RecordingLayer::default();
+        let subscriber =
+            Registry::default().with(TraceGuardrails::new(recorded.clone(), None, Some(2)));
+
+        tracing::subscriber::with_default(subscriber, || {
+            let root = tracing::info_span!("root");
+            let _root = root.enter();
+            let child = tracing::info_span!("child");
+            let _child = child.enter();
+            let grandchild = tracing::info_span!("grandchild");
+            let _grandchild = grandchild.enter();
+            let _nested = tracing::info_span!("nested");
+
+            // Measured against its explicit parent, rather than the (dropped) current span.
+            let _sibling = tracing::info_span!(parent: &root, "sibling");
+        });
+
+        assert_eq!(
+            recorded.spans(),
+            [
+                ("root", None),
+                ("child", Some("root")),
+                ("sibling", Some("root"))
+            ]
+        );
+        assert_eq!(recorded.entered(), ["root", "child"]);
+    }
+
+    #[test]
+    fn drops_spans_beyond_the_trace_limit() {
+        let recorded = RecordingLayer::default();
+        let subscriber =
+            Registry::default().with(TraceGuardrails::new(recorded.clone(), Some(3), None));
+
+        tracing::subscriber::with_default(subscriber, || {
+            let root = tracing::info_span!("root");
+            let _root = root.enter();
+            for _ in 0..5 {
+                let _child = tracing::info_span!("child").entered();
+            }
+
+            // Each trace has its own budget.
+            let _other = tracing::info_span!(parent: None, "other");
+        });
+
+        assert_eq!(
+            recorded.spans(),
+            [
+                ("root", None),
+                ("child", Some("root")),
+                ("child", Some("root")),
+                ("other", None)
+            ]
+        );
+    }
+
+    type RecordedSpan = (&'static str, Option<&'static str>);
+
+    #[derive(Clone, Default)]
+    struct RecordingLayer {
+        spans: Arc<Mutex<Vec<RecordedSpan>>>,
+        entered: Arc<Mutex<Vec<&'static str>>>,
+    }
+
+    impl RecordingLayer {
+        fn spans(&self) -> Vec<RecordedSpan> {
+            self.spans.lock().unwrap().clone()
+        }
+
+        fn entered(&self) -> Vec<&'static str> {
+            self.entered.lock().unwrap().clone()
+        }
+    }
+
+    impl<S> Layer<S> for RecordingLayer
+    where
+        S: Subscriber + for<'a> LookupSpan<'a>,
+    {
+        fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+            let parent = ctx
+                .span(id)
+                .and_then(|span| span.parent())
+                .map(|parent| parent.name());
+            self.spans
+                .lock()
+                .unwrap()
+                .push((attrs.metadata().name(), parent));
+        }
+
+        fn on_enter(&self, id: &Id, ctx: Context<'_, S>) {
+            if let Some(span) = ctx.span(id) {
+                self.entered.lock().unwrap().push(span.name());
+            }
+        }
+    }
+}
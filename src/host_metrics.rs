@@ -0,0 +1,179 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc, Mutex, OnceLock, PoisonError,
+    },
+    thread::JoinHandle,
+    time::Duration,
+};
+
+use sysinfo::{Disks, Networks, System};
+
+use crate::{Battery, BatteryBuilder, Metadata, Session, WeakSession};
+
+/// A battery which periodically exports host-level CPU, memory, disk, and network metrics.
+///
+/// <div class="warning">
+///
+/// This battery requires the `host-metrics` feature to be enabled.
+///
+/// </div>
+///
+/// Measurements are recorded through the session's metrics facade (see [`Session::gauge`] and [`Session::counter`]),
+/// so they will be exported by any battery which supports metrics. This is useful for single-binary appliances
+/// which are deployed without a separate host monitoring agent. The following instruments are recorded:
+///
+/// - `host.cpu.utilization` (gauge, ratio between 0 and 1)
+/// - `host.memory.total` and `host.memory.used` (gauges, bytes)
+/// - `host.disk.total` and `host.disk.available` (gauges, bytes, with `device` and `mountpoint` attributes)
+/// - `host.network.io` (counter, bytes, with `interface` and `direction` attributes)
+///
+/// ## Example
+/// ```no_run
+/// use std::time::Duration;
+/// use tracing_batteries::{Session, OpenTelemetry, HostMetrics};
+///
+/// let session = Session::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+///   .with_battery(OpenTelemetry::new("localhost:4317"))
+///   .with_battery(HostMetrics::new().with_interval(Duration::from_secs(30)));
+///
+/// session.shutdown();
+/// ```
+pub struct HostMetrics {
+    interval: Duration,
+}
+
+impl HostMetrics {
+    /// Creates a new host metrics collector which records measurements every 60 seconds.
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        Self {
+            interval: Duration::from_secs(60),
+        }
+    }
+
+    /// Configures how frequently host metrics are collected.
+    pub fn with_interval(self, interval: Duration) -> Self {
+        Self { interval }
+    }
+}
+
+impl BatteryBuilder for HostMetrics {
+    fn setup(self, _metadata: &Metadata, enabled: Arc<AtomicBool>) -> Box<dyn Battery> {
+        let session = Arc::new(OnceLock::<WeakSession>::new());
+        let (stop, stopped) = mpsc::channel::<()>();
+
+        let interval = self.interval;
+        let collector_session = session.clone();
+        let thread = std::thread::Builder::new()
+            .name("tracing-batteries-host-metrics".into())
+            .spawn(move || {
+                let mut collector = HostCollector::new();
+                while let Err(mpsc::RecvTimeoutError::Timeout) = stopped.recv_timeout(interval) {
+                    if !enabled.load(Ordering::Relaxed) {
+                        continue;
+                    }
+
+                    match collector_session.get().map(|session| session.upgrade()) {
+                        Some(Some(session)) => collector.collect(&session),
+                        Some(None) => break,
+                        None => {}
+                    }
+                }
+            })
+            .ok();
+
+        Box::new(HostMetricsBattery {
+            session,
+            stop: Mutex::new(Some(stop)),
+            thread: Mutex::new(thread),
+        })
+    }
+}
+
+struct HostMetricsBattery {
+    session: Arc<OnceLock<WeakSession>>,
+    stop: Mutex<Option<mpsc::Sender<()>>>,
+    thread: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl Battery for HostMetricsBattery {
+    fn attached(&self, session: WeakSession) {
+        self.session.set(session).ok();
+    }
+
+    fn shutdown(&self) {
+        self.stop
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take();
+
+        if let Some(thread) = self
+            .thread
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take()
+        {
+            crate::worker::join(thread);
+        }
+    }
+}
+
+struct HostCollector {
+    system: System,
+    networks: Networks,
+}
+
+impl HostCollector {
+    fn new() -> Self {
+        let mut system = System::new();
+        system.refresh_cpu_usage();
+
+        Self {
+            system,
+            networks: Networks::new_with_refreshed_list(),
+        }
+    }
+
+    fn collect(&mut self, session: &Session) {
+        self.system.refresh_cpu_usage();
+        self.system.refresh_memory();
+
+        session
+            .gauge("host.cpu.utilization")
+            .set(self.system.global_cpu_usage() as f64 / 100.0);
+        session
+            .gauge("host.memory.total")
+            .set(self.system.total_memory() as f64);
+        session
+            .gauge("host.memory.used")
+            .set(self.system.used_memory() as f64);
+
+        let disk_total = session.gauge("host.disk.total");
+        let disk_available = session.gauge("host.disk.available");
+        for disk in Disks::new_with_refreshed_list().list() {
+            let device = disk.name().to_string_lossy();
+            let mountpoint = disk.mount_point().to_string_lossy();
+            let attributes = [
+                ("device", device.as_ref()),
+                ("mountpoint", mountpoint.as_ref()),
+            ];
+
+            disk_total.set_with(disk.total_space() as f64, &attributes);
+            disk_available.set_with(disk.available_space() as f64, &attributes);
+        }
+
+        self.networks.refresh();
+        let network_io = session.counter("host.network.io");
+        for (interface, data) in self.networks.iter() {
+            network_io.inc_with(
+                data.received(),
+                &[("interface", interface.as_str()), ("direction", "receive")],
+            );
+            network_io.inc_with(
+                data.transmitted(),
+                &[("interface", interface.as_str()), ("direction", "transmit")],
+            );
+        }
+    }
+}
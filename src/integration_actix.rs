@@ -0,0 +1,261 @@
+use std::{
+    future::{ready, Future, Ready},
+    pin::Pin,
+};
+
+use actix_web::{
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    http::header::HeaderMap,
+    Error,
+};
+use opentelemetry::propagation::Extractor;
+use tracing::Instrument;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+use crate::Session;
+
+/// An [actix-web](https://actix.rs) middleware which creates a server span for each request
+/// handled by your application.
+///
+/// <div class="warning">
+///
+/// This integration requires the `actix-web` feature to be enabled.
+///
+/// </div>
+///
+/// The middleware will extract any trace context propagated by the caller (using the globally
+/// configured OpenTelemetry propagator), create a server span annotated with the standard HTTP
+/// semantic convention attributes, and report any server errors to the [`Session`]. When telemetry
+/// has been disabled through [`Session::enable`], requests are passed through without instrumentation.
+///
+/// ## Example
+/// ```no_run
+/// use actix_web::{web, App, HttpServer};
+/// use tracing_batteries::{Session, OpenTelemetry, ActixTracing};
+///
+/// # async fn run() -> std::io::Result<()> {
+/// let session = Session::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+///   .with_battery(OpenTelemetry::new("localhost:4317"));
+///
+/// let middleware_session = session.clone();
+/// HttpServer::new(move || {
+///     App::new()
+///       .wrap(ActixTracing::new(&middleware_session))
+///       .route("/", web::get().to(|| async { "Hello, world!" }))
+///   })
+///   .bind(("127.0.0.1", 8080))?
+///   .run()
+///   .await?;
+///
+/// session.shutdown();
+/// # Ok(())
+/// # }
+/// ```
+pub struct ActixTracing {
+    session: Session,
+}
+
+impl ActixTracing {
+    /// Creates a new middleware which reports requests and errors to the provided [`Session`].
+    pub fn new(session: &Session) -> Self {
+        Self {
+            session: session.clone(),
+        }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for ActixTracing
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = ActixTracingMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(ActixTracingMiddleware {
+            service,
+            session: self.session.clone(),
+        }))
+    }
+}
+
+/// The service created by the [`ActixTracing`] middleware for each of your application's workers.
+pub struct ActixTracingMiddleware<S> {
+    service: S,
+    session: Session,
+}
+
+impl<S, B> Service<ServiceRequest> for ActixTracingMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        if !self.session.is_enabled() {
+            return Box::pin(self.service.call(req));
+        }
+
+        let parent = opentelemetry::global::get_text_map_propagator(|propagator| {
+            propagator.extract(&HeaderExtractor(req.headers()))
+        });
+
+        let route = req.match_pattern();
+        let (scheme, host) = {
+            let info = req.connection_info();
+            (info.scheme().to_string(), info.host().to_string())
+        };
+
+        let span = tracing::info_span!(
+            "HTTP request",
+            otel.name = %format!("{} {}", req.method(), route.as_deref().unwrap_or(req.path())),
+            otel.kind = "server",
+            otel.status_code = tracing::field::Empty,
+            http.request.method = %req.method(),
+            http.route = route.as_deref(),
+            http.response.status_code = tracing::field::Empty,
+            url.path = req.path(),
+            url.scheme = %scheme,
+            server.address = %host,
+            user_agent.original = req
+                .headers()
+                .get("user-agent")
+                .and_then(|value| value.to_str().ok()),
+        );
+        span.set_parent(parent);
+
+        let session = self.session.clone();
+        let response = span.in_scope(|| self.service.call(req));
+        let request_span = span.clone();
+
+        Box::pin(
+            async move {
+                let result = response.await;
+                match &result {
+                    Ok(response) => {
+                        request_span
+                            .record("http.response.status_code", response.status().as_u16());
+
+                        if response.status().is_server_error() {
+                            request_span.record("otel.status_code", "ERROR");
+                            if let Some(error) = response.response().error() {
+                                session.record_error(error);
+                            }
+                        }
+                    }
+                    Err(error) => {
+                        let status = error.as_response_error().status_code();
+                        request_span.record("http.response.status_code", status.as_u16());
+
+                        // Client errors (such as a 404) are the caller's problem rather than the server's, so
+                        // they aren't marked as failed spans or reported, per the HTTP semantic conventions.
+                        if status.is_server_error() {
+                            request_span.record("otel.status_code", "ERROR");
+                            session.record_error(error);
+                        }
+                    }
+                }
+
+                result
+            }
+            .instrument(span),
+        )
+    }
+}
+
+struct HeaderExtractor<'a>(&'a HeaderMap);
+
+impl Extractor for HeaderExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|value| value.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(|key| key.as_str()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{dev::fn_service, error, test, web, App, HttpResponse};
+
+    use super::*;
+    use crate::test_support::ErrorBattery;
+
+    #[test]
+    fn reports_server_errors() {
+        let battery = ErrorBattery::default();
+        let session = Session::new("actix-test", "1.0.0").with_battery(battery.clone());
+
+        actix_web::rt::System::new().block_on(async {
+            let app = test::init_service(
+                App::new()
+                    .wrap(ActixTracing::new(&session))
+                    .route(
+                        "/ok",
+                        web::get().to(|| async { HttpResponse::Ok().finish() }),
+                    )
+                    .route(
+                        "/missing",
+                        web::get().to(|| async {
+                            Err::<HttpResponse, _>(error::ErrorNotFound("missing"))
+                        }),
+                    )
+                    .route(
+                        "/broken",
+                        web::get().to(|| async {
+                            Err::<HttpResponse, _>(error::ErrorInternalServerError("broken"))
+                        }),
+                    ),
+            )
+            .await;
+
+            for (path, status) in [("/ok", 200), ("/missing", 404), ("/broken", 500)] {
+                let response =
+                    test::call_service(&app, test::TestRequest::get().uri(path).to_request()).await;
+                assert_eq!(response.status().as_u16(), status);
+            }
+        });
+
+        assert_eq!(battery.messages(), ["broken"]);
+    }
+
+    #[test]
+    fn ignores_client_errors_from_services() {
+        let battery = ErrorBattery::default();
+        let session = Session::new("actix-test", "1.0.0").with_battery(battery.clone());
+
+        actix_web::rt::System::new().block_on(async {
+            for (error, reported) in [
+                (error::ErrorNotFound("missing"), false),
+                (error::ErrorServiceUnavailable("unavailable"), true),
+            ] {
+                let error = std::cell::RefCell::new(Some(error));
+                let middleware = ActixTracing::new(&session)
+                    .new_transform(fn_service(move |_req: ServiceRequest| {
+                        let error = error.borrow_mut().take().unwrap();
+                        async move { Err::<ServiceResponse, _>(error) }
+                    }))
+                    .await
+                    .unwrap();
+
+                assert!(middleware
+                    .call(test::TestRequest::get().uri("/").to_srv_request())
+                    .await
+                    .is_err());
+                assert_eq!(battery.messages().len(), reported as usize);
+            }
+        });
+    }
+}
@@ -0,0 +1,233 @@
+use std::{
+    borrow::Cow,
+    sync::{atomic::AtomicBool, Arc},
+    time::Duration,
+};
+
+use serde_json::json;
+
+use crate::{
+    dispatcher::HttpDispatcher,
+    notify::{environment_label, NotificationSeverity, RateLimiter},
+    queue::DEFAULT_QUEUE_CAPACITY,
+    Battery, BatteryBuilder, DropPolicy, Envelope, EnvelopePayload, Metadata,
+};
+
+/// An [Apprise API](https://github.com/caronc/apprise-api) integration which fans notifications out to any
+/// of the dozens of services supported by Apprise (Telegram, Matrix, Pushover, Slack, email, etc.) whenever
+/// errors (or, optionally, custom events) are recorded.
+///
+/// <div class="warning">
+///
+/// This integration requires the `apprise` feature to be enabled.
+///
+/// </div>
+///
+/// By default, notifications are sent to the persistent configuration stored under the provided key on your
+/// Apprise API server. Alternatively, you may provide the Apprise URLs which should be notified directly using
+/// [`Apprise::with_url`], in which case the server's stateless `/notify` endpoint is used. At most 10 notifications
+/// are sent per minute.
+///
+/// ## Example
+/// ```no_run
+/// use tracing_batteries::{Session, Apprise};
+///
+/// let session = Session::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+///   .with_battery(Apprise::new("http://apprise.example.com:8000", "my-service")
+///     .with_tag("oncall"));
+///
+/// session.shutdown();
+/// ```
+pub struct Apprise {
+    server: Cow<'static, str>,
+    key: Cow<'static, str>,
+    urls: Vec<Cow<'static, str>>,
+    tags: Vec<Cow<'static, str>>,
+    min_severity: NotificationSeverity,
+    rate_limit: (usize, Duration),
+    queue_capacity: usize,
+    drop_policy: DropPolicy,
+}
+
+impl Apprise {
+    /// Configures the Apprise integration to notify the configuration stored under `key` on the provided server.
+    pub fn new<S: Into<Cow<'static, str>>, K: Into<Cow<'static, str>>>(server: S, key: K) -> Self {
+        Self {
+            server: server.into(),
+            key: key.into(),
+            urls: Vec::new(),
+            tags: Vec::new(),
+            min_severity: NotificationSeverity::Error,
+            rate_limit: (10, Duration::from_secs(60)),
+            queue_capacity: DEFAULT_QUEUE_CAPACITY,
+            drop_policy: DropPolicy::default(),
+        }
+    }
+
+    /// Adds an Apprise URL (e.g. `tgram://bottoken/ChatID`) which is notified directly, instead of using a stored configuration.
+    pub fn with_url<U: Into<Cow<'static, str>>>(mut self, url: U) -> Self {
+        self.urls.push(url.into());
+        self
+    }
+
+    /// Adds a tag which limits the services in the stored configuration that are notified.
+    pub fn with_tag<T: Into<Cow<'static, str>>>(mut self, tag: T) -> Self {
+        self.tags.push(tag.into());
+        self
+    }
+
+    /// Configures the minimum severity of telemetry which results in a notification.
+    pub fn with_min_severity(self, severity: NotificationSeverity) -> Self {
+        Self {
+            min_severity: severity,
+            ..self
+        }
+    }
+
+    /// Configures the maximum number of notifications which are sent within the provided window.
+    pub fn with_rate_limit(self, limit: usize, window: Duration) -> Self {
+        Self {
+            rate_limit: (limit, window),
+            ..self
+        }
+    }
+
+    /// Configures the maximum number of notifications which may be waiting to be sent (10,000 by default), and which
+    /// are dropped when the Apprise server falls behind a burst of errors.
+    pub fn with_queue_limits(self, capacity: usize, policy: DropPolicy) -> Self {
+        Self {
+            queue_capacity: capacity,
+            drop_policy: policy,
+            ..self
+        }
+    }
+}
+
+impl BatteryBuilder for Apprise {
+    fn setup(self, metadata: &Metadata, _enabled: Arc<AtomicBool>) -> Box<dyn Battery> {
+        let server = self.server.trim_end_matches('/');
+        let url = if self.urls.is_empty() {
+            format!("{server}/notify/{}", self.key)
+        } else {
+            format!("{server}/notify")
+        };
+
+        Box::new(AppriseBattery {
+            url,
+            urls: self.urls.iter().map(|url| url.to_string()).collect(),
+            tags: self.tags.iter().map(|tag| tag.to_string()).collect(),
+            min_severity: self.min_severity,
+            environment: environment_label(metadata),
+            limiter: RateLimiter::new(self.rate_limit.0, self.rate_limit.1),
+            dispatcher: HttpDispatcher::new("apprise")
+                .with_limits(self.queue_capacity, self.drop_policy),
+        })
+    }
+}
+
+struct AppriseBattery {
+    url: String,
+    urls: Vec<String>,
+    tags: Vec<String>,
+    min_severity: NotificationSeverity,
+    environment: Option<String>,
+    limiter: RateLimiter,
+    dispatcher: HttpDispatcher,
+}
+
+impl Battery for AppriseBattery {
+    fn record_envelope(&self, envelope: &Envelope) {
+        let severity = match NotificationSeverity::of(envelope) {
+            Some(severity) if severity >= self.min_severity => severity,
+            _ => return,
+        };
+
+        if !self.limiter.allow() {
+            return;
+        }
+
+        let mut body = build_notification(envelope, severity, self.environment.as_deref());
+        if !self.urls.is_empty() {
+            body["urls"] = self.urls.join(",").into();
+        }
+        if !self.tags.is_empty() {
+            body["tag"] = self.tags.join(",").into();
+        }
+
+        let body = body.to_string();
+        let url = self.url.clone();
+        self.dispatcher
+            .dispatch(envelope.importance, move |client| {
+                client
+                    .post(url)
+                    .header("Content-Type", "application/json")
+                    .body(body)
+            });
+    }
+
+    fn shutdown(&self) {
+        self.dispatcher.shutdown();
+    }
+}
+
+fn build_notification(
+    envelope: &Envelope,
+    severity: NotificationSeverity,
+    environment: Option<&str>,
+) -> serde_json::Value {
+    let (kind, title) = match severity {
+        NotificationSeverity::Error => ("failure", format!("Error in {}", envelope.service)),
+        NotificationSeverity::Info => ("info", envelope.service.to_string()),
+    };
+
+    let mut body = match &envelope.payload {
+        EnvelopePayload::Error {
+            message, causes, ..
+        } => std::iter::once(message.clone())
+            .chain(causes.iter().map(|cause| format!("Caused by: {cause}")))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        _ => envelope.summary(),
+    };
+
+    body.push_str(&format!("\n\nVersion: {}", envelope.version));
+    if let Some(environment) = environment {
+        body.push_str(&format!("\nEnvironment: {environment}"));
+    }
+
+    json!({
+        "title": title,
+        "body": body,
+        "type": kind,
+        "format": "text",
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Session;
+
+    #[test]
+    fn error_notification() {
+        let metadata = Session::new("example", "0.0.1");
+        let envelope = Envelope::new(
+            &metadata,
+            EnvelopePayload::Error {
+                message: "Failed to connect".into(),
+                causes: vec!["Connection refused".into()],
+                fields: Default::default(),
+                backtrace: None,
+            },
+        );
+
+        let notification =
+            build_notification(&envelope, NotificationSeverity::Error, Some("production"));
+        assert_eq!(notification["type"], "failure");
+        assert_eq!(notification["title"], "Error in example");
+        assert_eq!(
+            notification["body"],
+            "Failed to connect\nCaused by: Connection refused\n\nVersion: 0.0.1\nEnvironment: production"
+        );
+    }
+}
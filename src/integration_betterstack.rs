@@ -0,0 +1,137 @@
+use std::{
+    borrow::Cow,
+    sync::{atomic::AtomicBool, Arc},
+    time::Duration,
+};
+
+use crate::{
+    batcher::BatchDispatcher, queue::DEFAULT_QUEUE_CAPACITY, Battery, BatteryBuilder, DropPolicy,
+    Envelope, EnvelopePayload, Metadata,
+};
+
+/// A [Better Stack](https://betterstack.com/logs) (Logtail) integration which ships errors, custom events,
+/// and page views to a Better Stack source as structured log events.
+///
+/// <div class="warning">
+///
+/// This integration requires the `betterstack` feature to be enabled.
+///
+/// </div>
+///
+/// Events are authenticated using the source token and delivered in batches, which are sent whenever
+/// 100 events have been collected or every 5 seconds (whichever happens first). Each log event includes
+/// the `dt`, `message`, and `level` fields expected by Better Stack, along with the full [`Envelope`].
+///
+/// ## Example
+/// ```no_run
+/// use tracing_batteries::{Session, BetterStack};
+///
+/// let session = Session::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+///   .with_battery(BetterStack::new("your-source-token"));
+///
+/// session.shutdown();
+/// ```
+pub struct BetterStack {
+    source_token: Cow<'static, str>,
+    ingesting_host: Cow<'static, str>,
+    batch_size: usize,
+    flush_interval: Duration,
+    queue_capacity: usize,
+    drop_policy: DropPolicy,
+}
+
+impl BetterStack {
+    /// Configures the Better Stack integration using the provided source token.
+    pub fn new<T: Into<Cow<'static, str>>>(source_token: T) -> Self {
+        Self {
+            source_token: source_token.into(),
+            ingesting_host: "in.logs.betterstack.com".into(),
+            batch_size: 100,
+            flush_interval: Duration::from_secs(5),
+            queue_capacity: DEFAULT_QUEUE_CAPACITY,
+            drop_policy: DropPolicy::default(),
+        }
+    }
+
+    /// Configures the ingesting host shown on your source's configuration page (e.g. `s1234.eu-nbg-2.betterstackdata.com`).
+    pub fn with_ingesting_host<H: Into<Cow<'static, str>>>(self, host: H) -> Self {
+        Self {
+            ingesting_host: host.into(),
+            ..self
+        }
+    }
+
+    /// Configures the maximum number of events which are sent in a single request, and how frequently
+    /// partial batches are flushed.
+    pub fn with_batching(self, batch_size: usize, flush_interval: Duration) -> Self {
+        Self {
+            batch_size: batch_size.max(1),
+            flush_interval,
+            ..self
+        }
+    }
+
+    /// Configures the maximum number of log entries which may be waiting to be sent (10,000 by default), and which are
+    /// dropped when the ingesting host falls behind a burst of activity.
+    pub fn with_queue_limits(self, capacity: usize, policy: DropPolicy) -> Self {
+        Self {
+            queue_capacity: capacity,
+            drop_policy: policy,
+            ..self
+        }
+    }
+}
+
+impl BatteryBuilder for BetterStack {
+    fn setup(self, _metadata: &Metadata, _enabled: Arc<AtomicBool>) -> Box<dyn Battery> {
+        let url = format!("https://{}", self.ingesting_host.trim_end_matches('/'));
+        let authorization = format!("Bearer {}", self.source_token);
+
+        Box::new(BetterStackBattery {
+            dispatcher: BatchDispatcher::new(
+                "betterstack",
+                self.batch_size,
+                self.flush_interval,
+                0,
+                move |client, events: &[serde_json::Value]| {
+                    client
+                        .post(&url)
+                        .header("Authorization", &authorization)
+                        .header("Content-Type", "application/json")
+                        .body(serde_json::Value::from(events).to_string())
+                },
+            )
+            .with_limits(self.queue_capacity, self.drop_policy),
+        })
+    }
+}
+
+struct BetterStackBattery {
+    dispatcher: BatchDispatcher<serde_json::Value>,
+}
+
+impl Battery for BetterStackBattery {
+    fn record_envelope(&self, envelope: &Envelope) {
+        let Ok(serde_json::Value::Object(mut event)) = serde_json::to_value(envelope) else {
+            return;
+        };
+
+        event.insert("dt".into(), envelope.timestamp.to_rfc3339().into());
+        event.insert("message".into(), envelope.summary().into());
+        event.insert(
+            "level".into(),
+            match envelope.payload {
+                EnvelopePayload::Error { .. } => "error",
+                _ => "info",
+            }
+            .into(),
+        );
+
+        self.dispatcher
+            .push(envelope.importance, serde_json::Value::Object(event));
+    }
+
+    fn shutdown(&self) {
+        self.dispatcher.shutdown();
+    }
+}
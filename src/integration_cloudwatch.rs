@@ -0,0 +1,343 @@
+use std::{
+    borrow::Cow,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use serde_json::json;
+
+use crate::{
+    aws::{self, AwsSigner},
+    batcher::BatchDispatcher,
+    queue::DEFAULT_QUEUE_CAPACITY,
+    Battery, BatteryBuilder, DropPolicy, Envelope, Importance, Metadata, Metric, MetricKind,
+};
+
+/// An [Amazon CloudWatch](https://aws.amazon.com/cloudwatch/) integration which writes errors, custom events and
+/// page views to a CloudWatch Logs log stream, and publishes metrics using the
+/// [Embedded Metric Format](https://docs.aws.amazon.com/AmazonCloudWatch/latest/monitoring/CloudWatch_Embedded_Metric_Format_Specification.html).
+///
+/// <div class="warning">
+///
+/// This integration requires the `cloudwatch` feature to be enabled.
+///
+/// </div>
+///
+/// Requests are signed using credentials from the standard AWS credential chain: the `AWS_ACCESS_KEY_ID`,
+/// `AWS_SECRET_ACCESS_KEY` and `AWS_SESSION_TOKEN` environment variables (as provided to Lambda functions), the
+/// shared credentials file, the ECS container credentials endpoint, or the EC2 instance metadata service. The
+/// region is read from `AWS_REGION` (or `AWS_DEFAULT_REGION`) unless configured using [`CloudWatch::with_region`].
+///
+/// The log group must already exist, while the log stream (which defaults to `{service}/{hostname}`) is created
+/// automatically. Each envelope is written as a JSON log event, and each metric as an EMF log event in the
+/// configured namespace (defaulting to your service name) with a `service` dimension alongside the metric's
+/// attributes, which CloudWatch extracts into metrics without any additional infrastructure.
+///
+/// ## Example
+/// ```no_run
+/// use tracing_batteries::{Session, CloudWatch};
+///
+/// let session = Session::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+///   .with_battery(CloudWatch::new("/my-team/my-service")
+///     .with_namespace("MyTeam/MyService"));
+///
+/// session.shutdown();
+/// ```
+pub struct CloudWatch {
+    log_group: Cow<'static, str>,
+    log_stream: Option<Cow<'static, str>>,
+    region: Option<Cow<'static, str>>,
+    namespace: Option<Cow<'static, str>>,
+    batch_size: usize,
+    flush_interval: Duration,
+    queue_capacity: usize,
+    drop_policy: DropPolicy,
+}
+
+impl CloudWatch {
+    /// Configures the CloudWatch integration to write to the provided log group.
+    pub fn new<G: Into<Cow<'static, str>>>(log_group: G) -> Self {
+        Self {
+            log_group: log_group.into(),
+            log_stream: None,
+            region: None,
+            namespace: None,
+            batch_size: 1000,
+            flush_interval: Duration::from_secs(5),
+            queue_capacity: DEFAULT_QUEUE_CAPACITY,
+            drop_policy: DropPolicy::default(),
+        }
+    }
+
+    /// Overrides the log stream which events are written to.
+    pub fn with_log_stream<S: Into<Cow<'static, str>>>(self, log_stream: S) -> Self {
+        Self {
+            log_stream: Some(log_stream.into()),
+            ..self
+        }
+    }
+
+    /// Overrides the AWS region which hosts the log group.
+    pub fn with_region<R: Into<Cow<'static, str>>>(self, region: R) -> Self {
+        Self {
+            region: Some(region.into()),
+            ..self
+        }
+    }
+
+    /// Overrides the CloudWatch namespace which metrics are published to.
+    pub fn with_namespace<N: Into<Cow<'static, str>>>(self, namespace: N) -> Self {
+        Self {
+            namespace: Some(namespace.into()),
+            ..self
+        }
+    }
+
+    /// Configures the maximum number of log events which are written in a single request, and how frequently
+    /// partial batches are flushed.
+    pub fn with_batching(self, batch_size: usize, flush_interval: Duration) -> Self {
+        Self {
+            // PutLogEvents accepts at most 10,000 events per request.
+            batch_size: batch_size.clamp(1, 10_000),
+            flush_interval,
+            ..self
+        }
+    }
+
+    /// Configures the maximum number of log events which may be waiting to be sent (10,000 by default), and which are
+    /// dropped when CloudWatch falls behind a burst of activity.
+    pub fn with_queue_limits(self, capacity: usize, policy: DropPolicy) -> Self {
+        Self {
+            queue_capacity: capacity,
+            drop_policy: policy,
+            ..self
+        }
+    }
+}
+
+impl BatteryBuilder for CloudWatch {
+    fn setup(self, metadata: &Metadata, _enabled: Arc<AtomicBool>) -> Box<dyn Battery> {
+        let region = self
+            .region
+            .map(|region| region.to_string())
+            .unwrap_or_else(aws::default_region);
+        let url = format!("{}/", aws::endpoint("logs", &region));
+        let signer = AwsSigner::new("logs", region);
+
+        let log_group = self.log_group.to_string();
+        let log_stream = self
+            .log_stream
+            .map(|stream| stream.to_string())
+            .unwrap_or_else(|| {
+                let host =
+                    std::env::var("HOSTNAME").unwrap_or_else(|_| std::process::id().to_string());
+                format!("{}/{host}", metadata.service)
+            });
+        let stream_created = AtomicBool::new(false);
+
+        let send = move |client: &reqwest::blocking::Client, target: &str, body: String| {
+            signer.request(
+                client,
+                reqwest::Method::POST,
+                &url,
+                &[
+                    ("Content-Type", "application/x-amz-json-1.1"),
+                    ("X-Amz-Target", target),
+                ],
+                body.into_bytes(),
+            )
+        };
+
+        Box::new(CloudWatchBattery {
+            service: metadata.service.to_string(),
+            namespace: self
+                .namespace
+                .map(|namespace| namespace.to_string())
+                .unwrap_or_else(|| metadata.service.to_string()),
+            dispatcher: BatchDispatcher::new(
+                "cloudwatch",
+                self.batch_size,
+                self.flush_interval,
+                3,
+                move |client, events: &[LogEvent]| {
+                    if !stream_created.swap(true, Ordering::Relaxed) {
+                        // This fails harmlessly if the stream already exists.
+                        send(
+                            client,
+                            "Logs_20140328.CreateLogStream",
+                            json!({
+                                "logGroupName": log_group,
+                                "logStreamName": log_stream,
+                            })
+                            .to_string(),
+                        )
+                        .send()
+                        .ok();
+                    }
+
+                    send(
+                        client,
+                        "Logs_20140328.PutLogEvents",
+                        put_log_events(&log_group, &log_stream, events),
+                    )
+                },
+            )
+            .with_limits(self.queue_capacity, self.drop_policy),
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct LogEvent {
+    timestamp: i64,
+    message: String,
+}
+
+/// Builds a `PutLogEvents` request body, whose events must be in chronological order.
+fn put_log_events(log_group: &str, log_stream: &str, events: &[LogEvent]) -> String {
+    let mut events = events.to_vec();
+    events.sort_by_key(|event| event.timestamp);
+
+    json!({
+        "logGroupName": log_group,
+        "logStreamName": log_stream,
+        "logEvents": events
+            .iter()
+            .map(|event| json!({ "timestamp": event.timestamp, "message": event.message }))
+            .collect::<Vec<_>>(),
+    })
+    .to_string()
+}
+
+struct CloudWatchBattery {
+    service: String,
+    namespace: String,
+    dispatcher: BatchDispatcher<LogEvent>,
+}
+
+impl Battery for CloudWatchBattery {
+    fn record_envelope(&self, envelope: &Envelope) {
+        self.dispatcher.push(
+            envelope.importance,
+            LogEvent {
+                timestamp: envelope.timestamp.timestamp_millis(),
+                message: envelope.to_json(),
+            },
+        );
+    }
+
+    fn record_metric(&self, metric: &Metric) {
+        let timestamp = chrono::Utc::now().timestamp_millis();
+        self.dispatcher.push(
+            Importance::Normal,
+            LogEvent {
+                timestamp,
+                message: format_emf(metric, &self.namespace, &self.service, timestamp),
+            },
+        );
+    }
+
+    fn shutdown(&self) {
+        self.dispatcher.shutdown();
+    }
+}
+
+/// Formats a [`Metric`] as an Embedded Metric Format log event, using its attributes as dimensions.
+///
+/// Aggregated measurements are reported using EMF's `Values` and `Counts` representation, so that a bucket of
+/// observations is sent as a single weighted value.
+fn format_emf(metric: &Metric, namespace: &str, service: &str, timestamp: i64) -> String {
+    let mut dimensions = vec!["service"];
+    let mut event = json!({ "service": service });
+
+    for (key, value) in metric.attributes {
+        if !value.is_empty() && *key != "service" {
+            dimensions.push(*key);
+            event[*key] = json!(value);
+        }
+    }
+
+    let mut definition = json!({ "Name": metric.name });
+    if metric.kind == MetricKind::Counter {
+        definition["Unit"] = json!("Count");
+    }
+
+    event[metric.name] = match metric.count {
+        0 | 1 => json!(metric.value),
+        count => json!({ "Values": [metric.value], "Counts": [count] }),
+    };
+    event["_aws"] = json!({
+        "Timestamp": timestamp,
+        "CloudWatchMetrics": [{
+            "Namespace": namespace,
+            "Dimensions": [dimensions],
+            "Metrics": [definition],
+        }],
+    });
+
+    event.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn embedded_metric_format() {
+        let metric = Metric {
+            name: "http.requests",
+            kind: MetricKind::Counter,
+            value: 1.0,
+            attributes: &[("route", "/settings"), ("status", "")],
+            count: 1,
+        };
+
+        let event: serde_json::Value =
+            serde_json::from_str(&format_emf(&metric, "MyService", "example", 1700000000000))
+                .unwrap();
+        assert_eq!(event["http.requests"], 1.0);
+        assert_eq!(event["route"], "/settings");
+        assert!(event.get("status").is_none());
+
+        let definition = &event["_aws"]["CloudWatchMetrics"][0];
+        assert_eq!(definition["Namespace"], "MyService");
+        assert_eq!(definition["Dimensions"][0], json!(["service", "route"]));
+        assert_eq!(definition["Metrics"][0]["Unit"], "Count");
+
+        let metric = Metric {
+            kind: MetricKind::Histogram,
+            value: 12.5,
+            count: 1000,
+            ..metric
+        };
+        let event: serde_json::Value =
+            serde_json::from_str(&format_emf(&metric, "MyService", "example", 1700000000000))
+                .unwrap();
+        assert_eq!(
+            event["http.requests"],
+            json!({ "Values": [12.5], "Counts": [1000] })
+        );
+    }
+
+    #[test]
+    fn log_events_are_chronological() {
+        let events = [
+            LogEvent {
+                timestamp: 2,
+                message: "second".into(),
+            },
+            LogEvent {
+                timestamp: 1,
+                message: "first".into(),
+            },
+        ];
+
+        let body: serde_json::Value =
+            serde_json::from_str(&put_log_events("group", "stream", &events)).unwrap();
+        assert_eq!(body["logEvents"][0]["message"], "first");
+        assert_eq!(body["logStreamName"], "stream");
+    }
+}
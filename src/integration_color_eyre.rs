@@ -0,0 +1,146 @@
+use std::{
+    collections::{hash_map::DefaultHasher, VecDeque},
+    fmt::{Debug, Display},
+    hash::{Hash, Hasher},
+    sync::{Mutex, PoisonError},
+};
+
+use color_eyre::{config::HookBuilder, eyre::InstallError};
+
+use crate::{Session, WeakSession};
+
+/// The number of recently reported errors which are remembered in order to avoid reporting the same error twice.
+const RECENT_REPORTS: usize = 128;
+
+impl Session {
+    /// Installs the [color-eyre](https://docs.rs/color-eyre) panic and report hooks configured by the provided
+    /// builder, additionally recording every [`eyre::Report`](color_eyre::eyre::Report) which is constructed to
+    /// the session.
+    ///
+    /// This ensures that errors which are merely displayed to users (for example, when they are returned from
+    /// `main()`) still reach your telemetry. Reports are deduplicated using their error chain, so an error which
+    /// is converted into a report several times as it propagates is only recorded once (until 128 other errors
+    /// have been reported). This replaces the usual call to [`HookBuilder::install`].
+    ///
+    /// <div class="warning">
+    ///
+    /// This method requires the `color-eyre` feature to be enabled.
+    ///
+    /// </div>
+    ///
+    /// ## Example
+    /// ```no_run
+    /// use color_eyre::{config::HookBuilder, eyre::eyre};
+    /// use tracing_batteries::{Session, Sentry};
+    ///
+    /// fn main() -> color_eyre::Result<()> {
+    ///   let session = Session::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+    ///     .with_battery(Sentry::new("https://yourdsn@sentry.example.com"))
+    ///     .with_color_eyre(HookBuilder::default())?;
+    ///
+    ///   Err(eyre!("the configuration file could not be found"))
+    /// }
+    /// ```
+    pub fn with_color_eyre(self, builder: HookBuilder) -> Result<Self, InstallError> {
+        let (panic_hook, eyre_hook) = builder.into_hooks();
+        let handler = eyre_hook.into_eyre_hook();
+
+        let session = self.downgrade();
+        let recent = Mutex::new(VecDeque::with_capacity(RECENT_REPORTS));
+        color_eyre::eyre::set_hook(Box::new(move |error| {
+            record_report(&session, &recent, error);
+            handler(error)
+        }))?;
+
+        panic_hook.install();
+        Ok(self)
+    }
+}
+
+fn record_report(
+    session: &WeakSession,
+    recent: &Mutex<VecDeque<u64>>,
+    error: &(dyn std::error::Error + 'static),
+) {
+    let Some(session) = session.upgrade() else {
+        return;
+    };
+
+    let mut hasher = DefaultHasher::new();
+    let mut source = Some(error);
+    while let Some(cause) = source {
+        cause.to_string().hash(&mut hasher);
+        source = cause.source();
+    }
+    let fingerprint = hasher.finish();
+
+    {
+        let mut recent = recent.lock().unwrap_or_else(PoisonError::into_inner);
+        if recent.contains(&fingerprint) {
+            return;
+        }
+
+        if recent.len() >= RECENT_REPORTS {
+            recent.pop_front();
+        }
+        recent.push_back(fingerprint);
+    }
+
+    session.record_error(&ReportedError(error));
+}
+
+/// Allows an error which is only available as a trait object to be recorded by the [`Session`].
+struct ReportedError<'a>(&'a (dyn std::error::Error + 'static));
+
+impl Debug for ReportedError<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Debug::fmt(self.0, f)
+    }
+}
+
+impl Display for ReportedError<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(self.0, f)
+    }
+}
+
+impl std::error::Error for ReportedError<'_> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.0.source()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::Ordering;
+
+    use super::*;
+
+    struct NoopBattery;
+
+    impl crate::BatteryBuilder for NoopBattery {
+        fn setup(
+            self,
+            _metadata: &crate::Metadata,
+            _enabled: std::sync::Arc<std::sync::atomic::AtomicBool>,
+        ) -> Box<dyn crate::Battery> {
+            Box::new(NoopBattery)
+        }
+    }
+
+    impl crate::Battery for NoopBattery {}
+
+    #[test]
+    fn deduplicates_reports() {
+        let session = Session::new("example", "0.0.1").with_battery(NoopBattery);
+        let weak = session.downgrade();
+        let recent = Mutex::new(VecDeque::new());
+
+        let error = std::io::Error::other("configuration file not found");
+        record_report(&weak, &recent, &error);
+        record_report(&weak, &recent, &error);
+        record_report(&weak, &recent, &std::io::Error::other("permission denied"));
+
+        assert_eq!(session.stats.errors.load(Ordering::Relaxed), 2);
+    }
+}
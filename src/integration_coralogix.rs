@@ -0,0 +1,149 @@
+use std::{
+    borrow::Cow,
+    sync::{atomic::AtomicBool, Arc},
+};
+
+use crate::{Battery, BatteryBuilder, Metadata, OpenTelemetry, OpenTelemetryProtocol};
+
+/// The Coralogix region which hosts your account, used to select the ingestion endpoint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CoralogixRegion {
+    /// Europe (Ireland), `coralogix.com`.
+    EU1,
+    /// Europe (Stockholm), `eu2.coralogix.com`.
+    EU2,
+    /// US (Ohio), `coralogix.us`.
+    US1,
+    /// US (Oregon), `cx498.coralogix.com`.
+    US2,
+    /// Asia Pacific (Mumbai), `coralogix.in`.
+    AP1,
+    /// Asia Pacific (Singapore), `coralogixsg.com`.
+    AP2,
+    /// Asia Pacific (Jakarta), `ap3.coralogix.com`.
+    AP3,
+}
+
+impl CoralogixRegion {
+    /// The Coralogix domain for this region.
+    pub fn domain(&self) -> &'static str {
+        match self {
+            CoralogixRegion::EU1 => "coralogix.com",
+            CoralogixRegion::EU2 => "eu2.coralogix.com",
+            CoralogixRegion::US1 => "coralogix.us",
+            CoralogixRegion::US2 => "cx498.coralogix.com",
+            CoralogixRegion::AP1 => "coralogix.in",
+            CoralogixRegion::AP2 => "coralogixsg.com",
+            CoralogixRegion::AP3 => "ap3.coralogix.com",
+        }
+    }
+}
+
+/// A [Coralogix](https://coralogix.com) integration which configures the [`OpenTelemetry`] integration
+/// to export traces and metrics to your region's OTLP ingress.
+///
+/// <div class="warning">
+///
+/// This integration requires the `coralogix` feature to be enabled.
+///
+/// </div>
+///
+/// Telemetry is authenticated using your Send-Your-Data API key (the "private key"), which may also be provided
+/// using the `CORALOGIX_PRIVATE_KEY` environment variable. Coralogix organizes telemetry by application and subsystem
+/// name: by default the application name is taken from the `deployment.environment` context field (falling back to the
+/// service name) and the subsystem name is the service name from your [`Metadata`].
+///
+/// ## Example
+/// ```no_run
+/// use tracing_batteries::{Session, Coralogix, CoralogixRegion};
+///
+/// let session = Session::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+///   .with_battery(Coralogix::new(CoralogixRegion::EU2, "cxtp_your-private-key")
+///     .with_application_name("production"));
+///
+/// session.shutdown();
+/// ```
+pub struct Coralogix {
+    region: CoralogixRegion,
+    private_key: Cow<'static, str>,
+    application_name: Option<Cow<'static, str>>,
+    subsystem_name: Option<Cow<'static, str>>,
+    configure: Box<dyn FnOnce(OpenTelemetry) -> OpenTelemetry>,
+}
+
+impl Coralogix {
+    /// Configures the Coralogix integration for the provided region and private key.
+    pub fn new<K: Into<Cow<'static, str>>>(region: CoralogixRegion, private_key: K) -> Self {
+        Self {
+            region,
+            private_key: std::env::var("CORALOGIX_PRIVATE_KEY")
+                .map(Cow::Owned)
+                .unwrap_or_else(|_| private_key.into()),
+            application_name: None,
+            subsystem_name: None,
+            configure: Box::new(|otel| otel),
+        }
+    }
+
+    /// Overrides the Coralogix application name which telemetry is reported under.
+    pub fn with_application_name<N: Into<Cow<'static, str>>>(self, name: N) -> Self {
+        Self {
+            application_name: Some(name.into()),
+            ..self
+        }
+    }
+
+    /// Overrides the Coralogix subsystem name which telemetry is reported under.
+    pub fn with_subsystem_name<N: Into<Cow<'static, str>>>(self, name: N) -> Self {
+        Self {
+            subsystem_name: Some(name.into()),
+            ..self
+        }
+    }
+
+    /// Customizes the underlying [`OpenTelemetry`] integration used to export traces and metrics.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use tracing_batteries::{Coralogix, CoralogixRegion, OpenTelemetryLevel};
+    ///
+    /// Coralogix::new(CoralogixRegion::EU2, "cxtp_your-private-key")
+    ///   .with_opentelemetry(|otel| otel.with_default_level(OpenTelemetryLevel::DEBUG));
+    /// ```
+    pub fn with_opentelemetry<F>(self, configure: F) -> Self
+    where
+        F: FnOnce(OpenTelemetry) -> OpenTelemetry + 'static,
+    {
+        Self {
+            configure: Box::new(configure),
+            ..self
+        }
+    }
+}
+
+impl BatteryBuilder for Coralogix {
+    fn setup(self, metadata: &Metadata, enabled: Arc<AtomicBool>) -> Box<dyn Battery> {
+        let application_name = self.application_name.clone().unwrap_or_else(|| {
+            metadata
+                .context
+                .get("deployment.environment")
+                .cloned()
+                .unwrap_or_else(|| metadata.service.clone())
+        });
+        let subsystem_name = self
+            .subsystem_name
+            .clone()
+            .unwrap_or_else(|| metadata.service.clone());
+
+        let otel = OpenTelemetry::new("")
+            .with_endpoint(format!("https://ingress.{}", self.region.domain()))
+            .with_protocol(OpenTelemetryProtocol::HttpBinary)
+            .with_header("Authorization", format!("Bearer {}", self.private_key))
+            .with_header("CX-Application-Name", application_name.clone())
+            .with_header("CX-Subsystem-Name", subsystem_name.clone())
+            .with_resource_attribute("cx.application.name", application_name)
+            .with_resource_attribute("cx.subsystem.name", subsystem_name);
+
+        (self.configure)(otel).setup(metadata, enabled)
+    }
+}
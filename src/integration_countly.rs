@@ -0,0 +1,283 @@
+use std::{
+    borrow::Cow,
+    path::PathBuf,
+    sync::{atomic::AtomicBool, mpsc, Arc, Mutex, PoisonError},
+    thread::JoinHandle,
+    time::{Duration, Instant},
+};
+
+use serde_json::json;
+
+use crate::{
+    device_id::{default_device_id_path, load_device_id},
+    dispatcher::HttpDispatcher,
+    queue::DEFAULT_QUEUE_CAPACITY,
+    Battery, BatteryBuilder, DropPolicy, Envelope, EnvelopePayload, Importance, Metadata,
+};
+
+/// How frequently the session duration is reported to Countly while the session is active.
+const SESSION_HEARTBEAT: Duration = Duration::from_secs(60);
+
+/// A [Countly](https://countly.com) integration which reports sessions, custom events, page views, and
+/// crash reports to a Countly server.
+///
+/// <div class="warning">
+///
+/// This integration requires the `countly` feature to be enabled.
+///
+/// </div>
+///
+/// The telemetry [`Session`](crate::Session) maps directly onto a Countly session: a session is started when the
+/// battery is attached, its duration is reported every minute, and it is ended when the session is shut down.
+/// Custom events are reported as Countly events (with their properties as segmentation), page views are reported
+/// as views, and errors are reported as non-fatal crashes.
+///
+/// Countly identifies users by their device ID, which is randomly generated the first time your application runs
+/// and persisted in the user's data directory (e.g. `~/.local/share/{service}/countly-device-id`) so that repeat
+/// usage is attributed to the same device.
+///
+/// ## Example
+/// ```no_run
+/// use tracing_batteries::{Session, Countly};
+///
+/// let session = Session::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+///   .with_battery(Countly::new("https://countly.example.com", "your-app-key"));
+///
+/// session.record_new_page("/settings");
+/// session.shutdown();
+/// ```
+pub struct Countly {
+    server: Cow<'static, str>,
+    app_key: Cow<'static, str>,
+    device_id: Option<Cow<'static, str>>,
+    device_id_path: Option<PathBuf>,
+    queue_capacity: usize,
+    drop_policy: DropPolicy,
+}
+
+impl Countly {
+    /// Configures the Countly integration for the provided server URL and app key.
+    pub fn new<S: Into<Cow<'static, str>>, K: Into<Cow<'static, str>>>(
+        server: S,
+        app_key: K,
+    ) -> Self {
+        Self {
+            server: server.into(),
+            app_key: app_key.into(),
+            device_id: None,
+            device_id_path: None,
+            queue_capacity: DEFAULT_QUEUE_CAPACITY,
+            drop_policy: DropPolicy::default(),
+        }
+    }
+
+    /// Overrides the device ID which telemetry is reported under, instead of generating and persisting one.
+    pub fn with_device_id<D: Into<Cow<'static, str>>>(self, device_id: D) -> Self {
+        Self {
+            device_id: Some(device_id.into()),
+            ..self
+        }
+    }
+
+    /// Overrides the file which the generated device ID is persisted to.
+    pub fn with_device_id_path<P: Into<PathBuf>>(self, path: P) -> Self {
+        Self {
+            device_id_path: Some(path.into()),
+            ..self
+        }
+    }
+
+    /// Configures the maximum number of requests which may be waiting to be sent (10,000 by default), and which are
+    /// dropped when a burst of activity fills the queue.
+    pub fn with_queue_limits(self, capacity: usize, policy: DropPolicy) -> Self {
+        Self {
+            queue_capacity: capacity,
+            drop_policy: policy,
+            ..self
+        }
+    }
+}
+
+impl BatteryBuilder for Countly {
+    fn setup(self, metadata: &Metadata, _enabled: Arc<AtomicBool>) -> Box<dyn Battery> {
+        let device_id = match self.device_id {
+            Some(device_id) => device_id.to_string(),
+            None => load_device_id(
+                self.device_id_path
+                    .or_else(|| default_device_id_path(&metadata.service, "countly-device-id")),
+            ),
+        };
+
+        let client = Arc::new(CountlyClient {
+            url: format!("{}/i", self.server.trim_end_matches('/')),
+            app_key: self.app_key.to_string(),
+            device_id,
+            app_version: metadata.version.to_string(),
+            dispatcher: HttpDispatcher::new("countly")
+                .with_limits(self.queue_capacity, self.drop_policy),
+        });
+
+        // Session lifecycle requests are critical, since the telemetry recorded during the session is
+        // attributed to it.
+        client.send(
+            Importance::Critical,
+            vec![
+                ("begin_session", "1".into()),
+                (
+                    "metrics",
+                    json!({
+                        "_os": std::env::consts::OS,
+                        "_app_version": metadata.version,
+                    })
+                    .to_string(),
+                ),
+            ],
+        );
+
+        let (stop, stopped) = mpsc::channel::<()>();
+        let heartbeat_client = client.clone();
+        let heartbeat = std::thread::Builder::new()
+            .name("tracing-batteries-countly".into())
+            .spawn(move || {
+                let mut last = Instant::now();
+                while let Err(mpsc::RecvTimeoutError::Timeout) =
+                    stopped.recv_timeout(SESSION_HEARTBEAT)
+                {
+                    heartbeat_client.send(
+                        Importance::Low,
+                        vec![("session_duration", last.elapsed().as_secs().to_string())],
+                    );
+                    last = Instant::now();
+                }
+
+                heartbeat_client.send(
+                    Importance::Critical,
+                    vec![
+                        ("end_session", "1".into()),
+                        ("session_duration", last.elapsed().as_secs().to_string()),
+                    ],
+                );
+            })
+            .ok();
+
+        Box::new(CountlyBattery {
+            client,
+            stop: Mutex::new(Some(stop)),
+            heartbeat: Mutex::new(heartbeat),
+        })
+    }
+}
+
+struct CountlyClient {
+    url: String,
+    app_key: String,
+    device_id: String,
+    app_version: String,
+    dispatcher: HttpDispatcher,
+}
+
+impl CountlyClient {
+    fn send(&self, importance: Importance, mut params: Vec<(&'static str, String)>) {
+        params.push(("app_key", self.app_key.clone()));
+        params.push(("device_id", self.device_id.clone()));
+        params.push((
+            "timestamp",
+            chrono::Utc::now().timestamp_millis().to_string(),
+        ));
+
+        let url = self.url.clone();
+        self.dispatcher
+            .dispatch(importance, move |client| client.post(url).form(&params));
+    }
+}
+
+struct CountlyBattery {
+    client: Arc<CountlyClient>,
+    stop: Mutex<Option<mpsc::Sender<()>>>,
+    heartbeat: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl Battery for CountlyBattery {
+    fn record_envelope(&self, envelope: &Envelope) {
+        if let Some(params) = build_params(envelope, &self.client.app_version) {
+            self.client.send(envelope.importance, params);
+        }
+    }
+
+    fn shutdown(&self) {
+        self.stop
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take();
+
+        if let Some(thread) = self
+            .heartbeat
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take()
+        {
+            crate::worker::join(thread);
+        }
+
+        self.client.dispatcher.shutdown();
+    }
+}
+
+fn build_params(envelope: &Envelope, app_version: &str) -> Option<Vec<(&'static str, String)>> {
+    let timestamp = envelope.timestamp.timestamp_millis();
+
+    match &envelope.payload {
+        EnvelopePayload::Event { name, properties } => Some(vec![(
+            "events",
+            json!([{
+                "key": name,
+                "count": 1,
+                "segmentation": properties,
+                "timestamp": timestamp,
+            }])
+            .to_string(),
+        )]),
+        EnvelopePayload::PageView { page } => Some(vec![(
+            "events",
+            json!([{
+                "key": "[CLY]_view",
+                "count": 1,
+                "segmentation": {
+                    "name": page,
+                    "segment": std::env::consts::OS,
+                    "visit": 1,
+                },
+                "timestamp": timestamp,
+            }])
+            .to_string(),
+        )]),
+        EnvelopePayload::Error {
+            message,
+            causes,
+            backtrace,
+            fields,
+        } => {
+            let mut error = message.clone();
+            for cause in causes {
+                error.push_str(&format!("\nCaused by: {cause}"));
+            }
+            if let Some(backtrace) = backtrace {
+                error.push_str(&format!("\n\n{backtrace}"));
+            }
+
+            Some(vec![(
+                "crash",
+                json!({
+                    "_os": std::env::consts::OS,
+                    "_app_version": app_version,
+                    "_name": message,
+                    "_error": error,
+                    "_nonfatal": true,
+                    "_custom": fields,
+                })
+                .to_string(),
+            )])
+        }
+        EnvelopePayload::Sealed { .. } | EnvelopePayload::Unknown => None,
+    }
+}
@@ -0,0 +1,235 @@
+use std::{
+    borrow::Cow,
+    sync::{atomic::AtomicBool, Arc},
+    time::Duration,
+};
+
+use notify_rust::{Notification, Timeout};
+
+use crate::{
+    notify::{environment_label, RateLimiter},
+    Battery, BatteryBuilder, Envelope, EnvelopePayload, Metadata,
+};
+
+/// An integration which raises a desktop notification whenever an error is recorded, or the session ends with a
+/// non-zero exit code, making failures visible during development and to the users of desktop applications.
+///
+/// <div class="warning">
+///
+/// This integration requires the `desktop-notify` feature to be enabled.
+///
+/// </div>
+///
+/// Notifications are raised using the operating system's native notification mechanism (D-Bus on Linux and BSD,
+/// the Notification Center on macOS and toast notifications on Windows). Abnormal exits are detected using the
+/// exit code provided to [`Session::shutdown_with_exit_code`](crate::Session::shutdown_with_exit_code), and at
+/// most 3 error notifications are raised per minute so that an error loop doesn't flood the user's desktop.
+///
+/// ## Example
+/// ```no_run
+/// use tracing_batteries::{Session, DesktopNotify};
+///
+/// let session = Session::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+///   .with_battery(DesktopNotify::new().with_app_name("My Application"));
+///
+/// session.shutdown_with_exit_code(1);
+/// ```
+pub struct DesktopNotify {
+    app_name: Option<Cow<'static, str>>,
+    icon: Option<Cow<'static, str>>,
+    errors: bool,
+    rate_limit: (usize, Duration),
+}
+
+impl DesktopNotify {
+    /// Configures the desktop notification integration to notify on errors and abnormal exits.
+    pub fn new() -> Self {
+        Self {
+            app_name: None,
+            icon: None,
+            errors: true,
+            rate_limit: (3, Duration::from_secs(60)),
+        }
+    }
+
+    /// Overrides the application name shown on notifications, which defaults to the service name.
+    pub fn with_app_name<N: Into<Cow<'static, str>>>(self, app_name: N) -> Self {
+        Self {
+            app_name: Some(app_name.into()),
+            ..self
+        }
+    }
+
+    /// Configures the icon shown on notifications, either as a path to an image or the name of an installed icon.
+    pub fn with_icon<I: Into<Cow<'static, str>>>(self, icon: I) -> Self {
+        Self {
+            icon: Some(icon.into()),
+            ..self
+        }
+    }
+
+    /// Only raises a notification when the session ends abnormally, ignoring individual errors.
+    pub fn only_on_abnormal_exit(self) -> Self {
+        Self {
+            errors: false,
+            ..self
+        }
+    }
+
+    /// Configures the maximum number of error notifications which are raised within the provided window.
+    pub fn with_rate_limit(self, limit: usize, window: Duration) -> Self {
+        Self {
+            rate_limit: (limit, window),
+            ..self
+        }
+    }
+}
+
+impl Default for DesktopNotify {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BatteryBuilder for DesktopNotify {
+    fn setup(self, metadata: &Metadata, _enabled: Arc<AtomicBool>) -> Box<dyn Battery> {
+        let app_name = self
+            .app_name
+            .map(|name| name.to_string())
+            .unwrap_or_else(|| metadata.service.to_string());
+
+        Box::new(DesktopNotifyBattery {
+            title: match environment_label(metadata) {
+                Some(environment) => format!("{app_name} ({environment})"),
+                None => app_name.clone(),
+            },
+            app_name,
+            icon: self.icon.map(|icon| icon.to_string()),
+            errors: self.errors,
+            limiter: RateLimiter::new(self.rate_limit.0, self.rate_limit.1),
+        })
+    }
+}
+
+struct DesktopNotifyBattery {
+    app_name: String,
+    title: String,
+    icon: Option<String>,
+    errors: bool,
+    limiter: RateLimiter,
+}
+
+impl Battery for DesktopNotifyBattery {
+    fn record_envelope(&self, envelope: &Envelope) {
+        let Some(alert) = build_alert(envelope, &self.title) else {
+            return;
+        };
+
+        // Abnormal exits are always reported, since they happen at most once per session.
+        if !alert.exit && (!self.errors || !self.limiter.allow()) {
+            return;
+        }
+
+        let mut notification = Notification::new();
+        notification
+            .appname(&self.app_name)
+            .summary(&alert.summary)
+            .body(&alert.body)
+            .timeout(Timeout::Milliseconds(10_000));
+
+        if let Some(icon) = &self.icon {
+            notification.icon(icon);
+        }
+
+        #[cfg(all(unix, not(target_os = "macos")))]
+        notification.urgency(notify_rust::Urgency::Critical);
+
+        notification.show().ok();
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct Alert {
+    summary: String,
+    body: String,
+    exit: bool,
+}
+
+/// Builds the notification raised for an [`Envelope`], returning `None` if it doesn't warrant one.
+fn build_alert(envelope: &Envelope, title: &str) -> Option<Alert> {
+    match &envelope.payload {
+        EnvelopePayload::Error { message, .. } => Some(Alert {
+            summary: format!("{title} encountered an error"),
+            body: message.clone(),
+            exit: false,
+        }),
+        EnvelopePayload::Event { name, properties } if name == "session_summary" => {
+            let exit_code = properties.get("exit_code")?.as_i64()?;
+            if exit_code == 0 {
+                return None;
+            }
+
+            let errors = properties
+                .get("errors")
+                .and_then(|errors| errors.as_u64())
+                .unwrap_or_default();
+
+            Some(Alert {
+                summary: format!("{title} exited unexpectedly"),
+                body: format!(
+                    "The application exited with code {exit_code} after recording {errors} error(s)."
+                ),
+                exit: true,
+            })
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Session;
+
+    #[test]
+    fn abnormal_exit_alerts() {
+        let metadata = Session::new("example", "0.0.1");
+        let summary = |exit_code: Option<i32>| {
+            Envelope::new(
+                &metadata,
+                EnvelopePayload::Event {
+                    name: "session_summary".into(),
+                    properties: [
+                        ("errors".to_string(), 2.into()),
+                        ("exit_code".to_string(), exit_code.into()),
+                    ]
+                    .into(),
+                },
+            )
+        };
+
+        assert_eq!(
+            build_alert(&summary(Some(3)), "example"),
+            Some(Alert {
+                summary: "example exited unexpectedly".into(),
+                body: "The application exited with code 3 after recording 2 error(s).".into(),
+                exit: true,
+            })
+        );
+        assert_eq!(build_alert(&summary(Some(0)), "example"), None);
+        assert_eq!(build_alert(&summary(None), "example"), None);
+
+        let error = Envelope::new(
+            &metadata,
+            EnvelopePayload::Error {
+                message: "The database could not be opened.".into(),
+                causes: vec![],
+                fields: Default::default(),
+                backtrace: None,
+            },
+        );
+        let alert = build_alert(&error, "example (production)").unwrap();
+        assert_eq!(alert.summary, "example (production) encountered an error");
+        assert!(!alert.exit);
+    }
+}
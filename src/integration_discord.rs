@@ -0,0 +1,232 @@
+use std::{
+    borrow::Cow,
+    sync::{atomic::AtomicBool, Arc},
+    time::Duration,
+};
+
+use serde_json::json;
+
+use crate::{
+    dispatcher::HttpDispatcher,
+    notify::{environment_label, NotificationSeverity, RateLimiter},
+    queue::DEFAULT_QUEUE_CAPACITY,
+    Battery, BatteryBuilder, DropPolicy, Envelope, EnvelopePayload, Metadata,
+};
+
+/// The maximum length of an embed's description, as enforced by Discord.
+const MAX_DESCRIPTION_LENGTH: usize = 4096;
+
+/// A [Discord](https://discord.com) integration which posts rich embeds to a channel's webhook whenever
+/// errors (or, optionally, custom events) are recorded.
+///
+/// <div class="warning">
+///
+/// This integration requires the `discord` feature to be enabled.
+///
+/// </div>
+///
+/// By default only errors are posted, and at most 10 notifications are sent per minute to avoid flooding the
+/// channel. Each embed is labeled with your service's version and environment (taken from the
+/// `deployment.environment` or `environment` context field), and may mention users or roles to get their attention.
+///
+/// ## Example
+/// ```no_run
+/// use tracing_batteries::{Session, DiscordNotifier};
+///
+/// let session = Session::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+///   .with_context("environment", "production")
+///   .with_battery(DiscordNotifier::new("https://discord.com/api/webhooks/1234/your-token")
+///     .with_mention("<@&123456789012345678>"));
+///
+/// session.shutdown();
+/// ```
+pub struct DiscordNotifier {
+    webhook_url: Cow<'static, str>,
+    min_severity: NotificationSeverity,
+    mentions: Vec<Cow<'static, str>>,
+    rate_limit: (usize, Duration),
+    queue_capacity: usize,
+    drop_policy: DropPolicy,
+}
+
+impl DiscordNotifier {
+    /// Configures the Discord integration to post to the provided webhook URL.
+    pub fn new<U: Into<Cow<'static, str>>>(webhook_url: U) -> Self {
+        Self {
+            webhook_url: webhook_url.into(),
+            min_severity: NotificationSeverity::Error,
+            mentions: Vec::new(),
+            rate_limit: (10, Duration::from_secs(60)),
+            queue_capacity: DEFAULT_QUEUE_CAPACITY,
+            drop_policy: DropPolicy::default(),
+        }
+    }
+
+    /// Configures the minimum severity of telemetry which is posted to the channel.
+    pub fn with_min_severity(self, severity: NotificationSeverity) -> Self {
+        Self {
+            min_severity: severity,
+            ..self
+        }
+    }
+
+    /// Adds a user (`<@id>`), role (`<@&id>`), `@here`, or `@everyone` mention to each notification.
+    pub fn with_mention<M: Into<Cow<'static, str>>>(mut self, mention: M) -> Self {
+        self.mentions.push(mention.into());
+        self
+    }
+
+    /// Configures the maximum number of notifications which are posted within the provided window.
+    pub fn with_rate_limit(self, limit: usize, window: Duration) -> Self {
+        Self {
+            rate_limit: (limit, window),
+            ..self
+        }
+    }
+
+    /// Configures the maximum number of notifications which may be waiting to be sent (10,000 by default), and which
+    /// are dropped when the webhook falls behind a burst of errors.
+    pub fn with_queue_limits(self, capacity: usize, policy: DropPolicy) -> Self {
+        Self {
+            queue_capacity: capacity,
+            drop_policy: policy,
+            ..self
+        }
+    }
+}
+
+impl BatteryBuilder for DiscordNotifier {
+    fn setup(self, metadata: &Metadata, _enabled: Arc<AtomicBool>) -> Box<dyn Battery> {
+        Box::new(DiscordBattery {
+            webhook_url: self.webhook_url.to_string(),
+            min_severity: self.min_severity,
+            content: self.mentions.join(" "),
+            environment: environment_label(metadata),
+            limiter: RateLimiter::new(self.rate_limit.0, self.rate_limit.1),
+            dispatcher: HttpDispatcher::new("discord")
+                .with_limits(self.queue_capacity, self.drop_policy),
+        })
+    }
+}
+
+struct DiscordBattery {
+    webhook_url: String,
+    min_severity: NotificationSeverity,
+    content: String,
+    environment: Option<String>,
+    limiter: RateLimiter,
+    dispatcher: HttpDispatcher,
+}
+
+impl Battery for DiscordBattery {
+    fn record_envelope(&self, envelope: &Envelope) {
+        match NotificationSeverity::of(envelope) {
+            Some(severity) if severity >= self.min_severity => {}
+            _ => return,
+        }
+
+        if !self.limiter.allow() {
+            return;
+        }
+
+        let body = json!({
+            "content": self.content,
+            "allowed_mentions": { "parse": ["users", "roles", "everyone"] },
+            "embeds": [build_embed(envelope, self.environment.as_deref())],
+        })
+        .to_string();
+
+        let url = self.webhook_url.clone();
+        self.dispatcher
+            .dispatch(envelope.importance, move |client| {
+                client
+                    .post(url)
+                    .header("Content-Type", "application/json")
+                    .body(body)
+            });
+    }
+
+    fn shutdown(&self) {
+        self.dispatcher.shutdown();
+    }
+}
+
+fn build_embed(envelope: &Envelope, environment: Option<&str>) -> serde_json::Value {
+    let mut fields = vec![json!({ "name": "Version", "value": envelope.version, "inline": true })];
+    if let Some(environment) = environment {
+        fields.push(json!({ "name": "Environment", "value": environment, "inline": true }));
+    }
+
+    let (title, color, description) = match &envelope.payload {
+        EnvelopePayload::Error {
+            message,
+            causes,
+            fields: error_fields,
+            ..
+        } => {
+            for (key, value) in error_fields {
+                fields.push(json!({ "name": key, "value": value, "inline": true }));
+            }
+
+            let mut description = message.clone();
+            for cause in causes {
+                description.push_str(&format!("\n> Caused by: {cause}"));
+            }
+
+            (
+                format!("Error in {}", envelope.service),
+                0xE74C3C,
+                description,
+            )
+        }
+        EnvelopePayload::Event { name, properties } => {
+            for (key, value) in properties {
+                fields.push(json!({ "name": key, "value": value.to_string(), "inline": true }));
+            }
+
+            (
+                format!("{name} in {}", envelope.service),
+                0x3498DB,
+                String::new(),
+            )
+        }
+        _ => (envelope.summary(), 0x95A5A6, String::new()),
+    };
+
+    json!({
+        "title": title,
+        "description": description.chars().take(MAX_DESCRIPTION_LENGTH).collect::<String>(),
+        "color": color,
+        "fields": fields.into_iter().take(25).collect::<Vec<_>>(),
+        "timestamp": envelope.timestamp.to_rfc3339(),
+        "footer": { "text": format!("{} v{}", envelope.service, envelope.version) },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Session;
+
+    #[test]
+    fn error_embed() {
+        let metadata = Session::new("example", "0.0.1");
+        let envelope = Envelope::new(
+            &metadata,
+            EnvelopePayload::Error {
+                message: "Failed to connect".into(),
+                causes: vec!["Connection refused".into()],
+                fields: Default::default(),
+                backtrace: None,
+            },
+        );
+
+        let embed = build_embed(&envelope, Some("production"));
+        assert_eq!(embed["title"], "Error in example");
+        assert_eq!(
+            embed["description"],
+            "Failed to connect\n> Caused by: Connection refused"
+        );
+        assert_eq!(embed["fields"][1]["value"], "production");
+    }
+}
@@ -0,0 +1,205 @@
+use std::{
+    borrow::Cow,
+    sync::{atomic::AtomicBool, Arc},
+    time::Duration,
+};
+
+use crate::{Battery, BatteryBuilder, Metadata, OpenTelemetry, OpenTelemetryProtocol};
+
+/// The token scopes which are required to ingest traces and metrics through Dynatrace's OTLP API.
+const REQUIRED_SCOPES: &[&str] = &["openTelemetryTrace.ingest", "metrics.ingest"];
+
+/// The files which OneAgent and the Dynatrace Operator use to publish the resource attributes that
+/// Dynatrace needs to associate telemetry with the monitored host and process.
+const ENRICHMENT_FILES: &[&str] = &[
+    "dt_metadata_e617c525669e072eebe3d0f08212e8f2.properties",
+    "/var/lib/dynatrace/enrichment/dt_metadata.properties",
+    "/var/lib/dynatrace/enrichment/dt_host_metadata.properties",
+];
+
+/// A [Dynatrace](https://www.dynatrace.com) integration which configures the [`OpenTelemetry`] integration
+/// to export traces and metrics to your environment's OTLP API.
+///
+/// <div class="warning">
+///
+/// This integration requires the `dynatrace` feature to be enabled.
+///
+/// </div>
+///
+/// The integration is configured with the URL of your Dynatrace environment (e.g. `https://abc12345.live.dynatrace.com`
+/// for SaaS, or `https://{your-domain}/e/{environment-id}` for Managed) and an access token with the
+/// `openTelemetryTrace.ingest` and `metrics.ingest` scopes, which is sent using the `Api-Token` authorization scheme.
+/// Metrics are exported using delta temporality and the resource is enriched with the `dt.*` attributes published by
+/// OneAgent or the Dynatrace Operator (when available).
+///
+/// The token's scopes are validated in the background at startup and a warning is emitted if any of the required
+/// scopes are missing. This lookup requires the `apiTokens.read` scope and is skipped if the token cannot be looked up.
+///
+/// ## Example
+/// ```no_run
+/// use tracing_batteries::{Session, Dynatrace};
+///
+/// let session = Session::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+///   .with_battery(Dynatrace::new("https://abc12345.live.dynatrace.com", "dt0c01.your-access-token"));
+///
+/// session.shutdown();
+/// ```
+pub struct Dynatrace {
+    environment: Cow<'static, str>,
+    token: Cow<'static, str>,
+    validate_scopes: bool,
+    configure: Box<dyn FnOnce(OpenTelemetry) -> OpenTelemetry>,
+}
+
+impl Dynatrace {
+    /// Configures the Dynatrace integration for the provided environment URL and access token.
+    pub fn new<E: Into<Cow<'static, str>>, T: Into<Cow<'static, str>>>(
+        environment: E,
+        token: T,
+    ) -> Self {
+        Self {
+            environment: environment.into(),
+            token: token.into(),
+            validate_scopes: true,
+            configure: Box::new(|otel| otel),
+        }
+    }
+
+    /// Configures whether the access token's scopes are validated at startup (enabled by default).
+    pub fn with_scope_validation(self, enabled: bool) -> Self {
+        Self {
+            validate_scopes: enabled,
+            ..self
+        }
+    }
+
+    /// Customizes the underlying [`OpenTelemetry`] integration used to export traces and metrics.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use tracing_batteries::{Dynatrace, OpenTelemetryLevel};
+    ///
+    /// Dynatrace::new("https://abc12345.live.dynatrace.com", "dt0c01.your-access-token")
+    ///   .with_opentelemetry(|otel| otel.with_default_level(OpenTelemetryLevel::DEBUG));
+    /// ```
+    pub fn with_opentelemetry<F>(self, configure: F) -> Self
+    where
+        F: FnOnce(OpenTelemetry) -> OpenTelemetry + 'static,
+    {
+        Self {
+            configure: Box::new(configure),
+            ..self
+        }
+    }
+
+    fn missing_scopes(environment: &str, token: &str) -> Option<Vec<&'static str>> {
+        #[derive(serde::Deserialize)]
+        struct TokenMetadata {
+            scopes: Vec<String>,
+        }
+
+        let response = reqwest::blocking::Client::builder()
+            .timeout(Duration::from_secs(5))
+            .build()
+            .ok()?
+            .post(format!("{environment}/api/v2/apiTokens/lookup"))
+            .header("Authorization", format!("Api-Token {token}"))
+            .header("Content-Type", "application/json")
+            .body(serde_json::json!({ "token": token }).to_string())
+            .send()
+            .and_then(|response| response.error_for_status())
+            .and_then(|response| response.text())
+            .ok()?;
+
+        let metadata: TokenMetadata = serde_json::from_str(&response).ok()?;
+        Some(
+            REQUIRED_SCOPES
+                .iter()
+                .copied()
+                .filter(|scope| !metadata.scopes.iter().any(|s| s == scope))
+                .collect(),
+        )
+    }
+}
+
+impl BatteryBuilder for Dynatrace {
+    fn setup(self, metadata: &Metadata, enabled: Arc<AtomicBool>) -> Box<dyn Battery> {
+        let environment = self.environment.trim_end_matches('/').to_string();
+
+        let mut otel = OpenTelemetry::new("")
+            .with_endpoint(format!("{environment}/api/v2/otlp"))
+            .with_protocol(OpenTelemetryProtocol::HttpBinary)
+            .with_header("Authorization", format!("Api-Token {}", self.token))
+            .with_delta_temporality(true);
+        for (key, value) in read_enrichment_attributes() {
+            otel = otel.with_resource_attribute(key, value);
+        }
+
+        let battery = (self.configure)(otel).setup(metadata, enabled);
+
+        if self.validate_scopes {
+            // The lookup is a blocking HTTP request, so it runs in the background to avoid delaying startup (or
+            // panicking when the session is built within an async runtime).
+            let token = self.token.to_string();
+            std::thread::Builder::new()
+                .name("tracing-batteries-dynatrace-scopes".into())
+                .spawn(move || {
+                    let missing_scopes =
+                        Self::missing_scopes(&environment, &token).unwrap_or_default();
+                    if !missing_scopes.is_empty() {
+                        tracing::warn!(
+                            environment = %environment,
+                            "The Dynatrace access token is missing the following scopes, telemetry may not be exported: {}",
+                            missing_scopes.join(", ")
+                        );
+                    }
+                })
+                .ok();
+        }
+
+        battery
+    }
+}
+
+/// Reads the `dt.*` resource attributes published by OneAgent or the Dynatrace Operator.
+fn read_enrichment_attributes() -> Vec<(String, String)> {
+    ENRICHMENT_FILES
+        .iter()
+        .filter_map(|file| {
+            let contents = std::fs::read_to_string(file).ok()?;
+            if file.starts_with("dt_metadata_") {
+                // OneAgent exposes the path of the real metadata file through this virtual file.
+                std::fs::read_to_string(contents.trim()).ok()
+            } else {
+                Some(contents)
+            }
+        })
+        .flat_map(|contents| parse_properties(&contents))
+        .collect()
+}
+
+fn parse_properties(contents: &str) -> Vec<(String, String)> {
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn properties() {
+        assert_eq!(
+            parse_properties("# comment\ndt.entity.host=HOST-1234\n\ndt.host_group.id = prod\n"),
+            vec![
+                ("dt.entity.host".to_string(), "HOST-1234".to_string()),
+                ("dt.host_group.id".to_string(), "prod".to_string()),
+            ]
+        );
+    }
+}
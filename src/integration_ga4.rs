@@ -0,0 +1,290 @@
+use std::{
+    borrow::Cow,
+    path::PathBuf,
+    sync::{atomic::AtomicBool, Arc},
+};
+
+use serde_json::json;
+
+use crate::{
+    device_id::{default_device_id_path, load_device_id},
+    dispatcher::HttpDispatcher,
+    queue::DEFAULT_QUEUE_CAPACITY,
+    Battery, BatteryBuilder, DropPolicy, Envelope, EnvelopePayload, Metadata,
+};
+
+/// The maximum length of an event or parameter name accepted by GA4.
+const MAX_NAME_LENGTH: usize = 40;
+
+/// The maximum length of a parameter value accepted by GA4.
+const MAX_VALUE_LENGTH: usize = 100;
+
+/// The maximum number of parameters which may be attached to a single event.
+const MAX_PARAMS: usize = 25;
+
+/// A [Google Analytics 4](https://developers.google.com/analytics/devguides/collection/protocol/ga4) integration
+/// which reports page views and custom events using the GA4 Measurement Protocol.
+///
+/// <div class="warning">
+///
+/// This integration requires the `ga4` feature to be enabled.
+///
+/// </div>
+///
+/// Page views are reported as `page_view` events and custom events are reported under their own name, with
+/// their properties as event parameters. GA4 only accepts event and parameter names made up of letters, digits
+/// and underscores (of at most 40 characters) and parameter values of at most 100 characters, so names are
+/// sanitized and values truncated before they are sent. Errors are not reported.
+///
+/// GA4 identifies users by their client ID, which is randomly generated the first time your application runs
+/// and persisted in the user's data directory (e.g. `~/.local/share/{service}/ga4-client-id`) so that repeat
+/// usage is attributed to the same user. Each run of your application is reported as a new GA4 session.
+///
+/// ## Example
+/// ```no_run
+/// use tracing_batteries::{Session, Ga4};
+///
+/// let session = Session::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+///   .with_battery(Ga4::new("G-XXXXXXXXXX", "your-api-secret"));
+///
+/// session.record_new_page("/settings");
+/// session.shutdown();
+/// ```
+pub struct Ga4 {
+    measurement_id: Cow<'static, str>,
+    api_secret: Cow<'static, str>,
+    server: Cow<'static, str>,
+    client_id: Option<Cow<'static, str>>,
+    client_id_path: Option<PathBuf>,
+    user_id: Option<Cow<'static, str>>,
+    queue_capacity: usize,
+    drop_policy: DropPolicy,
+}
+
+impl Ga4 {
+    /// Configures the GA4 integration for the provided measurement ID and Measurement Protocol API secret.
+    pub fn new<M: Into<Cow<'static, str>>, S: Into<Cow<'static, str>>>(
+        measurement_id: M,
+        api_secret: S,
+    ) -> Self {
+        Self {
+            measurement_id: measurement_id.into(),
+            api_secret: api_secret.into(),
+            server: "https://www.google-analytics.com".into(),
+            client_id: None,
+            client_id_path: None,
+            user_id: None,
+            queue_capacity: DEFAULT_QUEUE_CAPACITY,
+            drop_policy: DropPolicy::default(),
+        }
+    }
+
+    /// Sends events to GA4's EU collection endpoint, ensuring that data is collected within the EU.
+    pub fn with_eu_collection(self) -> Self {
+        Self {
+            server: "https://region1.google-analytics.com".into(),
+            ..self
+        }
+    }
+
+    /// Overrides the client ID which events are reported under, instead of generating and persisting one.
+    pub fn with_client_id<C: Into<Cow<'static, str>>>(self, client_id: C) -> Self {
+        Self {
+            client_id: Some(client_id.into()),
+            ..self
+        }
+    }
+
+    /// Overrides the file which the generated client ID is persisted to.
+    pub fn with_client_id_path<P: Into<PathBuf>>(self, path: P) -> Self {
+        Self {
+            client_id_path: Some(path.into()),
+            ..self
+        }
+    }
+
+    /// Associates events with a known user, allowing GA4 to report on them across devices.
+    pub fn with_user_id<U: Into<Cow<'static, str>>>(self, user_id: U) -> Self {
+        Self {
+            user_id: Some(user_id.into()),
+            ..self
+        }
+    }
+
+    /// Configures the maximum number of events which may be waiting to be sent (10,000 by default), and which are
+    /// dropped when a burst of activity fills the queue.
+    pub fn with_queue_limits(self, capacity: usize, policy: DropPolicy) -> Self {
+        Self {
+            queue_capacity: capacity,
+            drop_policy: policy,
+            ..self
+        }
+    }
+}
+
+impl BatteryBuilder for Ga4 {
+    fn setup(self, metadata: &Metadata, _enabled: Arc<AtomicBool>) -> Box<dyn Battery> {
+        let client_id = match self.client_id {
+            Some(client_id) => client_id.to_string(),
+            None => load_device_id(
+                self.client_id_path
+                    .or_else(|| default_device_id_path(&metadata.service, "ga4-client-id")),
+            ),
+        };
+
+        Box::new(Ga4Battery {
+            url: format!(
+                "{}/mp/collect?measurement_id={}&api_secret={}",
+                self.server, self.measurement_id, self.api_secret
+            ),
+            client_id,
+            user_id: self.user_id.map(|user_id| user_id.to_string()),
+            session_id: chrono::Utc::now().timestamp().to_string(),
+            service: metadata.service.to_string(),
+            dispatcher: HttpDispatcher::new("ga4")
+                .with_limits(self.queue_capacity, self.drop_policy),
+        })
+    }
+}
+
+struct Ga4Battery {
+    url: String,
+    client_id: String,
+    user_id: Option<String>,
+    session_id: String,
+    service: String,
+    dispatcher: HttpDispatcher,
+}
+
+impl Battery for Ga4Battery {
+    fn record_envelope(&self, envelope: &Envelope) {
+        let Some(event) = build_event(envelope, &self.service, &self.session_id) else {
+            return;
+        };
+
+        let mut body = json!({
+            "client_id": self.client_id,
+            "timestamp_micros": envelope.timestamp.timestamp_micros(),
+            "events": [event],
+        });
+        if let Some(user_id) = &self.user_id {
+            body["user_id"] = json!(user_id);
+        }
+
+        let url = self.url.clone();
+        let body = body.to_string();
+        self.dispatcher
+            .dispatch(envelope.importance, move |client| {
+                client
+                    .post(url)
+                    .header("Content-Type", "application/json")
+                    .body(body)
+            });
+    }
+
+    fn shutdown(&self) {
+        self.dispatcher.shutdown();
+    }
+}
+
+/// Converts an [`Envelope`] into a GA4 event, returning `None` for telemetry which isn't reported to GA4.
+fn build_event(envelope: &Envelope, service: &str, session_id: &str) -> Option<serde_json::Value> {
+    let (name, mut params) = match &envelope.payload {
+        EnvelopePayload::PageView { page } => {
+            let location = if page.contains("://") {
+                page.clone()
+            } else {
+                format!("app://{service}/{}", page.trim_start_matches('/'))
+            };
+
+            (
+                "page_view".to_string(),
+                json!({
+                    "page_location": location,
+                    "page_title": page,
+                }),
+            )
+        }
+        EnvelopePayload::Event { name, properties } => {
+            let mut params = json!({});
+            for (key, value) in properties.iter().take(MAX_PARAMS - 2) {
+                params[sanitize_name(key)] = match value {
+                    serde_json::Value::Number(_) | serde_json::Value::Bool(_) => value.clone(),
+                    serde_json::Value::String(value) => json!(truncate(value)),
+                    value => json!(truncate(&value.to_string())),
+                };
+            }
+
+            (sanitize_name(name), params)
+        }
+        _ => return None,
+    };
+
+    // GA4 only includes events in its session and engagement reports when these parameters are present.
+    params["session_id"] = json!(session_id);
+    params["engagement_time_msec"] = json!(1);
+
+    Some(json!({ "name": name, "params": params }))
+}
+
+/// Restricts a name to the letters, digits and underscores accepted by GA4, ensuring it starts with a letter.
+fn sanitize_name(name: &str) -> String {
+    let mut sanitized: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+
+    if !sanitized.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        sanitized.insert(0, 'e');
+    }
+
+    sanitized.truncate(MAX_NAME_LENGTH);
+    sanitized
+}
+
+fn truncate(value: &str) -> String {
+    value.chars().take(MAX_VALUE_LENGTH).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Session;
+
+    #[test]
+    fn measurement_protocol_events() {
+        let metadata = Session::new("example", "0.0.1");
+
+        let page_view = Envelope::new(
+            &metadata,
+            EnvelopePayload::PageView {
+                page: "/settings".into(),
+            },
+        );
+        let event = build_event(&page_view, "example", "1700000000").unwrap();
+        assert_eq!(event["name"], "page_view");
+        assert_eq!(event["params"]["page_location"], "app://example/settings");
+        assert_eq!(event["params"]["session_id"], "1700000000");
+
+        let custom = Envelope::new(
+            &metadata,
+            EnvelopePayload::Event {
+                name: "project.created".into(),
+                properties: [
+                    ("template-name".to_string(), json!("x".repeat(150))),
+                    ("count".to_string(), json!(3)),
+                ]
+                .into(),
+            },
+        );
+        let event = build_event(&custom, "example", "1700000000").unwrap();
+        assert_eq!(event["name"], "project_created");
+        assert_eq!(event["params"]["count"], 3);
+        assert_eq!(
+            event["params"]["template_name"].as_str().unwrap().len(),
+            MAX_VALUE_LENGTH
+        );
+
+        assert_eq!(sanitize_name("404 error"), "e404_error");
+    }
+}
@@ -0,0 +1,376 @@
+use std::{
+    borrow::Cow,
+    collections::HashSet,
+    fmt::Write,
+    sync::{atomic::AtomicBool, mpsc, Arc, Mutex, PoisonError},
+    thread::JoinHandle,
+};
+
+use reqwest::blocking::{Client, RequestBuilder};
+use serde_json::json;
+
+use crate::{hash::fnv1a, Battery, BatteryBuilder, Envelope, EnvelopePayload, Metadata};
+
+/// The maximum number of characters of a backtrace which are included in an issue, keeping it within GitHub's
+/// 65,536 character limit on issue bodies.
+const MAX_BACKTRACE_LENGTH: usize = 50_000;
+
+/// A [GitHub Issues](https://docs.github.com/en/issues) integration which files an issue for each distinct error
+/// your application encounters, giving open source maintainers crash reports without running a crash backend.
+///
+/// <div class="warning">
+///
+/// This integration requires the `github` feature to be enabled.
+///
+/// </div>
+///
+/// Each error is identified by a fingerprint, derived from its message and causes (ignoring any numbers they
+/// contain), which is included in the issue body. Before an issue is filed, the repository is searched for an
+/// existing issue with the same fingerprint and, if one is found, a comment noting the new occurrence is added to
+/// it instead. Each fingerprint is reported at most once per session.
+///
+/// Issues include the application's version, the operating system and architecture it was running on, any
+/// fields recorded alongside the error, and its backtrace (when available) with the user's home directory
+/// replaced by `~`. The token must be permitted to read and write issues on the repository, and since it is
+/// distributed with your application you should use a fine-grained token which is restricted to that alone.
+///
+/// ## Example
+/// ```no_run
+/// use tracing_batteries::{Session, GithubIssues};
+///
+/// let session = Session::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+///   .with_battery(GithubIssues::new("github_pat_...", "my-org/my-cli")
+///     .with_labels(["bug", "crash-report"]));
+///
+/// session.shutdown();
+/// ```
+pub struct GithubIssues {
+    token: Cow<'static, str>,
+    repository: Cow<'static, str>,
+    api_url: Cow<'static, str>,
+    labels: Vec<String>,
+}
+
+impl GithubIssues {
+    /// Configures the GitHub Issues integration to file issues in the provided `owner/name` repository.
+    pub fn new<T: Into<Cow<'static, str>>, R: Into<Cow<'static, str>>>(
+        token: T,
+        repository: R,
+    ) -> Self {
+        Self {
+            token: token.into(),
+            repository: repository.into(),
+            api_url: "https://api.github.com".into(),
+            labels: Vec::new(),
+        }
+    }
+
+    /// Configures the labels which are applied to newly filed issues.
+    pub fn with_labels<I, L>(self, labels: I) -> Self
+    where
+        I: IntoIterator<Item = L>,
+        L: Into<String>,
+    {
+        Self {
+            labels: labels.into_iter().map(Into::into).collect(),
+            ..self
+        }
+    }
+
+    /// Overrides the GitHub API URL, for use with GitHub Enterprise Server (e.g. `https://github.example.com/api/v3`).
+    pub fn with_api_url<U: Into<Cow<'static, str>>>(self, api_url: U) -> Self {
+        Self {
+            api_url: api_url.into(),
+            ..self
+        }
+    }
+}
+
+impl BatteryBuilder for GithubIssues {
+    fn setup(self, metadata: &Metadata, _enabled: Arc<AtomicBool>) -> Box<dyn Battery> {
+        let (sender, receiver) = mpsc::channel::<Report>();
+
+        let api = GithubApi {
+            api_url: self.api_url.trim_end_matches('/').to_string(),
+            repository: self.repository.to_string(),
+            authorization: format!("Bearer {}", self.token),
+            user_agent: format!("{}/{}", metadata.service, metadata.version),
+            labels: self.labels,
+        };
+
+        let thread = std::thread::Builder::new()
+            .name("tracing-batteries-github".into())
+            .spawn(move || {
+                let client = Client::new();
+                for report in receiver {
+                    api.file(&client, &report).ok();
+                }
+            })
+            .ok();
+
+        Box::new(GithubIssuesBattery {
+            home: std::env::var("HOME")
+                .or_else(|_| std::env::var("USERPROFILE"))
+                .ok()
+                .filter(|home| !home.is_empty()),
+            reported: Mutex::new(HashSet::new()),
+            sender: Mutex::new(Some(sender)),
+            thread: Mutex::new(thread),
+        })
+    }
+}
+
+struct Report {
+    fingerprint: String,
+    title: String,
+    body: String,
+    comment: String,
+}
+
+struct GithubApi {
+    api_url: String,
+    repository: String,
+    authorization: String,
+    user_agent: String,
+    labels: Vec<String>,
+}
+
+impl GithubApi {
+    fn request(&self, client: &Client, method: reqwest::Method, path: &str) -> RequestBuilder {
+        client
+            .request(method, format!("{}{path}", self.api_url))
+            .header("Authorization", &self.authorization)
+            .header("Accept", "application/vnd.github+json")
+            .header("X-GitHub-Api-Version", "2022-11-28")
+            .header("User-Agent", &self.user_agent)
+    }
+
+    /// Comments on the existing issue for the report's fingerprint, or files a new issue if there isn't one.
+    fn file(&self, client: &Client, report: &Report) -> Result<(), Box<dyn std::error::Error>> {
+        let query = format!(
+            "repo:{} is:issue in:body \"{}\"",
+            self.repository, report.fingerprint
+        );
+        let search = self
+            .request(client, reqwest::Method::GET, "/search/issues")
+            .query(&[("q", query)])
+            .send()?
+            .error_for_status()?
+            .text()?;
+        let search: serde_json::Value = serde_json::from_str(&search)?;
+
+        let request = match search["items"][0]["number"].as_u64() {
+            Some(number) => self
+                .request(
+                    client,
+                    reqwest::Method::POST,
+                    &format!("/repos/{}/issues/{number}/comments", self.repository),
+                )
+                .body(json!({ "body": report.comment }).to_string()),
+            None => self
+                .request(
+                    client,
+                    reqwest::Method::POST,
+                    &format!("/repos/{}/issues", self.repository),
+                )
+                .body(
+                    json!({
+                        "title": report.title,
+                        "body": report.body,
+                        "labels": self.labels,
+                    })
+                    .to_string(),
+                ),
+        };
+
+        request
+            .header("Content-Type", "application/json")
+            .send()?
+            .error_for_status()?;
+        Ok(())
+    }
+}
+
+struct GithubIssuesBattery {
+    home: Option<String>,
+    reported: Mutex<HashSet<String>>,
+    sender: Mutex<Option<mpsc::Sender<Report>>>,
+    thread: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl Battery for GithubIssuesBattery {
+    fn record_envelope(&self, envelope: &Envelope) {
+        let Some(report) = build_report(envelope, self.home.as_deref()) else {
+            return;
+        };
+
+        if !self
+            .reported
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(report.fingerprint.clone())
+        {
+            return;
+        }
+
+        if let Some(sender) = self
+            .sender
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .as_ref()
+        {
+            sender.send(report).ok();
+        }
+    }
+
+    fn shutdown(&self) {
+        self.sender
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take();
+
+        if let Some(thread) = self
+            .thread
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take()
+        {
+            crate::worker::join(thread);
+        }
+    }
+}
+
+/// Builds the issue and comment reported for an error [`Envelope`], returning `None` for any other telemetry.
+fn build_report(envelope: &Envelope, home: Option<&str>) -> Option<Report> {
+    let EnvelopePayload::Error {
+        message,
+        causes,
+        fields,
+        backtrace,
+    } = &envelope.payload
+    else {
+        return None;
+    };
+
+    let fingerprint = fingerprint(message, causes);
+    let platform = format!("{} ({})", std::env::consts::OS, std::env::consts::ARCH);
+
+    let mut title = format!("{}: {message}", envelope.service);
+    if title.chars().count() > 120 {
+        title = title.chars().take(119).chain(['…']).collect();
+    }
+
+    let mut body = format!("{message}\n\n");
+    for cause in causes {
+        writeln!(body, "- Caused by: {cause}").ok();
+    }
+
+    body.push_str("\n| Property | Value |\n| --- | --- |\n");
+    writeln!(body, "| Version | `{}` |", envelope.version).ok();
+    writeln!(body, "| Platform | `{platform}` |").ok();
+    for (key, value) in fields {
+        writeln!(body, "| {key} | `{}` |", value.replace('|', "\\|")).ok();
+    }
+
+    if let Some(backtrace) = backtrace {
+        let backtrace: String = sanitize_backtrace(backtrace, home)
+            .chars()
+            .take(MAX_BACKTRACE_LENGTH)
+            .collect();
+        write!(
+            body,
+            "\n<details>\n<summary>Backtrace</summary>\n\n```\n{backtrace}\n```\n\n</details>\n"
+        )
+        .ok();
+    }
+
+    writeln!(body, "\n**Fingerprint:** `{fingerprint}`").ok();
+
+    Some(Report {
+        comment: format!(
+            "This error occurred again in version `{}` on `{platform}`.",
+            envelope.version
+        ),
+        fingerprint,
+        title,
+        body,
+    })
+}
+
+/// Computes a stable fingerprint for an error, ignoring numbers (such as IDs and ports) which vary between occurrences.
+fn fingerprint(message: &str, causes: &[String]) -> String {
+    let mut previous_digit = false;
+    let normalized: String = std::iter::once(message)
+        .chain(causes.iter().map(String::as_str))
+        .flat_map(|part| part.chars().chain(['\n']))
+        .filter_map(|c| {
+            let digit = c.is_ascii_digit();
+            let repeated = digit && previous_digit;
+            previous_digit = digit;
+
+            (!repeated).then_some(if digit { '#' } else { c })
+        })
+        .collect();
+
+    format!("tb-{:016x}", fnv1a(normalized.bytes()))
+}
+
+/// Removes the user's home directory from a backtrace, avoiding leaking their username in a public issue.
+fn sanitize_backtrace(backtrace: &str, home: Option<&str>) -> String {
+    match home {
+        Some(home) => backtrace.replace(home.trim_end_matches(['/', '\\']), "~"),
+        None => backtrace.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Session;
+
+    #[test]
+    fn deduplicated_reports() {
+        let metadata = Session::new("example", "0.0.1");
+        let error = |message: &str| {
+            Envelope::new(
+                &metadata,
+                EnvelopePayload::Error {
+                    message: message.into(),
+                    causes: vec!["Connection refused (os error 111)".into()],
+                    fields: [("command".to_string(), "sync".to_string())].into(),
+                    backtrace: Some(
+                        "0: example::main\n    at /home/alice/src/example/src/main.rs:12:5".into(),
+                    ),
+                },
+            )
+        };
+
+        let report = build_report(
+            &error("Could not connect to 127.0.0.1:8080"),
+            Some("/home/alice/"),
+        )
+        .unwrap();
+        assert_eq!(report.title, "example: Could not connect to 127.0.0.1:8080");
+        assert!(report.body.contains("| Version | `0.0.1` |"));
+        assert!(report.body.contains("| command | `sync` |"));
+        assert!(report.body.contains("at ~/src/example/src/main.rs:12:5"));
+        assert!(!report.body.contains("alice"));
+        assert!(report
+            .body
+            .contains(&format!("**Fingerprint:** `{}`", report.fingerprint)));
+
+        let other_port = build_report(&error("Could not connect to 127.0.0.1:9090"), None).unwrap();
+        assert_eq!(report.fingerprint, other_port.fingerprint);
+
+        let different = build_report(&error("Could not open the database"), None).unwrap();
+        assert_ne!(report.fingerprint, different.fingerprint);
+
+        let page_view = Envelope::new(
+            &metadata,
+            EnvelopePayload::PageView {
+                page: "/settings".into(),
+            },
+        );
+        assert!(build_report(&page_view, None).is_none());
+    }
+}
@@ -0,0 +1,169 @@
+use std::{
+    borrow::Cow,
+    hash::{BuildHasher, Hasher},
+    sync::{atomic::AtomicBool, Arc},
+    time::Duration,
+};
+
+use serde_json::json;
+
+use crate::{
+    batcher::BatchDispatcher, queue::DEFAULT_QUEUE_CAPACITY, Battery, BatteryBuilder, DropPolicy,
+    Envelope, EnvelopePayload, Metadata,
+};
+
+/// The maximum number of hits which GoatCounter accepts in a single request.
+const MAX_HITS_PER_REQUEST: usize = 100;
+
+/// A [GoatCounter](https://www.goatcounter.com) integration which reports page views and custom events
+/// to a GoatCounter site using its API.
+///
+/// <div class="warning">
+///
+/// This integration requires the `goatcounter` feature to be enabled.
+///
+/// </div>
+///
+/// Page views recorded using [`Session::record_new_page`](crate::Session::record_new_page) are counted as hits
+/// for the page's path, while custom events recorded using [`Session::record_event`](crate::Session::record_event)
+/// are counted as GoatCounter events named after the event. All hits from a single process share a randomly
+/// generated session, so that each run of your application is counted as a single visitor. You will need to
+/// create an API token with the "Record pageviews" permission for your site.
+///
+/// ## Example
+/// ```no_run
+/// use tracing_batteries::{Session, GoatCounter};
+///
+/// let session = Session::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+///   .with_battery(GoatCounter::new("my-site", "your-api-token"));
+///
+/// session.record_new_page("/settings");
+/// session.shutdown();
+/// ```
+pub struct GoatCounter {
+    url: Cow<'static, str>,
+    token: Cow<'static, str>,
+    queue_capacity: usize,
+    drop_policy: DropPolicy,
+}
+
+impl GoatCounter {
+    /// Configures the GoatCounter integration for the hosted site with the provided site code.
+    pub fn new<C: Into<Cow<'static, str>>, T: Into<Cow<'static, str>>>(code: C, token: T) -> Self {
+        Self {
+            url: format!("https://{}.goatcounter.com", code.into()).into(),
+            token: token.into(),
+            queue_capacity: DEFAULT_QUEUE_CAPACITY,
+            drop_policy: DropPolicy::default(),
+        }
+    }
+
+    /// Overrides the URL of the GoatCounter instance, for use with self-hosted installations.
+    pub fn with_url<U: Into<Cow<'static, str>>>(self, url: U) -> Self {
+        Self {
+            url: url.into(),
+            ..self
+        }
+    }
+
+    /// Configures the maximum number of hits which may be waiting to be sent (10,000 by default), and which
+    /// are dropped when a burst of activity fills the queue.
+    pub fn with_queue_limits(self, capacity: usize, policy: DropPolicy) -> Self {
+        Self {
+            queue_capacity: capacity,
+            drop_policy: policy,
+            ..self
+        }
+    }
+}
+
+impl BatteryBuilder for GoatCounter {
+    fn setup(self, _metadata: &Metadata, _enabled: Arc<AtomicBool>) -> Box<dyn Battery> {
+        let url = format!("{}/api/v0/count", self.url.trim_end_matches('/'));
+        let authorization = format!("Bearer {}", self.token);
+
+        Box::new(GoatCounterBattery {
+            session: {
+                let mut hasher = std::collections::hash_map::RandomState::new().build_hasher();
+                hasher.write_u32(std::process::id());
+                format!("{:016x}", hasher.finish())
+            },
+            dispatcher: BatchDispatcher::new(
+                "goatcounter",
+                MAX_HITS_PER_REQUEST,
+                Duration::from_secs(10),
+                3,
+                move |client, hits: &[serde_json::Value]| {
+                    client
+                        .post(&url)
+                        .header("Authorization", &authorization)
+                        .header("Content-Type", "application/json")
+                        .body(json!({ "hits": hits }).to_string())
+                },
+            )
+            .with_limits(self.queue_capacity, self.drop_policy),
+        })
+    }
+}
+
+struct GoatCounterBattery {
+    session: String,
+    dispatcher: BatchDispatcher<serde_json::Value>,
+}
+
+impl Battery for GoatCounterBattery {
+    fn record_envelope(&self, envelope: &Envelope) {
+        if let Some(hit) = build_hit(envelope, &self.session) {
+            self.dispatcher.push(envelope.importance, hit);
+        }
+    }
+
+    fn shutdown(&self) {
+        self.dispatcher.shutdown();
+    }
+}
+
+fn build_hit(envelope: &Envelope, session: &str) -> Option<serde_json::Value> {
+    let (path, event) = match &envelope.payload {
+        EnvelopePayload::PageView { page } => (page.as_str(), false),
+        EnvelopePayload::Event { name, .. } => (name.as_str(), true),
+        _ => return None,
+    };
+
+    Some(json!({
+        "path": path,
+        "event": event,
+        "session": session,
+        "created_at": envelope.timestamp.to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Session;
+
+    #[test]
+    fn hits() {
+        let metadata = Session::new("example", "0.0.1");
+
+        let page = Envelope::new(
+            &metadata,
+            EnvelopePayload::PageView {
+                page: "/settings".into(),
+            },
+        );
+        let hit = build_hit(&page, "abc").unwrap();
+        assert_eq!(hit["path"], "/settings");
+        assert_eq!(hit["event"], false);
+
+        let event = Envelope::new(
+            &metadata,
+            EnvelopePayload::Event {
+                name: "export_pdf".into(),
+                properties: Default::default(),
+            },
+        );
+        assert_eq!(build_hit(&event, "abc").unwrap()["event"], true);
+    }
+}
@@ -0,0 +1,207 @@
+use std::{
+    borrow::Cow,
+    sync::{atomic::AtomicBool, Arc},
+};
+
+use base64::Engine;
+use serde_json::json;
+
+use crate::{
+    dispatcher::HttpDispatcher, queue::DEFAULT_QUEUE_CAPACITY, Battery, BatteryBuilder, DropPolicy,
+    Envelope, EnvelopePayload, ErrorContext, Metadata, Metric, OpenTelemetry,
+    OpenTelemetryProtocol, WeakSession,
+};
+
+/// A [Grafana Cloud](https://grafana.com/products/cloud/) integration which ships traces (to Tempo),
+/// metrics (to Prometheus/Mimir), and logs (to Loki) through your stack's OTLP gateway.
+///
+/// <div class="warning">
+///
+/// This integration requires the `grafana-cloud` feature to be enabled.
+///
+/// </div>
+///
+/// The integration is configured using the instance ID and zone of your Grafana Cloud stack (both of which
+/// are shown on the stack's OpenTelemetry configuration page) and an access policy token with write access
+/// to traces, metrics, and logs. Traces and metrics are exported by the [`OpenTelemetry`] integration, while
+/// errors, custom events, and page views are written to Loki as OTLP log records.
+///
+/// ## Example
+/// ```no_run
+/// use tracing_batteries::{Session, GrafanaCloud};
+///
+/// let session = Session::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+///   .with_battery(GrafanaCloud::new("123456", "prod-eu-west-2", "glc_your-access-token"));
+///
+/// session.shutdown();
+/// ```
+pub struct GrafanaCloud {
+    instance_id: Cow<'static, str>,
+    zone: Cow<'static, str>,
+    token: Cow<'static, str>,
+    configure: Box<dyn FnOnce(OpenTelemetry) -> OpenTelemetry>,
+    queue_capacity: usize,
+    drop_policy: DropPolicy,
+}
+
+impl GrafanaCloud {
+    /// Configures the Grafana Cloud integration for the stack with the provided instance ID and zone (e.g. `prod-us-east-0`).
+    pub fn new<I, Z, T>(instance_id: I, zone: Z, token: T) -> Self
+    where
+        I: Into<Cow<'static, str>>,
+        Z: Into<Cow<'static, str>>,
+        T: Into<Cow<'static, str>>,
+    {
+        Self {
+            instance_id: instance_id.into(),
+            zone: zone.into(),
+            token: token.into(),
+            configure: Box::new(|otel| otel),
+            queue_capacity: DEFAULT_QUEUE_CAPACITY,
+            drop_policy: DropPolicy::default(),
+        }
+    }
+
+    /// Customizes the underlying [`OpenTelemetry`] integration used to export traces and metrics.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use tracing_batteries::{GrafanaCloud, OpenTelemetryLevel};
+    ///
+    /// GrafanaCloud::new("123456", "prod-eu-west-2", "glc_your-access-token")
+    ///   .with_opentelemetry(|otel| otel.with_default_level(OpenTelemetryLevel::DEBUG));
+    /// ```
+    pub fn with_opentelemetry<F>(self, configure: F) -> Self
+    where
+        F: FnOnce(OpenTelemetry) -> OpenTelemetry + 'static,
+    {
+        Self {
+            configure: Box::new(configure),
+            ..self
+        }
+    }
+
+    /// Configures the maximum number of log entries which may be waiting to be sent (10,000 by default), and which are
+    /// dropped when the Loki endpoint falls behind a burst of activity.
+    pub fn with_queue_limits(self, capacity: usize, policy: DropPolicy) -> Self {
+        Self {
+            queue_capacity: capacity,
+            drop_policy: policy,
+            ..self
+        }
+    }
+}
+
+impl BatteryBuilder for GrafanaCloud {
+    fn setup(self, metadata: &Metadata, enabled: Arc<AtomicBool>) -> Box<dyn Battery> {
+        let endpoint = format!("https://otlp-gateway-{}.grafana.net/otlp", self.zone);
+        let authorization = format!(
+            "Basic {}",
+            base64::engine::general_purpose::STANDARD
+                .encode(format!("{}:{}", self.instance_id, self.token))
+        );
+
+        let otel = OpenTelemetry::new("")
+            .with_endpoint(endpoint.clone())
+            .with_protocol(OpenTelemetryProtocol::HttpBinary)
+            .with_header("Authorization", authorization.clone());
+
+        Box::new(GrafanaCloudBattery {
+            inner: (self.configure)(otel).setup(metadata, enabled),
+            logs_url: format!("{endpoint}/v1/logs"),
+            authorization,
+            dispatcher: HttpDispatcher::new("grafana-cloud")
+                .with_limits(self.queue_capacity, self.drop_policy),
+        })
+    }
+}
+
+struct GrafanaCloudBattery {
+    inner: Box<dyn Battery>,
+    logs_url: String,
+    authorization: String,
+    dispatcher: HttpDispatcher,
+}
+
+impl Battery for GrafanaCloudBattery {
+    fn record_error(&self, error: &dyn std::error::Error) {
+        self.inner.record_error(error)
+    }
+
+    fn record_error_with(&self, error: &dyn std::error::Error, context: &ErrorContext) {
+        self.inner.record_error_with(error, context)
+    }
+
+    fn record_envelope(&self, envelope: &Envelope) {
+        let body = build_log_record(envelope).to_string();
+        let url = self.logs_url.clone();
+        let authorization = self.authorization.clone();
+        self.dispatcher
+            .dispatch(envelope.importance, move |client| {
+                client
+                    .post(url)
+                    .header("Content-Type", "application/json")
+                    .header("Authorization", authorization)
+                    .body(body)
+            });
+    }
+
+    fn record_metric(&self, metric: &Metric) {
+        self.inner.record_metric(metric)
+    }
+
+    fn accepts_weighted_histograms(&self) -> bool {
+        self.inner.accepts_weighted_histograms()
+    }
+
+    fn shutdown(&self) {
+        self.dispatcher.shutdown();
+        self.inner.shutdown();
+    }
+
+    fn attached(&self, session: WeakSession) {
+        self.inner.attached(session)
+    }
+
+    fn validate(&self) -> Vec<crate::ValidationCheck> {
+        self.inner.validate()
+    }
+}
+
+/// Converts an [`Envelope`] into an OTLP/JSON logs request containing a single log record.
+fn build_log_record(envelope: &Envelope) -> serde_json::Value {
+    let severity = match envelope.payload {
+        EnvelopePayload::Error { .. } => ("ERROR", 17),
+        _ => ("INFO", 9),
+    };
+
+    let mut resource_attributes = vec![
+        json!({ "key": "service.name", "value": { "stringValue": envelope.service } }),
+        json!({ "key": "service.version", "value": { "stringValue": envelope.version } }),
+    ];
+    resource_attributes.extend(
+        envelope
+            .context
+            .iter()
+            .map(|(key, value)| json!({ "key": key, "value": { "stringValue": value } })),
+    );
+
+    json!({
+        "resourceLogs": [{
+            "resource": { "attributes": resource_attributes },
+            "scopeLogs": [{
+                "scope": { "name": "tracing-batteries" },
+                "logRecords": [{
+                    "timeUnixNano": envelope
+                        .timestamp
+                        .timestamp_nanos_opt()
+                        .unwrap_or_default()
+                        .to_string(),
+                    "severityText": severity.0,
+                    "severityNumber": severity.1,
+                    "body": { "stringValue": envelope.to_json() },
+                }],
+            }],
+        }],
+    })
+}
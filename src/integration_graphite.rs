@@ -0,0 +1,338 @@
+use std::{
+    borrow::Cow,
+    io::Write,
+    net::{TcpStream, UdpSocket},
+    sync::{atomic::AtomicBool, Arc, Mutex, PoisonError},
+    thread::JoinHandle,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    queue::{Pop, PriorityQueue, DEFAULT_QUEUE_CAPACITY, SHUTDOWN_TIMEOUT},
+    Battery, BatteryBuilder, DropPolicy, Importance, Metadata, Metric,
+};
+
+/// The maximum number of points which are sent in a single pickle frame.
+const MAX_PICKLE_BATCH: usize = 500;
+
+/// The protocol used to deliver metrics to Graphite.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GraphiteProtocol {
+    /// The plaintext protocol over TCP (usually port 2003).
+    Tcp,
+    /// The plaintext protocol over UDP (usually port 2003), sending one datagram per point.
+    Udp,
+    /// The pickle protocol over TCP (usually port 2004), which sends points in batches.
+    Pickle,
+}
+
+/// A [Graphite](https://graphiteapp.org) integration which writes metrics recorded through the session's
+/// metrics facade to a Carbon receiver.
+///
+/// <div class="warning">
+///
+/// This integration requires the `graphite` feature to be enabled.
+///
+/// </div>
+///
+/// Each measurement is written to `{prefix}.{metric name}`, where the prefix defaults to your service name,
+/// and the metric's attributes are attached as Graphite tags (e.g. `my-service.http.requests;status=200`).
+/// Metrics are delivered using the plaintext protocol over TCP unless another [`GraphiteProtocol`] is selected.
+/// Graphite has no notion of weighted observations, so histograms aggregated by
+/// [`Session::with_metric_aggregation`](crate::Session::with_metric_aggregation) are written as the mean of each
+/// bucket, once per bucket.
+///
+/// ## Example
+/// ```no_run
+/// use tracing_batteries::{Session, Graphite, GraphiteProtocol};
+///
+/// let session = Session::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+///   .with_battery(Graphite::new("graphite.example.com:2004")
+///     .with_protocol(GraphiteProtocol::Pickle)
+///     .with_prefix("apps.my-service"));
+///
+/// session.shutdown();
+/// ```
+pub struct Graphite {
+    address: Cow<'static, str>,
+    protocol: GraphiteProtocol,
+    prefix: Option<Cow<'static, str>>,
+    queue_capacity: usize,
+    drop_policy: DropPolicy,
+}
+
+impl Graphite {
+    /// Configures the Graphite integration to send metrics to the provided Carbon receiver (e.g. `localhost:2003`).
+    pub fn new<A: Into<Cow<'static, str>>>(address: A) -> Self {
+        Self {
+            address: address.into(),
+            protocol: GraphiteProtocol::Tcp,
+            prefix: None,
+            queue_capacity: DEFAULT_QUEUE_CAPACITY,
+            drop_policy: DropPolicy::default(),
+        }
+    }
+
+    /// Configures the protocol used to deliver metrics.
+    pub fn with_protocol(self, protocol: GraphiteProtocol) -> Self {
+        Self { protocol, ..self }
+    }
+
+    /// Overrides the prefix which is prepended to every metric path (defaults to the service name).
+    pub fn with_prefix<P: Into<Cow<'static, str>>>(self, prefix: P) -> Self {
+        Self {
+            prefix: Some(prefix.into()),
+            ..self
+        }
+    }
+
+    /// Configures the maximum number of points which may be waiting to be sent (10,000 by default), and which are
+    /// dropped when the Carbon receiver falls behind a burst of metrics.
+    pub fn with_queue_limits(self, capacity: usize, policy: DropPolicy) -> Self {
+        Self {
+            queue_capacity: capacity,
+            drop_policy: policy,
+            ..self
+        }
+    }
+}
+
+impl BatteryBuilder for Graphite {
+    fn setup(self, metadata: &Metadata, _enabled: Arc<AtomicBool>) -> Box<dyn Battery> {
+        let queue = Arc::new(PriorityQueue::<GraphitePoint>::new("graphite"));
+        queue.set_limits(self.queue_capacity, self.drop_policy);
+
+        let points = queue.clone();
+        let address = self.address.to_string();
+        let protocol = self.protocol;
+        let thread = std::thread::Builder::new()
+            .name("tracing-batteries-graphite".into())
+            .spawn(move || {
+                let mut connection = GraphiteConnection::new(address, protocol);
+                while let Pop::Item(point) = points.pop(None) {
+                    let mut batch = vec![point];
+                    while batch.len() < MAX_PICKLE_BATCH {
+                        match points.pop(Some(Instant::now())) {
+                            Pop::Item(point) => batch.push(point),
+                            _ => break,
+                        }
+                    }
+
+                    connection.send(&batch);
+                }
+            })
+            .ok();
+
+        Box::new(GraphiteBattery {
+            prefix: sanitize_path(&self.prefix.unwrap_or_else(|| metadata.service.clone())),
+            queue,
+            thread: Mutex::new(thread),
+        })
+    }
+}
+
+struct GraphitePoint {
+    path: String,
+    value: f64,
+    timestamp: u64,
+}
+
+struct GraphiteBattery {
+    prefix: String,
+    queue: Arc<PriorityQueue<GraphitePoint>>,
+    thread: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl Battery for GraphiteBattery {
+    fn record_metric(&self, metric: &Metric) {
+        let point = GraphitePoint {
+            path: format_path(&self.prefix, metric),
+            value: metric.value,
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+        };
+
+        self.queue.push(Importance::Normal, point);
+    }
+
+    fn shutdown(&self) {
+        self.queue.close(SHUTDOWN_TIMEOUT);
+
+        if let Some(thread) = self
+            .thread
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take()
+        {
+            crate::worker::join(thread);
+        }
+    }
+}
+
+struct GraphiteConnection {
+    address: String,
+    protocol: GraphiteProtocol,
+    tcp: Option<TcpStream>,
+    udp: Option<UdpSocket>,
+}
+
+impl GraphiteConnection {
+    fn new(address: String, protocol: GraphiteProtocol) -> Self {
+        Self {
+            address,
+            protocol,
+            tcp: None,
+            udp: None,
+        }
+    }
+
+    fn send(&mut self, batch: &[GraphitePoint]) {
+        match self.protocol {
+            GraphiteProtocol::Udp => {
+                if self.udp.is_none() {
+                    self.udp = UdpSocket::bind("0.0.0.0:0")
+                        .and_then(|socket| socket.connect(&self.address).map(|_| socket))
+                        .ok();
+                }
+
+                if let Some(socket) = self.udp.as_ref() {
+                    for point in batch {
+                        socket.send(format_plaintext(point).as_bytes()).ok();
+                    }
+                }
+            }
+            GraphiteProtocol::Tcp => {
+                let payload: String = batch.iter().map(format_plaintext).collect();
+                self.send_tcp(payload.as_bytes());
+            }
+            GraphiteProtocol::Pickle => {
+                let payload = format_pickle(batch);
+                let mut frame = (payload.len() as u32).to_be_bytes().to_vec();
+                frame.extend(payload);
+                self.send_tcp(&frame);
+            }
+        }
+    }
+
+    fn send_tcp(&mut self, payload: &[u8]) {
+        // Retry once on a fresh connection if the existing one has been closed.
+        for _ in 0..2 {
+            if self.tcp.is_none() {
+                self.tcp = TcpStream::connect(&self.address)
+                    .and_then(|stream| {
+                        stream.set_write_timeout(Some(Duration::from_secs(10)))?;
+                        Ok(stream)
+                    })
+                    .ok();
+            }
+
+            match self.tcp.as_mut().map(|stream| stream.write_all(payload)) {
+                Some(Err(_)) => self.tcp = None,
+                _ => break,
+            }
+        }
+    }
+}
+
+fn format_path(prefix: &str, metric: &Metric) -> String {
+    let mut path = if prefix.is_empty() {
+        sanitize_path(metric.name)
+    } else {
+        format!("{prefix}.{}", sanitize_path(metric.name))
+    };
+
+    for (key, value) in metric.attributes {
+        let (key, value) = (sanitize_tag(key), sanitize_tag(value));
+        if !key.is_empty() && !value.is_empty() {
+            path.push_str(&format!(";{key}={value}"));
+        }
+    }
+
+    path
+}
+
+fn sanitize_path(value: &str) -> String {
+    value
+        .chars()
+        .map(|c| match c {
+            c if c.is_whitespace() => '_',
+            ';' | '=' => '_',
+            c => c,
+        })
+        .collect()
+}
+
+fn sanitize_tag(value: &str) -> String {
+    value
+        .chars()
+        .filter(|c| !matches!(c, ';' | '~' | '!' | '^' | '=') && !c.is_whitespace())
+        .collect()
+}
+
+fn format_plaintext(point: &GraphitePoint) -> String {
+    format!("{} {} {}\n", point.path, point.value, point.timestamp)
+}
+
+/// Encodes a batch of points as a protocol 2 pickle of `[(path, (timestamp, value)), ...]`.
+fn format_pickle(batch: &[GraphitePoint]) -> Vec<u8> {
+    let mut pickle = vec![0x80, 0x02, b']', b'('];
+    for point in batch {
+        pickle.push(b'X');
+        pickle.extend((point.path.len() as u32).to_le_bytes());
+        pickle.extend(point.path.as_bytes());
+
+        pickle.extend([0x8a, 0x08]);
+        pickle.extend(point.timestamp.to_le_bytes());
+
+        pickle.push(b'G');
+        pickle.extend(point.value.to_be_bytes());
+
+        pickle.extend([0x86, 0x86]);
+    }
+    pickle.extend([b'e', b'.']);
+    pickle
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MetricKind;
+
+    #[test]
+    fn tagged_path() {
+        let metric = Metric {
+            name: "http.requests",
+            kind: MetricKind::Counter,
+            value: 1.0,
+            attributes: &[("route", "/users"), ("status", "200 OK")],
+            count: 1,
+        };
+
+        assert_eq!(
+            format_path("my-service", &metric),
+            "my-service.http.requests;route=/users;status=200OK"
+        );
+    }
+
+    #[test]
+    fn pickle() {
+        let pickle = format_pickle(&[GraphitePoint {
+            path: "a".into(),
+            value: 1.0,
+            timestamp: 2,
+        }]);
+
+        assert_eq!(
+            pickle,
+            [
+                &[0x80, 0x02, b']', b'(', b'X', 1, 0, 0, 0, b'a'][..],
+                &[0x8a, 0x08, 2, 0, 0, 0, 0, 0, 0, 0],
+                &[b'G', 0x3f, 0xf0, 0, 0, 0, 0, 0, 0],
+                &[0x86, 0x86, b'e', b'.'],
+            ]
+            .concat()
+        );
+    }
+}
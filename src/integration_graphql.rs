@@ -0,0 +1,431 @@
+use std::{
+    collections::HashSet,
+    sync::{Arc, Mutex, OnceLock, PoisonError},
+};
+
+use async_graphql::{
+    extensions::{
+        Extension, ExtensionContext, ExtensionFactory, NextExecute, NextParseQuery, NextRequest,
+        NextResolve, ResolveInfo,
+    },
+    parser::types::ExecutableDocument,
+    Response, ServerError, ServerResult, Value, Variables,
+};
+use tracing::{field::Empty, Instrument};
+
+use crate::Session;
+
+/// Creates a span representing the execution of a GraphQL operation, populated with the OpenTelemetry
+/// [GraphQL semantic convention](https://opentelemetry.io/docs/specs/semconv/graphql/graphql-spans/) attributes.
+///
+/// <div class="warning">
+///
+/// This function requires the `graphql` feature to be enabled.
+///
+/// </div>
+///
+/// This is used by the [`GraphQLTracing`] extension for `async-graphql`, and may be used directly to instrument
+/// other GraphQL servers (such as `juniper`). The span is named after the operation, and you may record its
+/// `graphql.operation.type` once the document has been parsed, along with `otel.status_code` if it fails.
+///
+/// ## Example
+/// ```rust
+/// use tracing::Instrument;
+/// use tracing_batteries::graphql_span;
+///
+/// # async fn run() {
+/// let span = graphql_span(Some("GetUser"));
+/// async {
+///   // Execute the operation...
+/// }
+/// .instrument(span)
+/// .await;
+/// # }
+/// ```
+pub fn graphql_span(operation_name: Option<&str>) -> tracing::Span {
+    tracing::info_span!(
+        "GraphQL operation",
+        otel.name = operation_name.unwrap_or("GraphQL Operation"),
+        otel.status_code = Empty,
+        graphql.operation.name = operation_name,
+        "graphql.operation.type" = Empty,
+    )
+}
+
+/// An [async-graphql](https://async-graphql.github.io) extension which creates a span for each GraphQL operation
+/// executed by your schema, and reports the errors returned by its resolvers to the [`Session`].
+///
+/// <div class="warning">
+///
+/// This integration requires the `graphql` feature to be enabled.
+///
+/// </div>
+///
+/// GraphQL services typically expose a single `POST /graphql` endpoint, which leaves HTTP instrumentation unable to
+/// tell operations apart. This extension creates a span for each operation (see [`graphql_span`]), named after its
+/// type and name (e.g. `query GetUser`), and records each resolver error with `graphql.path` and `graphql.field`
+/// context. Errors in the request itself (such as parsing or validation failures) mark the span as failed, but are
+/// not recorded since they are usually the client's mistake. When telemetry has been disabled through
+/// [`Session::enable`], operations are executed without instrumentation.
+///
+/// ## Example
+/// ```no_run
+/// use async_graphql::{EmptyMutation, EmptySubscription, Object, Schema};
+/// use tracing_batteries::{Session, OpenTelemetry, GraphQLTracing};
+///
+/// struct Query;
+///
+/// #[Object]
+/// impl Query {
+///   async fn hello(&self) -> &str {
+///     "Hello, world!"
+///   }
+/// }
+///
+/// let session = Session::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+///   .with_battery(OpenTelemetry::new("localhost:4317"));
+///
+/// let schema = Schema::build(Query, EmptyMutation, EmptySubscription)
+///   .extension(GraphQLTracing::new(&session))
+///   .finish();
+///
+/// session.shutdown();
+/// ```
+pub struct GraphQLTracing {
+    session: Session,
+}
+
+impl GraphQLTracing {
+    /// Creates a new extension which reports operations and resolver errors to the provided [`Session`].
+    pub fn new(session: &Session) -> Self {
+        Self {
+            session: session.clone(),
+        }
+    }
+}
+
+impl ExtensionFactory for GraphQLTracing {
+    fn create(&self) -> Arc<dyn Extension> {
+        Arc::new(GraphQLTracingExtension {
+            session: self.session.clone(),
+            span: OnceLock::new(),
+            operation_type: Mutex::new(None),
+            errors: Mutex::new(HashSet::new()),
+        })
+    }
+}
+
+/// The extension created by [`GraphQLTracing`] for each request handled by the schema.
+struct GraphQLTracingExtension {
+    session: Session,
+    /// The operation's span, which is created when the request starts so that it is nested within the caller's span.
+    span: OnceLock<tracing::Span>,
+    operation_type: Mutex<Option<String>>,
+    /// Identifies the resolver errors which have already been recorded, by their message, locations, and path.
+    errors: Mutex<HashSet<String>>,
+}
+
+impl GraphQLTracingExtension {
+    fn span(&self) -> tracing::Span {
+        self.span.get().cloned().unwrap_or_else(tracing::Span::none)
+    }
+}
+
+#[async_trait::async_trait]
+impl Extension for GraphQLTracingExtension {
+    async fn request(&self, ctx: &ExtensionContext<'_>, next: NextRequest<'_>) -> Response {
+        let span = self
+            .span
+            .get_or_init(|| {
+                if self.session.is_enabled() {
+                    graphql_span(None)
+                } else {
+                    tracing::Span::none()
+                }
+            })
+            .clone();
+
+        let response = next.run(ctx).instrument(span.clone()).await;
+        if response.is_err() {
+            span.record("otel.status_code", "ERROR");
+        }
+
+        response
+    }
+
+    async fn parse_query(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        query: &str,
+        variables: &Variables,
+        next: NextParseQuery<'_>,
+    ) -> ServerResult<ExecutableDocument> {
+        let document = next.run(ctx, query, variables).await?;
+
+        // Documents may contain several operations, however they almost always share the same type.
+        let mut types = document
+            .operations
+            .iter()
+            .map(|(_, operation)| operation.node.ty);
+        if let Some(ty) = types.next().filter(|ty| types.all(|other| other == *ty)) {
+            let ty = ty.to_string();
+            let span = self.span();
+            span.record("graphql.operation.type", ty.as_str());
+            span.record("otel.name", ty.as_str());
+            *self
+                .operation_type
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner) = Some(ty);
+        }
+
+        Ok(document)
+    }
+
+    async fn execute(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        operation_name: Option<&str>,
+        next: NextExecute<'_>,
+    ) -> Response {
+        if let Some(name) = operation_name {
+            let operation_type = self
+                .operation_type
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .clone();
+
+            let span = self.span();
+            span.record("graphql.operation.name", name);
+            span.record(
+                "otel.name",
+                match operation_type {
+                    Some(ty) => format!("{ty} {name}"),
+                    None => name.to_string(),
+                },
+            );
+        }
+
+        next.run(ctx, operation_name).await
+    }
+
+    async fn resolve(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        info: ResolveInfo<'_>,
+        next: NextResolve<'_>,
+    ) -> ServerResult<Option<Value>> {
+        let (path, parent_type, name) = (info.path_node, info.parent_type, info.name);
+
+        let result = next.run(ctx, info).await;
+        if let Err(err) = &result {
+            self.span().record("otel.status_code", "ERROR");
+
+            // Errors returned by non-nullable fields propagate to their parent's resolver, so each error is only
+            // recorded by the resolver which first returned it.
+            let key = format!("{:?}", (&err.message, &err.locations, &err.path));
+            let first = self
+                .errors
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .insert(key);
+            if first {
+                self.session.record_error_with(
+                    &ResolverError(err.clone()),
+                    [
+                        ("graphql.path", path.to_string()),
+                        ("graphql.field", format!("{parent_type}.{name}")),
+                    ],
+                );
+            }
+        }
+
+        result
+    }
+}
+
+/// An error returned by a GraphQL resolver, wrapped so that it can be reported as a [`std::error::Error`].
+#[derive(Debug)]
+struct ResolverError(ServerError);
+
+impl std::fmt::Display for ResolverError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0.message)
+    }
+}
+
+impl std::error::Error for ResolverError {}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, future::Future, sync::atomic::AtomicBool};
+
+    use async_graphql::{EmptyMutation, EmptySubscription, Object, Request, Schema};
+    use tracing::{
+        field::{Field, Visit},
+        span::{Attributes, Id, Record},
+        Subscriber,
+    };
+    use tracing_subscriber::{layer::Context, prelude::*, registry::LookupSpan, Layer};
+
+    use super::*;
+    use crate::{Battery, BatteryBuilder, ErrorContext, Metadata};
+
+    struct Query;
+
+    #[Object]
+    impl Query {
+        async fn user(&self) -> User {
+            User
+        }
+    }
+
+    struct User;
+
+    #[Object]
+    impl User {
+        async fn name(&self) -> async_graphql::Result<String> {
+            Err("the user's name is unavailable".into())
+        }
+    }
+
+    #[test]
+    fn traces_operations() {
+        let recorded = Arc::new(Mutex::new(Vec::new()));
+        let session = Session::new("graphql-test", "1.0.0").with_battery(ErrorBattery {
+            recorded: recorded.clone(),
+        });
+
+        let schema = Schema::build(Query, EmptyMutation, EmptySubscription)
+            .extension(GraphQLTracing::new(&session))
+            .finish();
+
+        let spans = SpanFieldsLayer::default();
+        let subscriber = tracing_subscriber::registry().with(spans.clone());
+        let response =
+            tracing::subscriber::with_default(subscriber, || {
+                resolve(schema.execute(
+                    Request::new("query GetUser { user { name } }").operation_name("GetUser"),
+                ))
+            });
+
+        assert!(response.is_err());
+
+        let fields = spans.fields("GraphQL operation");
+        assert_eq!(
+            fields.get("otel.name").map(String::as_str),
+            Some("query GetUser")
+        );
+        assert_eq!(
+            fields.get("graphql.operation.type").map(String::as_str),
+            Some("query")
+        );
+        assert_eq!(
+            fields.get("graphql.operation.name").map(String::as_str),
+            Some("GetUser")
+        );
+        assert_eq!(
+            fields.get("otel.status_code").map(String::as_str),
+            Some("ERROR")
+        );
+
+        // The error propagates from `User.name` to `Query.user`, but is only recorded once.
+        assert_eq!(
+            *recorded.lock().unwrap(),
+            vec![(
+                "the user's name is unavailable".to_string(),
+                "user.name".to_string(),
+                "User.name".to_string()
+            )]
+        );
+
+        session.shutdown();
+    }
+
+    /// Resolves a future using a no-op waker, which is sufficient for schemas whose resolvers never wait on I/O.
+    fn resolve<F: Future>(future: F) -> F::Output {
+        let mut context = std::task::Context::from_waker(std::task::Waker::noop());
+        let mut future = std::pin::pin!(future);
+        loop {
+            if let std::task::Poll::Ready(output) = future.as_mut().poll(&mut context) {
+                return output;
+            }
+        }
+    }
+
+    /// Records the latest value of each field on the spans created while it is installed, keyed by span name.
+    #[derive(Clone, Default)]
+    struct SpanFieldsLayer(Arc<Mutex<HashMap<&'static str, HashMap<String, String>>>>);
+
+    impl SpanFieldsLayer {
+        fn fields(&self, span: &str) -> HashMap<String, String> {
+            self.0
+                .lock()
+                .unwrap()
+                .get(span)
+                .cloned()
+                .unwrap_or_default()
+        }
+    }
+
+    struct FieldVisitor<'a>(&'a mut HashMap<String, String>);
+
+    impl Visit for FieldVisitor<'_> {
+        fn record_str(&mut self, field: &Field, value: &str) {
+            self.0.insert(field.name().to_string(), value.to_string());
+        }
+
+        fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+            self.0
+                .insert(field.name().to_string(), format!("{value:?}"));
+        }
+    }
+
+    impl<S> Layer<S> for SpanFieldsLayer
+    where
+        S: Subscriber + for<'a> LookupSpan<'a>,
+    {
+        fn on_new_span(&self, attrs: &Attributes<'_>, _id: &Id, _ctx: Context<'_, S>) {
+            let mut spans = self.0.lock().unwrap();
+            attrs.record(&mut FieldVisitor(
+                spans.entry(attrs.metadata().name()).or_default(),
+            ));
+        }
+
+        fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+            if let Some(span) = ctx.span(id) {
+                let mut spans = self.0.lock().unwrap();
+                values.record(&mut FieldVisitor(spans.entry(span.name()).or_default()));
+            }
+        }
+    }
+
+    type RecordedError = (String, String, String);
+
+    struct ErrorBattery {
+        recorded: Arc<Mutex<Vec<RecordedError>>>,
+    }
+
+    impl BatteryBuilder for ErrorBattery {
+        fn setup(self, _metadata: &Metadata, _enabled: Arc<AtomicBool>) -> Box<dyn Battery> {
+            Box::new(self)
+        }
+    }
+
+    impl Battery for ErrorBattery {
+        fn record_error_with(&self, error: &dyn std::error::Error, context: &ErrorContext) {
+            self.recorded.lock().unwrap().push((
+                error.to_string(),
+                context
+                    .fields
+                    .get("graphql.path")
+                    .cloned()
+                    .unwrap_or_default(),
+                context
+                    .fields
+                    .get("graphql.field")
+                    .cloned()
+                    .unwrap_or_default(),
+            ));
+        }
+    }
+}
@@ -0,0 +1,249 @@
+use std::{
+    borrow::Cow,
+    sync::{atomic::AtomicBool, Arc},
+    time::Duration,
+};
+
+use crate::{
+    batcher::BatchDispatcher, queue::DEFAULT_QUEUE_CAPACITY, Battery, BatteryBuilder, DropPolicy,
+    Envelope, EnvelopePayload, Importance, Metadata, Metric,
+};
+
+/// An [InfluxDB](https://www.influxdata.com) integration which writes metrics recorded through the session's
+/// metrics facade to an InfluxDB v2 bucket using the line protocol.
+///
+/// <div class="warning">
+///
+/// This integration requires the `influxdb` feature to be enabled.
+///
+/// </div>
+///
+/// Each measurement is written to a measurement named after the metric, with a `service` tag identifying your
+/// application alongside the metric's attributes. Points are batched and written using the `/api/v2/write` API,
+/// authenticated with an API token. Errors, custom events, and page views may also be written to an `events`
+/// measurement (as annotations for your dashboards) by calling [`InfluxDb::with_events`].
+///
+/// ## Example
+/// ```no_run
+/// use tracing_batteries::{Session, InfluxDb};
+///
+/// let session = Session::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+///   .with_battery(InfluxDb::new("http://localhost:8086", "my-org", "telemetry", "your-api-token")
+///     .with_events(true));
+///
+/// session.shutdown();
+/// ```
+pub struct InfluxDb {
+    url: Cow<'static, str>,
+    organization: Cow<'static, str>,
+    bucket: Cow<'static, str>,
+    token: Cow<'static, str>,
+    events: bool,
+    batch_size: usize,
+    flush_interval: Duration,
+    queue_capacity: usize,
+    drop_policy: DropPolicy,
+}
+
+impl InfluxDb {
+    /// Configures the InfluxDB integration to write to the provided organization and bucket.
+    pub fn new<U, O, B, T>(url: U, organization: O, bucket: B, token: T) -> Self
+    where
+        U: Into<Cow<'static, str>>,
+        O: Into<Cow<'static, str>>,
+        B: Into<Cow<'static, str>>,
+        T: Into<Cow<'static, str>>,
+    {
+        Self {
+            url: url.into(),
+            organization: organization.into(),
+            bucket: bucket.into(),
+            token: token.into(),
+            events: false,
+            batch_size: 1000,
+            flush_interval: Duration::from_secs(10),
+            queue_capacity: DEFAULT_QUEUE_CAPACITY,
+            drop_policy: DropPolicy::default(),
+        }
+    }
+
+    /// Configures whether errors, custom events, and page views are written to the `events` measurement.
+    pub fn with_events(self, enabled: bool) -> Self {
+        Self {
+            events: enabled,
+            ..self
+        }
+    }
+
+    /// Configures the maximum number of points which are written in a single request, and how frequently
+    /// partial batches are flushed.
+    pub fn with_batching(self, batch_size: usize, flush_interval: Duration) -> Self {
+        Self {
+            batch_size: batch_size.max(1),
+            flush_interval,
+            ..self
+        }
+    }
+
+    /// Configures the maximum number of points which may be waiting to be sent (10,000 by default), and which are
+    /// dropped when the server falls behind a burst of activity.
+    pub fn with_queue_limits(self, capacity: usize, policy: DropPolicy) -> Self {
+        Self {
+            queue_capacity: capacity,
+            drop_policy: policy,
+            ..self
+        }
+    }
+}
+
+impl BatteryBuilder for InfluxDb {
+    fn setup(self, metadata: &Metadata, _enabled: Arc<AtomicBool>) -> Box<dyn Battery> {
+        let url = format!("{}/api/v2/write", self.url.trim_end_matches('/'));
+        let query = [
+            ("org", self.organization.to_string()),
+            ("bucket", self.bucket.to_string()),
+            ("precision", "ns".to_string()),
+        ];
+        let authorization = format!("Token {}", self.token);
+
+        Box::new(InfluxDbBattery {
+            service: metadata.service.to_string(),
+            events: self.events,
+            dispatcher: BatchDispatcher::new(
+                "influxdb",
+                self.batch_size,
+                self.flush_interval,
+                3,
+                move |client, lines: &[String]| {
+                    client
+                        .post(&url)
+                        .query(&query)
+                        .header("Authorization", &authorization)
+                        .header("Content-Type", "text/plain; charset=utf-8")
+                        .body(lines.join("\n"))
+                },
+            )
+            .with_limits(self.queue_capacity, self.drop_policy),
+        })
+    }
+}
+
+struct InfluxDbBattery {
+    service: String,
+    events: bool,
+    dispatcher: BatchDispatcher<String>,
+}
+
+impl Battery for InfluxDbBattery {
+    fn record_envelope(&self, envelope: &Envelope) {
+        if !self.events {
+            return;
+        }
+
+        let kind = match envelope.payload {
+            EnvelopePayload::Error { .. } => "error",
+            EnvelopePayload::Event { .. } => "event",
+            EnvelopePayload::PageView { .. } => "page_view",
+            EnvelopePayload::Sealed { .. } | EnvelopePayload::Unknown => return,
+        };
+
+        self.dispatcher.push(
+            envelope.importance,
+            format!(
+                "events,service={},kind={} title={} {}",
+                escape_tag(&self.service),
+                kind,
+                escape_string(&envelope.summary()),
+                envelope.timestamp.timestamp_nanos_opt().unwrap_or_default()
+            ),
+        );
+    }
+
+    fn record_metric(&self, metric: &Metric) {
+        self.dispatcher.push(
+            Importance::Normal,
+            format_metric(
+                metric,
+                &self.service,
+                chrono::Utc::now().timestamp_nanos_opt().unwrap_or_default(),
+            ),
+        );
+    }
+
+    fn shutdown(&self) {
+        self.dispatcher.shutdown();
+    }
+}
+
+/// Formats a [`Metric`] as a line protocol point with a `value` field, and a `count` field for aggregated
+/// histogram measurements which represent more than one observation.
+fn format_metric(metric: &Metric, service: &str, timestamp: i64) -> String {
+    let mut line = format!(
+        "{},service={}",
+        escape_measurement(metric.name),
+        escape_tag(service)
+    );
+
+    for (key, value) in metric.attributes {
+        if !value.is_empty() {
+            line.push_str(&format!(",{}={}", escape_tag(key), escape_tag(value)));
+        }
+    }
+
+    line.push_str(&format!(" value={}", metric.value));
+    if metric.count > 1 {
+        line.push_str(&format!(",count={}i", metric.count));
+    }
+
+    format!("{line} {timestamp}")
+}
+
+fn escape_measurement(value: &str) -> String {
+    value.replace(',', "\\,").replace(' ', "\\ ")
+}
+
+fn escape_tag(value: &str) -> String {
+    value
+        .replace(',', "\\,")
+        .replace('=', "\\=")
+        .replace(' ', "\\ ")
+}
+
+fn escape_string(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MetricKind;
+
+    #[test]
+    fn line_protocol() {
+        let metric = Metric {
+            name: "http.requests",
+            kind: MetricKind::Counter,
+            value: 2.0,
+            attributes: &[("route", "/users/{id}"), ("status", "200 OK")],
+            count: 1,
+        };
+
+        assert_eq!(
+            format_metric(&metric, "my service", 1700000000000000000),
+            r"http.requests,service=my\ service,route=/users/{id},status=200\ OK value=2 1700000000000000000"
+        );
+
+        let metric = Metric {
+            name: "http.duration",
+            kind: MetricKind::Histogram,
+            value: 12.5,
+            attributes: &[],
+            count: 4,
+        };
+
+        assert_eq!(
+            format_metric(&metric, "api", 1700000000000000000),
+            "http.duration,service=api value=12.5,count=4i 1700000000000000000"
+        );
+    }
+}
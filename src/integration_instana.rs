@@ -0,0 +1,149 @@
+use std::{
+    borrow::Cow,
+    sync::{atomic::AtomicBool, Arc},
+};
+
+use crate::{Battery, BatteryBuilder, Metadata, OpenTelemetry, OpenTelemetryProtocol};
+
+/// The port on which the Instana host agent accepts OTLP/HTTP telemetry.
+const AGENT_OTLP_PORT: u16 = 4318;
+
+enum InstanaTarget {
+    Agent {
+        host: Cow<'static, str>,
+        port: u16,
+    },
+    Serverless {
+        endpoint: Cow<'static, str>,
+        agent_key: Cow<'static, str>,
+    },
+}
+
+/// An [IBM Instana](https://www.ibm.com/products/instana) integration which configures the [`OpenTelemetry`]
+/// integration to export traces and metrics to an Instana host agent, or directly to Instana's serverless
+/// acceptor when no agent is available.
+///
+/// <div class="warning">
+///
+/// This integration requires the `instana` feature to be enabled.
+///
+/// </div>
+///
+/// When using [`Instana::agent`], telemetry is sent to the agent's OTLP endpoint on the host identified by the
+/// `INSTANA_AGENT_HOST` environment variable (defaulting to `localhost`) and the process ID is attached so that
+/// the agent can correlate spans with the process it monitors. When using [`Instana::serverless`], telemetry is
+/// authenticated using your agent key (which may also be provided using the `INSTANA_AGENT_KEY` environment
+/// variable, alongside `INSTANA_ENDPOINT_URL`) and attributed to the host identified by the `host.name` context
+/// field (or the `HOSTNAME` environment variable).
+///
+/// ## Example
+/// ```no_run
+/// use tracing_batteries::{Session, Instana};
+///
+/// let session = Session::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+///   .with_battery(Instana::agent());
+///
+/// session.shutdown();
+/// ```
+pub struct Instana {
+    target: InstanaTarget,
+    configure: Box<dyn FnOnce(OpenTelemetry) -> OpenTelemetry>,
+}
+
+impl Instana {
+    /// Configures the Instana integration to export telemetry through the host agent.
+    pub fn agent() -> Self {
+        Self {
+            target: InstanaTarget::Agent {
+                host: std::env::var("INSTANA_AGENT_HOST")
+                    .map(Cow::Owned)
+                    .unwrap_or(Cow::Borrowed("localhost")),
+                port: AGENT_OTLP_PORT,
+            },
+            configure: Box::new(|otel| otel),
+        }
+    }
+
+    /// Configures the Instana integration to export telemetry directly to the provided serverless (OTLP) acceptor.
+    pub fn serverless<E: Into<Cow<'static, str>>, K: Into<Cow<'static, str>>>(
+        endpoint: E,
+        agent_key: K,
+    ) -> Self {
+        Self {
+            target: InstanaTarget::Serverless {
+                endpoint: std::env::var("INSTANA_ENDPOINT_URL")
+                    .map(Cow::Owned)
+                    .unwrap_or_else(|_| endpoint.into()),
+                agent_key: std::env::var("INSTANA_AGENT_KEY")
+                    .map(Cow::Owned)
+                    .unwrap_or_else(|_| agent_key.into()),
+            },
+            configure: Box::new(|otel| otel),
+        }
+    }
+
+    /// Overrides the host and port of the Instana agent's OTLP endpoint.
+    ///
+    /// This has no effect when exporting to the serverless acceptor.
+    pub fn with_agent<H: Into<Cow<'static, str>>>(self, host: H, port: u16) -> Self {
+        match self.target {
+            InstanaTarget::Agent { .. } => Self {
+                target: InstanaTarget::Agent {
+                    host: host.into(),
+                    port,
+                },
+                ..self
+            },
+            _ => self,
+        }
+    }
+
+    /// Customizes the underlying [`OpenTelemetry`] integration used to export traces and metrics.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use tracing_batteries::{Instana, OpenTelemetryLevel};
+    ///
+    /// Instana::agent()
+    ///   .with_opentelemetry(|otel| otel.with_default_level(OpenTelemetryLevel::DEBUG));
+    /// ```
+    pub fn with_opentelemetry<F>(self, configure: F) -> Self
+    where
+        F: FnOnce(OpenTelemetry) -> OpenTelemetry + 'static,
+    {
+        Self {
+            configure: Box::new(configure),
+            ..self
+        }
+    }
+}
+
+impl BatteryBuilder for Instana {
+    fn setup(self, metadata: &Metadata, enabled: Arc<AtomicBool>) -> Box<dyn Battery> {
+        let otel = OpenTelemetry::new("").with_protocol(OpenTelemetryProtocol::HttpBinary);
+
+        let otel = match self.target {
+            InstanaTarget::Agent { host, port } => otel
+                .with_endpoint(format!("http://{host}:{port}"))
+                .with_resource_attribute("process.pid", std::process::id().to_string()),
+            InstanaTarget::Serverless {
+                endpoint,
+                agent_key,
+            } => {
+                let host = metadata
+                    .context
+                    .get("host.name")
+                    .cloned()
+                    .or_else(|| std::env::var("HOSTNAME").ok().map(Cow::Owned))
+                    .unwrap_or_else(|| metadata.service.clone());
+
+                otel.with_endpoint(endpoint.trim_end_matches('/').to_string())
+                    .with_header("x-instana-key", agent_key)
+                    .with_header("x-instana-host", host.clone())
+                    .with_resource_attribute("host.name", host)
+            }
+        };
+
+        (self.configure)(otel).setup(metadata, enabled)
+    }
+}
@@ -0,0 +1,108 @@
+use std::{
+    borrow::Cow,
+    sync::{atomic::AtomicBool, Arc},
+};
+
+use crate::{Battery, BatteryBuilder, Metadata, OpenTelemetry};
+
+/// A [Jaeger](https://www.jaegertracing.io) integration which configures the [`OpenTelemetry`] integration
+/// to emit spans to a Jaeger agent using the Thrift compact protocol over UDP.
+///
+/// <div class="warning">
+///
+/// This integration requires the `jaeger` feature to be enabled.
+///
+/// </div>
+///
+/// This integration is intended for environments which still run bare Jaeger agents that don't accept OTLP;
+/// if your agent or collector accepts OTLP you should prefer the [`OpenTelemetry`] integration instead. The agent's
+/// address is read from the `OTEL_EXPORTER_JAEGER_AGENT_HOST` and `OTEL_EXPORTER_JAEGER_AGENT_PORT` environment
+/// variables, defaulting to `localhost:6831`.
+///
+/// Spans are packed into UDP packets of at most 65,000 bytes (configurable using [`Jaeger::with_max_packet_size`]),
+/// with large batches split across multiple packets. Any single span which cannot fit within a packet is dropped.
+/// Jaeger agents do not accept metrics, so metrics are not exported by this integration.
+///
+/// ## Example
+/// ```no_run
+/// use tracing_batteries::{Session, Jaeger};
+///
+/// let session = Session::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+///   .with_battery(Jaeger::new());
+///
+/// session.shutdown();
+/// ```
+pub struct Jaeger {
+    host: Cow<'static, str>,
+    port: u16,
+    max_packet_size: usize,
+    configure: Box<dyn FnOnce(OpenTelemetry) -> OpenTelemetry>,
+}
+
+impl Jaeger {
+    /// Configures the Jaeger integration to emit spans to the local Jaeger agent.
+    pub fn new() -> Self {
+        Self {
+            host: std::env::var("OTEL_EXPORTER_JAEGER_AGENT_HOST")
+                .map(Cow::Owned)
+                .unwrap_or(Cow::Borrowed("localhost")),
+            port: std::env::var("OTEL_EXPORTER_JAEGER_AGENT_PORT")
+                .ok()
+                .and_then(|port| port.parse().ok())
+                .unwrap_or(6831),
+            max_packet_size: 65_000,
+            configure: Box::new(|otel| otel),
+        }
+    }
+
+    /// Overrides the host and port of the Jaeger agent which spans are emitted to.
+    pub fn with_agent<H: Into<Cow<'static, str>>>(self, host: H, port: u16) -> Self {
+        Self {
+            host: host.into(),
+            port,
+            ..self
+        }
+    }
+
+    /// Configures the maximum size of each UDP packet sent to the agent, which should not exceed the agent's own limit.
+    pub fn with_max_packet_size(self, max_packet_size: usize) -> Self {
+        Self {
+            max_packet_size,
+            ..self
+        }
+    }
+
+    /// Customizes the underlying [`OpenTelemetry`] integration used to export spans.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use tracing_batteries::{Jaeger, OpenTelemetryLevel};
+    ///
+    /// Jaeger::new()
+    ///   .with_opentelemetry(|otel| otel.with_default_level(OpenTelemetryLevel::DEBUG));
+    /// ```
+    pub fn with_opentelemetry<F>(self, configure: F) -> Self
+    where
+        F: FnOnce(OpenTelemetry) -> OpenTelemetry + 'static,
+    {
+        Self {
+            configure: Box::new(configure),
+            ..self
+        }
+    }
+}
+
+impl Default for Jaeger {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BatteryBuilder for Jaeger {
+    fn setup(self, metadata: &Metadata, enabled: Arc<AtomicBool>) -> Box<dyn Battery> {
+        let otel = OpenTelemetry::new("")
+            .with_jaeger_agent(format!("{}:{}", self.host, self.port), self.max_packet_size);
+
+        (self.configure)(otel).setup(metadata, enabled)
+    }
+}
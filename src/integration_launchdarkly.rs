@@ -0,0 +1,166 @@
+use std::{borrow::Cow, time::Duration};
+
+use base64::Engine;
+
+use crate::{feature_flags::PolledFlags, FlagProvider, Str};
+
+/// A [`FlagProvider`] which reports the flags evaluated by [LaunchDarkly](https://launchdarkly.com) for a context
+/// (such as the current user or service instance).
+///
+/// <div class="warning">
+///
+/// This integration requires the `launchdarkly` feature to be enabled.
+///
+/// </div>
+///
+/// Flags are evaluated using LaunchDarkly's client-side evaluation endpoint, which requires your environment's
+/// client-side ID (rather than its SDK key, since the evaluated flags are attached to your telemetry). Evaluations
+/// are refreshed in the background every minute (see [`LaunchDarkly::with_interval`]) once the provider has been
+/// registered using [`Session::with_flag_provider`](crate::Session::with_flag_provider), with each flag reported
+/// using its JSON value (or the string itself, for string flags).
+///
+/// ## Example
+/// ```no_run
+/// use tracing_batteries::{Session, Sentry, LaunchDarkly};
+///
+/// let session = Session::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+///   .with_battery(Sentry::new("https://yourdsn@sentry.example.com"))
+///   .with_flag_provider(LaunchDarkly::new("your-client-side-id", "user-key")
+///     .with_attribute("plan", "enterprise"));
+///
+/// session.shutdown();
+/// ```
+pub struct LaunchDarkly {
+    client_side_id: Cow<'static, str>,
+    context: serde_json::Map<String, serde_json::Value>,
+    base_url: Cow<'static, str>,
+    interval: Duration,
+    flags: PolledFlags,
+}
+
+impl LaunchDarkly {
+    /// Creates a provider which evaluates flags for the user context with the provided key.
+    pub fn new<I: Into<Cow<'static, str>>, K: Into<String>>(client_side_id: I, key: K) -> Self {
+        let mut context = serde_json::Map::new();
+        context.insert("kind".into(), "user".into());
+        context.insert("key".into(), key.into().into());
+
+        Self {
+            client_side_id: client_side_id.into(),
+            context,
+            base_url: "https://clientsdk.launchdarkly.com".into(),
+            interval: Duration::from_secs(60),
+            flags: PolledFlags::new(),
+        }
+    }
+
+    /// Overrides the kind of context which flags are evaluated for (`user` by default).
+    pub fn with_kind<K: Into<String>>(mut self, kind: K) -> Self {
+        self.context.insert("kind".into(), kind.into().into());
+        self
+    }
+
+    /// Adds an attribute to the context which flags are evaluated for, allowing them to be targeted.
+    pub fn with_attribute<N: Into<String>, V: Into<serde_json::Value>>(
+        mut self,
+        name: N,
+        value: V,
+    ) -> Self {
+        self.context.insert(name.into(), value.into());
+        self
+    }
+
+    /// Overrides the URL of the LaunchDarkly client-side SDK API, allowing flags to be evaluated by a
+    /// relay proxy instead.
+    pub fn with_base_url<U: Into<Cow<'static, str>>>(self, base_url: U) -> Self {
+        Self {
+            base_url: base_url.into(),
+            ..self
+        }
+    }
+
+    /// Configures how frequently flags are re-evaluated.
+    pub fn with_interval(self, interval: Duration) -> Self {
+        Self { interval, ..self }
+    }
+}
+
+impl FlagProvider for LaunchDarkly {
+    fn flags(&self) -> Vec<(Str, Str)> {
+        self.flags.get()
+    }
+
+    fn start(&self) {
+        let url = format!(
+            "{}/sdk/evalx/{}/contexts/{}",
+            self.base_url.trim_end_matches('/'),
+            self.client_side_id,
+            base64::engine::general_purpose::URL_SAFE_NO_PAD
+                .encode(serde_json::Value::Object(self.context.clone()).to_string())
+        );
+
+        self.flags.start("launchdarkly", self.interval, move || {
+            let body = reqwest::blocking::Client::builder()
+                .timeout(Duration::from_secs(10))
+                .build()
+                .and_then(|client| client.get(&url).send())
+                .and_then(|response| response.error_for_status())
+                .and_then(|response| response.text())
+                .map_err(|err| {
+                    tracing::debug!(error = %err, "Failed to evaluate LaunchDarkly flags.");
+                })
+                .ok()?;
+
+            parse_evaluations(&body)
+        });
+    }
+}
+
+/// Parses the response of the client-side evaluation endpoint, which maps each flag's key to its evaluation.
+fn parse_evaluations(body: &str) -> Option<Vec<(Str, Str)>> {
+    let evaluations: serde_json::Map<String, serde_json::Value> =
+        serde_json::from_str(body).ok()?;
+
+    let mut flags: Vec<(Str, Str)> = evaluations
+        .into_iter()
+        .filter_map(|(key, evaluation)| {
+            let value = match evaluation.get("value")? {
+                serde_json::Value::String(value) => value.clone(),
+                value => value.to_string(),
+            };
+
+            Some((key.into(), value.into()))
+        })
+        .collect();
+    flags.sort();
+
+    Some(flags)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_evaluations() {
+        let flags = parse_evaluations(
+            r#"{
+                "new-checkout": { "value": true, "variation": 0, "version": 12 },
+                "banner-text": { "value": "Welcome back!", "variation": 2, "version": 3 },
+                "retry-limit": { "value": 3, "variation": 1, "version": 7 }
+            }"#,
+        )
+        .expect("the evaluations should be parsed");
+
+        assert_eq!(
+            flags,
+            vec![
+                ("banner-text".into(), "Welcome back!".into()),
+                ("new-checkout".into(), "true".into()),
+                ("retry-limit".into(), "3".into()),
+            ]
+        );
+
+        assert!(parse_evaluations("Unauthorized").is_none());
+    }
+}
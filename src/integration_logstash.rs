@@ -0,0 +1,241 @@
+use std::{
+    borrow::Cow,
+    collections::BTreeMap,
+    io::Write,
+    net::TcpStream,
+    sync::{atomic::AtomicBool, Arc, Mutex, PoisonError},
+    thread::JoinHandle,
+    time::Duration,
+};
+
+use serde_json::json;
+
+use crate::{
+    queue::{Pop, PriorityQueue, DEFAULT_QUEUE_CAPACITY, SHUTDOWN_TIMEOUT},
+    reconnect::Backoff,
+    tls, Battery, BatteryBuilder, DropPolicy, Envelope, Metadata,
+};
+
+/// A [Logstash](https://www.elastic.co/logstash) integration which ships errors, custom events, and page views
+/// as JSON lines to a Logstash `tcp` input (using the `json_lines` codec) or a Filebeat TCP input.
+///
+/// <div class="warning">
+///
+/// This integration requires the `logstash` feature to be enabled.
+///
+/// </div>
+///
+/// Each [`Envelope`] is written as a single line of JSON with the `@timestamp`, `@version` and `message` fields
+/// that Logstash expects, alongside the envelope's own fields and any static fields added using
+/// [`Logstash::with_field`]. Connections may optionally be secured using TLS with [`Logstash::with_tls`].
+///
+/// If the input becomes unreachable, the integration reconnects with an exponential backoff (up to one minute
+/// between attempts) while events wait in a bounded queue (configured using [`Logstash::with_queue_limits`]), from
+/// which the least important events are shed.
+///
+/// ## Example
+/// ```no_run
+/// use tracing_batteries::{Session, Logstash};
+///
+/// let session = Session::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+///   .with_battery(Logstash::new("logstash.example.com", 5000).with_tls());
+///
+/// session.shutdown();
+/// ```
+pub struct Logstash {
+    host: Cow<'static, str>,
+    port: u16,
+    tls: bool,
+    fields: BTreeMap<String, String>,
+    queue_capacity: usize,
+    drop_policy: DropPolicy,
+}
+
+impl Logstash {
+    /// Configures the Logstash integration to send events to the TCP input at the provided host and port.
+    pub fn new<H: Into<Cow<'static, str>>>(host: H, port: u16) -> Self {
+        Self {
+            host: host.into(),
+            port,
+            tls: false,
+            fields: BTreeMap::new(),
+            queue_capacity: DEFAULT_QUEUE_CAPACITY,
+            drop_policy: DropPolicy::default(),
+        }
+    }
+
+    /// Secures the connection to the Logstash input using TLS, verifying its certificate against the web PKI roots.
+    pub fn with_tls(self) -> Self {
+        Self { tls: true, ..self }
+    }
+
+    /// Adds a static field (such as `type` or `environment`) to every event sent to Logstash.
+    pub fn with_field<K: Into<String>, V: Into<String>>(self, key: K, value: V) -> Self {
+        let mut fields = self.fields;
+        fields.insert(key.into(), value.into());
+        Self { fields, ..self }
+    }
+
+    /// Configures the maximum number of events which may be waiting to be sent (10,000 by default), and which are
+    /// dropped when the input is unreachable for long enough to fill the queue.
+    pub fn with_queue_limits(self, capacity: usize, policy: DropPolicy) -> Self {
+        Self {
+            queue_capacity: capacity,
+            drop_policy: policy,
+            ..self
+        }
+    }
+}
+
+impl BatteryBuilder for Logstash {
+    fn setup(self, _metadata: &Metadata, _enabled: Arc<AtomicBool>) -> Box<dyn Battery> {
+        let queue = Arc::new(PriorityQueue::<String>::new("logstash"));
+        queue.set_limits(self.queue_capacity, self.drop_policy);
+
+        let lines = queue.clone();
+        let host = self.host.to_string();
+        let port = self.port;
+        let use_tls = self.tls;
+        let thread = std::thread::Builder::new()
+            .name("tracing-batteries-logstash".into())
+            .spawn(move || {
+                let mut connection = None;
+                let mut backoff = Backoff::default();
+                let mut pending = None;
+
+                loop {
+                    let line = match pending.take() {
+                        Some(line) => line,
+                        None => match lines.pop(None) {
+                            Pop::Item(line) => line,
+                            _ => break,
+                        },
+                    };
+
+                    // While the input is unreachable, newer events wait in the (bounded) queue.
+                    if connection.is_none() {
+                        if !lines.wait_until(backoff.retry_at()) {
+                            break;
+                        }
+
+                        match connect(&host, port, use_tls) {
+                            Ok(stream) => connection = Some(stream),
+                            Err(_) => {
+                                backoff.failed();
+                                pending = Some(line);
+                                continue;
+                            }
+                        }
+                    }
+
+                    let written = connection.as_mut().is_some_and(|stream| {
+                        stream
+                            .write_all(line.as_bytes())
+                            .and_then(|_| stream.flush())
+                            .is_ok()
+                    });
+
+                    if written {
+                        backoff.reset();
+                    } else {
+                        connection = None;
+                        backoff.failed();
+                        pending = Some(line);
+                    }
+                }
+            })
+            .ok();
+
+        Box::new(LogstashBattery {
+            fields: self.fields,
+            queue,
+            thread: Mutex::new(thread),
+        })
+    }
+}
+
+fn connect(
+    host: &str,
+    port: u16,
+    use_tls: bool,
+) -> Result<Box<dyn Write + Send>, Box<dyn std::error::Error>> {
+    if use_tls {
+        Ok(Box::new(tls::connect(host, port, Duration::from_secs(10))?))
+    } else {
+        let socket = TcpStream::connect((host, port))?;
+        socket.set_write_timeout(Some(Duration::from_secs(10)))?;
+        Ok(Box::new(socket))
+    }
+}
+
+struct LogstashBattery {
+    fields: BTreeMap<String, String>,
+    queue: Arc<PriorityQueue<String>>,
+    thread: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl Battery for LogstashBattery {
+    fn record_envelope(&self, envelope: &Envelope) {
+        let line = format_event(envelope, &self.fields);
+
+        self.queue.push(envelope.importance, line);
+    }
+
+    fn shutdown(&self) {
+        self.queue.close(SHUTDOWN_TIMEOUT);
+
+        if let Some(thread) = self
+            .thread
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take()
+        {
+            crate::worker::join(thread);
+        }
+    }
+}
+
+/// Formats an [`Envelope`] as a newline-terminated Logstash JSON event.
+fn format_event(envelope: &Envelope, fields: &BTreeMap<String, String>) -> String {
+    let mut event = serde_json::to_value(envelope).unwrap_or_else(|_| json!({}));
+
+    for (key, value) in fields {
+        event[key] = json!(value);
+    }
+
+    event["@timestamp"] = json!(envelope
+        .timestamp
+        .to_rfc3339_opts(chrono::SecondsFormat::Millis, true));
+    event["@version"] = json!("1");
+    event["message"] = json!(envelope.summary());
+
+    format!("{event}\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{EnvelopePayload, Session};
+
+    #[test]
+    fn json_event() {
+        let metadata = Session::new("example", "0.0.1");
+        let envelope = Envelope::new(
+            &metadata,
+            EnvelopePayload::PageView {
+                page: "/settings".into(),
+            },
+        );
+
+        let fields = [("type".to_string(), "telemetry".to_string())].into();
+        let line = format_event(&envelope, &fields);
+        assert!(line.ends_with('\n'));
+        assert_eq!(line.lines().count(), 1);
+
+        let event: serde_json::Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(event["@version"], "1");
+        assert_eq!(event["message"], "Page view: /settings");
+        assert_eq!(event["type"], "telemetry");
+        assert_eq!(event["page"], "/settings");
+    }
+}
@@ -0,0 +1,282 @@
+use std::{
+    borrow::Cow,
+    fmt::Write,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use serde_json::json;
+
+use crate::{
+    dispatcher::HttpDispatcher,
+    notify::{environment_label, escape_html, RateLimiter},
+    queue::DEFAULT_QUEUE_CAPACITY,
+    Battery, BatteryBuilder, DropPolicy, Envelope, EnvelopePayload, Metadata,
+};
+
+/// A [Matrix](https://matrix.org) integration which posts a message to a room whenever an error is recorded, and
+/// a summary of the session when it is shut down.
+///
+/// <div class="warning">
+///
+/// This integration requires the `matrix` feature to be enabled.
+///
+/// </div>
+///
+/// Messages are sent using the client-server API of your homeserver, authenticated with the access token of a
+/// user (usually a dedicated bot account) which has joined the room. Each message is formatted with the error's
+/// causes and fields, along with your service's version and environment (taken from the `deployment.environment`
+/// or `environment` context field). At most 10 error messages are posted per minute to avoid flooding the room.
+///
+/// ## Example
+/// ```no_run
+/// use tracing_batteries::{Session, Matrix};
+///
+/// let session = Session::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+///   .with_context("environment", "production")
+///   .with_battery(Matrix::new("https://matrix.example.com", "syt_your_token", "!roomid:example.com"));
+///
+/// session.shutdown();
+/// ```
+pub struct Matrix {
+    homeserver: Cow<'static, str>,
+    access_token: Cow<'static, str>,
+    room_id: Cow<'static, str>,
+    session_summary: bool,
+    rate_limit: (usize, Duration),
+    queue_capacity: usize,
+    drop_policy: DropPolicy,
+}
+
+impl Matrix {
+    /// Configures the Matrix integration to post to the provided room using the homeserver at `homeserver`.
+    pub fn new<H, T, R>(homeserver: H, access_token: T, room_id: R) -> Self
+    where
+        H: Into<Cow<'static, str>>,
+        T: Into<Cow<'static, str>>,
+        R: Into<Cow<'static, str>>,
+    {
+        Self {
+            homeserver: homeserver.into(),
+            access_token: access_token.into(),
+            room_id: room_id.into(),
+            session_summary: true,
+            rate_limit: (10, Duration::from_secs(60)),
+            queue_capacity: DEFAULT_QUEUE_CAPACITY,
+            drop_policy: DropPolicy::default(),
+        }
+    }
+
+    /// Disables the summary message which is posted when the session is shut down.
+    pub fn without_session_summary(self) -> Self {
+        Self {
+            session_summary: false,
+            ..self
+        }
+    }
+
+    /// Configures the maximum number of error messages which are posted within the provided window.
+    pub fn with_rate_limit(self, limit: usize, window: Duration) -> Self {
+        Self {
+            rate_limit: (limit, window),
+            ..self
+        }
+    }
+
+    /// Configures the maximum number of messages which may be waiting to be sent (10,000 by default), and which are
+    /// dropped when the homeserver falls behind a burst of errors.
+    pub fn with_queue_limits(self, capacity: usize, policy: DropPolicy) -> Self {
+        Self {
+            queue_capacity: capacity,
+            drop_policy: policy,
+            ..self
+        }
+    }
+}
+
+impl BatteryBuilder for Matrix {
+    fn setup(self, metadata: &Metadata, _enabled: Arc<AtomicBool>) -> Box<dyn Battery> {
+        Box::new(MatrixBattery {
+            url: format!(
+                "{}/_matrix/client/v3/rooms/{}/send/m.room.message",
+                self.homeserver.trim_end_matches('/'),
+                encode_path_segment(&self.room_id)
+            ),
+            authorization: format!("Bearer {}", self.access_token),
+            transaction_prefix: format!(
+                "tb-{}-{}",
+                std::process::id(),
+                chrono::Utc::now().timestamp_millis()
+            ),
+            transaction: AtomicU64::new(0),
+            session_summary: self.session_summary,
+            environment: environment_label(metadata),
+            limiter: RateLimiter::new(self.rate_limit.0, self.rate_limit.1),
+            dispatcher: HttpDispatcher::new("matrix")
+                .with_limits(self.queue_capacity, self.drop_policy),
+        })
+    }
+}
+
+struct MatrixBattery {
+    url: String,
+    authorization: String,
+    transaction_prefix: String,
+    transaction: AtomicU64,
+    session_summary: bool,
+    environment: Option<String>,
+    limiter: RateLimiter,
+    dispatcher: HttpDispatcher,
+}
+
+impl Battery for MatrixBattery {
+    fn record_envelope(&self, envelope: &Envelope) {
+        let allowed = match &envelope.payload {
+            EnvelopePayload::Error { .. } => self.limiter.allow(),
+            EnvelopePayload::Event { name, .. } => {
+                self.session_summary && name == "session_summary"
+            }
+            _ => false,
+        };
+
+        let Some(message) = allowed
+            .then(|| build_message(envelope, self.environment.as_deref()))
+            .flatten()
+        else {
+            return;
+        };
+
+        // Transaction IDs allow the homeserver to deduplicate messages if a request is retried.
+        let url = format!(
+            "{}/{}-{}",
+            self.url,
+            self.transaction_prefix,
+            self.transaction.fetch_add(1, Ordering::Relaxed)
+        );
+        let authorization = self.authorization.clone();
+        let body = message.to_string();
+        self.dispatcher
+            .dispatch(envelope.importance, move |client| {
+                client
+                    .put(url)
+                    .header("Authorization", authorization)
+                    .header("Content-Type", "application/json")
+                    .body(body)
+            });
+    }
+
+    fn shutdown(&self) {
+        self.dispatcher.shutdown();
+    }
+}
+
+/// Builds an `m.room.message` event for an [`Envelope`], with both a markdown body and an HTML formatted body.
+fn build_message(envelope: &Envelope, environment: Option<&str>) -> Option<serde_json::Value> {
+    let (title, details) = match &envelope.payload {
+        EnvelopePayload::Error {
+            message,
+            causes,
+            fields,
+            ..
+        } => {
+            let mut details = vec![message.clone()];
+            details.extend(causes.iter().map(|cause| format!("Caused by: {cause}")));
+            details.extend(fields.iter().map(|(key, value)| format!("{key}: {value}")));
+            (format!("🚨 Error in {}", envelope.service), details)
+        }
+        EnvelopePayload::Event { name, properties } if name == "session_summary" => {
+            let details = properties
+                .iter()
+                .filter(|(_, value)| !value.is_null())
+                .map(|(key, value)| format!("{key}: {value}"))
+                .collect();
+            (
+                format!("📊 Session summary for {}", envelope.service),
+                details,
+            )
+        }
+        _ => return None,
+    };
+
+    let mut labels = vec![format!("v{}", envelope.version)];
+    labels.extend(environment.map(str::to_string));
+    let labels = labels.join(" · ");
+
+    let mut body = format!("**{title}** ({labels})\n");
+    let mut formatted_body = format!(
+        "<strong>{}</strong> <em>({})</em><ul>",
+        escape_html(&title),
+        escape_html(&labels)
+    );
+    for detail in &details {
+        writeln!(body, "- {detail}").ok();
+        write!(formatted_body, "<li>{}</li>", escape_html(detail)).ok();
+    }
+    formatted_body.push_str("</ul>");
+
+    Some(json!({
+        "msgtype": "m.text",
+        "body": body.trim_end(),
+        "format": "org.matrix.custom.html",
+        "formatted_body": formatted_body,
+    }))
+}
+
+/// Percent-encodes a value (such as a `!room:server` ID) for use as a single URL path segment.
+fn encode_path_segment(value: &str) -> String {
+    value
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{byte:02X}"),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Session;
+
+    #[test]
+    fn formatted_messages() {
+        let metadata = Session::new("example", "0.0.1");
+        let envelope = Envelope::new(
+            &metadata,
+            EnvelopePayload::Error {
+                message: "Failed to parse <config>".into(),
+                causes: vec!["Unexpected EOF".into()],
+                fields: Default::default(),
+                backtrace: None,
+            },
+        );
+
+        let message = build_message(&envelope, Some("production")).unwrap();
+        assert_eq!(
+            message["body"],
+            "**🚨 Error in example** (v0.0.1 · production)\n- Failed to parse <config>\n- Caused by: Unexpected EOF"
+        );
+        assert_eq!(
+            message["formatted_body"],
+            "<strong>🚨 Error in example</strong> <em>(v0.0.1 · production)</em><ul><li>Failed to parse &lt;config&gt;</li><li>Caused by: Unexpected EOF</li></ul>"
+        );
+
+        let page_view = Envelope::new(
+            &metadata,
+            EnvelopePayload::PageView {
+                page: "/settings".into(),
+            },
+        );
+        assert!(build_message(&page_view, None).is_none());
+
+        assert_eq!(
+            encode_path_segment("!abc123:example.com"),
+            "%21abc123%3Aexample.com"
+        );
+    }
+}
@@ -0,0 +1,296 @@
+use std::{
+    borrow::Cow,
+    sync::{atomic::AtomicBool, Arc},
+};
+
+use serde_json::json;
+
+use crate::{
+    dispatcher::HttpDispatcher, limits::AttributeLimits, queue::DEFAULT_QUEUE_CAPACITY, Battery,
+    BatteryBuilder, DropPolicy, Envelope, EnvelopePayload, ErrorContext, Metadata, Metric,
+    MetricExemplar, OpenTelemetry, OpenTelemetryProtocol, WeakSession,
+};
+
+/// The attribute limits enforced by New Relic, beyond which attributes are dropped at ingest.
+const NEW_RELIC_LIMITS: AttributeLimits = AttributeLimits {
+    max_key_length: 255,
+    max_value_length: 4095,
+};
+
+/// The New Relic data center which hosts your account, used to select the ingestion endpoints.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NewRelicRegion {
+    /// The US data center, `otlp.nr-data.net`.
+    US,
+    /// The EU data center, `otlp.eu01.nr-data.net`.
+    EU,
+    /// The FedRAMP compliant US data center, `gov-otlp.nr-data.net`.
+    FedRAMP,
+}
+
+impl NewRelicRegion {
+    /// The OTLP endpoint for this region.
+    pub fn otlp_endpoint(&self) -> &'static str {
+        match self {
+            NewRelicRegion::US => "https://otlp.nr-data.net",
+            NewRelicRegion::EU => "https://otlp.eu01.nr-data.net",
+            NewRelicRegion::FedRAMP => "https://gov-otlp.nr-data.net",
+        }
+    }
+
+    /// The Event API endpoint for this region.
+    pub fn events_endpoint(&self) -> &'static str {
+        match self {
+            NewRelicRegion::US => "https://insights-collector.newrelic.com",
+            NewRelicRegion::EU => "https://insights-collector.eu01.nr-data.net",
+            NewRelicRegion::FedRAMP => "https://gov-insights-collector.newrelic.com",
+        }
+    }
+}
+
+/// A [New Relic](https://newrelic.com) integration which configures the [`OpenTelemetry`] integration to
+/// export traces and metrics to your region's OTLP endpoint.
+///
+/// <div class="warning">
+///
+/// This integration requires the `newrelic` feature to be enabled.
+///
+/// </div>
+///
+/// Telemetry is authenticated using your ingest license key, which may also be provided using the
+/// `NEW_RELIC_LICENSE_KEY` environment variable. New Relic drops attributes whose names exceed 255 characters
+/// or whose values exceed 4095 characters, so these are truncated before export.
+///
+/// Errors recorded through the session are attached to their spans, but may optionally also be sent to the
+/// [Errors Inbox](https://docs.newrelic.com/docs/errors-inbox/errors-inbox/) as `TransactionError` events
+/// using [`NewRelic::with_errors_inbox`].
+///
+/// ## Example
+/// ```no_run
+/// use tracing_batteries::{Session, NewRelic, NewRelicRegion};
+///
+/// let session = Session::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+///   .with_battery(NewRelic::new(NewRelicRegion::EU, "your-license-key")
+///     .with_errors_inbox("1234567"));
+///
+/// session.shutdown();
+/// ```
+pub struct NewRelic {
+    region: NewRelicRegion,
+    license_key: Cow<'static, str>,
+    account_id: Option<Cow<'static, str>>,
+    configure: Box<dyn FnOnce(OpenTelemetry) -> OpenTelemetry>,
+    queue_capacity: usize,
+    drop_policy: DropPolicy,
+}
+
+impl NewRelic {
+    /// Configures the New Relic integration for the provided region and license key.
+    pub fn new<K: Into<Cow<'static, str>>>(region: NewRelicRegion, license_key: K) -> Self {
+        Self {
+            region,
+            license_key: std::env::var("NEW_RELIC_LICENSE_KEY")
+                .map(Cow::Owned)
+                .unwrap_or_else(|_| license_key.into()),
+            account_id: None,
+            configure: Box::new(|otel| otel),
+            queue_capacity: DEFAULT_QUEUE_CAPACITY,
+            drop_policy: DropPolicy::default(),
+        }
+    }
+
+    /// Sends recorded errors to the Errors Inbox of the provided New Relic account.
+    pub fn with_errors_inbox<A: Into<Cow<'static, str>>>(self, account_id: A) -> Self {
+        Self {
+            account_id: Some(account_id.into()),
+            ..self
+        }
+    }
+
+    /// Customizes the underlying [`OpenTelemetry`] integration used to export traces and metrics.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use tracing_batteries::{NewRelic, NewRelicRegion, OpenTelemetryLevel};
+    ///
+    /// NewRelic::new(NewRelicRegion::US, "your-license-key")
+    ///   .with_opentelemetry(|otel| otel.with_default_level(OpenTelemetryLevel::DEBUG));
+    /// ```
+    pub fn with_opentelemetry<F>(self, configure: F) -> Self
+    where
+        F: FnOnce(OpenTelemetry) -> OpenTelemetry + 'static,
+    {
+        Self {
+            configure: Box::new(configure),
+            ..self
+        }
+    }
+
+    /// Configures the maximum number of custom events which may be waiting to be sent (10,000 by default), and which
+    /// are dropped when the Event API falls behind a burst of activity.
+    pub fn with_queue_limits(self, capacity: usize, policy: DropPolicy) -> Self {
+        Self {
+            queue_capacity: capacity,
+            drop_policy: policy,
+            ..self
+        }
+    }
+}
+
+impl BatteryBuilder for NewRelic {
+    fn setup(self, metadata: &Metadata, enabled: Arc<AtomicBool>) -> Box<dyn Battery> {
+        let otel = OpenTelemetry::new("")
+            .with_endpoint(self.region.otlp_endpoint())
+            .with_protocol(OpenTelemetryProtocol::HttpBinary)
+            .with_header("api-key", self.license_key.clone())
+            .with_attribute_limits(NEW_RELIC_LIMITS);
+
+        let inner = (self.configure)(otel).setup(metadata, enabled);
+
+        match self.account_id {
+            Some(account_id) => Box::new(NewRelicBattery {
+                inner,
+                events_url: format!(
+                    "{}/v1/accounts/{account_id}/events",
+                    self.region.events_endpoint()
+                ),
+                license_key: self.license_key.to_string(),
+                dispatcher: HttpDispatcher::new("newrelic")
+                    .with_limits(self.queue_capacity, self.drop_policy),
+            }),
+            None => inner,
+        }
+    }
+}
+
+struct NewRelicBattery {
+    inner: Box<dyn Battery>,
+    events_url: String,
+    license_key: String,
+    dispatcher: HttpDispatcher,
+}
+
+impl Battery for NewRelicBattery {
+    fn record_error(&self, error: &dyn std::error::Error) {
+        self.inner.record_error(error)
+    }
+
+    fn record_error_with(&self, error: &dyn std::error::Error, context: &ErrorContext) {
+        self.inner.record_error_with(error, context)
+    }
+
+    fn record_envelope(&self, envelope: &Envelope) {
+        self.inner.record_envelope(envelope);
+
+        let trace_id = MetricExemplar::current().map(|trace| trace.trace_id_hex());
+        let Some(event) = build_error_event(envelope, trace_id.as_deref()) else {
+            return;
+        };
+
+        let body = json!([event]).to_string();
+        let url = self.events_url.clone();
+        let license_key = self.license_key.clone();
+        self.dispatcher
+            .dispatch(envelope.importance, move |client| {
+                client
+                    .post(url)
+                    .header("Content-Type", "application/json")
+                    .header("Api-Key", license_key)
+                    .body(body)
+            });
+    }
+
+    fn record_metric(&self, metric: &Metric) {
+        self.inner.record_metric(metric)
+    }
+
+    fn accepts_weighted_histograms(&self) -> bool {
+        self.inner.accepts_weighted_histograms()
+    }
+
+    fn shutdown(&self) {
+        self.dispatcher.shutdown();
+        self.inner.shutdown();
+    }
+
+    fn attached(&self, session: WeakSession) {
+        self.inner.attached(session)
+    }
+
+    fn validate(&self) -> Vec<crate::ValidationCheck> {
+        self.inner.validate()
+    }
+}
+
+/// Converts an error [`Envelope`] into a `TransactionError` event, which New Relic groups in the Errors Inbox.
+fn build_error_event(envelope: &Envelope, trace_id: Option<&str>) -> Option<serde_json::Value> {
+    let EnvelopePayload::Error {
+        message,
+        causes,
+        fields,
+        ..
+    } = &envelope.payload
+    else {
+        return None;
+    };
+
+    let mut event = json!({
+        "eventType": "TransactionError",
+        "timestamp": envelope.timestamp.timestamp_millis(),
+        "appName": envelope.service,
+        "service.name": envelope.service,
+        "service.version": envelope.version,
+        "error.class": causes.last().unwrap_or(message),
+        "error.message": message,
+        "error.expected": false,
+    });
+
+    let context = envelope
+        .context
+        .iter()
+        .map(|(key, value)| (key.as_str(), value.as_str()));
+    for (key, value) in context.chain(
+        fields
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_str())),
+    ) {
+        event[key] = json!(value
+            .chars()
+            .take(NEW_RELIC_LIMITS.max_value_length)
+            .collect::<String>());
+    }
+
+    if let Some(trace_id) = trace_id {
+        event["trace.id"] = json!(trace_id);
+    }
+
+    Some(event)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Session;
+
+    #[test]
+    fn error_event() {
+        let metadata = Session::new("example", "0.0.1");
+        let envelope = Envelope::new(
+            &metadata,
+            EnvelopePayload::Error {
+                message: "Failed to connect".into(),
+                causes: vec!["Connection refused".into()],
+                fields: Default::default(),
+                backtrace: None,
+            },
+        );
+
+        let event = build_error_event(&envelope, Some("0af7651916cd43dd8448eb211c80319c")).unwrap();
+        assert_eq!(event["eventType"], "TransactionError");
+        assert_eq!(event["error.class"], "Connection refused");
+        assert_eq!(event["trace.id"], "0af7651916cd43dd8448eb211c80319c");
+
+        let envelope = Envelope::new(&metadata, EnvelopePayload::PageView { page: "/".into() });
+        assert!(build_error_event(&envelope, None).is_none());
+    }
+}
@@ -0,0 +1,270 @@
+use std::{
+    borrow::Cow,
+    sync::{atomic::AtomicBool, Arc},
+    time::Duration,
+};
+
+use serde_json::json;
+
+use crate::{
+    dispatcher::HttpDispatcher,
+    notify::{environment_label, NotificationSeverity, RateLimiter},
+    queue::DEFAULT_QUEUE_CAPACITY,
+    Battery, BatteryBuilder, DropPolicy, Envelope, EnvelopePayload, Metadata, MetricExemplar,
+};
+
+/// An [ntfy](https://ntfy.sh) integration which pushes notifications to an ntfy topic whenever errors
+/// (or, optionally, custom events) are recorded, delivering instant alerts to your phone or desktop.
+///
+/// <div class="warning">
+///
+/// This integration requires the `ntfy` feature to be enabled.
+///
+/// </div>
+///
+/// Notifications are published to `https://ntfy.sh` by default, but any self-hosted ntfy server may be used
+/// with [`Ntfy::with_server`]. Errors are published with high priority and custom events with the default
+/// priority, and at most 10 notifications are sent per minute. When a logs URL is configured using
+/// [`Ntfy::with_logs_url`], each notification includes a button which opens it (with `{trace_id}` replaced by the
+/// ID of the active [`OpenTelemetry`](crate::OpenTelemetry) trace, when one is available).
+///
+/// ## Example
+/// ```no_run
+/// use tracing_batteries::{Session, Ntfy};
+///
+/// let session = Session::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+///   .with_battery(Ntfy::new("my-service-alerts")
+///     .with_logs_url("https://logs.example.com/my-service"));
+///
+/// session.shutdown();
+/// ```
+pub struct Ntfy {
+    server: Cow<'static, str>,
+    topic: Cow<'static, str>,
+    token: Option<Cow<'static, str>>,
+    logs_url: Option<Cow<'static, str>>,
+    min_severity: NotificationSeverity,
+    rate_limit: (usize, Duration),
+    queue_capacity: usize,
+    drop_policy: DropPolicy,
+}
+
+impl Ntfy {
+    /// Configures the ntfy integration to publish to the provided topic on `https://ntfy.sh`.
+    pub fn new<T: Into<Cow<'static, str>>>(topic: T) -> Self {
+        Self {
+            server: "https://ntfy.sh".into(),
+            topic: topic.into(),
+            token: None,
+            logs_url: None,
+            min_severity: NotificationSeverity::Error,
+            rate_limit: (10, Duration::from_secs(60)),
+            queue_capacity: DEFAULT_QUEUE_CAPACITY,
+            drop_policy: DropPolicy::default(),
+        }
+    }
+
+    /// Configures the ntfy server which notifications are published to, for use with self-hosted installations.
+    pub fn with_server<S: Into<Cow<'static, str>>>(self, server: S) -> Self {
+        Self {
+            server: server.into(),
+            ..self
+        }
+    }
+
+    /// Configures the access token used to publish to a protected topic.
+    pub fn with_token<T: Into<Cow<'static, str>>>(self, token: T) -> Self {
+        Self {
+            token: Some(token.into()),
+            ..self
+        }
+    }
+
+    /// Configures a link to your logs which is attached to each notification as a "View logs" button.
+    pub fn with_logs_url<U: Into<Cow<'static, str>>>(self, url: U) -> Self {
+        Self {
+            logs_url: Some(url.into()),
+            ..self
+        }
+    }
+
+    /// Configures the minimum severity of telemetry which is published to the topic.
+    pub fn with_min_severity(self, severity: NotificationSeverity) -> Self {
+        Self {
+            min_severity: severity,
+            ..self
+        }
+    }
+
+    /// Configures the maximum number of notifications which are published within the provided window.
+    pub fn with_rate_limit(self, limit: usize, window: Duration) -> Self {
+        Self {
+            rate_limit: (limit, window),
+            ..self
+        }
+    }
+
+    /// Configures the maximum number of notifications which may be waiting to be sent (10,000 by default), and which
+    /// are dropped when the ntfy server falls behind a burst of errors.
+    pub fn with_queue_limits(self, capacity: usize, policy: DropPolicy) -> Self {
+        Self {
+            queue_capacity: capacity,
+            drop_policy: policy,
+            ..self
+        }
+    }
+}
+
+impl BatteryBuilder for Ntfy {
+    fn setup(self, metadata: &Metadata, _enabled: Arc<AtomicBool>) -> Box<dyn Battery> {
+        Box::new(NtfyBattery {
+            server: self.server.trim_end_matches('/').to_string(),
+            topic: self.topic.to_string(),
+            authorization: self.token.map(|token| format!("Bearer {token}")),
+            logs_url: self.logs_url.map(|url| url.to_string()),
+            min_severity: self.min_severity,
+            environment: environment_label(metadata),
+            limiter: RateLimiter::new(self.rate_limit.0, self.rate_limit.1),
+            dispatcher: HttpDispatcher::new("ntfy")
+                .with_limits(self.queue_capacity, self.drop_policy),
+        })
+    }
+}
+
+struct NtfyBattery {
+    server: String,
+    topic: String,
+    authorization: Option<String>,
+    logs_url: Option<String>,
+    min_severity: NotificationSeverity,
+    environment: Option<String>,
+    limiter: RateLimiter,
+    dispatcher: HttpDispatcher,
+}
+
+impl Battery for NtfyBattery {
+    fn record_envelope(&self, envelope: &Envelope) {
+        let severity = match NotificationSeverity::of(envelope) {
+            Some(severity) if severity >= self.min_severity => severity,
+            _ => return,
+        };
+
+        if !self.limiter.allow() {
+            return;
+        }
+
+        let logs_url = self.logs_url.as_ref().map(|template| {
+            let trace_id = MetricExemplar::current()
+                .map(|trace| trace.trace_id_hex())
+                .unwrap_or_default();
+            template.replace("{trace_id}", &trace_id)
+        });
+
+        let body = build_message(
+            envelope,
+            &self.topic,
+            severity,
+            self.environment.as_deref(),
+            logs_url.as_deref(),
+        )
+        .to_string();
+
+        let url = self.server.clone();
+        let authorization = self.authorization.clone();
+        self.dispatcher
+            .dispatch(envelope.importance, move |client| {
+                let request = client
+                    .post(url)
+                    .header("Content-Type", "application/json")
+                    .body(body);
+
+                match authorization {
+                    Some(authorization) => request.header("Authorization", authorization),
+                    None => request,
+                }
+            });
+    }
+
+    fn shutdown(&self) {
+        self.dispatcher.shutdown();
+    }
+}
+
+fn build_message(
+    envelope: &Envelope,
+    topic: &str,
+    severity: NotificationSeverity,
+    environment: Option<&str>,
+    logs_url: Option<&str>,
+) -> serde_json::Value {
+    let (priority, tag, title) = match severity {
+        NotificationSeverity::Error => (
+            4,
+            "rotating_light",
+            format!("Error in {}", envelope.service),
+        ),
+        NotificationSeverity::Info => (3, "information_source", envelope.service.to_string()),
+    };
+
+    let mut tags = vec![tag.to_string(), format!("v{}", envelope.version)];
+    tags.extend(environment.map(|environment| environment.to_string()));
+
+    let message = match &envelope.payload {
+        EnvelopePayload::Error {
+            message, causes, ..
+        } => std::iter::once(message.clone())
+            .chain(causes.iter().map(|cause| format!("Caused by: {cause}")))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        _ => envelope.summary(),
+    };
+
+    let mut body = json!({
+        "topic": topic,
+        "title": title,
+        "message": message,
+        "priority": priority,
+        "tags": tags,
+    });
+
+    if let Some(url) = logs_url {
+        body["actions"] = json!([{ "action": "view", "label": "View logs", "url": url }]);
+    }
+
+    body
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Session;
+
+    #[test]
+    fn error_message() {
+        let metadata = Session::new("example", "0.0.1");
+        let envelope = Envelope::new(
+            &metadata,
+            EnvelopePayload::Error {
+                message: "Failed to connect".into(),
+                causes: vec!["Connection refused".into()],
+                fields: Default::default(),
+                backtrace: None,
+            },
+        );
+
+        let message = build_message(
+            &envelope,
+            "alerts",
+            NotificationSeverity::Error,
+            Some("production"),
+            Some("https://logs.example.com"),
+        );
+        assert_eq!(message["priority"], 4);
+        assert_eq!(
+            message["message"],
+            "Failed to connect\nCaused by: Connection refused"
+        );
+        assert_eq!(message["tags"][2], "production");
+        assert_eq!(message["actions"][0]["url"], "https://logs.example.com");
+    }
+}
@@ -0,0 +1,206 @@
+use std::{
+    borrow::Cow,
+    sync::{atomic::AtomicBool, Arc},
+};
+
+use base64::Engine;
+
+use crate::{
+    dispatcher::HttpDispatcher, queue::DEFAULT_QUEUE_CAPACITY, Battery, BatteryBuilder, DropPolicy,
+    Envelope, ErrorContext, Metadata, Metric, OpenTelemetry, OpenTelemetryProtocol, WeakSession,
+};
+
+/// An [OpenObserve](https://openobserve.ai) integration which exports traces and metrics through
+/// OpenObserve's OTLP endpoint and ships errors and custom events to its `_json` ingestion API.
+///
+/// <div class="warning">
+///
+/// This integration requires the `openobserve` feature to be enabled.
+///
+/// </div>
+///
+/// The integration is configured with the base URL of your OpenObserve instance and the organization
+/// which telemetry should be ingested into. Errors, custom events, and page views are serialized as
+/// [`Envelope`]s and written to the configured stream (`default` unless otherwise specified).
+///
+/// ## Example
+/// ```no_run
+/// use tracing_batteries::{Session, OpenObserve};
+///
+/// let session = Session::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+///   .with_battery(OpenObserve::new("https://openobserve.example.com", "default")
+///     .with_credentials("root@example.com", "your-password")
+///     .with_stream("my-service"));
+///
+/// session.shutdown();
+/// ```
+pub struct OpenObserve {
+    endpoint: Cow<'static, str>,
+    organization: Cow<'static, str>,
+    stream: Cow<'static, str>,
+    credentials: Option<(Cow<'static, str>, Cow<'static, str>)>,
+    configure: Box<dyn FnOnce(OpenTelemetry) -> OpenTelemetry>,
+    queue_capacity: usize,
+    drop_policy: DropPolicy,
+}
+
+impl OpenObserve {
+    /// Configures the OpenObserve integration for the provided instance URL and organization.
+    pub fn new<E: Into<Cow<'static, str>>, O: Into<Cow<'static, str>>>(
+        endpoint: E,
+        organization: O,
+    ) -> Self {
+        Self {
+            endpoint: endpoint.into(),
+            organization: organization.into(),
+            stream: "default".into(),
+            credentials: None,
+            configure: Box::new(|otel| otel),
+            queue_capacity: DEFAULT_QUEUE_CAPACITY,
+            drop_policy: DropPolicy::default(),
+        }
+    }
+
+    /// Configures the credentials used to authenticate with OpenObserve using HTTP basic authentication.
+    pub fn with_credentials<U: Into<Cow<'static, str>>, P: Into<Cow<'static, str>>>(
+        self,
+        username: U,
+        password: P,
+    ) -> Self {
+        Self {
+            credentials: Some((username.into(), password.into())),
+            ..self
+        }
+    }
+
+    /// Configures the stream which telemetry should be written to.
+    pub fn with_stream<S: Into<Cow<'static, str>>>(self, stream: S) -> Self {
+        Self {
+            stream: stream.into(),
+            ..self
+        }
+    }
+
+    /// Customizes the underlying [`OpenTelemetry`] integration used to export traces and metrics.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use tracing_batteries::{OpenObserve, OpenTelemetryLevel};
+    ///
+    /// OpenObserve::new("https://openobserve.example.com", "default")
+    ///   .with_opentelemetry(|otel| otel.with_default_level(OpenTelemetryLevel::DEBUG));
+    /// ```
+    pub fn with_opentelemetry<F>(self, configure: F) -> Self
+    where
+        F: FnOnce(OpenTelemetry) -> OpenTelemetry + 'static,
+    {
+        Self {
+            configure: Box::new(configure),
+            ..self
+        }
+    }
+
+    fn build_authorization(&self) -> Option<String> {
+        self.credentials.as_ref().map(|(username, password)| {
+            format!(
+                "Basic {}",
+                base64::engine::general_purpose::STANDARD.encode(format!("{username}:{password}"))
+            )
+        })
+    }
+
+    /// Configures the maximum number of log entries which may be waiting to be sent (10,000 by default), and which are
+    /// dropped when the ingestion endpoint falls behind a burst of activity.
+    pub fn with_queue_limits(self, capacity: usize, policy: DropPolicy) -> Self {
+        Self {
+            queue_capacity: capacity,
+            drop_policy: policy,
+            ..self
+        }
+    }
+}
+
+impl BatteryBuilder for OpenObserve {
+    fn setup(self, metadata: &Metadata, enabled: Arc<AtomicBool>) -> Box<dyn Battery> {
+        let endpoint = self.endpoint.trim_end_matches('/');
+        let authorization = self.build_authorization();
+
+        let mut otel = OpenTelemetry::new("")
+            .with_endpoint(format!("{endpoint}/api/{}", self.organization))
+            .with_protocol(OpenTelemetryProtocol::HttpBinary)
+            .with_header("stream-name", self.stream.clone());
+        if let Some(authorization) = &authorization {
+            otel = otel.with_header("Authorization", authorization.clone());
+        }
+
+        let json_url = format!("{endpoint}/api/{}/{}/_json", self.organization, self.stream);
+        let inner = (self.configure)(otel).setup(metadata, enabled);
+
+        Box::new(OpenObserveBattery {
+            inner,
+            json_url,
+            authorization,
+            dispatcher: HttpDispatcher::new("openobserve")
+                .with_limits(self.queue_capacity, self.drop_policy),
+        })
+    }
+}
+
+struct OpenObserveBattery {
+    inner: Box<dyn Battery>,
+    json_url: String,
+    authorization: Option<String>,
+    dispatcher: HttpDispatcher,
+}
+
+impl Battery for OpenObserveBattery {
+    fn record_error(&self, error: &dyn std::error::Error) {
+        self.inner.record_error(error)
+    }
+
+    fn record_error_with(&self, error: &dyn std::error::Error, context: &ErrorContext) {
+        self.inner.record_error_with(error, context)
+    }
+
+    fn record_envelope(&self, envelope: &Envelope) {
+        let Ok(body) = serde_json::to_vec(&[envelope]) else {
+            return;
+        };
+
+        let url = self.json_url.clone();
+        let authorization = self.authorization.clone();
+        self.dispatcher
+            .dispatch(envelope.importance, move |client| {
+                let request = client
+                    .post(url)
+                    .header("Content-Type", "application/json")
+                    .body(body);
+
+                match authorization {
+                    Some(authorization) => request.header("Authorization", authorization),
+                    None => request,
+                }
+            });
+    }
+
+    fn record_metric(&self, metric: &Metric) {
+        self.inner.record_metric(metric)
+    }
+
+    fn accepts_weighted_histograms(&self) -> bool {
+        self.inner.accepts_weighted_histograms()
+    }
+
+    fn shutdown(&self) {
+        self.dispatcher.shutdown();
+        self.inner.shutdown();
+    }
+
+    fn attached(&self, session: WeakSession) {
+        self.inner.attached(session)
+    }
+
+    fn validate(&self) -> Vec<crate::ValidationCheck> {
+        self.inner.validate()
+    }
+}
@@ -0,0 +1,354 @@
+use std::{
+    borrow::Cow,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use reqwest::blocking::{Client, RequestBuilder};
+use serde_json::json;
+
+use crate::{
+    aws::{self, AwsSigner},
+    batcher::BatchDispatcher,
+    queue::DEFAULT_QUEUE_CAPACITY,
+    Battery, BatteryBuilder, DropPolicy, Envelope, EnvelopePayload, Metadata,
+};
+
+enum OpenSearchAuth {
+    None,
+    Basic {
+        username: Cow<'static, str>,
+        password: Cow<'static, str>,
+    },
+    SigV4 {
+        region: Cow<'static, str>,
+        service: &'static str,
+    },
+}
+
+enum Authenticator {
+    None,
+    Basic { username: String, password: String },
+    SigV4(AwsSigner),
+}
+
+impl Authenticator {
+    fn request(
+        &self,
+        client: &Client,
+        method: reqwest::Method,
+        url: &str,
+        content_type: &str,
+        body: Vec<u8>,
+    ) -> RequestBuilder {
+        match self {
+            Authenticator::None => client
+                .request(method, url)
+                .header("Content-Type", content_type)
+                .body(body),
+            Authenticator::Basic { username, password } => client
+                .request(method, url)
+                .basic_auth(username, Some(password))
+                .header("Content-Type", content_type)
+                .body(body),
+            Authenticator::SigV4(signer) => {
+                let payload_hash = aws::payload_hash(&body);
+                signer.request(
+                    client,
+                    method,
+                    url,
+                    &[
+                        ("Content-Type", content_type),
+                        ("x-amz-content-sha256", &payload_hash),
+                    ],
+                    body,
+                )
+            }
+        }
+    }
+}
+
+/// An [OpenSearch](https://opensearch.org) (or Elasticsearch) integration which bulk-indexes errors, custom events,
+/// and page views into date-based indices.
+///
+/// <div class="warning">
+///
+/// This integration requires the `opensearch` feature to be enabled.
+///
+/// </div>
+///
+/// Each [`Envelope`] is indexed as a document (with an additional `@timestamp` field) into an index named after
+/// the configured prefix and the day on which it was recorded, e.g. `telemetry-2024.03.07`. Before the first batch
+/// is written, an index template is installed for the prefix so that these fields are mapped appropriately; this
+/// may be disabled using [`OpenSearch::without_index_template`] if you manage your templates separately.
+///
+/// Documents are written in batches using the `_bulk` API. When the cluster applies backpressure (responding with
+/// `429 Too Many Requests`) or is temporarily unavailable, batches are retried with an exponential backoff.
+/// Requests may be authenticated using HTTP basic auth, or signed using AWS Signature Version 4 for Amazon
+/// OpenSearch Service domains.
+///
+/// ## Example
+/// ```no_run
+/// use tracing_batteries::{Session, OpenSearch};
+///
+/// let session = Session::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+///   .with_battery(OpenSearch::new("https://opensearch.example.com:9200")
+///     .with_basic_auth("telemetry", "your-password"));
+///
+/// session.shutdown();
+/// ```
+pub struct OpenSearch {
+    url: Cow<'static, str>,
+    auth: OpenSearchAuth,
+    index_prefix: Cow<'static, str>,
+    index_template: bool,
+    batch_size: usize,
+    flush_interval: Duration,
+    queue_capacity: usize,
+    drop_policy: DropPolicy,
+}
+
+impl OpenSearch {
+    /// Configures the OpenSearch integration to index documents into the cluster at the provided URL.
+    pub fn new<U: Into<Cow<'static, str>>>(url: U) -> Self {
+        Self {
+            url: url.into(),
+            auth: OpenSearchAuth::None,
+            index_prefix: "telemetry".into(),
+            index_template: true,
+            batch_size: 500,
+            flush_interval: Duration::from_secs(5),
+            queue_capacity: DEFAULT_QUEUE_CAPACITY,
+            drop_policy: DropPolicy::default(),
+        }
+    }
+
+    /// Authenticates with the cluster using HTTP basic authentication.
+    pub fn with_basic_auth<U: Into<Cow<'static, str>>, P: Into<Cow<'static, str>>>(
+        self,
+        username: U,
+        password: P,
+    ) -> Self {
+        Self {
+            auth: OpenSearchAuth::Basic {
+                username: username.into(),
+                password: password.into(),
+            },
+            ..self
+        }
+    }
+
+    /// Signs requests using AWS Signature Version 4, for Amazon OpenSearch Service domains in the provided region.
+    ///
+    /// Credentials are loaded from the standard AWS credential chain.
+    pub fn with_aws_sigv4<R: Into<Cow<'static, str>>>(self, region: R) -> Self {
+        Self {
+            auth: OpenSearchAuth::SigV4 {
+                region: region.into(),
+                service: "es",
+            },
+            ..self
+        }
+    }
+
+    /// Signs requests using AWS Signature Version 4, for Amazon OpenSearch Serverless collections in the provided region.
+    pub fn with_aws_serverless<R: Into<Cow<'static, str>>>(self, region: R) -> Self {
+        Self {
+            auth: OpenSearchAuth::SigV4 {
+                region: region.into(),
+                service: "aoss",
+            },
+            ..self
+        }
+    }
+
+    /// Overrides the prefix of the daily indices which documents are written to.
+    pub fn with_index_prefix<P: Into<Cow<'static, str>>>(self, prefix: P) -> Self {
+        Self {
+            index_prefix: prefix.into(),
+            ..self
+        }
+    }
+
+    /// Disables installation of the index template for the configured index prefix.
+    pub fn without_index_template(self) -> Self {
+        Self {
+            index_template: false,
+            ..self
+        }
+    }
+
+    /// Configures the maximum number of documents which are written in a single bulk request, and how frequently
+    /// partial batches are flushed.
+    pub fn with_batching(self, batch_size: usize, flush_interval: Duration) -> Self {
+        Self {
+            batch_size: batch_size.max(1),
+            flush_interval,
+            ..self
+        }
+    }
+
+    /// Configures the maximum number of documents which may be waiting to be sent (10,000 by default), and which are
+    /// dropped when the cluster falls behind a burst of activity.
+    pub fn with_queue_limits(self, capacity: usize, policy: DropPolicy) -> Self {
+        Self {
+            queue_capacity: capacity,
+            drop_policy: policy,
+            ..self
+        }
+    }
+}
+
+impl BatteryBuilder for OpenSearch {
+    fn setup(self, _metadata: &Metadata, _enabled: Arc<AtomicBool>) -> Box<dyn Battery> {
+        let url = self.url.trim_end_matches('/').to_string();
+        let authenticator = match self.auth {
+            OpenSearchAuth::None => Authenticator::None,
+            OpenSearchAuth::Basic { username, password } => Authenticator::Basic {
+                username: username.to_string(),
+                password: password.to_string(),
+            },
+            OpenSearchAuth::SigV4 { region, service } => {
+                Authenticator::SigV4(AwsSigner::new(service, region.to_string()))
+            }
+        };
+
+        let template_url = format!("{url}/_index_template/{}", self.index_prefix);
+        let template = index_template(&self.index_prefix).to_string();
+        let template_installed = AtomicBool::new(!self.index_template);
+        let bulk_url = format!("{url}/_bulk");
+
+        Box::new(OpenSearchBattery {
+            index_prefix: self.index_prefix.to_string(),
+            dispatcher: BatchDispatcher::new(
+                "opensearch",
+                self.batch_size,
+                self.flush_interval,
+                5,
+                move |client, documents: &[String]| {
+                    if !template_installed.swap(true, Ordering::Relaxed) {
+                        authenticator
+                            .request(
+                                client,
+                                reqwest::Method::PUT,
+                                &template_url,
+                                "application/json",
+                                template.clone().into_bytes(),
+                            )
+                            .send()
+                            .ok();
+                    }
+
+                    authenticator.request(
+                        client,
+                        reqwest::Method::POST,
+                        &bulk_url,
+                        "application/x-ndjson",
+                        documents.concat().into_bytes(),
+                    )
+                },
+            )
+            .with_limits(self.queue_capacity, self.drop_policy),
+        })
+    }
+}
+
+/// Builds the index template which maps the fields of indexed envelopes.
+fn index_template(prefix: &str) -> serde_json::Value {
+    json!({
+        "index_patterns": [format!("{prefix}-*")],
+        "template": {
+            "mappings": {
+                "properties": {
+                    "@timestamp": { "type": "date" },
+                    "timestamp": { "type": "date" },
+                    "service": { "type": "keyword" },
+                    "version": { "type": "keyword" },
+                    "kind": { "type": "keyword" },
+                    "message": { "type": "text" },
+                    "causes": { "type": "text" },
+                    "backtrace": { "type": "text", "index": false },
+                    "name": { "type": "keyword" },
+                    "page": { "type": "keyword" },
+                },
+            },
+        },
+    })
+}
+
+struct OpenSearchBattery {
+    index_prefix: String,
+    dispatcher: BatchDispatcher<String>,
+}
+
+impl Battery for OpenSearchBattery {
+    fn record_envelope(&self, envelope: &Envelope) {
+        if let Some(document) = bulk_document(envelope, &self.index_prefix) {
+            self.dispatcher.push(envelope.importance, document);
+        }
+    }
+
+    fn shutdown(&self) {
+        self.dispatcher.shutdown();
+    }
+}
+
+/// Formats an [`Envelope`] as the action and source lines of a `_bulk` index request.
+fn bulk_document(envelope: &Envelope, index_prefix: &str) -> Option<String> {
+    if envelope.payload == EnvelopePayload::Unknown {
+        return None;
+    }
+
+    let mut document = serde_json::to_value(envelope).ok()?;
+    document["@timestamp"] = json!(envelope
+        .timestamp
+        .to_rfc3339_opts(chrono::SecondsFormat::Millis, true));
+
+    let action = json!({
+        "index": {
+            "_index": format!("{index_prefix}-{}", envelope.timestamp.format("%Y.%m.%d")),
+        },
+    });
+
+    Some(format!("{action}\n{document}\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Session;
+
+    #[test]
+    fn bulk_documents() {
+        let metadata = Session::new("example", "0.0.1");
+        let envelope = Envelope::new(
+            &metadata,
+            EnvelopePayload::Event {
+                name: "project.created".into(),
+                properties: Default::default(),
+            },
+        );
+
+        let document = bulk_document(&envelope, "telemetry").unwrap();
+        let lines: Vec<serde_json::Value> = document
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+
+        assert_eq!(lines.len(), 2);
+        assert_eq!(
+            lines[0]["index"]["_index"],
+            format!("telemetry-{}", envelope.timestamp.format("%Y.%m.%d"))
+        );
+        assert_eq!(lines[1]["name"], "project.created");
+        assert!(lines[1]["@timestamp"].is_string());
+
+        assert_eq!(
+            index_template("telemetry")["index_patterns"][0],
+            "telemetry-*"
+        );
+    }
+}
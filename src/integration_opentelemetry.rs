@@ -1,22 +1,39 @@
 use std::{
     borrow::Cow,
-    collections::HashMap,
-    sync::{atomic::AtomicBool, Arc},
+    collections::{HashMap, HashSet},
+    sync::{atomic::AtomicBool, Arc, Mutex},
+    time::Duration,
 };
 
-use opentelemetry::trace::TracerProvider;
+use opentelemetry::metrics::MeterProvider;
+use opentelemetry::trace::{Tracer, TracerProvider};
 use opentelemetry_otlp::{WithExportConfig, WithHttpConfig, WithTonicConfig};
-use opentelemetry_sdk::{trace::Sampler, Resource};
+use opentelemetry_sdk::{metrics::SdkMeterProvider, trace::Sampler, Resource};
 use tracing::Subscriber;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
 use tracing_subscriber::{
-    layer::SubscriberExt, registry::LookupSpan, util::SubscriberInitExt, Layer,
+    filter::{FilterExt, LevelFilter},
+    fmt::{writer::BoxMakeWriter, MakeWriter},
+    layer::SubscriberExt,
+    registry::LookupSpan,
+    util::SubscriberInitExt,
+    Layer, Registry,
 };
 
-use crate::{Battery, BatteryBuilder};
+use crate::{Battery, BatteryBuilder, ErrorContext, Metric, MetricKind, Session};
+
+pub use opentelemetry::global::{
+    BoxedSpan as OpenTelemetrySpan, BoxedTracer as OpenTelemetryTracer,
+};
+pub use opentelemetry::trace::SpanKind as OpenTelemetrySpanKind;
 pub use opentelemetry_otlp::Protocol as OpenTelemetryProtocol;
 pub use opentelemetry_sdk::trace::Sampler as OpenTelemetrySampler;
+pub use reqwest::header::HeaderValue as OpenTelemetryHeaderValue;
 pub use tracing::Level as OpenTelemetryLevel;
 
+/// The environment variable which may hold a comma separated list of collector endpoints to fail over between.
+const OTLP_ENDPOINTS_ENV: &str = "TRACING_BATTERIES_OTLP_ENDPOINTS";
+
 /// An [OpenTelemetry](opentelemetry) integration which leverages the [`tracing`] ecosystem
 /// to emit span information to an OpenTelemetry collector.
 ///
@@ -30,6 +47,10 @@ pub use tracing::Level as OpenTelemetryLevel;
 /// collector. The endpoint may either be a gRPC or HTTP endpoint, and additional headers may
 /// be used to configure the connection (these are often used for authentication).
 ///
+/// Setting the `OTEL_SDK_DISABLED` environment variable to `true` disables the integration entirely,
+/// no exporters or propagators will be configured and spans will only be written to stdout (unless
+/// this has been disabled using [`OpenTelemetry::with_stdout`]).
+///
 /// ## Example (gRPC)
 /// ```no_run
 /// use tracing_batteries::{Session, OpenTelemetry, OpenTelemetryProtocol};
@@ -54,13 +75,54 @@ pub use tracing::Level as OpenTelemetryLevel;
 /// session.shutdown();
 /// ```
 ///
+/// ## Example (Failover)
+/// ```no_run
+/// use tracing_batteries::{Session, OpenTelemetry};
+///
+/// let session = Session::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+///   .with_battery(OpenTelemetry::new(["collector-a:4317", "collector-b:4317"]));
+///
+/// session.shutdown();
+/// ```
+///
 pub struct OpenTelemetry {
     endpoint: Cow<'static, str>,
+    failover_endpoints: Vec<Cow<'static, str>>,
+    /// The environment variable which replaced the endpoints provided to [`OpenTelemetry::new`], along with
+    /// those endpoints, so that the override can be reported once logging has been set up.
+    endpoint_override: Option<(&'static str, Vec<Cow<'static, str>>)>,
     headers: HashMap<Cow<'static, str>, Cow<'static, str>>,
     protocol: Option<OpenTelemetryProtocol>,
     sampler: OpenTelemetrySampler,
     default_level: Option<OpenTelemetryLevel>,
     force_stdout: Option<bool>,
+    quiet: bool,
+    stdout_level: Option<OpenTelemetryLevel>,
+    stdout_writer: Option<BoxMakeWriter>,
+    stdout_suspend: Option<Arc<crate::suspend::Suspend>>,
+    coalesce_interval: Option<Duration>,
+    span_compression: Option<usize>,
+    max_spans_per_trace: Option<usize>,
+    max_span_depth: Option<usize>,
+    event_metrics: bool,
+    detect_resources: bool,
+    thread_attributes: bool,
+    task_attributes: bool,
+    connectivity_check: Option<Duration>,
+    metric_views: Vec<OpenTelemetryMetricView>,
+    resource_attributes: Vec<(Cow<'static, str>, Cow<'static, str>)>,
+    delta_temporality: bool,
+    attribute_limits: Option<crate::limits::AttributeLimits>,
+    auth: Option<Arc<crate::auth_provider::AuthToken>>,
+    auth_refresh_interval: Duration,
+    #[cfg(feature = "sigv4")]
+    sigv4: Option<Arc<crate::aws::AwsSigner>>,
+    #[cfg(feature = "jaeger")]
+    jaeger_agent: Option<(String, usize)>,
+    #[cfg(feature = "xray")]
+    xray: bool,
+    #[cfg(feature = "xray")]
+    xray_daemon: Option<String>,
 }
 
 impl OpenTelemetry {
@@ -70,6 +132,15 @@ impl OpenTelemetry {
     /// the endpoint should correspond to the configured [`OpenTelemetryProtocol`] in use
     /// (e.g. `http://localhost:4318` for HTTP, or `localhost:4317` for gRPC).
     ///
+    /// You may also provide a list of collector endpoints (either here, or as a comma separated list in the
+    /// `TRACING_BATTERIES_OTLP_ENDPOINTS` environment variable). Spans are exported to the first endpoint until an
+    /// export fails, at which point the batch is retried against the next endpoint (in round-robin order) which
+    /// then receives subsequent exports. Failover only applies to spans (and the events recorded within them), while
+    /// metrics are only ever exported to the first endpoint.
+    ///
+    /// When set, the `TRACING_BATTERIES_OTLP_ENDPOINTS` or (standard, single endpoint) `OTEL_EXPORTER_OTLP_ENDPOINT`
+    /// environment variables replace the endpoints provided here, which is logged when the session starts.
+    ///
     /// ## Example
     /// ```no_run
     /// use tracing_batteries::{Session, OpenTelemetry};
@@ -79,11 +150,41 @@ impl OpenTelemetry {
     ///
     /// session.shutdown();
     /// ```
-    pub fn new<S: Into<Cow<'static, str>>>(endpoint: S) -> Self {
+    pub fn new<E: OpenTelemetryEndpoints>(endpoints: E) -> Self {
+        let configured = endpoints.into_endpoints();
+
+        // The OTLP specification only allows a single endpoint in `OTEL_EXPORTER_OTLP_ENDPOINT`, so lists of
+        // failover endpoints are read from a variable of our own.
+        let overrides = std::env::var(OTLP_ENDPOINTS_ENV)
+            .map(|endpoints| {
+                let endpoints = endpoints
+                    .split(',')
+                    .map(|endpoint| Cow::Owned(endpoint.trim().to_string()))
+                    .collect::<Vec<_>>();
+                (OTLP_ENDPOINTS_ENV, endpoints)
+            })
+            .or_else(|_| {
+                std::env::var("OTEL_EXPORTER_OTLP_ENDPOINT")
+                    .map(|endpoint| ("OTEL_EXPORTER_OTLP_ENDPOINT", vec![Cow::Owned(endpoint)]))
+            })
+            .ok();
+
+        let (mut endpoints, endpoint_override) = match overrides {
+            Some((variable, endpoints)) => {
+                let replaced = configured.iter().any(|endpoint| !endpoint.is_empty())
+                    && configured != endpoints;
+                (
+                    endpoints.into_iter(),
+                    replaced.then_some((variable, configured)),
+                )
+            }
+            None => (configured.into_iter(), None),
+        };
+
         Self {
-            endpoint: std::env::var("OTEL_EXPORTER_OTLP_ENDPOINT")
-                .map(Cow::Owned)
-                .unwrap_or_else(|_| endpoint.into()),
+            endpoint: endpoints.next().unwrap_or_default(),
+            failover_endpoints: endpoints.filter(|endpoint| !endpoint.is_empty()).collect(),
+            endpoint_override,
             headers: {
                 let mut headers = HashMap::new();
 
@@ -100,6 +201,33 @@ impl OpenTelemetry {
             sampler: Self::build_sampler(),
             default_level: None,
             force_stdout: None,
+            quiet: false,
+            stdout_level: None,
+            stdout_writer: None,
+            stdout_suspend: None,
+            coalesce_interval: None,
+            span_compression: None,
+            max_spans_per_trace: None,
+            max_span_depth: None,
+            event_metrics: true,
+            detect_resources: false,
+            thread_attributes: true,
+            task_attributes: false,
+            connectivity_check: None,
+            metric_views: Vec::new(),
+            resource_attributes: Vec::new(),
+            delta_temporality: false,
+            attribute_limits: None,
+            auth: None,
+            auth_refresh_interval: Duration::from_secs(15 * 60),
+            #[cfg(feature = "sigv4")]
+            sigv4: None,
+            #[cfg(feature = "jaeger")]
+            jaeger_agent: None,
+            #[cfg(feature = "xray")]
+            xray: false,
+            #[cfg(feature = "xray")]
+            xray_daemon: None,
         }
     }
 
@@ -133,6 +261,118 @@ impl OpenTelemetry {
         self
     }
 
+    /// Configures a provider for the `Authorization` header sent to the OpenTelemetry collector, for credentials
+    /// which expire and can't be configured as a static header (such as OAuth2 client credentials or workload
+    /// identity tokens).
+    ///
+    /// The provider is consulted when the integration starts, and then periodically in the background (every 15
+    /// minutes by default, see [`OpenTelemetry::with_auth_refresh_interval`]). When exporting over HTTP, it is also
+    /// consulted whenever the collector rejects an export with a `401 Unauthorized` response, after which the export
+    /// is retried.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use tracing_batteries::{OpenTelemetry, OpenTelemetryHeaderValue};
+    ///
+    /// # async fn fetch_access_token() -> String { String::new() }
+    /// OpenTelemetry::new("localhost:4317")
+    ///   .with_auth_provider(|| async {
+    ///     let token = fetch_access_token().await;
+    ///     OpenTelemetryHeaderValue::from_str(&format!("Bearer {token}")).unwrap()
+    ///   });
+    /// ```
+    pub fn with_auth_provider<F, Fut>(self, provider: F) -> Self
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: std::future::Future<Output = OpenTelemetryHeaderValue> + Send + 'static,
+    {
+        Self {
+            auth: Some(Arc::new(crate::auth_provider::AuthToken::new(provider))),
+            ..self
+        }
+    }
+
+    /// Configures how frequently the provider configured using [`OpenTelemetry::with_auth_provider`] is consulted
+    /// for a fresh `Authorization` header, which should be comfortably shorter than the lifetime of its tokens.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use tracing_batteries::{OpenTelemetry, OpenTelemetryHeaderValue};
+    /// use std::time::Duration;
+    ///
+    /// OpenTelemetry::new("localhost:4317")
+    ///   .with_auth_provider(|| async { OpenTelemetryHeaderValue::from_static("Bearer my-token") })
+    ///   .with_auth_refresh_interval(Duration::from_secs(5 * 60));
+    /// ```
+    pub fn with_auth_refresh_interval(self, interval: Duration) -> Self {
+        Self {
+            auth_refresh_interval: interval,
+            ..self
+        }
+    }
+
+    /// Signs each export using [AWS Signature Version 4](https://docs.aws.amazon.com/IAM/latest/UserGuide/reference_sigv.html),
+    /// allowing traces and metrics to be sent directly to AWS-managed OTLP endpoints (such as OpenSearch Ingestion,
+    /// using the `osis` service, or Amazon Managed Service for Prometheus, using the `aps` service).
+    ///
+    /// <div class="warning">
+    ///
+    /// This method requires the `sigv4` feature to be enabled.
+    ///
+    /// </div>
+    ///
+    /// Signing is only supported for OTLP over HTTP, which is used by default once signing has been enabled (unless
+    /// another protocol is configured explicitly). The region is read from the `AWS_REGION` or `AWS_DEFAULT_REGION`
+    /// environment variables, and credentials are resolved from the standard AWS credential chain (environment
+    /// variables, the shared credentials file, the ECS/Lambda container credentials endpoint, or the EC2 instance
+    /// metadata service).
+    ///
+    /// ## Example
+    /// ```rust
+    /// use tracing_batteries::OpenTelemetry;
+    ///
+    /// OpenTelemetry::new("https://my-pipeline.us-east-1.osis.amazonaws.com")
+    ///   .with_aws_sigv4("osis");
+    /// ```
+    #[cfg(feature = "sigv4")]
+    pub fn with_aws_sigv4(self, service: &'static str) -> Self {
+        Self {
+            protocol: self.protocol.or(Some(OpenTelemetryProtocol::HttpBinary)),
+            sigv4: Some(Arc::new(crate::aws::AwsSigner::new(
+                service,
+                crate::aws::default_region(),
+            ))),
+            ..self
+        }
+    }
+
+    /// Overrides the collector endpoint, ignoring the `OTEL_EXPORTER_OTLP_ENDPOINT` and `TRACING_BATTERIES_OTLP_ENDPOINTS`
+    /// environment variables.
+    ///
+    /// This is used by batteries which wrap the OpenTelemetry integration for a specific vendor and
+    /// derive the endpoint from their own configuration.
+    #[cfg_attr(
+        not(any(
+            feature = "coralogix",
+            feature = "dynatrace",
+            feature = "grafana-cloud",
+            feature = "instana",
+            feature = "newrelic",
+            feature = "openobserve",
+            feature = "signoz",
+            feature = "uptrace"
+        )),
+        allow(dead_code)
+    )]
+    pub(crate) fn with_endpoint<S: Into<Cow<'static, str>>>(self, endpoint: S) -> Self {
+        Self {
+            endpoint: endpoint.into(),
+            failover_endpoints: Vec::new(),
+            endpoint_override: None,
+            ..self
+        }
+    }
+
     /// Configures the OpenTelemetry integration to use the provided protocol.
     ///
     /// This method is used to configure the protocol used to communicate with the OpenTelemetry collector,
@@ -219,198 +459,1436 @@ impl OpenTelemetry {
         }
     }
 
-    fn build_opentelemetry_layer<S>(
-        &self,
-        metadata: &crate::Metadata,
-    ) -> Option<Box<dyn Layer<S> + Send + Sync + 'static>>
+    /// Configures the level of the events which are written to stdout, independently of the level of the telemetry
+    /// which is exported to the collector (see [`OpenTelemetry::with_default_level`]).
+    ///
+    /// By default, stdout uses the same level as the collector. This is commonly used to export detailed telemetry
+    /// while keeping your console output quiet.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use tracing_batteries::{OpenTelemetry, OpenTelemetryLevel};
+    ///
+    /// OpenTelemetry::new("localhost:4317")
+    ///   .with_default_level(OpenTelemetryLevel::DEBUG)
+    ///   .with_stdout(true)
+    ///   .with_stdout_level(OpenTelemetryLevel::WARN);
+    /// ```
+    pub fn with_stdout_level(self, level: OpenTelemetryLevel) -> Self {
+        Self {
+            stdout_level: Some(level),
+            ..self
+        }
+    }
+
+    /// Configures where the events written to stdout are written to instead, such as stderr or a log file.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use tracing_batteries::OpenTelemetry;
+    ///
+    /// OpenTelemetry::new("localhost:4317")
+    ///   .with_stdout(true)
+    ///   .with_stdout_writer(std::io::stderr);
+    /// ```
+    pub fn with_stdout_writer<W>(self, writer: W) -> Self
     where
-        S: Subscriber + Send + Sync,
-        for<'a> S: LookupSpan<'a>,
+        W: for<'a> MakeWriter<'a> + Send + Sync + 'static,
     {
-        if self.endpoint.is_empty() {
-            return None;
+        Self {
+            stdout_writer: Some(BoxMakeWriter::new(writer)),
+            ..self
         }
-
-        let pipeline_builder = opentelemetry_sdk::trace::Builder::default()
-            .with_resource(self.build_resource(metadata))
-            .with_sampler(self.sampler.clone());
-
-        let pipeline_builder = match self.get_protocol() {
-            OpenTelemetryProtocol::Grpc => pipeline_builder.with_batch_exporter(
-                opentelemetry_otlp::SpanExporter::builder()
-                    .with_tonic()
-                    .with_endpoint(self.endpoint.clone())
-                    .with_metadata({
-                        let mut tracing_metadata = tonic::metadata::MetadataMap::new();
-                        for (key, value) in self.headers.iter() {
-                            tracing_metadata.insert(
-                                key.parse()
-                                    .unwrap_or(tonic::metadata::MetadataKey::from_static("")),
-                                value
-                                    .to_string()
-                                    .parse()
-                                    .unwrap_or(tonic::metadata::MetadataValue::from_static("")),
-                            );
-                        }
-                        tracing_metadata
-                    })
-                    .build()
-                    .ok()?,
-                opentelemetry_sdk::runtime::Tokio,
-            ),
-            proto @ (OpenTelemetryProtocol::HttpBinary | OpenTelemetryProtocol::HttpJson) => {
-                pipeline_builder.with_batch_exporter(
-                    opentelemetry_otlp::SpanExporter::builder()
-                        .with_http()
-                        .with_protocol(proto)
-                        .with_endpoint(format!("{}/v1/traces", self.endpoint))
-                        .with_headers({
-                            let mut tracing_headers = HashMap::new();
-                            for (key, value) in self.headers.iter() {
-                                tracing_headers.insert(key.to_string(), value.to_string());
-                            }
-                            tracing_headers
-                        })
-                        .with_http_client(reqwest::Client::new())
-                        .build()
-                        .ok()?,
-                    opentelemetry_sdk::runtime::Tokio,
-                )
-            }
-        };
-
-        let provider = pipeline_builder.build();
-        opentelemetry::global::set_tracer_provider(provider.clone());
-
-        Some(Box::new(tracing_opentelemetry::OpenTelemetryLayer::new(
-            provider.tracer(metadata.service.clone()),
-        )))
     }
 
-    fn get_protocol(&self) -> OpenTelemetryProtocol {
-        match std::env::var("OTEL_EXPORTER_OTLP_PROTOCOL").ok().as_deref() {
-            Some("http-binary") => opentelemetry_otlp::Protocol::HttpBinary,
-            Some("http-json") => opentelemetry_otlp::Protocol::HttpJson,
-            Some("grpc") => opentelemetry_otlp::Protocol::Grpc,
-            _ => self.protocol.unwrap_or(OpenTelemetryProtocol::Grpc),
+    /// Configures a hook which each line written to stdout is written within, allowing progress bars (such as those
+    /// drawn by `indicatif`) to be cleared before the line is written and redrawn afterwards, rather than being
+    /// corrupted by it.
+    ///
+    /// The hook is provided with a function which writes the line, and must call it exactly once.
+    ///
+    /// ## Example
+    /// ```rust,ignore
+    /// use indicatif::ProgressBar;
+    /// use tracing_batteries::OpenTelemetry;
+    ///
+    /// let progress = ProgressBar::new(100);
+    ///
+    /// OpenTelemetry::new("localhost:4317")
+    ///   .with_stdout(true)
+    ///   .with_stdout_suspend({
+    ///     let progress = progress.clone();
+    ///     move |write| progress.suspend(write)
+    ///   });
+    /// ```
+    pub fn with_stdout_suspend<F>(self, suspend: F) -> Self
+    where
+        F: Fn(&mut dyn FnMut()) + Send + Sync + 'static,
+    {
+        Self {
+            stdout_suspend: Some(Arc::new(suspend)),
+            ..self
         }
     }
 
-    fn build_resource(&self, metadata: &crate::Metadata) -> Resource {
-        let mut resource_metadata = vec![
-            opentelemetry::KeyValue::new("service.name", metadata.service.clone()),
-            opentelemetry::KeyValue::new("service.version", metadata.version.clone()),
-            opentelemetry::KeyValue::new("host.os", std::env::consts::OS),
-            opentelemetry::KeyValue::new("host.architecture", std::env::consts::ARCH),
-        ];
+    /// Configures the OpenTelemetry integration to write nothing to stdout, as is commonly requested using a
+    /// `--quiet` flag, while continuing to export telemetry to the collector.
+    ///
+    /// Quiet mode takes precedence over [`OpenTelemetry::with_stdout`], and also prevents events from being written
+    /// to stdout when no collector endpoint is configured or the collector can't be reached.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use tracing_batteries::OpenTelemetry;
+    ///
+    /// let quiet = std::env::args().any(|arg| arg == "--quiet" || arg == "-q");
+    ///
+    /// OpenTelemetry::new("localhost:4317")
+    ///   .with_quiet(quiet);
+    /// ```
+    pub fn with_quiet(self, quiet: bool) -> Self {
+        Self { quiet, ..self }
+    }
 
-        for (key, value) in metadata.context.iter() {
-            resource_metadata.push(opentelemetry::KeyValue::new(*key, value.clone()));
+    /// Configures the OpenTelemetry integration to check that the collector endpoint is reachable during startup.
+    ///
+    /// When enabled, a connection will be opened to the configured endpoint (waiting at most for the provided
+    /// timeout) before the exporter is installed. If the endpoint cannot be reached, a warning describing the
+    /// problem will be logged and, unless [`OpenTelemetry::with_stdout`] has been used to disable it, events
+    /// will also be written to stdout so that they are not lost.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use tracing_batteries::OpenTelemetry;
+    /// use std::time::Duration;
+    ///
+    /// OpenTelemetry::new("localhost:4317")
+    ///   .with_connectivity_check(Duration::from_secs(2));
+    /// ```
+    pub fn with_connectivity_check(self, timeout: Duration) -> Self {
+        Self {
+            connectivity_check: Some(timeout),
+            ..self
         }
+    }
 
-        Resource::new(resource_metadata)
+    /// Adds a view which customizes how a metric instrument is exported by the OpenTelemetry integration.
+    ///
+    /// Views may be used to rename instruments, configure the bucket boundaries used by histograms, and
+    /// restrict the attributes which are exported (dropping any high-cardinality attributes which are not listed).
+    ///
+    /// ## Example
+    /// ```rust
+    /// use tracing_batteries::{OpenTelemetry, OpenTelemetryMetricView};
+    ///
+    /// OpenTelemetry::new("localhost:4317")
+    ///   .with_metric_view(OpenTelemetryMetricView::new("operation.duration")
+    ///     .with_histogram_buckets([0.01, 0.05, 0.1, 0.5, 1.0, 5.0])
+    ///     .with_allowed_attributes(["operation"]));
+    /// ```
+    pub fn with_metric_view(mut self, view: OpenTelemetryMetricView) -> Self {
+        self.metric_views.push(view);
+        self
     }
 
-    fn build_sampler() -> Sampler {
-        fn get_trace_ratio() -> f64 {
-            std::env::var("OTEL_TRACES_SAMPLER_ARG")
-                .ok()
-                .and_then(|ratio| ratio.parse().ok())
-                .unwrap_or(1.0)
+    /// Configures the OpenTelemetry integration to coalesce identical events which are emitted in quick succession.
+    ///
+    /// When enabled, the first occurrence of an event will be emitted as normal while any identical events
+    /// (those emitted from the same location with the same fields) are suppressed. Once the provided interval
+    /// elapses, a single event with a `count` field is emitted for each suppressed event, reporting how many
+    /// times it was repeated. This is useful for protecting both your console output and your ingestion
+    /// quotas from events which are emitted in tight loops.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use tracing_batteries::OpenTelemetry;
+    /// use std::time::Duration;
+    ///
+    /// OpenTelemetry::new("localhost:4317")
+    ///   .with_event_coalescing(Duration::from_secs(10));
+    /// ```
+    pub fn with_event_coalescing(self, interval: Duration) -> Self {
+        Self {
+            coalesce_interval: Some(interval),
+            ..self
         }
-
-        std::env::var("OTEL_TRACES_SAMPLER")
-            .map(|s| match s.as_str() {
-                "always_on" => opentelemetry_sdk::trace::Sampler::AlwaysOn,
-                "always_off" => opentelemetry_sdk::trace::Sampler::AlwaysOff,
-                "traceidratio" => {
-                    opentelemetry_sdk::trace::Sampler::TraceIdRatioBased(get_trace_ratio())
-                }
-                "parentbased_always_on" => opentelemetry_sdk::trace::Sampler::ParentBased(
-                    Box::new(opentelemetry_sdk::trace::Sampler::AlwaysOn),
-                ),
-                "parentbased_always_off" => opentelemetry_sdk::trace::Sampler::ParentBased(
-                    Box::new(opentelemetry_sdk::trace::Sampler::AlwaysOff),
-                ),
-                "parentbased_traceidratio" => {
-                    opentelemetry_sdk::trace::Sampler::ParentBased(Box::new(
-                        opentelemetry_sdk::trace::Sampler::TraceIdRatioBased(get_trace_ratio()),
-                    ))
-                }
-                _ => opentelemetry_sdk::trace::Sampler::AlwaysOn,
-            })
-            .unwrap_or(opentelemetry_sdk::trace::Sampler::AlwaysOn)
     }
 
-    fn build_level(&self) -> OpenTelemetryLevel {
-        match std::env::var("LOG_LEVEL")
-            .map(|s| s.to_lowercase())
-            .as_deref()
-        {
-            Ok("error") => OpenTelemetryLevel::ERROR,
-            Ok("warn") => OpenTelemetryLevel::WARN,
-            Ok("info") => OpenTelemetryLevel::INFO,
-            Ok("debug") => OpenTelemetryLevel::DEBUG,
-            Ok("trace") => OpenTelemetryLevel::TRACE,
-            _ => self.default_level.unwrap_or(OpenTelemetryLevel::INFO),
+    /// Configures the OpenTelemetry integration to collapse runs of identical child spans before they are exported.
+    ///
+    /// When enabled, any run of more than `threshold` consecutive sibling spans with the same name, status and
+    /// attributes (such as those produced by tight retry or polling loops) is replaced by a single span which
+    /// covers the whole run and carries a `compression.count` attribute holding the number of spans it replaced.
+    /// Runs are detected within each exported batch, and spans which have children of their own are never
+    /// collapsed.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use tracing_batteries::OpenTelemetry;
+    ///
+    /// OpenTelemetry::new("localhost:4317")
+    ///   .with_span_compression(3);
+    /// ```
+    pub fn with_span_compression(self, threshold: usize) -> Self {
+        Self {
+            span_compression: Some(threshold),
+            ..self
         }
     }
-}
-
-impl BatteryBuilder for OpenTelemetry {
-    fn setup(self, metadata: &crate::Metadata, enabled: Arc<AtomicBool>) -> Box<dyn Battery> {
-        opentelemetry::global::set_text_map_propagator(
-            opentelemetry_sdk::propagation::TraceContextPropagator::new(),
-        );
-
-        let registry = tracing_subscriber::registry()
-            .with(match self.build_level() {
-                OpenTelemetryLevel::ERROR => tracing_subscriber::filter::LevelFilter::ERROR,
-                OpenTelemetryLevel::WARN => tracing_subscriber::filter::LevelFilter::WARN,
-                OpenTelemetryLevel::INFO => tracing_subscriber::filter::LevelFilter::INFO,
-                OpenTelemetryLevel::DEBUG => tracing_subscriber::filter::LevelFilter::DEBUG,
-                OpenTelemetryLevel::TRACE => tracing_subscriber::filter::LevelFilter::TRACE,
-            })
-            .with(tracing_subscriber::filter::dynamic_filter_fn(
-                move |_meta, _ctx| enabled.load(std::sync::atomic::Ordering::Relaxed),
-            ));
 
-        if let Some(provider) = self.build_opentelemetry_layer(metadata) {
-            match self.force_stdout {
-                Some(true) => {
-                    registry
-                        .with(provider)
-                        .with(
-                            tracing_subscriber::filter::filter_fn(|meta| meta.is_event())
-                                .and_then(tracing_subscriber::fmt::layer()),
-                        )
-                        .init();
-                }
-                _ => {
-                    registry.with(provider).init();
-                }
-            }
-        } else if !matches!(self.force_stdout, Some(false)) {
-            registry
-                .with(
-                    tracing_subscriber::filter::filter_fn(|meta| meta.is_event())
-                        .and_then(tracing_subscriber::fmt::layer()),
-                )
-                .init();
+    /// Configures the maximum number of spans which will be exported for each trace, protecting your backend from
+    /// pathological traces such as those produced by runaway recursion.
+    ///
+    /// Once a trace reaches the limit, any further spans within it are not exported (and neither are the events
+    /// recorded within them, although these are still logged by your other batteries). The dropped spans are counted in the
+    /// [`diagnostics`](crate::diagnostics) report and a warning is emitted when the trace's root span closes.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use tracing_batteries::OpenTelemetry;
+    ///
+    /// OpenTelemetry::new("localhost:4317")
+    ///   .with_max_spans_per_trace(10_000);
+    /// ```
+    pub fn with_max_spans_per_trace(self, max_spans: usize) -> Self {
+        Self {
+            max_spans_per_trace: Some(max_spans),
+            ..self
         }
-
-        Box::new(OpenTelemetryBattery {})
     }
-}
 
-struct OpenTelemetryBattery {}
+    /// Configures the maximum depth to which spans may be nested before they are no longer exported, where root
+    /// spans have a depth of 1.
+    ///
+    /// Spans nested beyond the limit are dropped in the same way as those which exceed
+    /// [`OpenTelemetry::with_max_spans_per_trace`].
+    ///
+    /// ## Example
+    /// ```rust
+    /// use tracing_batteries::OpenTelemetry;
+    ///
+    /// OpenTelemetry::new("localhost:4317")
+    ///   .with_max_span_depth(64);
+    /// ```
+    pub fn with_max_span_depth(self, max_depth: usize) -> Self {
+        Self {
+            max_span_depth: Some(max_depth),
+            ..self
+        }
+    }
+
+    /// Configures whether the OpenTelemetry integration counts the tracing events emitted by your application.
+    ///
+    /// When enabled (the default), each event is counted in the `log_events_total` metric with `level` and
+    /// `target` attributes, allowing you to build error rate dashboards and alerts for services which only
+    /// emit logs. Only events which pass the configured level filter are counted.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use tracing_batteries::OpenTelemetry;
+    ///
+    /// OpenTelemetry::new("localhost:4317")
+    ///   .with_event_metrics(false);
+    /// ```
+    pub fn with_event_metrics(self, enabled: bool) -> Self {
+        Self {
+            event_metrics: enabled,
+            ..self
+        }
+    }
+
+    /// Configures the OpenTelemetry integration to automatically detect container and Kubernetes resource attributes.
+    ///
+    /// When enabled, the `container.id`, `k8s.namespace.name`, `k8s.pod.name`, `k8s.pod.uid`, and `k8s.node.name`
+    /// attributes will be added to the exported resource when they can be detected. The container ID is read from the
+    /// process' cgroup membership, while the Kubernetes attributes are read from the pod's service account mount and the
+    /// downward API environment variables (`K8S_NAMESPACE_NAME`, `K8S_POD_NAME`, `K8S_POD_UID`, and `K8S_NODE_NAME`).
+    ///
+    /// Any context provided through [`Metadata::with_context`](crate::Metadata::with_context) will take precedence
+    /// over the detected attributes.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use tracing_batteries::OpenTelemetry;
+    ///
+    /// OpenTelemetry::new("localhost:4317")
+    ///   .with_detected_resources(true);
+    /// ```
+    pub fn with_detected_resources(self, enabled: bool) -> Self {
+        Self {
+            detect_resources: enabled,
+            ..self
+        }
+    }
+
+    /// Configures whether spans and events exported to OpenTelemetry are stamped with the `thread.name` and
+    /// `thread.id` of the thread which emitted them.
+    ///
+    /// Thread attributes are enabled by default.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use tracing_batteries::OpenTelemetry;
+    ///
+    /// OpenTelemetry::new("localhost:4317")
+    ///   .with_thread_attributes(false);
+    /// ```
+    pub fn with_thread_attributes(self, enabled: bool) -> Self {
+        Self {
+            thread_attributes: enabled,
+            ..self
+        }
+    }
+
+    /// Configures whether spans and events exported to OpenTelemetry are stamped with the `tokio.task.id`
+    /// of the Tokio task which emitted them.
+    ///
+    /// <div class="warning">
+    ///
+    /// This method is only available when the `tokio` feature is enabled.
+    ///
+    /// </div>
+    ///
+    /// ## Example
+    /// ```rust
+    /// use tracing_batteries::OpenTelemetry;
+    ///
+    /// OpenTelemetry::new("localhost:4317")
+    ///   .with_task_attributes(true);
+    /// ```
+    #[cfg(feature = "tokio")]
+    pub fn with_task_attributes(self, enabled: bool) -> Self {
+        Self {
+            task_attributes: enabled,
+            ..self
+        }
+    }
+
+    /// Adds an attribute to the resource which is attached to all exported spans and metrics.
+    ///
+    /// Any context provided through [`Metadata::with_context`](crate::Metadata::with_context) will take precedence
+    /// over attributes configured here.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use tracing_batteries::OpenTelemetry;
+    ///
+    /// OpenTelemetry::new("localhost:4317")
+    ///   .with_resource_attribute("deployment.environment", "production");
+    /// ```
+    pub fn with_resource_attribute<K: Into<Cow<'static, str>>, V: Into<Cow<'static, str>>>(
+        mut self,
+        key: K,
+        value: V,
+    ) -> Self {
+        self.resource_attributes.push((key.into(), value.into()));
+        self
+    }
+
+    /// Configures metrics to be exported using delta temporality, as required by some vendor backends.
+    #[cfg_attr(not(feature = "dynatrace"), allow(dead_code))]
+    pub(crate) fn with_delta_temporality(self, enabled: bool) -> Self {
+        Self {
+            delta_temporality: enabled,
+            ..self
+        }
+    }
+
+    /// Configures the maximum length of attribute keys and values, truncating any which exceed it before export.
+    #[cfg_attr(not(feature = "newrelic"), allow(dead_code))]
+    pub(crate) fn with_attribute_limits(self, limits: crate::limits::AttributeLimits) -> Self {
+        Self {
+            attribute_limits: Some(limits),
+            ..self
+        }
+    }
+
+    /// Configures spans to be exported to a Jaeger agent over UDP, in packets no larger than `max_packet_size`.
+    #[cfg(feature = "jaeger")]
+    pub(crate) fn with_jaeger_agent(self, agent: String, max_packet_size: usize) -> Self {
+        Self {
+            endpoint: agent.clone().into(),
+            jaeger_agent: Some((agent, max_packet_size)),
+            ..self
+        }
+    }
+
+    /// Configures X-Ray compatible trace IDs and propagation, optionally emitting spans to an X-Ray daemon over UDP.
+    #[cfg(feature = "xray")]
+    pub(crate) fn with_xray(self, daemon: Option<String>) -> Self {
+        Self {
+            endpoint: daemon.clone().map(Cow::Owned).unwrap_or(self.endpoint),
+            xray: true,
+            xray_daemon: daemon,
+            ..self
+        }
+    }
+
+    fn build_opentelemetry_layer<S>(
+        &self,
+        metadata: &crate::Metadata,
+    ) -> Option<Box<dyn Layer<S> + Send + Sync + 'static>>
+    where
+        S: Subscriber + Send + Sync,
+        for<'a> S: LookupSpan<'a>,
+    {
+        if self.endpoint.is_empty() {
+            return None;
+        }
+
+        let pipeline_builder = opentelemetry_sdk::trace::Builder::default()
+            .with_resource(self.build_resource(metadata))
+            .with_sampler(crate::sampling::NotifyingSampler::new(self.sampler.clone()));
+
+        #[cfg(feature = "xray")]
+        let pipeline_builder = if self.xray {
+            pipeline_builder.with_id_generator(crate::xray::XRayIdGenerator::default())
+        } else {
+            pipeline_builder
+        };
+
+        #[cfg(feature = "jaeger")]
+        if let Some((agent, max_packet_size)) = &self.jaeger_agent {
+            let provider = pipeline_builder
+                .with_batch_exporter(
+                    self.wrap_exporter(
+                        crate::jaeger::JaegerAgentExporter::new(agent, *max_packet_size).ok()?,
+                    ),
+                    opentelemetry_sdk::runtime::Tokio,
+                )
+                .build();
+
+            return Some(self.build_tracing_layer(provider, metadata));
+        }
+
+        #[cfg(feature = "xray")]
+        if let Some(daemon) = &self.xray_daemon {
+            let provider = pipeline_builder
+                .with_batch_exporter(
+                    self.wrap_exporter(crate::xray::XRayDaemonExporter::new(daemon).ok()?),
+                    opentelemetry_sdk::runtime::Tokio,
+                )
+                .build();
+
+            return Some(self.build_tracing_layer(provider, metadata));
+        }
+
+        let exporters = self
+            .endpoints()
+            .map(|endpoint| Some((endpoint.to_string(), self.build_span_exporter(endpoint)?)))
+            .collect::<Option<Vec<_>>>()?;
+
+        let provider = pipeline_builder
+            .with_batch_exporter(
+                self.wrap_exporter(crate::failover::FailoverSpanExporter::new(exporters)),
+                opentelemetry_sdk::runtime::Tokio,
+            )
+            .build();
+
+        Some(self.build_tracing_layer(provider, metadata))
+    }
+
+    fn build_span_exporter(&self, endpoint: &str) -> Option<opentelemetry_otlp::SpanExporter> {
+        match self.get_protocol() {
+            OpenTelemetryProtocol::Grpc => self
+                .with_grpc_auth(
+                    opentelemetry_otlp::SpanExporter::builder()
+                        .with_tonic()
+                        .with_endpoint(endpoint)
+                        .with_metadata(self.build_grpc_metadata()),
+                )
+                .build()
+                .ok(),
+            proto @ (OpenTelemetryProtocol::HttpBinary | OpenTelemetryProtocol::HttpJson) => self
+                .with_http_client(
+                    opentelemetry_otlp::SpanExporter::builder()
+                        .with_http()
+                        .with_protocol(proto)
+                        .with_endpoint(format!("{endpoint}/v1/traces"))
+                        .with_headers(self.build_http_headers()),
+                )
+                .build()
+                .ok(),
+        }
+    }
+
+    /// Attaches the credentials from the configured auth provider (if there is one) to each gRPC export.
+    fn with_grpc_auth<B: WithTonicConfig>(&self, builder: B) -> B {
+        match &self.auth {
+            Some(auth) => builder.with_interceptor(auth.interceptor()),
+            None => builder,
+        }
+    }
+
+    /// Configures the client used for HTTP exports, signing each export (when SigV4 signing has been enabled) or
+    /// attaching the credentials from the configured auth provider (if there is one).
+    fn with_http_client<B: WithHttpConfig>(&self, builder: B) -> B {
+        #[cfg(feature = "sigv4")]
+        if let Some(signer) = &self.sigv4 {
+            return builder
+                .with_http_client(crate::auth_provider::SigV4HttpClient::new(signer.clone()));
+        }
+
+        match &self.auth {
+            Some(auth) => builder.with_http_client(
+                crate::auth_provider::AuthenticatedHttpClient::new(auth.clone()),
+            ),
+            None => builder.with_http_client(reqwest::Client::new()),
+        }
+    }
+
+    /// Returns the collector endpoints which spans are exported to, starting with the primary endpoint.
+    fn endpoints(&self) -> impl Iterator<Item = &str> {
+        std::iter::once(self.endpoint.as_ref()).chain(
+            self.failover_endpoints
+                .iter()
+                .map(|endpoint| endpoint.as_ref()),
+        )
+    }
+
+    /// Wraps the exporter used to ship spans, compressing repetitive spans and applying any attribute limits
+    /// before they are exported.
+    fn wrap_exporter<E: opentelemetry_sdk::export::trace::SpanExporter>(
+        &self,
+        exporter: E,
+    ) -> crate::compression::CompressingSpanExporter<crate::limits::LimitedSpanExporter<E>> {
+        crate::compression::CompressingSpanExporter::new(
+            crate::limits::LimitedSpanExporter::new(exporter, self.attribute_limits),
+            self.span_compression,
+        )
+    }
+
+    fn build_tracing_layer<S>(
+        &self,
+        provider: opentelemetry_sdk::trace::TracerProvider,
+        metadata: &crate::Metadata,
+    ) -> Box<dyn Layer<S> + Send + Sync + 'static>
+    where
+        S: Subscriber + Send + Sync,
+        for<'a> S: LookupSpan<'a>,
+    {
+        opentelemetry::global::set_tracer_provider(provider.clone());
+
+        let layer = tracing_opentelemetry::OpenTelemetryLayer::new(
+            provider.tracer(metadata.service.clone()),
+        )
+        .with_threads(false)
+        .and_then(
+            crate::enrichment::EnrichmentLayer::new(self.thread_attributes, self.task_attributes)
+                .with_parent(crate::propagation::parent_context_from_env()),
+        );
+
+        if self.max_spans_per_trace.is_some() || self.max_span_depth.is_some() {
+            Box::new(crate::guardrails::TraceGuardrails::new(
+                layer,
+                self.max_spans_per_trace,
+                self.max_span_depth,
+            ))
+        } else {
+            Box::new(layer)
+        }
+    }
+
+    fn build_propagator(&self) -> opentelemetry::propagation::TextMapCompositePropagator {
+        #[allow(unused_mut)]
+        let mut propagators: Vec<
+            Box<dyn opentelemetry::propagation::TextMapPropagator + Send + Sync>,
+        > = vec![Box::new(
+            opentelemetry_sdk::propagation::TraceContextPropagator::new(),
+        )];
+
+        #[cfg(feature = "xray")]
+        if self.xray {
+            propagators.push(Box::new(crate::xray::XRayPropagator));
+        }
+
+        opentelemetry::propagation::TextMapCompositePropagator::new(propagators)
+    }
+
+    fn build_meter_provider(&self, metadata: &crate::Metadata) -> Option<SdkMeterProvider> {
+        if self.endpoint.is_empty() {
+            return None;
+        }
+
+        // Jaeger agents only accept spans, so there is nowhere to export metrics to.
+        #[cfg(feature = "jaeger")]
+        if self.jaeger_agent.is_some() {
+            return None;
+        }
+
+        // The X-Ray daemon only accepts segments, so there is nowhere to export metrics to.
+        #[cfg(feature = "xray")]
+        if self.xray_daemon.is_some() {
+            return None;
+        }
+
+        let temporality = if self.delta_temporality {
+            opentelemetry_sdk::metrics::Temporality::Delta
+        } else {
+            opentelemetry_sdk::metrics::Temporality::Cumulative
+        };
+
+        let exporter = match self.get_protocol() {
+            OpenTelemetryProtocol::Grpc => self
+                .with_grpc_auth(
+                    opentelemetry_otlp::MetricExporter::builder()
+                        .with_temporality(temporality)
+                        .with_tonic()
+                        .with_endpoint(self.endpoint.clone())
+                        .with_metadata(self.build_grpc_metadata()),
+                )
+                .build()
+                .ok()?,
+            proto @ (OpenTelemetryProtocol::HttpBinary | OpenTelemetryProtocol::HttpJson) => self
+                .with_http_client(
+                    opentelemetry_otlp::MetricExporter::builder()
+                        .with_temporality(temporality)
+                        .with_http()
+                        .with_protocol(proto)
+                        .with_endpoint(format!("{}/v1/metrics", self.endpoint))
+                        .with_headers(self.build_http_headers()),
+                )
+                .build()
+                .ok()?,
+        };
+
+        let mut builder = SdkMeterProvider::builder()
+            .with_resource(self.build_resource(metadata))
+            .with_reader(
+                opentelemetry_sdk::metrics::PeriodicReader::builder(
+                    exporter,
+                    opentelemetry_sdk::runtime::Tokio,
+                )
+                .build(),
+            );
+
+        for view in self.metric_views.iter().filter_map(|view| view.build()) {
+            builder = builder.with_view(view);
+        }
+
+        let provider = builder.build();
+        opentelemetry::global::set_meter_provider(provider.clone());
+
+        Some(provider)
+    }
+
+    fn build_grpc_metadata(&self) -> tonic::metadata::MetadataMap {
+        let mut grpc_metadata = tonic::metadata::MetadataMap::new();
+        for (key, value) in self.headers.iter() {
+            grpc_metadata.insert(
+                key.parse()
+                    .unwrap_or(tonic::metadata::MetadataKey::from_static("")),
+                value
+                    .to_string()
+                    .parse()
+                    .unwrap_or(tonic::metadata::MetadataValue::from_static("")),
+            );
+        }
+        grpc_metadata
+    }
+
+    fn build_http_headers(&self) -> HashMap<String, String> {
+        let mut http_headers = HashMap::new();
+        for (key, value) in self.headers.iter() {
+            http_headers.insert(key.to_string(), value.to_string());
+        }
+        http_headers
+    }
+
+    /// Builds the battery along with the layers which process telemetry for the collector, returning `None` in place
+    /// of the collector's own layer when no tracer provider is built (because the SDK has been disabled using
+    /// `OTEL_SDK_DISABLED`, or no endpoint has been configured).
+    #[allow(clippy::type_complexity)]
+    fn build_pipeline(
+        &self,
+        metadata: &crate::Metadata,
+        disabled: bool,
+    ) -> (
+        OpenTelemetryBattery,
+        Vec<Box<dyn Layer<Registry> + Send + Sync>>,
+        Option<Box<dyn Layer<Registry> + Send + Sync>>,
+    ) {
+        let mut layers: Vec<Box<dyn Layer<Registry> + Send + Sync>> = Vec::new();
+
+        let coalesce = self.coalesce_interval.map(|interval| {
+            let layer = crate::coalesce::CoalescingLayer::new(interval);
+            let flusher = layer.flusher();
+            layers.push(Box::new(layer));
+            flusher
+        });
+
+        let (mut battery, provider) = if disabled {
+            (OpenTelemetryBattery::new(metadata, None), None)
+        } else {
+            (
+                OpenTelemetryBattery {
+                    endpoints: self
+                        .endpoints()
+                        .map(|endpoint| endpoint.to_string())
+                        .collect(),
+                    ..OpenTelemetryBattery::new(metadata, self.build_meter_provider(metadata))
+                },
+                self.build_opentelemetry_layer(metadata),
+            )
+        };
+        battery.coalesce = coalesce;
+
+        if let Some(meter) = battery.meter.as_ref().filter(|_| self.event_metrics) {
+            layers.push(Box::new(crate::event_metrics::EventMetricsLayer::new(
+                meter,
+            )));
+        }
+
+        (battery, layers, provider)
+    }
+
+    /// Determines whether events should be written to stdout, given whether a collector is configured and reachable.
+    fn use_stdout(&self, collector: bool, unreachable: bool) -> bool {
+        !self.quiet
+            && match self.force_stdout {
+                Some(stdout) => stdout,
+                None => !collector || unreachable,
+            }
+    }
+
+    /// Builds the subscriber which delivers telemetry to the provided collector layers and (optionally) stdout.
+    fn build_subscriber(
+        &mut self,
+        mut layers: Vec<Box<dyn Layer<Registry> + Send + Sync>>,
+        stdout: bool,
+        enabled: Arc<AtomicBool>,
+    ) -> impl Subscriber + Send + Sync + 'static {
+        layers.push(Box::new(crate::layers::extension_layer()));
+
+        // Levels apply to each of our layers rather than the subscriber as a whole, so that stdout and the collector
+        // can be filtered independently, and so that verbose extensions (such as the flight recorder) can still
+        // observe the events which they filter out.
+        let level = LevelFilter::from_level(self.build_level());
+        let mut layers: Vec<Box<dyn Layer<Registry> + Send + Sync>> = vec![
+            Box::new(layers.with_filter(level)),
+            Box::new(crate::layers::verbose_extension_layer()),
+        ];
+
+        if stdout {
+            let stdout_level = self
+                .stdout_level
+                .map(LevelFilter::from_level)
+                .unwrap_or(level);
+
+            let writer = self
+                .stdout_writer
+                .take()
+                .unwrap_or_else(|| BoxMakeWriter::new(std::io::stdout));
+            let writer = match self.stdout_suspend.take() {
+                Some(suspend) => {
+                    BoxMakeWriter::new(crate::suspend::SuspendingMakeWriter::new(writer, suspend))
+                }
+                None => writer,
+            };
+
+            layers.push(Box::new(
+                tracing_subscriber::fmt::layer()
+                    .with_writer(writer)
+                    .with_filter(
+                        stdout_level.and(tracing_subscriber::filter::filter_fn(|meta| {
+                            meta.is_event()
+                        })),
+                    ),
+            ));
+        }
+
+        tracing_subscriber::registry().with(layers).with(
+            tracing_subscriber::filter::dynamic_filter_fn(move |_meta, _ctx| {
+                enabled.load(std::sync::atomic::Ordering::Relaxed)
+            }),
+        )
+    }
+
+    fn is_sdk_disabled() -> bool {
+        std::env::var("OTEL_SDK_DISABLED")
+            .map(|value| value.trim().eq_ignore_ascii_case("true"))
+            .unwrap_or_default()
+    }
+
+    fn get_protocol(&self) -> OpenTelemetryProtocol {
+        match std::env::var("OTEL_EXPORTER_OTLP_PROTOCOL").ok().as_deref() {
+            Some("http-binary") => opentelemetry_otlp::Protocol::HttpBinary,
+            Some("http-json") => opentelemetry_otlp::Protocol::HttpJson,
+            Some("grpc") => opentelemetry_otlp::Protocol::Grpc,
+            _ => self.protocol.unwrap_or(OpenTelemetryProtocol::Grpc),
+        }
+    }
+
+    fn build_resource(&self, metadata: &crate::Metadata) -> Resource {
+        let mut resource_metadata = vec![
+            opentelemetry::KeyValue::new("service.name", metadata.service.clone()),
+            opentelemetry::KeyValue::new("service.version", metadata.version.clone()),
+            opentelemetry::KeyValue::new("host.os", std::env::consts::OS),
+            opentelemetry::KeyValue::new("host.architecture", std::env::consts::ARCH),
+        ];
+
+        if self.detect_resources {
+            resource_metadata.extend(crate::resource_detection::detect_resources());
+        }
+
+        for (key, value) in self.resource_attributes.iter() {
+            resource_metadata.push(opentelemetry::KeyValue::new(key.clone(), value.clone()));
+        }
+
+        for (key, value) in metadata.context.iter() {
+            resource_metadata.push(opentelemetry::KeyValue::new(*key, value.clone()));
+        }
+
+        match self.attribute_limits {
+            Some(limits) => Resource::new(
+                resource_metadata
+                    .into_iter()
+                    .map(|attribute| limits.apply(attribute)),
+            ),
+            None => Resource::new(resource_metadata),
+        }
+    }
+
+    fn build_sampler() -> Sampler {
+        fn get_trace_ratio() -> f64 {
+            std::env::var("OTEL_TRACES_SAMPLER_ARG")
+                .ok()
+                .and_then(|ratio| ratio.parse().ok())
+                .unwrap_or(1.0)
+        }
+
+        std::env::var("OTEL_TRACES_SAMPLER")
+            .map(|s| match s.as_str() {
+                "always_on" => opentelemetry_sdk::trace::Sampler::AlwaysOn,
+                "always_off" => opentelemetry_sdk::trace::Sampler::AlwaysOff,
+                "traceidratio" => {
+                    opentelemetry_sdk::trace::Sampler::TraceIdRatioBased(get_trace_ratio())
+                }
+                "parentbased_always_on" => opentelemetry_sdk::trace::Sampler::ParentBased(
+                    Box::new(opentelemetry_sdk::trace::Sampler::AlwaysOn),
+                ),
+                "parentbased_always_off" => opentelemetry_sdk::trace::Sampler::ParentBased(
+                    Box::new(opentelemetry_sdk::trace::Sampler::AlwaysOff),
+                ),
+                "parentbased_traceidratio" => {
+                    opentelemetry_sdk::trace::Sampler::ParentBased(Box::new(
+                        opentelemetry_sdk::trace::Sampler::TraceIdRatioBased(get_trace_ratio()),
+                    ))
+                }
+                _ => opentelemetry_sdk::trace::Sampler::AlwaysOn,
+            })
+            .unwrap_or(opentelemetry_sdk::trace::Sampler::AlwaysOn)
+    }
+
+    fn build_level(&self) -> OpenTelemetryLevel {
+        match std::env::var("LOG_LEVEL")
+            .map(|s| s.to_lowercase())
+            .as_deref()
+        {
+            Ok("error") => OpenTelemetryLevel::ERROR,
+            Ok("warn") => OpenTelemetryLevel::WARN,
+            Ok("info") => OpenTelemetryLevel::INFO,
+            Ok("debug") => OpenTelemetryLevel::DEBUG,
+            Ok("trace") => OpenTelemetryLevel::TRACE,
+            _ => self.default_level.unwrap_or(OpenTelemetryLevel::INFO),
+        }
+    }
+}
+
+impl BatteryBuilder for OpenTelemetry {
+    fn setup(mut self, metadata: &crate::Metadata, enabled: Arc<AtomicBool>) -> Box<dyn Battery> {
+        let disabled = Self::is_sdk_disabled();
+        if !disabled {
+            opentelemetry::global::set_text_map_propagator(self.build_propagator());
+
+            if let Some(auth) = self.auth.as_ref().filter(|_| !self.endpoint.is_empty()) {
+                auth.start(self.auth_refresh_interval);
+            }
+        }
+
+        let (battery, mut layers, provider) = self.build_pipeline(metadata, disabled);
+
+        // With failover endpoints configured, telemetry is only lost if none of the collectors can be reached.
+        let unreachable = match (self.connectivity_check, &provider) {
+            (Some(timeout), Some(_)) => {
+                let mut unreachable = None;
+                for endpoint in self.endpoints() {
+                    match crate::preflight::check_reachable(endpoint, timeout) {
+                        Ok(()) => {
+                            unreachable = None;
+                            break;
+                        }
+                        Err(err) => {
+                            unreachable.get_or_insert(err);
+                        }
+                    }
+                }
+
+                unreachable
+            }
+            _ => None,
+        };
+
+        let stdout = self.use_stdout(provider.is_some(), unreachable.is_some());
+        if let Some(provider) = provider {
+            layers.push(provider);
+        } else if !stdout {
+            return Box::new(battery);
+        }
+
+        self.build_subscriber(layers, stdout, enabled).init();
+
+        if let Some((variable, configured)) = &self.endpoint_override {
+            tracing::info!(
+                endpoint = %self.endpoints().collect::<Vec<_>>().join(", "),
+                "The {} environment variable replaced the configured OpenTelemetry endpoints ({}).",
+                variable,
+                configured.join(", ")
+            );
+        }
+
+        if let Some(err) = unreachable {
+            tracing::warn!(
+                endpoint = %self.endpoints().collect::<Vec<_>>().join(", "),
+                "The OpenTelemetry collector endpoint could not be reached, telemetry may not be exported: {}",
+                err
+            );
+        }
+
+        Box::new(battery)
+    }
+}
+
+/// The collector endpoint (or list of failover endpoints) which may be provided to [`OpenTelemetry::new`].
+///
+/// <div class="warning">
+///
+/// This type requires the `opentelemetry` feature to be enabled.
+///
+/// </div>
+///
+/// This is implemented for string types (a single endpoint), along with arrays and [`Vec`]s of them (a list of
+/// endpoints which are failed over between).
+pub trait OpenTelemetryEndpoints {
+    /// Converts the value into a list of endpoints, starting with the primary endpoint.
+    fn into_endpoints(self) -> Vec<Cow<'static, str>>;
+}
+
+impl OpenTelemetryEndpoints for &'static str {
+    fn into_endpoints(self) -> Vec<Cow<'static, str>> {
+        vec![Cow::Borrowed(self)]
+    }
+}
+
+impl OpenTelemetryEndpoints for String {
+    fn into_endpoints(self) -> Vec<Cow<'static, str>> {
+        vec![Cow::Owned(self)]
+    }
+}
+
+impl OpenTelemetryEndpoints for Cow<'static, str> {
+    fn into_endpoints(self) -> Vec<Cow<'static, str>> {
+        vec![self]
+    }
+}
+
+impl<S: Into<Cow<'static, str>>, const N: usize> OpenTelemetryEndpoints for [S; N] {
+    fn into_endpoints(self) -> Vec<Cow<'static, str>> {
+        self.into_iter().map(Into::into).collect()
+    }
+}
+
+impl<S: Into<Cow<'static, str>>> OpenTelemetryEndpoints for Vec<S> {
+    fn into_endpoints(self) -> Vec<Cow<'static, str>> {
+        self.into_iter().map(Into::into).collect()
+    }
+}
+
+/// A view which customizes how a metric instrument is exported, configured using [`OpenTelemetry::with_metric_view`].
+///
+/// <div class="warning">
+///
+/// This type requires the `opentelemetry` feature to be enabled.
+///
+/// </div>
+#[derive(Debug, Clone)]
+pub struct OpenTelemetryMetricView {
+    instrument: Cow<'static, str>,
+    name: Option<Cow<'static, str>>,
+    buckets: Option<Vec<f64>>,
+    allowed_attributes: Option<Vec<Cow<'static, str>>>,
+}
+
+impl OpenTelemetryMetricView {
+    /// Creates a new view which applies to the instrument with the provided name.
+    ///
+    /// The name may include `*` and `?` wildcards to match multiple instruments.
+    pub fn new<N: Into<Cow<'static, str>>>(instrument: N) -> Self {
+        Self {
+            instrument: instrument.into(),
+            name: None,
+            buckets: None,
+            allowed_attributes: None,
+        }
+    }
+
+    /// Renames the instrument when it is exported.
+    pub fn with_name<N: Into<Cow<'static, str>>>(self, name: N) -> Self {
+        Self {
+            name: Some(name.into()),
+            ..self
+        }
+    }
+
+    /// Configures the bucket boundaries used when exporting a histogram instrument.
+    pub fn with_histogram_buckets<B: IntoIterator<Item = f64>>(self, boundaries: B) -> Self {
+        Self {
+            buckets: Some(boundaries.into_iter().collect()),
+            ..self
+        }
+    }
+
+    /// Restricts the attributes which are exported with the instrument's measurements to the provided keys.
+    pub fn with_allowed_attributes<K, I>(self, keys: I) -> Self
+    where
+        K: Into<Cow<'static, str>>,
+        I: IntoIterator<Item = K>,
+    {
+        Self {
+            allowed_attributes: Some(keys.into_iter().map(|key| key.into()).collect()),
+            ..self
+        }
+    }
+
+    fn build(&self) -> Option<Box<dyn opentelemetry_sdk::metrics::View>> {
+        let mut stream = opentelemetry_sdk::metrics::Stream::new();
+
+        if let Some(name) = &self.name {
+            stream = stream.name(name.clone());
+        }
+
+        if let Some(boundaries) = &self.buckets {
+            stream = stream.aggregation(
+                opentelemetry_sdk::metrics::Aggregation::ExplicitBucketHistogram {
+                    boundaries: boundaries.clone(),
+                    record_min_max: true,
+                },
+            );
+        }
+
+        if let Some(keys) = &self.allowed_attributes {
+            stream = stream.allowed_attribute_keys(
+                keys.iter().map(|key| opentelemetry::Key::new(key.clone())),
+            );
+        }
+
+        opentelemetry_sdk::metrics::new_view(
+            opentelemetry_sdk::metrics::Instrument::new().name(self.instrument.clone()),
+            stream,
+        )
+        .ok()
+    }
+}
+
+struct OpenTelemetryBattery {
+    endpoints: Vec<String>,
+    meter_provider: Option<SdkMeterProvider>,
+    meter: Option<opentelemetry::metrics::Meter>,
+    instruments: Mutex<OpenTelemetryInstruments>,
+    coalesce: Option<crate::coalesce::CoalescingFlush>,
+}
+
+#[derive(Default)]
+struct OpenTelemetryInstruments {
+    by_name: HashMap<Cow<'static, str>, OpenTelemetryInstrument>,
+    /// The names of the instruments which have been recorded with a conflicting [`MetricKind`], so that the
+    /// conflict is only reported once.
+    conflicts: HashSet<Cow<'static, str>>,
+}
+
+enum OpenTelemetryInstrument {
+    Counter(opentelemetry::metrics::Counter<f64>),
+    Gauge(opentelemetry::metrics::Gauge<f64>),
+    Histogram(opentelemetry::metrics::Histogram<f64>),
+}
+
+impl OpenTelemetryInstrument {
+    fn kind(&self) -> MetricKind {
+        match self {
+            Self::Counter(_) => MetricKind::Counter,
+            Self::Gauge(_) => MetricKind::Gauge,
+            Self::Histogram(_) => MetricKind::Histogram,
+        }
+    }
+}
+
+impl OpenTelemetryBattery {
+    fn new(metadata: &crate::Metadata, meter_provider: Option<SdkMeterProvider>) -> Self {
+        let meter = meter_provider.as_ref().map(|provider| {
+            provider.meter_with_scope(
+                opentelemetry::InstrumentationScope::builder(metadata.service.clone())
+                    .with_version(metadata.version.clone())
+                    .build(),
+            )
+        });
+
+        Self {
+            endpoints: Vec::new(),
+            meter_provider,
+            meter,
+            instruments: Mutex::new(OpenTelemetryInstruments::default()),
+            coalesce: None,
+        }
+    }
+}
 
 impl Battery for OpenTelemetryBattery {
     fn shutdown(&self) {
+        // Report the events suppressed during the final interval while the tracer provider can still export them.
+        if let Some(coalesce) = &self.coalesce {
+            coalesce.flush();
+        }
+
         opentelemetry::global::shutdown_tracer_provider();
+
+        if let Some(provider) = &self.meter_provider {
+            provider.shutdown().ok();
+        }
+    }
+
+    fn validate(&self) -> Vec<crate::ValidationCheck> {
+        self.endpoints
+            .iter()
+            .map(|endpoint| {
+                let description = format!("the collector at {endpoint} is reachable");
+                match crate::preflight::check_reachable(
+                    endpoint,
+                    crate::preflight::VALIDATION_TIMEOUT,
+                ) {
+                    Ok(()) => crate::ValidationCheck::passed("OpenTelemetry", description),
+                    Err(err) => crate::ValidationCheck::failed("OpenTelemetry", description, err),
+                }
+            })
+            .collect()
+    }
+
+    fn record_metric(&self, metric: &Metric) {
+        let Some(meter) = &self.meter else {
+            return;
+        };
+
+        let attributes: Vec<opentelemetry::KeyValue> = metric
+            .attributes
+            .iter()
+            .map(|(key, value)| opentelemetry::KeyValue::new(*key, value.to_string()))
+            .collect();
+
+        let conflict = {
+            let Ok(mut instruments) = self.instruments.lock() else {
+                return;
+            };
+
+            if !instruments.by_name.contains_key(metric.name) {
+                let name: Cow<'static, str> = Cow::Owned(metric.name.to_string());
+                let instrument = match metric.kind {
+                    MetricKind::Counter => {
+                        OpenTelemetryInstrument::Counter(meter.f64_counter(name.clone()).build())
+                    }
+                    MetricKind::Gauge => {
+                        OpenTelemetryInstrument::Gauge(meter.f64_gauge(name.clone()).build())
+                    }
+                    MetricKind::Histogram => OpenTelemetryInstrument::Histogram(
+                        meter.f64_histogram(name.clone()).build(),
+                    ),
+                };
+                instruments.by_name.insert(name, instrument);
+            }
+
+            let OpenTelemetryInstruments { by_name, conflicts } = &mut *instruments;
+            match &by_name[metric.name] {
+                instrument if instrument.kind() != metric.kind => {
+                    // Mixing kinds would produce meaningless values (such as gauge readings summed into a
+                    // counter), so conflicting measurements are dropped rather than recorded.
+                    (!conflicts.contains(metric.name)).then(|| {
+                        conflicts.insert(Cow::Owned(metric.name.to_string()));
+                        instrument.kind()
+                    })
+                }
+                OpenTelemetryInstrument::Counter(counter) => {
+                    counter.add(metric.value, &attributes);
+                    None
+                }
+                OpenTelemetryInstrument::Gauge(gauge) => {
+                    gauge.record(metric.value, &attributes);
+                    None
+                }
+                OpenTelemetryInstrument::Histogram(histogram) => {
+                    histogram.record(metric.value, &attributes);
+                    None
+                }
+            }
+        };
+
+        if let Some(existing) = conflict {
+            tracing::warn!(
+                metric = metric.name,
+                kind = ?metric.kind,
+                existing = ?existing,
+                "Dropped measurements for a metric which was previously recorded as a different kind of instrument."
+            );
+        }
+    }
+
+    fn accepts_weighted_histograms(&self) -> bool {
+        // The SDK has no weighted observations, so histograms are aggregated by its own reader instead.
+        false
     }
 
     fn record_error(&self, error: &dyn std::error::Error) {
         opentelemetry::trace::get_active_span(|span| span.record_error(error))
     }
+
+    fn record_error_with(&self, error: &dyn std::error::Error, context: &ErrorContext) {
+        let mut attributes = vec![opentelemetry::KeyValue::new(
+            "exception.message",
+            error.to_string(),
+        )];
+
+        for (key, value) in context.fields.iter() {
+            attributes.push(opentelemetry::KeyValue::new(*key, value.clone()));
+        }
+
+        if let Some(backtrace) = &context.backtrace {
+            attributes.push(opentelemetry::KeyValue::new(
+                "exception.stacktrace",
+                backtrace.to_string(),
+            ));
+        }
+
+        opentelemetry::trace::get_active_span(|span| span.add_event("exception", attributes))
+    }
+}
+
+impl Session {
+    /// Gets the OpenTelemetry tracer for this service, allowing you to manually create spans.
+    ///
+    /// <div class="warning">
+    ///
+    /// This method requires the `opentelemetry` feature to be enabled.
+    ///
+    /// </div>
+    ///
+    /// Most applications should prefer using the [`tracing`] ecosystem (for example, the `#[instrument]` attribute)
+    /// to create spans, however this method may be used when you need direct control over the OpenTelemetry spans
+    /// which are emitted. The tracer is named after the service provided to [`Session::new`].
+    ///
+    /// ## Example
+    /// ```no_run
+    /// use tracing_batteries::{Session, OpenTelemetry, prelude::*};
+    /// use opentelemetry::trace::Tracer;
+    ///
+    /// let session = Session::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+    ///   .with_battery(OpenTelemetry::new("localhost:4317"));
+    ///
+    /// session.tracer().in_span("manual-span", |_cx| {
+    ///   // Your code here
+    /// });
+    ///
+    /// session.shutdown();
+    /// ```
+    pub fn tracer(&self) -> OpenTelemetryTracer {
+        opentelemetry::global::tracer(self.metadata.service.clone())
+    }
+
+    /// Starts a new OpenTelemetry span with the provided name and kind.
+    ///
+    /// <div class="warning">
+    ///
+    /// This method requires the `opentelemetry` feature to be enabled.
+    ///
+    /// </div>
+    ///
+    /// The span will be created as a child of the current [`tracing::Span`] and will be ended when it is dropped,
+    /// or when [`opentelemetry::trace::Span::end`] is called on it.
+    ///
+    /// ## Example
+    /// ```no_run
+    /// use tracing_batteries::{Session, OpenTelemetry, OpenTelemetrySpanKind, prelude::*};
+    /// use opentelemetry::trace::Span;
+    ///
+    /// let session = Session::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+    ///   .with_battery(OpenTelemetry::new("localhost:4317"));
+    ///
+    /// let mut span = session.span("GET /api/v1/status", OpenTelemetrySpanKind::Client);
+    /// span.set_attribute(opentelemetry::KeyValue::new("http.request.method", "GET"));
+    /// span.end();
+    ///
+    /// session.shutdown();
+    /// ```
+    pub fn span<N: Into<Cow<'static, str>>>(
+        &self,
+        name: N,
+        kind: OpenTelemetrySpanKind,
+    ) -> OpenTelemetrySpan {
+        let tracer = self.tracer();
+        tracer
+            .span_builder(name)
+            .with_kind(kind)
+            .start_with_context(&tracer, &tracing::Span::current().context())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+
+    /// Captures the output written to stdout by the subscriber.
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Buffer {
+        fn contents(&self) -> String {
+            String::from_utf8_lossy(&self.0.lock().unwrap()).into_owned()
+        }
+    }
+
+    impl Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn disabled_sdk_logs_to_stdout() {
+        let metadata = Session::new("otel-test", "1.0.0");
+        let stdout = Buffer::default();
+        let mut otel =
+            OpenTelemetry::new("localhost:4317").with_stdout_writer(Mutex::new(stdout.clone()));
+
+        let (battery, layers, provider) = otel.build_pipeline(&metadata, true);
+        assert!(provider.is_none(), "no tracer provider should be built");
+        assert!(
+            battery.meter_provider.is_none(),
+            "no meter provider should be built"
+        );
+        assert!(battery.meter.is_none());
+        assert!(battery.endpoints.is_empty());
+
+        let use_stdout = otel.use_stdout(provider.is_some(), false);
+        assert!(use_stdout, "stdout should be used when the SDK is disabled");
+
+        let subscriber = otel.build_subscriber(layers, use_stdout, Arc::new(AtomicBool::new(true)));
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!("Hello from a disabled SDK");
+        });
+
+        assert!(stdout.contents().contains("Hello from a disabled SDK"));
+    }
+
+    /// Records the levels of the events delivered to the collector layers.
+    #[derive(Clone, Default)]
+    struct Collector(Arc<Mutex<Vec<tracing::Level>>>);
+
+    impl Layer<Registry> for Collector {
+        fn on_event(
+            &self,
+            event: &tracing::Event<'_>,
+            _ctx: tracing_subscriber::layer::Context<'_, Registry>,
+        ) {
+            self.0.lock().unwrap().push(*event.metadata().level());
+        }
+    }
+
+    fn emit_events(otel: &mut OpenTelemetry, collector: &Collector) {
+        let subscriber = otel.build_subscriber(
+            vec![Box::new(collector.clone())],
+            true,
+            Arc::new(AtomicBool::new(true)),
+        );
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::debug!("debug event");
+            tracing::info!("info event");
+            tracing::warn!("warn event");
+        });
+    }
+
+    #[test]
+    fn stdout_and_collector_levels_are_independent() {
+        let stdout = Buffer::default();
+        let collector = Collector::default();
+        let mut otel = OpenTelemetry::new("localhost:4317")
+            .with_default_level(OpenTelemetryLevel::DEBUG)
+            .with_stdout_level(OpenTelemetryLevel::WARN)
+            .with_stdout_writer(Mutex::new(stdout.clone()));
+        emit_events(&mut otel, &collector);
+
+        assert_eq!(
+            *collector.0.lock().unwrap(),
+            vec![
+                tracing::Level::DEBUG,
+                tracing::Level::INFO,
+                tracing::Level::WARN
+            ],
+            "the collector should receive events at its own level"
+        );
+        let output = stdout.contents();
+        assert!(!output.contains("debug event"), "{output}");
+        assert!(!output.contains("info event"), "{output}");
+        assert!(output.contains("warn event"), "{output}");
+
+        // A more verbose stdout level should not leak extra events to the collector.
+        let stdout = Buffer::default();
+        let collector = Collector::default();
+        let mut otel = OpenTelemetry::new("localhost:4317")
+            .with_default_level(OpenTelemetryLevel::WARN)
+            .with_stdout_level(OpenTelemetryLevel::DEBUG)
+            .with_stdout_writer(Mutex::new(stdout.clone()));
+        emit_events(&mut otel, &collector);
+
+        assert_eq!(*collector.0.lock().unwrap(), vec![tracing::Level::WARN]);
+        let output = stdout.contents();
+        assert!(output.contains("debug event"), "{output}");
+        assert!(output.contains("info event"), "{output}");
+        assert!(output.contains("warn event"), "{output}");
+    }
+
+    fn record(battery: &OpenTelemetryBattery, name: &str, kind: MetricKind) {
+        battery.record_metric(&Metric {
+            name,
+            kind,
+            value: 1.0,
+            attributes: &[("queue", "default")],
+            count: 1,
+        });
+    }
+
+    #[test]
+    fn reports_conflicting_metric_kinds() {
+        let metadata = Session::new("otel-test", "1.0.0");
+        let battery = OpenTelemetryBattery::new(&metadata, Some(SdkMeterProvider::default()));
+
+        record(&battery, "jobs.processed", MetricKind::Counter);
+        record(&battery, "jobs.processed", MetricKind::Gauge);
+        record(&battery, "jobs.processed", MetricKind::Gauge);
+        record(&battery, "jobs.duration", MetricKind::Histogram);
+
+        let instruments = battery.instruments.lock().unwrap();
+        assert_eq!(
+            instruments.by_name["jobs.processed"].kind(),
+            MetricKind::Counter
+        );
+        assert_eq!(
+            instruments.by_name["jobs.duration"].kind(),
+            MetricKind::Histogram
+        );
+        assert_eq!(instruments.conflicts.len(), 1);
+        assert!(instruments.conflicts.contains("jobs.processed"));
+    }
 }
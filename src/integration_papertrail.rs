@@ -0,0 +1,212 @@
+use std::{
+    borrow::Cow,
+    io::Write,
+    sync::{atomic::AtomicBool, Arc, Mutex, PoisonError},
+    thread::JoinHandle,
+    time::Duration,
+};
+
+use crate::{
+    queue::{Pop, PriorityQueue, DEFAULT_QUEUE_CAPACITY, SHUTDOWN_TIMEOUT},
+    tls, Battery, BatteryBuilder, DropPolicy, Envelope, EnvelopePayload, Metadata,
+};
+
+/// A [Papertrail](https://www.papertrail.com) integration which ships errors, custom events, and page views
+/// to a remote syslog destination over TCP+TLS.
+///
+/// <div class="warning">
+///
+/// This integration requires the `papertrail` feature to be enabled.
+///
+/// </div>
+///
+/// Each [`Envelope`] is written as an [RFC 5424](https://datatracker.ietf.org/doc/html/rfc5424) syslog message
+/// whose body is the envelope's JSON representation. The syslog hostname (Papertrail's "system name") defaults
+/// to the service name from your [`Metadata`], and the program name is set to the service name as well. Any syslog
+/// server which accepts newline-delimited messages over TLS may be used as the destination.
+///
+/// ## Example
+/// ```no_run
+/// use tracing_batteries::{Session, Papertrail};
+///
+/// let session = Session::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+///   .with_battery(Papertrail::new("logs1.papertrailapp.com", 12345));
+///
+/// session.shutdown();
+/// ```
+pub struct Papertrail {
+    host: Cow<'static, str>,
+    port: u16,
+    system_name: Option<Cow<'static, str>>,
+    queue_capacity: usize,
+    drop_policy: DropPolicy,
+}
+
+impl Papertrail {
+    /// Configures the Papertrail integration to send logs to the provided log destination.
+    pub fn new<H: Into<Cow<'static, str>>>(host: H, port: u16) -> Self {
+        Self {
+            host: host.into(),
+            port,
+            system_name: None,
+            queue_capacity: DEFAULT_QUEUE_CAPACITY,
+            drop_policy: DropPolicy::default(),
+        }
+    }
+
+    /// Overrides the system name (syslog hostname) which messages are reported under.
+    pub fn with_system_name<N: Into<Cow<'static, str>>>(self, name: N) -> Self {
+        Self {
+            system_name: Some(name.into()),
+            ..self
+        }
+    }
+
+    /// Configures the maximum number of messages which may be waiting to be sent (10,000 by default), and which are
+    /// dropped when the log destination falls behind a burst of activity.
+    pub fn with_queue_limits(self, capacity: usize, policy: DropPolicy) -> Self {
+        Self {
+            queue_capacity: capacity,
+            drop_policy: policy,
+            ..self
+        }
+    }
+}
+
+impl BatteryBuilder for Papertrail {
+    fn setup(self, metadata: &Metadata, _enabled: Arc<AtomicBool>) -> Box<dyn Battery> {
+        let queue = Arc::new(PriorityQueue::<String>::new("papertrail"));
+        queue.set_limits(self.queue_capacity, self.drop_policy);
+
+        let messages = queue.clone();
+        let host = self.host.to_string();
+        let port = self.port;
+        let thread = std::thread::Builder::new()
+            .name("tracing-batteries-papertrail".into())
+            .spawn(move || {
+                let mut connection = None;
+                while let Pop::Item(message) = messages.pop(None) {
+                    // Retry once on a fresh connection if the existing one has been closed.
+                    for _ in 0..2 {
+                        if connection.is_none() {
+                            connection = tls::connect(&host, port, Duration::from_secs(10)).ok();
+                        }
+
+                        let written = connection.as_mut().map(|stream| {
+                            stream
+                                .write_all(message.as_bytes())
+                                .and_then(|_| stream.flush())
+                                .is_ok()
+                        });
+
+                        match written {
+                            Some(false) => connection = None,
+                            _ => break,
+                        }
+                    }
+                }
+
+                if let Some(mut stream) = connection {
+                    stream.flush().ok();
+                    stream.conn.send_close_notify();
+                    stream.flush().ok();
+                }
+            })
+            .ok();
+
+        Box::new(PapertrailBattery {
+            system_name: self
+                .system_name
+                .map(|name| name.to_string())
+                .unwrap_or_else(|| metadata.service.to_string()),
+            app_name: metadata.service.to_string(),
+            queue,
+            thread: Mutex::new(thread),
+        })
+    }
+}
+
+struct PapertrailBattery {
+    system_name: String,
+    app_name: String,
+    queue: Arc<PriorityQueue<String>>,
+    thread: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl Battery for PapertrailBattery {
+    fn record_envelope(&self, envelope: &Envelope) {
+        let message = format_syslog(envelope, &self.system_name, &self.app_name);
+
+        self.queue.push(envelope.importance, message);
+    }
+
+    fn shutdown(&self) {
+        self.queue.close(SHUTDOWN_TIMEOUT);
+
+        if let Some(thread) = self
+            .thread
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take()
+        {
+            crate::worker::join(thread);
+        }
+    }
+}
+
+/// Formats an [`Envelope`] as a newline-terminated RFC 5424 syslog message using the `user` facility.
+fn format_syslog(envelope: &Envelope, system_name: &str, app_name: &str) -> String {
+    let severity = match envelope.payload {
+        EnvelopePayload::Error { .. } => 3,
+        _ => 6,
+    };
+
+    format!(
+        "<{}>1 {} {} {} {} - - {}\n",
+        8 + severity,
+        envelope
+            .timestamp
+            .to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+        syslog_field(system_name),
+        syslog_field(app_name),
+        std::process::id(),
+        envelope.to_json()
+    )
+}
+
+/// Syslog header fields must be non-empty printable ASCII without spaces.
+fn syslog_field(value: &str) -> String {
+    let value: String = value
+        .chars()
+        .filter(|c| c.is_ascii_graphic())
+        .take(48)
+        .collect();
+
+    if value.is_empty() {
+        "-".to_string()
+    } else {
+        value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Session;
+
+    #[test]
+    fn syslog_message() {
+        let metadata = Session::new("example", "0.0.1");
+        let envelope = Envelope::new(
+            &metadata,
+            EnvelopePayload::PageView {
+                page: "/settings".into(),
+            },
+        );
+
+        let message = format_syslog(&envelope, "my host", "example");
+        assert!(message.starts_with("<14>1 "));
+        assert!(message.contains(" myhost example "));
+        assert!(message.ends_with(&format!("{}\n", envelope.to_json())));
+    }
+}
@@ -0,0 +1,316 @@
+use std::{
+    borrow::Cow,
+    collections::BTreeMap,
+    sync::{atomic::AtomicBool, Arc, Mutex, PoisonError},
+    time::{Duration, Instant},
+};
+
+use serde_json::json;
+
+use crate::{
+    dispatcher::HttpDispatcher, queue::DEFAULT_QUEUE_CAPACITY, Battery, BatteryBuilder, DropPolicy,
+    Envelope, EnvelopePayload, Metadata,
+};
+
+/// A [Pirsch](https://pirsch.io) integration which reports page views and custom events to a Pirsch
+/// dashboard using its API.
+///
+/// <div class="warning">
+///
+/// This integration requires the `pirsch` feature to be enabled.
+///
+/// </div>
+///
+/// The integration authenticates using the client ID and secret of an API client for your dashboard (or an
+/// access key, by providing an empty client ID). Page views recorded using [`Session::record_new_page`](crate::Session::record_new_page)
+/// are reported as hits on the configured hostname, while custom events recorded using
+/// [`Session::record_event`](crate::Session::record_event) are reported as Pirsch events whose metadata includes
+/// the event's properties alongside the `version` of your application and any context from your [`Metadata`].
+///
+/// ## Example
+/// ```no_run
+/// use tracing_batteries::{Session, Pirsch};
+///
+/// let session = Session::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+///   .with_battery(Pirsch::new("app.example.com", "your-client-id", "your-client-secret"));
+///
+/// session.record_new_page("/settings");
+/// session.shutdown();
+/// ```
+pub struct Pirsch {
+    hostname: Cow<'static, str>,
+    client_id: Cow<'static, str>,
+    client_secret: Cow<'static, str>,
+    api_url: Cow<'static, str>,
+    queue_capacity: usize,
+    drop_policy: DropPolicy,
+}
+
+impl Pirsch {
+    /// Configures the Pirsch integration for the dashboard's hostname using the provided API client credentials.
+    pub fn new<H, I, S>(hostname: H, client_id: I, client_secret: S) -> Self
+    where
+        H: Into<Cow<'static, str>>,
+        I: Into<Cow<'static, str>>,
+        S: Into<Cow<'static, str>>,
+    {
+        Self {
+            hostname: hostname.into(),
+            client_id: client_id.into(),
+            client_secret: client_secret.into(),
+            api_url: "https://api.pirsch.io".into(),
+            queue_capacity: DEFAULT_QUEUE_CAPACITY,
+            drop_policy: DropPolicy::default(),
+        }
+    }
+
+    /// Configures the maximum number of requests which may be waiting to be sent (10,000 by default), and which
+    /// are dropped when a burst of page views fills the queue.
+    pub fn with_queue_limits(self, capacity: usize, policy: DropPolicy) -> Self {
+        Self {
+            queue_capacity: capacity,
+            drop_policy: policy,
+            ..self
+        }
+    }
+}
+
+impl BatteryBuilder for Pirsch {
+    fn setup(self, metadata: &Metadata, _enabled: Arc<AtomicBool>) -> Box<dyn Battery> {
+        let mut tags: BTreeMap<String, String> = metadata
+            .context
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect();
+        tags.insert("version".into(), metadata.version.to_string());
+
+        Box::new(PirschBattery {
+            base_url: format!("https://{}", self.hostname.trim_end_matches('/')),
+            user_agent: format!(
+                "{}/{} ({}; {})",
+                metadata.service,
+                metadata.version,
+                std::env::consts::OS,
+                std::env::consts::ARCH
+            ),
+            tags,
+            token: Arc::new(PirschToken {
+                api_url: self.api_url.to_string(),
+                client_id: self.client_id.to_string(),
+                client_secret: self.client_secret.to_string(),
+                cached: Mutex::new(None),
+            }),
+            dispatcher: HttpDispatcher::new("pirsch")
+                .with_limits(self.queue_capacity, self.drop_policy),
+        })
+    }
+}
+
+struct PirschBattery {
+    base_url: String,
+    user_agent: String,
+    tags: BTreeMap<String, String>,
+    token: Arc<PirschToken>,
+    dispatcher: HttpDispatcher,
+}
+
+impl Battery for PirschBattery {
+    fn record_envelope(&self, envelope: &Envelope) {
+        let Some((path, body)) = self.build_request(envelope) else {
+            return;
+        };
+
+        let url = format!("{}/api/v1/{path}", self.token.api_url);
+        let token = self.token.clone();
+        self.dispatcher
+            .try_dispatch(envelope.importance, move |client| {
+                // Without a token the request would be rejected, so it is dropped and the failure reported instead.
+                let token = token
+                    .get(client)
+                    .map_err(|err| {
+                        crate::diagnostics::record_export_error(
+                            "pirsch",
+                            format!("Failed to obtain an access token: {err}"),
+                        )
+                    })
+                    .ok()?;
+
+                Some(
+                    client
+                        .post(url)
+                        .header("Authorization", format!("Bearer {token}"))
+                        .header("Content-Type", "application/json")
+                        .body(body.to_string()),
+                )
+            });
+    }
+
+    fn shutdown(&self) {
+        self.dispatcher.shutdown();
+    }
+}
+
+impl PirschBattery {
+    fn build_request(&self, envelope: &Envelope) -> Option<(&'static str, serde_json::Value)> {
+        match &envelope.payload {
+            EnvelopePayload::PageView { page } => Some((
+                "hit",
+                json!({
+                    "url": format!("{}{page}", self.base_url),
+                    "ip": "127.0.0.1",
+                    "user_agent": self.user_agent,
+                    "tags": self.tags,
+                }),
+            )),
+            EnvelopePayload::Event { name, properties } => {
+                let mut meta = self.tags.clone();
+                meta.extend(properties.iter().map(|(key, value)| {
+                    let value = match value {
+                        serde_json::Value::String(value) => value.clone(),
+                        value => value.to_string(),
+                    };
+                    (key.clone(), value)
+                }));
+
+                Some((
+                    "event",
+                    json!({
+                        "url": format!("{}/", self.base_url),
+                        "ip": "127.0.0.1",
+                        "user_agent": self.user_agent,
+                        "event_name": name,
+                        "event_meta": meta,
+                    }),
+                ))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Exchanges the client credentials for an access token, caching it until shortly before it expires.
+struct PirschToken {
+    api_url: String,
+    client_id: String,
+    client_secret: String,
+    cached: Mutex<Option<(String, Instant)>>,
+}
+
+impl PirschToken {
+    fn get(&self, client: &reqwest::blocking::Client) -> Result<String, String> {
+        // Access keys are used directly, without exchanging them for a token.
+        if self.client_id.is_empty() {
+            return Ok(self.client_secret.clone());
+        }
+
+        let mut cached = self.cached.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(token) = Self::cached(&cached, Instant::now()) {
+            return Ok(token);
+        }
+
+        let response = client
+            .post(format!("{}/api/v1/token", self.api_url))
+            .header("Content-Type", "application/json")
+            .body(
+                json!({
+                    "client_id": self.client_id,
+                    "client_secret": self.client_secret,
+                })
+                .to_string(),
+            )
+            .send()
+            .and_then(|response| response.error_for_status())
+            .and_then(|response| response.text())
+            .map_err(|err| err.without_url().to_string())?;
+
+        let (token, lifetime) = parse_token(&response, chrono::Utc::now())
+            .ok_or_else(|| "the token response could not be parsed".to_string())?;
+
+        *cached = Some((token.clone(), Instant::now() + lifetime));
+        Ok(token)
+    }
+
+    /// Returns the cached token, if it hasn't expired yet.
+    fn cached(cached: &Option<(String, Instant)>, now: Instant) -> Option<String> {
+        cached
+            .as_ref()
+            .filter(|(_, expires)| *expires > now)
+            .map(|(token, _)| token.clone())
+    }
+}
+
+/// Parses the response of the token endpoint, returning the access token along with how long it may be cached for.
+///
+/// Tokens are refreshed a minute before the expiry reported by Pirsch to avoid using an expired token, while tokens
+/// without an expiry are assumed to be valid for 10 minutes.
+fn parse_token(body: &str, now: chrono::DateTime<chrono::Utc>) -> Option<(String, Duration)> {
+    #[derive(serde::Deserialize)]
+    struct TokenResponse {
+        access_token: String,
+        expires_at: Option<chrono::DateTime<chrono::Utc>>,
+    }
+
+    let response = serde_json::from_str::<TokenResponse>(body).ok()?;
+    if response.access_token.is_empty() {
+        return None;
+    }
+
+    let lifetime = match response.expires_at {
+        Some(expires_at) => (expires_at - now)
+            .to_std()
+            .unwrap_or_default()
+            .saturating_sub(Duration::from_secs(60)),
+        None => Duration::from_secs(10 * 60),
+    };
+
+    Some((response.access_token, lifetime))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn caches_tokens_until_expiry() {
+        let now = chrono::Utc::now();
+        let expires_at = (now + chrono::Duration::minutes(15)).to_rfc3339();
+        let (token, lifetime) = parse_token(
+            &format!(r#"{{"access_token":"abc123","expires_at":"{expires_at}"}}"#),
+            now,
+        )
+        .expect("the token should be parsed");
+
+        assert_eq!(token, "abc123");
+        assert_eq!(lifetime, Duration::from_secs(14 * 60));
+
+        let issued = Instant::now();
+        let cached = Some((token, issued + lifetime));
+        assert_eq!(
+            PirschToken::cached(&cached, issued + Duration::from_secs(60)).as_deref(),
+            Some("abc123")
+        );
+        assert_eq!(PirschToken::cached(&cached, issued + lifetime), None);
+        assert_eq!(PirschToken::cached(&None, issued), None);
+
+        assert!(parse_token(r#"{"access_token":""}"#, now).is_none());
+        assert_eq!(
+            parse_token(r#"{"access_token":"abc123"}"#, now).map(|(_, lifetime)| lifetime),
+            Some(Duration::from_secs(10 * 60))
+        );
+    }
+
+    #[test]
+    fn uses_access_keys_directly() {
+        let token = PirschToken {
+            api_url: "https://api.pirsch.io".into(),
+            client_id: String::new(),
+            client_secret: "pa_access_key".into(),
+            cached: Mutex::new(None),
+        };
+
+        assert_eq!(
+            token.get(&reqwest::blocking::Client::new()),
+            Ok("pa_access_key".to_string())
+        );
+    }
+}
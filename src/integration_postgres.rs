@@ -0,0 +1,573 @@
+use std::{
+    borrow::Cow,
+    sync::{atomic::AtomicBool, Arc, Mutex, PoisonError},
+    thread::JoinHandle,
+    time::{Duration, Instant},
+};
+
+use postgres::{types::ToSql, Client, Config, NoTls};
+use serde_json::json;
+
+use crate::{
+    queue::DEFAULT_QUEUE_CAPACITY,
+    queue::{Pop, PriorityQueue, SHUTDOWN_TIMEOUT},
+    reconnect::{Backoff, CONNECT_TIMEOUT},
+    spans::{SpanSummary, SpanSummaryLayer},
+    Battery, BatteryBuilder, DropPolicy, Envelope, EnvelopePayload, Importance, Metadata,
+};
+
+const ERROR_COLUMNS: &[&str] = &[
+    "timestamp",
+    "service",
+    "version",
+    "message",
+    "causes",
+    "fields",
+    "context",
+    "backtrace",
+];
+
+const EVENT_COLUMNS: &[&str] = &[
+    "timestamp",
+    "service",
+    "version",
+    "kind",
+    "name",
+    "properties",
+    "context",
+];
+
+const SPAN_COLUMNS: &[&str] = &[
+    "timestamp",
+    "service",
+    "version",
+    "name",
+    "target",
+    "level",
+    "parent",
+    "duration_ms",
+    "fields",
+];
+
+/// A [PostgreSQL](https://www.postgresql.org) integration which batch-inserts errors, custom events, page views,
+/// and (optionally) span summaries into tables in your own database, so that they can be queried with plain SQL.
+///
+/// <div class="warning">
+///
+/// This integration requires the `postgres` feature to be enabled.
+///
+/// </div>
+///
+/// Errors are written to the `telemetry_errors` table and events and page views to `telemetry_events` by default,
+/// while spans are only written once a table has been configured for them using [`Postgres::with_spans_table`].
+/// Rows are inserted in batches of up to 100 (or every 5 seconds, whichever comes first) in a single transaction.
+/// If the database becomes unreachable, the integration reconnects with an exponential backoff (up to one minute
+/// between attempts) while rows wait in a bounded queue, from which the least important rows are shed.
+///
+/// The tables must exist before telemetry can be written to them. You can create them yourself using the SQL
+/// returned by [`Postgres::migrations`], or have the integration create them on connect using
+/// [`Postgres::with_migrations`].
+///
+/// ## Example
+/// ```no_run
+/// use tracing_batteries::{Session, Postgres};
+///
+/// let session = Session::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+///   .with_battery(Postgres::new("postgres://telemetry@localhost/my_tool")
+///     .with_spans_table("telemetry_spans")
+///     .with_migrations());
+///
+/// session.shutdown();
+/// ```
+pub struct Postgres {
+    connection: Cow<'static, str>,
+    errors_table: Cow<'static, str>,
+    events_table: Cow<'static, str>,
+    spans_table: Option<Cow<'static, str>>,
+    migrate: bool,
+    batch_size: usize,
+    flush_interval: Duration,
+    queue_capacity: usize,
+    drop_policy: DropPolicy,
+}
+
+impl Postgres {
+    /// Configures the PostgreSQL integration to write to the database identified by the provided connection
+    /// string (either a `postgres://` URL or a `key=value` connection string).
+    pub fn new<C: Into<Cow<'static, str>>>(connection: C) -> Self {
+        Self {
+            connection: connection.into(),
+            errors_table: "telemetry_errors".into(),
+            events_table: "telemetry_events".into(),
+            spans_table: None,
+            migrate: false,
+            batch_size: 100,
+            flush_interval: Duration::from_secs(5),
+            queue_capacity: DEFAULT_QUEUE_CAPACITY,
+            drop_policy: DropPolicy::default(),
+        }
+    }
+
+    /// Overrides the table which errors are written to, optionally qualified with a schema (e.g. `telemetry.errors`).
+    pub fn with_errors_table<T: Into<Cow<'static, str>>>(self, table: T) -> Self {
+        Self {
+            errors_table: table.into(),
+            ..self
+        }
+    }
+
+    /// Overrides the table which custom events and page views are written to.
+    pub fn with_events_table<T: Into<Cow<'static, str>>>(self, table: T) -> Self {
+        Self {
+            events_table: table.into(),
+            ..self
+        }
+    }
+
+    /// Writes a summary of each completed span to the provided table.
+    ///
+    /// Spans are observed through the tracing subscriber installed by the [`OpenTelemetry`](crate::OpenTelemetry)
+    /// integration, so it must also be attached to the session for spans to be written.
+    pub fn with_spans_table<T: Into<Cow<'static, str>>>(self, table: T) -> Self {
+        Self {
+            spans_table: Some(table.into()),
+            ..self
+        }
+    }
+
+    /// Creates any missing tables (using the SQL returned by [`Postgres::migrations`]) when connecting.
+    pub fn with_migrations(self) -> Self {
+        Self {
+            migrate: true,
+            ..self
+        }
+    }
+
+    /// Configures the maximum number of rows inserted in a single batch.
+    pub fn with_batch_size(self, batch_size: usize) -> Self {
+        Self {
+            batch_size: batch_size.max(1),
+            ..self
+        }
+    }
+
+    /// Configures how often partial batches are written to the database.
+    pub fn with_flush_interval(self, flush_interval: Duration) -> Self {
+        Self {
+            flush_interval,
+            ..self
+        }
+    }
+
+    /// Returns the SQL which creates the tables used by this integration, if they do not already exist.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use tracing_batteries::Postgres;
+    ///
+    /// let sql = Postgres::new("postgres://localhost/my_tool").migrations();
+    /// assert!(sql.contains("CREATE TABLE IF NOT EXISTS \"telemetry_errors\""));
+    /// ```
+    pub fn migrations(&self) -> String {
+        let mut sql = format!(
+            "CREATE TABLE IF NOT EXISTS {} (
+    id BIGSERIAL PRIMARY KEY,
+    timestamp TIMESTAMPTZ NOT NULL,
+    service TEXT NOT NULL,
+    version TEXT NOT NULL,
+    message TEXT NOT NULL,
+    causes JSONB NOT NULL,
+    fields JSONB NOT NULL,
+    context JSONB NOT NULL,
+    backtrace TEXT
+);
+
+CREATE TABLE IF NOT EXISTS {} (
+    id BIGSERIAL PRIMARY KEY,
+    timestamp TIMESTAMPTZ NOT NULL,
+    service TEXT NOT NULL,
+    version TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    name TEXT NOT NULL,
+    properties JSONB NOT NULL,
+    context JSONB NOT NULL
+);
+",
+            quote_table(&self.errors_table),
+            quote_table(&self.events_table)
+        );
+
+        if let Some(spans_table) = &self.spans_table {
+            sql.push_str(&format!(
+                "
+CREATE TABLE IF NOT EXISTS {} (
+    id BIGSERIAL PRIMARY KEY,
+    timestamp TIMESTAMPTZ NOT NULL,
+    service TEXT NOT NULL,
+    version TEXT NOT NULL,
+    name TEXT NOT NULL,
+    target TEXT NOT NULL,
+    level TEXT NOT NULL,
+    parent TEXT,
+    duration_ms DOUBLE PRECISION NOT NULL,
+    fields JSONB NOT NULL
+);
+",
+                quote_table(spans_table)
+            ));
+        }
+
+        sql
+    }
+
+    /// Configures the maximum number of rows which may be waiting to be sent (10,000 by default), and which are dropped
+    /// when the database is unreachable for long enough to fill the queue.
+    pub fn with_queue_limits(self, capacity: usize, policy: DropPolicy) -> Self {
+        Self {
+            queue_capacity: capacity,
+            drop_policy: policy,
+            ..self
+        }
+    }
+}
+
+impl BatteryBuilder for Postgres {
+    fn setup(self, metadata: &Metadata, enabled: Arc<AtomicBool>) -> Box<dyn Battery> {
+        let queue = Arc::new(PriorityQueue::<Row>::new("postgres"));
+        queue.set_limits(self.queue_capacity, self.drop_policy);
+
+        let rows = queue.clone();
+        let connection = self.connection.to_string();
+        let migrations = self.migrate.then(|| self.migrations());
+        let tables = Tables {
+            errors: quote_table(&self.errors_table),
+            events: quote_table(&self.events_table),
+            spans: self
+                .spans_table
+                .as_deref()
+                .map(quote_table)
+                .unwrap_or_default(),
+        };
+        let batch_size = self.batch_size;
+        let interval = self.flush_interval;
+        let thread = std::thread::Builder::new()
+            .name("tracing-batteries-postgres".into())
+            .spawn(move || {
+                let mut client: Option<Client> = None;
+                let mut backoff = Backoff::default();
+                let mut batch = Vec::with_capacity(batch_size);
+                let mut deadline = Instant::now() + interval;
+
+                loop {
+                    let closed = match rows.pop(Some(deadline)) {
+                        Pop::Item(row) => {
+                            batch.push(row);
+                            if batch.len() < batch_size {
+                                continue;
+                            }
+                            false
+                        }
+                        Pop::Timeout => false,
+                        Pop::Closed => true,
+                    };
+
+                    // While the database is unreachable, the batch is retried with a backoff and newer rows wait
+                    // in the (bounded) queue. Batches which the database rejects are retried once on a fresh
+                    // connection, in case the existing one has been closed.
+                    let mut attempts = 0;
+                    while !batch.is_empty() {
+                        if client.is_none() {
+                            if !rows.wait_until(backoff.retry_at()) {
+                                break;
+                            }
+
+                            match connect(&connection, migrations.as_deref()) {
+                                Ok(connected) => client = Some(connected),
+                                Err(err) => {
+                                    crate::diagnostics::record_export_error("postgres", err);
+                                    backoff.failed();
+                                    continue;
+                                }
+                            }
+                        }
+
+                        let written = client
+                            .as_mut()
+                            .is_some_and(|client| insert_batch(client, &tables, &batch).is_ok());
+
+                        attempts += 1;
+                        if written {
+                            backoff.reset();
+                        } else {
+                            client = None;
+                        }
+
+                        if written || attempts >= 2 {
+                            batch.clear();
+                        }
+                    }
+
+                    if closed {
+                        break;
+                    }
+
+                    deadline = Instant::now() + interval;
+                }
+            })
+            .ok();
+
+        if self.spans_table.is_some() {
+            let service = metadata.service.to_string();
+            let version = metadata.version.to_string();
+            let queue = queue.clone();
+            crate::layers::attach_layer(SpanSummaryLayer::new(enabled, move |span| {
+                queue.push(Importance::Low, span_row(&span, &service, &version))
+            }));
+        }
+
+        Box::new(PostgresBattery {
+            queue,
+            thread: Mutex::new(thread),
+        })
+    }
+}
+
+fn connect(connection: &str, migrations: Option<&str>) -> Result<Client, postgres::Error> {
+    let mut config: Config = connection.parse()?;
+    if config.get_connect_timeout().is_none() {
+        config.connect_timeout(CONNECT_TIMEOUT);
+    }
+
+    let mut client = config.connect(NoTls)?;
+    if let Some(migrations) = migrations {
+        client.batch_execute(migrations)?;
+    }
+
+    Ok(client)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Table {
+    Errors,
+    Events,
+    Spans,
+}
+
+impl Table {
+    fn columns(&self) -> &'static [&'static str] {
+        match self {
+            Table::Errors => ERROR_COLUMNS,
+            Table::Events => EVENT_COLUMNS,
+            Table::Spans => SPAN_COLUMNS,
+        }
+    }
+}
+
+struct Tables {
+    errors: String,
+    events: String,
+    spans: String,
+}
+
+impl Tables {
+    fn name(&self, table: Table) -> &str {
+        match table {
+            Table::Errors => &self.errors,
+            Table::Events => &self.events,
+            Table::Spans => &self.spans,
+        }
+    }
+}
+
+struct Row {
+    table: Table,
+    values: Vec<Box<dyn ToSql + Sync + Send>>,
+}
+
+/// Inserts a batch of rows using one multi-row `INSERT` per table, within a single transaction.
+fn insert_batch(client: &mut Client, tables: &Tables, rows: &[Row]) -> Result<(), postgres::Error> {
+    let mut transaction = client.transaction()?;
+
+    for table in [Table::Errors, Table::Events, Table::Spans] {
+        let rows: Vec<&Row> = rows.iter().filter(|row| row.table == table).collect();
+
+        // PostgreSQL limits each statement to 65,535 bound parameters.
+        for chunk in rows.chunks(u16::MAX as usize / table.columns().len()) {
+            let params: Vec<&(dyn ToSql + Sync)> = chunk
+                .iter()
+                .flat_map(|row| row.values.iter())
+                .map(|value| value.as_ref() as &(dyn ToSql + Sync))
+                .collect();
+
+            transaction.execute(
+                &insert_statement(tables.name(table), table.columns(), chunk.len()),
+                &params,
+            )?;
+        }
+    }
+
+    transaction.commit()
+}
+
+/// Builds a multi-row `INSERT` statement with numbered parameters for each row.
+fn insert_statement(table: &str, columns: &[&str], rows: usize) -> String {
+    let values = (0..rows)
+        .map(|row| {
+            let params = (1..=columns.len())
+                .map(|column| format!("${}", row * columns.len() + column))
+                .collect::<Vec<_>>()
+                .join(", ");
+            format!("({params})")
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    format!(
+        "INSERT INTO {table} ({}) VALUES {values}",
+        columns.join(", ")
+    )
+}
+
+/// Quotes a (possibly schema qualified) table name so that it can be safely interpolated into SQL.
+fn quote_table(table: &str) -> String {
+    table
+        .split('.')
+        .map(|part| format!("\"{}\"", part.replace('"', "\"\"")))
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+fn envelope_row(envelope: &Envelope) -> Option<Row> {
+    let timestamp = envelope.timestamp;
+    let service = envelope.service.to_string();
+    let version = envelope.version.to_string();
+    let context = json!(envelope.context);
+
+    match &envelope.payload {
+        EnvelopePayload::Error {
+            message,
+            causes,
+            fields,
+            backtrace,
+        } => Some(Row {
+            table: Table::Errors,
+            values: vec![
+                Box::new(timestamp),
+                Box::new(service),
+                Box::new(version),
+                Box::new(message.clone()),
+                Box::new(json!(causes)),
+                Box::new(json!(fields)),
+                Box::new(context),
+                Box::new(backtrace.clone()),
+            ],
+        }),
+        EnvelopePayload::Event { name, properties } => Some(Row {
+            table: Table::Events,
+            values: vec![
+                Box::new(timestamp),
+                Box::new(service),
+                Box::new(version),
+                Box::new("event"),
+                Box::new(name.clone()),
+                Box::new(json!(properties)),
+                Box::new(context),
+            ],
+        }),
+        EnvelopePayload::PageView { page } => Some(Row {
+            table: Table::Events,
+            values: vec![
+                Box::new(timestamp),
+                Box::new(service),
+                Box::new(version),
+                Box::new("page_view"),
+                Box::new(page.clone()),
+                Box::new(json!({})),
+                Box::new(context),
+            ],
+        }),
+        EnvelopePayload::Sealed { .. } | EnvelopePayload::Unknown => None,
+    }
+}
+
+fn span_row(span: &SpanSummary, service: &str, version: &str) -> Row {
+    Row {
+        table: Table::Spans,
+        values: vec![
+            Box::new(span.started_at),
+            Box::new(service.to_string()),
+            Box::new(version.to_string()),
+            Box::new(span.name),
+            Box::new(span.target.clone()),
+            Box::new(span.level.as_str()),
+            Box::new(span.parent),
+            Box::new(span.duration.as_secs_f64() * 1000.0),
+            Box::new(json!(span.fields)),
+        ],
+    }
+}
+
+struct PostgresBattery {
+    queue: Arc<PriorityQueue<Row>>,
+    thread: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl Battery for PostgresBattery {
+    fn record_envelope(&self, envelope: &Envelope) {
+        if let Some(row) = envelope_row(envelope) {
+            self.queue.push(envelope.importance, row);
+        }
+    }
+
+    fn shutdown(&self) {
+        self.queue.close(SHUTDOWN_TIMEOUT);
+
+        if let Some(thread) = self
+            .thread
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take()
+        {
+            crate::worker::join(thread);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Session;
+
+    #[test]
+    fn insert_statements() {
+        assert_eq!(
+            insert_statement("\"telemetry\".\"events\"", &["timestamp", "name"], 2),
+            "INSERT INTO \"telemetry\".\"events\" (timestamp, name) VALUES ($1, $2), ($3, $4)"
+        );
+        assert_eq!(quote_table("telemetry.events"), "\"telemetry\".\"events\"");
+        assert_eq!(quote_table("odd\"name"), "\"odd\"\"name\"");
+    }
+
+    #[test]
+    fn envelope_rows() {
+        let metadata = Session::new("example", "0.0.1");
+
+        let error = Envelope::new(
+            &metadata,
+            EnvelopePayload::Error {
+                message: "Failed to connect".into(),
+                causes: vec![],
+                fields: Default::default(),
+                backtrace: None,
+            },
+        );
+        let row = envelope_row(&error).unwrap();
+        assert_eq!(row.table, Table::Errors);
+        assert_eq!(row.values.len(), ERROR_COLUMNS.len());
+
+        let page_view = Envelope::new(&metadata, EnvelopePayload::PageView { page: "/".into() });
+        let row = envelope_row(&page_view).unwrap();
+        assert_eq!(row.table, Table::Events);
+        assert_eq!(row.values.len(), EVENT_COLUMNS.len());
+    }
+}
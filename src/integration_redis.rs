@@ -0,0 +1,359 @@
+use std::{
+    borrow::Cow,
+    io::{self, BufRead, BufReader, Read, Write},
+    net::TcpStream,
+    sync::{atomic::AtomicBool, Arc, Mutex, PoisonError},
+    thread::JoinHandle,
+    time::Duration,
+};
+
+use crate::{
+    queue::DEFAULT_QUEUE_CAPACITY,
+    queue::{Pop, PriorityQueue, SHUTDOWN_TIMEOUT},
+    reconnect::{self, Backoff},
+    spans::SpanSummaryLayer,
+    Battery, BatteryBuilder, DropPolicy, Envelope, EnvelopePayload, Importance, Metadata,
+};
+
+/// A [Redis Streams](https://redis.io/docs/latest/develop/data-types/streams/) integration which publishes
+/// errors, custom events, page views, and (optionally) completed spans to Redis streams using `XADD`.
+///
+/// <div class="warning">
+///
+/// This integration requires the `redis` feature to be enabled.
+///
+/// </div>
+///
+/// Each entry is added with a single `data` field holding its JSON representation; errors and events use the
+/// [`Envelope`] format, while spans include their name, target, level, duration, and recorded fields. Errors are
+/// published to the `telemetry:errors` stream and events and page views to `telemetry:events` by default, while
+/// spans are only published once a stream has been configured for them using [`RedisStreams::with_spans_stream`].
+///
+/// Streams are trimmed to approximately 10,000 entries (configurable using [`RedisStreams::with_max_len`]) so that
+/// they can be used as a lightweight telemetry bus without growing without bound.
+///
+/// If the server becomes unreachable, the integration reconnects with an exponential backoff (up to one minute
+/// between attempts) while entries wait in a bounded queue, from which the least important entries are shed.
+///
+/// ## Example
+/// ```no_run
+/// use tracing_batteries::{Session, RedisStreams};
+///
+/// let session = Session::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+///   .with_battery(RedisStreams::new("localhost", 6379)
+///     .with_spans_stream("telemetry:spans"));
+///
+/// session.shutdown();
+/// ```
+pub struct RedisStreams {
+    host: Cow<'static, str>,
+    port: u16,
+    password: Option<Cow<'static, str>>,
+    database: u32,
+    errors_stream: Cow<'static, str>,
+    events_stream: Cow<'static, str>,
+    spans_stream: Option<Cow<'static, str>>,
+    max_len: usize,
+    queue_capacity: usize,
+    drop_policy: DropPolicy,
+}
+
+impl RedisStreams {
+    /// Configures the Redis Streams integration to publish to the Redis server at the provided host and port.
+    pub fn new<H: Into<Cow<'static, str>>>(host: H, port: u16) -> Self {
+        Self {
+            host: host.into(),
+            port,
+            password: None,
+            database: 0,
+            errors_stream: "telemetry:errors".into(),
+            events_stream: "telemetry:events".into(),
+            spans_stream: None,
+            max_len: 10_000,
+            queue_capacity: DEFAULT_QUEUE_CAPACITY,
+            drop_policy: DropPolicy::default(),
+        }
+    }
+
+    /// Authenticates with the Redis server using the provided password.
+    pub fn with_password<P: Into<Cow<'static, str>>>(self, password: P) -> Self {
+        Self {
+            password: Some(password.into()),
+            ..self
+        }
+    }
+
+    /// Selects the logical database which streams are written to.
+    pub fn with_database(self, database: u32) -> Self {
+        Self { database, ..self }
+    }
+
+    /// Overrides the stream which errors are published to.
+    pub fn with_errors_stream<S: Into<Cow<'static, str>>>(self, stream: S) -> Self {
+        Self {
+            errors_stream: stream.into(),
+            ..self
+        }
+    }
+
+    /// Overrides the stream which custom events and page views are published to.
+    pub fn with_events_stream<S: Into<Cow<'static, str>>>(self, stream: S) -> Self {
+        Self {
+            events_stream: stream.into(),
+            ..self
+        }
+    }
+
+    /// Publishes completed spans to the provided stream.
+    ///
+    /// Spans are observed through the tracing subscriber installed by the [`OpenTelemetry`](crate::OpenTelemetry)
+    /// integration, so it must also be attached to the session for spans to be published.
+    pub fn with_spans_stream<S: Into<Cow<'static, str>>>(self, stream: S) -> Self {
+        Self {
+            spans_stream: Some(stream.into()),
+            ..self
+        }
+    }
+
+    /// Configures the approximate maximum number of entries retained in each stream.
+    pub fn with_max_len(self, max_len: usize) -> Self {
+        Self { max_len, ..self }
+    }
+
+    /// Configures the maximum number of stream entries which may be waiting to be sent (10,000 by default), and which
+    /// are dropped when the server is unreachable for long enough to fill the queue.
+    pub fn with_queue_limits(self, capacity: usize, policy: DropPolicy) -> Self {
+        Self {
+            queue_capacity: capacity,
+            drop_policy: policy,
+            ..self
+        }
+    }
+}
+
+impl BatteryBuilder for RedisStreams {
+    fn setup(self, metadata: &Metadata, enabled: Arc<AtomicBool>) -> Box<dyn Battery> {
+        let queue = Arc::new(PriorityQueue::<StreamEntry>::new("redis"));
+        queue.set_limits(self.queue_capacity, self.drop_policy);
+
+        let entries = queue.clone();
+        let host = self.host.to_string();
+        let port = self.port;
+        let password = self.password.map(|password| password.to_string());
+        let database = self.database;
+        let max_len = self.max_len.to_string();
+        let thread = std::thread::Builder::new()
+            .name("tracing-batteries-redis".into())
+            .spawn(move || {
+                let mut connection = None;
+                let mut backoff = Backoff::default();
+                let mut pending = None;
+
+                loop {
+                    let (entry, retried) = match pending.take() {
+                        Some(pending) => pending,
+                        None => match entries.pop(None) {
+                            Pop::Item(entry) => (entry, false),
+                            _ => break,
+                        },
+                    };
+
+                    // While the server is unreachable, newer entries wait in the (bounded) queue.
+                    if connection.is_none() {
+                        if !entries.wait_until(backoff.retry_at()) {
+                            break;
+                        }
+
+                        match RedisConnection::connect(&host, port, password.as_deref(), database) {
+                            Ok(established) => connection = Some(established),
+                            Err(err) => {
+                                crate::diagnostics::record_export_error("redis", err);
+                                backoff.failed();
+                                pending = Some((entry, retried));
+                                continue;
+                            }
+                        }
+                    }
+
+                    let written = connection.as_mut().is_some_and(|connection| {
+                        connection
+                            .command(&[
+                                "XADD",
+                                &entry.stream,
+                                "MAXLEN",
+                                "~",
+                                &max_len,
+                                "*",
+                                "data",
+                                &entry.data,
+                            ])
+                            .is_ok()
+                    });
+
+                    if written {
+                        backoff.reset();
+                    } else {
+                        // Retry once on a fresh connection in case the existing one has been closed.
+                        connection = None;
+                        if !retried {
+                            pending = Some((entry, true));
+                        }
+                    }
+                }
+            })
+            .ok();
+
+        if let Some(stream) = self.spans_stream {
+            let service = metadata.service.to_string();
+            let version = metadata.version.to_string();
+            let queue = queue.clone();
+            crate::layers::attach_layer(SpanSummaryLayer::new(enabled, move |span| {
+                queue.push(
+                    Importance::Low,
+                    StreamEntry {
+                        stream: stream.to_string(),
+                        data: span.to_json(&service, &version).to_string(),
+                    },
+                )
+            }));
+        }
+
+        Box::new(RedisStreamsBattery {
+            errors_stream: self.errors_stream.to_string(),
+            events_stream: self.events_stream.to_string(),
+            queue,
+            thread: Mutex::new(thread),
+        })
+    }
+}
+
+struct StreamEntry {
+    stream: String,
+    data: String,
+}
+
+struct RedisStreamsBattery {
+    errors_stream: String,
+    events_stream: String,
+    queue: Arc<PriorityQueue<StreamEntry>>,
+    thread: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl Battery for RedisStreamsBattery {
+    fn record_envelope(&self, envelope: &Envelope) {
+        let stream = match envelope.payload {
+            EnvelopePayload::Error { .. } => &self.errors_stream,
+            _ => &self.events_stream,
+        };
+
+        self.queue.push(
+            envelope.importance,
+            StreamEntry {
+                stream: stream.clone(),
+                data: envelope.to_json(),
+            },
+        );
+    }
+
+    fn shutdown(&self) {
+        self.queue.close(SHUTDOWN_TIMEOUT);
+
+        if let Some(thread) = self
+            .thread
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take()
+        {
+            crate::worker::join(thread);
+        }
+    }
+}
+
+/// A minimal [RESP](https://redis.io/docs/latest/develop/reference/protocol-spec/) client, supporting only
+/// the request/reply commands needed to publish to streams.
+struct RedisConnection {
+    writer: TcpStream,
+    reader: BufReader<TcpStream>,
+}
+
+impl RedisConnection {
+    fn connect(host: &str, port: u16, password: Option<&str>, database: u32) -> io::Result<Self> {
+        let socket = reconnect::connect(host, port)?;
+        socket.set_write_timeout(Some(Duration::from_secs(10)))?;
+        socket.set_read_timeout(Some(Duration::from_secs(10)))?;
+
+        let mut connection = Self {
+            reader: BufReader::new(socket.try_clone()?),
+            writer: socket,
+        };
+
+        if let Some(password) = password {
+            connection.command(&["AUTH", password])?;
+        }
+
+        if database != 0 {
+            connection.command(&["SELECT", &database.to_string()])?;
+        }
+
+        Ok(connection)
+    }
+
+    /// Sends a command and waits for its reply, returning an error if the server rejected it.
+    fn command(&mut self, args: &[&str]) -> io::Result<()> {
+        self.writer.write_all(&encode_command(args))?;
+        self.writer.flush()?;
+        read_reply(&mut self.reader)
+    }
+}
+
+/// Encodes a command as a RESP array of bulk strings.
+fn encode_command(args: &[&str]) -> Vec<u8> {
+    let mut buffer = format!("*{}\r\n", args.len()).into_bytes();
+    for arg in args {
+        buffer.extend_from_slice(format!("${}\r\n", arg.len()).as_bytes());
+        buffer.extend_from_slice(arg.as_bytes());
+        buffer.extend_from_slice(b"\r\n");
+    }
+
+    buffer
+}
+
+/// Reads a single RESP reply, discarding its value.
+fn read_reply<R: BufRead>(reader: &mut R) -> io::Result<()> {
+    let mut line = String::new();
+    if reader.read_line(&mut line)? == 0 {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+
+    let line = line.trim_end();
+    match line.split_at(line.len().min(1)) {
+        ("+", _) | (":", _) => Ok(()),
+        ("-", error) => Err(io::Error::other(error.to_string())),
+        ("$", length) => match length.parse::<i64>() {
+            Ok(length) if length >= 0 => {
+                let mut value = vec![0; length as usize + 2];
+                reader.read_exact(&mut value)
+            }
+            Ok(_) => Ok(()),
+            Err(_) => Err(io::ErrorKind::InvalidData.into()),
+        },
+        _ => Err(io::ErrorKind::InvalidData.into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resp_encoding() {
+        assert_eq!(
+            encode_command(&["XADD", "telemetry:errors", "*", "data", "{}"]),
+            b"*5\r\n$4\r\nXADD\r\n$16\r\ntelemetry:errors\r\n$1\r\n*\r\n$4\r\ndata\r\n$2\r\n{}\r\n"
+        );
+
+        assert!(read_reply(&mut &b"+OK\r\n"[..]).is_ok());
+        assert!(read_reply(&mut &b"$15\r\n1526919030474-0\r\n"[..]).is_ok());
+        assert!(read_reply(&mut &b"-WRONGTYPE Operation against a key\r\n"[..]).is_err());
+    }
+}
@@ -0,0 +1,382 @@
+use std::{
+    borrow::Cow,
+    io::Write,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+use flate2::{write::GzEncoder, Compression};
+
+use crate::{
+    aws::{self, AwsSigner},
+    batcher::BatchDispatcher,
+    dispatcher::HttpDispatcher,
+    queue::DEFAULT_QUEUE_CAPACITY,
+    spans::SpanSummaryLayer,
+    Artifact, Battery, BatteryBuilder, DropPolicy, Envelope, Importance, Metadata,
+};
+
+/// An [Amazon S3](https://aws.amazon.com/s3/) integration which periodically uploads gzip compressed batches of
+/// errors, custom events, page views and (optionally) span summaries to a bucket as newline-delimited JSON.
+///
+/// <div class="warning">
+///
+/// This integration requires the `s3` feature to be enabled.
+///
+/// </div>
+///
+/// This is intended to act as a cheap, long-term archive of your telemetry (and a feed for offline analysis with
+/// tools like Athena or DuckDB) which is independent of your live observability backends. Batches are uploaded
+/// every 5 minutes, or whenever 10,000 records have been collected, to objects whose keys start with a prefix
+/// template (defaulting to `{service}/{year}/{month}/{day}/`). The template may use the `{service}`, `{version}`,
+/// `{year}`, `{month}`, `{day}` and `{hour}` placeholders, which are filled in using the time of the upload.
+///
+/// Artifacts attached using [`Session::attach_artifact`](crate::Session::attach_artifact) are uploaded immediately,
+/// as individual objects beneath an `artifacts/` folder within the same prefix.
+///
+/// Requests are signed using credentials from the standard AWS credential chain: environment variables, the shared
+/// credentials file, the ECS container credentials endpoint, or the EC2 instance metadata service. S3 compatible object stores (such as MinIO or Cloudflare R2) may
+/// be used by providing their endpoint with [`S3Archive::with_endpoint`].
+///
+/// ## Example
+/// ```no_run
+/// use tracing_batteries::{Session, S3Archive};
+///
+/// let session = Session::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+///   .with_battery(S3Archive::new("my-telemetry-archive")
+///     .with_prefix("telemetry/{service}/{year}/{month}/{day}/")
+///     .with_spans());
+///
+/// session.shutdown();
+/// ```
+pub struct S3Archive {
+    bucket: Cow<'static, str>,
+    prefix: Cow<'static, str>,
+    region: Option<Cow<'static, str>>,
+    endpoint: Option<Cow<'static, str>>,
+    spans: bool,
+    batch_size: usize,
+    flush_interval: Duration,
+    queue_capacity: usize,
+    drop_policy: DropPolicy,
+}
+
+impl S3Archive {
+    /// Configures the S3 integration to upload batches to the provided bucket.
+    pub fn new<B: Into<Cow<'static, str>>>(bucket: B) -> Self {
+        Self {
+            bucket: bucket.into(),
+            prefix: "{service}/{year}/{month}/{day}/".into(),
+            region: None,
+            endpoint: None,
+            spans: false,
+            batch_size: 10_000,
+            flush_interval: Duration::from_secs(300),
+            queue_capacity: DEFAULT_QUEUE_CAPACITY,
+            drop_policy: DropPolicy::default(),
+        }
+    }
+
+    /// Overrides the template used to build the prefix of each uploaded object's key.
+    pub fn with_prefix<P: Into<Cow<'static, str>>>(self, prefix: P) -> Self {
+        Self {
+            prefix: prefix.into(),
+            ..self
+        }
+    }
+
+    /// Overrides the AWS region which hosts the bucket.
+    pub fn with_region<R: Into<Cow<'static, str>>>(self, region: R) -> Self {
+        Self {
+            region: Some(region.into()),
+            ..self
+        }
+    }
+
+    /// Uploads to an S3 compatible object store at the provided endpoint, using path-style requests.
+    pub fn with_endpoint<E: Into<Cow<'static, str>>>(self, endpoint: E) -> Self {
+        Self {
+            endpoint: Some(endpoint.into()),
+            ..self
+        }
+    }
+
+    /// Includes a summary of each completed span in the archive.
+    ///
+    /// Spans are observed through the tracing subscriber installed by the [`OpenTelemetry`](crate::OpenTelemetry)
+    /// integration, so it must also be attached to the session for spans to be archived.
+    pub fn with_spans(self) -> Self {
+        Self {
+            spans: true,
+            ..self
+        }
+    }
+
+    /// Configures the maximum number of records included in each uploaded object, and how frequently partial
+    /// batches are uploaded.
+    pub fn with_batching(self, batch_size: usize, flush_interval: Duration) -> Self {
+        Self {
+            batch_size: batch_size.max(1),
+            flush_interval,
+            ..self
+        }
+    }
+
+    /// Configures the maximum number of log lines and artifacts which may be waiting to be sent (10,000 by default),
+    /// and which are dropped when the bucket falls behind a burst of activity.
+    pub fn with_queue_limits(self, capacity: usize, policy: DropPolicy) -> Self {
+        Self {
+            queue_capacity: capacity,
+            drop_policy: policy,
+            ..self
+        }
+    }
+}
+
+impl BatteryBuilder for S3Archive {
+    fn setup(self, metadata: &Metadata, enabled: Arc<AtomicBool>) -> Box<dyn Battery> {
+        let region = self
+            .region
+            .map(|region| region.to_string())
+            .unwrap_or_else(aws::default_region);
+        let base_url = match &self.endpoint {
+            Some(endpoint) => format!("{}/{}", endpoint.trim_end_matches('/'), self.bucket),
+            None => {
+                aws::endpoint("s3", &region).replacen("://", &format!("://{}.", self.bucket), 1)
+            }
+        };
+        let signer = Arc::new(AwsSigner::new("s3", region));
+
+        let prefix = self.prefix.to_string();
+        let service = metadata.service.to_string();
+        let version = metadata.version.to_string();
+        let sequence = AtomicU64::new(0);
+
+        let artifacts = ArtifactUploader {
+            dispatcher: HttpDispatcher::new("s3-artifacts")
+                .with_limits(self.queue_capacity, self.drop_policy),
+            signer: signer.clone(),
+            base_url: base_url.clone(),
+            prefix: prefix.clone(),
+            service: service.clone(),
+            version: version.clone(),
+        };
+
+        let dispatcher = Arc::new(
+            BatchDispatcher::new(
+                "s3",
+                self.batch_size,
+                self.flush_interval,
+                3,
+                move |client, lines: &[String]| {
+                    let key = object_key(
+                        &prefix,
+                        &service,
+                        &version,
+                        Utc::now(),
+                        sequence.fetch_add(1, Ordering::Relaxed),
+                    );
+                    let body = compress(lines);
+                    let payload_hash = aws::payload_hash(&body);
+
+                    signer.request(
+                        client,
+                        reqwest::Method::PUT,
+                        &format!("{base_url}/{}", aws::uri_encode(&key, false)),
+                        &[
+                            ("Content-Type", "application/x-ndjson"),
+                            ("Content-Encoding", "gzip"),
+                            ("x-amz-content-sha256", &payload_hash),
+                        ],
+                        body,
+                    )
+                },
+            )
+            .with_limits(self.queue_capacity, self.drop_policy),
+        );
+
+        if self.spans {
+            let service = metadata.service.to_string();
+            let version = metadata.version.to_string();
+            let dispatcher = dispatcher.clone();
+            crate::layers::attach_layer(SpanSummaryLayer::new(enabled, move |span| {
+                dispatcher.push(
+                    Importance::Low,
+                    span.to_json(&service, &version).to_string(),
+                )
+            }));
+        }
+
+        Box::new(S3ArchiveBattery {
+            dispatcher,
+            artifacts,
+        })
+    }
+}
+
+/// Builds the key for an uploaded object by filling in the prefix template and appending a unique file name.
+fn object_key(
+    prefix: &str,
+    service: &str,
+    version: &str,
+    now: DateTime<Utc>,
+    sequence: u64,
+) -> String {
+    format!(
+        "{}{}-{}-{sequence}.jsonl.gz",
+        templated_prefix(prefix, service, version, now),
+        now.format("%Y%m%dT%H%M%SZ"),
+        std::process::id()
+    )
+}
+
+/// Builds the key for an uploaded artifact, placing it in an `artifacts/` folder beneath the templated prefix.
+fn artifact_key(
+    prefix: &str,
+    service: &str,
+    version: &str,
+    now: DateTime<Utc>,
+    name: &str,
+) -> String {
+    format!(
+        "{}artifacts/{}-{}-{}",
+        templated_prefix(prefix, service, version, now),
+        now.format("%Y%m%dT%H%M%SZ"),
+        std::process::id(),
+        name.replace(['/', '\\'], "_")
+    )
+}
+
+fn templated_prefix(prefix: &str, service: &str, version: &str, now: DateTime<Utc>) -> String {
+    prefix
+        .replace("{service}", service)
+        .replace("{version}", version)
+        .replace("{year}", &now.format("%Y").to_string())
+        .replace("{month}", &now.format("%m").to_string())
+        .replace("{day}", &now.format("%d").to_string())
+        .replace("{hour}", &now.format("%H").to_string())
+}
+
+fn compress(lines: &[String]) -> Vec<u8> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    for line in lines {
+        encoder.write_all(line.as_bytes()).ok();
+        encoder.write_all(b"\n").ok();
+    }
+    encoder.finish().unwrap_or_default()
+}
+
+struct ArtifactUploader {
+    dispatcher: HttpDispatcher,
+    signer: Arc<AwsSigner>,
+    base_url: String,
+    prefix: String,
+    service: String,
+    version: String,
+}
+
+impl ArtifactUploader {
+    fn upload(&self, artifact: &Artifact) {
+        let key = artifact_key(
+            &self.prefix,
+            &self.service,
+            &self.version,
+            Utc::now(),
+            &artifact.name,
+        );
+        let url = format!("{}/{}", self.base_url, aws::uri_encode(&key, false));
+        let content_type = artifact.content_type.to_string();
+        let body = artifact.data.to_vec();
+        let signer = self.signer.clone();
+
+        self.dispatcher
+            .dispatch(Importance::Critical, move |client| {
+                let payload_hash = aws::payload_hash(&body);
+                signer.request(
+                    client,
+                    reqwest::Method::PUT,
+                    &url,
+                    &[
+                        ("Content-Type", &content_type),
+                        ("x-amz-content-sha256", &payload_hash),
+                    ],
+                    body,
+                )
+            });
+    }
+}
+
+struct S3ArchiveBattery {
+    dispatcher: Arc<BatchDispatcher<String>>,
+    artifacts: ArtifactUploader,
+}
+
+impl Battery for S3ArchiveBattery {
+    fn record_envelope(&self, envelope: &Envelope) {
+        self.dispatcher
+            .push(envelope.importance, envelope.to_json());
+    }
+
+    fn record_artifact(&self, artifact: &Artifact) -> bool {
+        self.artifacts.upload(artifact);
+        true
+    }
+
+    fn shutdown(&self) {
+        self.dispatcher.shutdown();
+        self.artifacts.dispatcher.shutdown();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    #[test]
+    fn templated_keys() {
+        let now = Utc.with_ymd_and_hms(2024, 3, 7, 9, 30, 0).unwrap();
+
+        assert_eq!(
+            object_key(
+                "{service}/{year}/{month}/{day}/",
+                "example",
+                "1.0.0",
+                now,
+                4
+            ),
+            format!(
+                "example/2024/03/07/20240307T093000Z-{}-4.jsonl.gz",
+                std::process::id()
+            )
+        );
+        assert!(
+            object_key("archive/v{version}/{hour}/", "example", "1.0.0", now, 0)
+                .starts_with("archive/v1.0.0/09/20240307T093000Z-")
+        );
+    }
+
+    #[test]
+    fn artifact_keys() {
+        let now = Utc.with_ymd_and_hms(2024, 3, 7, 9, 30, 0).unwrap();
+
+        assert_eq!(
+            artifact_key(
+                "{service}/{year}/{month}/{day}/",
+                "example",
+                "1.0.0",
+                now,
+                "../state.json"
+            ),
+            format!(
+                "example/2024/03/07/artifacts/20240307T093000Z-{}-.._state.json",
+                std::process::id()
+            )
+        );
+    }
+}
@@ -1,12 +1,31 @@
-use std::sync::{atomic::AtomicBool, Arc};
+use std::sync::{atomic::AtomicBool, Arc, Mutex, PoisonError};
 
-use crate::{Battery, BatteryBuilder, Metadata};
+use crate::{Battery, BatteryBuilder, ErrorContext, Metadata, ValidationCheck};
 
 use sentry;
 pub use sentry::Level as SentryLevel;
 
 struct SentryBattery {
     raven: sentry::ClientInitGuard,
+    /// The artifacts which will be attached to the next error reported to Sentry.
+    attachments: Mutex<Vec<sentry::protocol::Attachment>>,
+}
+
+impl SentryBattery {
+    /// Adds any pending artifacts to the scope of the error which is about to be reported, so that they are sent
+    /// once rather than accompanying every subsequent event.
+    fn attach_pending(&self, scope: &mut sentry::Scope) {
+        let attachments = std::mem::take(
+            &mut *self
+                .attachments
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner),
+        );
+
+        for attachment in attachments {
+            scope.add_attachment(attachment);
+        }
+    }
 }
 
 impl Battery for SentryBattery {
@@ -15,8 +34,75 @@ impl Battery for SentryBattery {
         self.raven.close(None);
     }
 
+    fn validate(&self) -> Vec<ValidationCheck> {
+        let Some(dsn) = self.raven.dsn() else {
+            return vec![ValidationCheck::failed(
+                "Sentry",
+                "a DSN is configured",
+                "no DSN was provided (or set using SENTRY_DSN), so errors will not be reported",
+            )];
+        };
+
+        let description = format!("the Sentry server at {} is reachable", dsn.host());
+        vec![match crate::preflight::check_reachable(
+            &format!("{}:{}", dsn.host(), dsn.port()),
+            crate::preflight::VALIDATION_TIMEOUT,
+        ) {
+            Ok(()) => ValidationCheck::passed("Sentry", description),
+            Err(err) => ValidationCheck::failed("Sentry", description, err),
+        }]
+    }
+
+    fn record_artifact(&self, artifact: &crate::Artifact) -> bool {
+        self.attachments
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(sentry::protocol::Attachment {
+                buffer: artifact.data.to_vec(),
+                filename: artifact.name.to_string(),
+                content_type: Some(artifact.content_type.to_string()),
+                ..Default::default()
+            });
+        true
+    }
+
     fn record_error(&self, error: &dyn std::error::Error) {
-        sentry::capture_error(error);
+        sentry::with_scope(
+            |scope| self.attach_pending(scope),
+            || sentry::capture_error(error),
+        );
+    }
+
+    fn record_error_with(&self, error: &dyn std::error::Error, context: &ErrorContext) {
+        sentry::with_scope(
+            |scope| {
+                self.attach_pending(scope);
+
+                for (key, value) in &context.fields {
+                    scope.set_extra(key, value.clone().into());
+                }
+
+                for breadcrumb in &context.breadcrumbs {
+                    scope.add_breadcrumb(sentry::Breadcrumb {
+                        category: Some(breadcrumb.category.to_string()),
+                        message: Some(breadcrumb.message.clone()),
+                        ..Default::default()
+                    });
+                }
+            },
+            || {
+                let mut event = sentry::event_from_error(error);
+                if let Some(backtrace) = &context.backtrace {
+                    if let Some(exception) = event.exception.values.last_mut() {
+                        exception.stacktrace = sentry::integrations::backtrace::parse_stacktrace(
+                            &backtrace.to_string(),
+                        );
+                    }
+                }
+
+                sentry::capture_event(event)
+            },
+        );
     }
 }
 
@@ -161,6 +247,63 @@ impl BatteryBuilder for Sentry {
 
         sentry::start_session();
 
-        Box::new(SentryBattery { raven })
+        Box::new(SentryBattery {
+            raven,
+            attachments: Mutex::new(Vec::new()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Artifact, Session};
+
+    struct CapturingTransport(Arc<Mutex<Vec<sentry::Envelope>>>);
+
+    impl sentry::Transport for CapturingTransport {
+        fn send_envelope(&self, envelope: sentry::Envelope) {
+            self.0.lock().unwrap().push(envelope);
+        }
+    }
+
+    #[test]
+    fn attaches_artifacts_to_the_next_error() {
+        let envelopes = Arc::new(Mutex::new(Vec::new()));
+        let transport = Arc::new(CapturingTransport(envelopes.clone()));
+        let battery = Sentry::new((
+            "https://public@sentry.example.com/1",
+            sentry::ClientOptions {
+                transport: Some(Arc::new(transport)),
+                ..Default::default()
+            },
+        ))
+        .setup(
+            &Session::new("example", "0.0.1"),
+            Arc::new(AtomicBool::new(true)),
+        );
+
+        assert!(battery.record_artifact(&Artifact {
+            name: "state.json".into(),
+            content_type: "application/json".into(),
+            data: b"{}".to_vec().into(),
+        }));
+
+        battery.record_error(&std::io::Error::other("first"));
+        battery.record_error_with(&std::io::Error::other("second"), &ErrorContext::default());
+
+        let attachments: Vec<usize> = envelopes
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|envelope| envelope.event().is_some())
+            .map(|envelope| {
+                envelope
+                    .items()
+                    .filter(|item| matches!(item, sentry::protocol::EnvelopeItem::Attachment(_)))
+                    .count()
+            })
+            .collect();
+        assert_eq!(attachments, vec![1, 0]);
     }
 }
@@ -0,0 +1,121 @@
+use std::{
+    borrow::Cow,
+    sync::{atomic::AtomicBool, Arc},
+};
+
+use crate::{Battery, BatteryBuilder, Metadata, OpenTelemetry, OpenTelemetryProtocol};
+
+/// The SigNoz Cloud region which hosts your account, used to select the ingestion endpoint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SigNozRegion {
+    /// United States, `ingest.us.signoz.cloud`.
+    US,
+    /// Europe, `ingest.eu.signoz.cloud`.
+    EU,
+    /// India, `ingest.in.signoz.cloud`.
+    IN,
+}
+
+impl SigNozRegion {
+    /// The OTLP/HTTP ingestion endpoint for this region.
+    pub fn endpoint(&self) -> &'static str {
+        match self {
+            SigNozRegion::US => "https://ingest.us.signoz.cloud:443",
+            SigNozRegion::EU => "https://ingest.eu.signoz.cloud:443",
+            SigNozRegion::IN => "https://ingest.in.signoz.cloud:443",
+        }
+    }
+}
+
+/// A [SigNoz](https://signoz.io) integration which configures the [`OpenTelemetry`] integration to export
+/// traces and metrics to SigNoz Cloud or a self-hosted SigNoz installation.
+///
+/// <div class="warning">
+///
+/// This integration requires the `signoz` feature to be enabled.
+///
+/// </div>
+///
+/// SigNoz Cloud authenticates telemetry using the `signoz-ingestion-key` header, whose value may also be provided
+/// using the `SIGNOZ_INGESTION_KEY` environment variable. Self-hosted installations accept unauthenticated
+/// OTLP/HTTP telemetry through their bundled collector, which listens on `http://localhost:4318` by default.
+///
+/// ## Example
+/// ```no_run
+/// use tracing_batteries::{Session, SigNoz, SigNozRegion};
+///
+/// let session = Session::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+///   .with_battery(SigNoz::cloud(SigNozRegion::EU, "your-ingestion-key"));
+///
+/// session.shutdown();
+/// ```
+pub struct SigNoz {
+    endpoint: Cow<'static, str>,
+    ingestion_key: Option<Cow<'static, str>>,
+    configure: Box<dyn FnOnce(OpenTelemetry) -> OpenTelemetry>,
+}
+
+impl SigNoz {
+    /// Configures the SigNoz integration for SigNoz Cloud in the provided region.
+    pub fn cloud<K: Into<Cow<'static, str>>>(region: SigNozRegion, ingestion_key: K) -> Self {
+        Self {
+            endpoint: region.endpoint().into(),
+            ingestion_key: Some(
+                std::env::var("SIGNOZ_INGESTION_KEY")
+                    .map(Cow::Owned)
+                    .unwrap_or_else(|_| ingestion_key.into()),
+            ),
+            configure: Box::new(|otel| otel),
+        }
+    }
+
+    /// Configures the SigNoz integration for a self-hosted installation, using the collector on `http://localhost:4318`.
+    pub fn self_hosted() -> Self {
+        Self {
+            endpoint: "http://localhost:4318".into(),
+            ingestion_key: None,
+            configure: Box::new(|otel| otel),
+        }
+    }
+
+    /// Overrides the OTLP/HTTP endpoint which telemetry is exported to (e.g. your self-hosted collector's address).
+    pub fn with_endpoint<E: Into<Cow<'static, str>>>(self, endpoint: E) -> Self {
+        Self {
+            endpoint: endpoint.into(),
+            ..self
+        }
+    }
+
+    /// Customizes the underlying [`OpenTelemetry`] integration used to export traces and metrics.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use tracing_batteries::{SigNoz, OpenTelemetryLevel};
+    ///
+    /// SigNoz::self_hosted()
+    ///   .with_opentelemetry(|otel| otel.with_default_level(OpenTelemetryLevel::DEBUG));
+    /// ```
+    pub fn with_opentelemetry<F>(self, configure: F) -> Self
+    where
+        F: FnOnce(OpenTelemetry) -> OpenTelemetry + 'static,
+    {
+        Self {
+            configure: Box::new(configure),
+            ..self
+        }
+    }
+}
+
+impl BatteryBuilder for SigNoz {
+    fn setup(self, metadata: &Metadata, enabled: Arc<AtomicBool>) -> Box<dyn Battery> {
+        let mut otel = OpenTelemetry::new("")
+            .with_endpoint(self.endpoint.trim_end_matches('/').to_string())
+            .with_protocol(OpenTelemetryProtocol::HttpBinary);
+
+        if let Some(ingestion_key) = self.ingestion_key {
+            otel = otel.with_header("signoz-ingestion-key", ingestion_key);
+        }
+
+        (self.configure)(otel).setup(metadata, enabled)
+    }
+}
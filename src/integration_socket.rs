@@ -0,0 +1,273 @@
+use std::{
+    io::{self, Write},
+    net::{TcpStream, UdpSocket},
+    sync::{atomic::AtomicBool, Arc, Mutex, PoisonError},
+    thread::JoinHandle,
+    time::Duration,
+};
+
+#[cfg(unix)]
+use std::{
+    os::unix::net::{UnixDatagram, UnixStream},
+    path::PathBuf,
+};
+
+use crate::{
+    queue::{Pop, PriorityQueue, DEFAULT_QUEUE_CAPACITY, SHUTDOWN_TIMEOUT},
+    Battery, BatteryBuilder, DropPolicy, Envelope, Metadata,
+};
+
+/// The largest datagram which will be sent to a UDP socket, beyond which envelopes are dropped.
+const MAX_DATAGRAM_SIZE: usize = 65_507;
+
+#[derive(Debug, Clone)]
+enum SocketTarget {
+    Tcp(String),
+    Udp(String),
+    #[cfg(unix)]
+    Unix(PathBuf),
+    #[cfg(unix)]
+    UnixDatagram(PathBuf),
+}
+
+/// An integration which writes every [`Envelope`] to a local socket, allowing you to build your own sidecar
+/// collectors around the crate's envelope schema.
+///
+/// <div class="warning">
+///
+/// This integration requires the `socket` feature to be enabled.
+///
+/// </div>
+///
+/// Each envelope is written as a frame consisting of its length (as a 4-byte big-endian unsigned integer)
+/// followed by its JSON representation, which may be parsed using [`Envelope::from_json`]. Stream sockets (TCP
+/// and Unix domain sockets) carry a sequence of frames, while datagram sockets (UDP and Unix datagram sockets)
+/// carry a single frame per datagram. Stream connections are re-established automatically if the collector is
+/// restarted, and envelopes are dropped while it is unavailable.
+///
+/// ## Example
+/// ```no_run
+/// use tracing_batteries::{Session, SocketEmitter};
+///
+/// let session = Session::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+///   .with_battery(SocketEmitter::tcp("127.0.0.1:7070"));
+///
+/// session.shutdown();
+/// ```
+pub struct SocketEmitter {
+    target: SocketTarget,
+    queue_capacity: usize,
+    drop_policy: DropPolicy,
+}
+
+impl SocketEmitter {
+    /// Writes envelopes to the TCP socket at the provided address (e.g. `127.0.0.1:7070`).
+    pub fn tcp<A: Into<String>>(address: A) -> Self {
+        Self {
+            target: SocketTarget::Tcp(address.into()),
+            queue_capacity: DEFAULT_QUEUE_CAPACITY,
+            drop_policy: DropPolicy::default(),
+        }
+    }
+
+    /// Sends envelopes as datagrams to the UDP socket at the provided address.
+    pub fn udp<A: Into<String>>(address: A) -> Self {
+        Self {
+            target: SocketTarget::Udp(address.into()),
+            queue_capacity: DEFAULT_QUEUE_CAPACITY,
+            drop_policy: DropPolicy::default(),
+        }
+    }
+
+    /// Writes envelopes to the Unix domain stream socket at the provided path.
+    #[cfg(unix)]
+    pub fn unix<P: Into<PathBuf>>(path: P) -> Self {
+        Self {
+            target: SocketTarget::Unix(path.into()),
+            queue_capacity: DEFAULT_QUEUE_CAPACITY,
+            drop_policy: DropPolicy::default(),
+        }
+    }
+
+    /// Sends envelopes as datagrams to the Unix domain datagram socket at the provided path.
+    #[cfg(unix)]
+    pub fn unix_datagram<P: Into<PathBuf>>(path: P) -> Self {
+        Self {
+            target: SocketTarget::UnixDatagram(path.into()),
+            queue_capacity: DEFAULT_QUEUE_CAPACITY,
+            drop_policy: DropPolicy::default(),
+        }
+    }
+
+    /// Configures the maximum number of envelopes which may be waiting to be sent (10,000 by default), and which are
+    /// dropped when the collector falls behind a burst of activity.
+    pub fn with_queue_limits(self, capacity: usize, policy: DropPolicy) -> Self {
+        Self {
+            queue_capacity: capacity,
+            drop_policy: policy,
+            ..self
+        }
+    }
+}
+
+impl BatteryBuilder for SocketEmitter {
+    fn setup(self, _metadata: &Metadata, _enabled: Arc<AtomicBool>) -> Box<dyn Battery> {
+        let queue = Arc::new(PriorityQueue::<Vec<u8>>::new("socket"));
+        queue.set_limits(self.queue_capacity, self.drop_policy);
+
+        let frames = queue.clone();
+        let target = self.target;
+        let thread = std::thread::Builder::new()
+            .name("tracing-batteries-socket".into())
+            .spawn(move || {
+                let mut connection = None;
+                while let Pop::Item(frame) = frames.pop(None) {
+                    // Retry once on a fresh connection if the existing one has been closed.
+                    for _ in 0..2 {
+                        if connection.is_none() {
+                            connection = Connection::open(&target).ok();
+                        }
+
+                        let written = connection
+                            .as_mut()
+                            .map(|connection| connection.send(&frame).is_ok());
+
+                        match written {
+                            Some(false) => connection = None,
+                            _ => break,
+                        }
+                    }
+                }
+            })
+            .ok();
+
+        Box::new(SocketEmitterBattery {
+            queue,
+            thread: Mutex::new(thread),
+        })
+    }
+}
+
+enum Connection {
+    Stream(Box<dyn Write + Send>),
+    Udp(UdpSocket),
+    #[cfg(unix)]
+    UnixDatagram(UnixDatagram),
+}
+
+impl Connection {
+    fn open(target: &SocketTarget) -> io::Result<Self> {
+        let timeout = Some(Duration::from_secs(5));
+        match target {
+            SocketTarget::Tcp(address) => {
+                let stream = TcpStream::connect(address)?;
+                stream.set_write_timeout(timeout)?;
+                Ok(Connection::Stream(Box::new(stream)))
+            }
+            SocketTarget::Udp(address) => {
+                let socket = UdpSocket::bind(if address.starts_with('[') {
+                    "[::]:0"
+                } else {
+                    "0.0.0.0:0"
+                })?;
+                socket.connect(address)?;
+                Ok(Connection::Udp(socket))
+            }
+            #[cfg(unix)]
+            SocketTarget::Unix(path) => {
+                let stream = UnixStream::connect(path)?;
+                stream.set_write_timeout(timeout)?;
+                Ok(Connection::Stream(Box::new(stream)))
+            }
+            #[cfg(unix)]
+            SocketTarget::UnixDatagram(path) => {
+                let socket = UnixDatagram::unbound()?;
+                socket.connect(path)?;
+                Ok(Connection::UnixDatagram(socket))
+            }
+        }
+    }
+
+    fn send(&mut self, frame: &[u8]) -> io::Result<()> {
+        match self {
+            Connection::Stream(stream) => stream.write_all(frame).and_then(|_| stream.flush()),
+            Connection::Udp(socket) => {
+                // Oversized envelopes can never be delivered, so they are dropped rather than retried.
+                if frame.len() <= MAX_DATAGRAM_SIZE {
+                    socket.send(frame)?;
+                }
+                Ok(())
+            }
+            #[cfg(unix)]
+            Connection::UnixDatagram(socket) => socket.send(frame).map(|_| ()),
+        }
+    }
+}
+
+/// Encodes an [`Envelope`] as a length-prefixed JSON frame.
+fn encode_frame(envelope: &Envelope) -> Vec<u8> {
+    let json = envelope.to_json();
+    let mut frame = Vec::with_capacity(json.len() + 4);
+    frame.extend_from_slice(&(json.len() as u32).to_be_bytes());
+    frame.extend_from_slice(json.as_bytes());
+    frame
+}
+
+struct SocketEmitterBattery {
+    queue: Arc<PriorityQueue<Vec<u8>>>,
+    thread: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl Battery for SocketEmitterBattery {
+    fn record_envelope(&self, envelope: &Envelope) {
+        self.queue.push(envelope.importance, encode_frame(envelope));
+    }
+
+    fn shutdown(&self) {
+        self.queue.close(SHUTDOWN_TIMEOUT);
+
+        if let Some(thread) = self
+            .thread
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take()
+        {
+            crate::worker::join(thread);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{EnvelopePayload, Session};
+
+    #[test]
+    fn udp_frames() {
+        let collector = UdpSocket::bind("127.0.0.1:0").unwrap();
+        collector
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+
+        let metadata = Session::new("example", "0.0.1");
+        let envelope = Envelope::new(
+            &metadata,
+            EnvelopePayload::PageView {
+                page: "/settings".into(),
+            },
+        );
+
+        let target = SocketTarget::Udp(collector.local_addr().unwrap().to_string());
+        let mut connection = Connection::open(&target).unwrap();
+        connection.send(&encode_frame(&envelope)).unwrap();
+
+        let mut buffer = [0; 1024];
+        let received = collector.recv(&mut buffer).unwrap();
+        let length = u32::from_be_bytes(buffer[..4].try_into().unwrap()) as usize;
+        assert_eq!(length, received - 4);
+        assert_eq!(
+            Envelope::from_json(std::str::from_utf8(&buffer[4..received]).unwrap()).unwrap(),
+            envelope
+        );
+    }
+}
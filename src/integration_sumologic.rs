@@ -0,0 +1,266 @@
+use std::{
+    borrow::Cow,
+    io::Write,
+    sync::{atomic::AtomicBool, Arc},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use flate2::{write::GzEncoder, Compression};
+
+use crate::{
+    batcher::BatchDispatcher, queue::DEFAULT_QUEUE_CAPACITY, Battery, BatteryBuilder, DropPolicy,
+    Envelope, Importance, Metadata, Metric,
+};
+
+/// A [Sumo Logic](https://www.sumologic.com) integration which posts errors, custom events, page views,
+/// and metrics to a hosted HTTP source.
+///
+/// <div class="warning">
+///
+/// This integration requires the `sumologic` feature to be enabled.
+///
+/// </div>
+///
+/// Logs are sent as newline-delimited JSON [`Envelope`]s, while metrics are sent using the Carbon 2.0 format.
+/// Requests are batched, gzip compressed, and retried if the source is unavailable. The source category, host,
+/// and name are populated from your [`Metadata`] (using the service name, the `host.name` context field, and the
+/// service version respectively) unless they are overridden.
+///
+/// ## Example
+/// ```no_run
+/// use tracing_batteries::{Session, SumoLogic};
+///
+/// let session = Session::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+///   .with_battery(SumoLogic::new("https://endpoint1.collection.sumologic.com/receiver/v1/http/your-token")
+///     .with_category("prod/my-service"));
+///
+/// session.shutdown();
+/// ```
+pub struct SumoLogic {
+    url: Cow<'static, str>,
+    category: Option<Cow<'static, str>>,
+    host: Option<Cow<'static, str>>,
+    name: Option<Cow<'static, str>>,
+    batch_size: usize,
+    flush_interval: Duration,
+    retries: usize,
+    queue_capacity: usize,
+    drop_policy: DropPolicy,
+}
+
+impl SumoLogic {
+    /// Configures the Sumo Logic integration to post to the provided HTTP source URL.
+    pub fn new<U: Into<Cow<'static, str>>>(url: U) -> Self {
+        Self {
+            url: url.into(),
+            category: None,
+            host: None,
+            name: None,
+            batch_size: 100,
+            flush_interval: Duration::from_secs(5),
+            retries: 3,
+            queue_capacity: DEFAULT_QUEUE_CAPACITY,
+            drop_policy: DropPolicy::default(),
+        }
+    }
+
+    /// Overrides the source category (`X-Sumo-Category`) which telemetry is reported under.
+    pub fn with_category<C: Into<Cow<'static, str>>>(self, category: C) -> Self {
+        Self {
+            category: Some(category.into()),
+            ..self
+        }
+    }
+
+    /// Overrides the source host (`X-Sumo-Host`) which telemetry is reported under.
+    pub fn with_host<H: Into<Cow<'static, str>>>(self, host: H) -> Self {
+        Self {
+            host: Some(host.into()),
+            ..self
+        }
+    }
+
+    /// Overrides the source name (`X-Sumo-Name`) which telemetry is reported under.
+    pub fn with_name<N: Into<Cow<'static, str>>>(self, name: N) -> Self {
+        Self {
+            name: Some(name.into()),
+            ..self
+        }
+    }
+
+    /// Configures the maximum number of records which are sent in a single request, and how frequently
+    /// partial batches are flushed.
+    pub fn with_batching(self, batch_size: usize, flush_interval: Duration) -> Self {
+        Self {
+            batch_size: batch_size.max(1),
+            flush_interval,
+            ..self
+        }
+    }
+
+    /// Configures how many times a failed request is retried before the batch is dropped.
+    pub fn with_retries(self, retries: usize) -> Self {
+        Self { retries, ..self }
+    }
+
+    /// Configures the maximum number of log lines and metrics which may be waiting to be sent (10,000 by default), and
+    /// which are dropped when the collector falls behind a burst of activity.
+    pub fn with_queue_limits(self, capacity: usize, policy: DropPolicy) -> Self {
+        Self {
+            queue_capacity: capacity,
+            drop_policy: policy,
+            ..self
+        }
+    }
+}
+
+impl BatteryBuilder for SumoLogic {
+    fn setup(self, metadata: &Metadata, _enabled: Arc<AtomicBool>) -> Box<dyn Battery> {
+        let mut headers = vec![(
+            "X-Sumo-Category",
+            self.category
+                .unwrap_or_else(|| metadata.service.clone())
+                .to_string(),
+        )];
+        if let Some(host) = self
+            .host
+            .or_else(|| metadata.context.get("host.name").cloned())
+        {
+            headers.push(("X-Sumo-Host", host.to_string()));
+        }
+        headers.push((
+            "X-Sumo-Name",
+            self.name
+                .unwrap_or_else(|| metadata.version.clone())
+                .to_string(),
+        ));
+
+        let dispatcher = |name: &str, content_type: &'static str| {
+            let url = self.url.to_string();
+            let headers = headers.clone();
+            BatchDispatcher::new(
+                name,
+                self.batch_size,
+                self.flush_interval,
+                self.retries,
+                move |client, lines: &[String]| {
+                    let mut request = client
+                        .post(&url)
+                        .header("Content-Type", content_type)
+                        .header("Content-Encoding", "gzip")
+                        .body(compress(lines));
+                    for (key, value) in headers.iter() {
+                        request = request.header(*key, value);
+                    }
+                    request
+                },
+            )
+            .with_limits(self.queue_capacity, self.drop_policy)
+        };
+
+        Box::new(SumoLogicBattery {
+            logs: dispatcher("sumologic-logs", "application/json"),
+            metrics: dispatcher("sumologic-metrics", "application/vnd.sumologic.carbon2"),
+        })
+    }
+}
+
+struct SumoLogicBattery {
+    logs: BatchDispatcher<String>,
+    metrics: BatchDispatcher<String>,
+}
+
+impl Battery for SumoLogicBattery {
+    fn record_envelope(&self, envelope: &Envelope) {
+        self.logs.push(envelope.importance, envelope.to_json());
+    }
+
+    fn record_metric(&self, metric: &Metric) {
+        self.metrics.push(
+            Importance::Normal,
+            format_carbon2(metric, SystemTime::now()),
+        );
+    }
+
+    fn shutdown(&self) {
+        self.logs.shutdown();
+        self.metrics.shutdown();
+    }
+}
+
+fn compress(lines: &[String]) -> Vec<u8> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    for line in lines {
+        encoder.write_all(line.as_bytes()).ok();
+        encoder.write_all(b"\n").ok();
+    }
+    encoder.finish().unwrap_or_default()
+}
+
+/// Formats a [`Metric`] using the [Carbon 2.0](https://help.sumologic.com/docs/metrics/introduction/metric-formats/) format,
+/// attaching a `count` metadata tag to aggregated histogram measurements which represent more than one observation.
+fn format_carbon2(metric: &Metric, timestamp: SystemTime) -> String {
+    fn tag(value: &str) -> String {
+        value
+            .chars()
+            .map(|c| {
+                if c.is_whitespace() || c == '=' {
+                    '_'
+                } else {
+                    c
+                }
+            })
+            .collect()
+    }
+
+    let mut line = format!("metric={}", tag(metric.name));
+    for (key, value) in metric.attributes {
+        line.push_str(&format!(" {}={}", tag(key), tag(value)));
+    }
+
+    let seconds = timestamp
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    line.push(' ');
+    if metric.count > 1 {
+        line.push_str(&format!(" count={}", metric.count));
+    }
+
+    format!("{line} {} {seconds}", metric.value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MetricKind;
+
+    #[test]
+    fn carbon2() {
+        let metric = Metric {
+            name: "http.requests",
+            kind: MetricKind::Counter,
+            value: 1.0,
+            attributes: &[("route", "/users/{id}"), ("status", "200 OK")],
+            count: 1,
+        };
+
+        assert_eq!(
+            format_carbon2(&metric, UNIX_EPOCH + Duration::from_secs(1700000000)),
+            "metric=http.requests route=/users/{id} status=200_OK  1 1700000000"
+        );
+
+        let metric = Metric {
+            name: "http.duration",
+            kind: MetricKind::Histogram,
+            value: 12.5,
+            attributes: &[],
+            count: 4,
+        };
+
+        assert_eq!(
+            format_carbon2(&metric, UNIX_EPOCH + Duration::from_secs(1700000000)),
+            "metric=http.duration  count=4 12.5 1700000000"
+        );
+    }
+}
@@ -0,0 +1,247 @@
+use std::{
+    borrow::Cow,
+    sync::{atomic::AtomicBool, Arc},
+    time::Duration,
+};
+
+use serde_json::json;
+
+use crate::{
+    dispatcher::HttpDispatcher,
+    notify::{environment_label, RateLimiter},
+    queue::DEFAULT_QUEUE_CAPACITY,
+    Battery, BatteryBuilder, DropPolicy, Envelope, EnvelopePayload, Metadata, MetricExemplar,
+};
+
+/// A [Microsoft Teams](https://www.microsoft.com/microsoft-teams) integration which posts Adaptive Card
+/// messages to an incoming webhook whenever errors are recorded and when the session is shut down.
+///
+/// <div class="warning">
+///
+/// This integration requires the `teams` feature to be enabled.
+///
+/// </div>
+///
+/// Error notifications are throttled to at most 10 per minute, and each card includes your service's version
+/// and environment (taken from the `deployment.environment` or `environment` context field). When a trace URL
+/// template is configured using [`TeamsNotifier::with_trace_url`] and an error is recorded inside a sampled
+/// [`OpenTelemetry`](crate::OpenTelemetry) span, the card will include a button linking to the trace.
+///
+/// ## Example
+/// ```no_run
+/// use tracing_batteries::{Session, TeamsNotifier};
+///
+/// let session = Session::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+///   .with_battery(TeamsNotifier::new("https://example.webhook.office.com/webhookb2/your-webhook")
+///     .with_trace_url("https://jaeger.example.com/trace/{trace_id}"));
+///
+/// session.shutdown();
+/// ```
+pub struct TeamsNotifier {
+    webhook_url: Cow<'static, str>,
+    trace_url: Option<Cow<'static, str>>,
+    summaries: bool,
+    rate_limit: (usize, Duration),
+    queue_capacity: usize,
+    drop_policy: DropPolicy,
+}
+
+impl TeamsNotifier {
+    /// Configures the Teams integration to post to the provided incoming webhook URL.
+    pub fn new<U: Into<Cow<'static, str>>>(webhook_url: U) -> Self {
+        Self {
+            webhook_url: webhook_url.into(),
+            trace_url: None,
+            summaries: true,
+            rate_limit: (10, Duration::from_secs(60)),
+            queue_capacity: DEFAULT_QUEUE_CAPACITY,
+            drop_policy: DropPolicy::default(),
+        }
+    }
+
+    /// Configures a link to your tracing backend, where `{trace_id}` is replaced with the ID of the active trace.
+    pub fn with_trace_url<U: Into<Cow<'static, str>>>(self, url: U) -> Self {
+        Self {
+            trace_url: Some(url.into()),
+            ..self
+        }
+    }
+
+    /// Configures whether a summary of the session is posted when it is shut down (enabled by default).
+    pub fn with_shutdown_summary(self, enabled: bool) -> Self {
+        Self {
+            summaries: enabled,
+            ..self
+        }
+    }
+
+    /// Configures the maximum number of error notifications which are posted within the provided window.
+    pub fn with_rate_limit(self, limit: usize, window: Duration) -> Self {
+        Self {
+            rate_limit: (limit, window),
+            ..self
+        }
+    }
+
+    /// Configures the maximum number of notifications which may be waiting to be sent (10,000 by default), and which
+    /// are dropped when the webhook falls behind a burst of errors.
+    pub fn with_queue_limits(self, capacity: usize, policy: DropPolicy) -> Self {
+        Self {
+            queue_capacity: capacity,
+            drop_policy: policy,
+            ..self
+        }
+    }
+}
+
+impl BatteryBuilder for TeamsNotifier {
+    fn setup(self, metadata: &Metadata, _enabled: Arc<AtomicBool>) -> Box<dyn Battery> {
+        Box::new(TeamsBattery {
+            webhook_url: self.webhook_url.to_string(),
+            trace_url: self.trace_url.map(|url| url.to_string()),
+            summaries: self.summaries,
+            environment: environment_label(metadata),
+            limiter: RateLimiter::new(self.rate_limit.0, self.rate_limit.1),
+            dispatcher: HttpDispatcher::new("teams")
+                .with_limits(self.queue_capacity, self.drop_policy),
+        })
+    }
+}
+
+struct TeamsBattery {
+    webhook_url: String,
+    trace_url: Option<String>,
+    summaries: bool,
+    environment: Option<String>,
+    limiter: RateLimiter,
+    dispatcher: HttpDispatcher,
+}
+
+impl Battery for TeamsBattery {
+    fn record_envelope(&self, envelope: &Envelope) {
+        let card = match &envelope.payload {
+            EnvelopePayload::Error { .. } if self.limiter.allow() => {
+                let trace_url = self.trace_url.as_ref().and_then(|template| {
+                    MetricExemplar::current()
+                        .map(|trace| template.replace("{trace_id}", &trace.trace_id_hex()))
+                });
+
+                build_card(envelope, self.environment.as_deref(), trace_url.as_deref())
+            }
+            EnvelopePayload::Event { name, .. } if self.summaries && name == "session_summary" => {
+                build_card(envelope, self.environment.as_deref(), None)
+            }
+            _ => return,
+        };
+
+        let body = json!({
+            "type": "message",
+            "attachments": [{
+                "contentType": "application/vnd.microsoft.card.adaptive",
+                "content": card,
+            }],
+        })
+        .to_string();
+
+        let url = self.webhook_url.clone();
+        self.dispatcher
+            .dispatch(envelope.importance, move |client| {
+                client
+                    .post(url)
+                    .header("Content-Type", "application/json")
+                    .body(body)
+            });
+    }
+
+    fn shutdown(&self) {
+        self.dispatcher.shutdown();
+    }
+}
+
+fn build_card(
+    envelope: &Envelope,
+    environment: Option<&str>,
+    trace_url: Option<&str>,
+) -> serde_json::Value {
+    let mut facts = vec![json!({ "title": "Version", "value": envelope.version })];
+    if let Some(environment) = environment {
+        facts.push(json!({ "title": "Environment", "value": environment }));
+    }
+
+    let (title, color, text) = match &envelope.payload {
+        EnvelopePayload::Error {
+            message,
+            causes,
+            fields,
+            ..
+        } => {
+            facts.extend(
+                fields
+                    .iter()
+                    .map(|(key, value)| json!({ "title": key, "value": value })),
+            );
+
+            let mut text = message.clone();
+            for cause in causes {
+                text.push_str(&format!("\n\nCaused by: {cause}"));
+            }
+
+            (format!("Error in {}", envelope.service), "attention", text)
+        }
+        EnvelopePayload::Event { properties, .. } => {
+            facts.extend(
+                properties
+                    .iter()
+                    .map(|(key, value)| json!({ "title": key, "value": value.to_string() })),
+            );
+
+            (
+                format!("{} has shut down", envelope.service),
+                "default",
+                String::new(),
+            )
+        }
+        _ => (envelope.summary(), "default", String::new()),
+    };
+
+    let mut card = json!({
+        "$schema": "http://adaptivecards.io/schemas/adaptive-card.json",
+        "type": "AdaptiveCard",
+        "version": "1.4",
+        "body": [
+            { "type": "TextBlock", "text": title, "weight": "bolder", "size": "medium", "color": color, "wrap": true },
+            { "type": "TextBlock", "text": text, "wrap": true },
+            { "type": "FactSet", "facts": facts },
+        ],
+    });
+
+    if let Some(url) = trace_url {
+        card["actions"] = json!([{ "type": "Action.OpenUrl", "title": "View trace", "url": url }]);
+    }
+
+    card
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Session;
+
+    #[test]
+    fn error_card() {
+        let metadata = Session::new("example", "0.0.1");
+        let envelope = Envelope::new(
+            &metadata,
+            EnvelopePayload::Error {
+                message: "Failed to connect".into(),
+                causes: vec![],
+                fields: Default::default(),
+                backtrace: None,
+            },
+        );
+
+        let card = build_card(&envelope, None, Some("https://example.com/trace/1"));
+        assert_eq!(card["body"][0]["text"], "Error in example");
+        assert_eq!(card["actions"][0]["url"], "https://example.com/trace/1");
+    }
+}
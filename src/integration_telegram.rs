@@ -0,0 +1,348 @@
+use std::{
+    borrow::Cow,
+    fmt::Write,
+    sync::{atomic::AtomicBool, Arc, Mutex, PoisonError},
+    time::Duration,
+};
+
+use chrono::{NaiveDate, Timelike};
+use serde_json::json;
+
+use crate::{
+    dispatcher::HttpDispatcher,
+    notify::{environment_label, escape_html, RateLimiter},
+    queue::DEFAULT_QUEUE_CAPACITY,
+    Battery, BatteryBuilder, DropPolicy, Envelope, EnvelopePayload, Importance, Metadata,
+};
+
+/// The maximum length of an error message included in an alert, keeping it within Telegram's 4096 character limit.
+const MAX_MESSAGE_LENGTH: usize = 3000;
+
+/// A [Telegram](https://telegram.org) integration which sends error alerts, and a daily summary of your
+/// application's usage, to a chat through the [Bot API](https://core.telegram.org/bots/api).
+///
+/// <div class="warning">
+///
+/// This integration requires the `telegram` feature to be enabled.
+///
+/// </div>
+///
+/// Messages are sent by the bot whose token you provide, to the chat (or channel) with the provided ID, which
+/// the bot must have been added to. At most 10 error alerts are sent per minute. The usage summary reports the
+/// number of errors, custom events and page views recorded on the previous day, and is sent once the first
+/// telemetry is recorded on a new day (so it is only sent by long-running applications).
+///
+/// Silent hours may be configured using [`Telegram::with_silent_hours`], during which messages are still sent
+/// but are delivered without a notification sound.
+///
+/// ## Example
+/// ```no_run
+/// use tracing_batteries::{Session, Telegram};
+///
+/// let session = Session::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+///   .with_battery(Telegram::new("123456:your-bot-token", "-1001234567890")
+///     .with_silent_hours(22, 7));
+///
+/// session.shutdown();
+/// ```
+pub struct Telegram {
+    bot_token: Cow<'static, str>,
+    chat_id: Cow<'static, str>,
+    daily_summary: bool,
+    silent_hours: Option<(u32, u32)>,
+    rate_limit: (usize, Duration),
+    queue_capacity: usize,
+    drop_policy: DropPolicy,
+}
+
+impl Telegram {
+    /// Configures the Telegram integration to send messages to the provided chat using the bot's token.
+    pub fn new<T: Into<Cow<'static, str>>, C: Into<Cow<'static, str>>>(
+        bot_token: T,
+        chat_id: C,
+    ) -> Self {
+        Self {
+            bot_token: bot_token.into(),
+            chat_id: chat_id.into(),
+            daily_summary: true,
+            silent_hours: None,
+            rate_limit: (10, Duration::from_secs(60)),
+            queue_capacity: DEFAULT_QUEUE_CAPACITY,
+            drop_policy: DropPolicy::default(),
+        }
+    }
+
+    /// Disables the daily usage summary, so that only error alerts are sent.
+    pub fn without_daily_summary(self) -> Self {
+        Self {
+            daily_summary: false,
+            ..self
+        }
+    }
+
+    /// Configures the hours (in local time) between which messages are delivered silently, e.g. `(22, 7)` to
+    /// avoid notifications overnight.
+    pub fn with_silent_hours(self, start_hour: u32, end_hour: u32) -> Self {
+        Self {
+            silent_hours: Some((start_hour % 24, end_hour % 24)),
+            ..self
+        }
+    }
+
+    /// Configures the maximum number of error alerts which are sent within the provided window.
+    pub fn with_rate_limit(self, limit: usize, window: Duration) -> Self {
+        Self {
+            rate_limit: (limit, window),
+            ..self
+        }
+    }
+
+    /// Configures the maximum number of messages which may be waiting to be sent (10,000 by default), and which are
+    /// dropped when the Bot API falls behind a burst of errors.
+    pub fn with_queue_limits(self, capacity: usize, policy: DropPolicy) -> Self {
+        Self {
+            queue_capacity: capacity,
+            drop_policy: policy,
+            ..self
+        }
+    }
+}
+
+impl BatteryBuilder for Telegram {
+    fn setup(self, metadata: &Metadata, _enabled: Arc<AtomicBool>) -> Box<dyn Battery> {
+        Box::new(TelegramBattery {
+            url: format!("https://api.telegram.org/bot{}/sendMessage", self.bot_token),
+            chat_id: self.chat_id.to_string(),
+            silent_hours: self.silent_hours,
+            environment: environment_label(metadata),
+            usage: self
+                .daily_summary
+                .then(|| Mutex::new(DailyUsage::new(chrono::Local::now().date_naive()))),
+            limiter: RateLimiter::new(self.rate_limit.0, self.rate_limit.1),
+            dispatcher: HttpDispatcher::new("telegram")
+                .with_limits(self.queue_capacity, self.drop_policy),
+        })
+    }
+}
+
+struct TelegramBattery {
+    url: String,
+    chat_id: String,
+    silent_hours: Option<(u32, u32)>,
+    environment: Option<String>,
+    usage: Option<Mutex<DailyUsage>>,
+    limiter: RateLimiter,
+    dispatcher: HttpDispatcher,
+}
+
+impl TelegramBattery {
+    fn send(&self, importance: Importance, text: String) {
+        let silent = self
+            .silent_hours
+            .is_some_and(|hours| is_silent(chrono::Local::now().hour(), hours));
+
+        let url = self.url.clone();
+        let body = json!({
+            "chat_id": self.chat_id,
+            "text": text,
+            "parse_mode": "HTML",
+            "disable_notification": silent,
+            "link_preview_options": { "is_disabled": true },
+        })
+        .to_string();
+
+        self.dispatcher.dispatch(importance, move |client| {
+            client
+                .post(url)
+                .header("Content-Type", "application/json")
+                .body(body)
+        });
+    }
+}
+
+impl Battery for TelegramBattery {
+    fn record_envelope(&self, envelope: &Envelope) {
+        if let Some(usage) = &self.usage {
+            let completed = usage
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .record(envelope, chrono::Local::now().date_naive());
+
+            if let Some(completed) = completed {
+                self.send(
+                    Importance::Normal,
+                    format_summary(&completed, &envelope.service, self.environment.as_deref()),
+                );
+            }
+        }
+
+        if matches!(envelope.payload, EnvelopePayload::Error { .. }) && self.limiter.allow() {
+            if let Some(alert) = format_alert(envelope, self.environment.as_deref()) {
+                self.send(envelope.importance, alert);
+            }
+        }
+    }
+
+    fn shutdown(&self) {
+        self.dispatcher.shutdown();
+    }
+}
+
+/// Counts the telemetry recorded on a single day, for inclusion in the daily usage summary.
+#[derive(Debug, Clone, PartialEq)]
+struct DailyUsage {
+    day: NaiveDate,
+    errors: usize,
+    events: usize,
+    pages: usize,
+}
+
+impl DailyUsage {
+    fn new(day: NaiveDate) -> Self {
+        Self {
+            day,
+            errors: 0,
+            events: 0,
+            pages: 0,
+        }
+    }
+
+    /// Records an envelope against the current day, returning the previous day's usage once the day changes.
+    fn record(&mut self, envelope: &Envelope, today: NaiveDate) -> Option<DailyUsage> {
+        let completed = (today != self.day)
+            .then(|| std::mem::replace(self, DailyUsage::new(today)))
+            .filter(|usage| usage.errors + usage.events + usage.pages > 0);
+
+        match envelope.payload {
+            EnvelopePayload::Error { .. } => self.errors += 1,
+            EnvelopePayload::Event { .. } => self.events += 1,
+            EnvelopePayload::PageView { .. } => self.pages += 1,
+            EnvelopePayload::Sealed { .. } | EnvelopePayload::Unknown => {}
+        }
+
+        completed
+    }
+}
+
+fn heading(emoji: &str, title: &str, version: &str, environment: Option<&str>) -> String {
+    let mut heading = format!(
+        "{emoji} <b>{}</b> <i>v{}",
+        escape_html(title),
+        escape_html(version)
+    );
+    if let Some(environment) = environment {
+        write!(heading, " · {}", escape_html(environment)).ok();
+    }
+    heading.push_str("</i>\n");
+    heading
+}
+
+/// Formats the HTML alert sent for an error [`Envelope`].
+fn format_alert(envelope: &Envelope, environment: Option<&str>) -> Option<String> {
+    let EnvelopePayload::Error {
+        message,
+        causes,
+        fields,
+        ..
+    } = &envelope.payload
+    else {
+        return None;
+    };
+
+    let message: String = message.chars().take(MAX_MESSAGE_LENGTH).collect();
+    let mut text = heading(
+        "🚨",
+        &format!("Error in {}", envelope.service),
+        &envelope.version,
+        environment,
+    );
+    writeln!(text, "\n{}", escape_html(&message)).ok();
+    for cause in causes.iter().take(5) {
+        writeln!(text, "↳ {}", escape_html(cause)).ok();
+    }
+    for (key, value) in fields.iter().take(10) {
+        writeln!(
+            text,
+            "<b>{}</b>: <code>{}</code>",
+            escape_html(key),
+            escape_html(value)
+        )
+        .ok();
+    }
+
+    Some(text.trim_end().to_string())
+}
+
+/// Formats the HTML summary sent for a completed day's usage.
+fn format_summary(usage: &DailyUsage, service: &str, environment: Option<&str>) -> String {
+    let mut text = format!("📊 <b>Daily usage for {}</b>", escape_html(service));
+    if let Some(environment) = environment {
+        write!(text, " <i>{}</i>", escape_html(environment)).ok();
+    }
+    write!(
+        text,
+        "\n{}\n\nErrors: <b>{}</b>\nEvents: <b>{}</b>\nPage views: <b>{}</b>",
+        usage.day.format("%A, %-d %B %Y"),
+        usage.errors,
+        usage.events,
+        usage.pages
+    )
+    .ok();
+    text
+}
+
+/// Determines whether the provided hour falls within the silent hours, which may wrap around midnight.
+fn is_silent(hour: u32, (start, end): (u32, u32)) -> bool {
+    if start <= end {
+        (start..end).contains(&hour)
+    } else {
+        hour >= start || hour < end
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Session;
+
+    #[test]
+    fn alerts_and_summaries() {
+        let metadata = Session::new("example", "0.0.1");
+        let error = Envelope::new(
+            &metadata,
+            EnvelopePayload::Error {
+                message: "Failed to parse <config>".into(),
+                causes: vec!["Unexpected EOF".into()],
+                fields: Default::default(),
+                backtrace: None,
+            },
+        );
+
+        assert_eq!(
+            format_alert(&error, Some("production")).unwrap(),
+            "🚨 <b>Error in example</b> <i>v0.0.1 · production</i>\n\nFailed to parse &lt;config&gt;\n↳ Unexpected EOF"
+        );
+
+        let monday = NaiveDate::from_ymd_opt(2024, 3, 4).unwrap();
+        let tuesday = monday.succ_opt().unwrap();
+        let mut usage = DailyUsage::new(monday);
+        assert_eq!(usage.record(&error, monday), None);
+        assert_eq!(usage.record(&error, monday), None);
+
+        let completed = usage.record(&error, tuesday).unwrap();
+        assert_eq!(completed.day, monday);
+        assert_eq!(completed.errors, 2);
+        assert_eq!(usage.errors, 1);
+        assert!(format_summary(&completed, "example", None)
+            .contains("Monday, 4 March 2024\n\nErrors: <b>2</b>"));
+    }
+
+    #[test]
+    fn silent_hours() {
+        assert!(is_silent(23, (22, 7)));
+        assert!(is_silent(3, (22, 7)));
+        assert!(!is_silent(7, (22, 7)));
+        assert!(is_silent(13, (12, 14)));
+        assert!(!is_silent(14, (12, 14)));
+    }
+}
@@ -0,0 +1,158 @@
+use std::{borrow::Cow, time::Duration};
+
+use crate::{feature_flags::PolledFlags, FlagProvider, Str};
+
+/// A [`FlagProvider`] which reports the toggles evaluated by [Unleash](https://www.getunleash.io) for the
+/// configured context.
+///
+/// <div class="warning">
+///
+/// This integration requires the `unleash` feature to be enabled.
+///
+/// </div>
+///
+/// Toggles are evaluated using Unleash's Frontend API (or Unleash Edge), authenticated with a frontend token.
+/// Evaluations are refreshed in the background every minute (see [`Unleash::with_interval`]) once the provider
+/// has been registered using [`Session::with_flag_provider`](crate::Session::with_flag_provider). Since the
+/// Frontend API only returns enabled toggles, each toggle is reported using the name of its enabled variant,
+/// or `true` if it has none.
+///
+/// ## Example
+/// ```no_run
+/// use tracing_batteries::{Session, Sentry, Unleash};
+///
+/// let session = Session::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+///   .with_battery(Sentry::new("https://yourdsn@sentry.example.com"))
+///   .with_flag_provider(Unleash::new("https://unleash.example.com/api/frontend", "your-frontend-token")
+///     .with_context("userId", "alice"));
+///
+/// session.shutdown();
+/// ```
+pub struct Unleash {
+    url: Cow<'static, str>,
+    token: Cow<'static, str>,
+    context: Vec<(String, String)>,
+    interval: Duration,
+    flags: PolledFlags,
+}
+
+impl Unleash {
+    /// Creates a provider which evaluates toggles using the Frontend API at the provided URL.
+    pub fn new<U: Into<Cow<'static, str>>, T: Into<Cow<'static, str>>>(url: U, token: T) -> Self {
+        Self {
+            url: url.into(),
+            token: token.into(),
+            context: Vec::new(),
+            interval: Duration::from_secs(60),
+            flags: PolledFlags::new(),
+        }
+    }
+
+    /// Adds a field (such as `userId`, `sessionId` or a custom property) to the context which toggles are
+    /// evaluated for.
+    pub fn with_context<K: Into<String>, V: Into<String>>(mut self, key: K, value: V) -> Self {
+        self.context.push((key.into(), value.into()));
+        self
+    }
+
+    /// Configures how frequently toggles are re-evaluated.
+    pub fn with_interval(self, interval: Duration) -> Self {
+        Self { interval, ..self }
+    }
+}
+
+impl FlagProvider for Unleash {
+    fn flags(&self) -> Vec<(Str, Str)> {
+        self.flags.get()
+    }
+
+    fn start(&self) {
+        let url = self.url.to_string();
+        let token = self.token.to_string();
+        let context = self.context.clone();
+
+        self.flags.start("unleash", self.interval, move || {
+            let body = reqwest::blocking::Client::builder()
+                .timeout(Duration::from_secs(10))
+                .build()
+                .and_then(|client| {
+                    client
+                        .get(&url)
+                        .header("Authorization", &token)
+                        .query(&context)
+                        .send()
+                })
+                .and_then(|response| response.error_for_status())
+                .and_then(|response| response.text())
+                .map_err(|err| {
+                    tracing::debug!(error = %err, "Failed to evaluate Unleash toggles.");
+                })
+                .ok()?;
+
+            parse_toggles(&body)
+        });
+    }
+}
+
+/// Parses the response of the Frontend API, which lists the toggles that are enabled for the context.
+fn parse_toggles(body: &str) -> Option<Vec<(Str, Str)>> {
+    let response: serde_json::Value = serde_json::from_str(body).ok()?;
+
+    let mut flags: Vec<(Str, Str)> = response
+        .get("toggles")?
+        .as_array()?
+        .iter()
+        .filter(|toggle| toggle.get("enabled").and_then(|e| e.as_bool()) != Some(false))
+        .filter_map(|toggle| {
+            let name = toggle.get("name")?.as_str()?;
+            let variant = toggle
+                .get("variant")
+                .filter(|variant| variant.get("enabled").and_then(|e| e.as_bool()) == Some(true))
+                .and_then(|variant| variant.get("name")?.as_str())
+                .unwrap_or("true");
+
+            Some((name.into(), variant.into()))
+        })
+        .collect();
+    flags.sort();
+
+    Some(flags)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_toggles() {
+        let flags = parse_toggles(
+            r#"{
+                "toggles": [
+                    {
+                        "name": "new-checkout",
+                        "enabled": true,
+                        "variant": { "name": "disabled", "enabled": false },
+                        "impressionData": false
+                    },
+                    {
+                        "name": "banner",
+                        "enabled": true,
+                        "variant": { "name": "blue", "enabled": true },
+                        "impressionData": false
+                    }
+                ]
+            }"#,
+        )
+        .expect("the toggles should be parsed");
+
+        assert_eq!(
+            flags,
+            vec![
+                ("banner".into(), "blue".into()),
+                ("new-checkout".into(), "true".into()),
+            ]
+        );
+
+        assert!(parse_toggles("{}").is_none());
+    }
+}
@@ -0,0 +1,111 @@
+use std::{
+    borrow::Cow,
+    sync::{atomic::AtomicBool, Arc},
+};
+
+use crate::{Battery, BatteryBuilder, Metadata, OpenTelemetry, OpenTelemetryProtocol};
+
+/// An [Uptrace](https://uptrace.dev) integration which configures the [`OpenTelemetry`] integration
+/// from an Uptrace DSN.
+///
+/// <div class="warning">
+///
+/// This integration requires the `uptrace` feature to be enabled.
+///
+/// </div>
+///
+/// The OTLP endpoint is derived from the DSN (using `https://otlp.uptrace.dev` for Uptrace Cloud, or
+/// the DSN's host for self-hosted installations) and the DSN is attached to each export using the
+/// `uptrace-dsn` header. The DSN may also be provided using the `UPTRACE_DSN` environment variable.
+///
+/// ## Example
+/// ```no_run
+/// use tracing_batteries::{Session, Uptrace};
+///
+/// let session = Session::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+///   .with_battery(Uptrace::new("https://<secret>@api.uptrace.dev?grpc=4317"));
+///
+/// session.shutdown();
+/// ```
+pub struct Uptrace {
+    dsn: Cow<'static, str>,
+    configure: Box<dyn FnOnce(OpenTelemetry) -> OpenTelemetry>,
+}
+
+impl Uptrace {
+    /// Configures the Uptrace integration using the provided DSN.
+    pub fn new<D: Into<Cow<'static, str>>>(dsn: D) -> Self {
+        Self {
+            dsn: std::env::var("UPTRACE_DSN")
+                .map(Cow::Owned)
+                .unwrap_or_else(|_| dsn.into()),
+            configure: Box::new(|otel| otel),
+        }
+    }
+
+    /// Customizes the underlying [`OpenTelemetry`] integration used to export traces and metrics.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use tracing_batteries::{Uptrace, OpenTelemetryLevel};
+    ///
+    /// Uptrace::new("https://<secret>@api.uptrace.dev?grpc=4317")
+    ///   .with_opentelemetry(|otel| otel.with_default_level(OpenTelemetryLevel::DEBUG));
+    /// ```
+    pub fn with_opentelemetry<F>(self, configure: F) -> Self
+    where
+        F: FnOnce(OpenTelemetry) -> OpenTelemetry + 'static,
+    {
+        Self {
+            configure: Box::new(configure),
+            ..self
+        }
+    }
+}
+
+impl BatteryBuilder for Uptrace {
+    fn setup(self, metadata: &Metadata, enabled: Arc<AtomicBool>) -> Box<dyn Battery> {
+        let otel = match parse_endpoint(&self.dsn) {
+            Some(endpoint) => OpenTelemetry::new("")
+                .with_endpoint(endpoint)
+                .with_protocol(OpenTelemetryProtocol::HttpBinary)
+                .with_header("uptrace-dsn", self.dsn.clone()),
+            None => OpenTelemetry::new("").with_endpoint(""),
+        };
+
+        (self.configure)(otel).setup(metadata, enabled)
+    }
+}
+
+/// Derives the OTLP/HTTP endpoint from an Uptrace DSN of the form `scheme://token@host[:port][/project][?grpc=port]`.
+fn parse_endpoint(dsn: &str) -> Option<String> {
+    let (scheme, rest) = dsn.split_once("://")?;
+    let (_token, rest) = rest.split_once('@')?;
+    let host = rest
+        .split(['/', '?'])
+        .next()
+        .filter(|host| !host.is_empty())?;
+
+    match host {
+        "uptrace.dev" | "api.uptrace.dev" => Some("https://otlp.uptrace.dev".to_string()),
+        host => Some(format!("{scheme}://{host}")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn endpoint_from_dsn() {
+        assert_eq!(
+            parse_endpoint("https://secret@api.uptrace.dev?grpc=4317").as_deref(),
+            Some("https://otlp.uptrace.dev")
+        );
+        assert_eq!(
+            parse_endpoint("http://project2_secret@localhost:14318?grpc=14317").as_deref(),
+            Some("http://localhost:14318")
+        );
+        assert_eq!(parse_endpoint("not-a-dsn"), None);
+    }
+}
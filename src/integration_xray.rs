@@ -0,0 +1,132 @@
+use std::{
+    borrow::Cow,
+    sync::{atomic::AtomicBool, Arc},
+};
+
+use crate::{Battery, BatteryBuilder, Metadata, OpenTelemetry};
+
+/// An [AWS X-Ray](https://aws.amazon.com/xray/) integration which configures the [`OpenTelemetry`] integration
+/// to generate trace IDs that X-Ray accepts, propagate trace context using the `X-Amzn-Trace-Id` header, and
+/// export spans to the X-Ray daemon (or an OTLP collector with an X-Ray exporter, such as the ADOT collector).
+///
+/// <div class="warning">
+///
+/// This integration requires the `xray` feature to be enabled.
+///
+/// </div>
+///
+/// By default, spans are emitted as segment documents to the X-Ray daemon over UDP, whose address is read from the
+/// `AWS_XRAY_DAEMON_ADDRESS` environment variable (as configured by Lambda and the ECS daemon sidecar), defaulting
+/// to `127.0.0.1:2000`. The daemon does not accept metrics, so use [`XRay::with_collector`] to export through an
+/// OTLP collector instead if you also need metrics.
+///
+/// Trace context is propagated using both the W3C `traceparent` and X-Ray `X-Amzn-Trace-Id` headers, and the
+/// `_X_AMZN_TRACE_ID` environment variable which Lambda sets is used as the parent of the application's spans.
+///
+/// ## Example
+/// ```no_run
+/// use tracing_batteries::{Session, XRay};
+///
+/// let session = Session::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+///   .with_battery(XRay::new());
+///
+/// session.shutdown();
+/// ```
+pub struct XRay {
+    daemon: Option<Cow<'static, str>>,
+    collector: Cow<'static, str>,
+    configure: Box<dyn FnOnce(OpenTelemetry) -> OpenTelemetry>,
+}
+
+impl XRay {
+    /// Configures the X-Ray integration to emit spans to the local X-Ray daemon.
+    pub fn new() -> Self {
+        Self {
+            daemon: Some(
+                std::env::var("AWS_XRAY_DAEMON_ADDRESS")
+                    .map(|address| Cow::Owned(daemon_address(&address).to_string()))
+                    .unwrap_or(Cow::Borrowed("127.0.0.1:2000")),
+            ),
+            collector: "localhost:4317".into(),
+            configure: Box::new(|otel| otel),
+        }
+    }
+
+    /// Overrides the address (`host:port`) of the X-Ray daemon which spans are emitted to.
+    pub fn with_daemon<A: Into<Cow<'static, str>>>(self, address: A) -> Self {
+        Self {
+            daemon: Some(address.into()),
+            ..self
+        }
+    }
+
+    /// Exports traces and metrics to an OTLP collector (such as the AWS Distro for OpenTelemetry collector) instead
+    /// of the X-Ray daemon. The endpoint should correspond to the protocol configured on the underlying
+    /// [`OpenTelemetry`] integration, which uses gRPC by default (e.g. `localhost:4317`).
+    pub fn with_collector<E: Into<Cow<'static, str>>>(self, endpoint: E) -> Self {
+        Self {
+            daemon: None,
+            collector: endpoint.into(),
+            ..self
+        }
+    }
+
+    /// Customizes the underlying [`OpenTelemetry`] integration used to export spans.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use tracing_batteries::{XRay, OpenTelemetryLevel};
+    ///
+    /// XRay::new()
+    ///   .with_opentelemetry(|otel| otel.with_default_level(OpenTelemetryLevel::DEBUG));
+    /// ```
+    pub fn with_opentelemetry<F>(self, configure: F) -> Self
+    where
+        F: FnOnce(OpenTelemetry) -> OpenTelemetry + 'static,
+    {
+        Self {
+            configure: Box::new(configure),
+            ..self
+        }
+    }
+}
+
+impl Default for XRay {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BatteryBuilder for XRay {
+    fn setup(self, metadata: &Metadata, enabled: Arc<AtomicBool>) -> Box<dyn Battery> {
+        let otel = match self.daemon {
+            Some(daemon) => OpenTelemetry::new("").with_xray(Some(daemon.into_owned())),
+            None => OpenTelemetry::new(self.collector).with_xray(None),
+        };
+
+        (self.configure)(otel).setup(metadata, enabled)
+    }
+}
+
+/// Extracts the UDP address from the value of the `AWS_XRAY_DAEMON_ADDRESS` environment variable, which may either
+/// be a plain `host:port` address or separate TCP and UDP addresses (`tcp:host:port udp:host:port`).
+fn daemon_address(address: &str) -> &str {
+    address
+        .split_whitespace()
+        .find_map(|address| address.strip_prefix("udp:"))
+        .unwrap_or(address.trim())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_daemon_address() {
+        assert_eq!(daemon_address("169.254.79.129:2000"), "169.254.79.129:2000");
+        assert_eq!(
+            daemon_address("tcp:127.0.0.1:2000 udp:127.0.0.2:2001"),
+            "127.0.0.2:2001"
+        );
+    }
+}
@@ -0,0 +1,181 @@
+use std::{
+    borrow::Borrow,
+    collections::HashSet,
+    fmt::Display,
+    ops::Deref,
+    sync::{Arc, Mutex, OnceLock, PoisonError},
+};
+
+use serde::{Deserialize, Serialize};
+
+/// The maximum number of distinct strings held by the intern pool, beyond which new strings are allocated
+/// individually rather than being added to the pool.
+const MAX_INTERNED: usize = 4096;
+
+/// An immutable, reference counted string which is cheap to clone and share between batteries.
+///
+/// Values which are repeated across many pieces of telemetry, such as the service name, version and context of
+/// each [`Envelope`](crate::Envelope), are represented using [`Str`] so that recording telemetry doesn't allocate
+/// a fresh copy of them each time. Strings created using [`Str::intern`] are shared with every other interned
+/// string holding the same value.
+///
+/// [`Str`] dereferences to a [`str`], and may be compared with (or created from) the standard string types.
+///
+/// ## Example
+/// ```
+/// use tracing_batteries::Str;
+///
+/// let environment = Str::intern("production");
+/// assert_eq!(environment, "production");
+/// assert_eq!(environment.len(), 10);
+/// ```
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Str(Arc<str>);
+
+impl Str {
+    /// Returns a [`Str`] holding the provided value, sharing the allocation of any previously interned string
+    /// with the same value.
+    pub fn intern(value: &str) -> Self {
+        static POOL: OnceLock<Mutex<HashSet<Arc<str>>>> = OnceLock::new();
+
+        let mut pool = POOL
+            .get_or_init(Default::default)
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        if let Some(existing) = pool.get(value) {
+            return Self(existing.clone());
+        }
+
+        let value: Arc<str> = Arc::from(value);
+        if pool.len() < MAX_INTERNED {
+            pool.insert(value.clone());
+        }
+
+        Self(value)
+    }
+
+    /// Returns the string as a [`str`].
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Deref for Str {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for Str {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Borrow<str> for Str {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Display for Str {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::fmt::Debug for Str {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Debug::fmt(&*self.0, f)
+    }
+}
+
+impl Default for Str {
+    fn default() -> Self {
+        Self::intern("")
+    }
+}
+
+impl From<&str> for Str {
+    fn from(value: &str) -> Self {
+        Self(Arc::from(value))
+    }
+}
+
+impl From<String> for Str {
+    fn from(value: String) -> Self {
+        Self(Arc::from(value))
+    }
+}
+
+impl From<&String> for Str {
+    fn from(value: &String) -> Self {
+        Self(Arc::from(value.as_str()))
+    }
+}
+
+impl From<Str> for String {
+    fn from(value: Str) -> Self {
+        value.0.to_string()
+    }
+}
+
+impl PartialEq<str> for Str {
+    fn eq(&self, other: &str) -> bool {
+        &*self.0 == other
+    }
+}
+
+impl PartialEq<&str> for Str {
+    fn eq(&self, other: &&str) -> bool {
+        &*self.0 == *other
+    }
+}
+
+impl PartialEq<String> for Str {
+    fn eq(&self, other: &String) -> bool {
+        *self.0 == **other
+    }
+}
+
+impl PartialEq<Str> for &str {
+    fn eq(&self, other: &Str) -> bool {
+        **self == *other.0
+    }
+}
+
+impl PartialEq<Str> for String {
+    fn eq(&self, other: &Str) -> bool {
+        **self == *other.0
+    }
+}
+
+impl Serialize for Str {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for Str {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(Self::intern(&String::deserialize(deserializer)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shares_interned_strings() {
+        let first = Str::intern("billing");
+        let second = Str::intern("billing");
+        assert!(Arc::ptr_eq(&first.0, &second.0));
+        assert_eq!(first, "billing");
+        assert_eq!(first.to_string(), "billing");
+        assert_eq!(serde_json::to_string(&first).unwrap(), "\"billing\"");
+    }
+}
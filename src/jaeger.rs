@@ -0,0 +1,396 @@
+use std::{
+    future::Future,
+    net::{ToSocketAddrs, UdpSocket},
+    pin::Pin,
+    time::SystemTime,
+};
+
+use opentelemetry::{trace::SpanKind, KeyValue, Value};
+use opentelemetry_sdk::export::trace::{ExportResult, SpanData, SpanExporter};
+
+// Thrift compact protocol type identifiers.
+const BOOL_TRUE: u8 = 1;
+const BOOL_FALSE: u8 = 2;
+const I32: u8 = 5;
+const I64: u8 = 6;
+const DOUBLE: u8 = 7;
+const BINARY: u8 = 8;
+const LIST: u8 = 9;
+const STRUCT: u8 = 12;
+
+// Jaeger tag value types.
+const TAG_STRING: i32 = 0;
+const TAG_DOUBLE: i32 = 1;
+const TAG_BOOL: i32 = 2;
+const TAG_LONG: i32 = 3;
+
+/// The number of bytes reserved in each packet for the message header, batch framing, and span list header.
+const PACKET_OVERHEAD: usize = 32;
+
+/// A minimal writer for the Thrift compact protocol, supporting the subset of types used by Jaeger's agent API.
+struct CompactWriter {
+    buf: Vec<u8>,
+    last_field: Vec<i16>,
+}
+
+impl CompactWriter {
+    fn new() -> Self {
+        Self {
+            buf: Vec::new(),
+            last_field: vec![0],
+        }
+    }
+
+    fn varint(&mut self, mut value: u64) {
+        while value >= 0x80 {
+            self.buf.push((value as u8) | 0x80);
+            value >>= 7;
+        }
+        self.buf.push(value as u8);
+    }
+
+    fn field(&mut self, id: i16, kind: u8) {
+        let last = self.last_field.last_mut().expect("a struct must be open");
+        let delta = id - *last;
+        if (1..=15).contains(&delta) {
+            self.buf.push(((delta as u8) << 4) | kind);
+        } else {
+            self.buf.push(kind);
+            self.varint(((id << 1) ^ (id >> 15)) as u16 as u64);
+        }
+        *last = id;
+    }
+
+    fn begin_struct(&mut self) {
+        self.last_field.push(0);
+    }
+
+    fn end_struct(&mut self) {
+        self.buf.push(0);
+        self.last_field.pop();
+    }
+
+    fn i32(&mut self, id: i16, value: i32) {
+        self.field(id, I32);
+        self.varint(((value << 1) ^ (value >> 31)) as u32 as u64);
+    }
+
+    fn i64(&mut self, id: i16, value: i64) {
+        self.field(id, I64);
+        self.varint(((value << 1) ^ (value >> 63)) as u64);
+    }
+
+    fn double(&mut self, id: i16, value: f64) {
+        self.field(id, DOUBLE);
+        self.buf.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn bool(&mut self, id: i16, value: bool) {
+        self.field(id, if value { BOOL_TRUE } else { BOOL_FALSE });
+    }
+
+    fn string(&mut self, id: i16, value: &str) {
+        self.field(id, BINARY);
+        self.varint(value.len() as u64);
+        self.buf.extend_from_slice(value.as_bytes());
+    }
+
+    fn list(&mut self, id: i16, kind: u8, size: usize) {
+        self.field(id, LIST);
+        self.list_header(kind, size);
+    }
+
+    fn list_header(&mut self, kind: u8, size: usize) {
+        if size < 15 {
+            self.buf.push(((size as u8) << 4) | kind);
+        } else {
+            self.buf.push(0xF0 | kind);
+            self.varint(size as u64);
+        }
+    }
+
+    fn tag(&mut self, attribute: &KeyValue) {
+        self.begin_struct();
+        self.string(1, attribute.key.as_str());
+        match &attribute.value {
+            Value::Bool(value) => {
+                self.i32(2, TAG_BOOL);
+                self.bool(5, *value);
+            }
+            Value::I64(value) => {
+                self.i32(2, TAG_LONG);
+                self.i64(6, *value);
+            }
+            Value::F64(value) => {
+                self.i32(2, TAG_DOUBLE);
+                self.double(4, *value);
+            }
+            value => {
+                self.i32(2, TAG_STRING);
+                self.string(3, &value.as_str());
+            }
+        }
+        self.end_struct();
+    }
+
+    fn tags(&mut self, id: i16, tags: &[KeyValue]) {
+        self.list(id, STRUCT, tags.len());
+        for tag in tags {
+            self.tag(tag);
+        }
+    }
+}
+
+fn micros(time: SystemTime) -> i64 {
+    time.duration_since(SystemTime::UNIX_EPOCH)
+        .map(|duration| duration.as_micros() as i64)
+        .unwrap_or_default()
+}
+
+fn trace_id(id: opentelemetry::trace::TraceId) -> (i64, i64) {
+    let id = u128::from_be_bytes(id.to_bytes());
+    ((id as u64) as i64, ((id >> 64) as u64) as i64)
+}
+
+fn span_id(id: opentelemetry::trace::SpanId) -> i64 {
+    u64::from_be_bytes(id.to_bytes()) as i64
+}
+
+/// Encodes a span as a Jaeger `Span` struct.
+fn encode_span(span: &SpanData) -> Vec<u8> {
+    let mut writer = CompactWriter::new();
+    let (trace_id_low, trace_id_high) = trace_id(span.span_context.trace_id());
+
+    writer.i64(1, trace_id_low);
+    writer.i64(2, trace_id_high);
+    writer.i64(3, span_id(span.span_context.span_id()));
+    writer.i64(4, span_id(span.parent_span_id));
+    writer.string(5, &span.name);
+
+    if !span.links.links.is_empty() {
+        writer.list(6, STRUCT, span.links.links.len());
+        for link in span.links.links.iter() {
+            let (low, high) = trace_id(link.span_context.trace_id());
+            writer.begin_struct();
+            writer.i32(1, 1); // FOLLOWS_FROM
+            writer.i64(2, low);
+            writer.i64(3, high);
+            writer.i64(4, span_id(link.span_context.span_id()));
+            writer.end_struct();
+        }
+    }
+
+    writer.i32(7, span.span_context.trace_flags().to_u8() as i32);
+    writer.i64(8, micros(span.start_time));
+    writer.i64(
+        9,
+        span.end_time
+            .duration_since(span.start_time)
+            .map(|duration| duration.as_micros() as i64)
+            .unwrap_or_default(),
+    );
+
+    let mut tags = span.attributes.clone();
+    match span.span_kind {
+        SpanKind::Client => tags.push(KeyValue::new("span.kind", "client")),
+        SpanKind::Server => tags.push(KeyValue::new("span.kind", "server")),
+        SpanKind::Producer => tags.push(KeyValue::new("span.kind", "producer")),
+        SpanKind::Consumer => tags.push(KeyValue::new("span.kind", "consumer")),
+        SpanKind::Internal => {}
+    }
+    match &span.status {
+        opentelemetry::trace::Status::Error { description } => {
+            tags.push(KeyValue::new("error", true));
+            tags.push(KeyValue::new("otel.status_code", "ERROR"));
+            tags.push(KeyValue::new(
+                "otel.status_description",
+                description.to_string(),
+            ));
+        }
+        opentelemetry::trace::Status::Ok => tags.push(KeyValue::new("otel.status_code", "OK")),
+        opentelemetry::trace::Status::Unset => {}
+    }
+    writer.tags(10, &tags);
+
+    if !span.events.events.is_empty() {
+        writer.list(11, STRUCT, span.events.events.len());
+        for event in span.events.events.iter() {
+            let mut fields = vec![KeyValue::new("event", event.name.clone())];
+            fields.extend(event.attributes.iter().cloned());
+
+            writer.begin_struct();
+            writer.i64(1, micros(event.timestamp));
+            writer.tags(2, &fields);
+            writer.end_struct();
+        }
+    }
+
+    writer.end_struct();
+    writer.buf
+}
+
+/// Encodes the Jaeger `Process` struct describing the service which emitted the spans.
+fn encode_process(resource: &opentelemetry_sdk::Resource) -> Vec<u8> {
+    let service = resource
+        .get(opentelemetry::Key::new("service.name"))
+        .map(|value| value.as_str().to_string())
+        .unwrap_or_else(|| "unknown_service".to_string());
+
+    let tags: Vec<KeyValue> = resource
+        .iter()
+        .filter(|(key, _)| key.as_str() != "service.name")
+        .map(|(key, value)| KeyValue::new(key.clone(), value.clone()))
+        .collect();
+
+    let mut writer = CompactWriter::new();
+    writer.string(1, &service);
+    writer.tags(2, &tags);
+    writer.end_struct();
+    writer.buf
+}
+
+/// Wraps a process and set of encoded spans in an `Agent.emitBatch` oneway message.
+fn encode_batch(process: &[u8], spans: &[Vec<u8>], seq_id: i32) -> Vec<u8> {
+    let mut writer = CompactWriter::new();
+    writer.buf.push(0x82); // protocol ID
+    writer.buf.push((4 << 5) | 1); // ONEWAY, version 1
+    writer.varint(seq_id as u32 as u64);
+    writer.varint("emitBatch".len() as u64);
+    writer.buf.extend_from_slice(b"emitBatch");
+
+    // emitBatch_args { 1: Batch batch }
+    writer.field(1, STRUCT);
+    writer.begin_struct();
+
+    writer.field(1, STRUCT);
+    writer.buf.extend_from_slice(process);
+
+    writer.list(2, STRUCT, spans.len());
+    for span in spans {
+        writer.buf.extend_from_slice(span);
+    }
+
+    writer.end_struct();
+    writer.end_struct();
+    writer.buf
+}
+
+/// Splits encoded spans into groups which fit within the maximum packet size once wrapped in a batch,
+/// dropping any span which is too large to ever fit.
+fn split_packets(
+    process_len: usize,
+    spans: Vec<Vec<u8>>,
+    max_packet_size: usize,
+) -> Vec<Vec<Vec<u8>>> {
+    let budget = max_packet_size.saturating_sub(process_len + PACKET_OVERHEAD);
+
+    let mut packets = Vec::new();
+    let mut current = Vec::new();
+    let mut size = 0;
+    for span in spans {
+        if span.len() > budget {
+            continue;
+        }
+
+        if size + span.len() > budget {
+            packets.push(std::mem::take(&mut current));
+            size = 0;
+        }
+
+        size += span.len();
+        current.push(span);
+    }
+
+    if !current.is_empty() {
+        packets.push(current);
+    }
+
+    packets
+}
+
+/// A [`SpanExporter`] which emits spans to a Jaeger agent using the Thrift compact protocol over UDP.
+#[derive(Debug)]
+pub(crate) struct JaegerAgentExporter {
+    socket: UdpSocket,
+    max_packet_size: usize,
+    process: Vec<u8>,
+    seq_id: i32,
+}
+
+impl JaegerAgentExporter {
+    pub fn new(agent: &str, max_packet_size: usize) -> std::io::Result<Self> {
+        let address = agent.to_socket_addrs()?.next().ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("could not resolve '{agent}'"),
+            )
+        })?;
+
+        let socket = if address.is_ipv4() {
+            UdpSocket::bind("0.0.0.0:0")?
+        } else {
+            UdpSocket::bind("[::]:0")?
+        };
+        socket.connect(address)?;
+
+        Ok(Self {
+            socket,
+            max_packet_size,
+            process: encode_process(&opentelemetry_sdk::Resource::empty()),
+            seq_id: 0,
+        })
+    }
+}
+
+impl SpanExporter for JaegerAgentExporter {
+    fn export(
+        &mut self,
+        batch: Vec<SpanData>,
+    ) -> Pin<Box<dyn Future<Output = ExportResult> + Send + 'static>> {
+        let spans = batch.iter().map(encode_span).collect();
+
+        let mut result = Ok(());
+        for spans in split_packets(self.process.len(), spans, self.max_packet_size) {
+            self.seq_id = self.seq_id.wrapping_add(1);
+            let packet = encode_batch(&self.process, &spans, self.seq_id);
+            if let Err(err) = self.socket.send(&packet) {
+                result = Err(opentelemetry::trace::TraceError::Other(Box::new(err)));
+            }
+        }
+
+        Box::pin(std::future::ready(result))
+    }
+
+    fn set_resource(&mut self, resource: &opentelemetry_sdk::Resource) {
+        self.process = encode_process(resource);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compact_encoding() {
+        let mut writer = CompactWriter::new();
+        writer.i32(1, -1);
+        writer.string(2, "ab");
+        writer.i64(20, 150);
+        writer.end_struct();
+
+        assert_eq!(
+            writer.buf,
+            vec![0x15, 0x01, 0x18, 0x02, b'a', b'b', 0x06, 0x28, 0xAC, 0x02, 0x00]
+        );
+    }
+
+    #[test]
+    fn packet_splitting() {
+        let spans = vec![vec![0; 40], vec![0; 40], vec![0; 200], vec![0; 40]];
+        let packets = split_packets(10, spans, 130);
+
+        assert_eq!(packets.len(), 2);
+        assert_eq!(packets[0].len(), 2);
+        assert_eq!(packets[1].len(), 1);
+    }
+}
@@ -0,0 +1,86 @@
+use std::sync::{Mutex, PoisonError};
+
+use tracing_subscriber::{reload, Layer, Registry};
+
+type ExtensionLayer = Box<dyn Layer<Registry> + Send + Sync>;
+
+enum Extensions {
+    Pending(Vec<ExtensionLayer>),
+    Installed(reload::Handle<Vec<ExtensionLayer>, Registry>),
+}
+
+static EXTENSIONS: Mutex<Extensions> = Mutex::new(Extensions::Pending(Vec::new()));
+static VERBOSE_EXTENSIONS: Mutex<Extensions> = Mutex::new(Extensions::Pending(Vec::new()));
+
+/// Attaches a [`Layer`] to the tracing subscriber installed by this library.
+///
+/// This allows batteries which need to observe spans or events (but which are not responsible for
+/// installing the subscriber themselves) to register a layer regardless of the order in which they
+/// were attached to the session. Layers attached before the subscriber is installed are held until
+/// it is, while layers attached afterwards are added to the running subscriber.
+///
+/// Extension layers should not use per-layer filters, since these cannot be registered once the
+/// subscriber has been installed.
+pub(crate) fn attach_layer<L>(layer: L)
+where
+    L: Layer<Registry> + Send + Sync + 'static,
+{
+    attach(&EXTENSIONS, layer)
+}
+
+/// Attaches a [`Layer`] which observes every span and event, regardless of the level configured for the
+/// tracing subscriber installed by this library, in the same way as [`attach_layer`].
+///
+/// Verbose layers should provide a [`Layer::max_level_hint`] which is as restrictive as possible, since
+/// the subscriber will otherwise need to construct events which none of its other layers are interested in.
+pub(crate) fn attach_verbose_layer<L>(layer: L)
+where
+    L: Layer<Registry> + Send + Sync + 'static,
+{
+    attach(&VERBOSE_EXTENSIONS, layer)
+}
+
+fn attach<L>(extensions: &Mutex<Extensions>, layer: L)
+where
+    L: Layer<Registry> + Send + Sync + 'static,
+{
+    let mut extensions = extensions.lock().unwrap_or_else(PoisonError::into_inner);
+    match &mut *extensions {
+        Extensions::Pending(layers) => layers.push(Box::new(layer)),
+        Extensions::Installed(handle) => {
+            handle.modify(|layers| layers.push(Box::new(layer))).ok();
+        }
+    }
+}
+
+/// Builds the layer which hosts any extensions registered using [`attach_layer`], to be installed by
+/// the battery which is responsible for initializing the tracing subscriber.
+#[cfg_attr(not(feature = "opentelemetry"), allow(dead_code))]
+pub(crate) fn extension_layer() -> reload::Layer<Vec<ExtensionLayer>, Registry> {
+    install(&EXTENSIONS)
+}
+
+/// Builds the layer which hosts any extensions registered using [`attach_verbose_layer`], to be installed
+/// (without any level filter) by the battery which is responsible for initializing the tracing subscriber.
+#[cfg_attr(not(feature = "opentelemetry"), allow(dead_code))]
+pub(crate) fn verbose_extension_layer() -> reload::Layer<Vec<ExtensionLayer>, Registry> {
+    install(&VERBOSE_EXTENSIONS)
+}
+
+fn install(extensions: &Mutex<Extensions>) -> reload::Layer<Vec<ExtensionLayer>, Registry> {
+    let mut extensions = extensions.lock().unwrap_or_else(PoisonError::into_inner);
+    let layers = match std::mem::replace(&mut *extensions, Extensions::Pending(Vec::new())) {
+        Extensions::Pending(layers) => layers,
+        Extensions::Installed(handle) => {
+            let mut layers = Vec::new();
+            handle
+                .modify(|installed| layers = std::mem::take(installed))
+                .ok();
+            layers
+        }
+    };
+
+    let (layer, handle) = reload::Layer::new(layers);
+    *extensions = Extensions::Installed(handle);
+    layer
+}
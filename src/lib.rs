@@ -1,17 +1,380 @@
-use std::sync::atomic::AtomicBool;
-use std::sync::Arc;
+use std::backtrace::{Backtrace, BacktraceStatus};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, OnceLock, PoisonError, RwLock, RwLockReadGuard, Weak};
 use std::{borrow::Cow, collections::HashMap};
 
+mod artifacts;
+#[cfg(feature = "opentelemetry")]
+mod auth_provider;
+#[cfg(any(
+    feature = "cloudwatch",
+    feature = "opensearch",
+    feature = "s3",
+    feature = "sigv4"
+))]
+mod aws;
+#[cfg(any(
+    feature = "betterstack",
+    feature = "cloudwatch",
+    feature = "goatcounter",
+    feature = "influxdb",
+    feature = "opensearch",
+    feature = "s3",
+    feature = "sumologic"
+))]
+mod batcher;
+mod bounded;
+mod clock;
+#[cfg(feature = "opentelemetry")]
+mod coalesce;
+mod command;
+mod command_line;
+#[cfg(feature = "clap")]
+mod command_usage;
+#[cfg(feature = "opentelemetry")]
+mod compression;
+#[cfg(feature = "connectivity")]
+mod connectivity;
+mod deferred;
+#[cfg(any(feature = "countly", feature = "ga4"))]
+mod device_id;
+pub mod diagnostics;
+#[cfg(any(
+    feature = "apprise",
+    feature = "countly",
+    feature = "discord",
+    feature = "ga4",
+    feature = "grafana-cloud",
+    feature = "matrix",
+    feature = "newrelic",
+    feature = "ntfy",
+    feature = "openobserve",
+    feature = "pirsch",
+    feature = "s3",
+    feature = "teams",
+    feature = "telegram"
+))]
+mod dispatcher;
+mod dynamic_context;
+#[cfg(feature = "encryption")]
+mod encrypted;
+#[cfg(feature = "opentelemetry")]
+mod enrichment;
+mod envelope;
+mod environment;
+#[cfg(feature = "opentelemetry")]
+mod event_metrics;
+mod event_registry;
+#[cfg(feature = "opentelemetry")]
+mod failover;
+mod feature_flags;
+mod features;
+mod filtered;
+mod flight_recorder;
+#[cfg(feature = "opentelemetry")]
+mod guardrails;
+mod hash;
+#[cfg(feature = "host-metrics")]
+mod host_metrics;
+#[cfg(feature = "actix-web")]
+mod integration_actix;
+#[cfg(feature = "apprise")]
+mod integration_apprise;
+#[cfg(feature = "betterstack")]
+mod integration_betterstack;
+#[cfg(feature = "cloudwatch")]
+mod integration_cloudwatch;
+#[cfg(feature = "color-eyre")]
+mod integration_color_eyre;
+#[cfg(feature = "coralogix")]
+mod integration_coralogix;
+#[cfg(feature = "countly")]
+mod integration_countly;
+#[cfg(feature = "desktop-notify")]
+mod integration_desktop_notify;
+#[cfg(feature = "discord")]
+mod integration_discord;
+#[cfg(feature = "dynatrace")]
+mod integration_dynatrace;
+#[cfg(feature = "ga4")]
+mod integration_ga4;
+#[cfg(feature = "github")]
+mod integration_github;
+#[cfg(feature = "goatcounter")]
+mod integration_goatcounter;
+#[cfg(feature = "grafana-cloud")]
+mod integration_grafana;
+#[cfg(feature = "graphite")]
+mod integration_graphite;
+#[cfg(feature = "graphql")]
+mod integration_graphql;
+#[cfg(feature = "influxdb")]
+mod integration_influxdb;
+#[cfg(feature = "instana")]
+mod integration_instana;
+#[cfg(feature = "jaeger")]
+mod integration_jaeger;
+#[cfg(feature = "launchdarkly")]
+mod integration_launchdarkly;
+#[cfg(feature = "logstash")]
+mod integration_logstash;
+#[cfg(feature = "matrix")]
+mod integration_matrix;
+#[cfg(feature = "newrelic")]
+mod integration_newrelic;
+#[cfg(feature = "ntfy")]
+mod integration_ntfy;
+#[cfg(feature = "openobserve")]
+mod integration_openobserve;
+#[cfg(feature = "opensearch")]
+mod integration_opensearch;
 #[cfg(feature = "opentelemetry")]
 mod integration_opentelemetry;
+#[cfg(feature = "papertrail")]
+mod integration_papertrail;
+#[cfg(feature = "pirsch")]
+mod integration_pirsch;
+#[cfg(feature = "postgres")]
+mod integration_postgres;
+#[cfg(feature = "redis")]
+mod integration_redis;
+#[cfg(feature = "s3")]
+mod integration_s3;
 #[cfg(feature = "sentry")]
 mod integration_sentry;
+#[cfg(feature = "signoz")]
+mod integration_signoz;
+#[cfg(feature = "socket")]
+mod integration_socket;
+#[cfg(feature = "sumologic")]
+mod integration_sumologic;
+#[cfg(feature = "teams")]
+mod integration_teams;
+#[cfg(feature = "telegram")]
+mod integration_telegram;
+#[cfg(feature = "unleash")]
+mod integration_unleash;
+#[cfg(feature = "uptrace")]
+mod integration_uptrace;
+#[cfg(feature = "xray")]
+mod integration_xray;
+mod interned;
+#[cfg(feature = "jaeger")]
+mod jaeger;
+mod layers;
+#[cfg(feature = "opentelemetry")]
+mod limits;
+mod mapped;
+#[cfg(feature = "opentelemetry")]
+mod messaging;
+mod metrics;
+#[cfg(any(
+    feature = "apprise",
+    feature = "desktop-notify",
+    feature = "discord",
+    feature = "matrix",
+    feature = "ntfy",
+    feature = "teams",
+    feature = "telegram"
+))]
+mod notify;
+#[cfg(feature = "opentelemetry")]
+mod output_capture;
+#[cfg(debug_assertions)]
+mod overhead;
+mod preflight;
 pub mod prelude;
+#[cfg(feature = "opentelemetry")]
+mod propagation;
+#[cfg(any(
+    feature = "apprise",
+    feature = "betterstack",
+    feature = "cloudwatch",
+    feature = "countly",
+    feature = "discord",
+    feature = "ga4",
+    feature = "goatcounter",
+    feature = "grafana-cloud",
+    feature = "graphite",
+    feature = "influxdb",
+    feature = "logstash",
+    feature = "matrix",
+    feature = "newrelic",
+    feature = "ntfy",
+    feature = "openobserve",
+    feature = "opensearch",
+    feature = "papertrail",
+    feature = "pirsch",
+    feature = "postgres",
+    feature = "redis",
+    feature = "s3",
+    feature = "socket",
+    feature = "sumologic",
+    feature = "teams",
+    feature = "telegram"
+))]
+mod queue;
+#[cfg(any(feature = "logstash", feature = "postgres", feature = "redis"))]
+mod reconnect;
+mod redacted;
+#[cfg(feature = "opentelemetry")]
+mod resource_detection;
+mod sampled;
+mod sampling;
+#[cfg(feature = "tokio")]
+mod scheduled;
+mod semconv;
+mod slo;
+#[cfg(any(feature = "postgres", feature = "redis", feature = "s3"))]
+mod spans;
+mod summary;
+#[cfg(feature = "opentelemetry")]
+mod suspend;
+mod tenant;
+#[cfg(test)]
+mod test_support;
+mod threads;
+mod timer;
+#[cfg(any(feature = "logstash", feature = "papertrail"))]
+mod tls;
+mod validated;
+#[cfg(feature = "version-check")]
+mod version_check;
+#[cfg(feature = "watchdog")]
+mod watchdog;
+mod worker;
+#[cfg(feature = "xray")]
+mod xray;
 
+pub use artifacts::*;
+pub use bounded::*;
+pub use command::*;
+#[cfg(feature = "connectivity")]
+pub use connectivity::*;
+pub use deferred::*;
+pub use dynamic_context::{DynamicContext, DynamicContextGuard, WithDynamicContext};
+#[cfg(feature = "encryption")]
+pub use encrypted::*;
+pub use envelope::*;
+pub use event_registry::*;
+pub use feature_flags::{EnvFlags, FlagProvider};
+pub use filtered::*;
+pub use flight_recorder::FlightRecorder;
+#[cfg(feature = "host-metrics")]
+pub use host_metrics::*;
+#[cfg(feature = "actix-web")]
+pub use integration_actix::*;
+#[cfg(feature = "apprise")]
+pub use integration_apprise::*;
+#[cfg(feature = "betterstack")]
+pub use integration_betterstack::*;
+#[cfg(feature = "cloudwatch")]
+pub use integration_cloudwatch::*;
+#[cfg(feature = "coralogix")]
+pub use integration_coralogix::*;
+#[cfg(feature = "countly")]
+pub use integration_countly::*;
+#[cfg(feature = "desktop-notify")]
+pub use integration_desktop_notify::*;
+#[cfg(feature = "discord")]
+pub use integration_discord::*;
+#[cfg(feature = "dynatrace")]
+pub use integration_dynatrace::*;
+#[cfg(feature = "ga4")]
+pub use integration_ga4::*;
+#[cfg(feature = "github")]
+pub use integration_github::*;
+#[cfg(feature = "goatcounter")]
+pub use integration_goatcounter::*;
+#[cfg(feature = "grafana-cloud")]
+pub use integration_grafana::*;
+#[cfg(feature = "graphite")]
+pub use integration_graphite::*;
+#[cfg(feature = "graphql")]
+pub use integration_graphql::*;
+#[cfg(feature = "influxdb")]
+pub use integration_influxdb::*;
+#[cfg(feature = "instana")]
+pub use integration_instana::*;
+#[cfg(feature = "jaeger")]
+pub use integration_jaeger::*;
+#[cfg(feature = "launchdarkly")]
+pub use integration_launchdarkly::*;
+#[cfg(feature = "logstash")]
+pub use integration_logstash::*;
+#[cfg(feature = "matrix")]
+pub use integration_matrix::*;
+#[cfg(feature = "newrelic")]
+pub use integration_newrelic::*;
+#[cfg(feature = "ntfy")]
+pub use integration_ntfy::*;
+#[cfg(feature = "openobserve")]
+pub use integration_openobserve::*;
+#[cfg(feature = "opensearch")]
+pub use integration_opensearch::*;
 #[cfg(feature = "opentelemetry")]
 pub use integration_opentelemetry::*;
+#[cfg(feature = "papertrail")]
+pub use integration_papertrail::*;
+#[cfg(feature = "pirsch")]
+pub use integration_pirsch::*;
+#[cfg(feature = "postgres")]
+pub use integration_postgres::*;
+#[cfg(feature = "redis")]
+pub use integration_redis::*;
+#[cfg(feature = "s3")]
+pub use integration_s3::*;
 #[cfg(feature = "sentry")]
 pub use integration_sentry::*;
+#[cfg(feature = "signoz")]
+pub use integration_signoz::*;
+#[cfg(feature = "socket")]
+pub use integration_socket::*;
+#[cfg(feature = "sumologic")]
+pub use integration_sumologic::*;
+#[cfg(feature = "teams")]
+pub use integration_teams::*;
+#[cfg(feature = "telegram")]
+pub use integration_telegram::*;
+#[cfg(feature = "unleash")]
+pub use integration_unleash::*;
+#[cfg(feature = "uptrace")]
+pub use integration_uptrace::*;
+#[cfg(feature = "xray")]
+pub use integration_xray::*;
+pub use interned::*;
+pub use mapped::*;
+#[cfg(feature = "opentelemetry")]
+pub use messaging::*;
+pub use metrics::*;
+#[cfg(any(
+    feature = "apprise",
+    feature = "desktop-notify",
+    feature = "discord",
+    feature = "matrix",
+    feature = "ntfy",
+    feature = "teams",
+    feature = "telegram"
+))]
+pub use notify::NotificationSeverity;
+#[cfg(feature = "opentelemetry")]
+pub use output_capture::*;
+#[cfg(debug_assertions)]
+pub use overhead::*;
+pub use preflight::*;
+pub use redacted::*;
+pub use sampled::*;
+pub use sampling::SamplingDecision;
+pub use semconv::*;
+pub use slo::*;
+pub use tenant::*;
+pub use threads::*;
+pub use timer::*;
+pub use validated::*;
+#[cfg(feature = "version-check")]
+pub use version_check::*;
+#[cfg(feature = "watchdog")]
+pub use watchdog::*;
 
 /// A trait which is implemented by integration builders, allowing them to be used with this library.
 ///
@@ -40,17 +403,79 @@ pub trait BatteryBuilder {
 /// notifications about errors and to be shut down when the process is exiting.
 ///
 /// This trait should be implemented on the type which is returned by the [`BatteryBuilder::setup`] method.
-pub trait Battery {
+/// Batteries may be shared across threads by clones of the [`Session`], so they must be both [`Send`] and [`Sync`].
+pub trait Battery: Send + Sync {
     /// Called whenever the [`Session::record_error`] method is called, allowing the integration
     /// to report an error to the telemetry system through the appropriate mechanism.
     fn record_error(&self, _error: &dyn std::error::Error) {}
 
+    /// Called whenever an error is recorded along with additional [`ErrorContext`], allowing the
+    /// integration to attach that context to the report it sends to the telemetry system.
+    ///
+    /// By default this will fall back to [`Battery::record_error`], discarding the context.
+    fn record_error_with(&self, error: &dyn std::error::Error, _context: &ErrorContext) {
+        self.record_error(error)
+    }
+
+    /// Called for every [`Envelope`] emitted by the [`Session`], including errors, custom events, and page views.
+    ///
+    /// This method is intended for integrations which ship telemetry to generic transports (such as webhooks,
+    /// message queues, or files) and should serialize the envelope in a stable format. Envelopes are only
+    /// emitted while the session is enabled.
+    fn record_envelope(&self, _envelope: &Envelope) {}
+
+    /// Called whenever a measurement is recorded using one of the [`Counter`], [`Gauge`], or [`Histogram`]
+    /// handles, allowing integrations which support metrics to forward it to their backend.
+    ///
+    /// Metrics are only recorded while the session is enabled.
+    fn record_metric(&self, _metric: &Metric) {}
+
+    /// Called by [`Session::with_metric_aggregation`] to determine whether the integration can report weighted
+    /// histogram measurements (see [`Metric::count`]).
+    ///
+    /// Integrations whose backend cannot record an observation more than once should return `false`, in which case
+    /// histogram observations are delivered to them individually rather than being aggregated.
+    fn accepts_weighted_histograms(&self) -> bool {
+        true
+    }
+
+    /// Called whenever a file is attached using [`Session::attach_artifact`], allowing integrations which support
+    /// attachments to deliver it alongside the application's error reports.
+    ///
+    /// Integrations should return `true` if they have accepted the artifact. When no battery accepts an artifact,
+    /// the session saves it to the local disk instead.
+    fn record_artifact(&self, _artifact: &Artifact) -> bool {
+        false
+    }
+
+    /// Called by [`Session::release`] once any telemetry held by [`Deferred`] batteries may be sent, allowing
+    /// combinators which wrap another battery to forward the notification to it.
+    fn release(&self) {}
+
     /// Called when the process is exiting, allowing the integration to perform any necessary cleanup
     /// and shutdown operations.
     ///
     /// There is no guarantee that the application will not attempt to use the integration after this
     /// method is called, so if necessary the integration should ensure that it can handle this safely.
     fn shutdown(&self) {}
+
+    /// Called once the battery has been attached to a [`Session`], providing a [`WeakSession`] which
+    /// the integration may retain if it needs to report telemetry through the session's other batteries
+    /// (for example, to raise an alert when a threshold is exceeded).
+    fn attached(&self, _session: WeakSession) {}
+
+    /// Called by [`Session::validate`] to check that the integration is correctly configured, for example by
+    /// confirming that its endpoint can be reached, without emitting any telemetry.
+    fn validate(&self) -> Vec<ValidationCheck> {
+        Vec::new()
+    }
+
+    /// Called by [`Session::update_available`] to determine whether the integration has discovered a newer
+    /// release of the application.
+    #[cfg(feature = "version-check")]
+    fn update_available(&self) -> Option<AvailableUpdate> {
+        None
+    }
 }
 
 /// A telemetry session which is used to manage the lifecycle of the telemetry subsystem.
@@ -61,10 +486,23 @@ pub trait Battery {
 /// You can attach new batteries to the service at any time, however it is expected that these
 /// are attached at the beginning of the application's lifecycle and the session is retained until
 /// the application is ready to exit.
+///
+/// Sessions are cheap to clone, allowing them to be shared with request middleware and background
+/// tasks which need to report errors. Clones share the same batteries (including any which are attached
+/// after the clone was made), so calling [`Session::shutdown`] on any clone will shut down the batteries
+/// for all of them.
+#[derive(Clone)]
 pub struct Session {
-    metadata: Metadata,
-    batteries: Vec<Box<dyn Battery>>,
+    metadata: Arc<Metadata>,
+    header: Arc<envelope::EnvelopeHeader>,
+    batteries: Arc<RwLock<Vec<Arc<dyn Battery>>>>,
     enabled: Arc<AtomicBool>,
+    error_backtraces: Arc<AtomicBool>,
+    stats: Arc<summary::SessionStats>,
+    features: Arc<features::FeatureUsage>,
+    aggregation: Arc<metrics::MetricAggregation>,
+    events: Arc<event_registry::EventRegistry>,
+    recorder: Arc<OnceLock<Arc<flight_recorder::FlightRecording>>>,
 }
 
 impl Session {
@@ -119,24 +557,243 @@ impl Session {
     /// }
     /// ```
     pub fn record_error<'a, E: std::error::Error>(&self, exception: &'a E) -> &'a E {
-        for battery in &self.batteries {
-            battery.record_error(exception);
+        self.record_error_with(exception, std::iter::empty::<(&'static str, String)>())
+    }
+
+    /// Records that an error has occurred within the application, attaching the provided key-value
+    /// context to the report sent to each registered battery.
+    ///
+    /// This behaves in the same way as [`Session::record_error`], however the provided context will
+    /// be attached to the error report by each battery (for example, as `extra` fields in Sentry or as
+    /// attributes on the OpenTelemetry exception event).
+    ///
+    /// ## Example
+    /// ```no_run
+    /// use tracing_batteries::{Session, Sentry};
+    ///
+    /// let session = Session::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+    ///   .with_battery(Sentry::new("https://yourdsn@sentry.example.com"));
+    ///
+    /// let attempt = 3;
+    /// match std::fs::read_to_string("nonexistent-file.txt") {
+    ///  Ok(_) => {}
+    ///  Err(e) => eprintln!("{:?}", session.record_error_with(&e, [
+    ///    ("path", "nonexistent-file.txt".to_string()),
+    ///    ("attempt", attempt.to_string()),
+    ///  ])),
+    /// }
+    /// ```
+    pub fn record_error_with<'a, E, I, V>(&self, exception: &'a E, context: I) -> &'a E
+    where
+        E: std::error::Error,
+        I: IntoIterator<Item = (&'static str, V)>,
+        V: ToString,
+    {
+        if !self.is_enabled() {
+            return exception;
+        }
+
+        self.record_error_with_breadcrumbs(
+            exception,
+            context
+                .into_iter()
+                .map(|(key, value)| (key, value.to_string()))
+                .collect(),
+            Vec::new(),
+        )
+    }
+
+    pub(crate) fn record_error_with_breadcrumbs<'a, E: std::error::Error>(
+        &self,
+        exception: &'a E,
+        mut fields: HashMap<&'static str, String>,
+        mut breadcrumbs: Vec<Breadcrumb>,
+    ) -> &'a E {
+        if !self.is_enabled() {
+            return exception;
+        }
+
+        self.stats.errors.fetch_add(1, Ordering::Relaxed);
+
+        for (key, value) in dynamic_context::current() {
+            fields.entry(key).or_insert_with(|| value.to_string());
         }
 
+        for (key, value) in feature_flags::current() {
+            if let Some(field) = feature_flags::field_name(&key) {
+                fields.entry(field).or_insert_with(|| value.to_string());
+            }
+        }
+
+        if let Some(recorder) = self.recorder.get() {
+            let mut recorded = recorder.take(exception, &fields);
+            if !recorded.is_empty() {
+                recorded.append(&mut breadcrumbs);
+                breadcrumbs = recorded;
+            }
+        }
+
+        let context = ErrorContext {
+            fields,
+            breadcrumbs,
+            backtrace: if self.error_backtraces.load(Ordering::Relaxed) {
+                Some(Backtrace::capture())
+                    .filter(|backtrace| backtrace.status() == BacktraceStatus::Captured)
+                    .map(Arc::new)
+            } else {
+                None
+            },
+        };
+
+        for battery in self.batteries().iter() {
+            battery.record_error_with(exception, &context);
+        }
+
+        self.record_envelope(|| {
+            let mut causes = Vec::new();
+            let mut source = exception.source();
+            while let Some(cause) = source {
+                causes.push(cause.to_string());
+                source = cause.source();
+            }
+
+            EnvelopePayload::Error {
+                message: exception.to_string(),
+                causes,
+                fields: context
+                    .fields
+                    .iter()
+                    .map(|(key, value)| (key.to_string(), value.clone()))
+                    .collect(),
+                backtrace: context.backtrace.as_ref().map(|b| b.to_string()),
+            }
+        });
+
         exception
     }
 
+    /// Records a custom event with the provided properties, reporting it to any registered batteries.
+    ///
+    /// Custom events are delivered to batteries as an [`Envelope`] and are most commonly used to
+    /// report product analytics, such as the usage of a specific feature.
+    ///
+    /// ## Example
+    /// ```no_run
+    /// use tracing_batteries::{Session, Sentry};
+    ///
+    /// let session = Session::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+    ///   .with_battery(Sentry::new("https://yourdsn@sentry.example.com"));
+    ///
+    /// session.record_event("export_pdf", [("pages", 3)]);
+    /// ```
+    pub fn record_event<N, I, K, V>(&self, name: N, properties: I)
+    where
+        N: Into<String>,
+        I: IntoIterator<Item = (K, V)>,
+        K: Into<String>,
+        V: Into<serde_json::Value>,
+    {
+        if !self.is_enabled() {
+            return;
+        }
+
+        let name = name.into();
+        let properties = properties
+            .into_iter()
+            .map(|(key, value)| (key.into(), value.into()))
+            .collect();
+        if !self.events.allows(&name, &properties) {
+            return;
+        }
+
+        self.stats.events.fetch_add(1, Ordering::Relaxed);
+        self.record_envelope(|| EnvelopePayload::Event { name, properties });
+    }
+
+    /// Records that the user has navigated to a new page (or screen) within the application.
+    ///
+    /// ## Example
+    /// ```no_run
+    /// use tracing_batteries::{Session, Sentry};
+    ///
+    /// let session = Session::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+    ///   .with_battery(Sentry::new("https://yourdsn@sentry.example.com"));
+    ///
+    /// session.record_new_page("/settings");
+    /// ```
+    pub fn record_new_page<P: Into<String>>(&self, page: P) {
+        if !self.is_enabled() {
+            return;
+        }
+
+        self.stats.pages.fetch_add(1, Ordering::Relaxed);
+        self.record_envelope(|| EnvelopePayload::PageView { page: page.into() });
+    }
+
+    pub(crate) fn record_envelope<F: FnOnce() -> EnvelopePayload>(&self, payload: F) {
+        if !self.is_enabled() {
+            return;
+        }
+
+        let mut envelope = Envelope::with_header(&self.metadata, &self.header, payload());
+        for (key, value) in dynamic_context::current() {
+            if !envelope.context.contains_key(key) {
+                envelope.context.insert(Str::from(key), value);
+            }
+        }
+
+        for battery in self.batteries().iter() {
+            battery.record_envelope(&envelope);
+        }
+    }
+
     /// Shuts down the telemetry session, ensuring that all batteries are properly cleaned up.
     ///
     /// This method should be called when the application is ready to exit, ensuring that all
     /// telemetry data has been flushed and that all resources have been released. It is a
     /// blocking operation and will not return until all batteries have been shut down.
+    ///
+    /// Before the batteries are shut down, a `session_summary` custom event is recorded which reports
+    /// the session's duration along with the number of errors, page views, and custom events which were
+    /// recorded. Use [`Session::shutdown_with_exit_code`] to include your application's exit code.
     pub fn shutdown(self) {
-        for battery in self.batteries {
+        self.flush_feature_usage();
+        self.flush_metrics();
+        self.record_summary(None);
+        self.shutdown_batteries();
+    }
+
+    fn shutdown_batteries(&self) {
+        for battery in self.batteries().iter() {
             battery.shutdown();
         }
     }
 
+    /// Creates a [`WeakSession`] which refers to this session without keeping its batteries alive.
+    ///
+    /// This is primarily intended for use by batteries which need to report telemetry through the
+    /// session's other batteries, since holding a strong [`Session`] would create a reference cycle.
+    pub fn downgrade(&self) -> WeakSession {
+        WeakSession {
+            metadata: self.metadata.clone(),
+            header: self.header.clone(),
+            batteries: Arc::downgrade(&self.batteries),
+            enabled: self.enabled.clone(),
+            error_backtraces: self.error_backtraces.clone(),
+            stats: self.stats.clone(),
+            features: self.features.clone(),
+            aggregation: self.aggregation.clone(),
+            events: self.events.clone(),
+            recorder: self.recorder.clone(),
+        }
+    }
+
+    fn batteries(&self) -> RwLockReadGuard<'_, Vec<Arc<dyn Battery>>> {
+        self.batteries
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
     /// Returns a reference to the [`AtomicBool`] which is used to control the enabled state of the telemetry session.
     ///
     /// This method is intended to be used by the hosting application to either check, or modify, whether the telemetry
@@ -168,18 +825,143 @@ impl Session {
     pub fn enable(&self) -> Arc<AtomicBool> {
         self.enabled.clone()
     }
+
+    /// Returns whether the telemetry session is currently reporting data to its batteries.
+    ///
+    /// While the session is disabled, recording errors, events, page views, metrics, and feature usage returns
+    /// immediately without formatting, allocating, or contacting any battery. This method may be used to skip
+    /// expensive work (such as formatting properties) which is only needed when telemetry is being reported.
+    ///
+    /// ## Example
+    /// ```no_run
+    /// use tracing_batteries::{Session, Sentry};
+    ///
+    /// let session = Session::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+    ///   .with_battery(Sentry::new("https://yourdsn@sentry.example.com"));
+    ///
+    /// if session.is_enabled() {
+    ///   let pages = std::fs::read_dir(".").map(|entries| entries.count()).unwrap_or_default();
+    ///   session.record_event("export_pdf", [("pages", pages)]);
+    /// }
+    ///
+    /// session.shutdown();
+    /// ```
+    #[inline]
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
 }
 
 impl Session {
     /// Attaches a new battery to the telemetry session, integrating the requested telemetry
     /// provider into the application.
-    pub fn with_battery<B: BatteryBuilder>(mut self, builder: B) -> Self {
-        let battery = builder.setup(&self.metadata, self.enabled.clone());
-        self.batteries.push(battery);
+    pub fn with_battery<B: BatteryBuilder>(self, builder: B) -> Self {
+        #[cfg(debug_assertions)]
+        let battery: Arc<dyn Battery> = {
+            let battery = overhead::TimedBattery::new(std::any::type_name::<B>(), || {
+                builder.setup(&self.metadata, self.enabled.clone())
+            });
+            self.stats
+                .overhead
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .push(battery.timings());
+            Arc::new(battery)
+        };
+
+        #[cfg(not(debug_assertions))]
+        let battery: Arc<dyn Battery> =
+            Arc::from(builder.setup(&self.metadata, self.enabled.clone()));
+        self.batteries
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(battery.clone());
+        battery.attached(self.downgrade());
+        self
+    }
+
+    /// Configures whether a [`Backtrace`] should be captured whenever an error is recorded.
+    ///
+    /// When enabled, calls to [`Session::record_error`] and [`Session::record_error_with`] will capture
+    /// a backtrace and forward it to each battery through the [`ErrorContext`]. Backtraces are captured
+    /// using [`Backtrace::capture`], so they will only be collected when the `RUST_BACKTRACE` or
+    /// `RUST_LIB_BACKTRACE` environment variables permit it.
+    ///
+    /// ## Example
+    /// ```no_run
+    /// use tracing_batteries::{Session, Sentry};
+    ///
+    /// let session = Session::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+    ///   .with_battery(Sentry::new("https://yourdsn@sentry.example.com"))
+    ///   .with_error_backtraces(true);
+    ///
+    /// session.shutdown();
+    /// ```
+    pub fn with_error_backtraces(self, enabled: bool) -> Self {
+        self.error_backtraces.store(enabled, Ordering::Relaxed);
         self
     }
 }
 
+/// A weak reference to a [`Session`], created using [`Session::downgrade`].
+///
+/// Weak sessions do not keep the session's batteries alive and must be upgraded into a [`Session`]
+/// before they can be used to report telemetry.
+#[derive(Clone)]
+pub struct WeakSession {
+    metadata: Arc<Metadata>,
+    header: Arc<envelope::EnvelopeHeader>,
+    batteries: Weak<RwLock<Vec<Arc<dyn Battery>>>>,
+    enabled: Arc<AtomicBool>,
+    error_backtraces: Arc<AtomicBool>,
+    stats: Arc<summary::SessionStats>,
+    features: Arc<features::FeatureUsage>,
+    aggregation: Arc<metrics::MetricAggregation>,
+    events: Arc<event_registry::EventRegistry>,
+    recorder: Arc<OnceLock<Arc<flight_recorder::FlightRecording>>>,
+}
+
+impl WeakSession {
+    /// Attempts to upgrade this weak reference into a [`Session`], returning `None` if the session has been dropped.
+    pub fn upgrade(&self) -> Option<Session> {
+        Some(Session {
+            metadata: self.metadata.clone(),
+            header: self.header.clone(),
+            batteries: self.batteries.upgrade()?,
+            enabled: self.enabled.clone(),
+            error_backtraces: self.error_backtraces.clone(),
+            stats: self.stats.clone(),
+            features: self.features.clone(),
+            aggregation: self.aggregation.clone(),
+            events: self.events.clone(),
+            recorder: self.recorder.clone(),
+        })
+    }
+}
+
+/// Additional context which is attached to an error when it is reported using [`Session::record_error_with`].
+///
+/// Batteries should attach these fields to the error report they emit using whichever mechanism
+/// is most appropriate for their telemetry system.
+#[derive(Debug, Default, Clone)]
+pub struct ErrorContext {
+    pub fields: HashMap<&'static str, String>,
+
+    /// A trail of events which led up to the error, such as the trailing output of a failed subprocess.
+    pub breadcrumbs: Vec<Breadcrumb>,
+
+    /// The backtrace captured when the error was recorded, if [`Session::with_error_backtraces`] is enabled.
+    pub backtrace: Option<Arc<Backtrace>>,
+}
+
+/// A single entry in the trail of events which led up to an error, attached to an [`ErrorContext`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Breadcrumb {
+    /// The category of the breadcrumb (e.g. `process.stderr`).
+    pub category: Cow<'static, str>,
+    pub message: String,
+}
+
 /// Metadata about the service which is being monitored by the telemetry system.
 ///
 /// This struct contains information about the service which is being monitored, including the service name,
@@ -196,6 +978,7 @@ impl Session {
 ///
 /// let session = Session::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
 ///  .with_context("example", "yes");
+#[derive(Clone)]
 pub struct Metadata {
     pub service: Cow<'static, str>,
     pub version: Cow<'static, str>,
@@ -214,9 +997,16 @@ impl Metadata {
     /// provider into the application.
     pub fn with_battery<B: BatteryBuilder>(self, battery: B) -> Session {
         Session {
-            metadata: self,
-            batteries: Vec::new(),
+            header: Arc::new(envelope::EnvelopeHeader::new(&self)),
+            metadata: Arc::new(self),
+            batteries: Arc::new(RwLock::new(Vec::new())),
             enabled: Arc::new(AtomicBool::new(true)),
+            error_backtraces: Arc::new(AtomicBool::new(false)),
+            stats: Arc::new(summary::SessionStats::new()),
+            features: Arc::new(features::FeatureUsage::new()),
+            aggregation: Arc::new(metrics::MetricAggregation::new()),
+            events: Arc::new(event_registry::EventRegistry::new()),
+            recorder: Arc::new(OnceLock::new()),
         }
         .with_battery(battery)
     }
@@ -224,9 +1014,15 @@ impl Metadata {
 
 #[cfg(test)]
 mod tests {
-    use std::sync::{atomic::AtomicBool, Arc};
+    use std::sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    };
 
-    use crate::{Battery, BatteryBuilder, Session};
+    use crate::{
+        test_support::{EnvelopeBattery, ErrorBattery},
+        Battery, BatteryBuilder, EnvelopePayload, Session,
+    };
 
     #[test]
     fn basic_setup() {
@@ -237,6 +1033,108 @@ mod tests {
         session.shutdown();
     }
 
+    #[test]
+    fn record_error_with_context() {
+        let battery = ErrorBattery::default();
+        let session = Session::new("example", "0.0.1").with_battery(battery.clone());
+
+        let error = std::io::Error::new(std::io::ErrorKind::NotFound, "missing file");
+        session.record_error_with(&error, [("order_id", "abc123"), ("retry", "2")]);
+        session.record_error(&error);
+
+        let recorded = battery.contexts();
+        assert_eq!(recorded.len(), 2);
+        assert_eq!(
+            recorded[0].fields.get("order_id").map(|s| s.as_str()),
+            Some("abc123")
+        );
+        assert_eq!(
+            recorded[0].fields.get("retry").map(|s| s.as_str()),
+            Some("2")
+        );
+        assert!(recorded[1].fields.is_empty());
+        assert!(recorded.iter().all(|context| context.backtrace.is_none()));
+
+        session.shutdown();
+    }
+
+    #[test]
+    fn disabled_sessions_skip_errors() {
+        let battery = ErrorBattery::default();
+        let session = Session::new("example", "0.0.1").with_battery(battery.clone());
+
+        session.enable().store(false, Ordering::Relaxed);
+        assert!(!session.is_enabled());
+
+        let error = std::io::Error::new(std::io::ErrorKind::NotFound, "missing file");
+        session.record_error_with(&error, [("order_id", "abc123")]);
+        session.record_error(&error);
+
+        assert!(battery.contexts().is_empty());
+        session.shutdown();
+    }
+
+    #[test]
+    fn record_envelopes() {
+        let battery = EnvelopeBattery::default();
+        let session = Session::new("example", "0.0.1").with_battery(battery.clone());
+
+        session.record_new_page("/settings");
+        session.record_event("export_pdf", [("pages", 3)]);
+
+        session.enable().store(false, Ordering::Relaxed);
+        session.record_new_page("/hidden");
+
+        let envelopes = battery.envelopes();
+        assert_eq!(envelopes.len(), 2);
+        assert_eq!(envelopes[0].service, "example");
+        assert_eq!(
+            envelopes[0].payload,
+            EnvelopePayload::PageView {
+                page: "/settings".into()
+            }
+        );
+        assert!(
+            matches!(&envelopes[1].payload, EnvelopePayload::Event { name, .. } if name == "export_pdf")
+        );
+
+        session.shutdown();
+    }
+
+    #[test]
+    fn session_summary() {
+        let battery = EnvelopeBattery::default();
+        let session = Session::new("example", "0.0.1").with_battery(battery.clone());
+
+        session.record_new_page("/settings");
+        session.record_event("export_pdf", [("pages", 3)]);
+        session.record_error(&std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            "missing file",
+        ));
+
+        // Telemetry which is recorded while the session is disabled is never delivered, so it isn't counted.
+        session.enable().store(false, Ordering::Relaxed);
+        session.record_new_page("/hidden");
+        session.record_event("hidden", [("pages", 1)]);
+        session.record_error(&std::io::Error::other("hidden"));
+        session.enable().store(true, Ordering::Relaxed);
+
+        session.clone().shutdown_with_exit_code(2);
+
+        let envelopes = battery.envelopes();
+        match &envelopes.last().unwrap().payload {
+            EnvelopePayload::Event { name, properties } => {
+                assert_eq!(name, "session_summary");
+                assert_eq!(properties["errors"], 1);
+                assert_eq!(properties["pages"], 1);
+                assert_eq!(properties["events"], 1);
+                assert_eq!(properties["exit_code"], 2);
+            }
+            payload => panic!("unexpected payload: {payload:?}"),
+        }
+    }
+
     struct ExampleBattery;
 
     impl BatteryBuilder for ExampleBattery {
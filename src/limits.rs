@@ -0,0 +1,109 @@
+use std::{future::Future, pin::Pin};
+
+use opentelemetry::{KeyValue, StringValue, Value};
+use opentelemetry_sdk::export::trace::{ExportResult, SpanData, SpanExporter};
+
+/// The maximum length of attribute keys and values accepted by a telemetry backend, used to truncate
+/// attributes before they are exported rather than having the backend reject or drop them.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct AttributeLimits {
+    pub max_key_length: usize,
+    pub max_value_length: usize,
+}
+
+impl AttributeLimits {
+    pub fn apply(&self, attribute: KeyValue) -> KeyValue {
+        let key = if attribute.key.as_str().chars().count() > self.max_key_length {
+            truncate(attribute.key.as_str(), self.max_key_length).into()
+        } else {
+            attribute.key
+        };
+
+        let value = match attribute.value {
+            Value::String(value) if value.as_str().chars().count() > self.max_value_length => {
+                Value::String(StringValue::from(truncate(
+                    value.as_str(),
+                    self.max_value_length,
+                )))
+            }
+            value => value,
+        };
+
+        KeyValue::new(key, value)
+    }
+}
+
+fn truncate(value: &str, length: usize) -> String {
+    value.chars().take(length).collect()
+}
+
+/// A [`SpanExporter`] which applies [`AttributeLimits`] to each span (and its events) before passing
+/// them on to the wrapped exporter.
+#[derive(Debug)]
+pub(crate) struct LimitedSpanExporter<E> {
+    inner: E,
+    limits: Option<AttributeLimits>,
+}
+
+impl<E: SpanExporter> LimitedSpanExporter<E> {
+    pub fn new(inner: E, limits: Option<AttributeLimits>) -> Self {
+        Self { inner, limits }
+    }
+}
+
+impl<E: SpanExporter> SpanExporter for LimitedSpanExporter<E> {
+    fn export(
+        &mut self,
+        mut batch: Vec<SpanData>,
+    ) -> Pin<Box<dyn Future<Output = ExportResult> + Send + 'static>> {
+        if let Some(limits) = self.limits {
+            for span in batch.iter_mut() {
+                span.attributes = std::mem::take(&mut span.attributes)
+                    .into_iter()
+                    .map(|attribute| limits.apply(attribute))
+                    .collect();
+
+                for event in span.events.events.iter_mut() {
+                    event.attributes = std::mem::take(&mut event.attributes)
+                        .into_iter()
+                        .map(|attribute| limits.apply(attribute))
+                        .collect();
+                }
+            }
+        }
+
+        self.inner.export(batch)
+    }
+
+    fn shutdown(&mut self) {
+        self.inner.shutdown()
+    }
+
+    fn force_flush(&mut self) -> Pin<Box<dyn Future<Output = ExportResult> + Send + 'static>> {
+        self.inner.force_flush()
+    }
+
+    fn set_resource(&mut self, resource: &opentelemetry_sdk::Resource) {
+        self.inner.set_resource(resource)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn truncates_attributes() {
+        let limits = AttributeLimits {
+            max_key_length: 4,
+            max_value_length: 5,
+        };
+
+        let attribute = limits.apply(KeyValue::new("service", "example"));
+        assert_eq!(attribute.key.as_str(), "serv");
+        assert_eq!(attribute.value.as_str(), "examp");
+
+        let attribute = limits.apply(KeyValue::new("id", 1234567_i64));
+        assert_eq!(attribute.value, Value::I64(1234567));
+    }
+}
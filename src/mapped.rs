@@ -0,0 +1,152 @@
+use std::sync::{atomic::AtomicBool, Arc};
+
+use crate::{Battery, BatteryBuilder, Envelope, ErrorContext, Metadata, Metric, WeakSession};
+
+/// A combinator which wraps another battery, applying a transformation to every [`Envelope`] before the
+/// wrapped battery receives it.
+///
+/// This provides last-mile customization of the telemetry sent by an integration without needing to fork it,
+/// for example adding context fields which only one backend needs, renaming properties to match an existing
+/// schema, or removing properties which shouldn't leave your infrastructure. The transformation receives a
+/// copy of each envelope, so other batteries attached to the session are unaffected.
+///
+/// Only envelopes are transformed; errors recorded through [`Battery::record_error_with`] and metrics are
+/// forwarded to the wrapped battery unchanged.
+///
+/// ## Example
+/// ```no_run
+/// use tracing_batteries::{Session, Mapped, Sentry, EnvelopePayload};
+///
+/// let session = Session::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+///   .with_battery(Mapped::new(Sentry::new("https://yourdsn@sentry.example.com"), |envelope| {
+///     envelope.context.insert("team".into(), "billing".into());
+///
+///     if let EnvelopePayload::Event { properties, .. } = &mut envelope.payload {
+///       properties.remove("email");
+///     }
+///   }));
+///
+/// session.shutdown();
+/// ```
+pub struct Mapped<B, F>
+where
+    B: BatteryBuilder,
+    F: Fn(&mut Envelope) + Send + Sync + 'static,
+{
+    battery: B,
+    transform: F,
+}
+
+impl<B, F> Mapped<B, F>
+where
+    B: BatteryBuilder,
+    F: Fn(&mut Envelope) + Send + Sync + 'static,
+{
+    /// Wraps the provided battery, applying `transform` to each envelope before it is forwarded.
+    pub fn new(battery: B, transform: F) -> Self {
+        Self { battery, transform }
+    }
+}
+
+impl<B, F> BatteryBuilder for Mapped<B, F>
+where
+    B: BatteryBuilder,
+    F: Fn(&mut Envelope) + Send + Sync + 'static,
+{
+    fn setup(self, metadata: &Metadata, enabled: Arc<AtomicBool>) -> Box<dyn Battery> {
+        Box::new(MappedBattery {
+            inner: self.battery.setup(metadata, enabled),
+            transform: self.transform,
+        })
+    }
+}
+
+struct MappedBattery<F: Fn(&mut Envelope) + Send + Sync + 'static> {
+    inner: Box<dyn Battery>,
+    transform: F,
+}
+
+impl<F: Fn(&mut Envelope) + Send + Sync + 'static> Battery for MappedBattery<F> {
+    fn record_error(&self, error: &dyn std::error::Error) {
+        self.inner.record_error(error);
+    }
+
+    fn record_error_with(&self, error: &dyn std::error::Error, context: &ErrorContext) {
+        self.inner.record_error_with(error, context);
+    }
+
+    fn record_envelope(&self, envelope: &Envelope) {
+        let mut envelope = envelope.clone();
+        (self.transform)(&mut envelope);
+        self.inner.record_envelope(&envelope);
+    }
+
+    fn record_metric(&self, metric: &Metric) {
+        self.inner.record_metric(metric);
+    }
+
+    fn accepts_weighted_histograms(&self) -> bool {
+        self.inner.accepts_weighted_histograms()
+    }
+
+    fn record_artifact(&self, artifact: &crate::Artifact) -> bool {
+        self.inner.record_artifact(artifact)
+    }
+
+    fn release(&self) {
+        self.inner.release();
+    }
+
+    fn shutdown(&self) {
+        self.inner.shutdown();
+    }
+
+    fn attached(&self, session: WeakSession) {
+        self.inner.attached(session);
+    }
+
+    fn validate(&self) -> Vec<crate::ValidationCheck> {
+        self.inner.validate()
+    }
+
+    #[cfg(feature = "version-check")]
+    fn update_available(&self) -> Option<crate::AvailableUpdate> {
+        self.inner.update_available()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{test_support::EnvelopeBattery, EnvelopePayload, Session};
+
+    #[test]
+    fn transforms_envelopes() {
+        let mapped = EnvelopeBattery::default();
+        let original = EnvelopeBattery::default();
+        let session = Session::new("example", "0.0.1")
+            .with_battery(Mapped::new(mapped.clone(), |envelope| {
+                envelope.context.insert("team".into(), "billing".into());
+                if let EnvelopePayload::Event { properties, .. } = &mut envelope.payload {
+                    if let Some(value) = properties.remove("pages") {
+                        properties.insert("page_count".into(), value);
+                    }
+                }
+            }))
+            .with_battery(original.clone());
+
+        session.record_event("export_pdf", [("pages", 3)]);
+
+        let mapped = mapped.envelopes();
+        assert_eq!(mapped[0].context["team"], "billing");
+        match &mapped[0].payload {
+            EnvelopePayload::Event { properties, .. } => {
+                assert_eq!(properties["page_count"], 3);
+                assert!(!properties.contains_key("pages"));
+            }
+            payload => panic!("unexpected payload: {payload:?}"),
+        }
+
+        assert!(!original.envelopes()[0].context.contains_key("team"));
+    }
+}
@@ -0,0 +1,63 @@
+use opentelemetry::trace::TraceContextExt;
+use tracing::field::Empty;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+/// Creates a span representing the processing of a message received from a queue or topic, populated with the
+/// OpenTelemetry [messaging semantic convention](https://opentelemetry.io/docs/specs/semconv/messaging/messaging-spans/)
+/// attributes and linked to the trace of the message's producer.
+///
+/// <div class="warning">
+///
+/// This function requires the `opentelemetry` feature to be enabled.
+///
+/// </div>
+///
+/// The trace context which the producer propagated through the message's headers (or attributes) is extracted
+/// using the globally configured propagator, and added to the span as a link. Headers may be provided as any
+/// collection of name and value pairs, such as a Kafka or NATS header map, or the string attributes of an SQS
+/// message, with names being matched case-insensitively.
+///
+/// The span is a `consumer` span named `process {destination}`, with the `messaging.system`,
+/// `messaging.destination.name`, `messaging.operation.type` and `messaging.operation.name` attributes. You may
+/// also record the `messaging.message.id`, `error.type` and `otel.status_code` on the span.
+///
+/// ## Example
+/// ```no_run
+/// use std::collections::HashMap;
+/// use tracing_batteries::consume_span;
+///
+/// let headers: HashMap<String, String> = HashMap::new(); // The headers of a received message
+///
+/// let span = consume_span("kafka", "orders", &headers);
+/// let _entered = span.enter();
+/// span.record("messaging.message.id", "a8c6e2");
+/// // Process the message...
+/// ```
+pub fn consume_span<I, K, V>(system: &str, destination: &str, headers: I) -> tracing::Span
+where
+    I: IntoIterator<Item = (K, V)>,
+    K: AsRef<str>,
+    V: AsRef<[u8]>,
+{
+    let span = tracing::info_span!(
+        "message processing",
+        otel.name = %format!("process {destination}"),
+        otel.kind = "consumer",
+        otel.status_code = Empty,
+        messaging.system = system,
+        messaging.destination.name = destination,
+        "messaging.operation.type" = "process",
+        messaging.operation.name = "process",
+        messaging.message.id = Empty,
+        "error.type" = Empty,
+    );
+
+    if let Some(producer) = crate::propagation::context_from_headers(headers) {
+        let producer = producer.span().span_context().clone();
+        if producer.is_valid() {
+            span.add_link(producer);
+        }
+    }
+
+    span
+}
@@ -0,0 +1,705 @@
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, HashMap},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, Once, PoisonError,
+    },
+    time::Duration,
+};
+
+use crate::Session;
+
+/// The number of aggregation buckets used for each doubling of a histogram observation's magnitude, which bounds
+/// the relative error of the mean reported for each bucket to roughly 19%.
+const HISTOGRAM_BUCKETS_PER_DOUBLING: f64 = 4.0;
+
+/// The type of instrument which recorded a [`Metric`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MetricKind {
+    /// A monotonically increasing value, where each measurement is the amount by which the counter was incremented.
+    Counter,
+    /// A value which may go up or down, where each measurement replaces the previous value.
+    Gauge,
+    /// A distribution of values, where each measurement is an individual observation.
+    Histogram,
+}
+
+/// A single measurement recorded by one of the [`Counter`], [`Gauge`], or [`Histogram`] handles.
+///
+/// Metrics are delivered to each battery through [`Battery::record_metric`](crate::Battery::record_metric),
+/// allowing batteries which support metrics to forward the measurement to their respective backends.
+#[derive(Debug, Clone, Copy)]
+pub struct Metric<'a> {
+    pub name: &'a str,
+    pub kind: MetricKind,
+    pub value: f64,
+    pub attributes: &'a [(&'static str, &'a str)],
+
+    /// The number of observations this measurement represents, which is always `1` unless histogram observations
+    /// have been combined by [`Session::with_metric_aggregation`], in which case `value` is their mean.
+    ///
+    /// Batteries should weight histogram measurements by this count so that the count and sum of the distribution
+    /// reported to their backend match the observations which were recorded.
+    pub count: u64,
+}
+
+/// A reference to a sampled trace, which batteries (such as the notification integrations) use to link the
+/// telemetry they send to the trace which was active when it was recorded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MetricExemplar {
+    pub trace_id: u128,
+    pub span_id: u64,
+}
+
+impl MetricExemplar {
+    /// Returns the trace ID in its W3C Trace Context (lowercase hex) representation.
+    pub fn trace_id_hex(&self) -> String {
+        format!("{:032x}", self.trace_id)
+    }
+
+    /// Returns the span ID in its W3C Trace Context (lowercase hex) representation.
+    pub fn span_id_hex(&self) -> String {
+        format!("{:016x}", self.span_id)
+    }
+
+    /// Returns a reference to the sampled trace which is currently active, if any.
+    #[cfg(feature = "opentelemetry")]
+    pub(crate) fn current() -> Option<Self> {
+        use opentelemetry::trace::TraceContextExt;
+        use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+        let context = tracing::Span::current().context();
+        let span = context.span();
+        let span_context = span.span_context();
+        if !span_context.is_valid() || !span_context.is_sampled() {
+            return None;
+        }
+
+        Some(Self {
+            trace_id: u128::from_be_bytes(span_context.trace_id().to_bytes()),
+            span_id: u64::from_be_bytes(span_context.span_id().to_bytes()),
+        })
+    }
+
+    #[cfg(not(feature = "opentelemetry"))]
+    pub(crate) fn current() -> Option<Self> {
+        None
+    }
+}
+
+/// Accumulates metric measurements in memory when [`Session::with_metric_aggregation`] is enabled, so that they
+/// can be delivered to the session's batteries periodically rather than on every call.
+pub(crate) struct MetricAggregation {
+    enabled: AtomicBool,
+    interval: Mutex<Duration>,
+    series: Mutex<HashMap<Cow<'static, str>, Vec<Series>>>,
+    flusher: Once,
+}
+
+/// The measurements accumulated for a single combination of metric name and attributes.
+struct Series {
+    kind: MetricKind,
+    attributes: Vec<(&'static str, String)>,
+    /// The sum of a counter's increments, or the most recent value of a gauge.
+    value: f64,
+    /// The observations recorded by a histogram, grouped into exponentially sized buckets.
+    buckets: BTreeMap<(i8, i32), HistogramBucket>,
+}
+
+/// The histogram observations which fell into a single aggregation bucket.
+struct HistogramBucket {
+    count: u64,
+    sum: f64,
+    min: f64,
+    max: f64,
+}
+
+impl HistogramBucket {
+    /// Identifies the bucket for an observation by its sign and the logarithm of its magnitude.
+    fn key(value: f64) -> (i8, i32) {
+        if value == 0.0 {
+            return (0, 0);
+        }
+
+        let index = (value.abs().log2() * HISTOGRAM_BUCKETS_PER_DOUBLING).ceil() as i32;
+        if value < 0.0 {
+            (-1, -index)
+        } else {
+            (1, index)
+        }
+    }
+
+    fn new(value: f64) -> Self {
+        Self {
+            count: 1,
+            sum: value,
+            min: value,
+            max: value,
+        }
+    }
+
+    fn record(&mut self, value: f64) {
+        self.count += 1;
+        self.sum += value;
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+    }
+
+    /// Returns the weighted measurements which represent this bucket, as `(value, count)` pairs.
+    ///
+    /// The bucket's minimum and maximum are reported individually and its remaining observations as their mean,
+    /// so that the count, sum, minimum, and maximum of the reported distribution are all exact.
+    fn measurements(&self) -> Vec<(f64, u64)> {
+        let mut measurements = vec![(self.min, 1)];
+        if self.count > 1 {
+            measurements.push((self.max, 1));
+        }
+
+        if self.count > 2 {
+            let count = self.count - 2;
+            measurements.push(((self.sum - self.min - self.max) / count as f64, count));
+        }
+
+        measurements
+    }
+}
+
+impl MetricAggregation {
+    pub fn new() -> Self {
+        Self {
+            enabled: AtomicBool::new(false),
+            interval: Mutex::new(Duration::from_secs(10)),
+            series: Mutex::new(HashMap::new()),
+            flusher: Once::new(),
+        }
+    }
+
+    fn interval(&self) -> Duration {
+        *self.interval.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn record(&self, instrument: &Instrument, value: f64, attributes: &[(&'static str, &str)]) {
+        let mut series = self.series.lock().unwrap_or_else(PoisonError::into_inner);
+        if !series.contains_key(instrument.name.as_ref()) {
+            series.insert(instrument.name.clone(), Vec::new());
+        }
+
+        let entries = series.get_mut(instrument.name.as_ref()).unwrap();
+
+        let entry = match entries.iter().position(|entry| {
+            entry.kind == instrument.kind
+                && entry.attributes.len() == attributes.len()
+                && entry
+                    .attributes
+                    .iter()
+                    .zip(attributes)
+                    .all(|((k1, v1), (k2, v2))| k1 == k2 && v1.as_str() == *v2)
+        }) {
+            Some(index) => &mut entries[index],
+            None => {
+                entries.push(Series {
+                    kind: instrument.kind,
+                    attributes: attributes
+                        .iter()
+                        .map(|(key, value)| (*key, value.to_string()))
+                        .collect(),
+                    value: 0.0,
+                    buckets: BTreeMap::new(),
+                });
+                entries.last_mut().unwrap()
+            }
+        };
+
+        match instrument.kind {
+            MetricKind::Counter => entry.value += value,
+            MetricKind::Gauge => entry.value = value,
+            MetricKind::Histogram => {
+                entry
+                    .buckets
+                    .entry(HistogramBucket::key(value))
+                    .and_modify(|bucket| bucket.record(value))
+                    .or_insert_with(|| HistogramBucket::new(value));
+            }
+        }
+    }
+
+    fn take(&self) -> HashMap<Cow<'static, str>, Vec<Series>> {
+        std::mem::take(&mut *self.series.lock().unwrap_or_else(PoisonError::into_inner))
+    }
+}
+
+struct Instrument {
+    name: Cow<'static, str>,
+    kind: MetricKind,
+    session: Session,
+}
+
+impl Instrument {
+    fn new<N: Into<Cow<'static, str>>>(session: &Session, name: N, kind: MetricKind) -> Arc<Self> {
+        Arc::new(Self {
+            name: name.into(),
+            kind,
+            session: session.clone(),
+        })
+    }
+
+    fn record(&self, value: f64, attributes: &[(&'static str, &str)]) {
+        if !self.session.is_enabled() {
+            return;
+        }
+
+        let aggregated = self.session.aggregation.enabled.load(Ordering::Relaxed);
+        if aggregated {
+            self.session.aggregation.record(self, value, attributes);
+            if self.kind != MetricKind::Histogram {
+                return;
+            }
+        }
+
+        let metric = Metric {
+            name: &self.name,
+            kind: self.kind,
+            value,
+            attributes,
+            count: 1,
+        };
+
+        // Batteries which can't report weighted measurements receive each histogram observation individually.
+        for battery in self.session.batteries().iter() {
+            if !aggregated || !battery.accepts_weighted_histograms() {
+                battery.record_metric(&metric);
+            }
+        }
+    }
+}
+
+/// A handle to a counter metric, created using [`Session::counter`].
+///
+/// Counter handles are cheap to clone and may be freely shared between threads.
+///
+/// ## Example
+/// ```no_run
+/// use tracing_batteries::{Session, OpenTelemetry};
+///
+/// let session = Session::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+///   .with_battery(OpenTelemetry::new("localhost:4317"));
+///
+/// let processed = session.counter("items_processed");
+/// processed.inc(10);
+/// processed.inc_with(1, &[("queue", "priority")]);
+///
+/// session.shutdown();
+/// ```
+#[derive(Clone)]
+pub struct Counter(Arc<Instrument>);
+
+impl Counter {
+    /// Increments the counter by the provided amount.
+    pub fn inc(&self, value: u64) {
+        self.0.record(value as f64, &[])
+    }
+
+    /// Increments the counter by the provided amount, attaching the provided attributes to the measurement.
+    pub fn inc_with(&self, value: u64, attributes: &[(&'static str, &str)]) {
+        self.0.record(value as f64, attributes)
+    }
+}
+
+/// A handle to a gauge metric, created using [`Session::gauge`].
+///
+/// Gauge handles are cheap to clone and may be freely shared between threads.
+///
+/// ## Example
+/// ```no_run
+/// use tracing_batteries::{Session, OpenTelemetry};
+///
+/// let session = Session::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+///   .with_battery(OpenTelemetry::new("localhost:4317"));
+///
+/// session.gauge("queue_depth").set(42.0);
+///
+/// session.shutdown();
+/// ```
+#[derive(Clone)]
+pub struct Gauge(Arc<Instrument>);
+
+impl Gauge {
+    /// Sets the current value of the gauge.
+    pub fn set(&self, value: f64) {
+        self.0.record(value, &[])
+    }
+
+    /// Sets the current value of the gauge, attaching the provided attributes to the measurement.
+    pub fn set_with(&self, value: f64, attributes: &[(&'static str, &str)]) {
+        self.0.record(value, attributes)
+    }
+}
+
+/// A handle to a histogram metric, created using [`Session::histogram`].
+///
+/// Histogram handles are cheap to clone and may be freely shared between threads.
+///
+/// ## Example
+/// ```no_run
+/// use tracing_batteries::{Session, OpenTelemetry};
+///
+/// let session = Session::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+///   .with_battery(OpenTelemetry::new("localhost:4317"));
+///
+/// session.histogram("request_duration_ms").record(12.5);
+///
+/// session.shutdown();
+/// ```
+#[derive(Clone)]
+pub struct Histogram(Arc<Instrument>);
+
+impl Histogram {
+    /// Records an observation in the histogram.
+    pub fn record(&self, value: f64) {
+        self.0.record(value, &[])
+    }
+
+    /// Records an observation in the histogram, attaching the provided attributes to the measurement.
+    pub fn record_with(&self, value: f64, attributes: &[(&'static str, &str)]) {
+        self.0.record(value, attributes)
+    }
+}
+
+impl Session {
+    /// Creates a handle to the counter with the provided name.
+    ///
+    /// Measurements recorded using the handle are delivered to every battery which supports metrics,
+    /// allowing your application to record metrics without depending on a specific backend.
+    pub fn counter<N: Into<Cow<'static, str>>>(&self, name: N) -> Counter {
+        Counter(Instrument::new(self, name, MetricKind::Counter))
+    }
+
+    /// Creates a handle to the gauge with the provided name.
+    ///
+    /// Measurements recorded using the handle are delivered to every battery which supports metrics,
+    /// allowing your application to record metrics without depending on a specific backend.
+    pub fn gauge<N: Into<Cow<'static, str>>>(&self, name: N) -> Gauge {
+        Gauge(Instrument::new(self, name, MetricKind::Gauge))
+    }
+
+    /// Creates a handle to the histogram with the provided name.
+    ///
+    /// Measurements recorded using the handle are delivered to every battery which supports metrics,
+    /// allowing your application to record metrics without depending on a specific backend.
+    pub fn histogram<N: Into<Cow<'static, str>>>(&self, name: N) -> Histogram {
+        Histogram(Instrument::new(self, name, MetricKind::Histogram))
+    }
+
+    /// Enables client-side aggregation of the measurements recorded by [`Counter`], [`Gauge`], and [`Histogram`]
+    /// handles, delivering them to the session's batteries once per interval rather than on every call.
+    ///
+    /// Counter increments are summed and reported as a single delta for each combination of attributes, gauges
+    /// report their most recent value, and histogram observations are grouped into exponentially sized buckets
+    /// which are delivered as weighted measurements (see [`Metric::count`]). The count, sum, minimum, and maximum
+    /// of each histogram are preserved exactly, while individual observations are approximated by the mean of
+    /// their bucket. This allows hot loops to record metrics millions of times per second without paying each
+    /// battery's per-measurement overhead or holding every observation in memory. Any outstanding measurements
+    /// are delivered when the session is shut down.
+    ///
+    /// Batteries which cannot report weighted measurements (such as the OpenTelemetry integration, whose SDK
+    /// aggregates histograms itself) continue to receive each histogram observation as it is recorded.
+    ///
+    /// ## Example
+    /// ```no_run
+    /// use std::time::Duration;
+    /// use tracing_batteries::{Session, OpenTelemetry};
+    ///
+    /// let session = Session::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+    ///   .with_battery(OpenTelemetry::new("localhost:4317"))
+    ///   .with_metric_aggregation(Duration::from_secs(10));
+    ///
+    /// let processed = session.counter("items_processed");
+    /// for _ in 0..1_000_000 {
+    ///   processed.inc(1);
+    /// }
+    ///
+    /// session.shutdown();
+    /// ```
+    pub fn with_metric_aggregation(self, interval: Duration) -> Self {
+        *self
+            .aggregation
+            .interval
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = interval;
+        self.aggregation.enabled.store(true, Ordering::Relaxed);
+
+        self.aggregation.flusher.call_once(|| {
+            let session = self.downgrade();
+            std::thread::Builder::new()
+                .name("tracing-batteries-metrics".into())
+                .spawn(move || loop {
+                    let Some(interval) = session.upgrade().map(|s| s.aggregation.interval()) else {
+                        break;
+                    };
+
+                    std::thread::sleep(interval);
+                    match session.upgrade() {
+                        Some(session) => session.flush_metrics(),
+                        None => break,
+                    }
+                })
+                .ok();
+        });
+
+        self
+    }
+
+    pub(crate) fn flush_metrics(&self) {
+        let series = self.aggregation.take();
+        if series.is_empty() {
+            return;
+        }
+
+        let batteries = self.batteries();
+        for (name, entries) in series.iter() {
+            for entry in entries.iter() {
+                let attributes: Vec<(&'static str, &str)> = entry
+                    .attributes
+                    .iter()
+                    .map(|(key, value)| (*key, value.as_str()))
+                    .collect();
+
+                let metric = Metric {
+                    name,
+                    kind: entry.kind,
+                    value: entry.value,
+                    attributes: &attributes,
+                    count: 1,
+                };
+
+                match entry.kind {
+                    MetricKind::Histogram => {
+                        for (value, count) in entry
+                            .buckets
+                            .values()
+                            .flat_map(HistogramBucket::measurements)
+                        {
+                            let metric = Metric {
+                                value,
+                                count,
+                                ..metric
+                            };
+
+                            for battery in batteries
+                                .iter()
+                                .filter(|battery| battery.accepts_weighted_histograms())
+                            {
+                                battery.record_metric(&metric);
+                            }
+                        }
+                    }
+                    _ => {
+                        for battery in batteries.iter() {
+                            battery.record_metric(&metric);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{atomic::AtomicBool, Arc, Mutex},
+        time::Duration,
+    };
+
+    use crate::{Battery, BatteryBuilder, Metadata, Metric, MetricExemplar, MetricKind, Session};
+
+    #[test]
+    fn exemplar_formatting() {
+        let exemplar = MetricExemplar {
+            trace_id: 0x4bf92f3577b34da6a3ce929d0e0e4736,
+            span_id: 0x00f067aa0ba902b7,
+        };
+
+        assert_eq!(exemplar.trace_id_hex(), "4bf92f3577b34da6a3ce929d0e0e4736");
+        assert_eq!(exemplar.span_id_hex(), "00f067aa0ba902b7");
+    }
+
+    #[test]
+    fn records_metrics() {
+        let recorded = Arc::new(Mutex::new(Vec::new()));
+        let session = Session::new("example", "0.0.1").with_battery(MetricBattery {
+            recorded: recorded.clone(),
+            weighted: true,
+        });
+
+        let counter = session.counter("items_processed");
+        counter.inc(5);
+        counter.clone().inc_with(1, &[("queue", "priority")]);
+        session.gauge("queue_depth").set(3.0);
+        session.histogram("latency_ms").record(12.5);
+
+        let recorded = recorded.lock().unwrap();
+        assert_eq!(
+            *recorded,
+            vec![
+                (
+                    "items_processed".to_string(),
+                    MetricKind::Counter,
+                    5.0,
+                    0,
+                    1
+                ),
+                (
+                    "items_processed".to_string(),
+                    MetricKind::Counter,
+                    1.0,
+                    1,
+                    1
+                ),
+                ("queue_depth".to_string(), MetricKind::Gauge, 3.0, 0, 1),
+                ("latency_ms".to_string(), MetricKind::Histogram, 12.5, 0, 1),
+            ]
+        );
+
+        session.shutdown();
+    }
+
+    #[test]
+    fn aggregates_metrics() {
+        let recorded = Arc::new(Mutex::new(Vec::new()));
+        let session = Session::new("example", "0.0.1")
+            .with_battery(MetricBattery {
+                recorded: recorded.clone(),
+                weighted: true,
+            })
+            .with_metric_aggregation(Duration::from_secs(3600));
+
+        let counter = session.counter("items_processed");
+        for _ in 0..1000 {
+            counter.inc(2);
+        }
+        counter.inc_with(1, &[("queue", "priority")]);
+
+        let histogram = session.histogram("latency_ms");
+        for i in 0..2000 {
+            histogram.record(i as f64);
+        }
+
+        assert!(recorded.lock().unwrap().is_empty());
+        session.flush_metrics();
+
+        let recorded = recorded.lock().unwrap();
+        assert!(recorded.contains(&(
+            "items_processed".to_string(),
+            MetricKind::Counter,
+            2000.0,
+            0,
+            1
+        )));
+        assert!(recorded.contains(&(
+            "items_processed".to_string(),
+            MetricKind::Counter,
+            1.0,
+            1,
+            1
+        )));
+
+        let histogram: Vec<_> = recorded
+            .iter()
+            .filter(|(_, kind, ..)| *kind == MetricKind::Histogram)
+            .collect();
+        assert!(histogram.len() < 2000);
+        assert_eq!(histogram.iter().map(|(.., count)| count).sum::<u64>(), 2000);
+        assert_eq!(
+            histogram
+                .iter()
+                .map(|(_, _, value, _, count)| value * *count as f64)
+                .sum::<f64>()
+                .round(),
+            (0..2000).sum::<u64>() as f64
+        );
+        assert_eq!(
+            histogram
+                .iter()
+                .map(|(_, _, value, ..)| *value)
+                .fold(f64::INFINITY, f64::min),
+            0.0
+        );
+        assert_eq!(
+            histogram
+                .iter()
+                .map(|(_, _, value, ..)| *value)
+                .fold(f64::NEG_INFINITY, f64::max),
+            1999.0
+        );
+    }
+
+    #[test]
+    fn delivers_unweighted_histograms_individually() {
+        let recorded = Arc::new(Mutex::new(Vec::new()));
+        let session = Session::new("example", "0.0.1")
+            .with_battery(MetricBattery {
+                recorded: recorded.clone(),
+                weighted: false,
+            })
+            .with_metric_aggregation(Duration::from_secs(3600));
+
+        let counter = session.counter("items_processed");
+        let histogram = session.histogram("latency_ms");
+        for i in 0..100 {
+            counter.inc(1);
+            histogram.record(i as f64);
+        }
+
+        assert_eq!(recorded.lock().unwrap().len(), 100);
+        assert!(recorded
+            .lock()
+            .unwrap()
+            .iter()
+            .all(|(_, kind, .., count)| *kind == MetricKind::Histogram && *count == 1));
+
+        session.flush_metrics();
+        let recorded = recorded.lock().unwrap();
+        assert_eq!(recorded.len(), 101);
+        assert_eq!(
+            recorded.last(),
+            Some(&(
+                "items_processed".to_string(),
+                MetricKind::Counter,
+                100.0,
+                0,
+                1
+            ))
+        );
+    }
+
+    type RecordedMetric = (String, MetricKind, f64, usize, u64);
+
+    struct MetricBattery {
+        recorded: Arc<Mutex<Vec<RecordedMetric>>>,
+        weighted: bool,
+    }
+
+    impl BatteryBuilder for MetricBattery {
+        fn setup(self, _metadata: &Metadata, _enabled: Arc<AtomicBool>) -> Box<dyn Battery> {
+            Box::new(self)
+        }
+    }
+
+    impl Battery for MetricBattery {
+        fn record_metric(&self, metric: &Metric) {
+            self.recorded.lock().unwrap().push((
+                metric.name.to_string(),
+                metric.kind,
+                metric.value,
+                metric.attributes.len(),
+                metric.count,
+            ));
+        }
+
+        fn accepts_weighted_histograms(&self) -> bool {
+            self.weighted
+        }
+    }
+}
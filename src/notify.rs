@@ -0,0 +1,99 @@
+use std::{
+    collections::VecDeque,
+    sync::{Mutex, PoisonError},
+    time::{Duration, Instant},
+};
+
+use crate::{Envelope, EnvelopePayload, Metadata};
+
+/// The severity of a notification, used by notification batteries to decide which telemetry they should alert on.
+///
+/// Errors are reported with [`NotificationSeverity::Error`] severity, while custom events are reported with
+/// [`NotificationSeverity::Info`] severity. Page views never result in notifications.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum NotificationSeverity {
+    Info,
+    Error,
+}
+
+impl NotificationSeverity {
+    #[cfg_attr(
+        not(any(feature = "apprise", feature = "discord", feature = "ntfy")),
+        allow(dead_code)
+    )]
+    pub(crate) fn of(envelope: &Envelope) -> Option<Self> {
+        match envelope.payload {
+            EnvelopePayload::Error { .. } => Some(NotificationSeverity::Error),
+            EnvelopePayload::Event { .. } => Some(NotificationSeverity::Info),
+            _ => None,
+        }
+    }
+}
+
+/// Limits the number of notifications which are sent within a sliding window, preventing an error loop from
+/// flooding a channel (or being throttled by the notification service).
+pub(crate) struct RateLimiter {
+    limit: usize,
+    window: Duration,
+    sent: Mutex<VecDeque<Instant>>,
+}
+
+impl RateLimiter {
+    pub fn new(limit: usize, window: Duration) -> Self {
+        Self {
+            limit,
+            window,
+            sent: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    /// Returns `true` if another notification may be sent, recording it against the limit.
+    pub fn allow(&self) -> bool {
+        let now = Instant::now();
+        let mut sent = self.sent.lock().unwrap_or_else(PoisonError::into_inner);
+        while sent
+            .front()
+            .is_some_and(|sent| now.duration_since(*sent) >= self.window)
+        {
+            sent.pop_front();
+        }
+
+        if sent.len() >= self.limit {
+            return false;
+        }
+
+        sent.push_back(now);
+        true
+    }
+}
+
+/// Determines the environment label which notifications should include, based on the session's context.
+pub(crate) fn environment_label(metadata: &Metadata) -> Option<String> {
+    ["deployment.environment", "environment"]
+        .iter()
+        .find_map(|key| metadata.context.get(key))
+        .map(|environment| environment.to_string())
+}
+
+/// Escapes text for inclusion in the HTML formatted body of a notification.
+#[cfg(any(feature = "matrix", feature = "telegram"))]
+pub(crate) fn escape_html(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rate_limiter() {
+        let limiter = RateLimiter::new(2, Duration::from_secs(60));
+        assert!(limiter.allow());
+        assert!(limiter.allow());
+        assert!(!limiter.allow());
+    }
+}
@@ -0,0 +1,159 @@
+use std::{borrow::Cow, io::Write, time::SystemTime};
+
+use opentelemetry::KeyValue;
+use tracing_opentelemetry::OtelData;
+use tracing_subscriber::{registry::LookupSpan, Registry};
+
+/// The longest partial line which is held while waiting for a newline, beyond which it is recorded as-is.
+const MAX_LINE_LENGTH: usize = 8 * 1024;
+
+/// A writer which tees everything written to it into span events on the active span, preserving the textual
+/// output of a CLI step alongside its trace.
+///
+/// Output is passed through to the wrapped writer unchanged, while each complete line is recorded as an `output`
+/// event (with `output.stream` and `output.line` attributes) on the span which is active when the line is
+/// finished (or when the writer is flushed). Lines written outside of a span are only passed through. Use
+/// [`OutputCapture::stdout`] and [`OutputCapture::stderr`] in place of the process' standard streams, or
+/// [`OutputCapture::new`] to wrap any other writer (such as the output of a child process).
+///
+/// Capturing output is opt-in, since it may be large or contain sensitive information.
+///
+/// ## Example
+/// ```no_run
+/// use std::io::Write;
+/// use tracing_batteries::{OpenTelemetry, OutputCapture, Session};
+///
+/// let session = Session::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+///   .with_battery(OpenTelemetry::new("localhost:4317"));
+///
+/// let mut stdout = OutputCapture::stdout();
+/// tracing::info_span!("build").in_scope(|| {
+///   writeln!(stdout, "Compiled 42 files").ok();
+/// });
+///
+/// session.shutdown();
+/// ```
+///
+/// <div class="warning">
+///
+/// This type requires the `opentelemetry` feature to be enabled.
+///
+/// </div>
+pub struct OutputCapture<W: Write> {
+    stream: Cow<'static, str>,
+    inner: W,
+    line: Vec<u8>,
+}
+
+impl OutputCapture<std::io::Stdout> {
+    /// Wraps the process' standard output stream.
+    pub fn stdout() -> Self {
+        Self::new("stdout", std::io::stdout())
+    }
+}
+
+impl OutputCapture<std::io::Stderr> {
+    /// Wraps the process' standard error stream.
+    pub fn stderr() -> Self {
+        Self::new("stderr", std::io::stderr())
+    }
+}
+
+impl<W: Write> OutputCapture<W> {
+    /// Wraps the provided writer, recording its output under the provided stream name.
+    pub fn new<S: Into<Cow<'static, str>>>(stream: S, inner: W) -> Self {
+        Self {
+            stream: stream.into(),
+            inner,
+            line: Vec::new(),
+        }
+    }
+
+    /// Records any partial line which has been written, and returns the wrapped writer.
+    pub fn into_inner(mut self) -> W {
+        self.record_partial_line();
+        self.inner
+    }
+
+    fn record_partial_line(&mut self) {
+        if !self.line.is_empty() {
+            let line = std::mem::take(&mut self.line);
+            record_line(&self.stream, &line);
+        }
+    }
+}
+
+impl<W: Write> Write for OutputCapture<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let written = self.inner.write(buf)?;
+
+        for chunk in buf[..written].split_inclusive(|&b| b == b'\n') {
+            match chunk.strip_suffix(b"\n") {
+                Some(rest) => {
+                    self.line.extend_from_slice(rest);
+                    let line = std::mem::take(&mut self.line);
+                    record_line(&self.stream, &line);
+                }
+                None => {
+                    self.line.extend_from_slice(chunk);
+                    if self.line.len() >= MAX_LINE_LENGTH {
+                        self.record_partial_line();
+                    }
+                }
+            }
+        }
+
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.record_partial_line();
+        self.inner.flush()
+    }
+}
+
+/// Adds a line of output as an event on the OpenTelemetry representation of the active span.
+fn record_line(stream: &str, line: &[u8]) {
+    let line = String::from_utf8_lossy(line);
+    let line = line.strip_suffix('\r').unwrap_or(&line);
+
+    tracing::Span::current().with_subscriber(|(id, dispatch)| {
+        let Some(span) = dispatch
+            .downcast_ref::<Registry>()
+            .and_then(|registry| registry.span(id))
+        else {
+            return;
+        };
+
+        let mut extensions = span.extensions_mut();
+        if let Some(data) = extensions.get_mut::<OtelData>() {
+            data.builder.events.get_or_insert_with(Vec::new).push(
+                opentelemetry::trace::Event::new(
+                    "output",
+                    SystemTime::now(),
+                    vec![
+                        KeyValue::new("output.stream", stream.to_string()),
+                        KeyValue::new("output.line", line.to_string()),
+                    ],
+                    0,
+                ),
+            );
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn passes_output_through() {
+        let mut capture = OutputCapture::new("test", Vec::new());
+        write!(capture, "first line\nsecond").unwrap();
+        assert_eq!(capture.line, b"second");
+
+        writeln!(capture, " line").unwrap();
+        assert!(capture.line.is_empty());
+        assert_eq!(capture.into_inner(), b"first line\nsecond line\n");
+    }
+}
@@ -0,0 +1,268 @@
+use std::{
+    fmt::Display,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, PoisonError,
+    },
+    time::{Duration, Instant},
+};
+
+use crate::{Battery, Envelope, ErrorContext, Metric, Session, WeakSession};
+
+/// The time spent by a single battery, measured in debug builds so that [`Session::overhead_report`] can
+/// describe what each battery costs.
+pub(crate) struct BatteryTimings {
+    battery: &'static str,
+    setup: Duration,
+    errors: Timing,
+    envelopes: Timing,
+    metrics: Timing,
+}
+
+#[derive(Default)]
+struct Timing {
+    calls: AtomicU64,
+    nanos: AtomicU64,
+}
+
+impl Timing {
+    fn measure<T>(&self, f: impl FnOnce() -> T) -> T {
+        let start = Instant::now();
+        let result = f();
+        self.nanos
+            .fetch_add(start.elapsed().as_nanos() as u64, Ordering::Relaxed);
+        self.calls.fetch_add(1, Ordering::Relaxed);
+        result
+    }
+
+    fn overhead(&self) -> OperationOverhead {
+        OperationOverhead {
+            calls: self.calls.load(Ordering::Relaxed),
+            total: Duration::from_nanos(self.nanos.load(Ordering::Relaxed)),
+        }
+    }
+}
+
+/// Wraps a battery, recording the time spent in each of its methods.
+pub(crate) struct TimedBattery {
+    inner: Box<dyn Battery>,
+    timings: Arc<BatteryTimings>,
+}
+
+impl TimedBattery {
+    pub fn new(battery: &'static str, setup: impl FnOnce() -> Box<dyn Battery>) -> Self {
+        let start = Instant::now();
+        let inner = setup();
+
+        Self {
+            inner,
+            timings: Arc::new(BatteryTimings {
+                battery,
+                setup: start.elapsed(),
+                errors: Timing::default(),
+                envelopes: Timing::default(),
+                metrics: Timing::default(),
+            }),
+        }
+    }
+
+    pub fn timings(&self) -> Arc<BatteryTimings> {
+        self.timings.clone()
+    }
+}
+
+impl Battery for TimedBattery {
+    fn record_error(&self, error: &dyn std::error::Error) {
+        self.timings
+            .errors
+            .measure(|| self.inner.record_error(error));
+    }
+
+    fn record_error_with(&self, error: &dyn std::error::Error, context: &ErrorContext) {
+        self.timings
+            .errors
+            .measure(|| self.inner.record_error_with(error, context));
+    }
+
+    fn record_envelope(&self, envelope: &Envelope) {
+        self.timings
+            .envelopes
+            .measure(|| self.inner.record_envelope(envelope));
+    }
+
+    fn record_metric(&self, metric: &Metric) {
+        self.timings
+            .metrics
+            .measure(|| self.inner.record_metric(metric));
+    }
+
+    fn accepts_weighted_histograms(&self) -> bool {
+        self.inner.accepts_weighted_histograms()
+    }
+
+    fn record_artifact(&self, artifact: &crate::Artifact) -> bool {
+        self.inner.record_artifact(artifact)
+    }
+
+    fn release(&self) {
+        self.inner.release();
+    }
+
+    fn shutdown(&self) {
+        self.inner.shutdown();
+    }
+
+    fn attached(&self, session: WeakSession) {
+        self.inner.attached(session);
+    }
+
+    fn validate(&self) -> Vec<crate::ValidationCheck> {
+        self.inner.validate()
+    }
+
+    #[cfg(feature = "version-check")]
+    fn update_available(&self) -> Option<crate::AvailableUpdate> {
+        self.inner.update_available()
+    }
+}
+
+/// The number of times a battery was called to handle a type of telemetry, and the total time those calls took.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct OperationOverhead {
+    pub calls: u64,
+    pub total: Duration,
+}
+
+impl OperationOverhead {
+    /// The average time taken by each call, or zero if no calls have been made.
+    pub fn mean(&self) -> Duration {
+        if self.calls == 0 {
+            Duration::ZERO
+        } else {
+            Duration::from_nanos((self.total.as_nanos() / self.calls as u128) as u64)
+        }
+    }
+}
+
+impl Display for OperationOverhead {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} calls, {:?} total, {:?} mean",
+            self.calls,
+            self.total,
+            self.mean()
+        )
+    }
+}
+
+/// The time spent by a single battery, as reported by [`Session::overhead_report`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BatteryOverhead {
+    /// The type name of the battery's builder, such as `tracing_batteries::Sentry`.
+    pub battery: &'static str,
+    /// The time taken to set up the battery when it was attached to the session.
+    pub setup: Duration,
+    pub errors: OperationOverhead,
+    pub envelopes: OperationOverhead,
+    pub metrics: OperationOverhead,
+}
+
+/// A description of the time each of a session's batteries has spent handling telemetry, produced by
+/// [`Session::overhead_report`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OverheadReport {
+    pub batteries: Vec<BatteryOverhead>,
+}
+
+impl Display for OverheadReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for battery in self.batteries.iter() {
+            writeln!(f, "{} (setup took {:?})", battery.battery, battery.setup)?;
+            writeln!(f, "  errors:    {}", battery.errors)?;
+            writeln!(f, "  envelopes: {}", battery.envelopes)?;
+            writeln!(f, "  metrics:   {}", battery.metrics)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Session {
+    /// Reports how much time each of the session's batteries has spent setting up and handling the errors,
+    /// envelopes and metrics recorded so far, helping you quantify what enabling each battery costs before
+    /// shipping it.
+    ///
+    /// This method is only available in debug builds, since measuring each call adds overhead of its own. Time
+    /// spent by batteries in the background (such as exporting batches) or inside their tracing layers is not
+    /// included; use the benchmarks in this repository's `benches/` directory to measure span overhead.
+    ///
+    /// ## Example
+    /// ```no_run
+    /// use tracing_batteries::{Session, Sentry};
+    ///
+    /// let session = Session::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+    ///   .with_battery(Sentry::new("https://yourdsn@sentry.example.com"));
+    ///
+    /// session.record_event("export_pdf", [("pages", 3)]);
+    /// print!("{}", session.overhead_report());
+    ///
+    /// session.shutdown();
+    /// ```
+    pub fn overhead_report(&self) -> OverheadReport {
+        OverheadReport {
+            batteries: self
+                .stats
+                .overhead
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .iter()
+                .map(|timings| BatteryOverhead {
+                    battery: timings.battery,
+                    setup: timings.setup,
+                    errors: timings.errors.overhead(),
+                    envelopes: timings.envelopes.overhead(),
+                    metrics: timings.metrics.overhead(),
+                })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicBool;
+
+    use super::*;
+    use crate::{BatteryBuilder, Metadata};
+
+    struct SlowBattery;
+
+    impl BatteryBuilder for SlowBattery {
+        fn setup(self, _metadata: &Metadata, _enabled: Arc<AtomicBool>) -> Box<dyn Battery> {
+            Box::new(SlowBattery)
+        }
+    }
+
+    impl Battery for SlowBattery {
+        fn record_envelope(&self, _envelope: &Envelope) {
+            std::thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    #[test]
+    fn reports_overhead() {
+        let session = Session::new("example", "0.0.1").with_battery(SlowBattery);
+        session.record_new_page("/settings");
+        session.record_new_page("/profile");
+
+        let report = session.overhead_report();
+        assert_eq!(report.batteries.len(), 1);
+
+        let battery = &report.batteries[0];
+        assert!(battery.battery.ends_with("SlowBattery"));
+        assert_eq!(battery.envelopes.calls, 2);
+        assert!(battery.envelopes.mean() >= Duration::from_millis(1));
+        assert_eq!(battery.errors, OperationOverhead::default());
+    }
+}
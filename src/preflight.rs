@@ -0,0 +1,214 @@
+use std::{borrow::Cow, fmt::Display};
+
+use crate::Session;
+
+/// How long each battery's connectivity checks wait for a connection to be established.
+#[cfg(any(feature = "opentelemetry", feature = "sentry"))]
+pub(crate) const VALIDATION_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// The outcome of a single check performed by a battery during [`Session::validate`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationCheck {
+    /// The name of the battery which performed the check.
+    pub battery: Cow<'static, str>,
+    /// A description of what was checked, such as the endpoint which was contacted.
+    pub description: String,
+    /// The reason the check failed, if it did.
+    pub error: Option<String>,
+}
+
+impl ValidationCheck {
+    /// Records that a check passed.
+    pub fn passed<B: Into<Cow<'static, str>>, D: Into<String>>(battery: B, description: D) -> Self {
+        Self {
+            battery: battery.into(),
+            description: description.into(),
+            error: None,
+        }
+    }
+
+    /// Records that a check failed, along with the reason it failed.
+    pub fn failed<B: Into<Cow<'static, str>>, D: Into<String>, E: Into<String>>(
+        battery: B,
+        description: D,
+        error: E,
+    ) -> Self {
+        Self {
+            battery: battery.into(),
+            description: description.into(),
+            error: Some(error.into()),
+        }
+    }
+
+    /// Whether this check passed.
+    pub fn is_ok(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// A report describing whether each of a session's batteries is correctly configured, produced by
+/// [`Session::validate`].
+///
+/// The report's [`Display`] implementation produces a human readable summary with one line per check, making it
+/// suitable for printing from a `doctor` command.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ValidationReport {
+    pub checks: Vec<ValidationCheck>,
+}
+
+impl ValidationReport {
+    /// Whether every check in the report passed.
+    pub fn is_ok(&self) -> bool {
+        self.checks.iter().all(ValidationCheck::is_ok)
+    }
+
+    /// The checks which failed.
+    pub fn failures(&self) -> impl Iterator<Item = &ValidationCheck> {
+        self.checks.iter().filter(|check| !check.is_ok())
+    }
+}
+
+impl Display for ValidationReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.checks.is_empty() {
+            return writeln!(f, "No telemetry checks were performed.");
+        }
+
+        for check in self.checks.iter() {
+            match &check.error {
+                None => writeln!(f, "[ok]   {}: {}", check.battery, check.description)?,
+                Some(error) => writeln!(
+                    f,
+                    "[fail] {}: {} ({})",
+                    check.battery, check.description, error
+                )?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Session {
+    /// Checks that each of the session's batteries is correctly configured, for example by confirming that their
+    /// endpoints can be reached, without emitting any telemetry.
+    ///
+    /// This is intended to help users diagnose misconfigured telemetry, for example from a `doctor` command, and
+    /// may take several seconds to complete if an endpoint is unreachable.
+    ///
+    /// ## Example
+    /// ```no_run
+    /// use tracing_batteries::{Session, Sentry};
+    ///
+    /// let session = Session::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+    ///   .with_battery(Sentry::new("https://yourdsn@sentry.example.com/app-id"));
+    ///
+    /// let report = session.validate();
+    /// print!("{report}");
+    ///
+    /// if !report.is_ok() {
+    ///   std::process::exit(1);
+    /// }
+    /// ```
+    pub fn validate(&self) -> ValidationReport {
+        ValidationReport {
+            checks: self
+                .batteries()
+                .iter()
+                .flat_map(|battery| battery.validate())
+                .collect(),
+        }
+    }
+}
+
+/// Checks whether a TCP connection can be established to the host (and port) of the provided endpoint, which may
+/// either be a URL or a `host:port` pair.
+#[cfg(any(feature = "opentelemetry", feature = "sentry"))]
+pub(crate) fn check_reachable(endpoint: &str, timeout: std::time::Duration) -> Result<(), String> {
+    let (scheme, address) = match endpoint.split_once("://") {
+        Some((scheme, address)) => (scheme, address),
+        None => ("", endpoint),
+    };
+
+    let authority = address.split('/').next().unwrap_or(address);
+    let authority = authority.rsplit('@').next().unwrap_or(authority);
+    let has_port = authority
+        .rsplit_once(':')
+        .is_some_and(|(_, port)| port.parse::<u16>().is_ok());
+    let authority = match (has_port, scheme) {
+        (true, _) => authority.to_string(),
+        (false, "http") => format!("{authority}:80"),
+        (false, _) => format!("{authority}:443"),
+    };
+
+    let addresses = std::net::ToSocketAddrs::to_socket_addrs(&authority)
+        .map_err(|err| format!("could not resolve '{authority}': {err}"))?;
+
+    let mut last_error = format!("no addresses found for '{authority}'");
+    for address in addresses {
+        match std::net::TcpStream::connect_timeout(&address, timeout) {
+            Ok(_) => return Ok(()),
+            Err(err) => last_error = format!("could not connect to '{address}': {err}"),
+        }
+    }
+
+    Err(last_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{atomic::AtomicBool, Arc};
+
+    use super::*;
+    use crate::{Battery, BatteryBuilder, Metadata};
+
+    struct CheckedBattery(Option<&'static str>);
+
+    impl BatteryBuilder for CheckedBattery {
+        fn setup(self, _metadata: &Metadata, _enabled: Arc<AtomicBool>) -> Box<dyn Battery> {
+            Box::new(self)
+        }
+    }
+
+    impl Battery for CheckedBattery {
+        fn validate(&self) -> Vec<ValidationCheck> {
+            vec![match self.0 {
+                Some(error) => ValidationCheck::failed("Checked", "endpoint is reachable", error),
+                None => ValidationCheck::passed("Checked", "endpoint is reachable"),
+            }]
+        }
+    }
+
+    #[test]
+    fn reports_failed_checks() {
+        let session = Session::new("example", "0.0.1")
+            .with_battery(CheckedBattery(None))
+            .with_battery(CheckedBattery(Some("connection refused")));
+
+        let report = session.validate();
+        assert_eq!(report.checks.len(), 2);
+        assert!(!report.is_ok());
+        assert_eq!(report.failures().count(), 1);
+        assert_eq!(
+            report.to_string(),
+            "[ok]   Checked: endpoint is reachable\n[fail] Checked: endpoint is reachable (connection refused)\n"
+        );
+    }
+
+    #[test]
+    #[cfg(any(feature = "opentelemetry", feature = "sentry"))]
+    fn reachable_endpoints() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        assert!(check_reachable(
+            &format!("http://127.0.0.1:{port}/v1/traces"),
+            VALIDATION_TIMEOUT
+        )
+        .is_ok());
+        assert!(check_reachable(&format!("127.0.0.1:{port}"), VALIDATION_TIMEOUT).is_ok());
+
+        drop(listener);
+        assert!(check_reachable(&format!("http://127.0.0.1:{port}"), VALIDATION_TIMEOUT).is_err());
+    }
+}
@@ -0,0 +1,167 @@
+use std::{collections::HashMap, process::Command};
+
+use opentelemetry::propagation::{Extractor, Injector};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+use crate::Session;
+
+/// The environment variables which are used to propagate trace context to child processes,
+/// following the OpenTelemetry environment variable carrier conventions.
+const TRACE_CONTEXT_VARIABLES: &[&str] = &["TRACEPARENT", "TRACESTATE"];
+
+/// The environment variable which AWS Lambda uses to provide the X-Ray trace context of the current invocation.
+const XRAY_TRACE_VARIABLE: &str = "_X_AMZN_TRACE_ID";
+
+impl Session {
+    /// Injects the trace context of the current [`tracing::Span`] into the provided command's environment.
+    ///
+    /// <div class="warning">
+    ///
+    /// This method requires the `opentelemetry` feature to be enabled.
+    ///
+    /// </div>
+    ///
+    /// The context is written to the `TRACEPARENT` and `TRACESTATE` environment variables, allowing
+    /// child processes which use this library (or any other OpenTelemetry SDK which supports environment
+    /// variable propagation) to attach their spans to the same distributed trace. Commands run using
+    /// [`Session::instrument_command`] have their trace context injected automatically.
+    ///
+    /// ## Example
+    /// ```no_run
+    /// use std::process::Command;
+    /// use tracing_batteries::{Session, OpenTelemetry};
+    ///
+    /// let session = Session::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+    ///   .with_battery(OpenTelemetry::new("localhost:4317"));
+    ///
+    /// let mut command = Command::new("my-child-process");
+    /// session.inject_trace_context(&mut command).spawn()?;
+    ///
+    /// session.shutdown();
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn inject_trace_context<'a>(&self, command: &'a mut Command) -> &'a mut Command {
+        let context = tracing::Span::current().context();
+        opentelemetry::global::get_text_map_propagator(|propagator| {
+            propagator.inject_context(&context, &mut CommandInjector(command))
+        });
+
+        command
+    }
+}
+
+/// Extracts the trace context which was propagated to this process by its parent through the
+/// `TRACEPARENT` and `TRACESTATE` environment variables (or, when X-Ray propagation is configured,
+/// the `_X_AMZN_TRACE_ID` variable set by AWS Lambda), if present.
+pub(crate) fn parent_context_from_env() -> Option<opentelemetry::Context> {
+    context_from_headers(
+        TRACE_CONTEXT_VARIABLES
+            .iter()
+            .filter_map(|key| std::env::var(key).ok().map(|value| (*key, value)))
+            .chain(
+                std::env::var(XRAY_TRACE_VARIABLE)
+                    .ok()
+                    .map(|value| ("X-Amzn-Trace-Id", value)),
+            ),
+    )
+}
+
+/// Extracts the trace context which was propagated through the provided headers (such as the headers or attributes
+/// of a message), if any were provided. Header names are matched case-insensitively.
+pub(crate) fn context_from_headers<I, K, V>(headers: I) -> Option<opentelemetry::Context>
+where
+    I: IntoIterator<Item = (K, V)>,
+    K: AsRef<str>,
+    V: AsRef<[u8]>,
+{
+    let extractor = HeaderExtractor(
+        headers
+            .into_iter()
+            .map(|(key, value)| {
+                (
+                    key.as_ref().to_ascii_lowercase(),
+                    String::from_utf8_lossy(value.as_ref()).into_owned(),
+                )
+            })
+            .collect(),
+    );
+
+    if extractor.0.is_empty() {
+        return None;
+    }
+
+    Some(opentelemetry::global::get_text_map_propagator(
+        |propagator| propagator.extract(&extractor),
+    ))
+}
+
+struct CommandInjector<'a>(&'a mut Command);
+
+impl Injector for CommandInjector<'_> {
+    fn set(&mut self, key: &str, value: String) {
+        self.0.env(key.to_ascii_uppercase(), value);
+    }
+}
+
+struct HeaderExtractor(HashMap<String, String>);
+
+impl Extractor for HeaderExtractor {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(&key.to_ascii_lowercase()).map(|v| v.as_str())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(|k| k.as_str()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use opentelemetry::{
+        propagation::TextMapPropagator,
+        trace::{SpanContext, SpanId, TraceContextExt, TraceFlags, TraceId, TraceState},
+    };
+    use opentelemetry_sdk::propagation::TraceContextPropagator;
+
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let span_context = SpanContext::new(
+            TraceId::from_hex("4bf92f3577b34da6a3ce929d0e0e4736").unwrap(),
+            SpanId::from_hex("00f067aa0ba902b7").unwrap(),
+            TraceFlags::SAMPLED,
+            true,
+            TraceState::default(),
+        );
+        let context = opentelemetry::Context::new().with_remote_span_context(span_context.clone());
+
+        let propagator = TraceContextPropagator::new();
+        let mut command = Command::new("example");
+        propagator.inject_context(&context, &mut CommandInjector(&mut command));
+
+        let env = command
+            .get_envs()
+            .filter_map(|(key, value)| {
+                Some((
+                    key.to_string_lossy().to_ascii_lowercase(),
+                    value?.to_string_lossy().to_string(),
+                ))
+            })
+            .collect();
+        assert_eq!(
+            HeaderExtractor(env).get("traceparent"),
+            Some("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01")
+        );
+
+        let extracted = propagator.extract(&HeaderExtractor(
+            [(
+                "traceparent".to_string(),
+                "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01".to_string(),
+            )]
+            .into_iter()
+            .collect(),
+        ));
+        assert_eq!(extracted.span().span_context(), &span_context);
+    }
+}
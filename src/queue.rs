@@ -0,0 +1,245 @@
+use std::{
+    collections::VecDeque,
+    sync::{Condvar, Mutex, PoisonError},
+    time::{Duration, Instant},
+};
+
+use crate::{DropPolicy, Importance};
+
+/// The default maximum number of items which may be queued, beyond which the least important items are shed.
+pub(crate) const DEFAULT_QUEUE_CAPACITY: usize = 10_000;
+
+/// How long shutting down waits for queued items to be delivered before the remainder are dropped.
+pub(crate) const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The result of waiting for an item to be taken from a [`PriorityQueue`].
+pub(crate) enum Pop<T> {
+    Item(T),
+    Timeout,
+    Closed,
+}
+
+/// A bounded queue which hands out its most important items first, used by the background workers which
+/// deliver telemetry on behalf of batteries.
+///
+/// When the queue is full, one of its least important items is shed to make room for a new item (unless every
+/// queued item is more important than it, in which case the new item is dropped). The [`DropPolicy`] decides
+/// whether the oldest item is shed, or whether the newest telemetry is dropped instead. Each shed item is counted
+/// in the [`diagnostics`](crate::diagnostics) report. Once the queue is closed it stops accepting new items, and
+/// any items which haven't been taken by the shutdown deadline are dropped, ensuring that critical telemetry is
+/// delivered first when time is short.
+pub(crate) struct PriorityQueue<T> {
+    name: String,
+    state: Mutex<QueueState<T>>,
+    available: Condvar,
+}
+
+struct QueueState<T> {
+    /// The queued items, ordered from most to least important.
+    levels: [VecDeque<T>; 3],
+    capacity: usize,
+    policy: DropPolicy,
+    deadline: Option<Instant>,
+}
+
+fn level(importance: Importance) -> usize {
+    match importance {
+        Importance::Critical => 0,
+        Importance::Normal => 1,
+        Importance::Low => 2,
+    }
+}
+
+impl<T> PriorityQueue<T> {
+    /// Creates an empty queue, whose shed items are reported in diagnostics under the provided name.
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            state: Mutex::new(QueueState {
+                levels: Default::default(),
+                capacity: DEFAULT_QUEUE_CAPACITY,
+                policy: DropPolicy::default(),
+                deadline: None,
+            }),
+            available: Condvar::new(),
+        }
+    }
+
+    /// Configures the maximum number of items which may be queued, and which items are shed once it is reached.
+    pub fn set_limits(&self, capacity: usize, policy: DropPolicy) {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        state.capacity = capacity.max(1);
+        state.policy = policy;
+    }
+
+    /// Queues an item, shedding a less important item if the queue is full.
+    pub fn push(&self, importance: Importance, item: T) {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        if state.deadline.is_some() {
+            return;
+        }
+
+        let level = level(importance);
+        if state.levels.iter().map(VecDeque::len).sum::<usize>() >= state.capacity {
+            crate::diagnostics::record_dropped(&self.name);
+
+            match (level..state.levels.len())
+                .rev()
+                .find(|&shed| !state.levels[shed].is_empty())
+            {
+                Some(shed) if state.policy == DropPolicy::DropOldest => {
+                    state.levels[shed].pop_front();
+                }
+                Some(shed) if shed > level => {
+                    state.levels[shed].pop_back();
+                }
+                _ => return,
+            }
+        }
+
+        state.levels[level].push_back(item);
+        self.available.notify_one();
+    }
+
+    /// Takes the most important item from the queue, waiting until one is available, the provided time is
+    /// reached, or the queue is closed and has either been drained or passed its shutdown deadline.
+    pub fn pop(&self, until: Option<Instant>) -> Pop<T> {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        loop {
+            if state
+                .deadline
+                .is_some_and(|deadline| Instant::now() >= deadline)
+            {
+                return Pop::Closed;
+            }
+
+            if let Some(item) = state.levels.iter_mut().find_map(VecDeque::pop_front) {
+                return Pop::Item(item);
+            }
+
+            if state.deadline.is_some() {
+                return Pop::Closed;
+            }
+
+            state = match until {
+                Some(until) => {
+                    let now = Instant::now();
+                    if now >= until {
+                        return Pop::Timeout;
+                    }
+
+                    self.available
+                        .wait_timeout(state, until - now)
+                        .unwrap_or_else(PoisonError::into_inner)
+                        .0
+                }
+                None => self
+                    .available
+                    .wait(state)
+                    .unwrap_or_else(PoisonError::into_inner),
+            };
+        }
+    }
+
+    /// Waits until the provided time is reached (for example, before reconnecting to an unreachable backend),
+    /// returning `false` if the queue's shutdown deadline passes first.
+    // Only batteries which hold a connection to their backend wait on the queue, so it's unused by the others.
+    #[allow(dead_code)]
+    pub fn wait_until(&self, until: Instant) -> bool {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        loop {
+            let now = Instant::now();
+            if state.deadline.is_some_and(|deadline| now >= deadline) {
+                return false;
+            }
+
+            if now >= until {
+                return true;
+            }
+
+            let until = state.deadline.map_or(until, |deadline| deadline.min(until));
+            state = self
+                .available
+                .wait_timeout(state, until - now)
+                .unwrap_or_else(PoisonError::into_inner)
+                .0;
+        }
+    }
+
+    /// Stops accepting new items, allowing the queued items to be taken until the provided timeout elapses.
+    pub fn close(&self, timeout: Duration) {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        state.deadline.get_or_insert(Instant::now() + timeout);
+        self.available.notify_all();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prioritizes_important_items() {
+        let queue = PriorityQueue::new("test");
+        queue.push(Importance::Low, "page");
+        queue.push(Importance::Normal, "event");
+        queue.push(Importance::Critical, "error");
+        queue.close(SHUTDOWN_TIMEOUT);
+        queue.push(Importance::Critical, "too late");
+
+        let mut items = Vec::new();
+        while let Pop::Item(item) = queue.pop(None) {
+            items.push(item);
+        }
+
+        assert_eq!(items, ["error", "event", "page"]);
+    }
+
+    #[test]
+    fn sheds_least_important_items() {
+        let queue = PriorityQueue::new("test");
+        for _ in 0..DEFAULT_QUEUE_CAPACITY {
+            queue.push(Importance::Normal, Importance::Normal);
+        }
+
+        queue.push(Importance::Low, Importance::Low);
+        queue.push(Importance::Critical, Importance::Critical);
+
+        assert!(matches!(queue.pop(None), Pop::Item(Importance::Critical)));
+        assert!(matches!(
+            queue.pop(Some(Instant::now())),
+            Pop::Item(Importance::Normal)
+        ));
+
+        queue.close(Duration::ZERO);
+        assert!(matches!(queue.pop(None), Pop::Closed));
+    }
+
+    #[test]
+    fn drop_policies() {
+        let oldest = PriorityQueue::new("test");
+        oldest.set_limits(2, DropPolicy::DropOldest);
+        let newest = PriorityQueue::new("test");
+        newest.set_limits(2, DropPolicy::DropNewest);
+
+        for page in ["/first", "/second", "/third"] {
+            oldest.push(Importance::Low, page);
+            newest.push(Importance::Low, page);
+        }
+
+        oldest.close(SHUTDOWN_TIMEOUT);
+        newest.close(SHUTDOWN_TIMEOUT);
+
+        assert!(matches!(oldest.pop(None), Pop::Item("/second")));
+        assert!(matches!(newest.pop(None), Pop::Item("/first")));
+    }
+
+    #[test]
+    fn waits_until_shutdown_deadline() {
+        let queue = PriorityQueue::<()>::new("test");
+        assert!(queue.wait_until(Instant::now() + Duration::from_millis(1)));
+
+        queue.close(Duration::ZERO);
+        assert!(!queue.wait_until(Instant::now() + Duration::from_secs(60)));
+    }
+}
@@ -0,0 +1,78 @@
+use std::{
+    io,
+    net::{TcpStream, ToSocketAddrs},
+    time::{Duration, Instant},
+};
+
+/// The longest delay between attempts to reconnect to an unreachable backend.
+pub(crate) const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(60);
+
+/// How long establishing a connection to a backend may take before the attempt is abandoned.
+pub(crate) const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Tracks the exponential backoff between attempts to reach a backend which batteries hold a connection to.
+#[derive(Default)]
+pub(crate) struct Backoff {
+    delay: Duration,
+    retry_at: Option<Instant>,
+}
+
+impl Backoff {
+    /// The time at which the next attempt may be made.
+    pub fn retry_at(&self) -> Instant {
+        self.retry_at.unwrap_or_else(Instant::now)
+    }
+
+    pub fn failed(&mut self) {
+        self.delay = (self.delay * 2).clamp(Duration::from_secs(1), MAX_RECONNECT_DELAY);
+        self.retry_at = Some(Instant::now() + self.delay);
+    }
+
+    pub fn reset(&mut self) {
+        self.delay = Duration::ZERO;
+        self.retry_at = None;
+    }
+}
+
+/// Opens a TCP connection to the provided host and port, trying each of its addresses in turn and giving up on
+/// each one after [`CONNECT_TIMEOUT`] (rather than the operating system's much longer default).
+// Not every battery which reconnects with a backoff opens plain TCP connections.
+#[allow(dead_code)]
+pub(crate) fn connect(host: &str, port: u16) -> io::Result<TcpStream> {
+    let mut last_error = io::Error::new(
+        io::ErrorKind::NotFound,
+        format!("no addresses found for '{host}:{port}'"),
+    );
+
+    for address in (host, port).to_socket_addrs()? {
+        match TcpStream::connect_timeout(&address, CONNECT_TIMEOUT) {
+            Ok(socket) => return Ok(socket),
+            Err(err) => last_error = err,
+        }
+    }
+
+    Err(last_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reconnect_backoff() {
+        let mut backoff = Backoff::default();
+        assert!(backoff.retry_at() <= Instant::now());
+
+        backoff.failed();
+        assert_eq!(backoff.delay, Duration::from_secs(1));
+        assert!(backoff.retry_at() > Instant::now());
+
+        for _ in 0..10 {
+            backoff.failed();
+        }
+        assert_eq!(backoff.delay, MAX_RECONNECT_DELAY);
+
+        backoff.reset();
+        assert!(backoff.retry_at() <= Instant::now());
+    }
+}
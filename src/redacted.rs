@@ -0,0 +1,418 @@
+use std::{
+    borrow::Cow,
+    collections::HashSet,
+    sync::{atomic::AtomicBool, Arc},
+};
+
+use crate::{
+    deferred::HeldError, Battery, BatteryBuilder, Envelope, EnvelopePayload, ErrorContext,
+    Metadata, Metric, WeakSession,
+};
+
+/// The placeholder which replaces redacted error messages.
+const REDACTED: &str = "<redacted>";
+
+/// The placeholder which replaces file paths when [`Redacted::without_file_paths`] is used.
+const REDACTED_PATH: &str = "<path>";
+
+/// A combinator which wraps another battery, removing the fields, error messages and file paths which it
+/// shouldn't receive before they are forwarded to it.
+///
+/// This allows each backend to receive only the information it needs, for example sending full error reports
+/// to your error tracker while your analytics provider only learns that an error occurred, or ensuring that
+/// file paths (which often contain usernames) never reach a third party. The following policies may be
+/// combined:
+///
+/// - [`Redacted::allow_fields`] only forwards the listed error fields, event properties, context entries and
+///   metric attributes, while [`Redacted::deny_fields`] removes the listed ones.
+/// - [`Redacted::without_error_messages`] replaces error messages (and those of their causes) with a
+///   placeholder, and removes their breadcrumbs.
+/// - [`Redacted::without_file_paths`] replaces anything which looks like a file path with a placeholder and
+///   removes backtraces.
+///
+/// Artifacts attached using [`Session::attach_artifact`](crate::Session::attach_artifact) are never forwarded, since
+/// their contents cannot be inspected.
+///
+/// ## Example
+/// ```no_run
+/// use tracing_batteries::{Session, Redacted, Sentry};
+///
+/// let session = Session::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+///   .with_battery(Redacted::new(Sentry::new("https://yourdsn@sentry.example.com"))
+///     .deny_fields(["email", "user_id"])
+///     .without_file_paths());
+///
+/// session.shutdown();
+/// ```
+pub struct Redacted<B: BatteryBuilder> {
+    battery: B,
+    policy: RedactionPolicy,
+}
+
+impl<B: BatteryBuilder> Redacted<B> {
+    /// Wraps the provided battery, initially forwarding everything to it.
+    pub fn new(battery: B) -> Self {
+        Self {
+            battery,
+            policy: RedactionPolicy {
+                allowed: None,
+                denied: HashSet::new(),
+                error_messages: true,
+                file_paths: true,
+            },
+        }
+    }
+
+    /// Only forwards the error fields, event properties, context entries and metric attributes with these names.
+    pub fn allow_fields<I, T>(self, fields: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<Cow<'static, str>>,
+    {
+        Self {
+            policy: RedactionPolicy {
+                allowed: Some(fields.into_iter().map(Into::into).collect()),
+                ..self.policy
+            },
+            ..self
+        }
+    }
+
+    /// Removes the error fields, event properties, context entries and metric attributes with these names.
+    pub fn deny_fields<I, T>(self, fields: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<Cow<'static, str>>,
+    {
+        let mut denied = self.policy.denied;
+        denied.extend(fields.into_iter().map(Into::into));
+
+        Self {
+            policy: RedactionPolicy {
+                denied,
+                ..self.policy
+            },
+            ..self
+        }
+    }
+
+    /// Replaces error messages with a placeholder, so that the wrapped battery only learns that an error occurred.
+    pub fn without_error_messages(self) -> Self {
+        Self {
+            policy: RedactionPolicy {
+                error_messages: false,
+                ..self.policy
+            },
+            ..self
+        }
+    }
+
+    /// Replaces file paths with a placeholder and removes backtraces.
+    pub fn without_file_paths(self) -> Self {
+        Self {
+            policy: RedactionPolicy {
+                file_paths: false,
+                ..self.policy
+            },
+            ..self
+        }
+    }
+}
+
+impl<B: BatteryBuilder> BatteryBuilder for Redacted<B> {
+    fn setup(self, metadata: &Metadata, enabled: Arc<AtomicBool>) -> Box<dyn Battery> {
+        Box::new(RedactedBattery {
+            inner: self.battery.setup(metadata, enabled),
+            policy: self.policy,
+        })
+    }
+}
+
+struct RedactionPolicy {
+    allowed: Option<HashSet<Cow<'static, str>>>,
+    denied: HashSet<Cow<'static, str>>,
+    error_messages: bool,
+    file_paths: bool,
+}
+
+impl RedactionPolicy {
+    fn allows(&self, field: &str) -> bool {
+        !self.denied.contains(field)
+            && self
+                .allowed
+                .as_ref()
+                .is_none_or(|allowed| allowed.contains(field))
+    }
+
+    fn text<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        if self.file_paths {
+            Cow::Borrowed(text)
+        } else {
+            Cow::Owned(scrub_paths(text))
+        }
+    }
+
+    fn message(&self, message: &str) -> String {
+        if self.error_messages {
+            self.text(message).into_owned()
+        } else {
+            REDACTED.to_string()
+        }
+    }
+
+    fn error(&self, error: &dyn std::error::Error) -> HeldError {
+        HeldError::new(error).map_messages(&|message| self.message(message))
+    }
+
+    fn context(&self, context: &ErrorContext) -> ErrorContext {
+        ErrorContext {
+            fields: context
+                .fields
+                .iter()
+                .filter(|(key, _)| self.allows(key))
+                .map(|(key, value)| (*key, self.text(value).into_owned()))
+                .collect(),
+            breadcrumbs: if self.error_messages {
+                context
+                    .breadcrumbs
+                    .iter()
+                    .cloned()
+                    .map(|mut breadcrumb| {
+                        breadcrumb.message = self.text(&breadcrumb.message).into_owned();
+                        breadcrumb
+                    })
+                    .collect()
+            } else {
+                Vec::new()
+            },
+            backtrace: context.backtrace.clone().filter(|_| self.file_paths),
+        }
+    }
+
+    fn envelope(&self, envelope: &Envelope) -> Envelope {
+        let mut envelope = envelope.clone();
+        envelope.context.retain(|key, _| self.allows(key));
+        for value in envelope.context.values_mut() {
+            *value = self.text(value).into_owned().into();
+        }
+
+        match &mut envelope.payload {
+            EnvelopePayload::Error {
+                message,
+                causes,
+                fields,
+                backtrace,
+            } => {
+                *message = self.message(message);
+                for cause in causes.iter_mut() {
+                    *cause = self.message(cause);
+                }
+
+                fields.retain(|key, _| self.allows(key));
+                for value in fields.values_mut() {
+                    *value = self.text(value).into_owned();
+                }
+
+                if !self.file_paths {
+                    *backtrace = None;
+                }
+            }
+            EnvelopePayload::Event { properties, .. } => {
+                properties.retain(|key, _| self.allows(key));
+                if !self.file_paths {
+                    for value in properties.values_mut() {
+                        if let serde_json::Value::String(text) = value {
+                            *text = scrub_paths(text);
+                        }
+                    }
+                }
+            }
+            EnvelopePayload::PageView { page } => {
+                *page = self.text(page).into_owned();
+            }
+            EnvelopePayload::Sealed { .. } | EnvelopePayload::Unknown => {}
+        }
+
+        envelope
+    }
+}
+
+/// Replaces each whitespace separated word which looks like a file path with a placeholder.
+fn scrub_paths(text: &str) -> String {
+    let mut scrubbed = String::with_capacity(text.len());
+    let mut rest = text;
+    while !rest.is_empty() {
+        let word_end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        let (word, tail) = rest.split_at(word_end);
+
+        let start = word
+            .find(|c: char| !matches!(c, '"' | '\'' | '(' | '[' | '`'))
+            .unwrap_or(word.len());
+        let end = word
+            .rfind(|c: char| !matches!(c, '"' | '\'' | ')' | ']' | '`' | ',' | ';' | '.' | ':'))
+            .map_or(start, |end| end + 1)
+            .max(start);
+
+        if is_path(&word[start..end]) {
+            scrubbed.push_str(&word[..start]);
+            scrubbed.push_str(REDACTED_PATH);
+            scrubbed.push_str(&word[end..]);
+        } else {
+            scrubbed.push_str(word);
+        }
+
+        let whitespace_end = tail
+            .find(|c: char| !c.is_whitespace())
+            .unwrap_or(tail.len());
+        scrubbed.push_str(&tail[..whitespace_end]);
+        rest = &tail[whitespace_end..];
+    }
+
+    scrubbed
+}
+
+fn is_path(word: &str) -> bool {
+    if word.contains("://") {
+        return false;
+    }
+
+    let bytes = word.as_bytes();
+    let windows_drive = bytes.len() > 2
+        && bytes[0].is_ascii_alphabetic()
+        && bytes[1] == b':'
+        && matches!(bytes[2], b'\\' | b'/');
+
+    windows_drive
+        || word.starts_with("\\\\")
+        || word.starts_with("~/")
+        || word.starts_with("./")
+        || word.starts_with("../")
+        || (word.starts_with('/') && word[1..].contains(|c: char| c.is_alphanumeric()))
+}
+
+struct RedactedBattery {
+    inner: Box<dyn Battery>,
+    policy: RedactionPolicy,
+}
+
+impl Battery for RedactedBattery {
+    fn record_error(&self, error: &dyn std::error::Error) {
+        self.inner.record_error(&self.policy.error(error));
+    }
+
+    fn record_error_with(&self, error: &dyn std::error::Error, context: &ErrorContext) {
+        self.inner
+            .record_error_with(&self.policy.error(error), &self.policy.context(context));
+    }
+
+    fn record_envelope(&self, envelope: &Envelope) {
+        self.inner.record_envelope(&self.policy.envelope(envelope));
+    }
+
+    fn record_metric(&self, metric: &Metric) {
+        let values: Vec<(&'static str, Cow<'_, str>)> = metric
+            .attributes
+            .iter()
+            .filter(|(key, _)| self.policy.allows(key))
+            .map(|(key, value)| (*key, self.policy.text(value)))
+            .collect();
+        let attributes: Vec<(&'static str, &str)> = values
+            .iter()
+            .map(|(key, value)| (*key, value.as_ref()))
+            .collect();
+
+        self.inner.record_metric(&Metric {
+            attributes: &attributes,
+            ..*metric
+        });
+    }
+
+    fn accepts_weighted_histograms(&self) -> bool {
+        self.inner.accepts_weighted_histograms()
+    }
+
+    fn release(&self) {
+        self.inner.release();
+    }
+
+    fn shutdown(&self) {
+        self.inner.shutdown();
+    }
+
+    fn attached(&self, session: WeakSession) {
+        self.inner.attached(session);
+    }
+
+    fn validate(&self) -> Vec<crate::ValidationCheck> {
+        self.inner.validate()
+    }
+
+    #[cfg(feature = "version-check")]
+    fn update_available(&self) -> Option<crate::AvailableUpdate> {
+        self.inner.update_available()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{test_support::EnvelopeBattery, Session};
+
+    #[test]
+    fn redacts_envelopes() {
+        let battery = EnvelopeBattery::default();
+        let session = Session::new("example", "0.0.1").with_battery(
+            Redacted::new(battery.clone())
+                .deny_fields(["email"])
+                .without_error_messages()
+                .without_file_paths(),
+        );
+
+        session.record_event(
+            "export_pdf",
+            [
+                ("email", "user@example.com"),
+                ("output", "/home/user/report.pdf"),
+            ],
+        );
+        session.record_error_with(
+            &std::io::Error::other("disk full"),
+            [("path", "C:\\Users\\user\\report.pdf".to_string())],
+        );
+
+        let envelopes = battery.envelopes();
+        match &envelopes[0].payload {
+            EnvelopePayload::Event { properties, .. } => {
+                assert!(!properties.contains_key("email"));
+                assert_eq!(properties["output"], REDACTED_PATH);
+            }
+            payload => panic!("unexpected payload: {payload:?}"),
+        }
+
+        match &envelopes[1].payload {
+            EnvelopePayload::Error {
+                message, fields, ..
+            } => {
+                assert_eq!(message, REDACTED);
+                assert_eq!(fields["path"], REDACTED_PATH);
+            }
+            payload => panic!("unexpected payload: {payload:?}"),
+        }
+    }
+
+    #[test]
+    fn file_paths() {
+        assert_eq!(
+            scrub_paths("could not open '/home/user/.config/app.toml': denied"),
+            "could not open '<path>': denied"
+        );
+        assert_eq!(
+            scrub_paths("failed to read C:\\Users\\user\\file.txt."),
+            "failed to read <path>."
+        );
+        assert_eq!(
+            scrub_paths("GET https://example.com/api failed / retrying"),
+            "GET https://example.com/api failed / retrying"
+        );
+    }
+}
@@ -0,0 +1,155 @@
+use std::path::Path;
+
+use opentelemetry::KeyValue;
+
+const SERVICE_ACCOUNT_NAMESPACE: &str = "/var/run/secrets/kubernetes.io/serviceaccount/namespace";
+
+/// Detects attributes describing the container and Kubernetes pod which the process is running in.
+///
+/// Container IDs are detected from the process' cgroup membership (falling back to its mount table
+/// for cgroup v2 hosts), while Kubernetes attributes are detected from the downward API environment
+/// variables and the pod's service account mount.
+pub(crate) fn detect_resources() -> Vec<KeyValue> {
+    let mut attributes = Vec::new();
+
+    let container_id = read("/proc/self/cgroup")
+        .and_then(|cgroup| parse_cgroup_container_id(&cgroup))
+        .or_else(|| {
+            read("/proc/self/mountinfo").and_then(|mounts| parse_mountinfo_container_id(&mounts))
+        });
+    if let Some(container_id) = container_id {
+        attributes.push(KeyValue::new("container.id", container_id));
+    }
+
+    if std::env::var_os("KUBERNETES_SERVICE_HOST").is_some() {
+        let namespace = env(&["K8S_NAMESPACE_NAME", "POD_NAMESPACE"]).or_else(|| {
+            read(SERVICE_ACCOUNT_NAMESPACE).map(|namespace| namespace.trim().to_string())
+        });
+        if let Some(namespace) = namespace {
+            attributes.push(KeyValue::new("k8s.namespace.name", namespace));
+        }
+
+        if let Some(pod) = env(&["K8S_POD_NAME", "POD_NAME", "HOSTNAME"]) {
+            attributes.push(KeyValue::new("k8s.pod.name", pod));
+        }
+
+        if let Some(uid) = env(&["K8S_POD_UID", "POD_UID"]) {
+            attributes.push(KeyValue::new("k8s.pod.uid", uid));
+        }
+
+        if let Some(node) = env(&["K8S_NODE_NAME", "NODE_NAME"]) {
+            attributes.push(KeyValue::new("k8s.node.name", node));
+        }
+    }
+
+    attributes
+}
+
+fn read<P: AsRef<Path>>(path: P) -> Option<String> {
+    std::fs::read_to_string(path).ok()
+}
+
+fn env(keys: &[&str]) -> Option<String> {
+    keys.iter()
+        .filter_map(|key| std::env::var(key).ok())
+        .find(|value| !value.is_empty())
+}
+
+fn is_container_id(segment: &str) -> bool {
+    segment.len() == 64 && segment.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+fn parse_cgroup_container_id(contents: &str) -> Option<String> {
+    contents
+        .lines()
+        .flat_map(|line| line.split(['/', ' ', ':']))
+        .map(|segment| {
+            let segment = segment.strip_suffix(".scope").unwrap_or(segment);
+            segment.rsplit('-').next().unwrap_or(segment)
+        })
+        .find(|segment| is_container_id(segment))
+        .map(|id| id.to_string())
+}
+
+/// Finds the container ID in the paths of the `hostname`, `hosts`, and `resolv.conf` files which the container
+/// runtime bind mounts from its `containers/{id}` directory.
+///
+/// Other mounts are ignored, since the overlay filesystems which back a container's root (and are visible to
+/// every process on the host) are identified by their image layers rather than by the container.
+fn parse_mountinfo_container_id(contents: &str) -> Option<String> {
+    contents
+        .lines()
+        .filter_map(|line| line.split(' ').nth(3))
+        .find_map(|root| {
+            root.split('/')
+                .collect::<Vec<_>>()
+                .windows(2)
+                .find(|window| {
+                    matches!(window[0], "containers" | "overlay-containers")
+                        && is_container_id(window[1])
+                })
+                .map(|window| window[1].to_string())
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONTAINER_ID: &str = "8f6c0cba9a0bd2b24a6c2ad4fa5ae3a1d9e6b4c37d0c5a56a9e2c6b8a1f0e3d2";
+
+    #[test]
+    fn container_id_from_docker_cgroup() {
+        let cgroup = format!("12:cpu,cpuacct:/docker/{CONTAINER_ID}\n0::/docker/{CONTAINER_ID}");
+        assert_eq!(
+            parse_cgroup_container_id(&cgroup).as_deref(),
+            Some(CONTAINER_ID)
+        );
+    }
+
+    #[test]
+    fn container_id_from_systemd_cgroup() {
+        let cgroup = format!(
+            "0::/kubepods.slice/kubepods-pod1234.slice/cri-containerd-{CONTAINER_ID}.scope"
+        );
+        assert_eq!(
+            parse_cgroup_container_id(&cgroup).as_deref(),
+            Some(CONTAINER_ID)
+        );
+    }
+
+    #[test]
+    fn container_id_from_mountinfo() {
+        const LAYER_ID: &str = "0d4e7f1cb6a9f3e8e2a4b7c5d6f8091a2b3c4d5e6f708192a3b4c5d6e7f80912";
+
+        let mounts = format!(
+            "1466 1392 0:163 / / rw,relatime master:557 - overlay overlay rw,lowerdir=/var/lib/docker/overlay2/l/GZ7XQ:/var/lib/docker/overlay2/l/4LMRU,upperdir=/var/lib/docker/overlay2/{LAYER_ID}/diff,workdir=/var/lib/docker/overlay2/{LAYER_ID}/work
+1467 1466 0:166 / /proc rw,nosuid,nodev,noexec,relatime - proc proc rw
+1468 1466 0:167 / /dev rw,nosuid - tmpfs tmpfs rw,size=65536k,mode=755
+1474 1466 0:165 / /sys/fs/cgroup ro,nosuid,nodev,noexec,relatime - cgroup2 cgroup rw
+1478 1466 254:1 /docker/containers/{CONTAINER_ID}/resolv.conf /etc/resolv.conf rw,relatime - ext4 /dev/vda1 rw
+1479 1466 254:1 /docker/containers/{CONTAINER_ID}/hostname /etc/hostname rw,relatime - ext4 /dev/vda1 rw
+1480 1466 254:1 /docker/containers/{CONTAINER_ID}/hosts /etc/hosts rw,relatime - ext4 /dev/vda1 rw"
+        );
+        assert_eq!(
+            parse_mountinfo_container_id(&mounts).as_deref(),
+            Some(CONTAINER_ID)
+        );
+
+        // Processes on a Docker host can see the overlay mounts of its containers, but aren't containerized.
+        let host = format!(
+            "29 1 254:1 / / rw,relatime shared:1 - ext4 /dev/vda1 rw
+612 29 0:52 / /var/lib/docker/overlay2/{LAYER_ID}/merged rw,relatime shared:310 - overlay overlay rw,lowerdir=/var/lib/docker/overlay2/l/GZ7XQ,upperdir=/var/lib/docker/overlay2/{LAYER_ID}/diff,workdir=/var/lib/docker/overlay2/{LAYER_ID}/work"
+        );
+        assert_eq!(parse_mountinfo_container_id(&host), None);
+    }
+
+    #[test]
+    fn no_container_id() {
+        assert_eq!(parse_cgroup_container_id("0::/\n"), None);
+        assert_eq!(
+            parse_cgroup_container_id("0::/user.slice/user-1000.slice"),
+            None
+        );
+    }
+}
@@ -0,0 +1,201 @@
+use std::{
+    borrow::Cow,
+    hash::{BuildHasher, Hasher},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+use crate::{
+    hash::fnv1a, Battery, BatteryBuilder, Envelope, EnvelopePayload, ErrorContext, Metadata,
+    Metric, WeakSession,
+};
+
+/// A combinator which wraps an analytics battery, only forwarding a fraction of sessions (or events) to it
+/// while always forwarding errors.
+///
+/// This allows you to control the volume (and cost) of the analytics sent to a backend while still reporting
+/// every error. By default, the decision is made once per session so that each sampled session is reported in
+/// full, giving accurate funnels and session counts for the sampled fraction. Sessions are selected at random
+/// unless a key (such as a user or device ID) is provided using [`Sampled::with_key`], in which case the same
+/// key is always either sampled or not, ensuring that the same users are reported on across runs.
+///
+/// Alternatively, [`Sampled::per_event`] forwards an evenly spaced fraction of the custom events and page views
+/// recorded in every session. Errors and metrics are never sampled.
+///
+/// ## Example
+/// ```no_run
+/// use tracing_batteries::{Session, Sampled, Sentry};
+///
+/// let session = Session::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+///   .with_battery(Sampled::new(Sentry::new("https://yourdsn@sentry.example.com"), 0.1));
+///
+/// session.shutdown();
+/// ```
+pub struct Sampled<B: BatteryBuilder> {
+    battery: B,
+    ratio: f64,
+    key: Option<Cow<'static, str>>,
+    per_event: bool,
+}
+
+impl<B: BatteryBuilder> Sampled<B> {
+    /// Wraps the provided battery, forwarding the provided ratio (between 0 and 1) of sessions to it.
+    pub fn new(battery: B, ratio: f64) -> Self {
+        Self {
+            battery,
+            ratio: ratio.clamp(0.0, 1.0),
+            key: None,
+            per_event: false,
+        }
+    }
+
+    /// Samples sessions deterministically based on the provided key, such as a user or device ID.
+    pub fn with_key<K: Into<Cow<'static, str>>>(self, key: K) -> Self {
+        Self {
+            key: Some(key.into()),
+            ..self
+        }
+    }
+
+    /// Samples individual custom events and page views, rather than entire sessions.
+    pub fn per_event(self) -> Self {
+        Self {
+            per_event: true,
+            ..self
+        }
+    }
+}
+
+impl<B: BatteryBuilder> BatteryBuilder for Sampled<B> {
+    fn setup(self, metadata: &Metadata, enabled: Arc<AtomicBool>) -> Box<dyn Battery> {
+        let sampler = if self.per_event {
+            Sampler::PerEvent {
+                ratio: self.ratio,
+                seen: AtomicU64::new(0),
+            }
+        } else {
+            let position = match &self.key {
+                Some(key) => key_position(key),
+                None => {
+                    let mut hasher = std::collections::hash_map::RandomState::new().build_hasher();
+                    hasher.write_u32(std::process::id());
+                    hasher.finish() as f64 / u64::MAX as f64
+                }
+            };
+
+            Sampler::PerSession(position < self.ratio)
+        };
+
+        Box::new(SampledBattery {
+            inner: self.battery.setup(metadata, enabled),
+            sampler,
+        })
+    }
+}
+
+/// Maps a key onto a stable position in the range `[0, 1)`, which is sampled if it falls below the ratio.
+fn key_position(key: &str) -> f64 {
+    (fnv1a(key.bytes()) >> 11) as f64 / (1_u64 << 53) as f64
+}
+
+enum Sampler {
+    PerSession(bool),
+    PerEvent { ratio: f64, seen: AtomicU64 },
+}
+
+impl Sampler {
+    fn sample(&self) -> bool {
+        match self {
+            Sampler::PerSession(sampled) => *sampled,
+            Sampler::PerEvent { ratio, seen } => {
+                // Forwarding an event whenever the running total of the ratio crosses a whole number spaces the
+                // forwarded events evenly, so that exactly the configured fraction is forwarded.
+                let n = seen.fetch_add(1, Ordering::Relaxed) as f64;
+                ((n + 1.0) * ratio).floor() > (n * ratio).floor()
+            }
+        }
+    }
+}
+
+struct SampledBattery {
+    inner: Box<dyn Battery>,
+    sampler: Sampler,
+}
+
+impl Battery for SampledBattery {
+    fn record_error(&self, error: &dyn std::error::Error) {
+        self.inner.record_error(error);
+    }
+
+    fn record_error_with(&self, error: &dyn std::error::Error, context: &ErrorContext) {
+        self.inner.record_error_with(error, context);
+    }
+
+    fn record_envelope(&self, envelope: &Envelope) {
+        if matches!(envelope.payload, EnvelopePayload::Error { .. }) || self.sampler.sample() {
+            self.inner.record_envelope(envelope);
+        }
+    }
+
+    fn record_metric(&self, metric: &Metric) {
+        self.inner.record_metric(metric);
+    }
+
+    fn accepts_weighted_histograms(&self) -> bool {
+        self.inner.accepts_weighted_histograms()
+    }
+
+    fn record_artifact(&self, artifact: &crate::Artifact) -> bool {
+        self.inner.record_artifact(artifact)
+    }
+
+    fn release(&self) {
+        self.inner.release();
+    }
+
+    fn shutdown(&self) {
+        self.inner.shutdown();
+    }
+
+    fn attached(&self, session: WeakSession) {
+        self.inner.attached(session);
+    }
+
+    fn validate(&self) -> Vec<crate::ValidationCheck> {
+        self.inner.validate()
+    }
+
+    #[cfg(feature = "version-check")]
+    fn update_available(&self) -> Option<crate::AvailableUpdate> {
+        self.inner.update_available()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn per_event_sampling() {
+        let sampler = Sampler::PerEvent {
+            ratio: 0.25,
+            seen: AtomicU64::new(0),
+        };
+
+        let sampled = (0..100).filter(|_| sampler.sample()).count();
+        assert_eq!(sampled, 25);
+    }
+
+    #[test]
+    fn keyed_sessions() {
+        assert_eq!(key_position("user-1234"), key_position("user-1234"));
+        assert!((0.0..1.0).contains(&key_position("user-1234")));
+
+        let sampled = (0..10_000)
+            .filter(|user| key_position(&format!("user-{user}")) < 0.1)
+            .count();
+        assert!((800..1200).contains(&sampled), "{sampled}");
+    }
+}
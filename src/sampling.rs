@@ -0,0 +1,197 @@
+use std::sync::{Arc, PoisonError, RwLock};
+
+#[cfg(feature = "opentelemetry")]
+use opentelemetry_sdk::trace::ShouldSample;
+
+use crate::Session;
+
+type SamplingHook = Arc<dyn Fn(&SamplingDecision<'_>) + Send + Sync>;
+
+static HOOKS: RwLock<Vec<SamplingHook>> = RwLock::new(Vec::new());
+
+/// The decision made by the trace sampler about whether a new span will be recorded and exported, delivered to
+/// the hooks registered using [`Session::on_sampling_decision`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SamplingDecision<'a> {
+    /// The name of the span which the decision was made for.
+    pub span: &'a str,
+    /// The ID of the trace which the span belongs to.
+    pub trace_id: u128,
+    /// Whether the span will be recorded and exported.
+    pub sampled: bool,
+}
+
+impl Session {
+    /// Determines whether the telemetry recorded within the current span will be exported, allowing you to skip
+    /// collecting expensive debugging data (such as large attribute maps) which would be discarded anyway.
+    ///
+    /// This returns `false` when the session has been disabled, or when the current span belongs to a trace which
+    /// the OpenTelemetry sampler has chosen not to sample. When there is no active trace, telemetry may still be
+    /// delivered to the session's other batteries, so this returns `true`.
+    ///
+    /// ## Example
+    /// ```no_run
+    /// use tracing_batteries::{Session, Sentry};
+    ///
+    /// let session = Session::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+    ///   .with_battery(Sentry::new("https://yourdsn@sentry.example.com"));
+    ///
+    /// if session.is_sampled() {
+    ///   tracing::debug!(environment = ?std::env::vars().collect::<Vec<_>>(), "Loaded environment");
+    /// }
+    /// ```
+    pub fn is_sampled(&self) -> bool {
+        if !self.is_enabled() {
+            return false;
+        }
+
+        #[cfg(feature = "opentelemetry")]
+        {
+            use opentelemetry::trace::TraceContextExt;
+            use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+            let context = tracing::Span::current().context();
+            let span = context.span();
+            let span_context = span.span_context();
+            if span_context.is_valid() {
+                return span_context.is_sampled();
+            }
+        }
+
+        true
+    }
+
+    /// Registers a hook which is called each time the trace sampler decides whether a new span will be exported,
+    /// allowing your application to adapt the detail it collects (or to count how much is being discarded).
+    ///
+    /// Sampling decisions are made by the [`OpenTelemetry`](crate::OpenTelemetry) battery's sampler, and hooks
+    /// remain registered for the lifetime of the process. Hooks are called on the thread which created the span,
+    /// so they should return quickly.
+    ///
+    /// ## Example
+    /// ```no_run
+    /// use tracing_batteries::{Session, OpenTelemetry};
+    ///
+    /// let session = Session::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+    ///   .with_battery(OpenTelemetry::new("localhost:4317"))
+    ///   .on_sampling_decision(|decision| {
+    ///     if !decision.sampled {
+    ///       eprintln!("Dropped span {}", decision.span);
+    ///     }
+    ///   });
+    /// ```
+    pub fn on_sampling_decision<F>(self, hook: F) -> Self
+    where
+        F: Fn(&SamplingDecision<'_>) + Send + Sync + 'static,
+    {
+        HOOKS
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(Arc::new(hook));
+        self
+    }
+}
+
+#[cfg_attr(not(feature = "opentelemetry"), allow(dead_code))]
+fn notify(decision: &SamplingDecision<'_>) {
+    for hook in HOOKS.read().unwrap_or_else(PoisonError::into_inner).iter() {
+        hook(decision);
+    }
+}
+
+/// Wraps the configured OpenTelemetry sampler, notifying the hooks registered with
+/// [`Session::on_sampling_decision`] of each decision it makes.
+#[cfg(feature = "opentelemetry")]
+#[derive(Debug, Clone)]
+pub(crate) struct NotifyingSampler {
+    inner: opentelemetry_sdk::trace::Sampler,
+}
+
+#[cfg(feature = "opentelemetry")]
+impl NotifyingSampler {
+    pub fn new(inner: opentelemetry_sdk::trace::Sampler) -> Self {
+        Self { inner }
+    }
+}
+
+#[cfg(feature = "opentelemetry")]
+impl ShouldSample for NotifyingSampler {
+    fn should_sample(
+        &self,
+        parent_context: Option<&opentelemetry::Context>,
+        trace_id: opentelemetry::trace::TraceId,
+        name: &str,
+        span_kind: &opentelemetry::trace::SpanKind,
+        attributes: &[opentelemetry::KeyValue],
+        links: &[opentelemetry::trace::Link],
+    ) -> opentelemetry::trace::SamplingResult {
+        let result =
+            self.inner
+                .should_sample(parent_context, trace_id, name, span_kind, attributes, links);
+
+        notify(&SamplingDecision {
+            span: name,
+            trace_id: u128::from_be_bytes(trace_id.to_bytes()),
+            sampled: result.decision == opentelemetry::trace::SamplingDecision::RecordAndSample,
+        });
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn disabled_sessions_are_not_sampled() {
+        let session = Session::new("example", "0.0.1").with_battery(NoopBattery);
+        assert!(session.is_sampled());
+
+        session
+            .enable()
+            .store(false, std::sync::atomic::Ordering::Relaxed);
+        assert!(!session.is_sampled());
+    }
+
+    #[cfg(feature = "opentelemetry")]
+    #[test]
+    fn notifies_sampling_hooks() {
+        use std::sync::Mutex;
+
+        let decisions = Arc::new(Mutex::new(Vec::new()));
+        let recorded = decisions.clone();
+        Session::new("example", "0.0.1")
+            .with_battery(NoopBattery)
+            .on_sampling_decision(move |decision| {
+                if decision.span == "sampling-hook-test" {
+                    recorded.lock().unwrap().push(decision.sampled);
+                }
+            });
+
+        NotifyingSampler::new(opentelemetry_sdk::trace::Sampler::AlwaysOff).should_sample(
+            None,
+            opentelemetry::trace::TraceId::from_u128(1),
+            "sampling-hook-test",
+            &opentelemetry::trace::SpanKind::Internal,
+            &[],
+            &[],
+        );
+
+        assert_eq!(*decisions.lock().unwrap(), [false]);
+    }
+
+    struct NoopBattery;
+
+    impl crate::BatteryBuilder for NoopBattery {
+        fn setup(
+            self,
+            _metadata: &crate::Metadata,
+            _enabled: Arc<std::sync::atomic::AtomicBool>,
+        ) -> Box<dyn crate::Battery> {
+            Box::new(NoopBattery)
+        }
+    }
+
+    impl crate::Battery for NoopBattery {}
+}
@@ -0,0 +1,247 @@
+use std::{
+    borrow::Cow,
+    future::{Future, IntoFuture},
+    pin::Pin,
+    time::{Duration, Instant},
+};
+
+use tracing::Instrument;
+
+use crate::Session;
+
+/// A periodic job which is instrumented by the session, created using [`Session::instrument_interval`].
+///
+/// The task runs forever once awaited (or spawned onto a Tokio runtime using [`IntoFuture::into_future`]),
+/// running its job once per interval.
+#[must_use = "the task does nothing until it is awaited or spawned"]
+pub struct ScheduledTask<F> {
+    session: Session,
+    name: Cow<'static, str>,
+    period: Duration,
+    job: F,
+    heartbeat: Option<Cow<'static, str>>,
+}
+
+impl<F> ScheduledTask<F> {
+    /// Configures the task to send a `GET` request to the provided URL after each successful run, such as the
+    /// ping URL of a heartbeat monitor (Healthchecks.io, Better Stack, Cronitor, etc.) which alerts you when the
+    /// task stops running.
+    pub fn with_heartbeat<U: Into<Cow<'static, str>>>(self, url: U) -> Self {
+        Self {
+            heartbeat: Some(url.into()),
+            ..self
+        }
+    }
+}
+
+impl Session {
+    /// Runs a periodic job once per interval, instrumenting each run.
+    ///
+    /// Each run of the job executes within its own root `scheduled_task` span, and its duration is recorded in the
+    /// `task.duration` histogram while the `task.runs` counter is incremented, both with `task` and `outcome`
+    /// (`success` or `failure`) attributes. Errors returned by the job are recorded through the session with a
+    /// `task.name` field, and runs which take longer than the interval are counted in the `task.overruns` counter
+    /// and logged as a warning. Use [`ScheduledTask::with_heartbeat`] to also ping a heartbeat monitor after each
+    /// successful run.
+    ///
+    /// If a run overruns its interval, the next run starts as soon as it completes and the schedule is shifted
+    /// accordingly, rather than running several times in quick succession to catch up.
+    ///
+    /// <div class="warning">
+    ///
+    /// This method requires the `tokio` feature to be enabled, and must be awaited within a Tokio runtime.
+    ///
+    /// </div>
+    ///
+    /// ## Example
+    /// ```no_run
+    /// use std::{future::IntoFuture, time::Duration};
+    /// use tracing_batteries::{Session, OpenTelemetry};
+    ///
+    /// async fn sync_accounts() -> Result<(), std::io::Error> {
+    ///   // ...
+    ///   Ok(())
+    /// }
+    ///
+    /// # async fn run() {
+    /// let session = Session::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+    ///   .with_battery(OpenTelemetry::new("localhost:4317"));
+    ///
+    /// tokio::spawn(
+    ///   session
+    ///     .instrument_interval("sync", Duration::from_secs(300), sync_accounts)
+    ///     .with_heartbeat("https://hc-ping.com/your-uuid")
+    ///     .into_future(),
+    /// );
+    /// # }
+    /// ```
+    pub fn instrument_interval<N, F, Fut, T, E>(
+        &self,
+        name: N,
+        period: Duration,
+        job: F,
+    ) -> ScheduledTask<F>
+    where
+        N: Into<Cow<'static, str>>,
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, E>>,
+        E: std::error::Error,
+    {
+        ScheduledTask {
+            session: self.clone(),
+            name: name.into(),
+            period,
+            job,
+            heartbeat: None,
+        }
+    }
+}
+
+impl<F, Fut, T, E> IntoFuture for ScheduledTask<F>
+where
+    F: FnMut() -> Fut + Send + 'static,
+    Fut: Future<Output = Result<T, E>> + Send + 'static,
+    T: Send + 'static,
+    E: std::error::Error + Send + 'static,
+{
+    type Output = ();
+    type IntoFuture = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+    fn into_future(mut self) -> Self::IntoFuture {
+        Box::pin(async move {
+            let mut interval = tokio::time::interval(self.period);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+            let client = self.heartbeat.as_ref().map(|_| reqwest::Client::new());
+
+            loop {
+                interval.tick().await;
+
+                let span = tracing::info_span!(
+                    parent: None,
+                    "scheduled_task",
+                    otel.name = %self.name,
+                    task = %self.name,
+                    task.overrun = tracing::field::Empty
+                );
+
+                let start = Instant::now();
+                let result = (self.job)().instrument(span.clone()).await;
+                let elapsed = start.elapsed();
+
+                let succeeded = match result {
+                    Ok(_) => true,
+                    Err(err) => {
+                        self.session
+                            .record_error_with(&err, [("task.name", self.name.to_string())]);
+                        false
+                    }
+                };
+
+                let outcome = if succeeded { "success" } else { "failure" };
+
+                let attributes = [("task", self.name.as_ref()), ("outcome", outcome)];
+                self.session
+                    .histogram("task.duration")
+                    .record_with(elapsed.as_secs_f64(), &attributes);
+                self.session.counter("task.runs").inc_with(1, &attributes);
+
+                if elapsed > self.period {
+                    span.record("task.overrun", true);
+                    self.session
+                        .counter("task.overruns")
+                        .inc_with(1, &[("task", self.name.as_ref())]);
+                    tracing::warn!(
+                        parent: &span,
+                        elapsed_ms = elapsed.as_millis() as u64,
+                        "The '{}' task took {:?} to run, which is longer than its {:?} interval.",
+                        self.name,
+                        elapsed,
+                        self.period
+                    );
+                }
+
+                if let (true, Some(client), Some(url)) = (succeeded, &client, &self.heartbeat) {
+                    if let Err(err) = client
+                        .get(url.as_ref())
+                        .send()
+                        .await
+                        .and_then(|response| response.error_for_status())
+                    {
+                        crate::diagnostics::record_export_error("heartbeat", err.without_url());
+                    }
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{atomic::AtomicBool, Arc, Mutex};
+
+    use super::*;
+    use crate::{Battery, BatteryBuilder, ErrorContext, Metadata, Metric};
+
+    struct TaskBattery {
+        recorded: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl BatteryBuilder for TaskBattery {
+        fn setup(self, _metadata: &Metadata, _enabled: Arc<AtomicBool>) -> Box<dyn Battery> {
+            Box::new(self)
+        }
+    }
+
+    impl Battery for TaskBattery {
+        fn record_metric(&self, metric: &Metric) {
+            let outcome = metric
+                .attributes
+                .iter()
+                .find(|(key, _)| *key == "outcome")
+                .map(|(_, value)| *value)
+                .unwrap_or_default();
+
+            self.recorded
+                .lock()
+                .unwrap()
+                .push(format!("metric:{}:{outcome}", metric.name));
+        }
+
+        fn record_error_with(&self, error: &dyn std::error::Error, _context: &ErrorContext) {
+            self.recorded.lock().unwrap().push(format!("error:{error}"));
+        }
+    }
+
+    #[test]
+    fn instruments_each_run() {
+        let recorded = Arc::new(Mutex::new(Vec::new()));
+        let session = Session::new("example", "0.0.1").with_battery(TaskBattery {
+            recorded: recorded.clone(),
+        });
+
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()
+            .unwrap();
+
+        let task = session.instrument_interval("sync", Duration::from_secs(60), || async {
+            Err::<(), _>(std::io::Error::other("sync failed"))
+        });
+
+        runtime.block_on(async {
+            tokio::time::timeout(Duration::from_millis(50), task.into_future())
+                .await
+                .ok();
+        });
+
+        assert_eq!(
+            *recorded.lock().unwrap(),
+            vec![
+                "error:sync failed".to_string(),
+                "metric:task.duration:failure".to_string(),
+                "metric:task.runs:failure".to_string(),
+            ]
+        );
+    }
+}
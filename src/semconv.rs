@@ -0,0 +1,99 @@
+use tracing::field::Empty;
+
+/// Creates a span representing a database query, populated with the OpenTelemetry
+/// [database semantic convention](https://opentelemetry.io/docs/specs/semconv/database/database-spans/) attributes.
+///
+/// The span is named after the query's operation (the first keyword of the statement, such as `SELECT`) and the
+/// namespace (database) it targets, and has the `db.system`, `db.namespace`, `db.query.text` and
+/// `db.operation.name` attributes. Once the query completes, you may record `db.response.returned_rows`,
+/// `error.type` and `otel.status_code` on the span.
+///
+/// The statement is reported as provided, so it should use placeholders for its parameters rather than
+/// embedding (potentially sensitive) values in the query text.
+///
+/// ## Example
+/// ```rust
+/// use tracing_batteries::db_span;
+///
+/// let span = db_span("SELECT * FROM users WHERE id = $1", "postgresql", "accounts");
+/// let _entered = span.enter();
+/// // Run the query...
+/// span.record("db.response.returned_rows", 1);
+/// ```
+pub fn db_span(statement: &str, system: &str, namespace: &str) -> tracing::Span {
+    let operation = operation_name(statement);
+    let name = match &operation {
+        Some(operation) => format!("{operation} {namespace}"),
+        None => namespace.to_string(),
+    };
+
+    tracing::info_span!(
+        "db query",
+        otel.name = %name,
+        otel.kind = "client",
+        otel.status_code = Empty,
+        db.system = system,
+        db.namespace = namespace,
+        db.query.text = statement,
+        db.operation.name = operation.as_deref(),
+        db.response.returned_rows = Empty,
+        "error.type" = Empty,
+    )
+}
+
+/// Creates a span representing a cache operation (such as a `GET` against Redis or Memcached), populated with the
+/// OpenTelemetry database semantic convention attributes which cache clients share.
+///
+/// The span is named after the operation and the cache system, and has the `db.system` and `db.operation.name`
+/// attributes. Keys are not recorded, since they frequently contain identifiers. Once the operation completes, you
+/// may record whether it was a `cache.hit`, along with `error.type` and `otel.status_code`.
+///
+/// ## Example
+/// ```rust
+/// use tracing_batteries::cache_span;
+///
+/// let span = cache_span("GET", "redis");
+/// let _entered = span.enter();
+/// // Look up the value...
+/// span.record("cache.hit", true);
+/// ```
+pub fn cache_span(operation: &str, system: &str) -> tracing::Span {
+    tracing::info_span!(
+        "cache operation",
+        otel.name = %format!("{operation} {system}"),
+        otel.kind = "client",
+        otel.status_code = Empty,
+        db.system = system,
+        db.operation.name = operation,
+        cache.hit = Empty,
+        "error.type" = Empty,
+    )
+}
+
+/// Extracts the operation from a query statement, using its first keyword (such as `SELECT` or `INSERT`).
+fn operation_name(statement: &str) -> Option<String> {
+    statement
+        .split_whitespace()
+        .next()
+        .filter(|keyword| keyword.chars().all(|c| c.is_ascii_alphabetic()))
+        .map(|keyword| keyword.to_ascii_uppercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extracts_operations() {
+        assert_eq!(
+            operation_name("select * from users").as_deref(),
+            Some("SELECT")
+        );
+        assert_eq!(
+            operation_name("  INSERT INTO users VALUES ($1)").as_deref(),
+            Some("INSERT")
+        );
+        assert_eq!(operation_name("(SELECT 1)"), None);
+        assert_eq!(operation_name(""), None);
+    }
+}
@@ -0,0 +1,576 @@
+use std::{
+    borrow::Cow,
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, OnceLock, PoisonError, RwLock,
+    },
+    time::{Duration, Instant},
+};
+
+use tracing::{span, Subscriber};
+use tracing_subscriber::{layer::Context, registry::LookupSpan, Layer};
+
+use crate::{Battery, BatteryBuilder, Counter, Gauge, Metadata, WeakSession};
+
+const BUCKET_WIDTH: Duration = Duration::from_secs(60);
+
+/// A service level objective which is evaluated by the [`SloTracker`].
+///
+/// Objectives describe the proportion of spans with a given name which are expected to complete
+/// within a latency threshold over a rolling window (e.g. "99% of `request` spans complete in under 300ms
+/// over the last hour").
+#[derive(Debug, Clone)]
+pub struct SloObjective {
+    name: Cow<'static, str>,
+    span_name: Cow<'static, str>,
+    threshold: Duration,
+    target: f64,
+    window: Duration,
+}
+
+impl SloObjective {
+    /// Creates a new latency objective which expects `target` (a ratio between 0 and 1) of the spans
+    /// named `span_name` to complete within the provided `threshold`.
+    ///
+    /// By default, objectives are evaluated over a rolling one hour window.
+    pub fn latency<N: Into<Cow<'static, str>>, S: Into<Cow<'static, str>>>(
+        name: N,
+        span_name: S,
+        threshold: Duration,
+        target: f64,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            span_name: span_name.into(),
+            threshold,
+            target: target.clamp(0.0, 1.0),
+            window: Duration::from_secs(3600),
+        }
+    }
+
+    /// Configures the rolling window over which this objective is evaluated.
+    pub fn with_window(self, window: Duration) -> Self {
+        Self {
+            window: window.max(BUCKET_WIDTH),
+            ..self
+        }
+    }
+}
+
+/// A battery which evaluates [`SloObjective`]s against the spans emitted by your application and
+/// reports on how quickly their error budgets are being consumed.
+///
+/// The tracker exports the `slo.burn_rate` gauge and the `slo.spans` counter (with `objective` and `outcome`
+/// attributes) through the session's metrics batteries. When an objective's burn rate exceeds the configured
+/// alerting threshold, an error is recorded through the session so that any alerting batteries are notified.
+///
+/// <div class="warning">
+///
+/// Spans are observed through the tracing subscriber installed by the [`OpenTelemetry`](crate::OpenTelemetry)
+/// battery, so it must also be attached to the session for objectives to be evaluated.
+///
+/// </div>
+///
+/// ## Example
+/// ```no_run
+/// use std::time::Duration;
+/// use tracing_batteries::{Session, OpenTelemetry, SloTracker, SloObjective};
+///
+/// let session = Session::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+///   .with_battery(OpenTelemetry::new("localhost:4317"))
+///   .with_battery(SloTracker::new()
+///     .with_objective(SloObjective::latency("request-latency", "request", Duration::from_millis(300), 0.99)));
+///
+/// session.shutdown();
+/// ```
+pub struct SloTracker {
+    objectives: Vec<SloObjective>,
+    alert_burn_rate: f64,
+    min_spans: u64,
+}
+
+impl SloTracker {
+    /// Creates a new SLO tracker with no objectives.
+    ///
+    /// By default, an alert will be raised when an objective's budget is being consumed at more than
+    /// 14.4 times the sustainable rate, once at least 100 spans have been observed in its window.
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        Self {
+            objectives: Vec::new(),
+            alert_burn_rate: 14.4,
+            min_spans: 100,
+        }
+    }
+
+    /// Adds a new objective which will be evaluated by this tracker.
+    pub fn with_objective(mut self, objective: SloObjective) -> Self {
+        self.objectives.push(objective);
+        self
+    }
+
+    /// Configures the burn rate above which an alert will be raised for an objective.
+    ///
+    /// A burn rate of `1.0` indicates that the error budget is being consumed at exactly the rate
+    /// which would exhaust it at the end of the objective's window.
+    pub fn with_alert_burn_rate(self, burn_rate: f64) -> Self {
+        Self {
+            alert_burn_rate: burn_rate,
+            ..self
+        }
+    }
+
+    /// Configures the minimum number of spans which must be observed within an objective's window
+    /// before an alert may be raised for it.
+    pub fn with_min_spans(self, min_spans: u64) -> Self {
+        Self { min_spans, ..self }
+    }
+}
+
+impl SloTracker {
+    fn into_state(self, enabled: Arc<AtomicBool>) -> Arc<SloState> {
+        Arc::new(SloState {
+            objectives: self
+                .objectives
+                .into_iter()
+                .map(|objective| Mutex::new(ObjectiveState::new(objective)))
+                .collect(),
+            alert_burn_rate: self.alert_burn_rate,
+            min_spans: self.min_spans,
+            enabled,
+            session: OnceLock::new(),
+            instruments: RwLock::new(None),
+        })
+    }
+}
+
+impl BatteryBuilder for SloTracker {
+    fn setup(self, _metadata: &Metadata, enabled: Arc<AtomicBool>) -> Box<dyn Battery> {
+        let state = self.into_state(enabled);
+
+        crate::layers::attach_layer(SloLayer {
+            state: state.clone(),
+        });
+
+        Box::new(SloBattery { state })
+    }
+}
+
+struct SloBattery {
+    state: Arc<SloState>,
+}
+
+impl Battery for SloBattery {
+    fn attached(&self, session: WeakSession) {
+        if let Some(session) = session.upgrade() {
+            *self
+                .state
+                .instruments
+                .write()
+                .unwrap_or_else(PoisonError::into_inner) = Some(SloInstruments {
+                spans: session.counter("slo.spans"),
+                burn_rate: session.gauge("slo.burn_rate"),
+            });
+        }
+
+        self.state.session.set(session).ok();
+    }
+
+    fn shutdown(&self) {
+        // The instruments hold a reference to the session, so they're released here to avoid keeping it alive.
+        self.state
+            .instruments
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take();
+    }
+}
+
+struct SloState {
+    objectives: Vec<Mutex<ObjectiveState>>,
+    alert_burn_rate: f64,
+    min_spans: u64,
+    enabled: Arc<AtomicBool>,
+    session: OnceLock<WeakSession>,
+    instruments: RwLock<Option<SloInstruments>>,
+}
+
+struct SloInstruments {
+    spans: Counter,
+    burn_rate: Gauge,
+}
+
+/// The outcome of evaluating a span against one of the objectives, which is reported once the objective's lock
+/// has been released.
+struct SloObservation {
+    objective: Cow<'static, str>,
+    good: bool,
+    burn_rate: f64,
+    alert: Option<(u64, u64)>,
+}
+
+struct ObjectiveState {
+    objective: SloObjective,
+    buckets: VecDeque<Bucket>,
+    last_alert: Option<Instant>,
+}
+
+struct Bucket {
+    start: Instant,
+    good: u64,
+    bad: u64,
+}
+
+impl ObjectiveState {
+    fn new(objective: SloObjective) -> Self {
+        Self {
+            objective,
+            buckets: VecDeque::new(),
+            last_alert: None,
+        }
+    }
+
+    fn observe(&mut self, now: Instant, good: bool) {
+        while self
+            .buckets
+            .front()
+            .is_some_and(|bucket| now.duration_since(bucket.start) > self.objective.window)
+        {
+            self.buckets.pop_front();
+        }
+
+        match self.buckets.back_mut() {
+            Some(bucket) if now.duration_since(bucket.start) < BUCKET_WIDTH => {
+                if good {
+                    bucket.good += 1;
+                } else {
+                    bucket.bad += 1;
+                }
+            }
+            _ => self.buckets.push_back(Bucket {
+                start: now,
+                good: good as u64,
+                bad: !good as u64,
+            }),
+        }
+    }
+
+    fn totals(&self) -> (u64, u64) {
+        self.buckets.iter().fold((0, 0), |(good, bad), bucket| {
+            (good + bucket.good, bad + bucket.bad)
+        })
+    }
+
+    fn burn_rate(&self) -> f64 {
+        let (good, bad) = self.totals();
+        let total = good + bad;
+        if total == 0 {
+            return 0.0;
+        }
+
+        let budget = 1.0 - self.objective.target;
+        let error_rate = bad as f64 / total as f64;
+        if budget <= 0.0 {
+            if bad > 0 {
+                f64::INFINITY
+            } else {
+                0.0
+            }
+        } else {
+            error_rate / budget
+        }
+    }
+}
+
+struct SpanStart(Instant);
+
+struct SloLayer {
+    state: Arc<SloState>,
+}
+
+impl<S> Layer<S> for SloLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &span::Attributes<'_>, id: &span::Id, ctx: Context<'_, S>) {
+        let name = attrs.metadata().name();
+        if !self.state.objectives.iter().any(|objective| {
+            objective
+                .lock()
+                .is_ok_and(|objective| objective.objective.span_name == name)
+        }) {
+            return;
+        }
+
+        if let Some(span) = ctx.span(id) {
+            span.extensions_mut().insert(SpanStart(Instant::now()));
+        }
+    }
+
+    fn on_close(&self, id: span::Id, ctx: Context<'_, S>) {
+        if !self.state.enabled.load(Ordering::Relaxed) {
+            return;
+        }
+
+        let Some(span) = ctx.span(&id) else {
+            return;
+        };
+
+        let Some(elapsed) = span
+            .extensions()
+            .get::<SpanStart>()
+            .map(|start| start.0.elapsed())
+        else {
+            return;
+        };
+
+        let now = Instant::now();
+        let mut observations = Vec::new();
+        for objective in self.state.objectives.iter() {
+            let Ok(mut objective) = objective.lock() else {
+                continue;
+            };
+
+            if objective.objective.span_name != span.name() {
+                continue;
+            }
+
+            let good = elapsed <= objective.objective.threshold;
+            objective.observe(now, good);
+
+            let burn_rate = objective.burn_rate();
+            let (good_spans, bad_spans) = objective.totals();
+
+            let should_alert = burn_rate > self.state.alert_burn_rate
+                && good_spans + bad_spans >= self.state.min_spans
+                && objective.last_alert.map_or(true, |last| {
+                    now.duration_since(last) > objective.objective.window
+                });
+            if should_alert {
+                objective.last_alert = Some(now);
+            }
+
+            observations.push(SloObservation {
+                objective: objective.objective.name.clone(),
+                good,
+                burn_rate,
+                alert: should_alert.then_some((good_spans, bad_spans)),
+            });
+        }
+        drop(span);
+
+        if observations.is_empty() {
+            return;
+        }
+
+        if let Some(instruments) = self
+            .state
+            .instruments
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .as_ref()
+        {
+            for observation in observations.iter() {
+                let name: &str = &observation.objective;
+                instruments.spans.inc_with(
+                    1,
+                    &[
+                        ("objective", name),
+                        ("outcome", if observation.good { "good" } else { "bad" }),
+                    ],
+                );
+                instruments
+                    .burn_rate
+                    .set_with(observation.burn_rate, &[("objective", name)]);
+            }
+        }
+
+        let Some(session) = self
+            .state
+            .session
+            .get()
+            .and_then(|session| session.upgrade())
+        else {
+            return;
+        };
+
+        for observation in observations {
+            let Some((good_spans, bad_spans)) = observation.alert else {
+                continue;
+            };
+
+            let name = observation.objective.to_string();
+            session.record_error_with(
+                &SloBudgetBurnError {
+                    objective: name.clone(),
+                    burn_rate: observation.burn_rate,
+                },
+                [
+                    ("slo.objective", name),
+                    ("slo.burn_rate", format!("{:.2}", observation.burn_rate)),
+                    ("slo.good_spans", good_spans.to_string()),
+                    ("slo.bad_spans", bad_spans.to_string()),
+                ],
+            );
+        }
+    }
+}
+
+/// The error which is recorded by the [`SloTracker`] when an objective's error budget is burning too quickly.
+#[derive(Debug)]
+pub struct SloBudgetBurnError {
+    pub objective: String,
+    pub burn_rate: f64,
+}
+
+impl std::fmt::Display for SloBudgetBurnError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "The error budget for the '{}' objective is burning at {:.2}x the sustainable rate.",
+            self.objective, self.burn_rate
+        )
+    }
+}
+
+impl std::error::Error for SloBudgetBurnError {}
+
+#[cfg(test)]
+mod tests {
+    use tracing_subscriber::layer::SubscriberExt;
+
+    use super::*;
+    use crate::{ErrorContext, Metric, Session};
+
+    #[test]
+    fn burn_rate() {
+        let mut state = ObjectiveState::new(SloObjective::latency(
+            "request-latency",
+            "request",
+            Duration::from_millis(300),
+            0.99,
+        ));
+
+        let now = Instant::now();
+        for _ in 0..98 {
+            state.observe(now, true);
+        }
+        state.observe(now, false);
+        state.observe(now, false);
+
+        assert_eq!(state.totals(), (98, 2));
+        assert!((state.burn_rate() - 2.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn expires_old_buckets() {
+        let mut state = ObjectiveState::new(
+            SloObjective::latency(
+                "request-latency",
+                "request",
+                Duration::from_millis(300),
+                0.99,
+            )
+            .with_window(Duration::from_secs(120)),
+        );
+
+        let start = Instant::now();
+        state.observe(start, false);
+        state.observe(start + Duration::from_secs(61), true);
+        state.observe(start + Duration::from_secs(181), true);
+
+        assert_eq!(state.totals(), (1, 0));
+    }
+
+    #[test]
+    fn reports_slow_spans() {
+        let errors = Arc::new(Mutex::new(Vec::new()));
+        let metrics = Arc::new(Mutex::new(Vec::new()));
+        let session = Session::new("slo-test", "1.0.0").with_battery(RecordingBattery {
+            errors: errors.clone(),
+            metrics: metrics.clone(),
+        });
+
+        let state = SloTracker::new()
+            .with_objective(SloObjective::latency(
+                "request-latency",
+                "request",
+                Duration::ZERO,
+                0.99,
+            ))
+            .with_min_spans(3)
+            .into_state(session.enabled.clone());
+        SloBattery {
+            state: state.clone(),
+        }
+        .attached(session.downgrade());
+
+        let subscriber = tracing_subscriber::registry().with(SloLayer { state });
+        tracing::subscriber::with_default(subscriber, || {
+            for _ in 0..5 {
+                tracing::info_span!("request")
+                    .in_scope(|| std::thread::sleep(Duration::from_millis(1)));
+            }
+
+            tracing::info_span!("background").in_scope(|| {});
+        });
+
+        let metrics = metrics.lock().unwrap();
+        assert_eq!(
+            metrics
+                .iter()
+                .filter(|(name, outcome)| name == "slo.spans" && outcome == "bad")
+                .count(),
+            5
+        );
+        assert_eq!(
+            metrics
+                .iter()
+                .filter(|(name, _)| name == "slo.burn_rate")
+                .count(),
+            5
+        );
+
+        let errors = errors.lock().unwrap();
+        assert_eq!(
+            errors.len(),
+            1,
+            "the alert should only be raised once per window"
+        );
+        assert_eq!(
+            errors[0].fields.get("slo.objective").map(|s| s.as_str()),
+            Some("request-latency")
+        );
+    }
+
+    struct RecordingBattery {
+        errors: Arc<Mutex<Vec<ErrorContext>>>,
+        metrics: Arc<Mutex<Vec<(String, String)>>>,
+    }
+
+    impl BatteryBuilder for RecordingBattery {
+        fn setup(self, _metadata: &Metadata, _enabled: Arc<AtomicBool>) -> Box<dyn Battery> {
+            Box::new(self)
+        }
+    }
+
+    impl Battery for RecordingBattery {
+        fn record_error_with(&self, _error: &dyn std::error::Error, context: &ErrorContext) {
+            self.errors.lock().unwrap().push(context.clone());
+        }
+
+        fn record_metric(&self, metric: &Metric) {
+            let outcome = metric
+                .attributes
+                .iter()
+                .find(|(key, _)| *key == "outcome")
+                .map(|(_, value)| value.to_string())
+                .unwrap_or_default();
+            self.metrics
+                .lock()
+                .unwrap()
+                .push((metric.name.to_string(), outcome));
+        }
+    }
+}
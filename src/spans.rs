@@ -0,0 +1,191 @@
+use std::{
+    collections::BTreeMap,
+    fmt::Write as _,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use chrono::{DateTime, Utc};
+use tracing::{
+    field::{Field, Visit},
+    span, Level, Subscriber,
+};
+use tracing_subscriber::{layer::Context, registry::LookupSpan, Layer};
+
+/// A summary of a completed span, for batteries which ship spans to backends that don't speak OpenTelemetry.
+pub(crate) struct SpanSummary {
+    pub started_at: DateTime<Utc>,
+    pub name: &'static str,
+    pub target: String,
+    pub level: Level,
+    pub parent: Option<&'static str>,
+    pub duration: Duration,
+    pub fields: BTreeMap<String, String>,
+}
+
+impl SpanSummary {
+    /// Serializes this span (along with the service which recorded it) as a JSON object.
+    pub fn to_json(&self, service: &str, version: &str) -> serde_json::Value {
+        serde_json::json!({
+            "kind": "span",
+            "timestamp": self.started_at.to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+            "service": service,
+            "version": version,
+            "name": self.name,
+            "target": self.target,
+            "level": self.level.as_str(),
+            "parent": self.parent,
+            "duration_ms": self.duration.as_secs_f64() * 1000.0,
+            "fields": self.fields,
+        })
+    }
+}
+
+struct SpanTiming {
+    started_at: DateTime<Utc>,
+    start: Instant,
+    fields: BTreeMap<String, String>,
+}
+
+/// A [`Layer`] which builds a [`SpanSummary`] for every span as it closes and passes it to the provided callback
+/// while the session is enabled.
+///
+/// This is intended to be registered using [`attach_layer`](crate::layers::attach_layer).
+pub(crate) struct SpanSummaryLayer<F> {
+    enabled: Arc<AtomicBool>,
+    on_close: F,
+}
+
+impl<F> SpanSummaryLayer<F>
+where
+    F: Fn(SpanSummary) + Send + Sync + 'static,
+{
+    pub fn new(enabled: Arc<AtomicBool>, on_close: F) -> Self {
+        Self { enabled, on_close }
+    }
+}
+
+impl<S, F> Layer<S> for SpanSummaryLayer<F>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    F: Fn(SpanSummary) + Send + Sync + 'static,
+{
+    fn on_new_span(&self, attrs: &span::Attributes<'_>, id: &span::Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+
+        let mut fields = BTreeMap::new();
+        attrs.record(&mut FieldCollector(&mut fields));
+        span.extensions_mut().insert(SpanTiming {
+            started_at: Utc::now(),
+            start: Instant::now(),
+            fields,
+        });
+    }
+
+    fn on_record(&self, id: &span::Id, values: &span::Record<'_>, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(id) {
+            if let Some(timing) = span.extensions_mut().get_mut::<SpanTiming>() {
+                values.record(&mut FieldCollector(&mut timing.fields));
+            }
+        }
+    }
+
+    fn on_close(&self, id: span::Id, ctx: Context<'_, S>) {
+        if !self.enabled.load(Ordering::Relaxed) {
+            return;
+        }
+
+        let Some(span) = ctx.span(&id) else {
+            return;
+        };
+
+        let Some(timing) = span.extensions_mut().remove::<SpanTiming>() else {
+            return;
+        };
+
+        (self.on_close)(SpanSummary {
+            started_at: timing.started_at,
+            name: span.name(),
+            target: span.metadata().target().to_string(),
+            level: *span.metadata().level(),
+            parent: span.parent().map(|parent| parent.name()),
+            duration: timing.start.elapsed(),
+            fields: timing.fields,
+        });
+    }
+}
+
+struct FieldCollector<'a>(&'a mut BTreeMap<String, String>);
+
+impl Visit for FieldCollector<'_> {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().to_string(), value.to_string());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        let mut formatted = String::new();
+        let _ = write!(formatted, "{:?}", value);
+        self.0.insert(field.name().to_string(), formatted);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{mpsc, Mutex};
+
+    use tracing_subscriber::layer::SubscriberExt;
+
+    use super::*;
+
+    #[test]
+    fn summarizes_spans() {
+        let (sender, receiver) = mpsc::channel();
+        let sender = Mutex::new(sender);
+        let layer = SpanSummaryLayer::new(Arc::new(AtomicBool::new(true)), move |span| {
+            sender.lock().unwrap().send(span).unwrap()
+        });
+
+        let subscriber = tracing_subscriber::registry().with(layer);
+        tracing::subscriber::with_default(subscriber, || {
+            let _parent = tracing::info_span!("handler").entered();
+            let span = tracing::debug_span!(
+                "request",
+                path = "/settings",
+                status = tracing::field::Empty
+            );
+            span.record("status", 200);
+        });
+
+        let span = receiver.recv().unwrap();
+        assert_eq!(span.name, "request");
+        assert_eq!(span.level, Level::DEBUG);
+        assert_eq!(span.parent, Some("handler"));
+        assert_eq!(span.fields["path"], "/settings");
+        assert_eq!(span.fields["status"], "200");
+    }
+
+    #[test]
+    fn span_json() {
+        let span = SpanSummary {
+            started_at: Utc::now(),
+            name: "request",
+            target: "example::http".into(),
+            level: Level::INFO,
+            parent: None,
+            duration: Duration::from_millis(25),
+            fields: [("path".to_string(), "/settings".to_string())].into(),
+        };
+
+        let data = span.to_json("example", "0.0.1");
+        assert_eq!(data["kind"], "span");
+        assert_eq!(data["name"], "request");
+        assert_eq!(data["level"], "INFO");
+        assert_eq!(data["duration_ms"], 25.0);
+        assert_eq!(data["fields"]["path"], "/settings");
+    }
+}
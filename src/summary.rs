@@ -0,0 +1,91 @@
+use std::{
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Instant,
+};
+
+use crate::{EnvelopePayload, Session};
+
+/// Counters which track the telemetry delivered to batteries over the lifetime of a [`Session`], reported in the
+/// `session_summary` event when the session is shut down. Telemetry recorded while the session is disabled (or
+/// filtered out before delivery) is not counted.
+pub(crate) struct SessionStats {
+    started: Instant,
+    pub errors: AtomicUsize,
+    pub pages: AtomicUsize,
+    pub events: AtomicUsize,
+
+    /// The timings of each battery attached to the session, reported by [`Session::overhead_report`].
+    #[cfg(debug_assertions)]
+    pub overhead: std::sync::Mutex<Vec<std::sync::Arc<crate::overhead::BatteryTimings>>>,
+}
+
+impl SessionStats {
+    pub fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            errors: AtomicUsize::new(0),
+            pages: AtomicUsize::new(0),
+            events: AtomicUsize::new(0),
+            #[cfg(debug_assertions)]
+            overhead: std::sync::Mutex::new(Vec::new()),
+        }
+    }
+}
+
+impl Session {
+    /// Shuts down the telemetry session, reporting the provided exit code in the session summary.
+    ///
+    /// This behaves in the same way as [`Session::shutdown`], however the `exit_code` property of the
+    /// `session_summary` event will be populated, allowing stability dashboards to distinguish between
+    /// successful and failed runs of your application.
+    ///
+    /// ## Example
+    /// ```no_run
+    /// use tracing_batteries::{Session, Sentry};
+    ///
+    /// let session = Session::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+    ///   .with_battery(Sentry::new("https://yourdsn@sentry.example.com/app-id"));
+    ///
+    /// let exit_code = 1;
+    /// session.shutdown_with_exit_code(exit_code);
+    /// std::process::exit(exit_code);
+    /// ```
+    pub fn shutdown_with_exit_code(self, exit_code: i32) {
+        self.flush_feature_usage();
+        self.flush_metrics();
+        self.record_summary(Some(exit_code));
+        self.shutdown_batteries();
+    }
+
+    pub(crate) fn record_summary(&self, exit_code: Option<i32>) {
+        let duration = self.stats.started.elapsed();
+        let errors = self.stats.errors.load(Ordering::Relaxed);
+        let pages = self.stats.pages.load(Ordering::Relaxed);
+        let events = self.stats.events.load(Ordering::Relaxed);
+
+        tracing::info!(
+            duration_ms = duration.as_millis() as u64,
+            errors,
+            pages,
+            events,
+            exit_code,
+            "The telemetry session is shutting down."
+        );
+
+        self.record_envelope(|| EnvelopePayload::Event {
+            name: "session_summary".into(),
+            properties: [
+                (
+                    "duration_ms".to_string(),
+                    (duration.as_millis() as u64).into(),
+                ),
+                ("errors".to_string(), errors.into()),
+                ("pages".to_string(), pages.into()),
+                ("events".to_string(), events.into()),
+                ("exit_code".to_string(), exit_code.into()),
+            ]
+            .into_iter()
+            .collect(),
+        });
+    }
+}
@@ -0,0 +1,104 @@
+use std::{io::Write, sync::Arc};
+
+use tracing::Metadata;
+use tracing_subscriber::fmt::{writer::BoxMakeWriter, MakeWriter};
+
+pub(crate) type Suspend = dyn Fn(&mut dyn FnMut()) + Send + Sync;
+
+/// A [`MakeWriter`] which writes each line of stdout output within a suspend hook (such as indicatif's
+/// `ProgressBar::suspend`), so that the hook can clear any progress bars before the line is written and
+/// redraw them afterwards.
+pub(crate) struct SuspendingMakeWriter {
+    inner: BoxMakeWriter,
+    suspend: Arc<Suspend>,
+}
+
+impl SuspendingMakeWriter {
+    pub fn new(inner: BoxMakeWriter, suspend: Arc<Suspend>) -> Self {
+        Self { inner, suspend }
+    }
+}
+
+impl<'a> MakeWriter<'a> for SuspendingMakeWriter {
+    type Writer = SuspendingWriter<'a>;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        SuspendingWriter {
+            inner: self.inner.make_writer(),
+            suspend: &*self.suspend,
+        }
+    }
+
+    fn make_writer_for(&'a self, meta: &Metadata<'_>) -> Self::Writer {
+        SuspendingWriter {
+            inner: self.inner.make_writer_for(meta),
+            suspend: &*self.suspend,
+        }
+    }
+}
+
+/// The writer created by [`SuspendingMakeWriter`] for each line of output.
+pub(crate) struct SuspendingWriter<'a> {
+    inner: Box<dyn Write + 'a>,
+    suspend: &'a Suspend,
+}
+
+impl Write for SuspendingWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        // Each line is written in full within the hook, since a partial line would be overwritten once the
+        // progress bars are redrawn.
+        let mut result = Ok(buf.len());
+        (self.suspend)(&mut || result = self.inner.write_all(buf).map(|()| buf.len()));
+        result
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    };
+
+    use super::*;
+
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn writes_within_suspend_hook() {
+        let buffer = Buffer::default();
+        let suspended = Arc::new(AtomicUsize::new(0));
+
+        let writer = SuspendingMakeWriter::new(BoxMakeWriter::new(Mutex::new(buffer.clone())), {
+            let suspended = suspended.clone();
+            Arc::new(move |write: &mut dyn FnMut()| {
+                suspended.fetch_add(1, Ordering::Relaxed);
+                write();
+            })
+        });
+
+        writer
+            .make_writer()
+            .write_all(b"Compiled 42 files\n")
+            .unwrap();
+
+        assert_eq!(suspended.load(Ordering::Relaxed), 1);
+        assert_eq!(&*buffer.0.lock().unwrap(), b"Compiled 42 files\n");
+    }
+}
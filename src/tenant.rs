@@ -0,0 +1,326 @@
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    sync::{atomic::AtomicBool, Arc, Mutex, PoisonError},
+};
+
+use crate::{
+    Battery, BatteryBuilder, Envelope, ErrorContext, Metadata, Metric, Session, WeakSession,
+};
+
+/// The context key (and error field, or metric attribute) which identifies the tenant that telemetry was
+/// recorded on behalf of.
+pub const TENANT_KEY: &str = "tenant.id";
+
+/// A lightweight handle, created by [`Session::tenant`], which records telemetry on behalf of a single tenant.
+///
+/// Every envelope recorded through the handle carries the tenant's identifier in its context, and every error
+/// carries it as a field, under the [`TENANT_KEY`]. This keeps each customer's telemetry separable in
+/// multi-tenant services, and allows a [`Tenanted`] battery to route it to tenant-specific endpoints.
+///
+/// Tenants share the batteries, statistics and lifecycle of the session which created them, and are cheap to
+/// clone, so they may be created for each request if necessary.
+#[derive(Clone)]
+pub struct Tenant {
+    id: Cow<'static, str>,
+    session: Session,
+}
+
+impl Tenant {
+    /// The identifier of the tenant which this handle records telemetry for.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Records that an error has occurred on behalf of the tenant, see [`Session::record_error`].
+    pub fn record_error<'a, E: std::error::Error>(&self, exception: &'a E) -> &'a E {
+        self.record_error_with(exception, std::iter::empty::<(&'static str, String)>())
+    }
+
+    /// Records that an error has occurred on behalf of the tenant, attaching the provided key-value context to
+    /// the report, see [`Session::record_error_with`].
+    pub fn record_error_with<'a, E, I, V>(&self, exception: &'a E, context: I) -> &'a E
+    where
+        E: std::error::Error,
+        I: IntoIterator<Item = (&'static str, V)>,
+        V: ToString,
+    {
+        self.session.record_error_with(
+            exception,
+            context
+                .into_iter()
+                .map(|(key, value)| (key, value.to_string()))
+                .chain(std::iter::once((TENANT_KEY, self.id.to_string()))),
+        )
+    }
+
+    /// Records a custom event on behalf of the tenant, see [`Session::record_event`].
+    pub fn record_event<N, I, K, V>(&self, name: N, properties: I)
+    where
+        N: Into<String>,
+        I: IntoIterator<Item = (K, V)>,
+        K: Into<String>,
+        V: Into<serde_json::Value>,
+    {
+        self.session.record_event(name, properties);
+    }
+
+    /// Records that one of the tenant's users has navigated to a new page, see [`Session::record_new_page`].
+    pub fn record_new_page<P: Into<String>>(&self, page: P) {
+        self.session.record_new_page(page);
+    }
+}
+
+impl Session {
+    /// Creates a [`Tenant`] handle which stamps the telemetry recorded through it with the provided tenant's
+    /// identifier, for multi-tenant services which must keep each customer's telemetry separable.
+    ///
+    /// Combine this with a [`Tenanted`] battery to deliver each tenant's telemetry to its own endpoint.
+    ///
+    /// ## Example
+    /// ```no_run
+    /// use tracing_batteries::{Session, Sentry};
+    ///
+    /// let session = Session::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+    ///   .with_battery(Sentry::new("https://yourdsn@sentry.example.com"));
+    ///
+    /// let tenant = session.tenant("acme-corp");
+    /// tenant.record_event("export_pdf", [("pages", 3)]);
+    ///
+    /// session.shutdown();
+    /// ```
+    pub fn tenant<T: Into<Cow<'static, str>>>(&self, tenant_id: T) -> Tenant {
+        let id = tenant_id.into();
+
+        Tenant {
+            session: Session {
+                metadata: Arc::new(self.metadata.clone().with_context(TENANT_KEY, id.clone())),
+                ..self.clone()
+            },
+            id,
+        }
+    }
+}
+
+/// A combinator which sets up a separate battery for each tenant, routing the telemetry recorded through each
+/// [`Tenant`] handle to that tenant's battery.
+///
+/// The provided function is called the first time telemetry is recorded for each tenant, and returns the battery
+/// which should receive it (for example, one configured with the tenant's own endpoint or API key). Each battery
+/// is set up with the session's metadata plus the tenant's identifier under the [`TENANT_KEY`] context key.
+///
+/// Errors and envelopes are routed using the tenant identifier attached by the [`Tenant`] handle, while metrics
+/// are routed using their [`TENANT_KEY`] attribute. Telemetry which doesn't identify a tenant is not forwarded,
+/// so attach a separate battery to the session if you need to receive it. Batteries which install a global
+/// tracing subscriber (such as OpenTelemetry) may only be set up once per process, and so cannot be used here.
+///
+/// ## Example
+/// ```no_run
+/// # #[cfg(feature = "goatcounter")] {
+/// use tracing_batteries::{GoatCounter, Session, Tenanted};
+///
+/// let session = Session::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+///   .with_battery(Tenanted::new(|tenant: &str| {
+///     GoatCounter::new(format!("{tenant}-analytics"), std::env::var("GOATCOUNTER_TOKEN").unwrap_or_default())
+///   }));
+///
+/// session.tenant("acme-corp").record_new_page("/settings");
+///
+/// session.shutdown();
+/// # }
+/// ```
+///
+/// <div class="warning">
+/// Each tenant's battery is retained until the session is shut down, so the set of tenants should be bounded.
+/// </div>
+pub struct Tenanted<F> {
+    factory: F,
+}
+
+impl<F, B> Tenanted<F>
+where
+    F: Fn(&str) -> B + Send + Sync + 'static,
+    B: BatteryBuilder,
+{
+    /// Creates a combinator which uses the provided function to build the battery for each tenant.
+    pub fn new(factory: F) -> Self {
+        Self { factory }
+    }
+}
+
+impl<F, B> BatteryBuilder for Tenanted<F>
+where
+    F: Fn(&str) -> B + Send + Sync + 'static,
+    B: BatteryBuilder,
+{
+    fn setup(self, metadata: &Metadata, enabled: Arc<AtomicBool>) -> Box<dyn Battery> {
+        let factory = self.factory;
+        let metadata = metadata.clone();
+
+        Box::new(TenantedBattery {
+            factory: Box::new(move |tenant| {
+                factory(tenant).setup(
+                    &metadata
+                        .clone()
+                        .with_context(TENANT_KEY, tenant.to_string()),
+                    enabled.clone(),
+                )
+            }),
+            tenants: Mutex::new(HashMap::new()),
+            session: Mutex::new(None),
+        })
+    }
+}
+
+type TenantFactory = Box<dyn Fn(&str) -> Box<dyn Battery> + Send + Sync>;
+
+struct TenantedBattery {
+    factory: TenantFactory,
+    tenants: Mutex<HashMap<String, Arc<dyn Battery>>>,
+    session: Mutex<Option<WeakSession>>,
+}
+
+impl TenantedBattery {
+    fn tenant(&self, tenant: &str) -> Arc<dyn Battery> {
+        let (battery, created) = {
+            let mut tenants = self.tenants.lock().unwrap_or_else(PoisonError::into_inner);
+            match tenants.get(tenant) {
+                Some(battery) => (battery.clone(), false),
+                None => {
+                    let battery: Arc<dyn Battery> = Arc::from((self.factory)(tenant));
+                    tenants.insert(tenant.to_string(), battery.clone());
+                    (battery, true)
+                }
+            }
+        };
+
+        // The battery is attached outside of the lock, since it may record telemetry through the session.
+        if created {
+            let session = self
+                .session
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .clone();
+            if let Some(session) = session {
+                battery.attached(session);
+            }
+        }
+
+        battery
+    }
+
+    fn batteries(&self) -> Vec<Arc<dyn Battery>> {
+        self.tenants
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .values()
+            .cloned()
+            .collect()
+    }
+}
+
+impl Battery for TenantedBattery {
+    fn record_error_with(&self, error: &dyn std::error::Error, context: &ErrorContext) {
+        if let Some(tenant) = context.fields.get(TENANT_KEY) {
+            self.tenant(tenant).record_error_with(error, context);
+        }
+    }
+
+    fn record_envelope(&self, envelope: &Envelope) {
+        if let Some(tenant) = envelope.context.get(TENANT_KEY) {
+            self.tenant(tenant).record_envelope(envelope);
+        }
+    }
+
+    fn record_metric(&self, metric: &Metric) {
+        if let Some((_, tenant)) = metric.attributes.iter().find(|(key, _)| *key == TENANT_KEY) {
+            self.tenant(tenant).record_metric(metric);
+        }
+    }
+
+    fn accepts_weighted_histograms(&self) -> bool {
+        // Every tenant's battery is built by the same factory, so those which already exist are representative.
+        let batteries = self.batteries();
+        !batteries.is_empty()
+            && batteries
+                .iter()
+                .all(|battery| battery.accepts_weighted_histograms())
+    }
+
+    fn record_artifact(&self, artifact: &crate::Artifact) -> bool {
+        // Artifacts aren't associated with a tenant, so they accompany the reports of every active tenant.
+        self.batteries().iter().fold(false, |accepted, battery| {
+            battery.record_artifact(artifact) | accepted
+        })
+    }
+
+    fn release(&self) {
+        for battery in self.batteries() {
+            battery.release();
+        }
+    }
+
+    fn shutdown(&self) {
+        for battery in self.batteries() {
+            battery.shutdown();
+        }
+    }
+
+    fn attached(&self, session: WeakSession) {
+        *self.session.lock().unwrap_or_else(PoisonError::into_inner) = Some(session);
+    }
+
+    fn validate(&self) -> Vec<crate::ValidationCheck> {
+        self.batteries()
+            .iter()
+            .flat_map(|battery| battery.validate())
+            .collect()
+    }
+
+    #[cfg(feature = "version-check")]
+    fn update_available(&self) -> Option<crate::AvailableUpdate> {
+        self.batteries()
+            .iter()
+            .find_map(|battery| battery.update_available())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{test_support::EnvelopeBattery, EnvelopePayload};
+
+    #[test]
+    fn routes_tenant_telemetry() {
+        let routed = Arc::new(Mutex::new(HashMap::<String, EnvelopeBattery>::new()));
+        let factory_routed = routed.clone();
+        let session =
+            Session::new("example", "0.0.1").with_battery(Tenanted::new(move |tenant: &str| {
+                let battery = EnvelopeBattery::default();
+                factory_routed
+                    .lock()
+                    .unwrap()
+                    .insert(tenant.to_string(), battery.clone());
+                battery
+            }));
+
+        session.tenant("acme").record_new_page("/settings");
+        session.tenant("globex").record_new_page("/profile");
+        session
+            .tenant("acme")
+            .record_error(&std::io::Error::other("disk full"));
+        session.record_new_page("/untenanted");
+
+        let routed = routed.lock().unwrap();
+        assert_eq!(routed.len(), 2);
+
+        let acme = routed["acme"].envelopes();
+        assert_eq!(acme.len(), 2);
+        assert_eq!(acme[0].context.get(TENANT_KEY).unwrap(), "acme");
+        assert!(matches!(
+            &acme[1].payload,
+            EnvelopePayload::Error { fields, .. } if fields.get(TENANT_KEY).map(String::as_str) == Some("acme")
+        ));
+        assert_eq!(routed["globex"].envelopes().len(), 1);
+    }
+}
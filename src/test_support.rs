@@ -0,0 +1,89 @@
+use std::sync::{atomic::AtomicBool, Arc, Mutex};
+
+use crate::{Battery, BatteryBuilder, Envelope, EnvelopePayload, ErrorContext, Metadata};
+
+/// A battery which records the envelopes it receives.
+///
+/// Clones share the same recording, so one clone may be attached to a session (or wrapped by a combinator) while
+/// another is used to inspect the envelopes which reached it.
+#[derive(Clone, Default)]
+pub(crate) struct EnvelopeBattery {
+    envelopes: Arc<Mutex<Vec<Envelope>>>,
+}
+
+impl EnvelopeBattery {
+    /// The envelopes which have been recorded, in the order they were received.
+    pub fn envelopes(&self) -> Vec<Envelope> {
+        self.envelopes.lock().unwrap().clone()
+    }
+
+    /// The pages of the page views which have been recorded, in the order they were received.
+    pub fn pages(&self) -> Vec<String> {
+        self.envelopes
+            .lock()
+            .unwrap()
+            .iter()
+            .filter_map(|envelope| match &envelope.payload {
+                EnvelopePayload::PageView { page } => Some(page.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+impl BatteryBuilder for EnvelopeBattery {
+    fn setup(self, _metadata: &Metadata, _enabled: Arc<AtomicBool>) -> Box<dyn Battery> {
+        Box::new(self)
+    }
+}
+
+impl Battery for EnvelopeBattery {
+    fn record_envelope(&self, envelope: &Envelope) {
+        self.envelopes.lock().unwrap().push(envelope.clone());
+    }
+}
+
+/// A battery which records the message and context of each error it receives.
+///
+/// Like [`EnvelopeBattery`], clones share the same recording.
+#[derive(Clone, Default)]
+pub(crate) struct ErrorBattery {
+    errors: Arc<Mutex<Vec<(String, ErrorContext)>>>,
+}
+
+impl ErrorBattery {
+    /// The messages of the errors which have been recorded, in the order they were received.
+    pub fn messages(&self) -> Vec<String> {
+        self.errors
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(message, _)| message.clone())
+            .collect()
+    }
+
+    /// The context of the errors which have been recorded, in the order they were received.
+    pub fn contexts(&self) -> Vec<ErrorContext> {
+        self.errors
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(_, context)| context.clone())
+            .collect()
+    }
+}
+
+impl BatteryBuilder for ErrorBattery {
+    fn setup(self, _metadata: &Metadata, _enabled: Arc<AtomicBool>) -> Box<dyn Battery> {
+        Box::new(self)
+    }
+}
+
+impl Battery for ErrorBattery {
+    fn record_error_with(&self, error: &dyn std::error::Error, context: &ErrorContext) {
+        self.errors
+            .lock()
+            .unwrap()
+            .push((error.to_string(), context.clone()));
+    }
+}
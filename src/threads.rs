@@ -0,0 +1,153 @@
+use std::{
+    any::Any,
+    fmt::Display,
+    panic::{catch_unwind, resume_unwind, AssertUnwindSafe},
+    thread::{Builder, JoinHandle},
+};
+
+use crate::Session;
+
+/// An error describing a panic which occurred on a thread spawned using [`Session::spawn_instrumented`] or
+/// [`Session::instrument_thread`], as reported to the session's batteries.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ThreadPanic {
+    /// The name of the thread which panicked, if it was given one.
+    pub thread: Option<String>,
+    /// The message which the thread panicked with, when its payload was a string.
+    pub message: String,
+}
+
+impl ThreadPanic {
+    fn new(thread: Option<String>, payload: &(dyn Any + Send)) -> Self {
+        let message = payload
+            .downcast_ref::<&str>()
+            .map(|message| message.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "Box<dyn Any>".to_string());
+
+        Self { thread, message }
+    }
+}
+
+impl Display for ThreadPanic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "thread '{}' panicked: {}",
+            self.thread.as_deref().unwrap_or("<unnamed>"),
+            self.message
+        )
+    }
+}
+
+impl std::error::Error for ThreadPanic {}
+
+impl Session {
+    /// Spawns a new thread with the provided name, reporting any panic which occurs on it to the session's
+    /// batteries.
+    ///
+    /// Panics on spawned threads don't reach the application's `main()` function, so they are otherwise easy to
+    /// miss. This behaves like [`std::thread::Builder::spawn`], see [`Session::instrument_thread`] for details.
+    ///
+    /// ## Example
+    /// ```no_run
+    /// use tracing_batteries::{Session, Sentry};
+    ///
+    /// let session = Session::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+    ///   .with_battery(Sentry::new("https://yourdsn@sentry.example.com"));
+    ///
+    /// let worker = session
+    ///   .spawn_instrumented("indexer", || {
+    ///     // ...
+    ///   })
+    ///   .expect("failed to spawn the indexer thread");
+    ///
+    /// worker.join().ok();
+    /// session.shutdown();
+    /// ```
+    pub fn spawn_instrumented<N, F, T>(&self, name: N, f: F) -> std::io::Result<JoinHandle<T>>
+    where
+        N: Into<String>,
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        self.instrument_thread(Builder::new().name(name.into()), f)
+    }
+
+    /// Spawns a new thread using the provided builder, reporting any panic which occurs on it to the session's
+    /// batteries.
+    ///
+    /// The thread runs within the span which was active when it was spawned. If it panics, a [`ThreadPanic`]
+    /// error is recorded with a `thread.name` field before the panic resumes, so [`JoinHandle::join`] still
+    /// returns the panic's payload.
+    ///
+    /// ## Example
+    /// ```no_run
+    /// use tracing_batteries::{Session, Sentry};
+    ///
+    /// let session = Session::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+    ///   .with_battery(Sentry::new("https://yourdsn@sentry.example.com"));
+    ///
+    /// let builder = std::thread::Builder::new()
+    ///   .name("indexer".into())
+    ///   .stack_size(8 * 1024 * 1024);
+    ///
+    /// session.instrument_thread(builder, || {
+    ///   // ...
+    /// }).ok();
+    /// ```
+    pub fn instrument_thread<F, T>(&self, builder: Builder, f: F) -> std::io::Result<JoinHandle<T>>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let session = self.downgrade();
+        let span = tracing::Span::current();
+
+        builder.spawn(move || {
+            let _entered = span.enter();
+            match catch_unwind(AssertUnwindSafe(f)) {
+                Ok(result) => result,
+                Err(payload) => {
+                    if let Some(session) = session.upgrade() {
+                        let thread = std::thread::current().name().map(str::to_string);
+                        let panic = ThreadPanic::new(thread.clone(), payload.as_ref());
+                        session.record_error_with(&panic, thread.map(|name| ("thread.name", name)));
+                    }
+
+                    resume_unwind(payload)
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::ErrorBattery;
+
+    #[test]
+    fn reports_thread_panics() {
+        let battery = ErrorBattery::default();
+        let session = Session::new("example", "0.0.1").with_battery(battery.clone());
+
+        let result = session
+            .spawn_instrumented("panicking-worker", || panic!("queue corrupted"))
+            .unwrap()
+            .join();
+        assert!(result.is_err());
+
+        assert_eq!(
+            battery.messages(),
+            ["thread 'panicking-worker' panicked: queue corrupted"]
+        );
+        assert_eq!(
+            battery.contexts()[0]
+                .fields
+                .get("thread.name")
+                .map(String::as_str),
+            Some("panicking-worker")
+        );
+    }
+}
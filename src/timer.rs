@@ -0,0 +1,131 @@
+use std::{borrow::Cow, time::Instant};
+
+use crate::Session;
+
+/// A guard which measures the duration of an operation, created using [`Session::time`].
+///
+/// When the guard is dropped, the elapsed time (in seconds) is recorded in the `operation.duration`
+/// histogram with an `operation` attribute, the operation's span is closed, and (if enabled using
+/// [`OperationTimer::with_event`]) a custom event is recorded through the session.
+#[must_use = "the operation is timed until the guard is dropped"]
+pub struct OperationTimer {
+    name: Cow<'static, str>,
+    start: Instant,
+    span: tracing::Span,
+    session: Session,
+    event: bool,
+}
+
+impl OperationTimer {
+    /// Configures the timer to also record a custom event, named after the operation, with a
+    /// `duration_ms` property when it is dropped.
+    pub fn with_event(mut self) -> Self {
+        self.event = true;
+        self
+    }
+
+    /// Returns the span which represents this operation, allowing work to be attributed to it.
+    pub fn span(&self) -> &tracing::Span {
+        &self.span
+    }
+}
+
+impl Drop for OperationTimer {
+    fn drop(&mut self) {
+        let elapsed = self.start.elapsed();
+
+        self.session
+            .histogram("operation.duration")
+            .record_with(elapsed.as_secs_f64(), &[("operation", self.name.as_ref())]);
+
+        if self.event {
+            self.session.record_event(
+                self.name.to_string(),
+                [("duration_ms", elapsed.as_millis() as u64)],
+            );
+        }
+    }
+}
+
+impl Session {
+    /// Starts timing a coarse-grained operation, returning a guard which records its duration when dropped.
+    ///
+    /// A single call covers every backend: the duration is recorded as a histogram metric, the operation
+    /// is represented by a span, and a custom event may optionally be recorded using [`OperationTimer::with_event`].
+    ///
+    /// ## Example
+    /// ```no_run
+    /// use tracing_batteries::{Session, OpenTelemetry};
+    ///
+    /// let session = Session::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+    ///   .with_battery(OpenTelemetry::new("localhost:4317"));
+    ///
+    /// {
+    ///   let _timer = session.time("index_rebuild").with_event();
+    ///   // Rebuild the index...
+    /// }
+    ///
+    /// session.shutdown();
+    /// ```
+    pub fn time<N: Into<Cow<'static, str>>>(&self, name: N) -> OperationTimer {
+        let name = name.into();
+        OperationTimer {
+            span: tracing::info_span!("operation", otel.name = %name, operation = %name),
+            name,
+            start: Instant::now(),
+            session: self.clone(),
+            event: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{atomic::AtomicBool, Arc, Mutex};
+
+    use crate::{Battery, BatteryBuilder, Envelope, EnvelopePayload, Metadata, Metric, Session};
+
+    #[test]
+    fn records_operation() {
+        let recorded = Arc::new(Mutex::new(Vec::new()));
+        let session = Session::new("example", "0.0.1").with_battery(TimerBattery {
+            recorded: recorded.clone(),
+        });
+
+        drop(session.time("index_rebuild").with_event());
+
+        let recorded = recorded.lock().unwrap();
+        assert_eq!(
+            *recorded,
+            vec![
+                "metric:operation.duration".to_string(),
+                "event:index_rebuild".to_string()
+            ]
+        );
+    }
+
+    struct TimerBattery {
+        recorded: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl BatteryBuilder for TimerBattery {
+        fn setup(self, _metadata: &Metadata, _enabled: Arc<AtomicBool>) -> Box<dyn Battery> {
+            Box::new(self)
+        }
+    }
+
+    impl Battery for TimerBattery {
+        fn record_metric(&self, metric: &Metric) {
+            self.recorded
+                .lock()
+                .unwrap()
+                .push(format!("metric:{}", metric.name));
+        }
+
+        fn record_envelope(&self, envelope: &Envelope) {
+            if let EnvelopePayload::Event { name, .. } = &envelope.payload {
+                self.recorded.lock().unwrap().push(format!("event:{name}"));
+            }
+        }
+    }
+}
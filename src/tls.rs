@@ -0,0 +1,31 @@
+use std::{net::TcpStream, sync::Arc, time::Duration};
+
+use rustls::{pki_types::ServerName, ClientConfig, ClientConnection, RootCertStore, StreamOwned};
+
+/// A TLS client stream over TCP, validated against the bundled web PKI roots.
+pub(crate) type TlsStream = StreamOwned<ClientConnection, TcpStream>;
+
+/// Opens a TCP connection to the provided host and negotiates TLS over it, verifying the server's
+/// certificate against the bundled web PKI roots.
+pub(crate) fn connect(
+    host: &str,
+    port: u16,
+    write_timeout: Duration,
+) -> Result<TlsStream, Box<dyn std::error::Error>> {
+    let roots = RootCertStore {
+        roots: webpki_roots::TLS_SERVER_ROOTS.into(),
+    };
+    let config =
+        ClientConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+            .with_safe_default_protocol_versions()?
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+
+    let server_name = ServerName::try_from(host.to_string())?;
+    let connection = ClientConnection::new(Arc::new(config), server_name)?;
+
+    let socket = TcpStream::connect((host, port))?;
+    socket.set_write_timeout(Some(write_timeout))?;
+
+    Ok(StreamOwned::new(connection, socket))
+}
@@ -0,0 +1,452 @@
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, HashMap, HashSet},
+    sync::{atomic::AtomicBool, Arc, Mutex, PoisonError},
+};
+
+use serde_json::Value;
+
+use crate::{
+    Battery, BatteryBuilder, Envelope, EnvelopePayload, ErrorContext, Metadata, Metric, WeakSession,
+};
+
+const VALIDATION_TARGET: &str = "tracing_batteries::validated";
+
+/// The type of value which a custom event property is expected to hold.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PropertyType {
+    String,
+    Number,
+    Boolean,
+}
+
+impl PropertyType {
+    fn matches(self, value: &Value) -> bool {
+        match self {
+            PropertyType::String => value.is_string(),
+            PropertyType::Number => value.is_number(),
+            PropertyType::Boolean => value.is_boolean(),
+        }
+    }
+}
+
+/// The schema which a custom event is expected to conform to, used by the [`Validated`] combinator.
+///
+/// Properties which are not described by the schema are allowed, and are forwarded unchanged.
+#[derive(Debug, Clone)]
+pub struct EventSchema {
+    name: Cow<'static, str>,
+    properties: Vec<PropertySchema>,
+}
+
+#[derive(Debug, Clone)]
+struct PropertySchema {
+    name: Cow<'static, str>,
+    kind: PropertyType,
+    required: bool,
+    max_cardinality: Option<usize>,
+}
+
+impl EventSchema {
+    /// Creates a new schema for the custom events with the provided name.
+    pub fn new<N: Into<Cow<'static, str>>>(name: N) -> Self {
+        Self {
+            name: name.into(),
+            properties: Vec::new(),
+        }
+    }
+
+    /// Requires events to have the provided property, holding a value of the provided type.
+    pub fn require<N: Into<Cow<'static, str>>>(self, property: N, kind: PropertyType) -> Self {
+        self.with_property(property.into(), kind, true)
+    }
+
+    /// Allows events to have the provided property, which must hold a value of the provided type if present.
+    pub fn optional<N: Into<Cow<'static, str>>>(self, property: N, kind: PropertyType) -> Self {
+        self.with_property(property.into(), kind, false)
+    }
+
+    /// Limits the number of distinct values which the provided property may take over the course of a session,
+    /// preventing unbounded values (like IDs or free text) from being recorded where categories are expected.
+    pub fn with_max_cardinality<N: AsRef<str>>(mut self, property: N, limit: usize) -> Self {
+        if let Some(schema) = self
+            .properties
+            .iter_mut()
+            .find(|schema| schema.name == property.as_ref())
+        {
+            schema.max_cardinality = Some(limit);
+        }
+
+        self
+    }
+
+    /// The name of the custom events which this schema applies to.
+    pub(crate) fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Describes the first required property which is missing, or property which holds the wrong type of value,
+    /// if any. Cardinality limits are not checked, since they depend on the values seen by a [`Validated`] battery.
+    pub(crate) fn problems(&self, properties: &BTreeMap<String, Value>) -> Option<String> {
+        self.properties
+            .iter()
+            .find_map(|property| match properties.get(property.name.as_ref()) {
+                None if property.required => Some(format!(
+                    "the required property '{}' is missing",
+                    property.name
+                )),
+                Some(value) if !property.kind.matches(value) => Some(format!(
+                    "the property '{}' should be a {:?} but was {}",
+                    property.name, property.kind, value
+                )),
+                _ => None,
+            })
+    }
+
+    fn with_property(
+        mut self,
+        name: Cow<'static, str>,
+        kind: PropertyType,
+        required: bool,
+    ) -> Self {
+        self.properties.retain(|schema| schema.name != name);
+        self.properties.push(PropertySchema {
+            name,
+            kind,
+            required,
+            max_cardinality: None,
+        });
+        self
+    }
+}
+
+/// A combinator which wraps another battery, checking custom events against their [`EventSchema`]s and
+/// rejecting (or sanitizing) those which don't conform before the wrapped battery receives them.
+///
+/// This prevents a single misbehaving call site from polluting your analytics data, for example by recording
+/// a property with the wrong type, omitting a property your dashboards depend on, or recording a user ID in a
+/// property which is expected to hold one of a small number of categories. A warning describing the problem is
+/// emitted (with the `tracing_batteries::validated` target) whenever a non-conforming event is encountered.
+///
+/// By default non-conforming events are dropped, while [`Validated::sanitize`] instead removes the
+/// non-conforming properties and forwards the rest of the event (unless a required property is missing).
+/// Custom events without a schema, errors, page views and metrics are forwarded unchanged.
+///
+/// ## Example
+/// ```no_run
+/// use tracing_batteries::{Session, Validated, EventSchema, PropertyType, Sentry};
+///
+/// let session = Session::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+///   .with_battery(Validated::new(Sentry::new("https://yourdsn@sentry.example.com"))
+///     .with_schema(EventSchema::new("export_pdf")
+///       .require("pages", PropertyType::Number)
+///       .optional("template", PropertyType::String)
+///       .with_max_cardinality("template", 20)));
+///
+/// session.shutdown();
+/// ```
+pub struct Validated<B: BatteryBuilder> {
+    battery: B,
+    schemas: Vec<EventSchema>,
+    sanitize: bool,
+}
+
+impl<B: BatteryBuilder> Validated<B> {
+    /// Wraps the provided battery, initially without any schemas.
+    pub fn new(battery: B) -> Self {
+        Self {
+            battery,
+            schemas: Vec::new(),
+            sanitize: false,
+        }
+    }
+
+    /// Adds a schema which custom events with the same name must conform to.
+    pub fn with_schema(mut self, schema: EventSchema) -> Self {
+        self.schemas.retain(|existing| existing.name != schema.name);
+        self.schemas.push(schema);
+        self
+    }
+
+    /// Removes non-conforming properties from events, rather than dropping the events entirely.
+    pub fn sanitize(self) -> Self {
+        Self {
+            sanitize: true,
+            ..self
+        }
+    }
+}
+
+impl<B: BatteryBuilder> BatteryBuilder for Validated<B> {
+    fn setup(self, metadata: &Metadata, enabled: Arc<AtomicBool>) -> Box<dyn Battery> {
+        Box::new(ValidatedBattery {
+            inner: self.battery.setup(metadata, enabled),
+            schemas: self
+                .schemas
+                .into_iter()
+                .map(|schema| (schema.name.to_string(), schema))
+                .collect(),
+            sanitize: self.sanitize,
+            seen: Mutex::new(HashMap::new()),
+        })
+    }
+}
+
+/// The outcome of validating an event against its schema.
+#[derive(Debug, Default)]
+struct Validation {
+    problems: Vec<String>,
+    /// The properties which do not conform to the schema, and would be removed when sanitizing.
+    invalid: Vec<String>,
+    /// Whether a required property is missing (or invalid), preventing the event from being sanitized.
+    missing_required: bool,
+    /// The previously unseen values of cardinality limited properties, recorded once the event is forwarded.
+    new_values: Vec<(String, String)>,
+}
+
+struct ValidatedBattery {
+    inner: Box<dyn Battery>,
+    schemas: HashMap<String, EventSchema>,
+    sanitize: bool,
+    seen: Mutex<HashMap<(String, String), HashSet<String>>>,
+}
+
+impl ValidatedBattery {
+    fn validate(
+        &self,
+        schema: &EventSchema,
+        properties: &BTreeMap<String, Value>,
+        seen: &HashMap<(String, String), HashSet<String>>,
+    ) -> Validation {
+        let mut validation = Validation::default();
+
+        for property in schema.properties.iter() {
+            let problem = match properties.get(property.name.as_ref()) {
+                None if property.required => {
+                    validation.missing_required = true;
+                    validation.problems.push(format!(
+                        "the required property '{}' is missing",
+                        property.name
+                    ));
+                    continue;
+                }
+                None => continue,
+                Some(value) if !property.kind.matches(value) => Some(format!(
+                    "the property '{}' should be a {:?} but was {}",
+                    property.name, property.kind, value
+                )),
+                Some(value) => property.max_cardinality.and_then(|limit| {
+                    let key = (schema.name.to_string(), property.name.to_string());
+                    let value = value.to_string();
+                    let values = seen.get(&key);
+                    if values.is_some_and(|values| values.contains(&value)) {
+                        None
+                    } else if values.map_or(0, HashSet::len) >= limit {
+                        Some(format!(
+                            "the property '{}' exceeded its limit of {} distinct values with {}",
+                            property.name, limit, value
+                        ))
+                    } else {
+                        validation
+                            .new_values
+                            .push((property.name.to_string(), value));
+                        None
+                    }
+                }),
+            };
+
+            if let Some(problem) = problem {
+                validation.missing_required |= property.required;
+                validation.invalid.push(property.name.to_string());
+                validation.problems.push(problem);
+            }
+        }
+
+        validation
+    }
+}
+
+impl Battery for ValidatedBattery {
+    fn record_error(&self, error: &dyn std::error::Error) {
+        self.inner.record_error(error);
+    }
+
+    fn record_error_with(&self, error: &dyn std::error::Error, context: &ErrorContext) {
+        self.inner.record_error_with(error, context);
+    }
+
+    fn record_envelope(&self, envelope: &Envelope) {
+        let EnvelopePayload::Event { name, properties } = &envelope.payload else {
+            self.inner.record_envelope(envelope);
+            return;
+        };
+
+        let Some(schema) = self.schemas.get(name) else {
+            self.inner.record_envelope(envelope);
+            return;
+        };
+
+        let validation = {
+            let mut seen = self.seen.lock().unwrap_or_else(PoisonError::into_inner);
+            let validation = self.validate(schema, properties, &seen);
+
+            if validation.problems.is_empty() || (self.sanitize && !validation.missing_required) {
+                for (property, value) in validation.new_values.iter() {
+                    seen.entry((name.clone(), property.clone()))
+                        .or_default()
+                        .insert(value.clone());
+                }
+            }
+
+            validation
+        };
+
+        if validation.problems.is_empty() {
+            self.inner.record_envelope(envelope);
+            return;
+        }
+
+        let problems = validation.problems.join(", ");
+        if !self.sanitize || validation.missing_required {
+            tracing::warn!(
+                target: VALIDATION_TARGET,
+                event = %name,
+                "The '{}' event was dropped because it did not match its schema: {}",
+                name,
+                problems
+            );
+            return;
+        }
+
+        tracing::warn!(
+            target: VALIDATION_TARGET,
+            event = %name,
+            "The '{}' event was sanitized because it did not match its schema: {}",
+            name,
+            problems
+        );
+
+        let mut properties = properties.clone();
+        for property in validation.invalid.iter() {
+            properties.remove(property);
+        }
+
+        self.inner.record_envelope(&Envelope {
+            payload: EnvelopePayload::Event {
+                name: name.clone(),
+                properties,
+            },
+            ..envelope.clone()
+        });
+    }
+
+    fn record_metric(&self, metric: &Metric) {
+        self.inner.record_metric(metric);
+    }
+
+    fn accepts_weighted_histograms(&self) -> bool {
+        self.inner.accepts_weighted_histograms()
+    }
+
+    fn record_artifact(&self, artifact: &crate::Artifact) -> bool {
+        self.inner.record_artifact(artifact)
+    }
+
+    fn release(&self) {
+        self.inner.release();
+    }
+
+    fn shutdown(&self) {
+        self.inner.shutdown();
+    }
+
+    fn attached(&self, session: WeakSession) {
+        self.inner.attached(session);
+    }
+
+    fn validate(&self) -> Vec<crate::ValidationCheck> {
+        self.inner.validate()
+    }
+
+    #[cfg(feature = "version-check")]
+    fn update_available(&self) -> Option<crate::AvailableUpdate> {
+        self.inner.update_available()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{test_support::EnvelopeBattery, Session};
+
+    fn schema() -> EventSchema {
+        EventSchema::new("export_pdf")
+            .require("pages", PropertyType::Number)
+            .optional("template", PropertyType::String)
+            .with_max_cardinality("template", 1)
+    }
+
+    fn properties(battery: &EnvelopeBattery) -> Vec<BTreeMap<String, Value>> {
+        battery
+            .envelopes()
+            .iter()
+            .filter_map(|envelope| match &envelope.payload {
+                EnvelopePayload::Event { properties, .. } => Some(properties.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn rejects_invalid_events() {
+        let battery = EnvelopeBattery::default();
+        let session = Session::new("example", "0.0.1")
+            .with_battery(Validated::new(battery.clone()).with_schema(schema()));
+
+        session.record_event("export_pdf", [("pages", Value::from(3))]);
+        session.record_event("export_pdf", [("pages", Value::from("three"))]);
+        session.record_event("export_pdf", [("template", Value::from("invoice"))]);
+        session.record_event("other_event", [("pages", Value::from("three"))]);
+
+        assert_eq!(properties(&battery).len(), 2);
+    }
+
+    #[test]
+    fn sanitizes_invalid_properties() {
+        let battery = EnvelopeBattery::default();
+        let session = Session::new("example", "0.0.1").with_battery(
+            Validated::new(battery.clone())
+                .with_schema(schema())
+                .sanitize(),
+        );
+
+        session.record_event(
+            "export_pdf",
+            [
+                ("pages", Value::from(3)),
+                ("template", Value::from("invoice")),
+            ],
+        );
+        session.record_event(
+            "export_pdf",
+            [
+                ("pages", Value::from(1)),
+                ("template", Value::from("receipt")),
+            ],
+        );
+        session.record_event(
+            "export_pdf",
+            [
+                ("pages", Value::from(2)),
+                ("template", Value::from("invoice")),
+            ],
+        );
+
+        let properties = properties(&battery);
+        assert_eq!(properties.len(), 3);
+        assert_eq!(properties[0]["template"], "invoice");
+        assert!(!properties[1].contains_key("template"));
+        assert_eq!(properties[1]["pages"], 1);
+        assert_eq!(properties[2]["template"], "invoice");
+    }
+}
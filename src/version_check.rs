@@ -0,0 +1,264 @@
+use std::{
+    borrow::Cow,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, OnceLock,
+    },
+    time::Duration,
+};
+
+use crate::{Battery, BatteryBuilder, Metadata, Session, WeakSession};
+
+/// Details about a newer release of the application, as reported by [`Session::update_available`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AvailableUpdate {
+    pub current_version: String,
+    pub latest_version: String,
+}
+
+/// A battery which checks whether a newer version of your application has been released.
+///
+/// The check is performed once, in the background, when the battery is attached to the session and
+/// only if telemetry is enabled at that point, ensuring that network access is governed by the same
+/// consent as the rest of your telemetry. When the running version is outdated, an `update_available`
+/// custom event is recorded and the result is exposed through [`Session::update_available`].
+///
+/// The latest version may be retrieved from crates.io (see [`VersionCheck::crates_io`]) or from a
+/// custom URL which responds with either the version as plain text or a JSON object with a `version` field.
+///
+/// ## Example
+/// ```no_run
+/// use tracing_batteries::{Session, VersionCheck};
+///
+/// let session = Session::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+///   .with_battery(VersionCheck::crates_io(env!("CARGO_PKG_NAME")));
+///
+/// // Later on, once the check has completed...
+/// if let Some(update) = session.update_available() {
+///   eprintln!("Version {} is available, you are running {}.", update.latest_version, update.current_version);
+/// }
+///
+/// session.shutdown();
+/// ```
+pub struct VersionCheck {
+    source: VersionSource,
+    timeout: Duration,
+}
+
+enum VersionSource {
+    CratesIo(Cow<'static, str>),
+    Url(Cow<'static, str>),
+}
+
+impl VersionCheck {
+    /// Checks for new releases by retrieving the latest version from the provided URL.
+    ///
+    /// The URL should respond with the latest version, either as plain text or as a JSON
+    /// object with a `version` field (e.g. `{"version": "1.2.3"}`).
+    pub fn new<U: Into<Cow<'static, str>>>(url: U) -> Self {
+        Self {
+            source: VersionSource::Url(url.into()),
+            timeout: Duration::from_secs(5),
+        }
+    }
+
+    /// Checks for new releases by retrieving the latest stable version of the named crate from crates.io.
+    pub fn crates_io<N: Into<Cow<'static, str>>>(name: N) -> Self {
+        Self {
+            source: VersionSource::CratesIo(name.into()),
+            timeout: Duration::from_secs(5),
+        }
+    }
+
+    /// Configures how long the version check may take before it is abandoned.
+    pub fn with_timeout(self, timeout: Duration) -> Self {
+        Self { timeout, ..self }
+    }
+}
+
+impl BatteryBuilder for VersionCheck {
+    fn setup(self, metadata: &Metadata, enabled: Arc<AtomicBool>) -> Box<dyn Battery> {
+        Box::new(VersionCheckBattery {
+            source: self.source,
+            timeout: self.timeout,
+            service: metadata.service.to_string(),
+            current_version: metadata.version.to_string(),
+            enabled,
+            update: Arc::new(OnceLock::new()),
+        })
+    }
+}
+
+struct VersionCheckBattery {
+    source: VersionSource,
+    timeout: Duration,
+    service: String,
+    current_version: String,
+    enabled: Arc<AtomicBool>,
+    update: Arc<OnceLock<AvailableUpdate>>,
+}
+
+impl VersionCheckBattery {
+    fn fetch_latest_version(
+        source: &VersionSource,
+        service: &str,
+        current_version: &str,
+        timeout: Duration,
+    ) -> Option<String> {
+        let url = match source {
+            VersionSource::CratesIo(name) => format!("https://crates.io/api/v1/crates/{name}"),
+            VersionSource::Url(url) => url.to_string(),
+        };
+
+        let client = reqwest::blocking::Client::builder()
+            .timeout(timeout)
+            .user_agent(format!("{service}/{current_version} (tracing-batteries)"))
+            .build()
+            .ok()?;
+
+        let body = client
+            .get(url)
+            .send()
+            .and_then(|response| response.error_for_status())
+            .and_then(|response| response.text())
+            .ok()?;
+
+        match source {
+            VersionSource::CratesIo(_) => serde_json::from_str::<serde_json::Value>(&body)
+                .ok()?
+                .pointer("/crate/max_stable_version")?
+                .as_str()
+                .map(|version| version.to_string()),
+            VersionSource::Url(_) => match serde_json::from_str::<serde_json::Value>(&body) {
+                Ok(serde_json::Value::Object(object)) => object
+                    .get("version")?
+                    .as_str()
+                    .map(|version| version.to_string()),
+                _ => Some(body.trim().to_string()).filter(|version| !version.is_empty()),
+            },
+        }
+    }
+}
+
+impl Battery for VersionCheckBattery {
+    fn attached(&self, session: WeakSession) {
+        if !self.enabled.load(Ordering::Relaxed) {
+            return;
+        }
+
+        let source = match &self.source {
+            VersionSource::CratesIo(name) => VersionSource::CratesIo(name.clone()),
+            VersionSource::Url(url) => VersionSource::Url(url.clone()),
+        };
+        let timeout = self.timeout;
+        let service = self.service.clone();
+        let current_version = self.current_version.clone();
+        let update = self.update.clone();
+
+        std::thread::Builder::new()
+            .name("tracing-batteries-version-check".into())
+            .spawn(move || {
+                let Some(latest_version) =
+                    Self::fetch_latest_version(&source, &service, &current_version, timeout)
+                else {
+                    return;
+                };
+
+                if !is_newer(&latest_version, &current_version) {
+                    return;
+                }
+
+                tracing::info!(
+                    current_version = %current_version,
+                    latest_version = %latest_version,
+                    "A newer version of {} is available.",
+                    service
+                );
+
+                if let Some(session) = session.upgrade() {
+                    session.record_event(
+                        "update_available",
+                        [
+                            ("current_version", current_version.clone()),
+                            ("latest_version", latest_version.clone()),
+                        ],
+                    );
+                }
+
+                update
+                    .set(AvailableUpdate {
+                        current_version,
+                        latest_version,
+                    })
+                    .ok();
+            })
+            .ok();
+    }
+
+    fn update_available(&self) -> Option<AvailableUpdate> {
+        self.update.get().cloned()
+    }
+}
+
+impl Session {
+    /// Returns details about a newer release of the application, if one has been found by a [`VersionCheck`] battery.
+    ///
+    /// Version checks are performed in the background, so this will return `None` until the check has completed.
+    pub fn update_available(&self) -> Option<AvailableUpdate> {
+        self.batteries()
+            .iter()
+            .find_map(|battery| battery.update_available())
+    }
+}
+
+/// Determines whether `latest` is a newer version than `current`, comparing their numeric components.
+///
+/// Pre-release versions are never considered to be newer than the release they precede.
+fn is_newer(latest: &str, current: &str) -> bool {
+    fn parse(version: &str) -> (Vec<u64>, bool) {
+        let version = version.trim().trim_start_matches('v');
+        let version = version.split_once('+').map_or(version, |(v, _)| v);
+        let (release, pre) = match version.split_once('-') {
+            Some((release, _)) => (release, true),
+            None => (version, false),
+        };
+
+        (
+            release
+                .split('.')
+                .map(|part| part.parse().unwrap_or_default())
+                .collect(),
+            pre,
+        )
+    }
+
+    let (latest, latest_pre) = parse(latest);
+    let (current, current_pre) = parse(current);
+
+    let len = latest.len().max(current.len());
+    for i in 0..len {
+        let l = latest.get(i).copied().unwrap_or_default();
+        let c = current.get(i).copied().unwrap_or_default();
+        if l != c {
+            return l > c;
+        }
+    }
+
+    current_pre && !latest_pre
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn version_comparison() {
+        assert!(is_newer("1.2.4", "1.2.3"));
+        assert!(is_newer("v2.0.0", "1.9.9"));
+        assert!(is_newer("1.2.3", "1.2.3-beta.1"));
+        assert!(!is_newer("1.2.3", "1.2.3"));
+        assert!(!is_newer("1.2.3-beta.1", "1.2.3"));
+        assert!(!is_newer("1.2", "1.2.0"));
+        assert!(!is_newer("1.0.0", "1.10.0"));
+    }
+}
@@ -0,0 +1,403 @@
+use std::{
+    borrow::Cow,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc, Mutex, OnceLock, PoisonError,
+    },
+    thread::JoinHandle,
+    time::{Duration, Instant},
+};
+
+use crate::{Battery, BatteryBuilder, Metadata, WeakSession};
+
+/// A battery which monitors your application's async runtimes and reports when they stop making progress.
+///
+/// The watchdog periodically schedules a lightweight heartbeat task on each monitored runtime. If a heartbeat
+/// is not executed within the configured threshold (for example, because every worker is blocked on a
+/// synchronous call or a deadlock), an [`ExecutorStallError`] is recorded through the session along with a
+/// dump of the process' threads (where supported by the platform). A follow-up event is logged once the runtime
+/// recovers.
+///
+/// If no runtimes are explicitly configured, the runtime which is active when the battery is attached will be
+/// monitored.
+///
+/// ## Example
+/// ```no_run
+/// use std::time::Duration;
+/// use tracing_batteries::{Session, Sentry, Watchdog};
+///
+/// fn start(runtime: tokio::runtime::Handle) {
+///   let session = Session::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+///     .with_battery(Sentry::new("https://yourdsn@sentry.example.com/app-id"))
+///     .with_battery(Watchdog::new()
+///       .with_runtime("workers", runtime)
+///       .with_threshold(Duration::from_secs(2)));
+///
+///   session.shutdown();
+/// }
+/// ```
+pub struct Watchdog {
+    runtimes: Vec<(Cow<'static, str>, tokio::runtime::Handle)>,
+    threshold: Duration,
+    interval: Duration,
+}
+
+impl Watchdog {
+    /// Creates a new watchdog which will report runtimes that make no progress for more than 5 seconds.
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        Self {
+            runtimes: Vec::new(),
+            threshold: Duration::from_secs(5),
+            interval: Duration::from_secs(1),
+        }
+    }
+
+    /// Adds a runtime which should be monitored by this watchdog, identified by the provided name.
+    pub fn with_runtime<N: Into<Cow<'static, str>>>(
+        mut self,
+        name: N,
+        runtime: tokio::runtime::Handle,
+    ) -> Self {
+        self.runtimes.push((name.into(), runtime));
+        self
+    }
+
+    /// Configures how long a runtime may go without executing its heartbeat before it is reported as stalled.
+    pub fn with_threshold(self, threshold: Duration) -> Self {
+        Self { threshold, ..self }
+    }
+
+    /// Configures how frequently heartbeats are scheduled on each monitored runtime.
+    pub fn with_interval(self, interval: Duration) -> Self {
+        Self { interval, ..self }
+    }
+}
+
+impl BatteryBuilder for Watchdog {
+    fn setup(mut self, _metadata: &Metadata, enabled: Arc<AtomicBool>) -> Box<dyn Battery> {
+        if self.runtimes.is_empty() {
+            if let Ok(handle) = tokio::runtime::Handle::try_current() {
+                self.runtimes.push(("default".into(), handle));
+            }
+        }
+
+        let session = Arc::new(OnceLock::new());
+        let (stop, stopped) = mpsc::channel();
+
+        let monitor = WatchdogMonitor {
+            runtimes: self
+                .runtimes
+                .into_iter()
+                .map(|(name, handle)| MonitoredRuntime {
+                    name,
+                    handle,
+                    heartbeat: None,
+                    reported: false,
+                    closed: false,
+                })
+                .collect(),
+            threshold: self.threshold,
+            interval: self.interval,
+            enabled,
+            session: session.clone(),
+        };
+
+        let thread = std::thread::Builder::new()
+            .name("tracing-batteries-watchdog".into())
+            .spawn(move || monitor.run(stopped))
+            .ok();
+
+        Box::new(WatchdogBattery {
+            session,
+            stop: Mutex::new(Some(stop)),
+            thread: Mutex::new(thread),
+        })
+    }
+}
+
+struct WatchdogBattery {
+    session: Arc<OnceLock<WeakSession>>,
+    stop: Mutex<Option<mpsc::Sender<()>>>,
+    thread: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl Battery for WatchdogBattery {
+    fn attached(&self, session: WeakSession) {
+        self.session.set(session).ok();
+    }
+
+    fn shutdown(&self) {
+        self.stop
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take();
+
+        if let Some(thread) = self
+            .thread
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take()
+        {
+            crate::worker::join(thread);
+        }
+    }
+}
+
+struct Heartbeat {
+    scheduled: Instant,
+    completed: Arc<OnceLock<Instant>>,
+    task: tokio::task::JoinHandle<()>,
+}
+
+struct MonitoredRuntime {
+    name: Cow<'static, str>,
+    handle: tokio::runtime::Handle,
+    heartbeat: Option<Heartbeat>,
+    reported: bool,
+    closed: bool,
+}
+
+struct WatchdogMonitor {
+    runtimes: Vec<MonitoredRuntime>,
+    threshold: Duration,
+    interval: Duration,
+    enabled: Arc<AtomicBool>,
+    session: Arc<OnceLock<WeakSession>>,
+}
+
+impl WatchdogMonitor {
+    fn run(mut self, stopped: mpsc::Receiver<()>) {
+        while let Err(mpsc::RecvTimeoutError::Timeout) = stopped.recv_timeout(self.interval) {
+            self.check(Instant::now());
+        }
+    }
+
+    /// Checks the heartbeat of each monitored runtime, reporting those which have stalled and scheduling a new
+    /// heartbeat on those which have completed their last one.
+    fn check(&mut self, now: Instant) {
+        for runtime in self.runtimes.iter_mut() {
+            match &runtime.heartbeat {
+                Some(heartbeat) if heartbeat.completed.get().is_some() => {
+                    if runtime.reported {
+                        let completed = heartbeat.completed.get().copied().unwrap_or(now);
+                        tracing::info!(
+                            runtime = %runtime.name,
+                            stalled_ms = completed.duration_since(heartbeat.scheduled).as_millis() as u64,
+                            "The '{}' runtime has resumed making progress.",
+                            runtime.name
+                        );
+                    }
+
+                    runtime.heartbeat = None;
+                    runtime.reported = false;
+                }
+                // Tasks are cancelled when their runtime shuts down, so a heartbeat which finished without
+                // running means the runtime is gone rather than stalled.
+                Some(heartbeat) if heartbeat.task.is_finished() => {
+                    tracing::debug!(
+                        runtime = %runtime.name,
+                        "The '{}' runtime has shut down and is no longer being monitored.",
+                        runtime.name
+                    );
+                    runtime.closed = true;
+                    continue;
+                }
+                Some(heartbeat) => {
+                    let stalled_for = now.duration_since(heartbeat.scheduled);
+                    if !runtime.reported
+                        && stalled_for > self.threshold
+                        && self.enabled.load(Ordering::Relaxed)
+                    {
+                        runtime.reported = true;
+                        if let Some(session) = self.session.get().and_then(|s| s.upgrade()) {
+                            session.record_error_with(
+                                &ExecutorStallError {
+                                    runtime: runtime.name.to_string(),
+                                    stalled_for,
+                                },
+                                [
+                                    ("watchdog.runtime", runtime.name.to_string()),
+                                    ("watchdog.stalled_ms", stalled_for.as_millis().to_string()),
+                                    (
+                                        "watchdog.threads",
+                                        thread_dump().unwrap_or_else(|| "unavailable".into()),
+                                    ),
+                                ],
+                            );
+                        }
+                    }
+                }
+                None => {}
+            }
+
+            if runtime.heartbeat.is_none() {
+                let completed = Arc::new(OnceLock::new());
+                let signal = completed.clone();
+                let task = runtime.handle.spawn(async move {
+                    signal.set(Instant::now()).ok();
+                });
+
+                runtime.heartbeat = Some(Heartbeat {
+                    scheduled: now,
+                    completed,
+                    task,
+                });
+            }
+        }
+
+        self.runtimes.retain(|runtime| !runtime.closed);
+    }
+}
+
+/// The error which is recorded by the [`Watchdog`] when a monitored runtime stops making progress.
+#[derive(Debug)]
+pub struct ExecutorStallError {
+    pub runtime: String,
+    pub stalled_for: Duration,
+}
+
+impl std::fmt::Display for ExecutorStallError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "The '{}' runtime has not made progress for {}ms, which may indicate a blocked executor or a deadlock.",
+            self.runtime,
+            self.stalled_for.as_millis()
+        )
+    }
+}
+
+impl std::error::Error for ExecutorStallError {}
+
+/// Builds a summary of the process' threads, including their names, scheduler states, and the kernel
+/// function they are waiting in.
+#[cfg(target_os = "linux")]
+fn thread_dump() -> Option<String> {
+    let mut threads = std::fs::read_dir("/proc/self/task")
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| {
+            let path = entry.path();
+            let read = |file: &str| {
+                std::fs::read_to_string(path.join(file))
+                    .map(|value| value.trim().to_string())
+                    .unwrap_or_default()
+            };
+
+            let state = read("stat")
+                .rsplit_once(") ")
+                .and_then(|(_, rest)| rest.split_whitespace().next().map(|s| s.to_string()))
+                .unwrap_or_default();
+
+            format!(
+                "{} {} state={} wchan={}",
+                entry.file_name().to_string_lossy(),
+                read("comm"),
+                state,
+                read("wchan")
+            )
+        })
+        .collect::<Vec<_>>();
+
+    threads.sort();
+    Some(threads.join("\n"))
+}
+
+#[cfg(not(target_os = "linux"))]
+fn thread_dump() -> Option<String> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{test_support::ErrorBattery, Session};
+
+    const THRESHOLD: Duration = Duration::from_millis(50);
+
+    fn monitor(
+        name: &'static str,
+        handle: tokio::runtime::Handle,
+        session: &Session,
+    ) -> WatchdogMonitor {
+        WatchdogMonitor {
+            runtimes: vec![MonitoredRuntime {
+                name: name.into(),
+                handle,
+                heartbeat: None,
+                reported: false,
+                closed: false,
+            }],
+            threshold: THRESHOLD,
+            interval: Duration::from_millis(10),
+            enabled: session.enabled.clone(),
+            session: Arc::new(OnceLock::from(session.downgrade())),
+        }
+    }
+
+    #[test]
+    fn reports_stalled_runtime() {
+        // A current-thread runtime which is never driven will never execute its heartbeat.
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+
+        let battery = ErrorBattery::default();
+        let session = Session::new("example", "0.0.1").with_battery(battery.clone());
+        let mut monitor = monitor("stalled", runtime.handle().clone(), &session);
+
+        let start = Instant::now();
+        monitor.check(start);
+        monitor.check(start + THRESHOLD / 2);
+        assert!(
+            battery.contexts().is_empty(),
+            "the runtime should not be reported before the threshold is reached"
+        );
+
+        monitor.check(start + THRESHOLD * 2);
+        monitor.check(start + THRESHOLD * 3);
+
+        let recorded = battery.contexts();
+        assert_eq!(
+            recorded.len(),
+            1,
+            "the stall should be reported exactly once"
+        );
+        assert_eq!(
+            recorded[0]
+                .fields
+                .get("watchdog.runtime")
+                .map(|s| s.as_str()),
+            Some("stalled")
+        );
+    }
+
+    #[test]
+    fn ignores_closed_runtime() {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+
+        let battery = ErrorBattery::default();
+        let session = Session::new("example", "0.0.1").with_battery(battery.clone());
+        let mut monitor = monitor("closed", runtime.handle().clone(), &session);
+
+        let start = Instant::now();
+        monitor.check(start);
+
+        // Shutting the runtime down cancels the pending heartbeat before the stall threshold is reached.
+        drop(runtime);
+
+        monitor.check(start + THRESHOLD * 2);
+
+        assert!(
+            battery.contexts().is_empty(),
+            "a closed runtime should not be reported as stalled"
+        );
+        assert!(
+            monitor.runtimes.is_empty(),
+            "a closed runtime should no longer be monitored"
+        );
+    }
+}
@@ -0,0 +1,46 @@
+use std::thread::JoinHandle;
+
+/// Waits for one of the background threads which deliver telemetry on behalf of a battery to finish, as is done
+/// when the battery is shut down.
+///
+/// Shutting down may wait several seconds for outstanding telemetry to be delivered. When this happens on a
+/// worker thread of a multi-threaded tokio runtime, the wait is performed using
+/// [`block_in_place`](tokio::task::block_in_place) so that the runtime hands that worker's other tasks to
+/// another thread instead of stalling them. Everywhere else (including on single-threaded runtimes, where
+/// handing off tasks isn't possible) the calling thread simply blocks until the thread has finished.
+// Only batteries which run their own delivery threads call this, so it's unused in many feature combinations.
+#[allow(dead_code)]
+pub(crate) fn join(thread: JoinHandle<()>) {
+    #[cfg(feature = "tokio")]
+    if tokio::runtime::Handle::try_current()
+        .is_ok_and(|handle| handle.runtime_flavor() == tokio::runtime::RuntimeFlavor::MultiThread)
+    {
+        tokio::task::block_in_place(|| thread.join().ok());
+        return;
+    }
+
+    thread.join().ok();
+}
+
+#[cfg(all(test, feature = "tokio"))]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn joins_from_async_contexts() {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .build()
+            .unwrap();
+
+        runtime
+            .block_on(tokio::spawn(async {
+                join(std::thread::spawn(|| {
+                    std::thread::sleep(Duration::from_millis(10))
+                }));
+            }))
+            .unwrap();
+    }
+}
@@ -0,0 +1,296 @@
+use std::{
+    future::Future,
+    net::{ToSocketAddrs, UdpSocket},
+    pin::Pin,
+    sync::OnceLock,
+    time::SystemTime,
+};
+
+use opentelemetry::{
+    propagation::{text_map_propagator::FieldIter, Extractor, Injector, TextMapPropagator},
+    trace::{
+        SpanContext, SpanId, SpanKind, Status, TraceContextExt, TraceFlags, TraceId, TraceState,
+    },
+    Context, Value,
+};
+use opentelemetry_sdk::{
+    export::trace::{ExportResult, SpanData, SpanExporter},
+    trace::{IdGenerator, RandomIdGenerator},
+};
+
+/// The header (and Lambda environment variable, `_X_AMZN_TRACE_ID`) used by AWS services to propagate trace context.
+const TRACE_HEADER: &str = "x-amzn-trace-id";
+
+/// The header which must precede every segment document sent to the X-Ray daemon.
+const DAEMON_HEADER: &str = "{\"format\": \"json\", \"version\": 1}\n";
+
+/// The largest UDP packet the X-Ray daemon will accept.
+const MAX_PACKET_SIZE: usize = 64 * 1024;
+
+/// An [`IdGenerator`] which produces trace IDs that X-Ray accepts, whose first 32 bits hold the time at which the
+/// trace started (in seconds since the Unix epoch) and whose remaining 96 bits are random.
+#[derive(Debug, Default)]
+pub(crate) struct XRayIdGenerator {
+    random: RandomIdGenerator,
+}
+
+impl IdGenerator for XRayIdGenerator {
+    fn new_trace_id(&self) -> TraceId {
+        let mut id = self.random.new_trace_id().to_bytes();
+        id[..4].copy_from_slice(&epoch_seconds(SystemTime::now()).to_be_bytes());
+        TraceId::from_bytes(id)
+    }
+
+    fn new_span_id(&self) -> SpanId {
+        self.random.new_span_id()
+    }
+}
+
+/// A [`TextMapPropagator`] which reads and writes trace context using the `X-Amzn-Trace-Id` header, in the
+/// `Root=1-{time}-{random};Parent={span};Sampled={0|1}` format used by AWS load balancers, API Gateway and Lambda.
+#[derive(Debug, Default)]
+pub(crate) struct XRayPropagator;
+
+impl TextMapPropagator for XRayPropagator {
+    fn inject_context(&self, cx: &Context, injector: &mut dyn Injector) {
+        let span = cx.span();
+        let span_context = span.span_context();
+        if span_context.is_valid() {
+            injector.set(TRACE_HEADER, format_header(span_context));
+        }
+    }
+
+    fn extract_with_context(&self, cx: &Context, extractor: &dyn Extractor) -> Context {
+        extractor
+            .get(TRACE_HEADER)
+            .and_then(parse_header)
+            .map(|span_context| cx.with_remote_span_context(span_context))
+            .unwrap_or_else(|| cx.clone())
+    }
+
+    fn fields(&self) -> FieldIter<'_> {
+        static FIELDS: OnceLock<[String; 1]> = OnceLock::new();
+        FieldIter::new(FIELDS.get_or_init(|| [TRACE_HEADER.to_string()]))
+    }
+}
+
+/// Formats a trace ID in the `1-{time}-{random}` form used by X-Ray.
+fn format_trace_id(trace_id: TraceId) -> String {
+    let hex = trace_id.to_string();
+    format!("1-{}-{}", &hex[..8], &hex[8..])
+}
+
+fn format_header(span_context: &SpanContext) -> String {
+    format!(
+        "Root={};Parent={};Sampled={}",
+        format_trace_id(span_context.trace_id()),
+        span_context.span_id(),
+        if span_context.is_sampled() { "1" } else { "0" }
+    )
+}
+
+fn parse_header(header: &str) -> Option<SpanContext> {
+    let mut trace_id = None;
+    let mut span_id = None;
+    let mut flags = TraceFlags::default();
+
+    for part in header.split(';') {
+        match part.trim().split_once('=') {
+            Some(("Root", root)) => {
+                let (time, random) = root.strip_prefix("1-")?.split_once('-')?;
+                if time.len() != 8 || random.len() != 24 {
+                    return None;
+                }
+
+                trace_id = TraceId::from_hex(&format!("{time}{random}")).ok();
+            }
+            Some(("Parent", parent)) => span_id = SpanId::from_hex(parent).ok(),
+            Some(("Sampled", "1")) => flags = TraceFlags::SAMPLED,
+            _ => {}
+        }
+    }
+
+    let span_context = SpanContext::new(trace_id?, span_id?, flags, true, TraceState::default());
+    span_context.is_valid().then_some(span_context)
+}
+
+fn epoch_seconds(time: SystemTime) -> u32 {
+    time.duration_since(SystemTime::UNIX_EPOCH)
+        .map(|duration| duration.as_secs() as u32)
+        .unwrap_or_default()
+}
+
+fn timestamp(time: SystemTime) -> f64 {
+    time.duration_since(SystemTime::UNIX_EPOCH)
+        .map(|duration| duration.as_secs_f64())
+        .unwrap_or_default()
+}
+
+/// Replaces the characters which X-Ray does not permit in segment names, and truncates the name to 200 characters.
+fn segment_name(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c.is_whitespace() || "_.:/%&#=+\\-@".contains(c) {
+                c
+            } else {
+                '_'
+            }
+        })
+        .take(200)
+        .collect()
+}
+
+fn json_value(value: &Value) -> serde_json::Value {
+    match value {
+        Value::Bool(value) => (*value).into(),
+        Value::I64(value) => (*value).into(),
+        Value::F64(value) => (*value).into(),
+        value => value.as_str().into_owned().into(),
+    }
+}
+
+/// Encodes a span as an X-Ray segment document. Root spans, and those handling requests or messages (whose parent
+/// is usually in another service), become segments named after the service, while all other spans become
+/// subsegments of their parent.
+fn encode_segment(span: &SpanData, service: &str) -> serde_json::Value {
+    let is_segment = span.parent_span_id == SpanId::INVALID
+        || matches!(span.span_kind, SpanKind::Server | SpanKind::Consumer);
+
+    let metadata: serde_json::Map<String, serde_json::Value> = span
+        .attributes
+        .iter()
+        .map(|kv| (kv.key.to_string(), json_value(&kv.value)))
+        .collect();
+
+    let mut segment = serde_json::json!({
+        "name": segment_name(if is_segment { service } else { &span.name }),
+        "id": span.span_context.span_id().to_string(),
+        "trace_id": format_trace_id(span.span_context.trace_id()),
+        "start_time": timestamp(span.start_time),
+        "end_time": timestamp(span.end_time),
+        "metadata": { "default": metadata },
+    });
+
+    if span.parent_span_id != SpanId::INVALID {
+        segment["parent_id"] = span.parent_span_id.to_string().into();
+    }
+
+    if is_segment {
+        segment["annotations"] = serde_json::json!({ "operation": span.name.to_string() });
+    } else {
+        segment["type"] = "subsegment".into();
+    }
+
+    if matches!(span.span_kind, SpanKind::Client | SpanKind::Producer) {
+        segment["namespace"] = "remote".into();
+    }
+
+    if let Status::Error { description } = &span.status {
+        segment["fault"] = true.into();
+        segment["cause"] = serde_json::json!({
+            "exceptions": [{ "message": description.to_string() }],
+        });
+    }
+
+    segment
+}
+
+/// A [`SpanExporter`] which emits spans to the AWS X-Ray daemon as segment documents over UDP.
+#[derive(Debug)]
+pub(crate) struct XRayDaemonExporter {
+    socket: UdpSocket,
+    service: String,
+}
+
+impl XRayDaemonExporter {
+    pub fn new(daemon: &str) -> std::io::Result<Self> {
+        let address = daemon.to_socket_addrs()?.next().ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("could not resolve '{daemon}'"),
+            )
+        })?;
+
+        let socket = if address.is_ipv4() {
+            UdpSocket::bind("0.0.0.0:0")?
+        } else {
+            UdpSocket::bind("[::]:0")?
+        };
+        socket.connect(address)?;
+
+        Ok(Self {
+            socket,
+            service: "unknown_service".to_string(),
+        })
+    }
+}
+
+impl SpanExporter for XRayDaemonExporter {
+    fn export(
+        &mut self,
+        batch: Vec<SpanData>,
+    ) -> Pin<Box<dyn Future<Output = ExportResult> + Send + 'static>> {
+        let mut result = Ok(());
+        for span in batch.iter() {
+            let packet = format!("{DAEMON_HEADER}{}", encode_segment(span, &self.service));
+
+            // Segments which are too large for the daemon to accept are dropped rather than truncated.
+            if packet.len() > MAX_PACKET_SIZE {
+                crate::diagnostics::record_dropped("xray");
+                continue;
+            }
+
+            if let Err(err) = self.socket.send(packet.as_bytes()) {
+                result = Err(opentelemetry::trace::TraceError::Other(Box::new(err)));
+            }
+        }
+
+        Box::pin(std::future::ready(result))
+    }
+
+    fn set_resource(&mut self, resource: &opentelemetry_sdk::Resource) {
+        if let Some(service) = resource.get(opentelemetry::Key::new("service.name")) {
+            self.service = service.as_str().into_owned();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generates_timestamped_trace_ids() {
+        let before = epoch_seconds(SystemTime::now());
+        let trace_id = XRayIdGenerator::default().new_trace_id().to_bytes();
+        let time = u32::from_be_bytes([trace_id[0], trace_id[1], trace_id[2], trace_id[3]]);
+
+        assert!(time >= before && time <= before + 1);
+    }
+
+    #[test]
+    fn header_round_trip() {
+        let header = "Root=1-5759e988-bd862e3fe1be46a994272793;Parent=53995c3f42cd8ad8;Sampled=1";
+        let span_context = parse_header(header).expect("the header should be parsed");
+
+        assert_eq!(
+            span_context.trace_id(),
+            TraceId::from_hex("5759e988bd862e3fe1be46a994272793").unwrap()
+        );
+        assert_eq!(
+            span_context.span_id(),
+            SpanId::from_hex("53995c3f42cd8ad8").unwrap()
+        );
+        assert!(span_context.is_sampled());
+        assert_eq!(format_header(&span_context), header);
+
+        assert!(parse_header("Root=1-5759e988-bd862e3f;Parent=53995c3f42cd8ad8").is_none());
+        assert!(parse_header("Root=1-5759e988-bd862e3fe1be46a994272793").is_none());
+    }
+
+    #[test]
+    fn sanitizes_segment_names() {
+        assert_eq!(segment_name("GET /users/{id}"), "GET /users/_id_");
+        assert_eq!(segment_name(&"a".repeat(300)).len(), 200);
+    }
+}